@@ -3,10 +3,16 @@ use std::io::{self, Read, Write};
 use std::net::TcpStream;
 use std::path::PathBuf;
 use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
 #[cfg(feature = "tauri-app")]
 use std::sync::Mutex;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+use agent::AgentService;
+use ipc::jsonrpc::{Id, Request, Response};
+use ipc::JsonRpcServer;
+use serde_json::{json, Value};
 
 #[derive(Debug, Clone)]
 struct JsonRpcBridgeRequest {
@@ -21,6 +27,7 @@ struct JsonRpcBridgeResponse {
 #[derive(Debug)]
 struct BackendProcessManager {
     child: Option<Child>,
+    in_process: Option<InProcessBackend>,
     mode: BackendMode,
     http_addr: String,
     auto_spawn: bool,
@@ -29,15 +36,128 @@ struct BackendProcessManager {
 #[derive(Debug, Clone, Copy)]
 #[allow(dead_code)]
 enum BackendMode {
+    /// Links `agent` directly; no toolchain or subprocess needed on end-user machines.
+    InProcess,
     HttpDev,
     StdioMcp,
 }
 
+impl BackendMode {
+    fn from_env() -> Self {
+        match env::var("CMND_N_CTRL_BACKEND_MODE").as_deref() {
+            Ok("http") => Self::HttpDev,
+            _ => Self::InProcess,
+        }
+    }
+}
+
+/// Where a bridged request goes, resolved under the manager lock and used after releasing it so
+/// a long-polling `events.subscribe` does not block other commands.
+#[derive(Debug, Clone)]
+enum BackendHandle {
+    InProcess(InProcessBackend),
+    Http(String),
+}
+
+type InProcessCall = (Request, mpsc::Sender<Response>);
+
+/// `AgentService` is not `Send`, so it lives on a dedicated thread and requests are handed over
+/// through a channel.
+#[derive(Debug, Clone)]
+struct InProcessBackend {
+    requests: mpsc::Sender<InProcessCall>,
+}
+
+const LONG_POLL_TICK: Duration = Duration::from_millis(25);
+const LONG_POLL_MAX: Duration = Duration::from_secs(60);
+const MCP_SUPERVISE_INTERVAL: Duration = Duration::from_secs(1);
+#[cfg(feature = "tauri-app")]
+const AGENT_EVENT: &str = "agent-event";
+#[cfg_attr(not(feature = "tauri-app"), allow(dead_code))]
+const EVENT_POLL_TIMEOUT_MS: u64 = 25_000;
+#[cfg(feature = "tauri-app")]
+const EVENT_RETRY: Duration = Duration::from_secs(5);
+
+impl InProcessBackend {
+    fn spawn() -> io::Result<Self> {
+        let (tx, rx) = mpsc::channel::<InProcessCall>();
+        providers::secrets::set_secret_resolver(keychain_get);
+        thread::Builder::new().name("agent-backend".to_string()).spawn(move || {
+            let mut server = JsonRpcServer::new(AgentService::new_for_platform("desktop"));
+            loop {
+                match rx.recv_timeout(MCP_SUPERVISE_INTERVAL) {
+                    Ok((request, reply)) => {
+                        let _ = reply.send(server.handle(request));
+                    }
+                    Err(mpsc::RecvTimeoutError::Timeout) => {
+                        let _ = server.service().supervise_mcp_servers();
+                    }
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        })?;
+        Ok(Self { requests: tx })
+    }
+
+    fn call(&self, request: Request) -> io::Result<Response> {
+        let (reply_tx, reply_rx) = mpsc::channel();
+        self.requests
+            .send((request, reply_tx))
+            .map_err(|_| io::Error::other("in-process backend stopped"))?;
+        reply_rx
+            .recv()
+            .map_err(|_| io::Error::other("in-process backend dropped the request"))
+    }
+
+    /// Like `serve-http`, an `events.subscribe` with `timeout_ms` waits (up to 60s) for events
+    /// instead of returning an empty page.
+    fn jsonrpc_request(&self, payload_json: &str) -> io::Result<String> {
+        let (request, timeout_ms) = match parse_bridge_request(payload_json) {
+            Ok(parsed) => parsed,
+            Err(message) => return Ok(response_json(Response::error(Id::Null, -32700, message))),
+        };
+        let deadline = Instant::now() + Duration::from_millis(timeout_ms).min(LONG_POLL_MAX);
+        loop {
+            let response = self.call(request.clone())?;
+            if request.method != "events.subscribe" || Instant::now() >= deadline || !is_empty_event_page(&response) {
+                return Ok(response_json(response));
+            }
+            thread::sleep(LONG_POLL_TICK);
+        }
+    }
+}
+
+fn parse_bridge_request(payload_json: &str) -> Result<(Request, u64), String> {
+    let payload: Value = serde_json::from_str(payload_json).map_err(|err| format!("parse error: {err}"))?;
+    let method = payload
+        .get("method")
+        .and_then(Value::as_str)
+        .ok_or_else(|| "parse error: missing method".to_string())?;
+    let id = payload.get("id").and_then(Id::from_json).unwrap_or(Id::Null);
+    let params = payload.get("params").cloned().unwrap_or_else(|| json!({}));
+    let timeout_ms = params.get("timeout_ms").and_then(Value::as_u64).unwrap_or(0);
+    Ok((Request::new(id, method, params.to_string()), timeout_ms))
+}
+
+fn is_empty_event_page(response: &Response) -> bool {
+    response
+        .result_json
+        .as_deref()
+        .and_then(|raw| serde_json::from_str::<ipc::EventsSubscribeResponse>(raw).ok())
+        .map(|page| page.events.is_empty())
+        .unwrap_or(false)
+}
+
+fn response_json(response: Response) -> String {
+    response.to_json().to_string()
+}
+
 impl Default for BackendProcessManager {
     fn default() -> Self {
         Self {
             child: None,
-            mode: BackendMode::HttpDev,
+            in_process: None,
+            mode: BackendMode::from_env(),
             http_addr: env::var("CMND_N_CTRL_BACKEND_ADDR")
                 .unwrap_or_else(|_| "127.0.0.1:7777".to_string()),
             auto_spawn: env::var("CMND_N_CTRL_AUTOSPAWN_BACKEND")
@@ -48,10 +168,23 @@ impl Default for BackendProcessManager {
 }
 
 impl BackendProcessManager {
-    fn ensure_started(&mut self) -> io::Result<()> {
-        if !matches!(self.mode, BackendMode::HttpDev) {
-            return Err(io::Error::other("only HttpDev bridge mode implemented"));
+    fn handle(&mut self) -> io::Result<BackendHandle> {
+        match self.mode {
+            BackendMode::InProcess => {
+                if self.in_process.is_none() {
+                    self.in_process = Some(InProcessBackend::spawn()?);
+                }
+                Ok(BackendHandle::InProcess(self.in_process.clone().expect("in-process backend")))
+            }
+            BackendMode::HttpDev => {
+                self.ensure_started()?;
+                Ok(BackendHandle::Http(self.http_addr.clone()))
+            }
+            BackendMode::StdioMcp => Err(io::Error::other("stdio bridge mode not implemented")),
         }
+    }
+
+    fn ensure_started(&mut self) -> io::Result<()> {
 
         if self.check_http_ready().is_ok() {
             return Ok(());
@@ -95,9 +228,9 @@ impl BackendProcessManager {
         Err(last_err.unwrap_or_else(|| io::Error::other("backend did not become ready")))
     }
 
+    /// A TCP connect succeeds before the backend has loaded its state; `/readyz` does not.
     fn check_http_ready(&self) -> io::Result<()> {
-        let _ = TcpStream::connect(&self.http_addr)?;
-        Ok(())
+        get_http(&self.http_addr, "/readyz").map(|_| ())
     }
 
     fn stop(&mut self) {
@@ -108,8 +241,16 @@ impl BackendProcessManager {
     }
 
     fn jsonrpc_request(&mut self, req: JsonRpcBridgeRequest) -> io::Result<JsonRpcBridgeResponse> {
-        self.ensure_started()?;
-        let response_json = post_jsonrpc_http(&self.http_addr, &req.payload_json)?;
+        self.handle()?.jsonrpc_request(req)
+    }
+}
+
+impl BackendHandle {
+    fn jsonrpc_request(&self, req: JsonRpcBridgeRequest) -> io::Result<JsonRpcBridgeResponse> {
+        let response_json = match self {
+            Self::InProcess(backend) => backend.jsonrpc_request(&req.payload_json)?,
+            Self::Http(addr) => post_jsonrpc_http(addr, &req.payload_json)?,
+        };
         Ok(JsonRpcBridgeResponse { response_json })
     }
 }
@@ -129,7 +270,19 @@ fn post_jsonrpc_http(addr: &str, payload_json: &str) -> io::Result<String> {
     );
     stream.write_all(request.as_bytes())?;
     stream.flush()?;
+    read_http_body(&mut stream)
+}
 
+fn get_http(addr: &str, path: &str) -> io::Result<String> {
+    let mut stream = TcpStream::connect(addr)?;
+    let request = format!("GET {path} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n\r\n");
+    stream.write_all(request.as_bytes())?;
+    stream.flush()?;
+    read_http_body(&mut stream)
+}
+
+/// Body of a `Connection: close` response; anything but 200 is an error.
+fn read_http_body(stream: &mut TcpStream) -> io::Result<String> {
     let mut raw = Vec::new();
     stream.read_to_end(&mut raw)?;
     let raw = String::from_utf8(raw)
@@ -151,6 +304,86 @@ fn post_jsonrpc_http(addr: &str, payload_json: &str) -> io::Result<String> {
     Ok(body.to_string())
 }
 
+const KEYCHAIN_SERVICE: &str = "com.cmndnctrl.desktop";
+
+fn keychain_entry(name: &str) -> Result<keyring::Entry, String> {
+    if name.trim().is_empty() {
+        return Err("secret name must not be empty".to_string());
+    }
+    keyring::Entry::new(KEYCHAIN_SERVICE, name.trim()).map_err(|err| format!("keychain: {err}"))
+}
+
+/// Backs `api_key_ref: "keychain:<name>"` for the in-process agent and the `secret_get` command.
+fn keychain_get(name: &str) -> Result<Option<String>, String> {
+    match keychain_entry(name)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(format!("keychain: {err}")),
+    }
+}
+
+#[cfg_attr(not(feature = "tauri-app"), allow(dead_code))]
+fn keychain_set(name: &str, secret: &str) -> Result<(), String> {
+    keychain_entry(name)?
+        .set_password(secret)
+        .map_err(|err| format!("keychain: {err}"))
+}
+
+/// One `events.subscribe` long-poll after `cursor`; returns the next cursor and the events with
+/// their `payload_json` decoded, ready to emit to the webview.
+#[cfg_attr(not(feature = "tauri-app"), allow(dead_code))]
+fn next_event_page(handle: &BackendHandle, cursor: u64) -> io::Result<(u64, Vec<Value>)> {
+    let payload = json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "method": "events.subscribe",
+        "params": { "since": cursor, "timeout_ms": EVENT_POLL_TIMEOUT_MS },
+    });
+    let resp = handle.jsonrpc_request(JsonRpcBridgeRequest {
+        payload_json: payload.to_string(),
+    })?;
+    let value: Value = serde_json::from_str(&resp.response_json)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("events response: {err}")))?;
+    let page: ipc::EventsSubscribeResponse = serde_json::from_value(value["result"].clone())
+        .map_err(|_| io::Error::other(format!("events.subscribe failed: {}", value["error"])))?;
+    let events = page
+        .events
+        .into_iter()
+        .map(|event| {
+            json!({
+                "seq": event.seq,
+                "event_type": event.event_type,
+                "timestamp_unix_seconds": event.timestamp_unix_seconds,
+                "payload": serde_json::from_str::<Value>(&event.payload_json).unwrap_or(Value::Null),
+            })
+        })
+        .collect();
+    Ok((page.cursor, events))
+}
+
+/// JSON-RPC payload for a native approval dialog's answer: `consent.approve` (optionally for a
+/// subset of pending actions) or `consent.deny`.
+#[cfg_attr(not(feature = "tauri-app"), allow(dead_code))]
+fn consent_respond_payload(
+    consent_id: &str,
+    approve: bool,
+    approve_indices: Option<Vec<usize>>,
+    deny_indices: Vec<usize>,
+) -> String {
+    let method = if approve { "consent.approve" } else { "consent.deny" };
+    json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "method": method,
+        "params": {
+            "consent_id": consent_id,
+            "approve_indices": approve_indices,
+            "deny_indices": deny_indices,
+        },
+    })
+    .to_string()
+}
+
 fn repo_root_guess() -> io::Result<PathBuf> {
     let cwd = env::current_dir()?;
     if cwd.ends_with("src-tauri") {
@@ -183,35 +416,104 @@ impl Default for TauriBridgeState {
     }
 }
 
+// `async` keeps long-polls off the main thread; the lock is only held to resolve the backend.
 #[cfg(feature = "tauri-app")]
-#[tauri::command]
+#[tauri::command(async)]
 fn jsonrpc_request(
     state: tauri::State<'_, TauriBridgeState>,
     payload_json: String,
 ) -> Result<String, String> {
-    let mut guard = state
+    let handle = state
         .manager
         .lock()
-        .map_err(|_| "backend bridge mutex poisoned".to_string())?;
-    jsonrpc_request_command(&mut guard, payload_json)
+        .map_err(|_| "backend bridge mutex poisoned".to_string())?
+        .handle()
+        .map_err(|e| e.to_string())?;
+    handle
+        .jsonrpc_request(JsonRpcBridgeRequest { payload_json })
+        .map(|r| r.response_json)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "tauri-app")]
+#[tauri::command(async)]
+fn consent_respond(
+    state: tauri::State<'_, TauriBridgeState>,
+    consent_id: String,
+    approve: bool,
+    approve_indices: Option<Vec<usize>>,
+    deny_indices: Option<Vec<usize>>,
+) -> Result<String, String> {
+    let handle = state
+        .manager
+        .lock()
+        .map_err(|_| "backend bridge mutex poisoned".to_string())?
+        .handle()
+        .map_err(|e| e.to_string())?;
+    let payload_json = consent_respond_payload(&consent_id, approve, approve_indices, deny_indices.unwrap_or_default());
+    handle
+        .jsonrpc_request(JsonRpcBridgeRequest { payload_json })
+        .map(|r| r.response_json)
+        .map_err(|e| e.to_string())
+}
+
+/// Stores a provider key in the OS keychain; reference it with `api_key_ref: "keychain:<name>"`.
+#[cfg(feature = "tauri-app")]
+#[tauri::command]
+fn secret_set(name: String, secret: String) -> Result<(), String> {
+    keychain_set(&name, &secret)
+}
+
+#[cfg(feature = "tauri-app")]
+#[tauri::command]
+fn secret_get(name: String) -> Result<Option<String>, String> {
+    keychain_get(&name)
+}
+
+/// Forwards backend events (pending consents, MCP exits, ...) to the webview as `agent-event`.
+#[cfg(feature = "tauri-app")]
+fn spawn_event_pump(app: tauri::AppHandle, handle: BackendHandle) {
+    use tauri::Emitter;
+    thread::spawn(move || {
+        let mut cursor = 0;
+        loop {
+            match next_event_page(&handle, cursor) {
+                Ok((next, events)) => {
+                    cursor = next;
+                    for event in events {
+                        let _ = app.emit(AGENT_EVENT, event);
+                    }
+                }
+                Err(_) => thread::sleep(EVENT_RETRY),
+            }
+        }
+    });
 }
 
 #[cfg(feature = "tauri-app")]
 fn tauri_integration_contract() {
+    use tauri::Manager;
     tauri::Builder::default()
         .manage(TauriBridgeState::default())
-        .invoke_handler(tauri::generate_handler![jsonrpc_request])
+        .setup(|app| {
+            let handle = app
+                .state::<TauriBridgeState>()
+                .manager
+                .lock()
+                .map_err(|_| "backend bridge mutex poisoned")?
+                .handle()?;
+            spawn_event_pump(app.handle().clone(), handle);
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![jsonrpc_request, consent_respond, secret_set, secret_get])
         .run(tauri::generate_context!())
         .expect("failed to run tauri app");
 }
 
 #[cfg(not(feature = "tauri-app"))]
 fn main() {
-    // Compile-safe bridge binary for eventual Tauri v2 integration.
-    // Planned next step:
-    // - add `tauri` dependency and annotate `jsonrpc_request_command` as a Tauri command
-    // - store `BackendProcessManager` in managed Tauri state
-    // - call from frontend via `invoke('jsonrpc_request', { payloadJson })`
+    // Headless probe of the bridge (in-process by default, `CMND_N_CTRL_BACKEND_MODE=http` for the
+    // spawned `serve-http` path); the windowed app needs `--features tauri-app`.
     let mut manager = BackendProcessManager::default();
 
     let probe_payload =
@@ -239,6 +541,7 @@ mod tests {
         // Structural sanity test for the bridge command wrapper contract.
         let mut manager = BackendProcessManager {
             child: None,
+            in_process: None,
             mode: BackendMode::HttpDev,
             http_addr: "127.0.0.1:1".to_string(),
             auto_spawn: false,
@@ -249,4 +552,46 @@ mod tests {
         );
         assert!(result.is_err());
     }
+
+    #[test]
+    fn in_process_mode_serves_requests_without_a_subprocess() {
+        let mut manager = BackendProcessManager {
+            child: None,
+            in_process: None,
+            mode: BackendMode::InProcess,
+            http_addr: "127.0.0.1:1".to_string(),
+            auto_spawn: false,
+        };
+        let resp = jsonrpc_request_command(
+            &mut manager,
+            "{\"jsonrpc\":\"2.0\",\"id\":7,\"method\":\"tools.list\",\"params\":{}}".to_string(),
+        )
+        .expect("in-process response");
+        let value: Value = serde_json::from_str(&resp).expect("json");
+        assert_eq!(value["id"], 7);
+        assert!(value["result"].is_array());
+        assert!(manager.child.is_none());
+
+        let bad = jsonrpc_request_command(&mut manager, "not json".to_string()).expect("error response");
+        assert!(bad.contains("-32700"));
+    }
+
+    #[test]
+    fn keychain_rejects_empty_secret_names() {
+        assert!(keychain_get("  ").is_err());
+        assert!(keychain_set("", "sk-test").is_err());
+    }
+
+    #[test]
+    fn consent_respond_maps_to_consent_rpcs() {
+        let approve: Value = serde_json::from_str(&consent_respond_payload("consent-1", true, Some(vec![0, 2]), vec![1]))
+            .expect("json");
+        assert_eq!(approve["method"], "consent.approve");
+        assert_eq!(approve["params"]["approve_indices"], json!([0, 2]));
+        assert_eq!(approve["params"]["deny_indices"], json!([1]));
+
+        let deny: Value = serde_json::from_str(&consent_respond_payload("consent-1", false, None, Vec::new())).expect("json");
+        assert_eq!(deny["method"], "consent.deny");
+        assert_eq!(deny["params"]["consent_id"], "consent-1");
+    }
 }
@@ -45,6 +45,7 @@ let pendingConsentFingerprint = null;
 let pendingConsentToken = null;
 let pendingConsentMeta = null;
 let historyFilter = '';
+let eventCursor = 0;
 
 function nowLabel() {
   return new Date().toLocaleTimeString([], { hour: '2-digit', minute: '2-digit', second: '2-digit' });
@@ -607,18 +608,39 @@ async function runConsentQueue() {
   const sessionId = sessionIdEl && typeof sessionIdEl.value === 'string' ? sessionIdEl.value.trim() : '';
   const json = await callJsonRpc('consent.list', { status: 'pending', session_id: sessionId || null });
   setRaw(json);
-  if (json && json.result) setWorkspaceState({ approvals: json.result });
-  const consents = Array.isArray(json && json.result) ? json.result : [];
+  const consents = json && json.result && Array.isArray(json.result.items) ? json.result.items : [];
+  if (json && json.result) setWorkspaceState({ approvals: consents, total: json.result.total });
   setCurrentAction('event', `Approvals Queue (${consents.length})`, consents.map((c) => `${c.consent_id}: ${c.tool_name}`).join('\n') || '(empty)', ['consent-queue']);
   setStatus('Approvals queue received');
 }
 
+// Startup state in one `rpc.batch` round-trip instead of four separate calls.
+async function loadWorkspace() {
+  const json = await callJsonRpc('rpc.batch', {
+    requests: [
+      { method: 'providers.list', params: {} },
+      { method: 'sessions.list', params: {} },
+      { method: 'consent.list', params: { status: 'pending', session_id: null } },
+      { method: 'system.health', params: {} },
+    ],
+    abort_on_error: false,
+  });
+  const [providers, sessions, approvals, health] = (json && json.result && json.result.responses) || [];
+  if (!providers) return;
+  setWorkspaceState({
+    providers: providers.result || [],
+    sessions: sessions.result || [],
+    approvals: (approvals.result && approvals.result.items) || [],
+    health: health.result || null,
+  });
+}
+
 async function runAuditList() {
   const sessionId = sessionIdEl && typeof sessionIdEl.value === 'string' ? sessionIdEl.value.trim() : '';
   const json = await callJsonRpc('audit.list', { session_id: sessionId || null, limit: 20 });
   setRaw(json);
-  if (json && json.result) setWorkspaceState({ audit: json.result });
-  const audits = Array.isArray(json && json.result) ? json.result : [];
+  const audits = json && json.result && Array.isArray(json.result.items) ? json.result.items : [];
+  if (json && json.result) setWorkspaceState({ audit: audits, total: json.result.total });
   setCurrentAction('event', `Audit (${audits.length})`, audits.map((a) => `${a.audit_id} ${a.provider}`).join('\n') || '(empty)', ['audit']);
   setStatus('Audit list received');
 }
@@ -769,6 +791,51 @@ function escapeHtml(value) {
     .replaceAll("'", '&#39;');
 }
 
+const EVENT_LONG_POLL_MS = 25000;
+const EVENT_RETRY_MS = 5000;
+
+function describeAgentEvent(event) {
+  let payload = event.payload || {};
+  if (event.payload_json) {
+    try {
+      payload = JSON.parse(event.payload_json);
+    } catch (_) {
+      payload = {};
+    }
+  }
+  switch (event.event_type) {
+    case 'consent.created':
+      return ['warn', 'Approval Requested', `${payload.consent_id || ''} ${payload.tool_name || ''}`.trim()];
+    case 'consent.resolved':
+      return ['event', 'Approval Resolved', `${payload.consent_id || ''} ${payload.status || ''}`.trim()];
+    case 'mcp.server.exited':
+      return ['warn', 'MCP Server Exited', `${payload.name || payload.server_id || ''} ${payload.exit_status || ''}`.trim()];
+    default:
+      return null;
+  }
+}
+
+// Long-polls events.subscribe so consent and MCP changes made elsewhere (CLI, expiry) show up
+// without the user re-listing. Audit and session events only advance the cursor.
+async function watchAgentEvents() {
+  for (;;) {
+    try {
+      const json = await transport.callJsonRpc({
+        jsonrpc: '2.0',
+        id: Date.now(),
+        method: 'events.subscribe',
+        params: { since: eventCursor, timeout_ms: EVENT_LONG_POLL_MS },
+      });
+      const result = json && json.result;
+      if (!result) throw new Error((json && json.error && json.error.message) || 'events.subscribe failed');
+      eventCursor = result.cursor;
+      (result.events || []).forEach(showAgentEvent);
+    } catch (_) {
+      await new Promise((resolve) => setTimeout(resolve, EVENT_RETRY_MS));
+    }
+  }
+}
+
 function getTauriInvoke() {
   const tauri = window.__TAURI__;
   if (tauri && tauri.core && typeof tauri.core.invoke === 'function') {
@@ -964,3 +1031,42 @@ clearHistory();
 clearConsent();
 setCurrentAction('event', 'Ready', 'No actions yet.', ['idle']);
 setStatus(`Ready (${transport.name})`);
+loadWorkspace().catch((err) => setStatus(`Ready (${transport.name}); workspace not loaded: ${err}`));
+function showAgentEvent(event) {
+  const described = describeAgentEvent(event);
+  if (described) pushHistory(described[0], described[1], described[2], { status: event.event_type });
+}
+
+// Native approval dialog for consents raised outside the chat flow (CLI, other sessions).
+async function promptConsentNatively(tauriInvoke, event) {
+  const record = event.payload || {};
+  if (!record.consent_id) return;
+  const approve = window.confirm(
+    `Approve ${record.tool_name || 'action'} (${record.capability_tier || 'unknown tier'})?\n\n${record.rationale || ''}`,
+  );
+  try {
+    await tauriInvoke('consent_respond', { consentId: record.consent_id, approve });
+    setStatus(approve ? 'Consent approved' : 'Consent denied');
+  } catch (err) {
+    setStatus(humanizeConsentRpcError(String(err)) || `consent_respond failed: ${err}`);
+  }
+}
+
+// Under Tauri the bridge pushes `agent-event`s; in the browser dev loop we long-poll instead.
+function startAgentEvents() {
+  const tauri = window.__TAURI__;
+  const tauriInvoke = getTauriInvoke();
+  if (tauri && tauri.event && typeof tauri.event.listen === 'function' && tauriInvoke) {
+    tauri.event.listen('agent-event', ({ payload }) => {
+      showAgentEvent(payload);
+      // Consents from this window's own chat requests use the in-page consent card instead.
+      if (payload.event_type === 'consent.created' && !pendingConsentToken && !sendBtn.disabled) {
+        promptConsentNatively(tauriInvoke, payload);
+      }
+    });
+    return;
+  }
+  watchAgentEvents();
+}
+
+startAgentEvents();
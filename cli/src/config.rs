@@ -0,0 +1,124 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use directories::ProjectDirs;
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DefaultMode {
+    #[default]
+    BestEffort,
+    RequireConfirmation,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    /// One compact JSON event per line, for scripts and CI.
+    Jsonl,
+}
+
+impl OutputFormat {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "jsonl" => Ok(Self::Jsonl),
+            other => Err(format!("unknown output format '{other}' (expected text|json|jsonl)")),
+        }
+    }
+}
+
+/// Defaults read from `cli.toml`; explicit flags always win.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CliConfig {
+    pub provider: Option<String>,
+    pub addr: Option<String>,
+    #[serde(default)]
+    pub mode: DefaultMode,
+    #[serde(default)]
+    pub output: OutputFormat,
+    /// Always use plain output (see `plain_output`).
+    #[serde(default)]
+    pub plain: bool,
+    /// Opt-in: look for a newer release (at most daily) before running a command.
+    #[serde(default)]
+    pub update_check: bool,
+    /// Turns off every release check, `cli update check` included; for managed installs.
+    #[serde(default)]
+    pub disable_update_check: bool,
+    /// Release endpoint; defaults to the project's GitHub releases.
+    pub update_url: Option<String>,
+}
+
+impl CliConfig {
+    pub fn update_url(&self) -> &str {
+        self.update_url.as_deref().unwrap_or(crate::update::DEFAULT_RELEASES_URL)
+    }
+}
+
+static CLI_CONFIG: OnceLock<CliConfig> = OnceLock::new();
+static OUTPUT_OVERRIDE: OnceLock<OutputFormat> = OnceLock::new();
+static PLAIN_OVERRIDE: OnceLock<bool> = OnceLock::new();
+
+/// `$CMND_N_CTRL_CLI_CONFIG`, else `cli.toml` in the platform config dir
+/// (`~/.config/cmnd-n-ctrl/cli.toml` on Linux).
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("CMND_N_CTRL_CLI_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    ProjectDirs::from("com", "cmnd-n-ctrl", "cmnd-n-ctrl").map(|dirs| dirs.config_dir().join("cli.toml"))
+}
+
+fn load() -> Result<CliConfig, String> {
+    let Some(path) = config_path() else {
+        return Ok(CliConfig::default());
+    };
+    if !path.exists() {
+        return Ok(CliConfig::default());
+    }
+    let raw = fs::read_to_string(&path).map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+    toml::from_str(&raw).map_err(|err| format!("failed to parse {}: {err}", path.display()))
+}
+
+/// Loads the config once per process; a broken file is reported and ignored rather than
+/// blocking every command.
+pub fn cli_config() -> &'static CliConfig {
+    CLI_CONFIG.get_or_init(|| {
+        load().unwrap_or_else(|err| {
+            eprintln!("warning: ignoring cli config: {err}");
+            CliConfig::default()
+        })
+    })
+}
+
+/// Records the global `--output` flag; must run before anything is printed.
+pub fn set_output_format(format: OutputFormat) {
+    let _ = OUTPUT_OVERRIDE.set(format);
+}
+
+pub fn output_format() -> OutputFormat {
+    OUTPUT_OVERRIDE.get().copied().unwrap_or(cli_config().output)
+}
+
+/// Records the global `--plain` flag.
+pub fn set_plain_output() {
+    let _ = PLAIN_OVERRIDE.set(true);
+}
+
+/// Screen-reader friendly output: whole labeled lines only, with no ANSI escapes, no
+/// token-by-token streaming and no full-screen TUI. On with `--plain`, `plain = true`,
+/// `NO_COLOR` or `TERM=dumb`.
+pub fn plain_output() -> bool {
+    PLAIN_OVERRIDE.get().copied().unwrap_or(false)
+        || cli_config().plain
+        || env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+        || env::var("TERM").is_ok_and(|term| term == "dumb")
+}
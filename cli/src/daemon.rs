@@ -0,0 +1,160 @@
+use std::env;
+use std::fs;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use agent::AgentService;
+
+const PID_FILE: &str = "daemon.pid";
+const ADDR_FILE: &str = "daemon.addr";
+const LOG_FILE: &str = "daemon.log";
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+const PROBE_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Pidfile and address of a backgrounded `serve-http`, kept next to the agent's storage.
+#[derive(Clone, Debug)]
+pub struct DaemonInfo {
+    pub pid: u32,
+    pub addr: String,
+}
+
+fn state_path(name: &str) -> PathBuf {
+    AgentService::default_storage_dir().join(name)
+}
+
+pub fn log_path() -> PathBuf {
+    state_path(LOG_FILE)
+}
+
+pub fn read_info() -> Option<DaemonInfo> {
+    let pid = fs::read_to_string(state_path(PID_FILE)).ok()?.trim().parse().ok()?;
+    let addr = fs::read_to_string(state_path(ADDR_FILE)).ok()?.trim().to_string();
+    if addr.is_empty() {
+        return None;
+    }
+    Some(DaemonInfo { pid, addr })
+}
+
+fn clear_info() {
+    let _ = fs::remove_file(state_path(PID_FILE));
+    let _ = fs::remove_file(state_path(ADDR_FILE));
+}
+
+pub fn is_listening(addr: &str) -> bool {
+    if let Some(path) = crate::pipe::pipe_path(addr) {
+        return crate::pipe::is_listening(&path);
+    }
+    addr.to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .map(|sock| TcpStream::connect_timeout(&sock, PROBE_TIMEOUT).is_ok())
+        .unwrap_or(false)
+}
+
+/// Address of a running daemon, if its pidfile exists and it still accepts connections.
+pub fn discover_addr() -> Option<String> {
+    read_info().map(|info| info.addr).filter(|addr| is_listening(addr))
+}
+
+/// Re-executes the current binary as `serve-http --addr <addr>` detached from the terminal,
+/// waits for it to accept connections, then records its pid and address.
+pub fn start(addr: &str) -> Result<DaemonInfo, String> {
+    if let Some(info) = read_info() {
+        if is_listening(&info.addr) {
+            return Err(format!("daemon already running at {} (pid {})", info.addr, info.pid));
+        }
+        clear_info();
+    }
+    if is_listening(addr) {
+        return Err(format!("address {addr} is already in use"));
+    }
+
+    let exe = env::current_exe().map_err(|err| format!("failed to locate cli binary: {err}"))?;
+    let log = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path())
+        .map_err(|err| format!("failed to open {}: {err}", log_path().display()))?;
+    let mut command = Command::new(exe);
+    command
+        .args(["serve-http", "--addr", addr])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(log);
+    detach(&mut command);
+    let mut child = command.spawn().map_err(|err| format!("failed to spawn daemon: {err}"))?;
+
+    let deadline = Instant::now() + STARTUP_TIMEOUT;
+    while !is_listening(addr) {
+        if let Ok(Some(status)) = child.try_wait() {
+            return Err(format!(
+                "daemon exited during startup ({status}); see {}",
+                log_path().display()
+            ));
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            return Err(format!("daemon did not start listening on {addr} within {}s", STARTUP_TIMEOUT.as_secs()));
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    let info = DaemonInfo {
+        pid: child.id(),
+        addr: addr.to_string(),
+    };
+    fs::write(state_path(PID_FILE), format!("{}\n", info.pid))
+        .and_then(|_| fs::write(state_path(ADDR_FILE), format!("{}\n", info.addr)))
+        .map_err(|err| format!("failed to write daemon pidfile: {err}"))?;
+    Ok(info)
+}
+
+/// Terminates the recorded daemon and removes its pidfile. Stale pidfiles are cleaned up too.
+pub fn stop() -> Result<Option<DaemonInfo>, String> {
+    let Some(info) = read_info() else {
+        return Ok(None);
+    };
+    if is_listening(&info.addr) {
+        terminate(info.pid)?;
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        while is_listening(&info.addr) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+    clear_info();
+    Ok(Some(info))
+}
+
+#[cfg(unix)]
+fn detach(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    // Own process group, so Ctrl+C in the launching shell does not reach the daemon.
+    command.process_group(0);
+}
+
+#[cfg(windows)]
+fn detach(command: &mut Command) {
+    use std::os::windows::process::CommandExt;
+    const DETACHED_PROCESS: u32 = 0x0000_0008;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+}
+
+#[cfg(not(any(unix, windows)))]
+fn detach(_command: &mut Command) {}
+
+fn terminate(pid: u32) -> Result<(), String> {
+    let status = if cfg!(windows) {
+        Command::new("taskkill").args(["/PID", &pid.to_string(), "/F"]).status()
+    } else {
+        Command::new("kill").arg(pid.to_string()).status()
+    };
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("failed to stop daemon pid {pid} ({status})")),
+        Err(err) => Err(format!("failed to stop daemon pid {pid}: {err}")),
+    }
+}
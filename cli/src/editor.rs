@@ -0,0 +1,200 @@
+/// Small multi-line prompt editor for the TUI input box: a byte-indexed cursor kept on char
+/// boundaries, word-wise motion, and shell-style history recall.
+#[derive(Debug, Default)]
+pub struct LineEditor {
+    buffer: String,
+    cursor: usize,
+    history: Vec<String>,
+    history_pos: Option<usize>,
+    draft: String,
+}
+
+impl LineEditor {
+    const HISTORY_LIMIT: usize = 200;
+
+    pub fn text(&self) -> &str {
+        &self.buffer
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.buffer.split('\n').count()
+    }
+
+    /// Cursor as `(line, column)` in chars, for placing the terminal cursor.
+    pub fn cursor_line_col(&self) -> (usize, usize) {
+        let before = &self.buffer[..self.cursor];
+        let line = before.matches('\n').count();
+        let col = before.rsplit('\n').next().unwrap_or("").chars().count();
+        (line, col)
+    }
+
+    pub fn insert_char(&mut self, ch: char) {
+        self.buffer.insert(self.cursor, ch);
+        self.cursor += ch.len_utf8();
+    }
+
+    pub fn insert_newline(&mut self) {
+        self.insert_char('\n');
+    }
+
+    pub fn backspace(&mut self) {
+        if let Some(prev) = self.prev_boundary(self.cursor) {
+            self.buffer.replace_range(prev..self.cursor, "");
+            self.cursor = prev;
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if let Some(next) = self.next_boundary(self.cursor) {
+            self.buffer.replace_range(self.cursor..next, "");
+        }
+    }
+
+    pub fn move_left(&mut self) {
+        if let Some(prev) = self.prev_boundary(self.cursor) {
+            self.cursor = prev;
+        }
+    }
+
+    pub fn move_right(&mut self) {
+        if let Some(next) = self.next_boundary(self.cursor) {
+            self.cursor = next;
+        }
+    }
+
+    pub fn move_word_left(&mut self) {
+        let before = &self.buffer[..self.cursor];
+        let trimmed = before.trim_end_matches(|c: char| !c.is_alphanumeric());
+        self.cursor = trimmed
+            .char_indices()
+            .rev()
+            .find(|(_, c)| !c.is_alphanumeric())
+            .map(|(idx, c)| idx + c.len_utf8())
+            .unwrap_or(0);
+    }
+
+    pub fn move_word_right(&mut self) {
+        let after = &self.buffer[self.cursor..];
+        let skip_gap = after.len() - after.trim_start_matches(|c: char| !c.is_alphanumeric()).len();
+        let word = &after[skip_gap..];
+        let word_len = word.find(|c: char| !c.is_alphanumeric()).unwrap_or(word.len());
+        self.cursor += skip_gap + word_len;
+    }
+
+    pub fn move_line_start(&mut self) {
+        self.cursor = self.buffer[..self.cursor].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    }
+
+    pub fn move_line_end(&mut self) {
+        self.cursor += self.buffer[self.cursor..].find('\n').unwrap_or(self.buffer.len() - self.cursor);
+    }
+
+    /// Moves to the previous line of a multi-line buffer; returns false on the first line so the
+    /// caller can fall back to history recall.
+    pub fn move_up(&mut self) -> bool {
+        let (line, col) = self.cursor_line_col();
+        if line == 0 {
+            return false;
+        }
+        self.cursor = self.offset_for(line - 1, col);
+        true
+    }
+
+    pub fn move_down(&mut self) -> bool {
+        let (line, col) = self.cursor_line_col();
+        if line + 1 >= self.line_count() {
+            return false;
+        }
+        self.cursor = self.offset_for(line + 1, col);
+        true
+    }
+
+    /// Up arrow: previous line, or the previous history entry from the first line.
+    pub fn up(&mut self) {
+        if !self.move_up() {
+            self.history_prev();
+        }
+    }
+
+    pub fn down(&mut self) {
+        if !self.move_down() {
+            self.history_next();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.cursor = 0;
+        self.history_pos = None;
+    }
+
+    /// Returns the buffer for submission, recording it in history.
+    pub fn submit(&mut self) -> String {
+        let text = std::mem::take(&mut self.buffer);
+        self.cursor = 0;
+        self.history_pos = None;
+        if !text.trim().is_empty() && self.history.last() != Some(&text) {
+            self.history.push(text.clone());
+            if self.history.len() > Self::HISTORY_LIMIT {
+                self.history.remove(0);
+            }
+        }
+        text
+    }
+
+    pub fn history_prev(&mut self) {
+        let next = match self.history_pos {
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = self.buffer.clone();
+                self.history.len() - 1
+            }
+            Some(0) => return,
+            Some(pos) => pos - 1,
+        };
+        self.history_pos = Some(next);
+        self.replace_buffer(self.history[next].clone());
+    }
+
+    pub fn history_next(&mut self) {
+        let Some(pos) = self.history_pos else {
+            return;
+        };
+        if pos + 1 < self.history.len() {
+            self.history_pos = Some(pos + 1);
+            self.replace_buffer(self.history[pos + 1].clone());
+        } else {
+            self.history_pos = None;
+            let draft = std::mem::take(&mut self.draft);
+            self.replace_buffer(draft);
+        }
+    }
+
+    fn replace_buffer(&mut self, text: String) {
+        self.buffer = text;
+        self.cursor = self.buffer.len();
+    }
+
+    fn offset_for(&self, line: usize, col: usize) -> usize {
+        let start = self
+            .buffer
+            .split('\n')
+            .take(line)
+            .map(|l| l.len() + 1)
+            .sum::<usize>();
+        let text = self.buffer[start..].split('\n').next().unwrap_or("");
+        start + text.char_indices().nth(col).map(|(i, _)| i).unwrap_or(text.len())
+    }
+
+    fn prev_boundary(&self, idx: usize) -> Option<usize> {
+        self.buffer[..idx].char_indices().next_back().map(|(i, _)| i)
+    }
+
+    fn next_boundary(&self, idx: usize) -> Option<usize> {
+        self.buffer[idx..].chars().next().map(|c| idx + c.len_utf8())
+    }
+}
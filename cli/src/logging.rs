@@ -0,0 +1,215 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use agent::redaction::Redactor;
+use agent::AgentService;
+use serde_json::Value;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::EnvFilter;
+
+const LOG_PREFIX: &str = "cmnd-n-ctrl";
+const LOG_SUFFIX: &str = "log";
+const MAX_LOG_FILES: usize = 7;
+const FOLLOW_POLL: Duration = Duration::from_millis(500);
+
+pub fn log_dir() -> PathBuf {
+    AgentService::default_storage_dir().join("logs")
+}
+
+/// Installs a JSON-lines subscriber writing to a daily-rotated file under `log_dir()`. The level
+/// comes from `CMND_N_CTRL_LOG` (falling back to `RUST_LOG`, then `info`) using `EnvFilter`
+/// syntax. Lines pass through the `policy.json` redaction rules as read at startup. Logging is
+/// best-effort: an unwritable dir just leaves it disabled.
+pub fn init() {
+    let directives = env::var("CMND_N_CTRL_LOG")
+        .or_else(|_| env::var("RUST_LOG"))
+        .unwrap_or_else(|_| "info".to_string());
+    let filter = EnvFilter::try_new(&directives).unwrap_or_else(|_| EnvFilter::new("info"));
+    if fs::create_dir_all(log_dir()).is_err() {
+        return;
+    }
+    let Ok(appender) = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_PREFIX)
+        .filename_suffix(LOG_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(log_dir())
+    else {
+        return;
+    };
+    let _ = tracing_subscriber::fmt()
+        .json()
+        .with_ansi(false)
+        .with_env_filter(filter)
+        .with_writer(RedactingMakeWriter {
+            inner: appender,
+            redactor: Redactor::load(&AgentService::default_storage_dir()),
+        })
+        .try_init();
+}
+
+struct RedactingMakeWriter<M> {
+    inner: M,
+    redactor: Redactor,
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactingMakeWriter<M> {
+    type Writer = RedactingWriter<'a, M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter {
+            inner: self.inner.make_writer(),
+            redactor: &self.redactor,
+        }
+    }
+}
+
+/// The fmt layer hands each formatted event to a single `write`, so every call is one JSON line.
+struct RedactingWriter<'a, W> {
+    inner: W,
+    redactor: &'a Redactor,
+}
+
+impl<W: Write> Write for RedactingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let line = String::from_utf8_lossy(buf);
+        let redacted = self.redactor.redact_json_str(line.trim_end());
+        self.inner.write_all(redacted.as_bytes())?;
+        if line.ends_with('\n') {
+            self.inner.write_all(b"\n")?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn level_rank(level: &str) -> u8 {
+    match level.to_ascii_uppercase().as_str() {
+        "TRACE" => 0,
+        "DEBUG" => 1,
+        "INFO" => 2,
+        "WARN" | "WARNING" => 3,
+        "ERROR" => 4,
+        _ => 2,
+    }
+}
+
+pub fn parse_level(level: &str) -> Result<u8, String> {
+    match level.to_ascii_lowercase().as_str() {
+        "trace" | "debug" | "info" | "warn" | "warning" | "error" => Ok(level_rank(level)),
+        other => Err(format!("unknown log level '{other}' (expected trace|debug|info|warn|error)")),
+    }
+}
+
+fn newest_log_file() -> Option<PathBuf> {
+    let mut files = fs::read_dir(log_dir())
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.starts_with(LOG_PREFIX) && name.ends_with(LOG_SUFFIX))
+                .unwrap_or(false)
+        })
+        .collect::<Vec<_>>();
+    // Rotated names embed the date, so lexical order is chronological.
+    files.sort();
+    files.pop()
+}
+
+/// Renders one JSON log line as `timestamp LEVEL target: message key=value...`, or `None` when
+/// it is below `min_level`. Non-JSON lines pass through unchanged.
+fn format_line(line: &str, min_level: u8, raw: bool) -> Option<String> {
+    let Ok(Value::Object(entry)) = serde_json::from_str::<Value>(line) else {
+        return Some(line.to_string());
+    };
+    let level = entry.get("level").and_then(Value::as_str).unwrap_or("INFO");
+    if level_rank(level) < min_level {
+        return None;
+    }
+    if raw {
+        return Some(line.to_string());
+    }
+    let timestamp = entry.get("timestamp").and_then(Value::as_str).unwrap_or("");
+    let target = entry.get("target").and_then(Value::as_str).unwrap_or("");
+    let mut out = format!("{timestamp} {level:>5} {target}:");
+    if let Some(Value::Object(fields)) = entry.get("fields") {
+        if let Some(message) = fields.get("message").and_then(Value::as_str) {
+            out.push_str(&format!(" {message}"));
+        }
+        for (key, value) in fields.iter().filter(|(key, _)| key.as_str() != "message") {
+            match value {
+                Value::String(s) => out.push_str(&format!(" {key}={s}")),
+                other => out.push_str(&format!(" {key}={other}")),
+            }
+        }
+    }
+    Some(out)
+}
+
+/// Prints the last `lines` entries at or above `min_level`; with `follow`, keeps polling the
+/// newest file (switching over when the appender rotates) until interrupted.
+pub fn tail(lines: usize, min_level: u8, follow: bool, raw: bool) -> Result<(), String> {
+    let Some(mut path) = newest_log_file() else {
+        if !follow {
+            println!("no log files in {}", log_dir().display());
+            return Ok(());
+        }
+        return follow_from(None, min_level, raw);
+    };
+    let file = File::open(&path).map_err(|err| format!("failed to open {}: {err}", path.display()))?;
+    let mut reader = BufReader::new(file);
+    let mut recent = Vec::new();
+    let mut buf = String::new();
+    while reader.read_line(&mut buf).map_err(|err| err.to_string())? > 0 {
+        if let Some(formatted) = format_line(buf.trim_end(), min_level, raw) {
+            recent.push(formatted);
+        }
+        buf.clear();
+    }
+    for line in &recent[recent.len().saturating_sub(lines)..] {
+        println!("{line}");
+    }
+    if !follow {
+        return Ok(());
+    }
+    let offset = reader.stream_position().map_err(|err| err.to_string())?;
+    path = path.canonicalize().unwrap_or(path);
+    follow_from(Some((path, offset)), min_level, raw)
+}
+
+fn follow_from(mut current: Option<(PathBuf, u64)>, min_level: u8, raw: bool) -> Result<(), String> {
+    loop {
+        if let Some(newest) = newest_log_file() {
+            let newest = newest.canonicalize().unwrap_or(newest);
+            if current.as_ref().map(|(path, _)| path != &newest).unwrap_or(true) {
+                current = Some((newest, 0));
+            }
+        }
+        if let Some((path, offset)) = current.as_mut() {
+            if let Ok(mut file) = File::open(&*path) {
+                if file.seek(SeekFrom::Start(*offset)).is_ok() {
+                    let mut reader = BufReader::new(file);
+                    let mut buf = String::new();
+                    // Only consume complete lines so a half-written entry is re-read next poll.
+                    while reader.read_line(&mut buf).unwrap_or(0) > 0 && buf.ends_with('\n') {
+                        *offset += buf.len() as u64;
+                        if let Some(formatted) = format_line(buf.trim_end(), min_level, raw) {
+                            println!("{formatted}");
+                        }
+                        buf.clear();
+                    }
+                }
+            }
+        }
+        thread::sleep(FOLLOW_POLL);
+    }
+}
@@ -1,27 +1,32 @@
+mod config;
+mod daemon;
+mod editor;
+mod logging;
+mod pipe;
+mod service;
 mod tui;
+mod update;
 
 use std::env;
+use std::fmt;
 use std::io::{self, BufRead, BufReader, IsTerminal, Read, Write};
 use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+
+use agent::orchestrator::StreamEvent;
 use agent::AgentService;
-use ipc::jsonrpc::{Id, Request};
-use ipc::{mcp, ChatApproveRequest, ChatDenyRequest, ChatMode, ChatRequest, ChatResponse, ExecutionFeedItem, JsonRpcClient, JsonRpcServer, ProviderConfig, Tool};
+use config::{cli_config, output_format, plain_output, DefaultMode, OutputFormat};
+use ipc::jsonrpc::{self, Id, Payload, Request};
+use ipc::{mcp, ChatApproveRequest, McpRestartPolicy, ChatDenyRequest, ChatMode, ChatRequest, ChatResponse, ExecutionFeedItem, ChatService, JsonRpcClient, JsonRpcServer, Middleware, ProjectRecentResponse, ProviderConfig, Tool, WorkflowRunResponse};
 use serde::{Deserialize, Serialize};
 use serde::de::DeserializeOwned;
 use serde_json::{json, Value};
 
-#[derive(Debug, Deserialize)]
-struct WireRequest {
-    #[serde(rename = "jsonrpc")]
-    _jsonrpc: String,
-    #[serde(default)]
-    id: Option<Value>,
-    method: String,
-    #[serde(default)]
-    params: Value,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 struct WireError {
     code: i64,
@@ -43,52 +48,152 @@ fn print_help() {
     println!();
     println!("USAGE:");
     println!("  cli        # interactive mode: ratatui TUI if available, else line REPL");
+    println!("  cli --addr <host:port>   # TUI attached to a running serve-http; /connect <addr>|local switches");
+    println!("  cli --no-tui   # line REPL even on a capable terminal");
     println!("  cli --help");
     println!("  cli tools [--json] [--raw] [--addr <host:port>]");
-    println!("  cli chat <message> [--provider <name>] [--session <id>] [--require-confirmation] [--json] [--addr <host:port>]");
+    println!("  cli tools enable|disable <name|prefix*> [--path <project>] [--global] [--json] [--addr <host:port>]");
+    println!("  cli chat <message> [--provider <name>] [--session <id>] [--require-confirmation|--best-effort] [--no-interactive] [--json] [--addr <host:port>]");
+    println!("           [--file <path>]... [--no-stdin]   # piped stdin and --file contents are attached as context");
+    println!("           [--image <path>]...   # PNG/JPEG/GIF/WebP sent inline with the prompt to vision-capable providers");
+    println!("           [--model <id>] [--temperature <f>] [--top-p <f>] [--max-tokens <n>] [--stop <seq>]...");
+    println!("           [--cache-ttl <secs>]   # reuse an identical completed response from the last <secs>");
+    println!("           [--dry-run]   # simulate tools that would change anything; nothing is written or sent");
     println!("  cli approve <consent-token> [--json] [--addr <host:port>]   # requires running serve-http");
     println!("  cli deny <consent-token> [--json] [--addr <host:port>]      # requires running serve-http");
-    println!("  cli consent list|approve|deny ...");
+    println!("  cli consent list|approve|deny ...       # approve <id> [--actions 0,2] [--deny-actions 1] [--scope session|always]");
+    println!("  cli consent list [--status <s>] [--session <id>] [--page N] [--limit N]");
+    println!("  cli consent grants|revoke <grant-id>|revoke --tool <name>");
     println!("  cli session new|list|open|rm|append ...");
+    println!("  cli session list [--all] [--pinned-first]|pin|unpin|archive|unarchive <id>   # archived sessions need --all");
+    println!("  cli session fork <id> [<message-index>] [--title <t>]   # copy messages 0..=index into a new session");
+    println!("  cli session edit <id> <index|msg-id> <text>|rm-message <id> <index|msg-id>");
+    println!("  cli session policy <id> [--profile <name>] [--allow <tools>] [--deny <tools>] [--clear]");
+    println!("  cli session update <id> [--title <t>] [--provider <name>] [--model <m>] [--mode confirm|best] [--clear]");
     println!("  cli auth login|list|logout ...");
-    println!("  cli providers list|set|config-get|config-set ...");
-    println!("  cli mcp servers list|add|rm|start|stop|probe|tools|call|tool-call ...");
-    println!("  cli project open|status ...");
-    println!("  cli audit list|show ...");
+    println!("  cli providers list|set|config-get|config-set|models|embed ...");
+    println!("  cli mcp servers list|add|rm|start|stop|probe|tools|policy|call|tool-call ...");
+    println!("  cli mcp import --from claude|cursor [--path <file>] [--restart <policy>]");
+    println!("  cli project open|recent|pin|unpin|list|switch|status|watch|changes ...");
+    println!("  cli audit list|show ...|replay <audit_id> --dry-run");
+    println!("  cli audit list [--session <id>] [--page N] [--limit N]   # pages hold 20 entries unless --limit is given");
+    println!("           [--since <t>] [--until <t>] [--tool <name|prefix*>] [--provider <name>]   # t: unix seconds or an age like 7d, 12h, 30m");
+    println!("  cli audit show <audit_id> [--artifacts [--out <dir>]]   # stored tool evidence: prints text artifacts, --out saves all");
+    println!("  cli policy explain <tool> [<args-json>] [--mode confirm|best] [--session <id>]");
+    println!("  cli policy profile [strict|balanced|permissive]");
+    println!("  cli state backup <file.tar.gz> [--exclude-secrets]|restore <file.tar.gz>|prune [--dry-run]");
+    println!("  cli tasks list|create \"<schedule>\" <prompt> [--mode confirm|best] [--provider <name>] [--session <id>]|rm <id>");
+    println!("           # schedule: 5 cron fields (UTC), @hourly|@daily|@weekly|@monthly or \"@every 15m\"; runs under serve-http");
+    println!("  cli workflow run <file.yaml|file.json> [--mode confirm|best] [--provider <name>] [--session <id>]");
     println!("  cli doctor [--json] [--strict] [--addr <host:port>]");
+    println!("  cli version [--json] [--addr <host:port>]   # this binary and the backend it talks to");
+    println!("  cli update check [--json]   # compare with the latest release and print upgrade instructions");
     println!("  cli tui   # legacy alias for interactive mode (prefer plain `cli`)");
     println!("  cli rpc <method> <params-json> [--addr <host:port>]");
+    println!("  cli rpc --describe                      # OpenRPC document for every method");
     println!("  cli serve-stdio");
-    println!("  cli serve-http [--addr <host:port>]");
+    println!("  cli serve-http [--addr <host:port>|pipe:<name>] [--daemon]   # pipe: is a Windows named pipe");
+    println!("  cli serve-http --systemd-socket         # serve on the socket passed by systemd socket activation");
+    println!("  cli service install|uninstall [--name <name>] [--addr <addr>]   # Windows service running serve-http");
+    println!("  cli service generate-unit [--name <name>] [--addr <host:port>] [--socket] [--user <user>]");
+    println!("                            [--read-write <path>]... [--out <dir>]   # hardened systemd unit");
+    println!("  cli logs [--follow] [--level trace|debug|info|warn|error] [--lines <n>] [--json]");
+    println!("  cli daemon status|stop   # other commands auto-use a running daemon unless --local/--addr is given");
+    println!("  cli shutdown [--addr <host:port>]   # graceful stop via system.shutdown (token from the state dir)");
+    println!();
+    println!("Global: --output text|json|jsonl   # jsonl prints one JSON event per line (result, chat.response,");
+    println!("        consent.required, error); exit codes: 0 ok, 1 failure, 2 usage, 3 unhealthy, 4 awaiting consent");
+    println!("        --plain   # screen-reader friendly: labeled whole lines, no colors, streaming or TUI");
+    println!("                  # (also plain = true in cli.toml, NO_COLOR or TERM=dumb)");
+    println!("        --state-dir <dir>   # agent state directory (also CMND_N_CTRL_STATE_DIR)");
+    println!();
+    println!("Defaults for --provider, --addr, mode and output can be set in ~/.config/cmnd-n-ctrl/cli.toml");
+    println!("(override the path with CMND_N_CTRL_CLI_CONFIG); pass --local to ignore a configured --addr.");
+    println!("update_check = true there enables a daily release check; disable_update_check = true or the global");
+    println!("--disable-update-check flag turns every release check off.");
 }
 
 fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    if let Some(pos) = args.iter().position(|a| a == "--output") {
+        let value = args.get(pos + 1).cloned().unwrap_or_default();
+        args.drain(pos..(pos + 2).min(args.len()));
+        match OutputFormat::parse(&value) {
+            Ok(format) => config::set_output_format(format),
+            Err(err) => usage_exit(format_args!("error: {err}")),
+        }
+    }
+    if let Some(pos) = args.iter().position(|a| a == "--state-dir") {
+        let Some(dir) = args.get(pos + 1).cloned() else {
+            usage_exit(format_args!("error: --state-dir needs a directory"));
+        };
+        args.drain(pos..pos + 2);
+        env::set_var(agent::STATE_DIR_ENV, dir);
+    }
+    if let Some(pos) = args.iter().position(|a| a == "--plain") {
+        args.remove(pos);
+        config::set_plain_output();
+    }
+    let no_tui = match args.iter().position(|a| a == "--no-tui") {
+        Some(pos) => {
+            args.remove(pos);
+            true
+        }
+        None => false,
+    };
+    let update_check_disabled = match args.iter().position(|a| a == "--disable-update-check") {
+        Some(pos) => {
+            args.remove(pos);
+            true
+        }
+        None => cli_config().disable_update_check,
+    };
     if args.iter().any(|a| a == "--help" || a == "-h") {
         print_help();
         return;
     }
 
+    let config = cli_config();
+    logging::init();
+    tracing::debug!(command = args.first().map(String::as_str).unwrap_or("interactive"), "cli started");
+
     let service = AgentService::new_for_platform("cli");
+    let command = args.first().map(String::as_str).unwrap_or_default();
+    if config.update_check
+        && !update_check_disabled
+        && !matches!(command, "serve-stdio" | "serve-http" | "daemon" | "service" | "update" | "version")
+        && output_format() == OutputFormat::Text
+        && io::stderr().is_terminal()
+    {
+        if let Some(notice) = update::startup_notice(config.update_url()) {
+            eprintln!("{notice}");
+        }
+    }
     let mut server = JsonRpcServer::new(service);
     let mut client = JsonRpcClient::new(&mut server);
 
-    if args.is_empty() {
-        run_interactive_mode(&mut client);
+    // `cli --addr <host:port>` attaches the interactive UI to a running backend.
+    if args.is_empty() || args[0] == "--addr" {
+        if !positional_without_flags(&args).is_empty() {
+            usage_exit(format_args!("error: unexpected arguments after --addr; see `cli --help`"));
+        }
+        run_interactive_mode(&mut client, string_flag(&args, "--addr"), no_tui);
         return;
     }
 
     match args[0].as_str() {
+        "tools" if matches!(args.get(1).map(String::as_str), Some("enable" | "disable")) => {
+            handle_tools_toggle_command(&mut client, &args[1..]);
+        }
         "tools" => {
-            let json_output = args.iter().any(|a| a == "--json");
+            let json_output = json_output_flag(&args);
             let raw_output = args.iter().any(|a| a == "--raw");
             let remote_addr = parse_addr_flag(&args[1..]);
             let tools = if let Some(addr) = &remote_addr {
                 let wire = match call_http_jsonrpc(addr, "tools.list", json!({})) {
                     Ok(w) => w,
                     Err(err) => {
-                        eprintln!("tools error: {err}");
-                        std::process::exit(1);
+                        exit_with(EXIT_FAILURE, format_args!("tools error: {err}"));
                     }
                 };
                 if raw_output {
@@ -100,8 +205,7 @@ fn main() {
                 match wire_result::<Vec<Tool>>(wire) {
                     Ok(v) => v,
                     Err(err) => {
-                        eprintln!("tools error: {err}");
-                        std::process::exit(1);
+                        exit_with(EXIT_FAILURE, format_args!("tools error: {err}"));
                     }
                 }
             } else {
@@ -115,10 +219,7 @@ fn main() {
                 tools
             };
             if json_output {
-                println!(
-                    "{}",
-                    serde_json::to_string_pretty(&tools).unwrap_or_else(|_| "[]".to_string())
-                );
+                print_json(&tools);
             } else {
                 for tool in tools {
                     println!("{} - {}", tool.name, tool.description);
@@ -127,39 +228,118 @@ fn main() {
         }
         "chat" => {
             if args.len() < 2 {
-                eprintln!("error: missing chat message");
-                print_help();
-                std::process::exit(2);
+                usage_exit(format_args!("error: missing chat message"));
             }
             if contains_tool_syntax(&args[1]) {
-                eprintln!("error: explicit tool syntax is disabled; use natural language prompts");
-                std::process::exit(2);
+                exit_with(EXIT_USAGE, format_args!("error: explicit tool syntax is disabled; use natural language prompts"));
             }
-            let mut provider_name = "openai-stub".to_string();
-            let mut require_confirmation = false;
-            let mut json_output = false;
+            let mut provider_config = ProviderConfig {
+                provider_name: config.provider.clone().unwrap_or_else(|| "openai-stub".to_string()),
+                ..Default::default()
+            };
+            let mut require_confirmation = config.mode == DefaultMode::RequireConfirmation;
+            let mut json_output = output_format() != OutputFormat::Text;
+            let mut interactive = true;
+            let mut read_stdin = !io::stdin().is_terminal();
+            let mut attachment_paths = Vec::new();
+            let mut image_paths = Vec::new();
             let mut remote_addr = None;
+            let mut force_local = false;
             let mut session_id = None;
+            let mut cache_ttl_secs = None;
+            let mut dry_run = false;
             let mut i = 2;
             while i < args.len() {
                 match args[i].as_str() {
                     "--provider" => {
                         if let Some(next) = args.get(i + 1) {
-                            provider_name = next.clone();
+                            provider_config.provider_name = next.clone();
+                            i += 2;
+                            continue;
+                        }
+                    }
+                    "--model" => {
+                        if let Some(next) = args.get(i + 1) {
+                            provider_config.model = Some(next.clone());
+                            i += 2;
+                            continue;
+                        }
+                    }
+                    "--temperature" | "--top-p" | "--max-tokens" => {
+                        if let Some(next) = args.get(i + 1) {
+                            if let Err(err) = apply_generation_flag(&mut provider_config, &args[i], next) {
+                                exit_with(EXIT_USAGE, format_args!("error: {err}"));
+                            }
+                            i += 2;
+                            continue;
+                        }
+                    }
+                    "--stop" => {
+                        if let Some(next) = args.get(i + 1) {
+                            provider_config.stop.push(next.clone());
+                            i += 2;
+                            continue;
+                        }
+                    }
+                    "--cache-ttl" => {
+                        if let Some(next) = args.get(i + 1) {
+                            let ttl = next
+                                .parse::<u64>()
+                                .unwrap_or_else(|_| exit_with(EXIT_USAGE, format_args!("error: invalid --cache-ttl value: {next}")));
+                            cache_ttl_secs = Some(ttl);
                             i += 2;
                             continue;
                         }
                     }
+                    "--dry-run" => {
+                        dry_run = true;
+                        i += 1;
+                        continue;
+                    }
                     "--require-confirmation" => {
                         require_confirmation = true;
                         i += 1;
                         continue;
                     }
+                    "--best-effort" => {
+                        require_confirmation = false;
+                        i += 1;
+                        continue;
+                    }
+                    "--local" => {
+                        force_local = true;
+                        i += 1;
+                        continue;
+                    }
                     "--json" => {
                         json_output = true;
                         i += 1;
                         continue;
                     }
+                    "--no-interactive" => {
+                        interactive = false;
+                        i += 1;
+                        continue;
+                    }
+                    "--no-stdin" => {
+                        read_stdin = false;
+                        i += 1;
+                        continue;
+                    }
+                    "--file" => {
+                        if let Some(next) = args.get(i + 1) {
+                            attachment_paths.push(next.clone());
+                            i += 2;
+                            continue;
+                        }
+                    }
+                    "--image" => {
+                        if let Some(next) = args.get(i + 1) {
+                            image_paths.push(next.clone());
+                            i += 2;
+                            continue;
+                        }
+                    }
                     "--addr" => {
                         if let Some(next) = args.get(i + 1) {
                             remote_addr = Some(next.clone());
@@ -179,19 +359,51 @@ fn main() {
                 i += 1;
             }
 
+            let remote_addr = if force_local {
+                None
+            } else {
+                remote_addr.or_else(default_remote_addr)
+            };
+
+            let mut messages = Vec::new();
+            if read_stdin {
+                let mut piped = Vec::new();
+                if let Err(err) = io::stdin().read_to_end(&mut piped) {
+                    exit_with(EXIT_USAGE, format_args!("error: failed to read stdin: {err}"));
+                }
+                if !piped.iter().all(u8::is_ascii_whitespace) {
+                    messages.push(attachment_message("stdin", &piped));
+                }
+            }
+            for path in &attachment_paths {
+                match std::fs::read(path) {
+                    Ok(bytes) => messages.push(attachment_message(&format!("file {path}"), &bytes)),
+                    Err(err) => {
+                        exit_with(EXIT_USAGE, format_args!("error: failed to read --file {path}: {err}"));
+                    }
+                }
+            }
+            let mut prompt = ipc::sample_messages(&args[1]);
+            for path in &image_paths {
+                // Inlined here so a backend on another host never needs the file.
+                match providers::multimodal::image_part_from_path(path) {
+                    Ok(part) => prompt[0].parts.push(part),
+                    Err(err) => exit_with(EXIT_USAGE, format_args!("error: --image: {err}")),
+                }
+            }
+            messages.extend(prompt);
+
             let chat_request = ChatRequest {
                 session_id,
-                messages: ipc::sample_messages(&args[1]),
-                provider_config: ProviderConfig {
-                    provider_name,
-                    model: None,
-                    config_json: None,
-                },
-                mode: if require_confirmation {
+                messages,
+                provider_config,
+                mode: Some(if require_confirmation {
                     ChatMode::RequireConfirmation
                 } else {
                     ChatMode::BestEffort
-                },
+                }),
+                cache_ttl_secs,
+                dry_run,
             };
 
             let response = if let Some(addr) = remote_addr.as_deref() {
@@ -200,8 +412,7 @@ fn main() {
                 {
                     Ok(resp) => resp,
                     Err(err) => {
-                        eprintln!("chat error: {err}");
-                        std::process::exit(1);
+                        exit_with(EXIT_FAILURE, format_args!("chat error: {err}"));
                     }
                 }
             } else {
@@ -209,6 +420,12 @@ fn main() {
             };
 
             print_chat_response(&response, json_output);
+            exit_if_awaiting_consent(&response);
+            let prompt_for_consent =
+                interactive && !json_output && io::stdin().is_terminal() && io::stdout().is_terminal();
+            if prompt_for_consent {
+                resolve_consents_interactively(&mut client, remote_addr.as_deref(), response);
+            }
         }
         "consent" => {
             handle_consent_command(&mut client, &args[1..]);
@@ -231,20 +448,45 @@ fn main() {
         "audit" => {
             handle_audit_command(&mut client, &args[1..]);
         }
+        "state" => {
+            handle_state_command(&mut client, &args[1..]);
+        }
+        "policy" => {
+            handle_policy_command(&mut client, &args[1..]);
+        }
+        "tasks" => {
+            handle_tasks_command(&mut client, &args[1..]);
+        }
+        "workflow" => {
+            handle_workflow_command(&mut client, &args[1..]);
+        }
         "doctor" => {
             handle_doctor_command(&mut client, &args[1..]);
         }
+        "version" | "--version" => {
+            handle_version_command(&mut client, &args[1..]);
+        }
+        "update" => {
+            handle_update_command(&args[1..], update_check_disabled);
+        }
+        "daemon" => {
+            handle_daemon_command(&args[1..]);
+        }
+        "logs" => {
+            handle_logs_command(&args[1..]);
+        }
+        "service" => {
+            handle_service_command(&args[1..]);
+        }
         "tui" => {
             eprintln!("note: `cli` (no args) is the default interactive entry point");
-            run_interactive_mode(&mut client);
+            run_interactive_mode(&mut client, string_flag(&args[1..], "--addr"), no_tui);
         }
         "approve" => {
             if args.len() < 2 {
-                eprintln!("error: missing consent token");
-                print_help();
-                std::process::exit(2);
+                usage_exit(format_args!("error: missing consent token"));
             }
-            let json_output = args.iter().any(|a| a == "--json");
+            let json_output = json_output_flag(&args);
             let addr = parse_addr_flag(&args[1..]).unwrap_or_else(|| "127.0.0.1:7777".to_string());
             let params = serde_json::to_value(ChatApproveRequest {
                 consent_token: args[1].clone(),
@@ -255,19 +497,17 @@ fn main() {
             {
                 Ok(resp) => resp,
                 Err(err) => {
-                    eprintln!("approve error: {err}");
-                    std::process::exit(1);
+                    exit_with(EXIT_FAILURE, format_args!("approve error: {err}"));
                 }
             };
             print_chat_response(&response, json_output);
+            exit_if_awaiting_consent(&response);
         }
         "deny" => {
             if args.len() < 2 {
-                eprintln!("error: missing consent token");
-                print_help();
-                std::process::exit(2);
+                usage_exit(format_args!("error: missing consent token"));
             }
-            let json_output = args.iter().any(|a| a == "--json");
+            let json_output = json_output_flag(&args);
             let addr = parse_addr_flag(&args[1..]).unwrap_or_else(|| "127.0.0.1:7777".to_string());
             let params = serde_json::to_value(ChatDenyRequest {
                 consent_token: args[1].clone(),
@@ -278,17 +518,36 @@ fn main() {
             {
                 Ok(resp) => resp,
                 Err(err) => {
-                    eprintln!("deny error: {err}");
-                    std::process::exit(1);
+                    exit_with(EXIT_FAILURE, format_args!("deny error: {err}"));
                 }
             };
             print_chat_response(&response, json_output);
+            exit_if_awaiting_consent(&response);
+        }
+        "shutdown" => {
+            let addr = parse_addr_flag(&args[1..])
+                .unwrap_or_else(|| usage_exit(format_args!("error: no running server found; pass --addr <host:port>")));
+            let token_path = AgentService::default_storage_dir().join(agent::SHUTDOWN_TOKEN_FILE);
+            let token = std::fs::read_to_string(&token_path).unwrap_or_else(|err| {
+                exit_with(EXIT_FAILURE, format_args!("failed to read {}: {err}", token_path.display()))
+            });
+            let result = backend_call_value(&mut client, Some(&addr), "system.shutdown", json!({ "token": token.trim() }))
+                .unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("shutdown error: {err}")));
+            if json_output_flag(&args) {
+                print_json(&result);
+            } else {
+                println!("server at {addr} is shutting down");
+            }
+        }
+        "rpc" if has_flag(&args, "--describe") => {
+            let addr = parse_addr_flag(&args[1..]);
+            let result = backend_call_value(&mut client, addr.as_deref(), "system.describe", json!({}))
+                .unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("rpc error: {err}")));
+            print_json(&result);
         }
         "rpc" => {
             if args.len() < 3 {
-                eprintln!("error: usage: cli rpc <method> <params-json>");
-                print_help();
-                std::process::exit(2);
+                usage_exit(format_args!("error: usage: cli rpc <method> <params-json>"));
             }
             let remote_addr = parse_addr_flag(&args[3..]);
             let wire = if let Some(addr) = remote_addr.as_deref() {
@@ -296,8 +555,7 @@ fn main() {
                 match call_http_jsonrpc(addr, &args[1], params) {
                     Ok(w) => w,
                     Err(err) => {
-                        eprintln!("rpc error: {err}");
-                        std::process::exit(1);
+                        exit_with(EXIT_FAILURE, format_args!("rpc error: {err}"));
                     }
                 }
             } else {
@@ -308,20 +566,15 @@ fn main() {
                 ));
                 to_wire_response(response)
             };
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&wire)
-                    .unwrap_or_else(|_| "{\"error\":\"serialize\"}".to_string())
-            );
+            print_json(&wire);
         }
         "serve-stdio" => {
             if let Err(err) = serve_stdio_jsonrpc() {
-                eprintln!("stdio server error: {err}");
-                std::process::exit(1);
+                exit_with(EXIT_FAILURE, format_args!("stdio server error: {err}"));
             }
         }
         "serve-http" => {
-            let mut addr = "127.0.0.1:7777".to_string();
+            let mut addr = config.addr.clone().unwrap_or_else(|| "127.0.0.1:7777".to_string());
             let mut i = 1;
             while i < args.len() {
                 if args[i] == "--addr" {
@@ -334,16 +587,105 @@ fn main() {
                 i += 1;
             }
 
+            if has_flag(&args, "--daemon") {
+                let info = daemon::start(&addr)
+                    .unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("daemon error: {err}")));
+                if json_output_flag(&args) {
+                    print_json(&json!({ "pid": info.pid, "addr": info.addr }));
+                } else {
+                    println!("daemon started on {} (pid {})", endpoint_label(&info.addr), info.pid);
+                }
+                return;
+            }
+            if let Some(pos) = args.iter().position(|a| a == "--windows-service") {
+                let name = args
+                    .get(pos + 1)
+                    .filter(|name| !name.starts_with("--"))
+                    .cloned()
+                    .unwrap_or_else(|| service::DEFAULT_SERVICE_NAME.to_string());
+                if let Err(err) = service::run_as_windows_service(&name, move || serve_http_jsonrpc(&addr)) {
+                    exit_with(EXIT_FAILURE, format_args!("service error: {err}"));
+                }
+                return;
+            }
+            if has_flag(&args, "--systemd-socket") {
+                let listener = service::systemd_listener()
+                    .unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("http server error: {err}")));
+                if let Err(err) = serve_http_listener(listener) {
+                    exit_with(EXIT_FAILURE, format_args!("http server error: {err}"));
+                }
+                return;
+            }
             if let Err(err) = serve_http_jsonrpc(&addr) {
-                eprintln!("http server error: {err}");
-                std::process::exit(1);
+                exit_with(EXIT_FAILURE, format_args!("http server error: {err}"));
             }
         }
         _ => {
-            eprintln!("error: unknown command '{}'.", args[0]);
-            print_help();
-            std::process::exit(2);
+            usage_exit(format_args!("error: unknown command '{}'.", args[0]));
+        }
+    }
+}
+
+/// Keeps asking about the outstanding consent until it is approved, denied or skipped; an approval
+/// can surface a follow-up consent, which is offered in turn.
+fn resolve_consents_interactively(
+    client: &mut JsonRpcClient<AgentService>,
+    addr: Option<&str>,
+    mut response: ChatResponse,
+) {
+    let stdin = io::stdin();
+    while let Some(token) = response.consent_token.clone() {
+        print!("consent?> [a]pprove / [d]eny / [s]kip: ");
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+            return;
         }
+        let method = match line.trim().to_ascii_lowercase().as_str() {
+            "a" | "approve" => "chat.approve",
+            "d" | "deny" => "chat.deny",
+            "s" | "skip" | "" => {
+                println!("system> left pending; resolve later with `cli consent approve|deny {token}`");
+                return;
+            }
+            other => {
+                println!("system> unrecognized choice '{other}'");
+                continue;
+            }
+        };
+        let params = json!({ "consent_token": token });
+        response = match backend_call_value(client, addr, method, params)
+            .and_then(|value| serde_json::from_value::<ChatResponse>(value).map_err(|err| err.to_string()))
+        {
+            Ok(next) => next,
+            Err(err) => {
+                exit_with(EXIT_FAILURE, format_args!("consent error: {err}"));
+            }
+        };
+        print_chat_response(&response, false);
+    }
+}
+
+const ATTACHMENT_MAX_BYTES: usize = 64 * 1024;
+
+/// Wraps piped or `--file` content as a context message ahead of the prompt, capped at
+/// `ATTACHMENT_MAX_BYTES` so a stray log file cannot blow up the request.
+fn attachment_message(label: &str, bytes: &[u8]) -> ipc::ChatMessage {
+    let text = String::from_utf8_lossy(bytes);
+    let mut cut = text.len().min(ATTACHMENT_MAX_BYTES);
+    while !text.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    let mut content = format!("Attached {label}:\n```\n{}\n```", text[..cut].trim_end());
+    if cut < text.len() {
+        content.push_str(&format!("\n[truncated: first {cut} of {} bytes]", text.len()));
+    }
+    ipc::ChatMessage {
+        role: "user".to_string(),
+        content,
+        parts: Vec::new(),
+        message_id: None,
+        created_at_unix_seconds: None,
     }
 }
 
@@ -351,10 +693,25 @@ fn contains_tool_syntax(input: &str) -> bool {
     input.to_ascii_lowercase().contains("tool:")
 }
 
-fn run_interactive_mode(client: &mut JsonRpcClient<AgentService>) {
+fn run_interactive_mode(client: &mut JsonRpcClient<AgentService>, remote: Option<String>, no_tui: bool) {
     let has_terminal = io::stdin().is_terminal() && io::stdout().is_terminal();
-    if has_terminal {
-        if let Err(err) = tui::run(client) {
+    if let Some(addr) = remote {
+        // Only the TUI routes its calls through a remote backend; the shell stays in-process.
+        if no_tui || plain_output() || !has_terminal {
+            usage_exit(format_args!("error: --addr attaches the terminal UI, which needs a tty and no --no-tui/--plain"));
+        }
+        if !daemon::is_listening(&addr) {
+            exit_with(EXIT_FAILURE, format_args!("no backend listening at {addr}"));
+        }
+        if let Err(err) = tui::run(client, Some(addr)) {
+            exit_with(EXIT_FAILURE, format_args!("tui error: {err}"));
+        }
+        return;
+    }
+    if no_tui || plain_output() {
+        // The line-based shell was asked for; start it without a fallback notice.
+    } else if has_terminal {
+        if let Err(err) = tui::run(client, None) {
             eprintln!("tui unavailable ({err}); falling back to interactive shell");
         } else {
             return;
@@ -364,23 +721,31 @@ fn run_interactive_mode(client: &mut JsonRpcClient<AgentService>) {
     }
 
     if let Err(err) = run_repl(client) {
-        eprintln!("repl error: {err}");
-        std::process::exit(1);
+        exit_with(EXIT_FAILURE, format_args!("repl error: {err}"));
     }
 }
 
 fn run_repl(client: &mut JsonRpcClient<AgentService>) -> io::Result<()> {
-    let mut provider_name = "openai-stub".to_string();
+    // Provider plus the `/model`, `/temperature`, ... overrides sent with every request.
+    let mut provider_config = ProviderConfig {
+        provider_name: "openai-stub".to_string(),
+        ..Default::default()
+    };
     let mut require_confirmation = true;
     let mut session_id: Option<String> = None;
+    let mut cache_ttl_secs: Option<u64> = None;
     let mut history: Vec<ExecutionFeedItem> = Vec::new();
 
-    print_repl_banner(&provider_name, require_confirmation, session_id.as_deref());
+    print_repl_banner(&provider_config.provider_name, require_confirmation, session_id.as_deref());
 
     let stdin = io::stdin();
     let mut line = String::new();
     loop {
-        print!("\x1b[38;5;45m->\x1b[0m ");
+        if plain_output() {
+            print!("you> ");
+        } else {
+            print!("\x1b[38;5;45m->\x1b[0m ");
+        }
         io::stdout().flush()?;
 
         line.clear();
@@ -402,13 +767,17 @@ fn run_repl(client: &mut JsonRpcClient<AgentService>) -> io::Result<()> {
         if let Some(rest) = input.strip_prefix("/provider ") {
             let next = rest.trim();
             if next.is_empty() {
-                println!("system> provider unchanged: {}", provider_name);
+                println!("system> provider unchanged: {}", provider_config.provider_name);
             } else {
-                provider_name = next.to_string();
-                println!("system> provider set to {}", provider_name);
+                provider_config.provider_name = next.to_string();
+                println!("system> provider set to {}", provider_config.provider_name);
             }
             continue;
         }
+        if let Some(reply) = apply_generation_command(&mut provider_config, input) {
+            println!("system> {reply}");
+            continue;
+        }
         if let Some(rest) = input.strip_prefix("/mode ") {
             match rest.trim() {
                 "confirm" => {
@@ -423,6 +792,22 @@ fn run_repl(client: &mut JsonRpcClient<AgentService>) -> io::Result<()> {
             }
             continue;
         }
+        if let Some(rest) = input.strip_prefix("/cache ") {
+            match rest.trim() {
+                "off" => {
+                    cache_ttl_secs = None;
+                    println!("system> response cache off");
+                }
+                secs => match secs.parse::<u64>() {
+                    Ok(ttl) => {
+                        cache_ttl_secs = Some(ttl);
+                        println!("system> reusing identical responses from the last {ttl}s");
+                    }
+                    Err(_) => println!("system> usage: /cache <secs>|off"),
+                },
+            }
+            continue;
+        }
         if input.eq_ignore_ascii_case("/session clear") {
             session_id = None;
             println!("system> session cleared");
@@ -479,24 +864,22 @@ fn run_repl(client: &mut JsonRpcClient<AgentService>) -> io::Result<()> {
             let chat_request = ChatRequest {
                 session_id: session_id.clone(),
                 messages: ipc::sample_messages(&prompt),
-                provider_config: ProviderConfig {
-                    provider_name: provider_name.clone(),
-                    model: None,
-                    config_json: None,
-                },
-                mode: if require_confirmation {
+                provider_config: provider_config.clone(),
+                mode: Some(if require_confirmation {
                     ChatMode::RequireConfirmation
                 } else {
                     ChatMode::BestEffort
-                },
+                }),
+                cache_ttl_secs,
+                dry_run: false,
             };
-            let response = client.chat_request(chat_request);
+            let (response, streamed) = stream_repl_chat(client, chat_request);
             if response.session_id.is_some() {
                 session_id = response.session_id.clone();
             }
             let feed_item = response.to_execution_feed_item(Some(prompt));
             history.push(feed_item.clone());
-            print_feed_item(&feed_item, response.consent_token.as_deref());
+            print_feed_item(&feed_item, response.consent_token.as_deref(), Some(&streamed));
             continue;
         }
         if input.eq_ignore_ascii_case("/session new") {
@@ -513,8 +896,8 @@ fn run_repl(client: &mut JsonRpcClient<AgentService>) -> io::Result<()> {
             continue;
         }
         if input.eq_ignore_ascii_case("/consent list") {
-            let records: Value = local_rpc(client, "consent.list", json!({})).map_err(io::Error::other)?;
-            print_repl_consents(&records);
+            let page: Value = local_rpc(client, "consent.list", json!({})).map_err(io::Error::other)?;
+            print_repl_consents(&page["items"]);
             continue;
         }
         if let Some(rest) = input.strip_prefix("/consent approve ") {
@@ -527,7 +910,7 @@ fn run_repl(client: &mut JsonRpcClient<AgentService>) -> io::Result<()> {
                 .map_err(io::Error::other)?;
             let feed_item = response.to_execution_feed_item(None);
             history.push(feed_item.clone());
-            print_feed_item(&feed_item, response.consent_token.as_deref());
+            print_feed_item(&feed_item, response.consent_token.as_deref(), None);
             continue;
         }
         if let Some(rest) = input.strip_prefix("/consent deny ") {
@@ -540,7 +923,51 @@ fn run_repl(client: &mut JsonRpcClient<AgentService>) -> io::Result<()> {
                 .map_err(io::Error::other)?;
             let feed_item = response.to_execution_feed_item(None);
             history.push(feed_item.clone());
-            print_feed_item(&feed_item, response.consent_token.as_deref());
+            print_feed_item(&feed_item, response.consent_token.as_deref(), None);
+            continue;
+        }
+        if input.eq_ignore_ascii_case("/instructions") {
+            let record: Value =
+                local_rpc(client, "agent.instructions.get", json!({})).map_err(io::Error::other)?;
+            print_repl_instructions(&record);
+            continue;
+        }
+        if let Some(rest) = input.strip_prefix("/instructions ") {
+            let rest = rest.trim();
+            let params = if rest.eq_ignore_ascii_case("clear") {
+                json!({ "instructions": "" })
+            } else if let Some(text) = rest.strip_prefix("set ") {
+                json!({ "instructions": text.trim() })
+            } else if let Some(text) = rest.strip_prefix("global ") {
+                json!({ "instructions": text.trim(), "global": true })
+            } else {
+                println!("system> usage: /instructions [set <text>|global <text>|clear]");
+                continue;
+            };
+            let record: Value =
+                local_rpc(client, "agent.instructions.set", params).map_err(io::Error::other)?;
+            print_repl_instructions(&record);
+            continue;
+        }
+        if let Some(rest) = input.strip_prefix("/recent") {
+            let rest = rest.trim();
+            let recent: ProjectRecentResponse =
+                local_rpc(client, "project.recent", json!({})).map_err(io::Error::other)?;
+            if rest.is_empty() {
+                print_repl_recent_projects(&recent);
+                continue;
+            }
+            let Some(project) = rest
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| recent.projects.get(n.wrapping_sub(1)))
+            else {
+                println!("system> usage: /recent [N]");
+                continue;
+            };
+            let opened: Value = local_rpc(client, "project.open", json!({ "path": project.path }))
+                .map_err(io::Error::other)?;
+            println!("system> opened {}", opened.get("path").and_then(Value::as_str).unwrap_or(&project.path));
             continue;
         }
         if input.eq_ignore_ascii_case("/tools") {
@@ -564,29 +991,63 @@ fn run_repl(client: &mut JsonRpcClient<AgentService>) -> io::Result<()> {
         let chat_request = ChatRequest {
             session_id: session_id.clone(),
             messages: ipc::sample_messages(input),
-            provider_config: ProviderConfig {
-                provider_name: provider_name.clone(),
-                model: None,
-                config_json: None,
-            },
-            mode: if require_confirmation {
+            provider_config: provider_config.clone(),
+            mode: Some(if require_confirmation {
                 ChatMode::RequireConfirmation
             } else {
                 ChatMode::BestEffort
-            },
+            }),
+            cache_ttl_secs,
+            dry_run: false,
         };
-        let response = client.chat_request(chat_request);
+        let (response, streamed) = stream_repl_chat(client, chat_request);
         if response.session_id.is_some() {
             session_id = response.session_id.clone();
         }
         let feed_item = response.to_execution_feed_item(Some(input.to_string()));
         history.push(feed_item.clone());
-        print_feed_item(&feed_item, response.consent_token.as_deref());
+        print_feed_item(&feed_item, response.consent_token.as_deref(), Some(&streamed));
     }
 
     Ok(())
 }
 
+/// Runs `request` in process, printing assistant text as it arrives and each tool as it starts.
+/// Returns the response and the text shown since the last tool ran. In plain mode only the
+/// tool lines are printed and the answer follows as one line.
+fn stream_repl_chat(client: &mut JsonRpcClient<AgentService>, request: ChatRequest) -> (ChatResponse, String) {
+    let mut shown = String::new();
+    let mut mid_line = false;
+    let plain = plain_output();
+    let response = client.service_mut().chat_request_streaming(request, &mut |event| {
+        match event {
+            StreamEvent::Token(_) if plain => {}
+            StreamEvent::Token(token) => {
+                if !mid_line {
+                    print!("assistant> ");
+                    mid_line = true;
+                }
+                print!("{token}");
+                shown.push_str(&token);
+            }
+            StreamEvent::ToolStarted(name) => {
+                if mid_line {
+                    println!();
+                    mid_line = false;
+                }
+                shown.clear();
+                println!("tools> running {name}...");
+            }
+            StreamEvent::RoundFinished(_) => {}
+        }
+        let _ = io::stdout().flush();
+    });
+    if mid_line {
+        println!();
+    }
+    (response, shown)
+}
+
 fn print_repl_banner(provider_name: &str, require_confirmation: bool, session_id: Option<&str>) {
     println!("cmnd-n-ctrl shell");
     println!("natural language only");
@@ -605,6 +1066,11 @@ fn print_repl_help() {
     println!("  /quit");
     println!("  /provider <name>");
     println!("  /mode confirm|best");
+    println!("  /model <id>|default");
+    println!("  /temperature <0-2>|default, /top-p <0-1>|default, /max-tokens <n>|default");
+    println!("  /stop <seq>|clear   # repeat to add more stop sequences");
+    println!("  /generation         # show the overrides sent with each request");
+    println!("  /cache <secs>|off   # reuse identical responses (also for /replay)");
     println!("  /session new|clear|show");
     println!("  /history");
     println!("  /history find <text>");
@@ -612,9 +1078,31 @@ fn print_repl_help() {
     println!("  /consent list");
     println!("  /consent approve <id>");
     println!("  /consent deny <id>");
+    println!("  /instructions [set <text>|global <text>|clear]");
+    println!("  /recent [N]");
     println!("  /tools");
 }
 
+fn print_repl_recent_projects(recent: &ProjectRecentResponse) {
+    if recent.projects.is_empty() {
+        println!("system> no recent projects");
+        return;
+    }
+    println!("system> recent projects (/recent N to open)");
+    for (idx, project) in recent.projects.iter().enumerate() {
+        let pin = if project.pinned { " [pinned]" } else { "" };
+        println!("  {}. {}{}", idx + 1, project.path, pin);
+    }
+}
+
+fn print_repl_instructions(record: &Value) {
+    let source = record.get("source").and_then(|v| v.as_str()).unwrap_or("none");
+    match record.get("instructions").and_then(|v| v.as_str()) {
+        Some(text) => println!("system> instructions ({source}): {text}"),
+        None => println!("system> no instructions configured"),
+    }
+}
+
 fn print_repl_consents(records: &Value) {
     let Some(items) = records.as_array() else {
         println!(
@@ -653,62 +1141,99 @@ where
     serde_json::from_str::<T>(&result).map_err(|err| format!("invalid result payload: {err}"))
 }
 
-fn serve_stdio_jsonrpc() -> io::Result<()> {
-    let service = AgentService::new_for_platform("ipc-stdio");
-    let mut server = JsonRpcServer::new(service);
-    let stdin = io::stdin();
-    let stdout = io::stdout();
-    let mut reader = BufReader::new(stdin.lock());
-    let mut writer = stdout.lock();
+/// Logs each served request's method, outcome and duration at debug level.
+#[derive(Default)]
+struct RequestLog {
+    started: Option<std::time::Instant>,
+}
 
-    while let Some(frame) = mcp::read_stdio_frame_from(&mut reader)? {
-        let response = match parse_wire_request(&frame) {
-            Ok(request) => {
-                let raw_request = Request::new(
-                    json_value_to_id(request.id.unwrap_or(Value::Null)),
-                    request.method,
-                    request.params.to_string(),
-                );
-                to_wire_response(server.handle(raw_request))
-            }
-            Err(err) => WireResponse {
-                jsonrpc: "2.0".to_string(),
-                id: Value::Null,
-                result: None,
-                error: Some(WireError {
-                    code: -32700,
-                    message: format!("parse error: {err}"),
-                }),
-            },
-        };
+impl Middleware for RequestLog {
+    fn before(&mut self, _request: &Request) -> Option<ipc::jsonrpc::Response> {
+        self.started = Some(std::time::Instant::now());
+        None
+    }
+
+    fn after(&mut self, request: &Request, response: &mut ipc::jsonrpc::Response) {
+        let elapsed_ms = self.started.take().map(|t| t.elapsed().as_millis()).unwrap_or(0);
+        let error_code = response.error.as_ref().map(|e| e.code);
+        tracing::debug!(method = %request.method, ?error_code, elapsed_ms, "rpc handled");
+    }
+}
 
-        let payload = serde_json::to_string(&response)
-            .map_err(|err| io::Error::other(format!("serialize response: {err}")))?;
-        mcp::write_stdio_frame_to(&mut writer, &payload)?;
+static SHUTDOWN_SIGNALLED: AtomicBool = AtomicBool::new(false);
+
+/// SIGINT/SIGTERM (Ctrl+C / Ctrl+Break on Windows) only raise a flag; the serve loops notice it
+/// between requests, so the request in flight always completes.
+fn install_shutdown_handler() {
+    if let Err(err) = ctrlc::set_handler(|| SHUTDOWN_SIGNALLED.store(true, Ordering::SeqCst)) {
+        tracing::warn!(error = %err, "failed to install signal handler");
     }
+}
 
-    Ok(())
+/// Same as a signal; used by the Windows service control handler.
+#[cfg(windows)]
+fn request_shutdown() {
+    SHUTDOWN_SIGNALLED.store(true, Ordering::SeqCst);
 }
 
-fn parse_wire_request(payload: &str) -> Result<WireRequest, serde_json::Error> {
-    let req: WireRequest = serde_json::from_str(payload)?;
-    Ok(req)
+fn stop_requested(server: &JsonRpcServer<AgentService>) -> bool {
+    SHUTDOWN_SIGNALLED.load(Ordering::SeqCst) || server.service().shutdown_requested()
 }
 
-fn json_value_to_id(value: Value) -> Id {
-    match value {
-        Value::Number(n) => n.as_u64().map(Id::Number).unwrap_or(Id::Null),
-        Value::String(s) => Id::String(s),
-        _ => Id::Null,
+/// Common server setup: request logging, signal handling and the `system.shutdown` token.
+fn new_served_backend(platform: &'static str) -> JsonRpcServer<AgentService> {
+    install_shutdown_handler();
+    let mut service = AgentService::new_for_platform(platform);
+    if let Err(err) = service.enable_shutdown_rpc() {
+        tracing::warn!(%err, "system.shutdown disabled");
     }
+    JsonRpcServer::new(service).with_middleware(RequestLog::default())
 }
 
-fn id_to_json_value(id: Id) -> Value {
-    match id {
-        Id::Number(n) => Value::from(n),
-        Id::String(s) => Value::String(s),
-        Id::Null => Value::Null,
+fn serve_stdio_jsonrpc() -> io::Result<()> {
+    let mut server = new_served_backend("ipc-stdio");
+    let stdout = io::stdout();
+    let mut writer = stdout.lock();
+
+    // Frames are read on a separate thread so a signal is noticed while stdin is idle.
+    let (tx, rx) = mpsc::channel::<io::Result<String>>();
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(io::stdin().lock());
+        loop {
+            let frame = match mcp::read_stdio_frame_from(&mut reader) {
+                Ok(Some(frame)) => Ok(frame),
+                Ok(None) => break,
+                Err(err) => Err(err),
+            };
+            let failed = frame.is_err();
+            if tx.send(frame).is_err() || failed {
+                break;
+            }
+        }
+    });
+
+    let mut result = Ok(());
+    while !stop_requested(&server) {
+        match rx.recv_timeout(LONG_POLL_TICK) {
+            Ok(Ok(frame)) => {
+                if let Some(payload) = server.handle_json(&frame) {
+                    if let Err(err) = mcp::write_stdio_frame_to(&mut writer, &payload) {
+                        result = Err(err);
+                        break;
+                    }
+                }
+            }
+            Ok(Err(err)) => {
+                result = Err(err);
+                break;
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
     }
+
+    server.service_mut().shutdown();
+    result
 }
 
 fn to_wire_response(response: ipc::jsonrpc::Response) -> WireResponse {
@@ -725,13 +1250,17 @@ fn to_wire_response(response: ipc::jsonrpc::Response) -> WireResponse {
 
     WireResponse {
         jsonrpc: response.jsonrpc,
-        id: id_to_json_value(response.id),
+        id: response.id.to_json(),
         result,
         error,
     }
 }
 
+/// `--addr`, else the configured or daemon default; `--local` forces the in-process service.
 fn parse_addr_flag(args: &[String]) -> Option<String> {
+    if has_flag(args, "--local") {
+        return None;
+    }
     let mut i = 0;
     while i < args.len() {
         if args[i] == "--addr" {
@@ -739,7 +1268,68 @@ fn parse_addr_flag(args: &[String]) -> Option<String> {
         }
         i += 1;
     }
-    None
+    default_remote_addr()
+}
+
+/// Configured `addr`, else a running `serve-http --daemon`.
+fn default_remote_addr() -> Option<String> {
+    cli_config().addr.clone().or_else(daemon::discover_addr)
+}
+
+fn json_output_flag(args: &[String]) -> bool {
+    has_flag(args, "--json") || output_format() != OutputFormat::Text
+}
+
+/// Process exit codes; stable so scripts can branch on them.
+const EXIT_FAILURE: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_UNHEALTHY: i32 = 3;
+/// `--output jsonl` only: the command stopped on a pending consent.
+const EXIT_AWAITING_CONSENT: i32 = 4;
+
+fn exit_with(code: i32, message: fmt::Arguments) -> ! {
+    if output_format() == OutputFormat::Jsonl {
+        emit_event("error", json!({ "message": message.to_string(), "exit_code": code }));
+    } else {
+        eprintln!("{message}");
+    }
+    std::process::exit(code)
+}
+
+fn usage_exit(message: fmt::Arguments) -> ! {
+    if output_format() != OutputFormat::Jsonl {
+        eprintln!("{message}");
+        print_help();
+        std::process::exit(EXIT_USAGE)
+    }
+    exit_with(EXIT_USAGE, message)
+}
+
+/// Prints one `{"event": ..., ...fields}` line.
+fn emit_event(event: &str, fields: Value) {
+    let mut line = serde_json::Map::new();
+    line.insert("event".to_string(), Value::String(event.to_string()));
+    if let Value::Object(fields) = fields {
+        line.extend(fields);
+    }
+    println!("{}", Value::Object(line));
+}
+
+fn print_json<T: Serialize>(value: &T) {
+    if output_format() == OutputFormat::Jsonl {
+        emit_event("result", json!({ "data": value }));
+    } else {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(value).unwrap_or_else(|_| "null".to_string())
+        );
+    }
+}
+
+fn exit_if_awaiting_consent(response: &ChatResponse) {
+    if output_format() == OutputFormat::Jsonl && response.consent_token.is_some() {
+        std::process::exit(EXIT_AWAITING_CONSENT);
+    }
 }
 
 fn has_flag(args: &[String], flag: &str) -> bool {
@@ -757,15 +1347,124 @@ fn string_flag(args: &[String], flag: &str) -> Option<String> {
     None
 }
 
-fn positional_without_flags(args: &[String]) -> Vec<String> {
-    let mut out = Vec::new();
-    let mut i = 0;
-    while i < args.len() {
-        match args[i].as_str() {
-            "--json" | "--raw" | "--strict" => i += 1,
-            "--addr" | "--key" | "--env" | "--provider" | "--session" | "--title" | "--path" | "--command" | "--name" | "--status" | "--limit" => {
-                i += 2
-            }
+/// `/model`, `/temperature`, `/top-p`, `/max-tokens`, `/stop` and `/generation` in the REPL and
+/// TUI input. `None` when `input` is none of them, else the line to show; `default` (or `clear`
+/// for `/stop`) drops an override.
+fn apply_generation_command(config: &mut ProviderConfig, input: &str) -> Option<String> {
+    let (command, value) = input.split_once(' ').map_or((input, ""), |(c, v)| (c, v.trim()));
+    let reply = match (command, value) {
+        ("/generation", _) => format!("generation: {}", generation_label(config)),
+        ("/model" | "/temperature" | "/top-p" | "/max-tokens" | "/stop", "") => {
+            let reset = if command == "/stop" { "clear" } else { "default" };
+            let arg = match command {
+                "/model" => "<id>",
+                "/max-tokens" => "<n>",
+                "/stop" => "<seq>",
+                _ => "<f>",
+            };
+            format!("usage: {command} {arg}|{reset}")
+        }
+        ("/model", "default") => {
+            config.model = None;
+            "model: provider default".to_string()
+        }
+        ("/model", model) => {
+            config.model = Some(model.to_string());
+            format!("model set to {model}")
+        }
+        ("/stop", "clear") => {
+            config.stop.clear();
+            "stop sequences cleared".to_string()
+        }
+        ("/stop", seq) => {
+            config.stop.push(seq.to_string());
+            format!("stop sequences: {}", config.stop.join(", "))
+        }
+        ("/temperature" | "/top-p" | "/max-tokens", "default") => {
+            match command {
+                "/temperature" => config.temperature = None,
+                "/top-p" => config.top_p = None,
+                _ => config.max_tokens = None,
+            }
+            format!("{}: provider default", &command[1..])
+        }
+        ("/temperature" | "/top-p" | "/max-tokens", value) => {
+            match apply_generation_flag(config, &format!("--{}", &command[1..]), value) {
+                Ok(()) => format!("{} set to {value}", &command[1..]),
+                Err(err) => err,
+            }
+        }
+        _ => return None,
+    };
+    Some(reply)
+}
+
+/// The overrides a chat request will carry, e.g. `temperature=0.2 max_tokens=256`.
+fn generation_label(config: &ProviderConfig) -> String {
+    let mut parts = Vec::new();
+    if let Some(model) = &config.model {
+        parts.push(format!("model={model}"));
+    }
+    if let Some(t) = config.temperature {
+        parts.push(format!("temperature={t}"));
+    }
+    if let Some(p) = config.top_p {
+        parts.push(format!("top_p={p}"));
+    }
+    if let Some(n) = config.max_tokens {
+        parts.push(format!("max_tokens={n}"));
+    }
+    if !config.stop.is_empty() {
+        parts.push(format!("stop={:?}", config.stop));
+    }
+    if parts.is_empty() {
+        "provider defaults".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+fn apply_generation_flag(config: &mut ProviderConfig, flag: &str, value: &str) -> Result<(), String> {
+    match flag {
+        "--temperature" => {
+            let v = value
+                .parse::<f64>()
+                .map_err(|_| format!("invalid --temperature value: {value}"))?;
+            if !(0.0..=2.0).contains(&v) {
+                return Err("--temperature must be between 0 and 2".to_string());
+            }
+            config.temperature = Some(v);
+        }
+        "--top-p" => {
+            let v = value
+                .parse::<f64>()
+                .map_err(|_| format!("invalid --top-p value: {value}"))?;
+            if !(0.0..=1.0).contains(&v) {
+                return Err("--top-p must be between 0 and 1".to_string());
+            }
+            config.top_p = Some(v);
+        }
+        "--max-tokens" => {
+            let v = value
+                .parse::<u32>()
+                .map_err(|_| format!("invalid --max-tokens value: {value}"))?;
+            config.max_tokens = Some(v);
+        }
+        other => return Err(format!("unknown generation flag: {other}")),
+    }
+    Ok(())
+}
+
+fn positional_without_flags(args: &[String]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--json" | "--raw" | "--strict" | "--local" | "--daemon" | "--follow" | "--clear" | "--dry-run" | "--all"
+            | "--pinned-first" | "--socket" | "--artifacts" | "--global" => i += 1,
+            "--addr" | "--file" | "--key" | "--env" | "--provider" | "--session" | "--title" | "--path" | "--command" | "--name" | "--status" | "--limit" | "--level" | "--lines" | "--actions" | "--deny-actions" | "--restart" | "--from" | "--allow" | "--tier" | "--since" | "--until" | "--page" | "--mode" | "--profile" | "--deny" | "--scope" | "--tool" | "--model" | "--user" | "--read-write" | "--out" => {
+                i += 2
+            }
             "--args" => i += 2,
             _ => {
                 out.push(args[i].clone());
@@ -795,10 +1494,7 @@ fn backend_call_value(
 
 fn print_value(value: &Value, json_output: bool) {
     if json_output {
-        println!(
-            "{}",
-            serde_json::to_string_pretty(value).unwrap_or_else(|_| "null".to_string())
-        );
+        print_json(value);
     } else if let Some(s) = value.as_str() {
         println!("{s}");
     } else {
@@ -809,63 +1505,225 @@ fn print_value(value: &Value, json_output: bool) {
     }
 }
 
+const DEFAULT_PAGE_SIZE: usize = 20;
+
+/// Fetches page `--page` (1-based) of `audit.list`/`consent.list` by following
+/// `next_after_id` cursors; without `--page` this is the first page. `--page` alone uses
+/// `DEFAULT_PAGE_SIZE` entries per page.
+fn fetch_list_page(
+    client: &mut JsonRpcClient<AgentService>,
+    addr: Option<&str>,
+    method: &str,
+    mut params: Value,
+    args: &[String],
+) -> Result<Value, String> {
+    let parse_count = |flag: &str| {
+        string_flag(args, flag).map(|raw| match raw.parse::<usize>() {
+            Ok(n) if n > 0 => n,
+            _ => exit_with(EXIT_USAGE, format_args!("error: {flag} must be a positive number")),
+        })
+    };
+    let page = parse_count("--page");
+    let limit = parse_count("--limit").or(page.map(|_| DEFAULT_PAGE_SIZE));
+    params["limit"] = json!(limit);
+    let mut result = backend_call_value(client, addr, method, params.clone())?;
+    for _ in 1..page.unwrap_or(1) {
+        let Some(next) = result["next_after_id"].as_str().map(str::to_string) else {
+            return Ok(json!({ "items": [], "total": result["total"], "next_after_id": null }));
+        };
+        params["after_id"] = json!(next);
+        result = backend_call_value(client, addr, method, params.clone())?;
+    }
+    Ok(result)
+}
+
+/// Unix seconds, or an age such as `7d`, `12h` or `30m` counted back from now.
+fn parse_time_flag(flag: &str, raw: &str) -> u64 {
+    if let Ok(secs) = raw.parse::<u64>() {
+        return secs;
+    }
+    let unit_secs = match raw.chars().last() {
+        Some('m') => 60,
+        Some('h') => 3_600,
+        Some('d') => 86_400,
+        _ => 0,
+    };
+    let age = raw[..raw.len().saturating_sub(1)]
+        .parse::<u64>()
+        .ok()
+        .filter(|_| unit_secs > 0)
+        .unwrap_or_else(|| exit_with(EXIT_USAGE, format_args!("error: {flag} takes unix seconds or an age like 7d, 12h or 30m")));
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    now.saturating_sub(age.saturating_mul(unit_secs))
+}
+
+/// Text mode shows the entries, plus where the page sits when the list has more than one.
+fn print_list_page(page: &Value, json_output: bool, args: &[String]) {
+    if json_output {
+        print_json(page);
+        return;
+    }
+    print_value(&page["items"], false);
+    let shown = page["items"].as_array().map_or(0, Vec::len);
+    let total = page["total"].as_u64().unwrap_or(0) as usize;
+    if shown < total {
+        let next = string_flag(args, "--page").and_then(|p| p.parse::<usize>().ok()).unwrap_or(1) + 1;
+        let more = if page["next_after_id"].is_string() { format!("; --page {next} for more") } else { String::new() };
+        println!("({shown} of {total}{more})");
+    }
+}
+
 fn handle_consent_command(client: &mut JsonRpcClient<AgentService>, args: &[String]) {
-    let json_output = has_flag(args, "--json");
+    let json_output = json_output_flag(args);
     let addr = parse_addr_flag(args);
     let pos = positional_without_flags(args);
     if pos.is_empty() {
-        eprintln!("usage: cli consent list|approve|deny ...");
-        std::process::exit(2);
+        exit_with(EXIT_USAGE, format_args!("usage: cli consent list|approve|deny|grants|revoke ..."));
     }
     let (method, params) = match pos[0].as_str() {
-        "list" => (
-            "consent.list",
-            json!({
+        "list" => {
+            let params = json!({
                 "status": string_flag(args, "--status"),
                 "session_id": string_flag(args, "--session")
-            }),
-        ),
+            });
+            let page = fetch_list_page(client, addr.as_deref(), "consent.list", params, args)
+                .unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("consent error: {err}")));
+            print_list_page(&page, json_output, args);
+            return;
+        }
         "approve" if pos.len() >= 2 => (
             "consent.approve",
-            json!({ "consent_id": pos[1] }),
+            json!({
+                "consent_id": pos[1],
+                "approve_indices": string_flag(args, "--actions").map(|v| parse_index_list(&v)),
+                "deny_indices": string_flag(args, "--deny-actions").map(|v| parse_index_list(&v)).unwrap_or_default(),
+                "scope": string_flag(args, "--scope")
+            }),
+        ),
+        "grants" => ("consent.grants", json!({})),
+        "revoke" if pos.len() >= 2 || string_flag(args, "--tool").is_some() => (
+            "consent.revoke",
+            json!({ "grant_id": pos.get(1), "tool_name": string_flag(args, "--tool") }),
         ),
         "deny" if pos.len() >= 2 => (
             "consent.deny",
             json!({ "consent_id": pos[1] }),
         ),
         _ => {
-            eprintln!("usage: cli consent list|approve <id>|deny <id>");
-            std::process::exit(2);
+            exit_with(
+                EXIT_USAGE,
+                format_args!(
+                    "usage: cli consent list|approve <id> [--actions 0,2] [--deny-actions 1] [--scope once|session|always]|deny <id>|grants|revoke <grant-id>|revoke --tool <name>"
+                ),
+            );
         }
     };
     let result = backend_call_value(client, addr.as_deref(), method, params).unwrap_or_else(|err| {
-        eprintln!("consent error: {err}");
-        std::process::exit(1);
+        exit_with(EXIT_FAILURE, format_args!("consent error: {err}"));
     });
     if method == "consent.approve" || method == "consent.deny" {
         let response: ChatResponse = serde_json::from_value(result).unwrap_or_else(|err| {
-            eprintln!("consent parse error: {err}");
-            std::process::exit(1);
+            exit_with(EXIT_FAILURE, format_args!("consent parse error: {err}"));
         });
         print_chat_response(&response, json_output);
+        exit_if_awaiting_consent(&response);
     } else {
         print_value(&result, json_output);
     }
 }
 
+fn parse_index_list(raw: &str) -> Vec<usize> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            part.parse::<usize>().unwrap_or_else(|_| {
+                exit_with(EXIT_USAGE, format_args!("error: invalid action index '{part}'"));
+            })
+        })
+        .collect()
+}
+
 fn handle_session_command(client: &mut JsonRpcClient<AgentService>, args: &[String]) {
-    let json_output = has_flag(args, "--json");
+    let json_output = json_output_flag(args);
     let addr = parse_addr_flag(args);
     let pos = positional_without_flags(args);
     if pos.is_empty() {
-        eprintln!("usage: cli session new|list|open|rm|append ...");
-        std::process::exit(2);
+        exit_with(EXIT_USAGE, format_args!("usage: cli session new|list|open|rm|append ..."));
     }
     let (method, params) = match pos[0].as_str() {
         "new" => ("sessions.create", json!({ "title": string_flag(args, "--title") })),
-        "list" => ("sessions.list", json!({})),
+        "list" => (
+            "sessions.list",
+            json!({ "include_archived": has_flag(args, "--all"), "pinned_first": has_flag(args, "--pinned-first") }),
+        ),
         "open" | "get" if pos.len() >= 2 => ("sessions.get", json!({ "session_id": pos[1] })),
+        "pin" | "unpin" if pos.len() >= 2 => (
+            "sessions.pin",
+            json!({ "session_id": pos[1], "pinned": pos[0] == "pin" }),
+        ),
+        "archive" | "unarchive" if pos.len() >= 2 => (
+            "sessions.archive",
+            json!({ "session_id": pos[1], "archived": pos[0] == "archive" }),
+        ),
         "rm" | "delete" if pos.len() >= 2 => ("sessions.delete", json!({ "session_id": pos[1] })),
+        "fork" if pos.len() >= 2 => {
+            let message_index = pos.get(2).map(|raw| {
+                raw.parse::<usize>().unwrap_or_else(|_| {
+                    exit_with(EXIT_USAGE, format_args!("session error: message index must be a number, got '{raw}'"))
+                })
+            });
+            (
+                "sessions.fork",
+                json!({ "session_id": pos[1], "message_index": message_index, "title": string_flag(args, "--title") }),
+            )
+        }
+        "update" if pos.len() >= 2 => {
+            let mode = string_flag(args, "--mode").map(|mode| match mode.as_str() {
+                "confirm" => "RequireConfirmation",
+                "best" => "BestEffort",
+                other => exit_with(EXIT_USAGE, format_args!("session error: unknown mode '{other}' (expected confirm|best)")),
+            });
+            (
+                "sessions.update",
+                json!({
+                    "session_id": pos[1],
+                    "title": string_flag(args, "--title"),
+                    "provider_name": string_flag(args, "--provider"),
+                    "model": string_flag(args, "--model"),
+                    "default_mode": mode,
+                    "clear_defaults": has_flag(args, "--clear"),
+                }),
+            )
+        }
+        "policy" if pos.len() >= 2 => {
+            let patterns = |flag: &str| {
+                string_flag(args, flag)
+                    .map(|s| s.split(',').map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect::<Vec<_>>())
+            };
+            let policy = (!has_flag(args, "--clear")).then(|| {
+                json!({
+                    "profile": string_flag(args, "--profile"),
+                    "allow_tools": patterns("--allow"),
+                    "deny_tools": patterns("--deny").unwrap_or_default(),
+                })
+            });
+            ("sessions.policy.set", json!({ "session_id": pos[1], "policy": policy }))
+        }
+        "edit" if pos.len() >= 4 => {
+            let (index, id) = message_selector(&pos[2]);
+            (
+                "sessions.messages.edit",
+                json!({ "session_id": pos[1], "message_index": index, "message_id": id, "content": pos[3..].join(" ") }),
+            )
+        }
+        "rm-message" if pos.len() >= 3 => {
+            let (index, id) = message_selector(&pos[2]);
+            (
+                "sessions.messages.delete",
+                json!({ "session_id": pos[1], "message_index": index, "message_id": id }),
+            )
+        }
         "append" if pos.len() >= 3 => (
             "sessions.messages.append",
             json!({
@@ -874,38 +1732,46 @@ fn handle_session_command(client: &mut JsonRpcClient<AgentService>, args: &[Stri
             }),
         ),
         _ => {
-            eprintln!("usage: cli session new|list|open <id>|rm <id>|append <id> <message>");
-            std::process::exit(2);
+            exit_with(
+                EXIT_USAGE,
+                format_args!(
+                    "usage: cli session new|list [--all] [--pinned-first]|open <id>|rm <id>|pin|unpin|archive|unarchive <id>|fork <id> [<message-index>] [--title <t>]|append <id> <message>|edit <id> <index|msg-id> <text>|rm-message <id> <index|msg-id>|policy <id> [--profile <name>] [--allow <tools>] [--deny <tools>] [--clear]"
+                ),
+            );
         }
     };
     let result = backend_call_value(client, addr.as_deref(), method, params).unwrap_or_else(|err| {
-        eprintln!("session error: {err}");
-        std::process::exit(1);
+        exit_with(EXIT_FAILURE, format_args!("session error: {err}"));
     });
     print_value(&result, json_output);
 }
 
+/// A bare number is a message index, anything else a `msg-...` id.
+fn message_selector(raw: &str) -> (Option<usize>, Option<String>) {
+    match raw.parse::<usize>() {
+        Ok(index) => (Some(index), None),
+        Err(_) => (None, Some(raw.to_string())),
+    }
+}
+
 fn handle_auth_command(client: &mut JsonRpcClient<AgentService>, args: &[String]) {
-    let json_output = has_flag(args, "--json");
+    let json_output = json_output_flag(args);
     let addr = parse_addr_flag(args);
     let pos = positional_without_flags(args);
     if pos.is_empty() {
-        eprintln!("usage: cli auth login|list|logout ...");
-        std::process::exit(2);
+        exit_with(EXIT_USAGE, format_args!("usage: cli auth login|list|logout ..."));
     }
     match pos[0].as_str() {
         "list" => {
             let result = backend_call_value(client, addr.as_deref(), "providers.list", json!({}))
                 .unwrap_or_else(|err| {
-                    eprintln!("auth list error: {err}");
-                    std::process::exit(1);
+                    exit_with(EXIT_FAILURE, format_args!("auth list error: {err}"));
                 });
             print_value(&result, json_output);
         }
         "login" => {
             if pos.len() < 2 {
-                eprintln!("usage: cli auth login <provider> (--key <token> | --env <ENV_VAR>)");
-                std::process::exit(2);
+                exit_with(EXIT_USAGE, format_args!("usage: cli auth login <provider> (--key <token> | --env <ENV_VAR>)"));
             }
             let provider = pos[1].clone();
             let cfg = if let Some(env_var) = string_flag(args, "--env") {
@@ -921,8 +1787,7 @@ fn handle_auth_command(client: &mut JsonRpcClient<AgentService>, args: &[String]
                 json!({ "provider_name": provider, "config_json": cfg }),
             )
             .unwrap_or_else(|err| {
-                eprintln!("auth login error: {err}");
-                std::process::exit(1);
+                exit_with(EXIT_FAILURE, format_args!("auth login error: {err}"));
             });
             let result = backend_call_value(
                 client,
@@ -931,15 +1796,13 @@ fn handle_auth_command(client: &mut JsonRpcClient<AgentService>, args: &[String]
                 json!({ "provider_name": pos[1] }),
             )
             .unwrap_or_else(|err| {
-                eprintln!("auth login error: {err}");
-                std::process::exit(1);
+                exit_with(EXIT_FAILURE, format_args!("auth login error: {err}"));
             });
             print_value(&result, json_output);
         }
         "logout" => {
             if pos.len() < 2 {
-                eprintln!("usage: cli auth logout <provider>");
-                std::process::exit(2);
+                exit_with(EXIT_USAGE, format_args!("usage: cli auth logout <provider>"));
             }
             let result = backend_call_value(
                 client,
@@ -948,25 +1811,22 @@ fn handle_auth_command(client: &mut JsonRpcClient<AgentService>, args: &[String]
                 json!({ "provider_name": pos[1], "config_json": "{}" }),
             )
             .unwrap_or_else(|err| {
-                eprintln!("auth logout error: {err}");
-                std::process::exit(1);
+                exit_with(EXIT_FAILURE, format_args!("auth logout error: {err}"));
             });
             print_value(&result, json_output);
         }
         _ => {
-            eprintln!("usage: cli auth login|list|logout");
-            std::process::exit(2);
+            exit_with(EXIT_USAGE, format_args!("usage: cli auth login|list|logout"));
         }
     }
 }
 
 fn handle_providers_command(client: &mut JsonRpcClient<AgentService>, args: &[String]) {
-    let json_output = has_flag(args, "--json");
+    let json_output = json_output_flag(args);
     let addr = parse_addr_flag(args);
     let pos = positional_without_flags(args);
     if pos.is_empty() {
-        eprintln!("usage: cli providers list|set|config-get|config-set ...");
-        std::process::exit(2);
+        exit_with(EXIT_USAGE, format_args!("usage: cli providers list|set|config-get|config-set|models|embed ..."));
     }
     let (method, params) = match pos[0].as_str() {
         "list" => ("providers.list", json!({})),
@@ -979,25 +1839,41 @@ fn handle_providers_command(client: &mut JsonRpcClient<AgentService>, args: &[St
             "providers.config.set",
             json!({ "provider_name": pos[1], "config_json": pos[2] }),
         ),
+        "models" => (
+            "providers.models.list",
+            json!({ "provider_name": pos.get(1).cloned() }),
+        ),
+        "embed" if pos.len() >= 2 => (
+            "ai.embed",
+            json!({
+                "texts": &pos[1..],
+                "provider_name": string_flag(args, "--provider"),
+                "model": string_flag(args, "--model"),
+            }),
+        ),
         _ => {
-            eprintln!("usage: cli providers list|set <name>|config-get [name]|config-set <name> <json>");
-            std::process::exit(2);
+            exit_with(
+                EXIT_USAGE,
+                format_args!("usage: cli providers list|set <name>|config-get [name]|config-set <name> <json>|models [name]|embed <text>... [--provider <name>] [--model <name>]"),
+            );
         }
     };
     let result = backend_call_value(client, addr.as_deref(), method, params).unwrap_or_else(|err| {
-        eprintln!("providers error: {err}");
-        std::process::exit(1);
+        exit_with(EXIT_FAILURE, format_args!("providers error: {err}"));
     });
     print_value(&result, json_output);
 }
 
 fn handle_mcp_command(client: &mut JsonRpcClient<AgentService>, args: &[String]) {
-    let json_output = has_flag(args, "--json");
+    let json_output = json_output_flag(args);
     let addr = parse_addr_flag(args);
     let pos = positional_without_flags(args);
+    if pos.first().map(String::as_str) == Some("import") {
+        handle_mcp_import(client, args, addr.as_deref(), json_output);
+        return;
+    }
     if pos.len() < 2 || pos[0] != "servers" {
-        eprintln!("usage: cli mcp servers list|add|rm|start|stop|probe|tools|call|tool-call ...");
-        std::process::exit(2);
+        exit_with(EXIT_USAGE, format_args!("usage: cli mcp servers list|add|rm|start|stop|probe|tools|policy|call|tool-call ... | cli mcp import --from claude|cursor [--path <file>]"));
     }
     let (method, params) = match pos[1].as_str() {
         "list" => ("mcp.servers.list", json!({})),
@@ -1007,9 +1883,12 @@ fn handle_mcp_command(client: &mut JsonRpcClient<AgentService>, args: &[String])
             let argv = string_flag(args, "--args")
                 .map(|s| s.split_whitespace().map(|v| v.to_string()).collect::<Vec<_>>())
                 .unwrap_or_default();
+            let restart_policy = string_flag(args, "--restart")
+                .map(|value| McpRestartPolicy::parse(&value).unwrap_or_else(|err| usage_exit(format_args!("error: {err}"))))
+                .unwrap_or_default();
             (
                 "mcp.servers.add",
-                json!({ "name": name, "command": command, "args": argv }),
+                json!({ "name": name, "command": command, "args": argv, "restart_policy": restart_policy }),
             )
         }
         "rm" | "remove" if pos.len() >= 3 => ("mcp.servers.remove", json!({ "server_id": pos[2] })),
@@ -1017,6 +1896,14 @@ fn handle_mcp_command(client: &mut JsonRpcClient<AgentService>, args: &[String])
         "stop" if pos.len() >= 3 => ("mcp.servers.stop", json!({ "server_id": pos[2] })),
         "probe" if pos.len() >= 3 => ("mcp.servers.probe", json!({ "server_id": pos[2] })),
         "tools" if pos.len() >= 3 => ("mcp.servers.tools", json!({ "server_id": pos[2] })),
+        "policy" if pos.len() >= 3 => {
+            let allowed_tools = string_flag(args, "--allow")
+                .map(|s| s.split(',').map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect::<Vec<_>>());
+            (
+                "mcp.servers.policy.set",
+                json!({ "server_id": pos[2], "allowed_tools": allowed_tools, "capability_tier": string_flag(args, "--tier") }),
+            )
+        }
         "call" if pos.len() >= 4 => (
             "mcp.servers.call",
             json!({
@@ -1034,77 +1921,564 @@ fn handle_mcp_command(client: &mut JsonRpcClient<AgentService>, args: &[String])
             }),
         ),
         _ => {
-            eprintln!("usage: cli mcp servers list|add --name N --command CMD [--args \"...\"]|rm <id>|start <id>|stop <id>|probe <id>|tools <id>|call <id> <method> [--params JSON]|tool-call <id> <tool> [--args-json JSON]");
-            std::process::exit(2);
+            exit_with(EXIT_USAGE, format_args!("usage: cli mcp servers list|add --name N --command CMD [--args \"...\"] [--restart never|on-failure|always]|rm <id>|start <id>|stop <id>|probe <id>|tools <id>|policy <id> [--allow t1,t2] [--tier ReadOnly|LocalActions|SystemActions]|call <id> <method> [--params JSON]|tool-call <id> <tool> [--args-json JSON]"));
         }
     };
     let result = backend_call_value(client, addr.as_deref(), method, params).unwrap_or_else(|err| {
-        eprintln!("mcp error: {err}");
-        std::process::exit(1);
+        exit_with(EXIT_FAILURE, format_args!("mcp error: {err}"));
     });
     print_value(&result, json_output);
 }
 
+/// Well-known config locations: Claude Desktop keeps `claude_desktop_config.json` in the platform
+/// config dir, Cursor keeps a global `~/.cursor/mcp.json`.
+fn mcp_import_default_path(source: &str) -> Option<std::path::PathBuf> {
+    let dirs = directories::BaseDirs::new()?;
+    match source {
+        "claude" => Some(dirs.config_dir().join("Claude").join("claude_desktop_config.json")),
+        "cursor" => Some(dirs.home_dir().join(".cursor").join("mcp.json")),
+        _ => None,
+    }
+}
+
+fn handle_mcp_import(client: &mut JsonRpcClient<AgentService>, args: &[String], addr: Option<&str>, json_output: bool) {
+    let source = string_flag(args, "--from").unwrap_or_default();
+    if !matches!(source.as_str(), "claude" | "cursor") && string_flag(args, "--path").is_none() {
+        usage_exit(format_args!("usage: cli mcp import --from claude|cursor [--path <file>] [--restart never|on-failure|always]"));
+    }
+    let path = string_flag(args, "--path")
+        .map(std::path::PathBuf::from)
+        .or_else(|| mcp_import_default_path(&source))
+        .unwrap_or_else(|| exit_with(EXIT_FAILURE, format_args!("could not determine the {source} config location; pass --path")));
+    let config_json = std::fs::read_to_string(&path)
+        .unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("failed to read {}: {err}", path.display())));
+    let restart_policy = string_flag(args, "--restart")
+        .map(|value| McpRestartPolicy::parse(&value).unwrap_or_else(|err| usage_exit(format_args!("error: {err}"))))
+        .unwrap_or_default();
+    let result = backend_call_value(
+        client,
+        addr,
+        "mcp.servers.import",
+        json!({ "config_json": config_json, "restart_policy": restart_policy }),
+    )
+    .unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("mcp import error: {err}")));
+    if json_output {
+        print_json(&result);
+        return;
+    }
+    let response: ipc::McpServersImportResponse = serde_json::from_value(result)
+        .unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("invalid mcp.servers.import response: {err}")));
+    println!("imported {} server(s) from {}", response.imported.len(), path.display());
+    for server in &response.imported {
+        println!("  + {} ({}): {} {}", server.name, server.id, server.command, server.args.join(" "));
+    }
+    for skip in &response.skipped {
+        println!("  - {}: {}", skip.name, skip.reason);
+    }
+}
+
 fn handle_project_command(client: &mut JsonRpcClient<AgentService>, args: &[String]) {
-    let json_output = has_flag(args, "--json");
+    let json_output = json_output_flag(args);
     let addr = parse_addr_flag(args);
     let pos = positional_without_flags(args);
     if pos.is_empty() {
-        eprintln!("usage: cli project open <path>|status [--path <path>]");
-        std::process::exit(2);
+        exit_with(EXIT_USAGE, format_args!("usage: cli project open <path>|recent|pin|unpin <path|N>|list|switch <name|path> [--session <id>]|status [--path <path>]|watch on|off [--path <path>]|changes [--since N]"));
     }
     let (method, params) = match pos[0].as_str() {
         "open" if pos.len() >= 2 => ("project.open", json!({ "path": pos[1] })),
+        "recent" => ("project.recent", json!({})),
+        "pin" | "unpin" if pos.len() >= 2 => (
+            "project.recent.pin",
+            json!({
+                "path": resolve_recent_project(client, addr.as_deref(), &pos[1]),
+                "pinned": pos[0] == "pin"
+            }),
+        ),
+        "list" => ("project.list", json!({})),
+        "switch" if pos.len() >= 2 => (
+            "project.switch",
+            json!({ "name": pos[1], "session_id": string_flag(args, "--session") }),
+        ),
         "status" => ("project.status", json!({ "path": string_flag(args, "--path") })),
+        "watch" if pos.len() >= 2 && matches!(pos[1].as_str(), "on" | "off") => (
+            "project.watch",
+            json!({ "enabled": pos[1] == "on", "path": string_flag(args, "--path") }),
+        ),
+        "changes" => (
+            "project.changes",
+            json!({
+                "since": string_flag(args, "--since").and_then(|v| v.parse::<u64>().ok()).unwrap_or(0),
+                "limit": string_flag(args, "--limit").and_then(|v| v.parse::<usize>().ok())
+            }),
+        ),
         _ => {
-            eprintln!("usage: cli project open <path>|status [--path <path>]");
-            std::process::exit(2);
+            exit_with(EXIT_USAGE, format_args!("usage: cli project open <path>|recent|pin|unpin <path|N>|list|switch <name|path> [--session <id>]|status [--path <path>]|watch on|off [--path <path>]|changes [--since N]"));
         }
     };
     let result = backend_call_value(client, addr.as_deref(), method, params).unwrap_or_else(|err| {
-        eprintln!("project error: {err}");
-        std::process::exit(1);
+        exit_with(EXIT_FAILURE, format_args!("project error: {err}"));
     });
     print_value(&result, json_output);
 }
 
+/// `N` picks the Nth entry of `project recent` (1-based); anything else is taken as a path.
+fn resolve_recent_project(client: &mut JsonRpcClient<AgentService>, addr: Option<&str>, arg: &str) -> String {
+    let Ok(index) = arg.parse::<usize>() else {
+        return arg.to_string();
+    };
+    let recent = backend_call_value(client, addr, "project.recent", json!({})).unwrap_or_else(|err| {
+        exit_with(EXIT_FAILURE, format_args!("project error: {err}"));
+    });
+    recent
+        .get("projects")
+        .and_then(|p| p.get(index.wrapping_sub(1)))
+        .and_then(|p| p.get("path"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| exit_with(EXIT_USAGE, format_args!("no recent project #{index}; see `cli project recent`")))
+}
+
 fn handle_audit_command(client: &mut JsonRpcClient<AgentService>, args: &[String]) {
-    let json_output = has_flag(args, "--json");
+    let json_output = json_output_flag(args);
     let addr = parse_addr_flag(args);
     let pos = positional_without_flags(args);
     if pos.is_empty() {
-        eprintln!("usage: cli audit list|show ...");
-        std::process::exit(2);
+        exit_with(EXIT_USAGE, format_args!("usage: cli audit list|show ...|replay <audit_id> --dry-run"));
+    }
+    if pos[0] == "replay" {
+        if pos.len() < 2 || !has_flag(args, "--dry-run") {
+            exit_with(EXIT_USAGE, format_args!("usage: cli audit replay <audit_id> --dry-run"));
+        }
+        let params = json!({ "audit_id": pos[1], "dry_run": true });
+        let result = backend_call_value(client, addr.as_deref(), "audit.replay", params)
+            .unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("audit error: {err}")));
+        if json_output {
+            print_json(&result);
+            return;
+        }
+        let replay: ipc::AuditReplayResponse = serde_json::from_value(result)
+            .unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("audit error: {err}")));
+        if !replay.replayable {
+            println!("{}: recorded before tool calls were kept; nothing to replay", replay.audit_id);
+            return;
+        }
+        let verdict = if replay.unchanged { "same decisions" } else { "decisions changed" };
+        println!("{}: {verdict}", replay.audit_id);
+        for d in &replay.decisions {
+            let marker = if d.changed { "*" } else { " " };
+            println!("{marker} {}: {} -> {} ({})", d.tool_name, d.original_decision, d.current_decision, d.rule);
+        }
+        return;
+    }
+    if pos[0] == "show" && pos.len() >= 2 && has_flag(args, "--artifacts") {
+        let result = backend_call_value(client, addr.as_deref(), "audit.evidence.get", json!({ "audit_id": pos[1] }))
+            .unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("audit error: {err}")));
+        if json_output {
+            print_json(&result);
+            return;
+        }
+        let evidence: ipc::AuditEvidenceResponse = serde_json::from_value(result)
+            .unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("audit error: {err}")));
+        print_audit_artifacts(&evidence, string_flag(args, "--out").as_deref());
+        return;
     }
     let (method, params) = match pos[0].as_str() {
-        "list" => (
-            "audit.list",
-            json!({
+        "list" => {
+            let params = json!({
                 "session_id": string_flag(args, "--session"),
-                "limit": string_flag(args, "--limit").and_then(|s| s.parse::<usize>().ok())
-            }),
-        ),
+                "since_unix_seconds": string_flag(args, "--since").map(|t| parse_time_flag("--since", &t)),
+                "until_unix_seconds": string_flag(args, "--until").map(|t| parse_time_flag("--until", &t)),
+                "tool_name": string_flag(args, "--tool"),
+                "provider": string_flag(args, "--provider"),
+            });
+            let page = fetch_list_page(client, addr.as_deref(), "audit.list", params, args)
+                .unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("audit error: {err}")));
+            print_list_page(&page, json_output, args);
+            return;
+        }
         "show" if pos.len() >= 2 => ("audit.get", json!({ "audit_id": pos[1] })),
         _ => {
-            eprintln!("usage: cli audit list [--session <id>] [--limit N]|show <audit_id>");
-            std::process::exit(2);
+            exit_with(
+                EXIT_USAGE,
+                format_args!(
+                    "usage: cli audit list [--session <id>] [--since <t>] [--until <t>] [--tool <name>] [--provider <name>] [--page N] [--limit N]|show <audit_id> [--artifacts [--out <dir>]]"
+                ),
+            );
+        }
+    };
+    let result = backend_call_value(client, addr.as_deref(), method, params).unwrap_or_else(|err| {
+        exit_with(EXIT_FAILURE, format_args!("audit error: {err}"));
+    });
+    print_value(&result, json_output);
+}
+
+/// Text artifacts (diffs, pages) are printed; with `out` every artifact is saved there as
+/// `<sha256 prefix>-<name>` instead.
+fn print_audit_artifacts(evidence: &ipc::AuditEvidenceResponse, out: Option<&str>) {
+    if evidence.artifacts.is_empty() {
+        println!("{}: no stored artifacts", evidence.audit_id);
+        return;
+    }
+    if let Some(dir) = out {
+        if let Err(err) = std::fs::create_dir_all(dir) {
+            exit_with(EXIT_FAILURE, format_args!("audit error: cannot create {dir}: {err}"));
+        }
+    }
+    for item in &evidence.artifacts {
+        let artifact = &item.artifact;
+        let bytes = BASE64_STANDARD
+            .decode(&item.content_base64)
+            .unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("audit error: {}: {err}", artifact.name)));
+        println!(
+            "== {} ({}, {} bytes, from {}) sha256:{}",
+            artifact.name, artifact.media_type, artifact.size_bytes, artifact.tool_name, artifact.sha256
+        );
+        let file_name = Path::new(&artifact.name).file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        match out {
+            Some(dir) => {
+                let path = Path::new(dir).join(format!("{}-{file_name}", &artifact.sha256[..12.min(artifact.sha256.len())]));
+                if let Err(err) = std::fs::write(&path, &bytes) {
+                    exit_with(EXIT_FAILURE, format_args!("audit error: cannot write {}: {err}", path.display()));
+                }
+                println!("saved to {}", path.display());
+            }
+            None => match String::from_utf8(bytes) {
+                Ok(text) if artifact.media_type.starts_with("text/") || artifact.media_type.ends_with("json") => {
+                    println!("{}", text.trim_end())
+                }
+                _ => println!("(binary; use --out <dir> to save it)"),
+            },
+        }
+    }
+}
+
+fn handle_policy_command(client: &mut JsonRpcClient<AgentService>, args: &[String]) {
+    let json_output = json_output_flag(args);
+    let addr = parse_addr_flag(args);
+    let pos = positional_without_flags(args);
+    if pos.first().map(String::as_str) == Some("profile") {
+        let result = match pos.get(1) {
+            Some(name) => backend_call_value(client, addr.as_deref(), "policy.profile.set", json!({ "profile": name })),
+            None => backend_call_value(client, addr.as_deref(), "policy.profile.get", json!({})),
+        }
+        .unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("policy error: {err}")));
+        print_value(&result, json_output);
+        return;
+    }
+    if pos.len() < 2 || pos[0] != "explain" {
+        exit_with(
+            EXIT_USAGE,
+            format_args!(
+                "usage: cli policy explain <tool> [<args-json>] [--mode confirm|best] [--session <id>] | profile [strict|balanced|permissive]"
+            ),
+        );
+    }
+    let arguments_json = pos.get(2).cloned().unwrap_or_else(|| "{}".to_string());
+    if serde_json::from_str::<Value>(&arguments_json).is_err() {
+        exit_with(EXIT_USAGE, format_args!("policy error: <args-json> must be valid JSON"));
+    }
+    let mode = match string_flag(args, "--mode").as_deref() {
+        None | Some("best") => "BestEffort",
+        Some("confirm") => "RequireConfirmation",
+        Some(other) => exit_with(EXIT_USAGE, format_args!("policy error: unknown mode '{other}' (expected confirm|best)")),
+    };
+    let params = json!({
+        "tool_call": { "name": pos[1], "arguments_json": arguments_json },
+        "mode": mode,
+        "session_id": string_flag(args, "--session"),
+    });
+    let result = backend_call_value(client, addr.as_deref(), "policy.explain", params).unwrap_or_else(|err| {
+        exit_with(EXIT_FAILURE, format_args!("policy error: {err}"));
+    });
+    print_value(&result, json_output);
+}
+
+fn handle_tools_toggle_command(client: &mut JsonRpcClient<AgentService>, args: &[String]) {
+    let json_output = json_output_flag(args);
+    let addr = parse_addr_flag(args);
+    let pos = positional_without_flags(args);
+    if pos.len() != 2 {
+        exit_with(
+            EXIT_USAGE,
+            format_args!("usage: cli tools enable|disable <name|prefix*> [--path <project>] [--global]"),
+        );
+    }
+    let params = json!({
+        "name": pos[1],
+        "project_path": string_flag(args, "--path"),
+        "global": args.iter().any(|a| a == "--global"),
+    });
+    let result = backend_call_value(client, addr.as_deref(), &format!("tools.{}", pos[0]), params)
+        .unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("tools error: {err}")));
+    print_value(&result, json_output);
+}
+
+fn handle_workflow_command(client: &mut JsonRpcClient<AgentService>, args: &[String]) {
+    let json_output = json_output_flag(args);
+    let addr = parse_addr_flag(args);
+    let pos = positional_without_flags(args);
+    if pos.len() < 2 || pos[0] != "run" {
+        exit_with(
+            EXIT_USAGE,
+            format_args!("usage: cli workflow run <file.yaml|file.json> [--mode confirm|best] [--provider <name>] [--session <id>]"),
+        );
+    }
+    // Sent as text so a backend on another host never needs the file.
+    let definition = std::fs::read_to_string(&pos[1]).unwrap_or_else(|err| {
+        exit_with(EXIT_USAGE, format_args!("workflow error: failed to read {}: {err}", pos[1]));
+    });
+    let mode = match string_flag(args, "--mode").as_deref() {
+        None => None,
+        Some("confirm") => Some("RequireConfirmation"),
+        Some("best") => Some("BestEffort"),
+        Some(other) => exit_with(EXIT_USAGE, format_args!("workflow error: unknown mode '{other}' (expected confirm|best)")),
+    };
+    let params = json!({
+        "definition": definition,
+        "mode": mode,
+        "provider_name": string_flag(args, "--provider"),
+        "session_id": string_flag(args, "--session"),
+    });
+    let result = backend_call_value(client, addr.as_deref(), "workflow.run", params).unwrap_or_else(|err| {
+        exit_with(EXIT_FAILURE, format_args!("workflow error: {err}"));
+    });
+    let response: WorkflowRunResponse = serde_json::from_value(result.clone()).unwrap_or_else(|err| {
+        exit_with(EXIT_FAILURE, format_args!("workflow parse error: {err}"));
+    });
+    if json_output {
+        print_value(&result, true);
+    } else {
+        for step in &response.steps {
+            let audit = step.audit_id.as_deref().map(|id| format!(" [{id}]")).unwrap_or_default();
+            println!("{:<16} {}{audit}", step.status, step.step_id);
+            if let Some(token) = &step.consent_token {
+                println!("    resolve with `cli consent approve|deny {token}`");
+            }
+            for line in step.output.lines().take(5) {
+                println!("    {line}");
+            }
+        }
+        println!("workflow {}", response.status);
+    }
+    match response.status.as_str() {
+        "failed" => std::process::exit(EXIT_FAILURE),
+        "awaiting_consent" if output_format() == OutputFormat::Jsonl => std::process::exit(EXIT_AWAITING_CONSENT),
+        _ => {}
+    }
+}
+
+fn handle_tasks_command(client: &mut JsonRpcClient<AgentService>, args: &[String]) {
+    let json_output = json_output_flag(args);
+    let addr = parse_addr_flag(args);
+    let pos = positional_without_flags(args);
+    let (method, params) = match pos.first().map(String::as_str) {
+        Some("list") => ("tasks.list", json!({})),
+        Some("create") if pos.len() >= 3 => {
+            let mode = match string_flag(args, "--mode").as_deref() {
+                None | Some("confirm") => "RequireConfirmation",
+                Some("best") => "BestEffort",
+                Some(other) => exit_with(EXIT_USAGE, format_args!("tasks error: unknown mode '{other}' (expected confirm|best)")),
+            };
+            (
+                "tasks.create",
+                json!({
+                    "schedule": pos[1],
+                    "prompt": pos[2..].join(" "),
+                    "mode": mode,
+                    "provider_name": string_flag(args, "--provider"),
+                    "session_id": string_flag(args, "--session"),
+                }),
+            )
+        }
+        Some("rm" | "delete") if pos.len() >= 2 => ("tasks.delete", json!({ "task_id": pos[1] })),
+        _ => exit_with(
+            EXIT_USAGE,
+            format_args!(
+                "usage: cli tasks list|create \"<schedule>\" <prompt> [--mode confirm|best] [--provider <name>] [--session <id>]|rm <task-id>"
+            ),
+        ),
+    };
+    let result = backend_call_value(client, addr.as_deref(), method, params).unwrap_or_else(|err| {
+        exit_with(EXIT_FAILURE, format_args!("tasks error: {err}"));
+    });
+    print_value(&result, json_output);
+}
+
+fn handle_state_command(client: &mut JsonRpcClient<AgentService>, args: &[String]) {
+    let json_output = json_output_flag(args);
+    let addr = parse_addr_flag(args);
+    let pos = positional_without_flags(args);
+    if pos.first().map(String::as_str) == Some("prune") {
+        let result = backend_call_value(
+            client,
+            addr.as_deref(),
+            "storage.prune",
+            json!({ "dry_run": has_flag(args, "--dry-run") }),
+        )
+        .unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("state error: {err}")));
+        print_value(&result, json_output);
+        return;
+    }
+    if pos.len() < 2 {
+        exit_with(EXIT_USAGE, format_args!("usage: cli state backup <file.tar.gz> [--exclude-secrets]|restore <file.tar.gz>|prune [--dry-run]"));
+    }
+    // The daemon may run from another directory, so send it an absolute path.
+    let path = std::path::absolute(&pos[1]).map(|p| p.display().to_string()).unwrap_or_else(|_| pos[1].clone());
+    let (method, params) = match pos[0].as_str() {
+        "backup" => (
+            "state.backup",
+            json!({ "path": path, "exclude_secrets": has_flag(args, "--exclude-secrets") }),
+        ),
+        "restore" => ("state.restore", json!({ "path": path })),
+        _ => {
+            exit_with(EXIT_USAGE, format_args!("usage: cli state backup <file.tar.gz> [--exclude-secrets]|restore <file.tar.gz>|prune [--dry-run]"));
         }
     };
     let result = backend_call_value(client, addr.as_deref(), method, params).unwrap_or_else(|err| {
-        eprintln!("audit error: {err}");
-        std::process::exit(1);
+        exit_with(EXIT_FAILURE, format_args!("state error: {err}"));
     });
     print_value(&result, json_output);
 }
 
+fn handle_daemon_command(args: &[String]) {
+    let json_output = json_output_flag(args);
+    let pos = positional_without_flags(args);
+    match pos.first().map(String::as_str) {
+        Some("status") => {
+            let info = daemon::read_info();
+            let running = info.as_ref().map(|i| daemon::is_listening(&i.addr)).unwrap_or(false);
+            if json_output {
+                print_json(&json!({
+                    "running": running,
+                    "pid": info.as_ref().map(|i| i.pid),
+                    "addr": info.as_ref().map(|i| i.addr.clone()),
+                    "log": daemon::log_path(),
+                }));
+                return;
+            }
+            match info {
+                Some(info) if running => println!("daemon: running on {} (pid {})", info.addr, info.pid),
+                Some(info) => println!("daemon: not running (stale pidfile for pid {} at {})", info.pid, info.addr),
+                None => println!("daemon: not running"),
+            }
+        }
+        Some("stop") => {
+            let stopped = daemon::stop().unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("daemon error: {err}")));
+            if json_output {
+                print_json(&json!({ "stopped": stopped.as_ref().map(|i| i.pid) }));
+                return;
+            }
+            match stopped {
+                Some(info) => println!("daemon: stopped pid {} ({})", info.pid, info.addr),
+                None => println!("daemon: not running"),
+            }
+        }
+        _ => exit_with(EXIT_USAGE, format_args!("usage: cli daemon status|stop")),
+    }
+}
+
+fn handle_service_command(args: &[String]) {
+    let name = string_flag(args, "--name").unwrap_or_else(|| service::DEFAULT_SERVICE_NAME.to_string());
+    let pos = positional_without_flags(args);
+    match pos.first().map(String::as_str) {
+        Some("install") => {
+            let addr = string_flag(args, "--addr")
+                .or_else(|| cli_config().addr.clone())
+                .unwrap_or_else(|| "127.0.0.1:7777".to_string());
+            let command = service::install(&name, &addr)
+                .unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("service error: {err}")));
+            if json_output_flag(args) {
+                print_json(&json!({ "name": name, "addr": addr, "command": command }));
+            } else {
+                println!("service '{name}' installed and started on {}", endpoint_label(&addr));
+                println!("  command: {command}");
+            }
+        }
+        Some("uninstall") => {
+            service::uninstall(&name).unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("service error: {err}")));
+            if json_output_flag(args) {
+                print_json(&json!({ "name": name, "removed": true }));
+            } else {
+                println!("service '{name}' removed");
+            }
+        }
+        Some("generate-unit") => {
+            let options = service::SystemdUnitOptions {
+                name: name.clone(),
+                addr: string_flag(args, "--addr")
+                    .or_else(|| cli_config().addr.clone())
+                    .unwrap_or_else(|| "127.0.0.1:7777".to_string()),
+                socket: has_flag(args, "--socket"),
+                user: string_flag(args, "--user").or_else(|| env::var("USER").ok().filter(|user| user != "root")),
+                read_write_paths: args
+                    .windows(2)
+                    .filter(|pair| pair[0] == "--read-write")
+                    .map(|pair| std::path::PathBuf::from(&pair[1]))
+                    .collect(),
+            };
+            let units = service::systemd_units(&options)
+                .unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("service error: {err}")));
+            let mut files = vec![(format!("{name}.service"), units.service)];
+            if let Some(socket) = units.socket {
+                files.push((format!("{name}.socket"), socket));
+            }
+            if let Some(dir) = string_flag(args, "--out") {
+                let dir = std::path::PathBuf::from(dir);
+                for (file, contents) in &files {
+                    std::fs::write(dir.join(file), contents).unwrap_or_else(|err| {
+                        exit_with(EXIT_FAILURE, format_args!("failed to write {}: {err}", dir.join(file).display()))
+                    });
+                }
+            }
+            if json_output_flag(args) {
+                print_json(&Value::Object(
+                    files.into_iter().map(|(file, contents)| (file, Value::String(contents))).collect(),
+                ));
+            } else if has_flag(args, "--out") {
+                for (file, _) in &files {
+                    println!("wrote {file}");
+                }
+                let unit = files.last().map(|(file, _)| file.as_str()).unwrap_or_default();
+                println!("enable with: systemctl daemon-reload && systemctl enable --now {unit}");
+            } else {
+                for (i, (file, contents)) in files.iter().enumerate() {
+                    if i > 0 {
+                        println!();
+                    }
+                    println!("# {file}");
+                    print!("{contents}");
+                }
+            }
+        }
+        _ => exit_with(
+            EXIT_USAGE,
+            format_args!("usage: cli service install|uninstall|generate-unit [--name <name>] [--addr <addr>]"),
+        ),
+    }
+}
+
+fn handle_logs_command(args: &[String]) {
+    let min_level = string_flag(args, "--level")
+        .map(|level| logging::parse_level(&level).unwrap_or_else(|err| exit_with(EXIT_USAGE, format_args!("error: {err}"))))
+        .unwrap_or(0);
+    let lines = string_flag(args, "--lines")
+        .map(|n| {
+            n.parse::<usize>()
+                .unwrap_or_else(|_| exit_with(EXIT_USAGE, format_args!("error: invalid --lines value: {n}")))
+        })
+        .unwrap_or(50);
+    if let Err(err) = logging::tail(lines, min_level, has_flag(args, "--follow"), json_output_flag(args)) {
+        exit_with(EXIT_FAILURE, format_args!("logs error: {err}"));
+    }
+}
+
 fn handle_doctor_command(client: &mut JsonRpcClient<AgentService>, args: &[String]) {
-    let json_output = has_flag(args, "--json");
+    let json_output = json_output_flag(args);
     let strict = has_flag(args, "--strict");
     let addr = parse_addr_flag(args);
     let result = backend_call_value(client, addr.as_deref(), "system.health", json!({}))
         .unwrap_or_else(|err| {
-            eprintln!("doctor error: {err}");
-            std::process::exit(1);
+            exit_with(EXIT_FAILURE, format_args!("doctor error: {err}"));
         });
 
     if json_output {
@@ -1116,7 +2490,7 @@ fn handle_doctor_command(client: &mut JsonRpcClient<AgentService>, args: &[Strin
                 .map(|ok| !ok)
                 .unwrap_or(false)
         {
-            std::process::exit(3);
+            std::process::exit(EXIT_UNHEALTHY);
         }
         return;
     }
@@ -1130,6 +2504,13 @@ fn handle_doctor_command(client: &mut JsonRpcClient<AgentService>, args: &[Strin
                 has_warnings = true;
             }
         }
+        if let Some(build) = obj.get("build") {
+            println!(
+                "version: {} ({})",
+                build["version"].as_str().unwrap_or("?"),
+                build["git_commit"].as_str().unwrap_or("unknown commit")
+            );
+        }
         println!(
             "active_provider: {}",
             obj.get("active_provider")
@@ -1155,6 +2536,37 @@ fn handle_doctor_command(client: &mut JsonRpcClient<AgentService>, args: &[Strin
                 .and_then(|v| v.as_str())
                 .unwrap_or("(not set)")
         );
+        if let Some(stores) = obj.get("stores").and_then(|v| v.as_array()) {
+            let present = stores.iter().filter(|s| s["exists"].as_bool().unwrap_or(false)).count();
+            println!(
+                "storage: {} bytes, {present}/{} store files, integrity {}",
+                obj.get("storage_bytes").and_then(|v| v.as_u64()).unwrap_or(0),
+                stores.len(),
+                if obj.get("storage_integrity").and_then(|v| v.as_bool()).unwrap_or(true) { "ok" } else { "FAILED" }
+            );
+            for store in stores.iter().filter(|s| s["exists"].as_bool().unwrap_or(false)) {
+                let mut line = format!(
+                    "  {}: {} bytes, v{}",
+                    store["file_name"].as_str().unwrap_or("?"),
+                    store["size_bytes"].as_u64().unwrap_or(0),
+                    store["schema_version"].as_u64().map_or("?".to_string(), |v| v.to_string())
+                );
+                if let Some(modified) = store["modified_unix_seconds"].as_u64() {
+                    line.push_str(&format!(", written at {modified}"));
+                }
+                let contended = store["lock_contended"].as_u64().unwrap_or(0);
+                if contended > 0 {
+                    line.push_str(&format!(
+                        ", lock waited {contended}x ({} ms)",
+                        store["lock_wait_ms"].as_u64().unwrap_or(0)
+                    ));
+                }
+                if store["error"].is_string() {
+                    line.push_str(", UNREADABLE");
+                }
+                println!("{line}");
+            }
+        }
         if let Some(warnings) = obj.get("warnings").and_then(|v| v.as_array()) {
             if !warnings.is_empty() {
                 has_warnings = true;
@@ -1171,7 +2583,57 @@ fn handle_doctor_command(client: &mut JsonRpcClient<AgentService>, args: &[Strin
     }
 
     if strict && has_warnings {
-        std::process::exit(3);
+        std::process::exit(EXIT_UNHEALTHY);
+    }
+}
+
+fn handle_update_command(args: &[String], disabled: bool) {
+    if args.first().map(String::as_str) != Some("check") {
+        exit_with(EXIT_USAGE, format_args!("usage: cli update check [--json]"));
+    }
+    if disabled {
+        exit_with(
+            EXIT_FAILURE,
+            format_args!("update error: update checks are disabled (disable_update_check in cli.toml or --disable-update-check)"),
+        );
+    }
+    let status = update::check(cli_config().update_url())
+        .unwrap_or_else(|err| exit_with(EXIT_FAILURE, format_args!("update error: {err}")));
+    if json_output_flag(args) {
+        print_json(&status);
+    } else if status.update_available {
+        println!("{}", update::upgrade_instructions(&status));
+    } else {
+        println!("cli {} is up to date (latest release {})", status.current, status.latest);
+    }
+}
+
+fn handle_version_command(client: &mut JsonRpcClient<AgentService>, args: &[String]) {
+    let addr = parse_addr_flag(args);
+    let cli = agent::build_info::build_info();
+    let backend = backend_call_value(client, addr.as_deref(), "system.version", json!({}))
+        .and_then(|value| serde_json::from_value::<ipc::BuildInfo>(value).map_err(|err| err.to_string()));
+    if json_output_flag(args) {
+        let backend = match &backend {
+            Ok(info) => json!(info),
+            Err(err) => json!({ "error": err }),
+        };
+        print_json(&json!({ "cli": cli, "backend": backend }));
+        return;
+    }
+    let describe = |info: &ipc::BuildInfo| {
+        format!(
+            "{} (commit {}, built {}, features: {})",
+            info.version,
+            info.git_commit.as_deref().unwrap_or("unknown"),
+            info.build_date.as_deref().unwrap_or("unknown"),
+            if info.features.is_empty() { "none".to_string() } else { info.features.join(",") }
+        )
+    };
+    println!("cli {}", describe(&cli));
+    match backend {
+        Ok(info) => println!("backend {}", describe(&info)),
+        Err(err) => println!("backend unavailable: {err}"),
     }
 }
 
@@ -1187,18 +2649,29 @@ where
 }
 
 fn print_chat_response(response: &ChatResponse, json_output: bool) {
+    if output_format() == OutputFormat::Jsonl {
+        emit_event("chat.response", json!({ "data": response }));
+        if let Some(token) = &response.consent_token {
+            emit_event(
+                "consent.required",
+                json!({ "consent_token": token, "consent_request": response.consent_request }),
+            );
+        }
+        return;
+    }
     if json_output {
-        println!(
-            "{}",
-            serde_json::to_string_pretty(response).unwrap_or_else(|_| "{}".to_string())
-        );
+        print_json(response);
         return;
     }
     let feed_item = response.to_execution_feed_item(None);
-    print_feed_item(&feed_item, response.consent_token.as_deref());
+    print_feed_item(&feed_item, response.consent_token.as_deref(), None);
+    if response.provider_retries > 0 {
+        println!("system> provider retries: {}", response.provider_retries);
+    }
 }
 
-fn print_feed_item(item: &ExecutionFeedItem, consent_token: Option<&str>) {
+/// `streamed` is assistant text already printed live; it is not repeated.
+fn print_feed_item(item: &ExecutionFeedItem, consent_token: Option<&str>, streamed: Option<&str>) {
     let status_label = match item.status.as_str() {
         "completed" => "done",
         "denied" => "blocked",
@@ -1209,9 +2682,20 @@ fn print_feed_item(item: &ExecutionFeedItem, consent_token: Option<&str>) {
     if let Some(prompt) = &item.user_prompt {
         println!("you> {}", prompt);
     }
-    println!("assistant> {}", item.assistant_text);
+    if streamed != Some(item.assistant_text.as_str()) {
+        println!("assistant> {}", item.assistant_text);
+    }
     if let Some(consent) = &item.consent_request {
         println!("consent?> {}", consent.human_summary);
+        for line in consent.preview_diff.iter().flat_map(|diff| diff.lines()) {
+            println!("diff> {line}");
+        }
+    }
+    for evt in item.executed_action_events.iter().filter(|evt| evt.status == "simulated") {
+        println!("simulated> {}", evt.evidence_summary.as_deref().unwrap_or(&evt.tool_name));
+        for line in evt.preview_diff.iter().flat_map(|diff| diff.lines()) {
+            println!("diff> {line}");
+        }
     }
     let proposed = item
         .proposed_actions
@@ -1294,6 +2778,11 @@ fn compact_status(status: &str) -> &str {
     }
 }
 
+/// `http://<addr>/jsonrpc`, or the pipe path for a `pipe:` address.
+fn endpoint_label(addr: &str) -> String {
+    pipe::pipe_path(addr).unwrap_or_else(|| format!("http://{addr}/jsonrpc"))
+}
+
 fn call_http_jsonrpc(addr: &str, method: &str, params: Value) -> io::Result<WireResponse> {
     let body = serde_json::to_string(&json!({
         "jsonrpc": "2.0",
@@ -1303,6 +2792,12 @@ fn call_http_jsonrpc(addr: &str, method: &str, params: Value) -> io::Result<Wire
     }))
     .map_err(|err| io::Error::other(format!("serialize request: {err}")))?;
 
+    if let Some(path) = pipe::pipe_path(addr) {
+        let raw = pipe::call(&path, &body)?;
+        return serde_json::from_str::<WireResponse>(&raw)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("invalid json-rpc response: {err}")));
+    }
+
     let mut stream = TcpStream::connect(addr)?;
     let request = format!(
         "POST /jsonrpc HTTP/1.1\r\nHost: {addr}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
@@ -1336,35 +2831,166 @@ fn call_http_jsonrpc(addr: &str, method: &str, params: Value) -> io::Result<Wire
 }
 
 fn serve_http_jsonrpc(addr: &str) -> io::Result<()> {
-    let listener = TcpListener::bind(addr)?;
+    if let Some(path) = pipe::pipe_path(addr) {
+        return serve_pipe_jsonrpc(&path);
+    }
+    serve_http_listener(TcpListener::bind(addr)?)
+}
+
+/// HTTP serve loop on an already bound listener, e.g. one inherited from systemd.
+fn serve_http_listener(listener: TcpListener) -> io::Result<()> {
+    let addr = listener.local_addr()?;
     eprintln!("listening on http://{addr}/jsonrpc");
+    tracing::info!(%addr, "http json-rpc server listening");
+
+    let mut server = new_served_backend("ipc-http");
+    // Non-blocking accept so parked `events.subscribe` long-polls can be re-checked between
+    // connections; the server itself still handles one request at a time.
+    listener.set_nonblocking(true)?;
+    let mut parked: Vec<ParkedSubscription> = Vec::new();
+    let mut last_supervise = std::time::Instant::now();
+    let mut last_task_check = std::time::Instant::now();
+
+    while !stop_requested(&server) {
+        match listener.accept() {
+            Ok((mut stream, _)) => {
+                let _ = stream.set_nonblocking(false);
+                match handle_http_connection(&mut stream, &mut server) {
+                    Ok(Some(subscription)) => parked.push(subscription),
+                    Ok(None) => {}
+                    Err(err) => {
+                        let _ = write_http_error(&mut stream, 500, "Internal Server Error", &format!("{err}"));
+                    }
+                }
+            }
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                std::thread::sleep(LONG_POLL_TICK);
+            }
+            Err(err) => eprintln!("accept error: {err}"),
+        }
+        if !parked.is_empty() {
+            flush_parked_subscriptions(&mut parked, &mut server);
+        }
+        run_background_work(&mut server, &mut last_supervise, &mut last_task_check);
+    }
 
-    let service = AgentService::new_for_platform("ipc-http");
-    let mut server = JsonRpcServer::new(service);
+    tracing::info!("http json-rpc server shutting down");
+    drop(listener);
+    // Parked long-polls get whatever they have now rather than a dropped connection.
+    for sub in parked.iter_mut() {
+        sub.deadline = std::time::Instant::now();
+    }
+    flush_parked_subscriptions(&mut parked, &mut server);
+    server.service_mut().shutdown();
+    Ok(())
+}
 
-    for stream in listener.incoming() {
-        let mut stream = match stream {
-            Ok(s) => s,
-            Err(err) => {
-                eprintln!("accept error: {err}");
-                continue;
+/// Named-pipe variant of `serve_http_jsonrpc`. Pipe clients send one frame at a time, so an
+/// `events.subscribe` long-poll is answered immediately rather than parked.
+fn serve_pipe_jsonrpc(path: &str) -> io::Result<()> {
+    let requests = pipe::listen(path)?;
+    eprintln!("listening on {path}");
+    tracing::info!(%path, "named pipe json-rpc server listening");
+
+    let mut server = new_served_backend("ipc-pipe");
+    let mut last_supervise = std::time::Instant::now();
+    let mut last_task_check = std::time::Instant::now();
+    while !stop_requested(&server) {
+        match requests.recv_timeout(LONG_POLL_TICK) {
+            Ok(request) => {
+                let _ = request.reply.send(server.handle_json(&request.frame));
             }
-        };
-
-        if let Err(err) = handle_http_connection(&mut stream, &mut server) {
-            let _ = write_http_error(&mut stream, 500, "Internal Server Error", &format!("{err}"));
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
         }
+        run_background_work(&mut server, &mut last_supervise, &mut last_task_check);
     }
 
+    tracing::info!("named pipe json-rpc server shutting down");
+    server.service_mut().shutdown();
     Ok(())
 }
 
-fn handle_http_connection(stream: &mut TcpStream, server: &mut JsonRpcServer<AgentService>) -> io::Result<()> {
+/// MCP supervision and due scheduled tasks, between requests of a long-running server.
+fn run_background_work(
+    server: &mut JsonRpcServer<AgentService>,
+    last_supervise: &mut std::time::Instant,
+    last_task_check: &mut std::time::Instant,
+) {
+    if last_supervise.elapsed() >= MCP_SUPERVISE_INTERVAL {
+        *last_supervise = std::time::Instant::now();
+        if let Err(err) = server.service().supervise_mcp_servers() {
+            tracing::warn!(error = %err, "mcp supervision failed");
+        }
+    }
+    if last_task_check.elapsed() >= TASK_SCHEDULER_INTERVAL {
+        *last_task_check = std::time::Instant::now();
+        if let Err(err) = server.service_mut().run_due_tasks() {
+            tracing::warn!(error = %err, "scheduled task run failed");
+        }
+    }
+}
+
+const LONG_POLL_TICK: std::time::Duration = std::time::Duration::from_millis(25);
+const LONG_POLL_MAX: std::time::Duration = std::time::Duration::from_secs(60);
+const MCP_SUPERVISE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+/// Schedules have minute resolution; checking more often only keeps runs close to the minute.
+const TASK_SCHEDULER_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// An `events.subscribe` request with `timeout_ms` that found no events yet; answered once
+/// events arrive or the deadline passes.
+struct ParkedSubscription {
+    stream: TcpStream,
+    request: Request,
+    deadline: std::time::Instant,
+}
+
+fn long_poll_deadline(method: &str, params: &Value, response: &ipc::jsonrpc::Response) -> Option<std::time::Instant> {
+    if method != "events.subscribe" {
+        return None;
+    }
+    let timeout_ms = params.get("timeout_ms").and_then(Value::as_u64).unwrap_or(0);
+    if timeout_ms == 0 {
+        return None;
+    }
+    let empty = response
+        .result_json
+        .as_deref()
+        .and_then(|raw| serde_json::from_str::<ipc::EventsSubscribeResponse>(raw).ok())
+        .map(|result| result.events.is_empty())
+        .unwrap_or(false);
+    empty.then(|| std::time::Instant::now() + std::time::Duration::from_millis(timeout_ms).min(LONG_POLL_MAX))
+}
+
+fn flush_parked_subscriptions(parked: &mut Vec<ParkedSubscription>, server: &mut JsonRpcServer<AgentService>) {
+    let now = std::time::Instant::now();
+    parked.retain_mut(|sub| {
+        let response = server.handle(sub.request.clone());
+        let ready = response
+            .result_json
+            .as_deref()
+            .and_then(|raw| serde_json::from_str::<ipc::EventsSubscribeResponse>(raw).ok())
+            .map(|result| !result.events.is_empty())
+            .unwrap_or(true);
+        if !ready && now < sub.deadline {
+            return true;
+        }
+        if let Ok(payload) = serde_json::to_string(&to_wire_response(response)) {
+            let _ = write_http_json(&mut sub.stream, 200, &payload);
+        }
+        false
+    });
+}
+
+fn handle_http_connection(
+    stream: &mut TcpStream,
+    server: &mut JsonRpcServer<AgentService>,
+) -> io::Result<Option<ParkedSubscription>> {
     let mut reader = BufReader::new(stream.try_clone()?);
 
     let mut request_line = String::new();
     if reader.read_line(&mut request_line)? == 0 {
-        return Ok(());
+        return Ok(None);
     }
     let request_line = request_line.trim_end_matches(&['\r', '\n'][..]).to_string();
     let mut parts = request_line.split_whitespace();
@@ -1386,16 +3012,21 @@ fn handle_http_connection(stream: &mut TcpStream, server: &mut JsonRpcServer<Age
         }
     }
 
+    if method == "GET" && matches!(path, "/healthz" | "/readyz") {
+        let (status, body) = health_probe(server, path == "/readyz");
+        return write_http_json(stream, status, &body).map(|_| None);
+    }
+
     if path != "/jsonrpc" {
-        return write_http_error(stream, 404, "Not Found", "Use POST /jsonrpc");
+        return write_http_error(stream, 404, "Not Found", "Use POST /jsonrpc").map(|_| None);
     }
 
     if method == "OPTIONS" {
-        return write_http_options(stream);
+        return write_http_options(stream).map(|_| None);
     }
 
     if method != "POST" {
-        return write_http_error(stream, 405, "Method Not Allowed", "Use POST /jsonrpc");
+        return write_http_error(stream, 405, "Method Not Allowed", "Use POST /jsonrpc").map(|_| None);
     }
 
     let mut body = vec![0u8; content_length];
@@ -1403,40 +3034,53 @@ fn handle_http_connection(stream: &mut TcpStream, server: &mut JsonRpcServer<Age
     let body_str = String::from_utf8(body)
         .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("utf8 body: {err}")))?;
 
-    let wire_req = match parse_wire_request(&body_str) {
-        Ok(req) => req,
-        Err(err) => {
-            let payload = serde_json::to_string(&WireResponse {
-                jsonrpc: "2.0".to_string(),
-                id: Value::Null,
-                result: None,
-                error: Some(WireError {
-                    code: -32700,
-                    message: format!("parse error: {err}"),
-                }),
-            })
-            .map_err(|e| io::Error::other(format!("serialize parse error response: {e}")))?;
-            return write_http_json(stream, 200, &payload);
-        }
+    let payload = match jsonrpc::parse_payload(&body_str) {
+        Ok(payload) => payload,
+        Err(response) => return write_http_json(stream, 200, &response.to_json().to_string()).map(|_| None),
     };
+    // Only a lone request (not a batch or notification) may long-poll.
+    if let Payload::Single(Ok(call)) = &payload {
+        if call.id.is_some() {
+            let request = call.clone().into_request();
+            let response = server.handle(request.clone());
+            if let Some(deadline) = long_poll_deadline(&call.method, &call.params, &response) {
+                return Ok(Some(ParkedSubscription {
+                    stream: stream.try_clone()?,
+                    request,
+                    deadline,
+                }));
+            }
+            return write_http_json(stream, 200, &response.to_json().to_string()).map(|_| None);
+        }
+    }
+    match server.handle_payload(payload) {
+        Some(reply) => write_http_json(stream, 200, &reply.to_string()),
+        None => write_http_json(stream, 204, ""),
+    }
+    .map(|_| None)
+}
 
-    let raw_req = Request::new(
-        json_value_to_id(wire_req.id.unwrap_or(Value::Null)),
-        wire_req.method,
-        wire_req.params.to_string(),
-    );
-    let wire_resp = to_wire_response(server.handle(raw_req));
-    let payload = serde_json::to_string(&wire_resp)
-        .map_err(|err| io::Error::other(format!("serialize response: {err}")))?;
-    write_http_json(stream, 200, &payload)
+/// `system.health` for `GET /healthz` and `/readyz`. Ready means the backend has loaded its
+/// state and is serving (503 if the summary can't be built); healthy additionally requires no
+/// warnings, so a missing provider key or a dead MCP server turns `/healthz` into a 503.
+fn health_probe(server: &JsonRpcServer<AgentService>, readiness: bool) -> (u16, String) {
+    match server.service().system_health() {
+        Ok(health) => {
+            let status = if readiness || health.ok { 200 } else { 503 };
+            (status, serde_json::to_string(&health).unwrap_or_default())
+        }
+        Err(err) => (503, json!({ "ok": false, "error": err }).to_string()),
+    }
 }
 
 fn write_http_json(stream: &mut TcpStream, status: u16, body: &str) -> io::Result<()> {
     let status_text = match status {
         200 => "OK",
+        204 => "No Content",
         404 => "Not Found",
         405 => "Method Not Allowed",
         500 => "Internal Server Error",
+        503 => "Service Unavailable",
         _ => "OK",
     };
     let response = format!(
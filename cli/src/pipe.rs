@@ -0,0 +1,188 @@
+//! Windows named-pipe transport. `--addr pipe:<name>` serves and calls JSON-RPC over
+//! `\\.\pipe\<name>` with the same `Content-Length` frames as `serve-stdio`, so a local
+//! daemon or service needs no TCP port. Pipes reject remote clients.
+
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::sync::mpsc;
+
+use ipc::mcp;
+
+pub const PREFIX: &str = "pipe:";
+
+/// A frame read from a pipe client; the serve loop sends back the response payload, or
+/// `None` for a notification.
+pub struct PipeRequest {
+    pub frame: String,
+    pub reply: mpsc::Sender<Option<String>>,
+}
+
+/// `pipe:cmnd-n-ctrl` and `pipe:\\.\pipe\cmnd-n-ctrl` both name `\\.\pipe\cmnd-n-ctrl`.
+pub fn pipe_path(addr: &str) -> Option<String> {
+    let name = addr.strip_prefix(PREFIX)?;
+    Some(if name.starts_with(r"\\") {
+        name.to_string()
+    } else {
+        format!(r"\\.\pipe\{name}")
+    })
+}
+
+#[cfg(not(windows))]
+fn unsupported() -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, "named pipes are only available on Windows")
+}
+
+/// Sends one request frame and returns the response frame.
+pub fn call(path: &str, payload: &str) -> io::Result<String> {
+    let pipe = open(path)?;
+    mcp::write_stdio_frame_to(&mut &pipe, payload)?;
+    mcp::read_stdio_frame_from(&mut BufReader::new(&pipe))?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "pipe closed before responding"))
+}
+
+#[cfg(windows)]
+fn open(path: &str) -> io::Result<File> {
+    use std::time::{Duration, Instant};
+    const ERROR_PIPE_BUSY: i32 = 231;
+    const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+    // Every instance is busy while the server hands the last one to a connection thread.
+    let deadline = Instant::now() + BUSY_TIMEOUT;
+    loop {
+        match File::options().read(true).write(true).open(path) {
+            Err(err) if err.raw_os_error() == Some(ERROR_PIPE_BUSY) && Instant::now() < deadline => {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn open(_path: &str) -> io::Result<File> {
+    Err(unsupported())
+}
+
+/// Whether a server currently listens on `path`.
+#[cfg(windows)]
+pub fn is_listening(path: &str) -> bool {
+    use windows_sys::Win32::System::Pipes::WaitNamedPipeW;
+    let wide = windows::wide(path);
+    unsafe { WaitNamedPipeW(wide.as_ptr(), 200) != 0 }
+}
+
+#[cfg(not(windows))]
+pub fn is_listening(_path: &str) -> bool {
+    false
+}
+
+/// Creates the pipe and accepts clients on a background thread; every frame they send
+/// arrives on the returned channel. Fails if another server already owns `path`.
+#[cfg(windows)]
+pub fn listen(path: &str) -> io::Result<mpsc::Receiver<PipeRequest>> {
+    let first = windows::create_instance(path, true)?;
+    let (tx, rx) = mpsc::channel();
+    let path = path.to_string();
+    std::thread::spawn(move || {
+        let mut next = Ok(first);
+        loop {
+            let pipe = match next.and_then(windows::connect) {
+                Ok(pipe) => pipe,
+                Err(err) => {
+                    tracing::warn!(error = %err, "named pipe accept failed");
+                    break;
+                }
+            };
+            let tx = tx.clone();
+            std::thread::spawn(move || serve_connection(pipe, tx));
+            next = windows::create_instance(&path, false);
+        }
+    });
+    Ok(rx)
+}
+
+#[cfg(not(windows))]
+pub fn listen(_path: &str) -> io::Result<mpsc::Receiver<PipeRequest>> {
+    Err(unsupported())
+}
+
+#[cfg(windows)]
+fn serve_connection(pipe: File, requests: mpsc::Sender<PipeRequest>) {
+    let mut reader = BufReader::new(&pipe);
+    while let Ok(Some(frame)) = mcp::read_stdio_frame_from(&mut reader) {
+        let (reply, response) = mpsc::channel();
+        if requests.send(PipeRequest { frame, reply }).is_err() {
+            break;
+        }
+        match response.recv() {
+            Ok(Some(payload)) => {
+                if mcp::write_stdio_frame_to(&mut &pipe, &payload).is_err() {
+                    break;
+                }
+            }
+            Ok(None) => {}
+            Err(_) => break,
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::fs::File;
+    use std::io;
+    use std::os::windows::io::FromRawHandle;
+
+    use windows_sys::Win32::Foundation::{CloseHandle, ERROR_PIPE_CONNECTED, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::Storage::FileSystem::{FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX};
+    use windows_sys::Win32::System::Pipes::{
+        ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE,
+        PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+    };
+
+    const BUFFER_SIZE: u32 = 64 * 1024;
+
+    pub fn wide(text: &str) -> Vec<u16> {
+        text.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    /// One pipe instance with the default security descriptor: full access for the creating
+    /// user, SYSTEM and administrators, read-only for everyone else.
+    pub fn create_instance(path: &str, first: bool) -> io::Result<isize> {
+        let mut open_mode = PIPE_ACCESS_DUPLEX;
+        if first {
+            open_mode |= FILE_FLAG_FIRST_PIPE_INSTANCE;
+        }
+        let handle = unsafe {
+            CreateNamedPipeW(
+                wide(path).as_ptr(),
+                open_mode,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                BUFFER_SIZE,
+                BUFFER_SIZE,
+                0,
+                std::ptr::null(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            let err = io::Error::last_os_error();
+            return Err(io::Error::new(err.kind(), format!("failed to create {path}: {err}")));
+        }
+        Ok(handle as isize)
+    }
+
+    /// Blocks until a client connects to the instance.
+    pub fn connect(handle: isize) -> io::Result<File> {
+        let handle = handle as windows_sys::Win32::Foundation::HANDLE;
+        if unsafe { ConnectNamedPipe(handle, std::ptr::null_mut()) } == 0 {
+            let err = io::Error::last_os_error();
+            // The client connected between create and connect.
+            if err.raw_os_error() != Some(ERROR_PIPE_CONNECTED as i32) {
+                unsafe { CloseHandle(handle) };
+                return Err(err);
+            }
+        }
+        Ok(unsafe { File::from_raw_handle(handle) })
+    }
+}
+
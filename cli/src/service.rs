@@ -0,0 +1,327 @@
+//! `cli service`: runs `serve-http` under the platform's service manager.
+//!
+//! On Windows `install` registers an auto-start service with `sc.exe` whose command line is
+//! `cli --state-dir <dir> serve-http --addr <addr> --windows-service <name>`; the state dir is
+//! the installing user's, so the service and the cli share sessions and settings.
+//!
+//! On Linux `generate-unit` prints a hardened systemd unit with the same command line, and
+//! optionally a `.socket` unit whose listener `serve-http --systemd-socket` inherits.
+
+use std::env;
+use std::io;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::Command;
+
+use agent::AgentService;
+
+pub const DEFAULT_SERVICE_NAME: &str = "cmnd-n-ctrl";
+const DISPLAY_NAME: &str = "cmnd-n-ctrl agent";
+const DESCRIPTION: &str = "cmnd-n-ctrl JSON-RPC backend (cli serve-http)";
+
+fn sc(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("sc.exe")
+        .args(args)
+        .output()
+        .map_err(|err| format!("failed to run sc.exe: {err}"))?;
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.status.success() {
+        Ok(text)
+    } else {
+        Err(format!("sc.exe {} failed: {text}", args.first().copied().unwrap_or_default()))
+    }
+}
+
+/// Registers and starts the service. Needs an elevated prompt.
+pub fn install(name: &str, addr: &str) -> Result<String, String> {
+    if !cfg!(windows) {
+        return Err("service install is only available on Windows".to_string());
+    }
+    let exe = env::current_exe().map_err(|err| format!("failed to locate cli binary: {err}"))?;
+    let state_dir = AgentService::default_storage_dir();
+    let bin_path = format!(
+        "\"{}\" --state-dir \"{}\" serve-http --addr {addr} --windows-service {name}",
+        exe.display(),
+        state_dir.display()
+    );
+    sc(&["create", name, "binPath=", &bin_path, "start=", "auto", "DisplayName=", DISPLAY_NAME])?;
+    sc(&["description", name, DESCRIPTION])?;
+    sc(&["start", name])?;
+    Ok(bin_path)
+}
+
+/// Stops the service if it runs, then removes it.
+pub fn uninstall(name: &str) -> Result<(), String> {
+    if !cfg!(windows) {
+        return Err("service uninstall is only available on Windows".to_string());
+    }
+    // Already stopped is fine; `delete` reports a missing service.
+    let _ = sc(&["stop", name]);
+    sc(&["delete", name]).map(drop)
+}
+
+/// Options for `generate-unit`.
+pub struct SystemdUnitOptions {
+    pub name: String,
+    pub addr: String,
+    /// Also emit `<name>.socket` and let systemd own the listening socket.
+    pub socket: bool,
+    /// `User=` of the service; the unit runs as root when unset.
+    pub user: Option<String>,
+    /// Extra writable paths besides the state dir, e.g. project roots the agent edits.
+    pub read_write_paths: Vec<PathBuf>,
+}
+
+/// The generated unit files: `<name>.service` and, with `socket`, `<name>.socket`.
+pub struct SystemdUnits {
+    pub service: String,
+    pub socket: Option<String>,
+}
+
+/// Renders the units for the current binary and state dir. The service may only write to
+/// the state dir and `read_write_paths`; the rest of the filesystem is read-only to it.
+pub fn systemd_units(options: &SystemdUnitOptions) -> Result<SystemdUnits, String> {
+    let exe = env::current_exe().map_err(|err| format!("failed to locate cli binary: {err}"))?;
+    let state_dir = AgentService::default_storage_dir();
+    let SystemdUnitOptions {
+        name,
+        addr,
+        socket,
+        user,
+        read_write_paths,
+    } = options;
+    let listen = if *socket {
+        "--systemd-socket".to_string()
+    } else {
+        format!("--addr {addr}")
+    };
+    let read_write = std::iter::once(state_dir.as_path())
+        .chain(read_write_paths.iter().map(PathBuf::as_path))
+        .map(|path| quote_unit_arg(&path.display().to_string()))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut service = String::new();
+    service.push_str("[Unit]\n");
+    service.push_str(&format!("Description={DESCRIPTION}\n"));
+    service.push_str("After=network.target\n");
+    if *socket {
+        service.push_str(&format!("Requires={name}.socket\nAfter={name}.socket\n"));
+    }
+    service.push_str("\n[Service]\nType=simple\n");
+    service.push_str(&format!(
+        "ExecStart={} --state-dir {} serve-http {listen}\n",
+        quote_unit_arg(&exe.display().to_string()),
+        quote_unit_arg(&state_dir.display().to_string())
+    ));
+    if let Some(user) = user {
+        service.push_str(&format!("User={user}\n"));
+    }
+    service.push_str("Restart=on-failure\nRestartSec=2\n");
+    service.push_str(
+        "NoNewPrivileges=yes\n\
+         PrivateTmp=yes\n\
+         PrivateDevices=yes\n\
+         ProtectSystem=strict\n\
+         ProtectHome=read-only\n",
+    );
+    service.push_str(&format!("ReadWritePaths={read_write}\n"));
+    service.push_str(
+        "ProtectKernelTunables=yes\n\
+         ProtectKernelModules=yes\n\
+         ProtectKernelLogs=yes\n\
+         ProtectControlGroups=yes\n\
+         ProtectClock=yes\n\
+         RestrictNamespaces=yes\n\
+         RestrictRealtime=yes\n\
+         RestrictSUIDSGID=yes\n\
+         LockPersonality=yes\n\
+         RestrictAddressFamilies=AF_UNIX AF_INET AF_INET6\n\
+         SystemCallArchitectures=native\n\
+         CapabilityBoundingSet=\n",
+    );
+    service.push_str("\n[Install]\nWantedBy=multi-user.target\n");
+
+    let socket = socket.then(|| {
+        format!(
+            "[Unit]\nDescription={DESCRIPTION} socket\n\n[Socket]\nListenStream={addr}\n\n[Install]\nWantedBy=sockets.target\n"
+        )
+    });
+    Ok(SystemdUnits { service, socket })
+}
+
+/// systemd splits `ExecStart=` and `ReadWritePaths=` on whitespace unless the word is quoted.
+fn quote_unit_arg(arg: &str) -> String {
+    if arg.contains(char::is_whitespace) || arg.contains('"') {
+        format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        arg.to_string()
+    }
+}
+
+/// First descriptor passed by systemd socket activation (`SD_LISTEN_FDS_START`).
+#[cfg(unix)]
+const SD_LISTEN_FDS_START: i32 = 3;
+
+/// Takes the listening socket systemd passed to this process (`LISTEN_PID`/`LISTEN_FDS`).
+/// The variables are cleared so children such as MCP servers do not claim the socket too.
+#[cfg(unix)]
+pub fn systemd_listener() -> io::Result<TcpListener> {
+    use std::os::fd::FromRawFd;
+
+    let not_activated = |reason: &str| {
+        io::Error::new(io::ErrorKind::NotFound, format!("not started by systemd socket activation: {reason}"))
+    };
+    let pid = env::var("LISTEN_PID").map_err(|_| not_activated("LISTEN_PID is unset"))?;
+    if pid.trim().parse::<u32>().ok() != Some(std::process::id()) {
+        return Err(not_activated("LISTEN_PID names another process"));
+    }
+    let fds = env::var("LISTEN_FDS")
+        .ok()
+        .and_then(|fds| fds.trim().parse::<i32>().ok())
+        .unwrap_or(0);
+    env::remove_var("LISTEN_PID");
+    env::remove_var("LISTEN_FDS");
+    env::remove_var("LISTEN_FDNAMES");
+    match fds {
+        0 => Err(not_activated("LISTEN_FDS is 0")),
+        // The socket unit owns the descriptor for the service's whole lifetime.
+        1 => Ok(unsafe { TcpListener::from_raw_fd(SD_LISTEN_FDS_START) }),
+        n => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("expected one socket from systemd, got {n}"),
+        )),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn systemd_listener() -> io::Result<TcpListener> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "--systemd-socket is only available on Unix",
+    ))
+}
+
+/// Runs `serve` as the service `name` under the service control manager; a stop or system
+/// shutdown request raises the same flag as Ctrl+C. Only returns once the service stopped.
+#[cfg(windows)]
+pub fn run_as_windows_service(
+    name: &str,
+    serve: impl Fn() -> std::io::Result<()> + Send + Sync + 'static,
+) -> std::io::Result<()> {
+    windows::run(name, Box::new(serve))
+}
+
+#[cfg(not(windows))]
+pub fn run_as_windows_service(
+    _name: &str,
+    _serve: impl Fn() -> std::io::Result<()> + Send + Sync + 'static,
+) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "--windows-service is only available on Windows",
+    ))
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::ffi::c_void;
+    use std::io;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::OnceLock;
+
+    use windows_sys::core::PWSTR;
+    use windows_sys::Win32::Foundation::{ERROR_CALL_NOT_IMPLEMENTED, NO_ERROR};
+    use windows_sys::Win32::System::Services::{
+        RegisterServiceCtrlHandlerExW, SetServiceStatus, StartServiceCtrlDispatcherW, SERVICE_ACCEPT_SHUTDOWN,
+        SERVICE_ACCEPT_STOP, SERVICE_CONTROL_INTERROGATE, SERVICE_CONTROL_SHUTDOWN, SERVICE_CONTROL_STOP,
+        SERVICE_RUNNING, SERVICE_STATUS, SERVICE_STATUS_HANDLE, SERVICE_STOPPED, SERVICE_STOP_PENDING,
+        SERVICE_TABLE_ENTRYW, SERVICE_WIN32_OWN_PROCESS,
+    };
+
+    type Serve = Box<dyn Fn() -> io::Result<()> + Send + Sync>;
+
+    // The dispatcher calls plain functions, so the service's state lives in statics.
+    static NAME: OnceLock<Vec<u16>> = OnceLock::new();
+    static SERVE: OnceLock<Serve> = OnceLock::new();
+    static STATUS_HANDLE: AtomicUsize = AtomicUsize::new(0);
+
+    pub fn run(name: &str, serve: Serve) -> io::Result<()> {
+        let name = NAME.get_or_init(|| name.encode_utf16().chain(std::iter::once(0)).collect());
+        let _ = SERVE.set(serve);
+        let table = [
+            SERVICE_TABLE_ENTRYW {
+                lpServiceName: name.as_ptr() as PWSTR,
+                lpServiceProc: Some(service_main),
+            },
+            SERVICE_TABLE_ENTRYW {
+                lpServiceName: std::ptr::null_mut(),
+                lpServiceProc: None,
+            },
+        ];
+        if unsafe { StartServiceCtrlDispatcherW(table.as_ptr()) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn report(state: u32, exit_code: u32) {
+        let handle = STATUS_HANDLE.load(Ordering::SeqCst) as SERVICE_STATUS_HANDLE;
+        if handle.is_null() {
+            return;
+        }
+        let status = SERVICE_STATUS {
+            dwServiceType: SERVICE_WIN32_OWN_PROCESS,
+            dwCurrentState: state,
+            dwControlsAccepted: if state == SERVICE_RUNNING {
+                SERVICE_ACCEPT_STOP | SERVICE_ACCEPT_SHUTDOWN
+            } else {
+                0
+            },
+            dwWin32ExitCode: exit_code,
+            dwServiceSpecificExitCode: 0,
+            dwCheckPoint: 0,
+            dwWaitHint: if state == SERVICE_STOP_PENDING { 10_000 } else { 0 },
+        };
+        unsafe { SetServiceStatus(handle, &status) };
+    }
+
+    unsafe extern "system" fn service_main(_argc: u32, _argv: *mut PWSTR) {
+        let Some(name) = NAME.get() else {
+            return;
+        };
+        let handle = RegisterServiceCtrlHandlerExW(name.as_ptr(), Some(control_handler), std::ptr::null());
+        if handle.is_null() {
+            tracing::error!(error = %io::Error::last_os_error(), "failed to register service control handler");
+            return;
+        }
+        STATUS_HANDLE.store(handle as usize, Ordering::SeqCst);
+        report(SERVICE_RUNNING, NO_ERROR);
+        let exit_code = match SERVE.get().map(|serve| serve()) {
+            Some(Ok(())) => NO_ERROR,
+            Some(Err(err)) => {
+                tracing::error!(error = %err, "service stopped with an error");
+                err.raw_os_error().map_or(1, |code| code as u32)
+            }
+            None => 1,
+        };
+        report(SERVICE_STOPPED, exit_code);
+    }
+
+    unsafe extern "system" fn control_handler(
+        control: u32,
+        _event_type: u32,
+        _event_data: *mut c_void,
+        _context: *mut c_void,
+    ) -> u32 {
+        match control {
+            SERVICE_CONTROL_STOP | SERVICE_CONTROL_SHUTDOWN => {
+                crate::request_shutdown();
+                report(SERVICE_STOP_PENDING, NO_ERROR);
+                NO_ERROR
+            }
+            SERVICE_CONTROL_INTERROGATE => NO_ERROR,
+            _ => ERROR_CALL_NOT_IMPLEMENTED,
+        }
+    }
+}
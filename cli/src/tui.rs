@@ -1,20 +1,27 @@
+use crate::editor::LineEditor;
+use agent::orchestrator::StreamEvent;
 use agent::AgentService;
-use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+    MouseEventKind,
+};
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
 use crossterm::execute;
 use ipc::jsonrpc::{Id, Request};
-use ipc::{AuditEntry, ChatMessage, ChatMode, ChatRequest, ChatResponse, ExecutionFeedItem, JsonRpcClient, PendingConsentRecord, Session, SessionSummary};
+use ipc::{AuditEntry, ChatMessage, ChatMode, ChatRequest, ChatResponse, EventsSubscribeResponse, ExecutionFeedItem, JsonRpcClient, ListPage, McpServerMutationResponse, McpServerRecord, PendingConsentRecord, ProjectRecentResponse, ProviderInfo, ProviderModelsListResponse, Session, SessionSummary, ToolStatsResponse};
 use ratatui::backend::CrosstermBackend;
 use ratatui::layout::{Constraint, Direction, Layout};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap};
 use ratatui::layout::Rect;
 use ratatui::{Frame, Terminal};
 use serde::de::DeserializeOwned;
-use serde_json::json;
+use serde_json::{json, Map, Value};
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
 use std::io::stdout;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum FocusPane {
@@ -22,6 +29,7 @@ enum FocusPane {
     Chat,
     Consents,
     Audit,
+    Mcp,
 }
 
 impl FocusPane {
@@ -30,7 +38,8 @@ impl FocusPane {
             Self::Sessions => Self::Chat,
             Self::Chat => Self::Consents,
             Self::Consents => Self::Audit,
-            Self::Audit => Self::Sessions,
+            Self::Audit => Self::Mcp,
+            Self::Mcp => Self::Sessions,
         }
     }
 }
@@ -43,15 +52,86 @@ struct TuiApp {
     selected_consent: usize,
     audits: Vec<AuditEntry>,
     selected_audit: usize,
-    chat_input: String,
+    mcp_servers: Vec<McpServerRecord>,
+    selected_mcp: usize,
+    input: LineEditor,
     status: String,
     focus: FocusPane,
     require_confirmation: bool,
     provider_name: String,
+    model: Option<String>,
+    provider_picker: Option<ProviderPicker>,
+    /// Temperature, top_p, max_tokens and stop overrides for every request.
+    generation: ipc::ProviderConfig,
     last_chat_response: Option<ChatResponse>,
     feed: Vec<ExecutionFeedItem>,
     selected_execution: usize,
     show_execution_details: bool,
+    feed_scroll: u16,
+    feed_max_scroll: Cell<u16>,
+    pane_hits: RefCell<Vec<PaneHit>>,
+    search_mode: bool,
+    search_query: String,
+    consent_detail: Option<ConsentDetail>,
+    audit_detail: Option<AuditDetail>,
+    tool_stats: Option<ToolStatsView>,
+    event_cursor: u64,
+    last_event_sync: Instant,
+    /// Address of the attached `serve-http` backend, shown in the status line.
+    attached_to: Option<String>,
+}
+
+/// Screen area of a pane as last rendered, for mapping mouse clicks. `item_heights` is empty for
+/// panes without selectable rows; `offset` is the list's first visible item.
+struct PaneHit {
+    pane: FocusPane,
+    area: Rect,
+    offset: usize,
+    item_heights: Vec<u16>,
+}
+
+impl PaneHit {
+    /// Item under screen row `y`, skipping the pane's top border.
+    fn row_at(&self, y: u16) -> Option<usize> {
+        let mut top = self.area.y + 1;
+        for (idx, height) in self.item_heights.iter().enumerate().skip(self.offset) {
+            if y >= top && y < top + height {
+                return Some(idx);
+            }
+            top += height;
+        }
+        None
+    }
+}
+
+struct AuditDetail {
+    entry: AuditEntry,
+    scroll: u16,
+}
+
+/// `tools.stats` popup; `r` fetches a fresh snapshot.
+struct ToolStatsView {
+    stats: ToolStatsResponse,
+    scroll: u16,
+}
+
+/// Provider/model switcher popup. `models` is populated once a provider has been chosen; its
+/// first row always means "provider default".
+struct ProviderPicker {
+    providers: Vec<ProviderInfo>,
+    selected: usize,
+    models: Option<Vec<String>>,
+    model_selected: usize,
+}
+
+/// Detail popup for the selected consent; `edits` holds per-action argument overrides that are
+/// sent with `consent.approve_with_args`.
+#[derive(Default)]
+struct ConsentDetail {
+    action: usize,
+    field: usize,
+    editing: Option<String>,
+    edits: BTreeMap<usize, Map<String, Value>>,
 }
 
 fn pane_title(base: &str, focused: bool) -> String {
@@ -80,15 +160,31 @@ impl TuiApp {
             selected_consent: 0,
             audits: Vec::new(),
             selected_audit: 0,
-            chat_input: String::new(),
+            mcp_servers: Vec::new(),
+            selected_mcp: 0,
+            input: LineEditor::default(),
             status: "Ready".to_string(),
             focus: FocusPane::Chat,
             require_confirmation: true,
             provider_name: "openai-stub".to_string(),
+            model: None,
+            provider_picker: None,
+            generation: ipc::ProviderConfig::default(),
             last_chat_response: None,
             feed: Vec::new(),
             selected_execution: 0,
             show_execution_details: false,
+            feed_scroll: 0,
+            feed_max_scroll: Cell::new(0),
+            pane_hits: RefCell::new(Vec::new()),
+            event_cursor: 0,
+            last_event_sync: Instant::now(),
+            attached_to: None,
+            search_mode: false,
+            search_query: String::new(),
+            consent_detail: None,
+            audit_detail: None,
+            tool_stats: None,
         }
     }
 
@@ -99,21 +195,60 @@ impl TuiApp {
     fn set_status(&mut self, s: impl Into<String>) {
         self.status = s.into();
     }
+
+    /// Feed indices that pass the active search filter, oldest first.
+    fn visible_feed_indices(&self) -> Vec<usize> {
+        let query = self.search_query.to_lowercase();
+        self.feed
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| feed_entry_matches(entry, &query))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    fn scroll_feed(&mut self, delta: i32) {
+        let max = i32::from(self.feed_max_scroll.get());
+        self.feed_scroll = (i32::from(self.feed_scroll) + delta).clamp(0, max) as u16;
+    }
 }
 
-pub fn run(client: &mut JsonRpcClient<AgentService>) -> Result<(), String> {
+const FEED_PAGE_LINES: i32 = 10;
+const EVENT_SYNC_INTERVAL: Duration = Duration::from_secs(1);
+const SPINNER: [&str; 4] = ["|", "/", "-", "\\"];
+
+fn feed_entry_matches(entry: &ExecutionFeedItem, query: &str) -> bool {
+    query.is_empty()
+        || entry
+            .user_prompt
+            .as_deref()
+            .map(|p| p.to_lowercase().contains(query))
+            .unwrap_or(false)
+        || entry.assistant_text.to_lowercase().contains(query)
+}
+
+/// Where TUI calls go: the in-process agent, or a `serve-http` backend attached with `--addr`
+/// or `/connect`, in which case the TUI shares the daemon's sessions, consents and audit log.
+pub struct Backend<'c, 's> {
+    client: &'c mut JsonRpcClient<'s, AgentService>,
+    remote: Option<String>,
+}
+
+pub fn run(client: &mut JsonRpcClient<AgentService>, remote: Option<String>) -> Result<(), String> {
     enable_raw_mode().map_err(|e| e.to_string())?;
     let mut out = stdout();
-    execute!(out, EnterAlternateScreen).map_err(|e| e.to_string())?;
+    execute!(out, EnterAlternateScreen, EnableMouseCapture).map_err(|e| e.to_string())?;
     let backend = CrosstermBackend::new(out);
     let mut terminal = Terminal::new(backend).map_err(|e| e.to_string())?;
     terminal.clear().map_err(|e| e.to_string())?;
 
     let mut app = TuiApp::new();
-    let result = run_loop(&mut terminal, client, &mut app);
+    app.attached_to = remote.clone();
+    let mut backend = Backend { client, remote };
+    let result = run_loop(&mut terminal, &mut backend, &mut app);
 
     disable_raw_mode().map_err(|e| e.to_string())?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen).map_err(|e| e.to_string())?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture).map_err(|e| e.to_string())?;
     terminal.show_cursor().map_err(|e| e.to_string())?;
 
     result
@@ -121,86 +256,608 @@ pub fn run(client: &mut JsonRpcClient<AgentService>) -> Result<(), String> {
 
 fn run_loop(
     terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
-    client: &mut JsonRpcClient<AgentService>,
+    client: &mut Backend,
     app: &mut TuiApp,
 ) -> Result<(), String> {
     refresh_all(client, app)?;
     loop {
         terminal.draw(|f| render(f, app)).map_err(|e| e.to_string())?;
-        if event::poll(Duration::from_millis(100)).map_err(|e| e.to_string())? {
-            if let Event::Key(key) = event::read().map_err(|e| e.to_string())? {
-                if key.kind != KeyEventKind::Press {
+        if !event::poll(Duration::from_millis(100)).map_err(|e| e.to_string())? {
+            if app.last_event_sync.elapsed() >= EVENT_SYNC_INTERVAL {
+                match sync_events(client, app) {
+                    Ok(()) => {}
+                    // A restarting daemon should not close the TUI; the next sync retries.
+                    Err(err) if client.remote.is_some() => app.set_status(format!("Backend unreachable: {err}")),
+                    Err(err) => return Err(err),
+                }
+            }
+        } else {
+            let key = match event::read().map_err(|e| e.to_string())? {
+                Event::Key(key) => key,
+                Event::Mouse(mouse) => {
+                    handle_mouse(app, mouse);
                     continue;
                 }
+                _ => continue,
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if let Some(detail) = app.audit_detail.as_mut() {
                 match key.code {
-                    KeyCode::Char('q') => return Ok(()),
-                    KeyCode::Tab => app.focus = app.focus.next(),
-                    KeyCode::Char('r') => {
-                        refresh_all(client, app)?;
-                    }
-                    KeyCode::Char('c') => {
-                        app.require_confirmation = !app.require_confirmation;
-                        app.set_status(format!(
-                            "Require confirmation: {}",
-                            if app.require_confirmation { "on" } else { "off" }
-                        ));
-                    }
-                    KeyCode::Char('n') => {
-                        create_session(client, app)?;
-                    }
-                    KeyCode::Char('x') if app.focus == FocusPane::Sessions => {
-                        delete_selected_session(client, app)?;
+                    KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => app.audit_detail = None,
+                    KeyCode::Down | KeyCode::Char('j') => detail.scroll = detail.scroll.saturating_add(1),
+                    KeyCode::Up | KeyCode::Char('k') => detail.scroll = detail.scroll.saturating_sub(1),
+                    KeyCode::PageDown => detail.scroll = detail.scroll.saturating_add(FEED_PAGE_LINES as u16),
+                    KeyCode::PageUp => detail.scroll = detail.scroll.saturating_sub(FEED_PAGE_LINES as u16),
+                    KeyCode::Home => detail.scroll = 0,
+                    _ => {}
+                }
+                continue;
+            }
+            if let Some(view) = app.tool_stats.as_mut() {
+                match key.code {
+                    KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => app.tool_stats = None,
+                    KeyCode::Down | KeyCode::Char('j') => view.scroll = view.scroll.saturating_add(1),
+                    KeyCode::Up | KeyCode::Char('k') => view.scroll = view.scroll.saturating_sub(1),
+                    KeyCode::Char('r') => open_tool_stats(client, app)?,
+                    _ => {}
+                }
+                continue;
+            }
+            if app.provider_picker.is_some() {
+                handle_provider_picker_key(client, app, key.code)?;
+                continue;
+            }
+            if app.consent_detail.is_some() {
+                handle_consent_detail_key(client, app, key.code)?;
+                continue;
+            }
+            if app.search_mode {
+                handle_search_key(app, key.code);
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') => return Ok(()),
+                KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    app.generation.temperature = next_temperature_preset(app.generation.temperature);
+                    app.set_status(format!("Temperature: {}", temperature_label(app.generation.temperature)));
+                }
+                KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    open_provider_picker(client, app)?;
+                }
+                KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    open_next_recent_project(client, app)?;
+                }
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    open_tool_stats(client, app)?;
+                }
+                KeyCode::Tab => app.focus = app.focus.next(),
+                KeyCode::Char('r') => {
+                    refresh_all(client, app)?;
+                }
+                KeyCode::Char('c') => {
+                    app.require_confirmation = !app.require_confirmation;
+                    app.set_status(format!(
+                        "Require confirmation: {}",
+                        if app.require_confirmation { "on" } else { "off" }
+                    ));
+                    remember_session_defaults(client, app);
+                }
+                KeyCode::Char('n') => {
+                    create_session(client, app)?;
+                }
+                KeyCode::Char('x') if app.focus == FocusPane::Sessions => {
+                    delete_selected_session(client, app)?;
+                }
+                KeyCode::Char('p') if app.focus == FocusPane::Sessions => {
+                    toggle_selected_session_pin(client, app)?;
+                }
+                KeyCode::Char('a') if app.focus == FocusPane::Sessions => {
+                    archive_selected_session(client, app)?;
+                }
+                KeyCode::Char('s') if app.focus == FocusPane::Mcp => {
+                    toggle_selected_mcp_server(client, app)?;
+                }
+                KeyCode::Char('x') if app.focus == FocusPane::Mcp => {
+                    remove_selected_mcp_server(client, app)?;
+                }
+                KeyCode::Char('a') if app.focus == FocusPane::Consents => {
+                    approve_selected_consent(client, app)?;
+                }
+                KeyCode::Char('d') if app.focus == FocusPane::Consents => {
+                    deny_selected_consent(client, app)?;
+                }
+                KeyCode::Char('/') if app.focus == FocusPane::Chat && app.input.is_empty() => {
+                    app.search_mode = true;
+                    app.set_status("Search feed: type to filter, Enter keep, Esc clear");
+                }
+                KeyCode::PageDown => app.scroll_feed(FEED_PAGE_LINES),
+                KeyCode::PageUp => app.scroll_feed(-FEED_PAGE_LINES),
+                KeyCode::Home if app.focus == FocusPane::Chat && !app.input.is_empty() => {
+                    app.input.move_line_start();
+                }
+                KeyCode::End if app.focus == FocusPane::Chat && !app.input.is_empty() => {
+                    app.input.move_line_end();
+                }
+                KeyCode::Home if app.focus == FocusPane::Chat => app.feed_scroll = 0,
+                KeyCode::End if app.focus == FocusPane::Chat => app.feed_scroll = app.feed_max_scroll.get(),
+                KeyCode::Left if app.focus == FocusPane::Chat => {
+                    if key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+                        app.input.move_word_left();
+                    } else {
+                        app.input.move_left();
                     }
-                    KeyCode::Char('a') if app.focus == FocusPane::Consents => {
-                        approve_selected_consent(client, app)?;
+                }
+                KeyCode::Right if app.focus == FocusPane::Chat => {
+                    if key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+                        app.input.move_word_right();
+                    } else {
+                        app.input.move_right();
                     }
-                    KeyCode::Char('d') if app.focus == FocusPane::Consents => {
-                        deny_selected_consent(client, app)?;
+                }
+                KeyCode::Up if app.focus == FocusPane::Chat => {
+                    app.input.up();
+                }
+                KeyCode::Down if app.focus == FocusPane::Chat => {
+                    app.input.down();
+                }
+                KeyCode::Char('j') | KeyCode::Char('k')
+                    if app.focus == FocusPane::Chat && !app.input.is_empty() =>
+                {
+                    if let KeyCode::Char(ch) = key.code {
+                        app.input.insert_char(ch);
                     }
-                    KeyCode::Down | KeyCode::Char('j') => move_selection(app, 1),
-                    KeyCode::Up | KeyCode::Char('k') => move_selection(app, -1),
-                    KeyCode::Char('v') if app.focus == FocusPane::Chat => {
-                        app.show_execution_details = !app.show_execution_details;
-                        app.set_status(if app.show_execution_details {
-                            "Execution details expanded"
+                }
+                KeyCode::Down | KeyCode::Char('j') => move_selection(app, 1),
+                KeyCode::Up | KeyCode::Char('k') => move_selection(app, -1),
+                KeyCode::Char('v') if app.focus == FocusPane::Chat => {
+                    app.show_execution_details = !app.show_execution_details;
+                    app.set_status(if app.show_execution_details {
+                        "Execution details expanded"
+                    } else {
+                        "Execution details collapsed"
+                    });
+                }
+                KeyCode::Enter
+                    if app.focus == FocusPane::Chat
+                        && key.modifiers.intersects(KeyModifiers::SHIFT | KeyModifiers::ALT) =>
+                {
+                    app.input.insert_newline();
+                }
+                KeyCode::Enter => match app.focus {
+                    FocusPane::Sessions => load_selected_session(client, app)?,
+                    FocusPane::Chat => send_chat(terminal, client, app)?,
+                    FocusPane::Consents => {
+                        if app.consents.is_empty() {
+                            app.set_status("No pending consent selected");
                         } else {
-                            "Execution details collapsed"
-                        });
-                    }
-                    KeyCode::Enter => match app.focus {
-                        FocusPane::Sessions => load_selected_session(client, app)?,
-                        FocusPane::Chat => send_chat(client, app)?,
-                        FocusPane::Consents => approve_selected_consent(client, app)?,
-                        FocusPane::Audit => {}
-                    },
-                    KeyCode::Backspace if app.focus == FocusPane::Chat => {
-                        app.chat_input.pop();
+                            app.consent_detail = Some(ConsentDetail::default());
+                        }
                     }
-                    KeyCode::Esc if app.focus == FocusPane::Chat => {
-                        app.chat_input.clear();
+                    FocusPane::Audit => open_selected_audit(client, app)?,
+                    FocusPane::Mcp => toggle_selected_mcp_server(client, app)?,
+                },
+                KeyCode::Backspace if app.focus == FocusPane::Chat => {
+                    app.input.backspace();
+                }
+                KeyCode::Delete if app.focus == FocusPane::Chat => {
+                    app.input.delete();
+                }
+                KeyCode::Esc if app.focus == FocusPane::Chat => {
+                    app.input.clear();
+                }
+                KeyCode::Char(ch)
+                    if !key.modifiers.contains(KeyModifiers::CONTROL)
+                        && !key.modifiers.contains(KeyModifiers::ALT) =>
+                {
+                    if app.focus != FocusPane::Chat {
+                        app.focus = FocusPane::Chat;
                     }
-                    KeyCode::Char(ch)
-                        if !key.modifiers.contains(KeyModifiers::CONTROL)
-                            && !key.modifiers.contains(KeyModifiers::ALT) =>
-                    {
-                        if app.focus != FocusPane::Chat {
-                            app.focus = FocusPane::Chat;
+                    app.input.insert_char(ch);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn open_provider_picker(client: &mut Backend, app: &mut TuiApp) -> Result<(), String> {
+    let providers: Vec<ProviderInfo> = backend_call(client, "providers.list", json!({}))?;
+    let selected = providers
+        .iter()
+        .position(|p| p.name == app.provider_name)
+        .unwrap_or(0);
+    app.provider_picker = Some(ProviderPicker {
+        providers,
+        selected,
+        models: None,
+        model_selected: 0,
+    });
+    app.set_status("Pick a provider: Enter select, Esc close");
+    Ok(())
+}
+
+/// Cycles through `project.recent` (pinned first), opening the entry after the current project.
+fn open_next_recent_project(client: &mut Backend, app: &mut TuiApp) -> Result<(), String> {
+    let recent: ProjectRecentResponse = backend_call(client, "project.recent", json!({}))?;
+    let Some(current) = recent.projects.iter().max_by_key(|p| p.last_opened_unix_seconds) else {
+        app.set_status("No recent projects; open one with `cli project open <path>`");
+        return Ok(());
+    };
+    let position = recent.projects.iter().position(|p| p.path == current.path).unwrap_or(0);
+    let next = &recent.projects[(position + 1) % recent.projects.len()];
+    let _: Value = backend_call(client, "project.open", json!({ "path": next.path }))?;
+    let pin = if next.pinned { " (pinned)" } else { "" };
+    app.set_status(format!("Project: {}{}", next.path, pin));
+    Ok(())
+}
+
+fn handle_provider_picker_key(
+    client: &mut Backend,
+    app: &mut TuiApp,
+    code: KeyCode,
+) -> Result<(), String> {
+    let Some(picker) = app.provider_picker.as_mut() else {
+        return Ok(());
+    };
+    let rows = match &picker.models {
+        Some(models) => models.len() + 1,
+        None => picker.providers.len(),
+    };
+    let cursor = match picker.models {
+        Some(_) => &mut picker.model_selected,
+        None => &mut picker.selected,
+    };
+    match code {
+        KeyCode::Esc => {
+            if picker.models.is_some() {
+                picker.models = None;
+            } else {
+                app.provider_picker = None;
+            }
+        }
+        KeyCode::Down | KeyCode::Char('j') if rows > 0 => *cursor = (*cursor + 1).min(rows - 1),
+        KeyCode::Up | KeyCode::Char('k') => *cursor = cursor.saturating_sub(1),
+        KeyCode::Enter => {
+            if let Some(models) = &picker.models {
+                app.model = picker.model_selected.checked_sub(1).and_then(|idx| models.get(idx).cloned());
+                app.provider_picker = None;
+                app.set_status(format!(
+                    "Provider: {} model: {}",
+                    app.provider_name,
+                    app.model.as_deref().unwrap_or("default")
+                ));
+                remember_session_defaults(client, app);
+                return Ok(());
+            }
+            let Some(provider) = picker.providers.get(picker.selected).map(|p| p.name.clone()) else {
+                return Ok(());
+            };
+            if let Err(err) = backend_call::<ProviderInfo>(client, "providers.set", json!({ "provider_name": provider })) {
+                app.set_status(format!("Provider switch failed: {err}"));
+                return Ok(());
+            }
+            if provider != app.provider_name {
+                app.model = None;
+            }
+            app.provider_name = provider.clone();
+            let models = match backend_call::<ProviderModelsListResponse>(
+                client,
+                "providers.models.list",
+                json!({ "provider_name": provider }),
+            ) {
+                Ok(resp) => resp.models,
+                Err(err) => {
+                    app.set_status(format!("Models unavailable for {provider}: {err}"));
+                    Vec::new()
+                }
+            };
+            let model_selected = app
+                .model
+                .as_ref()
+                .and_then(|current| models.iter().position(|m| m == current))
+                .map(|idx| idx + 1)
+                .unwrap_or(0);
+            if let Some(picker) = app.provider_picker.as_mut() {
+                picker.models = Some(models);
+                picker.model_selected = model_selected;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn render_audit_detail(frame: &mut Frame, detail: &AuditDetail) {
+    let area = frame.area();
+    let popup = Rect {
+        x: area.x + area.width / 10,
+        y: area.y + area.height / 10,
+        width: area.width - area.width / 5,
+        height: area.height - area.height / 5,
+    };
+    let entry = &detail.entry;
+    let heading = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    let mut lines = vec![
+        Line::from(format!(
+            "{} provider={} session={} at {}",
+            entry.audit_id,
+            entry.provider,
+            entry.session_id.as_deref().unwrap_or("(none)"),
+            entry.timestamp_unix_seconds
+        )),
+    ];
+    if let Some(consent_id) = &entry.consent_id {
+        lines.push(Line::from(format!(
+            "consent={consent_id} mode={} fingerprint={}",
+            entry.mode.as_ref().map(|m| format!("{m:?}")).unwrap_or_else(|| "-".to_string()),
+            entry.request_fingerprint.as_deref().unwrap_or("-")
+        )));
+    }
+    lines.push(Line::from(""));
+    let sections = [
+        ("Proposed tool calls", &entry.proposed_tool_calls),
+        ("Policy decisions", &entry.policy_decisions),
+        ("Executed actions", &entry.executed_actions),
+        ("Evidence", &entry.evidence_summaries),
+    ];
+    for (title, items) in sections {
+        lines.push(Line::from(Span::styled(format!("{title} ({})", items.len()), heading)));
+        if items.is_empty() {
+            lines.push(Line::from("  (none)"));
+        }
+        lines.extend(items.iter().map(|item| Line::from(format!("  - {item}"))));
+        lines.push(Line::from(""));
+    }
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow))
+        .title("Audit detail (j/k PgUp/PgDn scroll, Esc close)");
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false })
+            .scroll((detail.scroll, 0)),
+        popup,
+    );
+}
+
+fn render_tool_stats(frame: &mut Frame, view: &ToolStatsView) {
+    let area = frame.area();
+    let popup = Rect {
+        x: area.x + area.width / 10,
+        y: area.y + area.height / 10,
+        width: area.width - area.width / 5,
+        height: area.height - area.height / 5,
+    };
+    let heading = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    let mut lines = vec![
+        Line::from(format!("Since {} (agent start)", view.stats.since_unix_seconds)),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("{:<32} {:>6} {:>6} {:>6} {:>7} {:>7} {:>7}", "tool", "calls", "errors", "err%", "p50ms", "p95ms", "p99ms"),
+            heading,
+        )),
+    ];
+    if view.stats.tools.is_empty() {
+        lines.push(Line::from("  (no tools have run yet)"));
+    }
+    for tool in &view.stats.tools {
+        let row = format!(
+            "{:<32} {:>6} {:>6} {:>5.1}% {:>7} {:>7} {:>7}",
+            truncate_inline(&tool.name, 29),
+            tool.calls,
+            tool.errors,
+            tool.error_rate * 100.0,
+            tool.p50_ms,
+            tool.p95_ms,
+            tool.p99_ms
+        );
+        let style = if tool.errors > 0 { Style::default().fg(Color::Red) } else { Style::default() };
+        lines.push(Line::from(Span::styled(row, style)));
+        if let Some(error) = &tool.last_error {
+            lines.push(Line::from(format!("  last error: {}", truncate_inline(error, 120))));
+        }
+    }
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow))
+        .title("Tool stats (r refresh, j/k scroll, Esc close)");
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(lines).block(block).scroll((view.scroll, 0)),
+        popup,
+    );
+}
+
+fn render_provider_picker(frame: &mut Frame, app: &TuiApp, picker: &ProviderPicker) {
+    let area = frame.area();
+    let popup = Rect {
+        x: area.x + area.width / 4,
+        y: area.y + area.height / 6,
+        width: area.width / 2,
+        height: area.height - area.height / 3,
+    };
+    let (title, items, selected) = match &picker.models {
+        Some(models) => {
+            let mut items = vec![ListItem::new("(provider default)")];
+            items.extend(models.iter().map(|m| {
+                let current = if app.model.as_deref() == Some(m.as_str()) { " *" } else { "" };
+                ListItem::new(format!("{m}{current}"))
+            }));
+            (format!("Model for {}", app.provider_name), items, picker.model_selected)
+        }
+        None => {
+            let items = picker
+                .providers
+                .iter()
+                .map(|p| {
+                    let active = if p.is_active { " *" } else { "" };
+                    let auth = if p.has_auth { "auth ok" } else { "no auth" };
+                    ListItem::new(format!("{}{active} [{auth}] {}", p.name, p.config_summary))
+                })
+                .collect::<Vec<_>>();
+            ("Provider (Enter select, Esc close)".to_string(), items, picker.selected)
+        }
+    };
+    let mut state = ListState::default().with_selected(Some(selected));
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow))
+                .title(title),
+        )
+        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut state);
+}
+
+fn handle_consent_detail_key(
+    client: &mut Backend,
+    app: &mut TuiApp,
+    code: KeyCode,
+) -> Result<(), String> {
+    let Some(consent) = app.consents.get(app.selected_consent).cloned() else {
+        app.consent_detail = None;
+        return Ok(());
+    };
+    let Some(detail) = app.consent_detail.as_mut() else {
+        return Ok(());
+    };
+    let action_count = consent.pending_actions.len();
+    let fields = editable_fields(&consent, detail.action);
+
+    if let Some(buffer) = detail.editing.as_mut() {
+        match code {
+            KeyCode::Enter => {
+                let value = std::mem::take(buffer);
+                detail.editing = None;
+                if let Some((name, original)) = fields.get(detail.field) {
+                    let edited = match original {
+                        Value::Number(_) => value.parse::<f64>().ok().and_then(|n| serde_json::Number::from_f64(n).map(Value::Number)),
+                        Value::Bool(_) => value.parse::<bool>().ok().map(Value::Bool),
+                        _ => Some(Value::String(value)),
+                    };
+                    match edited {
+                        Some(edited) => {
+                            detail.edits.entry(detail.action).or_default().insert(name.clone(), edited);
+                            app.set_status(format!("Edited '{name}'; press a to approve with edits"));
                         }
-                        app.chat_input.push(ch);
+                        None => app.set_status(format!("Invalid value for '{name}'")),
                     }
-                    _ => {}
                 }
             }
+            KeyCode::Esc => {
+                detail.editing = None;
+                app.set_status("Edit cancelled");
+            }
+            KeyCode::Backspace => {
+                buffer.pop();
+            }
+            KeyCode::Char(ch) => buffer.push(ch),
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    match code {
+        KeyCode::Esc | KeyCode::Char('q') => app.consent_detail = None,
+        KeyCode::Down | KeyCode::Char('j') if action_count > 0 => {
+            detail.action = (detail.action + 1).min(action_count - 1);
+            detail.field = 0;
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            detail.action = detail.action.saturating_sub(1);
+            detail.field = 0;
+        }
+        KeyCode::Char('f') | KeyCode::Tab if !fields.is_empty() => {
+            detail.field = (detail.field + 1) % fields.len();
+        }
+        KeyCode::Char('e') => match fields.get(detail.field) {
+            Some((name, original)) => {
+                let current = detail
+                    .edits
+                    .get(&detail.action)
+                    .and_then(|edits| edits.get(name))
+                    .unwrap_or(original);
+                detail.editing = Some(match current {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                });
+            }
+            None => app.set_status("No editable fields for this action"),
+        },
+        KeyCode::Char('a') | KeyCode::Enter => {
+            let edits = std::mem::take(&mut detail.edits);
+            app.consent_detail = None;
+            if edits.is_empty() {
+                approve_selected_consent(client, app)?;
+            } else {
+                approve_selected_consent_with_edits(client, app, &consent.consent_id, edits)?;
+            }
+        }
+        KeyCode::Char('d') => {
+            app.consent_detail = None;
+            deny_selected_consent(client, app)?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Top-level scalar arguments of a pending action, parsed from its redacted preview. Redacted or
+/// truncated previews yield no fields, so secrets and partial payloads are never editable.
+fn editable_fields(consent: &PendingConsentRecord, action: usize) -> Vec<(String, Value)> {
+    let Some(preview) = consent
+        .pending_actions
+        .get(action)
+        .and_then(|evt| evt.arguments_preview.as_deref())
+    else {
+        return Vec::new();
+    };
+    let Ok(Value::Object(args)) = serde_json::from_str::<Value>(preview) else {
+        return Vec::new();
+    };
+    args.into_iter()
+        .filter(|(_, v)| matches!(v, Value::String(_) | Value::Number(_) | Value::Bool(_)))
+        .filter(|(_, v)| v.as_str() != Some("[REDACTED]"))
+        .collect()
+}
+
+fn handle_search_key(app: &mut TuiApp, code: KeyCode) {
+    match code {
+        KeyCode::Enter => {
+            app.search_mode = false;
+            let shown = app.visible_feed_indices().len();
+            app.set_status(format!("Filter '{}': {shown} of {} entries", app.search_query, app.feed.len()));
+        }
+        KeyCode::Esc => {
+            app.search_mode = false;
+            app.search_query.clear();
+            app.set_status("Search cleared");
+        }
+        KeyCode::Backspace => {
+            app.search_query.pop();
         }
+        KeyCode::Char(ch) => app.search_query.push(ch),
+        _ => return,
+    }
+    app.feed_scroll = 0;
+    if let Some(newest) = app.visible_feed_indices().last() {
+        app.selected_execution = *newest;
     }
 }
 
 fn render(frame: &mut Frame, app: &TuiApp) {
+    app.pane_hits.borrow_mut().clear();
     let outer = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Min(10),
-            Constraint::Length(3),
+            Constraint::Length(app.input.line_count().min(6) as u16 + 2),
             Constraint::Length(1),
         ])
         .split(frame.area());
@@ -214,7 +871,7 @@ fn render(frame: &mut Frame, app: &TuiApp) {
             .direction(Direction::Horizontal)
             .constraints([Constraint::Percentage(28), Constraint::Percentage(72), Constraint::Length(0)])
             .split(outer[0]),
-        FocusPane::Consents | FocusPane::Audit => Layout::default()
+        FocusPane::Consents | FocusPane::Audit | FocusPane::Mcp => Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Length(0), Constraint::Percentage(72), Constraint::Percentage(28)])
             .split(outer[0]),
@@ -224,6 +881,7 @@ fn render(frame: &mut Frame, app: &TuiApp) {
         render_sessions(frame, columns[0], app);
     }
     render_chat(frame, columns[1], app);
+    record_pane_hit(app, FocusPane::Chat, columns[1], 0, Vec::new());
     if columns[2].width > 0 {
         render_right(frame, columns[2], app);
     }
@@ -235,7 +893,96 @@ fn render(frame: &mut Frame, app: &TuiApp) {
         height: outer[1].height,
     };
     render_input(frame, full_width_input, app);
+    record_pane_hit(app, FocusPane::Chat, full_width_input, 0, Vec::new());
     render_status(frame, outer[2], app);
+    if let Some(detail) = &app.consent_detail {
+        render_consent_detail(frame, app, detail);
+    }
+    if let Some(picker) = &app.provider_picker {
+        render_provider_picker(frame, app, picker);
+    }
+    if let Some(detail) = &app.audit_detail {
+        render_audit_detail(frame, detail);
+    }
+    if let Some(view) = &app.tool_stats {
+        render_tool_stats(frame, view);
+    }
+}
+
+fn render_consent_detail(frame: &mut Frame, app: &TuiApp, detail: &ConsentDetail) {
+    let Some(consent) = app.consents.get(app.selected_consent) else {
+        return;
+    };
+    let area = frame.area();
+    let popup = Rect {
+        x: area.x + area.width / 10,
+        y: area.y + area.height / 10,
+        width: area.width - area.width / 5,
+        height: area.height - area.height / 5,
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let ttl = if consent.expires_at_unix_seconds == 0 {
+        "no expiry".to_string()
+    } else if consent.expires_at_unix_seconds > now {
+        format!("expires in {}s", consent.expires_at_unix_seconds - now)
+    } else {
+        "expired".to_string()
+    };
+    let risk = if consent.risk_factors.is_empty() {
+        "(none)".to_string()
+    } else {
+        consent.risk_factors.join(", ")
+    };
+
+    let mut lines = vec![
+        Line::from(format!("{} [{}] {}", consent.consent_id, consent.status, ttl)),
+        Line::from(format!("rationale> {}", consent.rationale)),
+        Line::from(format!("risk> {risk}")),
+        Line::from(""),
+    ];
+    for (idx, evt) in consent.pending_actions.iter().enumerate() {
+        let marker = if idx == detail.action { ">" } else { " " };
+        lines.push(Line::from(Span::styled(
+            format!("{marker} #{idx} {} [{}]", evt.tool_name, evt.capability_tier),
+            Style::default().fg(if idx == detail.action { Color::Yellow } else { Color::Gray }),
+        )));
+        if idx != detail.action {
+            continue;
+        }
+        let fields = editable_fields(consent, idx);
+        let edits = detail.edits.get(&idx);
+        if fields.is_empty() {
+            lines.push(Line::from(format!("   args> {}", evt.arguments_preview.as_deref().unwrap_or("{}"))));
+        }
+        for (field_idx, (name, original)) in fields.iter().enumerate() {
+            let value = edits.and_then(|e| e.get(name)).unwrap_or(original);
+            let edited = if edits.map(|e| e.contains_key(name)).unwrap_or(false) { "*" } else { "" };
+            let cursor = if field_idx == detail.field { ">" } else { " " };
+            let text = match (&detail.editing, field_idx == detail.field) {
+                (Some(buffer), true) => format!("  {cursor} {name} = {buffer}_"),
+                _ => format!("  {cursor} {name}{edited} = {value}"),
+            };
+            lines.push(Line::from(text));
+        }
+        if let Some(diff) = &evt.preview_diff {
+            lines.extend(diff.lines().map(|line| diff_line(&format!("   {line}"), line)));
+        }
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "a approve | d deny | j/k action | f field | e edit | Esc close",
+        Style::default().fg(Color::Gray).add_modifier(Modifier::DIM),
+    )));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow))
+        .title("Consent detail");
+    frame.render_widget(Clear, popup);
+    frame.render_widget(Paragraph::new(lines).block(block).wrap(Wrap { trim: false }), popup);
 }
 
 fn render_sessions(frame: &mut Frame, area: ratatui::layout::Rect, app: &TuiApp) {
@@ -244,7 +991,13 @@ fn render_sessions(frame: &mut Frame, area: ratatui::layout::Rect, app: &TuiApp)
     } else {
         app.sessions
             .iter()
-            .map(|s| ListItem::new(format!("{} ({})", s.id, s.message_count)))
+            .map(|s| {
+                let pin = if s.pinned { "* " } else { "" };
+                match &s.forked_from {
+                    Some(parent) => ListItem::new(format!("{pin}{} ({}) fork of {parent}", s.id, s.message_count)),
+                    None => ListItem::new(format!("{pin}{} ({})", s.id, s.message_count)),
+                }
+            })
             .collect()
     };
     let mut state = ListState::default().with_selected(if app.sessions.is_empty() {
@@ -252,7 +1005,8 @@ fn render_sessions(frame: &mut Frame, area: ratatui::layout::Rect, app: &TuiApp)
     } else {
         Some(app.selected_session.min(app.sessions.len() - 1))
     });
-    let title = pane_title("Sessions", app.focus == FocusPane::Sessions);
+    let heights = list_item_heights(&items, !app.sessions.is_empty());
+    let title = pane_title("Sessions (p pin, a archive, x delete)", app.focus == FocusPane::Sessions);
     let list = List::new(items)
         .block(focused_block(title, app.focus == FocusPane::Sessions))
         .highlight_style(
@@ -261,12 +1015,14 @@ fn render_sessions(frame: &mut Frame, area: ratatui::layout::Rect, app: &TuiApp)
                 .add_modifier(Modifier::BOLD),
         );
     frame.render_stateful_widget(list, area, &mut state);
+    record_pane_hit(app, FocusPane::Sessions, area, state.offset(), heights);
 }
 
 fn render_chat(frame: &mut Frame, area: ratatui::layout::Rect, app: &TuiApp) {
     let mut lines = Vec::<Line>::new();
-    if !app.feed.is_empty() {
-        for (idx, entry) in app.feed.iter().enumerate().rev() {
+    let visible = app.visible_feed_indices();
+    if !visible.is_empty() {
+        for (idx, entry) in visible.iter().rev().map(|idx| (*idx, &app.feed[*idx])) {
             let marker = if idx == app.selected_execution && app.focus == FocusPane::Chat {
                 ">"
             } else {
@@ -287,13 +1043,17 @@ fn render_chat(frame: &mut Frame, area: ratatui::layout::Rect, app: &TuiApp) {
                 Style::default().fg(Color::Gray)
             };
             lines.push(Line::from(vec![Span::styled(row, row_style)]));
-            if idx == app.selected_execution && app.show_execution_details {
+            // A run in flight always shows its text so streamed tokens are visible.
+            if idx == app.selected_execution && (app.show_execution_details || entry.status == "running") {
                 lines.push(Line::from(format!("   assistant> {}", entry.assistant_text)));
                 if let Some(session_id) = &entry.session_id {
                     lines.push(Line::from(format!("   session> {}", session_id)));
                 }
                 if let Some(consent) = &entry.consent_request {
                     lines.push(Line::from(format!("   consent?> {}", consent.human_summary)));
+                    if let Some(diff) = &consent.preview_diff {
+                        lines.extend(diff.lines().map(|line| diff_line(&format!("   {line}"), line)));
+                    }
                 }
                 if !entry.proposed_actions.is_empty() || !entry.executed_action_events.is_empty() {
                     let proposed = entry
@@ -312,22 +1072,54 @@ fn render_chat(frame: &mut Frame, area: ratatui::layout::Rect, app: &TuiApp) {
                 }
             }
         }
+    } else if !app.feed.is_empty() {
+        lines.push(Line::from(format!("system> no entries match '{}'", app.search_query)));
     } else {
         lines.push(Line::from("system> no executions yet; send a natural-language request"));
     }
+    let title = if app.search_mode || !app.search_query.is_empty() {
+        format!("Feed (v details) /{}", app.search_query)
+    } else {
+        "Feed (v details)".to_string()
+    };
+    let wrap_width = usize::from(area.width.max(1));
+    let wrapped_lines: usize = lines.iter().map(|line| line.width().max(1).div_ceil(wrap_width)).sum();
     let chat = Paragraph::new(lines)
         .block(focused_block(
-            pane_title("Feed (v details)", app.focus == FocusPane::Chat),
+            pane_title(&title, app.focus == FocusPane::Chat),
             app.focus == FocusPane::Chat,
         ))
         .wrap(Wrap { trim: false });
+    let viewport = area.height.saturating_sub(1);
+    let total = u16::try_from(wrapped_lines).unwrap_or(u16::MAX);
+    app.feed_max_scroll.set(total.saturating_sub(viewport));
+    let chat = chat.scroll((app.feed_scroll.min(app.feed_max_scroll.get()), 0));
     frame.render_widget(chat, area);
 }
 
+fn diff_line(text: &str, raw: &str) -> Line<'static> {
+    let color = if raw.starts_with("+++") || raw.starts_with("---") {
+        Color::Gray
+    } else if raw.starts_with('+') {
+        Color::Green
+    } else if raw.starts_with('-') {
+        Color::Red
+    } else if raw.starts_with("@@") {
+        Color::Cyan
+    } else {
+        Color::Gray
+    };
+    Line::from(Span::styled(text.to_string(), Style::default().fg(color)))
+}
+
 fn render_right(frame: &mut Frame, area: ratatui::layout::Rect, app: &TuiApp) {
     let rows = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+        .constraints([
+            Constraint::Percentage(35),
+            Constraint::Percentage(40),
+            Constraint::Percentage(25),
+        ])
         .split(area);
 
     let consent_items = if app.consents.is_empty() {
@@ -335,14 +1127,20 @@ fn render_right(frame: &mut Frame, area: ratatui::layout::Rect, app: &TuiApp) {
     } else {
         app.consents
             .iter()
-            .map(|c| {
+            .enumerate()
+            .map(|(idx, c)| {
                 let ttl = if c.expires_at_unix_seconds > 0 {
                     format!(" exp={}", c.expires_at_unix_seconds)
                 } else {
                     String::new()
                 };
                 let label = format!("{} [{}] {}{}", c.consent_id, c.capability_tier, c.tool_name, ttl);
-                ListItem::new(label)
+                let mut lines = vec![Line::from(label)];
+                if idx == app.selected_consent {
+                    let diffs = c.pending_actions.iter().filter_map(|evt| evt.preview_diff.as_deref());
+                    lines.extend(diffs.flat_map(str::lines).map(|line| diff_line(&format!("  {line}"), line)));
+                }
+                ListItem::new(lines)
             })
             .collect()
     };
@@ -351,14 +1149,16 @@ fn render_right(frame: &mut Frame, area: ratatui::layout::Rect, app: &TuiApp) {
     } else {
         Some(app.selected_consent.min(app.consents.len() - 1))
     });
+    let consent_heights = list_item_heights(&consent_items, !app.consents.is_empty());
     let consent_title = pane_title("Consent", app.focus == FocusPane::Consents);
     let consent_list = List::new(consent_items)
         .block(focused_block(consent_title, app.focus == FocusPane::Consents))
         .highlight_style(Style::default().fg(Color::Yellow));
     frame.render_stateful_widget(consent_list, rows[0], &mut consent_state);
+    record_pane_hit(app, FocusPane::Consents, rows[0], consent_state.offset(), consent_heights);
 
-    let audit_lines = if app.audits.is_empty() {
-        vec![Line::from("(no audit entries)")]
+    let audit_items = if app.audits.is_empty() {
+        vec![ListItem::new("(no audit entries)")]
     } else {
         app.audits
             .iter()
@@ -369,40 +1169,179 @@ fn render_right(frame: &mut Frame, area: ratatui::layout::Rect, app: &TuiApp) {
                 } else {
                     " "
                 };
-                Line::from(format!("{marker} {} {}", a.audit_id, a.provider))
+                ListItem::new(format!("{marker} {} {}", a.audit_id, a.provider))
+            })
+            .collect()
+    };
+    let mut audit_state = ListState::default().with_selected(if app.audits.is_empty() {
+        None
+    } else {
+        Some(app.selected_audit.min(app.audits.len() - 1))
+    });
+    let audit_heights = list_item_heights(&audit_items, !app.audits.is_empty());
+    let audit = List::new(audit_items).block(focused_block(
+        pane_title("Audit", app.focus == FocusPane::Audit),
+        app.focus == FocusPane::Audit,
+    ));
+    frame.render_stateful_widget(audit, rows[1], &mut audit_state);
+    record_pane_hit(app, FocusPane::Audit, rows[1], audit_state.offset(), audit_heights);
+
+    let mcp_items = if app.mcp_servers.is_empty() {
+        vec![ListItem::new("(no MCP servers)")]
+    } else {
+        app.mcp_servers
+            .iter()
+            .enumerate()
+            .map(|(idx, server)| {
+                let color = match server.status.as_str() {
+                    "running" => Color::Green,
+                    "restarting" => Color::Yellow,
+                    "failed" => Color::Red,
+                    _ => Color::Gray,
+                };
+                let mut lines = vec![Line::from(Span::styled(
+                    format!("{} [{}] {}", server.name, server.status, server.id),
+                    Style::default().fg(color),
+                ))];
+                if idx == app.selected_mcp {
+                    lines.push(Line::from(format!("  cmd> {} {}", server.command, server.args.join(" "))));
+                    if let Some(exit) = &server.last_exit_status {
+                        let at = server
+                            .last_exit_unix_seconds
+                            .map(|ts| format!(" at {ts}"))
+                            .unwrap_or_default();
+                        lines.push(Line::from(format!("  last exit> {exit}{at}")));
+                    }
+                    if server.restart_count > 0 {
+                        lines.push(Line::from(format!("  restarts> {}", server.restart_count)));
+                    }
+                }
+                ListItem::new(lines)
             })
             .collect()
     };
-    let audit = Paragraph::new(audit_lines)
+    let mut mcp_state = ListState::default().with_selected(if app.mcp_servers.is_empty() {
+        None
+    } else {
+        Some(app.selected_mcp.min(app.mcp_servers.len() - 1))
+    });
+    let mcp_heights = list_item_heights(&mcp_items, !app.mcp_servers.is_empty());
+    let mcp_list = List::new(mcp_items)
         .block(focused_block(
-            pane_title("Audit", app.focus == FocusPane::Audit),
-            app.focus == FocusPane::Audit,
+            pane_title("MCP (s start/stop, x remove)", app.focus == FocusPane::Mcp),
+            app.focus == FocusPane::Mcp,
         ))
-        .wrap(Wrap { trim: false });
-    frame.render_widget(audit, rows[1]);
+        .highlight_style(Style::default().fg(Color::Yellow));
+    frame.render_stateful_widget(mcp_list, rows[2], &mut mcp_state);
+    record_pane_hit(app, FocusPane::Mcp, rows[2], mcp_state.offset(), mcp_heights);
+}
+
+/// Row heights for click mapping; placeholder rows ("(no sessions)") are not selectable.
+fn list_item_heights(items: &[ListItem], selectable: bool) -> Vec<u16> {
+    if !selectable {
+        return Vec::new();
+    }
+    items.iter().map(|item| item.height() as u16).collect()
+}
+
+fn record_pane_hit(app: &TuiApp, pane: FocusPane, area: Rect, offset: usize, item_heights: Vec<u16>) {
+    app.pane_hits.borrow_mut().push(PaneHit {
+        pane,
+        area,
+        offset,
+        item_heights,
+    });
+}
+
+const MOUSE_SCROLL_LINES: i32 = 3;
+
+/// Left click focuses the pane under the pointer and selects the clicked row; the wheel scrolls
+/// the feed or steps through audit entries. Popups only take wheel scrolling.
+fn handle_mouse(app: &mut TuiApp, mouse: MouseEvent) {
+    let delta = match mouse.kind {
+        MouseEventKind::ScrollUp => -1,
+        MouseEventKind::ScrollDown => 1,
+        MouseEventKind::Down(MouseButton::Left) => 0,
+        _ => return,
+    };
+    if let Some(detail) = app.audit_detail.as_mut() {
+        detail.scroll = (i32::from(detail.scroll) + delta * MOUSE_SCROLL_LINES).max(0) as u16;
+        return;
+    }
+    if let Some(view) = app.tool_stats.as_mut() {
+        view.scroll = (i32::from(view.scroll) + delta * MOUSE_SCROLL_LINES).max(0) as u16;
+        return;
+    }
+    if app.provider_picker.is_some() || app.consent_detail.is_some() || app.search_mode {
+        return;
+    }
+    let position = ratatui::layout::Position::new(mouse.column, mouse.row);
+    let hit = app
+        .pane_hits
+        .borrow()
+        .iter()
+        .find(|hit| hit.area.contains(position))
+        .map(|hit| (hit.pane, hit.row_at(mouse.row)));
+    let Some((pane, row)) = hit else {
+        return;
+    };
+    match (pane, delta) {
+        (FocusPane::Chat, 0) => app.focus = FocusPane::Chat,
+        (FocusPane::Chat, _) => app.scroll_feed(delta * MOUSE_SCROLL_LINES),
+        (FocusPane::Audit, _) if delta != 0 && !app.audits.is_empty() => {
+            let last = app.audits.len() as i32 - 1;
+            app.selected_audit = (app.selected_audit as i32 + delta).clamp(0, last) as usize;
+        }
+        (_, 0) => {
+            app.focus = pane;
+            match (pane, row) {
+                (FocusPane::Sessions, Some(idx)) => app.selected_session = idx,
+                (FocusPane::Consents, Some(idx)) => app.selected_consent = idx,
+                (FocusPane::Audit, Some(idx)) => app.selected_audit = idx,
+                (FocusPane::Mcp, Some(idx)) => app.selected_mcp = idx,
+                _ => {}
+            }
+        }
+        _ => {}
+    }
 }
 
 fn render_input(frame: &mut Frame, area: ratatui::layout::Rect, app: &TuiApp) {
     let title = format!(
-        "input [{}] {} {}",
+        "input [{}] {} {} {}",
         if app.require_confirmation {
             "confirm"
         } else {
             "best"
         },
-        app.provider_name,
+        app.model
+            .as_ref()
+            .map(|m| format!("{}/{m}", app.provider_name))
+            .unwrap_or_else(|| app.provider_name.clone()),
+        generation_title(&app.generation),
         app.current_session_id().unwrap_or_else(|| "(none)".to_string())
     );
-    let text = if app.chat_input.is_empty() {
-        "-> ".to_string()
-    } else {
-        format!("-> {}", app.chat_input)
-    };
-    let input = Paragraph::new(text)
+    let lines = app
+        .input
+        .text()
+        .split('\n')
+        .enumerate()
+        .map(|(idx, line)| Line::from(format!("{}{line}", if idx == 0 { "-> " } else { "   " })))
+        .collect::<Vec<_>>();
+    let (cursor_line, cursor_col) = app.input.cursor_line_col();
+    let visible = area.height.saturating_sub(2).max(1) as usize;
+    let scroll = cursor_line.saturating_sub(visible - 1) as u16;
+    let input = Paragraph::new(lines)
         .style(Style::default().bg(Color::Rgb(52, 56, 64)).fg(Color::White))
         .block(focused_block(title, app.focus == FocusPane::Chat))
-        .wrap(Wrap { trim: false });
+        .scroll((scroll, 0));
     frame.render_widget(input, area);
+    if app.focus == FocusPane::Chat {
+        frame.set_cursor_position((
+            area.x + 1 + (3 + cursor_col as u16).min(area.width.saturating_sub(3)),
+            area.y + 1 + cursor_line as u16 - scroll,
+        ));
+    }
 }
 
 fn render_status(frame: &mut Frame, area: ratatui::layout::Rect, app: &TuiApp) {
@@ -411,12 +1350,47 @@ fn render_status(frame: &mut Frame, area: ratatui::layout::Rect, app: &TuiApp) {
         FocusPane::Chat => "feed",
         FocusPane::Consents => "consent",
         FocusPane::Audit => "audit",
+        FocusPane::Mcp => "mcp",
     };
-    let status = Paragraph::new(format!("{pane} | {}", app.status))
+    let status = match &app.attached_to {
+        Some(addr) => format!("{addr} | {pane} | {}", app.status),
+        None => format!("{pane} | {}", app.status),
+    };
+    let status = Paragraph::new(status)
         .style(Style::default().fg(Color::Gray).add_modifier(Modifier::DIM));
     frame.render_widget(status, area);
 }
 
+const TEMPERATURE_PRESETS: [f64; 4] = [0.0, 0.2, 0.7, 1.0];
+
+fn next_temperature_preset(current: Option<f64>) -> Option<f64> {
+    match current {
+        None => Some(TEMPERATURE_PRESETS[0]),
+        Some(t) => TEMPERATURE_PRESETS.iter().copied().find(|p| *p > t),
+    }
+}
+
+/// `t=0.7`, plus whichever of top_p, max_tokens and stop are overridden.
+fn generation_title(generation: &ipc::ProviderConfig) -> String {
+    let mut title = format!("t={}", temperature_label(generation.temperature));
+    if let Some(top_p) = generation.top_p {
+        title.push_str(&format!(" p={top_p}"));
+    }
+    if let Some(max_tokens) = generation.max_tokens {
+        title.push_str(&format!(" max={max_tokens}"));
+    }
+    if !generation.stop.is_empty() {
+        title.push_str(&format!(" stop={}", generation.stop.len()));
+    }
+    title
+}
+
+fn temperature_label(temperature: Option<f64>) -> String {
+    temperature
+        .map(|t| format!("{t:.1}"))
+        .unwrap_or_else(|| "default".to_string())
+}
+
 fn move_selection(app: &mut TuiApp, delta: isize) {
     match app.focus {
         FocusPane::Sessions => {
@@ -443,23 +1417,41 @@ fn move_selection(app: &mut TuiApp, delta: isize) {
             let idx = (app.selected_audit as isize + delta).clamp(0, len - 1);
             app.selected_audit = idx as usize;
         }
+        FocusPane::Mcp => {
+            if app.mcp_servers.is_empty() {
+                return;
+            }
+            let len = app.mcp_servers.len() as isize;
+            let idx = (app.selected_mcp as isize + delta).clamp(0, len - 1);
+            app.selected_mcp = idx as usize;
+        }
         FocusPane::Chat => {
-            if app.feed.is_empty() {
+            let visible = app.visible_feed_indices();
+            if visible.is_empty() {
                 return;
             }
-            let len = app.feed.len() as isize;
-            let idx = (app.selected_execution as isize + delta).clamp(0, len - 1);
-            app.selected_execution = idx as usize;
+            let pos = visible
+                .iter()
+                .position(|idx| *idx == app.selected_execution)
+                .unwrap_or(visible.len() - 1) as isize;
+            let next = (pos + delta).clamp(0, visible.len() as isize - 1);
+            app.selected_execution = visible[next as usize];
         }
     }
 }
 
-fn local_call<T: DeserializeOwned>(
-    client: &mut JsonRpcClient<AgentService>,
+fn backend_call<T: DeserializeOwned>(
+    client: &mut Backend,
     method: &str,
     params: serde_json::Value,
 ) -> Result<T, String> {
-    let resp = client.call_raw(Request::new(Id::Number(1), method.to_string(), params.to_string()));
+    if let Some(addr) = client.remote.as_deref() {
+        let wire = crate::call_http_jsonrpc(addr, method, params).map_err(|e| format!("{addr}: {e}"))?;
+        return crate::wire_result(wire);
+    }
+    let resp = client
+        .client
+        .call_raw(Request::new(Id::Number(1), method.to_string(), params.to_string()));
     if let Some(err) = resp.error {
         return Err(format!("json-rpc {}: {}", err.code, err.message));
     }
@@ -469,21 +1461,66 @@ fn local_call<T: DeserializeOwned>(
     serde_json::from_str::<T>(&payload).map_err(|e| format!("result parse error: {e}"))
 }
 
-fn refresh_all(client: &mut JsonRpcClient<AgentService>, app: &mut TuiApp) -> Result<(), String> {
-    app.sessions = local_call(client, "sessions.list", json!({}))?;
+fn refresh_all(client: &mut Backend, app: &mut TuiApp) -> Result<(), String> {
+    let providers: Vec<ProviderInfo> = backend_call(client, "providers.list", json!({}))?;
+    if let Some(active) = providers.iter().find(|p| p.is_active) {
+        if active.name != app.provider_name {
+            app.provider_name = active.name.clone();
+            app.model = None;
+        }
+    }
+    app.sessions = backend_call(client, "sessions.list", json!({ "pinned_first": true }))?;
     if app.selected_session >= app.sessions.len() && !app.sessions.is_empty() {
         app.selected_session = app.sessions.len() - 1;
     }
     load_selected_session(client, app)?;
     refresh_consents(client, app)?;
     refresh_audit(client, app)?;
+    refresh_mcp_servers(client, app)?;
     app.set_status("Refreshed");
     Ok(())
 }
 
-fn load_selected_session(client: &mut JsonRpcClient<AgentService>, app: &mut TuiApp) -> Result<(), String> {
+/// Refreshes only the panes touched by agent events since the last sync, so consent expiry and
+/// MCP exits show up without a manual `r`.
+fn sync_events(client: &mut Backend, app: &mut TuiApp) -> Result<(), String> {
+    app.last_event_sync = Instant::now();
+    let response: EventsSubscribeResponse = backend_call(client, "events.subscribe", json!({ "since": app.event_cursor }))?;
+    app.event_cursor = response.cursor;
+    let touched = |prefix: &str| response.truncated || response.events.iter().any(|e| e.event_type.starts_with(prefix));
+    if touched("session.") {
+        app.sessions = backend_call(client, "sessions.list", json!({ "pinned_first": true }))?;
+        if app.selected_session >= app.sessions.len() && !app.sessions.is_empty() {
+            app.selected_session = app.sessions.len() - 1;
+        }
+        // Reload without `load_selected_session` so a background sync does not clobber the status.
+        app.session_detail = match app.current_session_id() {
+            Some(session_id) => Some(backend_call(client, "sessions.get", json!({ "session_id": session_id }))?),
+            None => None,
+        };
+    }
+    if touched("consent.") {
+        refresh_consents(client, app)?;
+    }
+    if touched("audit.") {
+        refresh_audit(client, app)?;
+    }
+    if touched("mcp.") {
+        refresh_mcp_servers(client, app)?;
+    }
+    Ok(())
+}
+
+fn load_selected_session(client: &mut Backend, app: &mut TuiApp) -> Result<(), String> {
     if let Some(session_id) = app.current_session_id() {
-        let session: Session = local_call(client, "sessions.get", json!({ "session_id": session_id }))?;
+        let session: Session = backend_call(client, "sessions.get", json!({ "session_id": session_id }))?;
+        if let Some(provider) = session.provider_name.clone() {
+            app.provider_name = provider;
+            app.model = session.model.clone();
+        }
+        if let Some(mode) = &session.default_mode {
+            app.require_confirmation = *mode == ChatMode::RequireConfirmation;
+        }
         app.session_detail = Some(session);
         app.set_status("Loaded session");
     } else {
@@ -492,38 +1529,100 @@ fn load_selected_session(client: &mut JsonRpcClient<AgentService>, app: &mut Tui
     Ok(())
 }
 
-fn refresh_consents(client: &mut JsonRpcClient<AgentService>, app: &mut TuiApp) -> Result<(), String> {
-    app.consents = local_call(
+fn open_selected_audit(client: &mut Backend, app: &mut TuiApp) -> Result<(), String> {
+    let Some(audit_id) = app.audits.get(app.selected_audit).map(|a| a.audit_id.clone()) else {
+        app.set_status("No audit entry selected");
+        return Ok(());
+    };
+    match backend_call::<AuditEntry>(client, "audit.get", json!({ "audit_id": audit_id })) {
+        Ok(entry) => app.audit_detail = Some(AuditDetail { entry, scroll: 0 }),
+        Err(err) => app.set_status(format!("Audit lookup failed: {err}")),
+    }
+    Ok(())
+}
+
+fn open_tool_stats(client: &mut Backend, app: &mut TuiApp) -> Result<(), String> {
+    match backend_call::<ToolStatsResponse>(client, "tools.stats", json!({})) {
+        Ok(stats) => {
+            let scroll = app.tool_stats.as_ref().map(|view| view.scroll).unwrap_or(0);
+            app.tool_stats = Some(ToolStatsView { stats, scroll });
+        }
+        Err(err) => app.set_status(format!("Tool stats failed: {err}")),
+    }
+    Ok(())
+}
+
+fn refresh_mcp_servers(client: &mut Backend, app: &mut TuiApp) -> Result<(), String> {
+    app.mcp_servers = backend_call(client, "mcp.servers.list", json!({}))?;
+    if app.selected_mcp >= app.mcp_servers.len() && !app.mcp_servers.is_empty() {
+        app.selected_mcp = app.mcp_servers.len() - 1;
+    }
+    Ok(())
+}
+
+fn toggle_selected_mcp_server(client: &mut Backend, app: &mut TuiApp) -> Result<(), String> {
+    let Some(server) = app.mcp_servers.get(app.selected_mcp).cloned() else {
+        app.set_status("No MCP server selected");
+        return Ok(());
+    };
+    let (method, verb) = if server.status == "running" {
+        ("mcp.servers.stop", "Stopped")
+    } else {
+        ("mcp.servers.start", "Started")
+    };
+    match backend_call::<McpServerMutationResponse>(client, method, json!({ "server_id": server.id })) {
+        Ok(_) => app.set_status(format!("{verb} MCP server {}", server.name)),
+        Err(err) => app.set_status(format!("MCP {} failed: {err}", verb.to_lowercase())),
+    }
+    refresh_mcp_servers(client, app)
+}
+
+fn remove_selected_mcp_server(client: &mut Backend, app: &mut TuiApp) -> Result<(), String> {
+    let Some(server) = app.mcp_servers.get(app.selected_mcp).cloned() else {
+        app.set_status("No MCP server selected");
+        return Ok(());
+    };
+    match backend_call::<McpServerMutationResponse>(client, "mcp.servers.remove", json!({ "server_id": server.id })) {
+        Ok(_) => app.set_status(format!("Removed MCP server {}", server.name)),
+        Err(err) => app.set_status(format!("MCP remove failed: {err}")),
+    }
+    refresh_mcp_servers(client, app)
+}
+
+fn refresh_consents(client: &mut Backend, app: &mut TuiApp) -> Result<(), String> {
+    app.consents = backend_call::<ListPage<PendingConsentRecord>>(
         client,
         "consent.list",
         json!({
             "status": "pending",
             "session_id": app.current_session_id(),
         }),
-    )?;
+    )?
+    .items;
     if app.selected_consent >= app.consents.len() && !app.consents.is_empty() {
         app.selected_consent = app.consents.len() - 1;
     }
     Ok(())
 }
 
-fn refresh_audit(client: &mut JsonRpcClient<AgentService>, app: &mut TuiApp) -> Result<(), String> {
-    app.audits = local_call(
+fn refresh_audit(client: &mut Backend, app: &mut TuiApp) -> Result<(), String> {
+    app.audits = backend_call::<ListPage<AuditEntry>>(
         client,
         "audit.list",
         json!({
             "session_id": app.current_session_id(),
             "limit": 20
         }),
-    )?;
+    )?
+    .items;
     if app.selected_audit >= app.audits.len() && !app.audits.is_empty() {
         app.selected_audit = app.audits.len() - 1;
     }
     Ok(())
 }
 
-fn create_session(client: &mut JsonRpcClient<AgentService>, app: &mut TuiApp) -> Result<(), String> {
-    let session: Session = local_call(client, "sessions.create", json!({ "title": null }))?;
+fn create_session(client: &mut Backend, app: &mut TuiApp) -> Result<(), String> {
+    let session: Session = backend_call(client, "sessions.create", json!({ "title": null }))?;
     app.set_status(format!("Created {}", session.id));
     refresh_all(client, app)?;
     if let Some(idx) = app.sessions.iter().position(|s| s.id == session.id) {
@@ -533,12 +1632,12 @@ fn create_session(client: &mut JsonRpcClient<AgentService>, app: &mut TuiApp) ->
     Ok(())
 }
 
-fn delete_selected_session(client: &mut JsonRpcClient<AgentService>, app: &mut TuiApp) -> Result<(), String> {
+fn delete_selected_session(client: &mut Backend, app: &mut TuiApp) -> Result<(), String> {
     let Some(session_id) = app.current_session_id() else {
         app.set_status("No session selected");
         return Ok(());
     };
-    let _: serde_json::Value = local_call(client, "sessions.delete", json!({ "session_id": session_id.clone() }))?;
+    let _: serde_json::Value = backend_call(client, "sessions.delete", json!({ "session_id": session_id.clone() }))?;
     app.set_status(format!("Deleted {}", session_id));
     if app.selected_session > 0 {
         app.selected_session -= 1;
@@ -546,40 +1645,180 @@ fn delete_selected_session(client: &mut JsonRpcClient<AgentService>, app: &mut T
     refresh_all(client, app)
 }
 
-fn send_chat(client: &mut JsonRpcClient<AgentService>, app: &mut TuiApp) -> Result<(), String> {
-    if app.chat_input.trim().is_empty() {
+/// Saves the current provider, model and confirmation mode as the selected session's defaults,
+/// so coming back to the session restores them.
+fn remember_session_defaults(client: &mut Backend, app: &mut TuiApp) {
+    let Some(session_id) = app.current_session_id() else {
+        return;
+    };
+    let mode = if app.require_confirmation { ChatMode::RequireConfirmation } else { ChatMode::BestEffort };
+    let params = json!({
+        "session_id": session_id,
+        "provider_name": app.provider_name,
+        "model": app.model.clone().unwrap_or_default(),
+        "default_mode": mode,
+    });
+    if let Err(err) = backend_call::<Session>(client, "sessions.update", params) {
+        app.set_status(format!("Session defaults not saved: {err}"));
+    }
+}
+
+fn toggle_selected_session_pin(client: &mut Backend, app: &mut TuiApp) -> Result<(), String> {
+    let Some(summary) = app.sessions.get(app.selected_session).cloned() else {
+        app.set_status("No session selected");
+        return Ok(());
+    };
+    let _: serde_json::Value = backend_call(
+        client,
+        "sessions.pin",
+        json!({ "session_id": summary.id.clone(), "pinned": !summary.pinned }),
+    )?;
+    refresh_all(client, app)?;
+    // Pinning reorders the list; keep the cursor on the same session.
+    if let Some(idx) = app.sessions.iter().position(|s| s.id == summary.id) {
+        app.selected_session = idx;
+    }
+    app.set_status(format!("{} {}", if summary.pinned { "Unpinned" } else { "Pinned" }, summary.id));
+    Ok(())
+}
+
+/// Archived sessions drop out of the pane; `cli session unarchive <id>` brings one back.
+fn archive_selected_session(client: &mut Backend, app: &mut TuiApp) -> Result<(), String> {
+    let Some(session_id) = app.current_session_id() else {
+        app.set_status("No session selected");
+        return Ok(());
+    };
+    let _: serde_json::Value = backend_call(
+        client,
+        "sessions.archive",
+        json!({ "session_id": session_id.clone(), "archived": true }),
+    )?;
+    if app.selected_session > 0 {
+        app.selected_session -= 1;
+    }
+    refresh_all(client, app)?;
+    app.set_status(format!("Archived {session_id}"));
+    Ok(())
+}
+
+fn send_chat(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    client: &mut Backend,
+    app: &mut TuiApp,
+) -> Result<(), String> {
+    if app.input.text().trim().is_empty() {
         app.set_status("Input is empty");
         return Ok(());
     }
-    if app.chat_input.to_ascii_lowercase().contains("tool:") {
+    let mut config = ipc::ProviderConfig {
+        model: app.model.clone(),
+        ..app.generation.clone()
+    };
+    if let Some(reply) = crate::apply_generation_command(&mut config, app.input.text().trim()) {
+        app.input.submit();
+        app.model = config.model.take();
+        app.generation = config;
+        app.set_status(reply);
+        return Ok(());
+    }
+    if let Some(target) = app.input.text().trim().strip_prefix("/connect") {
+        if target.is_empty() || target.starts_with(' ') {
+            let target = target.trim().to_string();
+            app.input.submit();
+            return connect(client, app, &target);
+        }
+    }
+    if app.input.text().to_ascii_lowercase().contains("tool:") {
         app.set_status("Use natural language only (explicit tool: syntax disabled)");
         return Ok(());
     }
     if app.current_session_id().is_none() {
         create_session(client, app)?;
     }
-    let prompt = std::mem::take(&mut app.chat_input);
+    let prompt = app.input.submit();
     let request = ChatRequest {
         session_id: app.current_session_id(),
         messages: vec![ChatMessage {
             role: "user".to_string(),
             content: prompt.clone(),
+            parts: Vec::new(),
+            message_id: None,
+            created_at_unix_seconds: None,
         }],
         provider_config: ipc::ProviderConfig {
             provider_name: app.provider_name.clone(),
-            model: None,
-            config_json: None,
+            model: app.model.clone(),
+            ..app.generation.clone()
         },
-        mode: if app.require_confirmation {
+        mode: Some(if app.require_confirmation {
             ChatMode::RequireConfirmation
         } else {
             ChatMode::BestEffort
-        },
+        }),
+        cache_ttl_secs: None,
+        dry_run: false,
     };
-    let response: ChatResponse = local_call(client, "chat.request", serde_json::to_value(request).unwrap_or(json!({})))?;
+    // The new entry is filled in as tokens arrive and replaced by the final response.
+    app.feed.push(ExecutionFeedItem {
+        execution_id: "live".to_string(),
+        session_id: request.session_id.clone(),
+        status: "running".to_string(),
+        user_prompt: Some(prompt.clone()),
+        assistant_text: String::new(),
+        consent_request: None,
+        proposed_actions: Vec::new(),
+        executed_action_events: Vec::new(),
+        events: Vec::new(),
+    });
+    let live = app.feed.len() - 1;
+    app.selected_execution = live;
+    app.feed_scroll = 0;
+    app.set_status(format!("{} waiting for {}", SPINNER[0], app.provider_name));
+    terminal.draw(|f| render(f, app)).map_err(|e| e.to_string())?;
+    if let Some(addr) = client.remote.clone() {
+        // `chat.request` over HTTP returns the whole response at once.
+        app.set_status(format!("{} waiting for {} via {addr}", SPINNER[0], app.provider_name));
+        terminal.draw(|f| render(f, app)).map_err(|e| e.to_string())?;
+        let params = serde_json::to_value(&request).map_err(|e| e.to_string())?;
+        return match backend_call::<ChatResponse>(client, "chat.request", params) {
+            Ok(response) => finish_chat(client, app, live, prompt, response),
+            Err(err) => {
+                app.feed.pop();
+                app.selected_execution = app.feed.len().saturating_sub(1);
+                app.set_status(format!("Chat failed: {err}"));
+                Ok(())
+            }
+        };
+    }
+    let mut tick = 0usize;
+    let response = client.client.service_mut().chat_request_streaming(request, &mut |event| {
+        tick += 1;
+        let spinner = SPINNER[tick % SPINNER.len()];
+        match event {
+            StreamEvent::Token(token) => {
+                app.feed[live].assistant_text.push_str(&token);
+                app.set_status(format!("{spinner} streaming"));
+            }
+            StreamEvent::ToolStarted(name) => app.set_status(format!("{spinner} running {name}")),
+            StreamEvent::RoundFinished(round) => {
+                app.set_status(format!("{spinner} tool round {round} done; waiting for {}", app.provider_name))
+            }
+        }
+        let _ = terminal.draw(|f| render(f, app));
+    });
+    finish_chat(client, app, live, prompt, response)
+}
+
+/// Replaces the live feed entry with the final response and reloads the panes it touched.
+fn finish_chat(
+    client: &mut Backend,
+    app: &mut TuiApp,
+    live: usize,
+    prompt: String,
+    response: ChatResponse,
+) -> Result<(), String> {
     app.last_chat_response = Some(response.clone());
-    app.feed.push(response.to_execution_feed_item(Some(prompt)));
-    app.selected_execution = app.feed.len().saturating_sub(1);
+    app.feed[live] = response.to_execution_feed_item(Some(prompt));
     if let Some(sid) = response.session_id.as_ref() {
         if let Some(idx) = app.sessions.iter().position(|s| &s.id == sid) {
             app.selected_session = idx;
@@ -592,12 +1831,47 @@ fn send_chat(client: &mut JsonRpcClient<AgentService>, app: &mut TuiApp) -> Resu
     Ok(())
 }
 
-fn approve_selected_consent(client: &mut JsonRpcClient<AgentService>, app: &mut TuiApp) -> Result<(), String> {
+/// `/connect <host:port>` attaches to a running `serve-http`; `/connect local` goes back to the
+/// in-process agent. A backend that does not answer leaves the current one in place.
+fn connect(client: &mut Backend, app: &mut TuiApp, target: &str) -> Result<(), String> {
+    let remote = match target {
+        "" => {
+            app.set_status("Usage: /connect <host:port> | /connect local");
+            return Ok(());
+        }
+        "local" => None,
+        addr => Some(addr.to_string()),
+    };
+    let previous = std::mem::replace(&mut client.remote, remote);
+    let previous_cursor = std::mem::replace(&mut app.event_cursor, 0);
+    if let Err(err) = refresh_all(client, app) {
+        client.remote = previous;
+        app.event_cursor = previous_cursor;
+        app.set_status(format!("Connect failed: {err}"));
+        return Ok(());
+    }
+    // Sessions and cursors of one backend mean nothing to another.
+    app.feed.clear();
+    app.selected_execution = 0;
+    app.selected_session = 0;
+    app.selected_consent = 0;
+    app.selected_audit = 0;
+    app.selected_mcp = 0;
+    load_selected_session(client, app)?;
+    app.attached_to = client.remote.clone();
+    app.set_status(match &client.remote {
+        Some(addr) => format!("Attached to {addr}"),
+        None => "Using the in-process agent".to_string(),
+    });
+    Ok(())
+}
+
+fn approve_selected_consent(client: &mut Backend, app: &mut TuiApp) -> Result<(), String> {
     let Some(consent) = app.consents.get(app.selected_consent).cloned() else {
         app.set_status("No pending consent selected");
         return Ok(());
     };
-    let response: ChatResponse = match local_call(client, "consent.approve", json!({ "consent_id": consent.consent_id })) {
+    let response: ChatResponse = match backend_call(client, "consent.approve", json!({ "consent_id": consent.consent_id })) {
         Ok(r) => r,
         Err(err) => {
             refresh_consents(client, app)?;
@@ -608,6 +1882,7 @@ fn approve_selected_consent(client: &mut JsonRpcClient<AgentService>, app: &mut
     app.last_chat_response = Some(response.clone());
     app.feed.push(response.to_execution_feed_item(None));
     app.selected_execution = app.feed.len().saturating_sub(1);
+    app.feed_scroll = 0;
     load_selected_session(client, app)?;
     refresh_consents(client, app)?;
     refresh_audit(client, app)?;
@@ -615,12 +1890,47 @@ fn approve_selected_consent(client: &mut JsonRpcClient<AgentService>, app: &mut
     Ok(())
 }
 
-fn deny_selected_consent(client: &mut JsonRpcClient<AgentService>, app: &mut TuiApp) -> Result<(), String> {
+fn approve_selected_consent_with_edits(
+    client: &mut Backend,
+    app: &mut TuiApp,
+    consent_id: &str,
+    edits: BTreeMap<usize, Map<String, Value>>,
+) -> Result<(), String> {
+    let edits = edits
+        .into_iter()
+        .map(|(action_index, fields)| {
+            json!({
+                "action_index": action_index,
+                "arguments_json": Value::Object(fields).to_string(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let params = json!({ "consent_id": consent_id, "edits": edits });
+    let response: ChatResponse = match backend_call(client, "consent.approve_with_args", params) {
+        Ok(r) => r,
+        Err(err) => {
+            refresh_consents(client, app)?;
+            app.set_status(format!("Approve failed: {}", humanize_consent_error(&err)));
+            return Ok(());
+        }
+    };
+    app.last_chat_response = Some(response.clone());
+    app.feed.push(response.to_execution_feed_item(None));
+    app.selected_execution = app.feed.len().saturating_sub(1);
+    app.feed_scroll = 0;
+    load_selected_session(client, app)?;
+    refresh_consents(client, app)?;
+    refresh_audit(client, app)?;
+    app.set_status("Consent approved with edited arguments");
+    Ok(())
+}
+
+fn deny_selected_consent(client: &mut Backend, app: &mut TuiApp) -> Result<(), String> {
     let Some(consent) = app.consents.get(app.selected_consent).cloned() else {
         app.set_status("No pending consent selected");
         return Ok(());
     };
-    let response: ChatResponse = match local_call(client, "consent.deny", json!({ "consent_id": consent.consent_id })) {
+    let response: ChatResponse = match backend_call(client, "consent.deny", json!({ "consent_id": consent.consent_id })) {
         Ok(r) => r,
         Err(err) => {
             refresh_consents(client, app)?;
@@ -631,6 +1941,7 @@ fn deny_selected_consent(client: &mut JsonRpcClient<AgentService>, app: &mut Tui
     app.last_chat_response = Some(response.clone());
     app.feed.push(response.to_execution_feed_item(None));
     app.selected_execution = app.feed.len().saturating_sub(1);
+    app.feed_scroll = 0;
     refresh_consents(client, app)?;
     refresh_audit(client, app)?;
     app.set_status("Consent denied");
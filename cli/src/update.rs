@@ -0,0 +1,114 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use agent::AgentService;
+use serde::Serialize;
+use serde_json::Value;
+
+/// GitHub's "latest release" endpoint; `update_url` in `cli.toml` points elsewhere, e.g. an
+/// internal mirror serving the same JSON (`tag_name`, `html_url`).
+pub const DEFAULT_RELEASES_URL: &str = "https://api.github.com/repos/kyambuthia/cmnd-n-ctrl/releases/latest";
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+/// The startup check must never make a command noticeably slower.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(2);
+const STARTUP_INTERVAL_SECS: u64 = 24 * 60 * 60;
+const STAMP_FILE: &str = "update-check";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UpdateStatus {
+    pub current: String,
+    pub latest: String,
+    pub update_available: bool,
+    pub release_url: Option<String>,
+}
+
+pub fn current_version() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
+
+pub fn check(url: &str) -> Result<UpdateStatus, String> {
+    check_with_timeout(url, CHECK_TIMEOUT)
+}
+
+fn check_with_timeout(url: &str, timeout: Duration) -> Result<UpdateStatus, String> {
+    let body = ureq::get(url)
+        .timeout(timeout)
+        .set("User-Agent", concat!("cmnd-n-ctrl-cli/", env!("CARGO_PKG_VERSION")))
+        .set("Accept", "application/json")
+        .call()
+        .map_err(|err| format!("release check failed: {err}"))?
+        .into_string()
+        .map_err(|err| format!("release check failed: {err}"))?;
+    let release: Value = serde_json::from_str(&body).map_err(|err| format!("unexpected release response: {err}"))?;
+    let latest = release
+        .get("tag_name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| "unexpected release response: missing tag_name".to_string())?;
+    let latest = latest.trim_start_matches('v').to_string();
+    Ok(UpdateStatus {
+        update_available: is_newer(&latest, current_version()),
+        current: current_version().to_string(),
+        latest,
+        release_url: release.get("html_url").and_then(Value::as_str).map(str::to_string),
+    })
+}
+
+/// Compares dotted numeric versions; a pre-release (`1.2.0-rc1`) is never offered.
+fn is_newer(latest: &str, current: &str) -> bool {
+    if latest.contains('-') {
+        return false;
+    }
+    let parse = |v: &str| {
+        v.split('-')
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse::<u64>().unwrap_or(0))
+            .collect::<Vec<_>>()
+    };
+    let (mut latest, mut current) = (parse(latest), parse(current));
+    let len = latest.len().max(current.len());
+    latest.resize(len, 0);
+    current.resize(len, 0);
+    latest > current
+}
+
+pub fn upgrade_instructions(status: &UpdateStatus) -> String {
+    let mut out = format!("cli {} is available (you have {}).\n", status.latest, status.current);
+    if let Some(url) = &status.release_url {
+        out.push_str(&format!("  download: {url}\n"));
+    }
+    out.push_str(&format!(
+        "  or build it: cargo install --git https://github.com/kyambuthia/cmnd-n-ctrl --tag v{} cli",
+        status.latest
+    ));
+    out
+}
+
+fn stamp_path() -> PathBuf {
+    AgentService::default_storage_dir().join(STAMP_FILE)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Opt-in check run before a command: at most once a day, with a short timeout, and silent
+/// on failure. Returns the notice to print when a newer release exists.
+pub fn startup_notice(url: &str) -> Option<String> {
+    let last = fs::read_to_string(stamp_path()).ok().and_then(|raw| raw.trim().parse::<u64>().ok());
+    let now = now_secs();
+    if last.is_some_and(|last| now.saturating_sub(last) < STARTUP_INTERVAL_SECS) {
+        return None;
+    }
+    // Stamped before checking so an offline machine does not retry on every command.
+    let _ = fs::write(stamp_path(), now.to_string());
+    let status = check_with_timeout(url, STARTUP_TIMEOUT).ok()?;
+    status.update_available.then(|| {
+        format!(
+            "note: cli {} is available (you have {}); run `cli update check` for upgrade instructions",
+            status.latest, status.current
+        )
+    })
+}
@@ -0,0 +1,373 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use zip::write::SimpleFileOptions;
+
+/// Extraction stops past this many bytes so a small archive cannot fill the disk.
+pub const MAX_EXTRACT_BYTES: u64 = 512 * 1024 * 1024;
+pub const MAX_ARCHIVE_ENTRIES: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// Explicit `format` wins; otherwise inferred from `.zip` / `.tar.gz` / `.tgz`.
+    pub fn resolve(format: Option<&str>, path: &Path) -> Result<Self, String> {
+        match format.map(|f| f.trim().to_ascii_lowercase()).as_deref() {
+            Some("zip") => return Ok(Self::Zip),
+            Some("tar.gz") | Some("tgz") => return Ok(Self::TarGz),
+            Some(other) => return Err(format!("unsupported_archive_format:{other}")),
+            None => {}
+        }
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        if name.ends_with(".zip") {
+            Ok(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Ok(Self::TarGz)
+        } else {
+            Err("unknown_archive_format".to_string())
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            Self::TarGz => "tar.gz",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ArchiveSummary {
+    /// `/`-separated entry names, in archive order.
+    pub entries: Vec<String>,
+    pub bytes: u64,
+}
+
+/// Packs `sources` (files or directories) into `dest`, naming entries relative to `base`.
+pub fn create_archive(base: &Path, sources: &[PathBuf], dest: &Path, format: ArchiveFormat) -> Result<ArchiveSummary, String> {
+    let mut files = Vec::new();
+    for source in sources {
+        collect_files(source, &mut files).map_err(|err| format!("archive_source_failed:{}:{err}", source.display()))?;
+    }
+    files.retain(|f| f != dest);
+    if files.is_empty() {
+        return Err("archive_sources_empty".to_string());
+    }
+    if files.len() > MAX_ARCHIVE_ENTRIES {
+        return Err("archive_too_many_entries".to_string());
+    }
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).map_err(|err| format!("archive_create_failed:{err}"))?;
+    }
+    let out = fs::File::create(dest).map_err(|err| format!("archive_create_failed:{err}"))?;
+    let mut summary = ArchiveSummary::default();
+    let write_err = |err: &dyn std::fmt::Display| format!("archive_write_failed:{err}");
+    match format {
+        ArchiveFormat::Zip => {
+            let mut zip = zip::ZipWriter::new(out);
+            let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+            for file in &files {
+                let name = entry_name(base, file)?;
+                zip.start_file(name.as_str(), options).map_err(|e| write_err(&e))?;
+                let mut input = fs::File::open(file).map_err(|e| write_err(&e))?;
+                summary.bytes += io::copy(&mut input, &mut zip).map_err(|e| write_err(&e))?;
+                summary.entries.push(name);
+            }
+            zip.finish().map_err(|e| write_err(&e))?;
+        }
+        ArchiveFormat::TarGz => {
+            let mut tar = tar::Builder::new(GzEncoder::new(out, Compression::default()));
+            for file in &files {
+                let name = entry_name(base, file)?;
+                let mut input = fs::File::open(file).map_err(|e| write_err(&e))?;
+                tar.append_file(&name, &mut input).map_err(|e| write_err(&e))?;
+                summary.bytes += input.metadata().map(|m| m.len()).unwrap_or(0);
+                summary.entries.push(name);
+            }
+            let gz = tar.into_inner().map_err(|e| write_err(&e))?;
+            gz.finish().and_then(|mut f| f.flush()).map_err(|e| write_err(&e))?;
+        }
+    }
+    Ok(summary)
+}
+
+/// Unpacks into `dest`. Entries that are absolute, contain `..`, or are links are rejected
+/// before anything is written, and an entry that would pass through a symlink already under
+/// `dest` is refused, so a hostile archive cannot escape the destination. If an entry fails,
+/// the files and directories this call created are removed again; files it overwrote keep
+/// their new contents.
+pub fn extract_archive(archive: &Path, dest: &Path, format: ArchiveFormat, overwrite: bool) -> Result<ArchiveSummary, String> {
+    let input = fs::File::open(archive).map_err(|err| format!("archive_open_failed:{err}"))?;
+    let read_err = |err: &dyn std::fmt::Display| format!("archive_read_failed:{err}");
+    let mut planned = Vec::new();
+    match format {
+        ArchiveFormat::Zip => {
+            let mut zip = zip::ZipArchive::new(input).map_err(|e| read_err(&e))?;
+            check_entry_count(zip.len())?;
+            for i in 0..zip.len() {
+                let entry = zip.by_index(i).map_err(|e| read_err(&e))?;
+                let kind = if entry.is_dir() {
+                    EntryKind::Dir
+                } else if entry.is_symlink() {
+                    EntryKind::Link
+                } else {
+                    EntryKind::File
+                };
+                planned.push((safe_entry_path(entry.name(), kind)?, kind));
+            }
+            preflight(dest, &planned, overwrite)?;
+            let mut summary = ArchiveSummary::default();
+            let mut created = Vec::new();
+            let result = (|| {
+                for (i, (relative, kind)) in planned.iter().enumerate() {
+                    let mut entry = zip.by_index(i).map_err(|e| read_err(&e))?;
+                    summary.bytes += write_entry(dest, relative, *kind, &mut entry, summary.bytes, &mut created)?;
+                    summary.entries.push(relative_label(relative));
+                }
+                Ok(())
+            })();
+            finish_extraction(result, summary, &created)
+        }
+        ArchiveFormat::TarGz => {
+            // Tar streams can't be rewound, so validate in one pass and unpack in a second.
+            let mut tar = tar::Archive::new(GzDecoder::new(input));
+            for entry in tar.entries().map_err(|e| read_err(&e))? {
+                let entry = entry.map_err(|e| read_err(&e))?;
+                let kind = match entry.header().entry_type() {
+                    t if t.is_dir() => EntryKind::Dir,
+                    t if t.is_file() => EntryKind::File,
+                    _ => EntryKind::Link,
+                };
+                let name = entry.path().map_err(|e| read_err(&e))?.to_string_lossy().to_string();
+                planned.push((safe_entry_path(&name, kind)?, kind));
+                check_entry_count(planned.len())?;
+            }
+            preflight(dest, &planned, overwrite)?;
+            let input = fs::File::open(archive).map_err(|err| format!("archive_open_failed:{err}"))?;
+            let mut tar = tar::Archive::new(GzDecoder::new(input));
+            let mut summary = ArchiveSummary::default();
+            let mut created = Vec::new();
+            let result = (|| {
+                for ((relative, kind), entry) in planned.iter().zip(tar.entries().map_err(|e| read_err(&e))?) {
+                    let mut entry = entry.map_err(|e| read_err(&e))?;
+                    summary.bytes += write_entry(dest, relative, *kind, &mut entry, summary.bytes, &mut created)?;
+                    summary.entries.push(relative_label(relative));
+                }
+                Ok(())
+            })();
+            finish_extraction(result, summary, &created)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EntryKind {
+    File,
+    Dir,
+    Link,
+}
+
+fn check_entry_count(count: usize) -> Result<(), String> {
+    if count > MAX_ARCHIVE_ENTRIES {
+        return Err("archive_too_many_entries".to_string());
+    }
+    Ok(())
+}
+
+fn safe_entry_path(name: &str, kind: EntryKind) -> Result<PathBuf, String> {
+    if kind == EntryKind::Link {
+        return Err(format!("archive_link_entry_rejected:{name}"));
+    }
+    let path = Path::new(name);
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => out.push(part),
+            Component::CurDir => {}
+            _ => return Err(format!("archive_entry_outside_destination:{name}")),
+        }
+    }
+    if out.as_os_str().is_empty() && kind == EntryKind::File {
+        return Err(format!("archive_entry_outside_destination:{name}"));
+    }
+    Ok(out)
+}
+
+fn preflight(dest: &Path, planned: &[(PathBuf, EntryKind)], overwrite: bool) -> Result<(), String> {
+    if overwrite {
+        return Ok(());
+    }
+    for (relative, kind) in planned {
+        if *kind == EntryKind::File && dest.join(relative).exists() {
+            return Err(format!("archive_entry_exists:{}", relative_label(relative)));
+        }
+    }
+    Ok(())
+}
+
+/// Rolls back a failed extraction by removing what it created, newest first.
+fn finish_extraction(result: Result<(), String>, summary: ArchiveSummary, created: &[PathBuf]) -> Result<ArchiveSummary, String> {
+    if let Err(err) = result {
+        for path in created.iter().rev() {
+            let _ = if path.is_dir() { fs::remove_dir(path) } else { fs::remove_file(path) };
+        }
+        return Err(err);
+    }
+    Ok(summary)
+}
+
+/// Writes one entry, recording every path it creates in `created`.
+fn write_entry(
+    dest: &Path,
+    relative: &Path,
+    kind: EntryKind,
+    entry: &mut dyn Read,
+    written: u64,
+    created: &mut Vec<PathBuf>,
+) -> Result<u64, String> {
+    let target = dest.join(relative);
+    let write_err = |err: io::Error| format!("archive_extract_failed:{}:{err}", relative.display());
+    if !dest.exists() {
+        created.push(dest.to_path_buf());
+    }
+    // Lexically safe names can still leave `dest` through a symlink already on disk.
+    let mut path = dest.to_path_buf();
+    for component in relative.components() {
+        path.push(component);
+        match fs::symlink_metadata(&path) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                return Err(format!("archive_entry_through_symlink:{}", relative_label(relative)));
+            }
+            Ok(_) => {}
+            Err(err) if err.kind() == io::ErrorKind::NotFound => created.push(path.clone()),
+            Err(err) => return Err(write_err(err)),
+        }
+    }
+    if kind == EntryKind::Dir {
+        fs::create_dir_all(&target).map_err(write_err)?;
+        return Ok(0);
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(write_err)?;
+    }
+    let budget = MAX_EXTRACT_BYTES.saturating_sub(written);
+    let mut out = fs::File::create(&target).map_err(write_err)?;
+    let copied = io::copy(&mut entry.take(budget + 1), &mut out).map_err(write_err)?;
+    if copied > budget {
+        drop(out);
+        let _ = fs::remove_file(&target);
+        return Err("archive_extract_size_limit".to_string());
+    }
+    Ok(copied)
+}
+
+fn collect_files(path: &Path, out: &mut Vec<PathBuf>) -> io::Result<()> {
+    let meta = fs::symlink_metadata(path)?;
+    if meta.is_dir() {
+        let mut children = fs::read_dir(path)?.flatten().map(|e| e.path()).collect::<Vec<_>>();
+        children.sort();
+        for child in children {
+            collect_files(&child, out)?;
+        }
+    } else if meta.is_file() {
+        out.push(path.to_path_buf());
+    }
+    Ok(())
+}
+
+fn entry_name(base: &Path, file: &Path) -> Result<String, String> {
+    let relative = file
+        .strip_prefix(base)
+        .map_err(|_| format!("archive_source_outside_base:{}", file.display()))?;
+    Ok(relative_label(relative))
+}
+
+fn relative_label(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn archives_round_trip_in_both_formats() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::create_dir_all(root.join("dist/assets")).expect("mkdir");
+        fs::write(root.join("dist/index.html"), "<html></html>").expect("write");
+        fs::write(root.join("dist/assets/app.js"), "console.log(1)").expect("write");
+
+        for (name, format) in [("out.zip", ArchiveFormat::Zip), ("out.tar.gz", ArchiveFormat::TarGz)] {
+            let archive = root.join(name);
+            let created = create_archive(root, &[root.join("dist")], &archive, format).expect("create");
+            assert_eq!(created.entries, vec!["dist/assets/app.js", "dist/index.html"]);
+            assert_eq!(ArchiveFormat::resolve(None, &archive), Ok(format));
+
+            let dest = root.join(format!("unpacked-{}", format.label()));
+            let extracted = extract_archive(&archive, &dest, format, false).expect("extract");
+            assert_eq!(extracted.bytes, 27);
+            assert_eq!(fs::read_to_string(dest.join("dist/index.html")).expect("read"), "<html></html>");
+            assert!(extract_archive(&archive, &dest, format, false)
+                .expect_err("exists")
+                .starts_with("archive_entry_exists"));
+        }
+    }
+
+    #[test]
+    fn extraction_rejects_path_traversal() {
+        let dir = tempdir().expect("tempdir");
+        let archive = dir.path().join("evil.zip");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&archive).expect("create"));
+        zip.start_file("../escape.txt", SimpleFileOptions::default()).expect("entry");
+        zip.write_all(b"nope").expect("write");
+        zip.finish().expect("finish");
+
+        let dest = dir.path().join("out");
+        let err = extract_archive(&archive, &dest, ArchiveFormat::Zip, false).expect_err("traversal");
+        assert!(err.starts_with("archive_entry_outside_destination"));
+        assert!(!dir.path().join("escape.txt").exists());
+        assert!(!dest.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn extraction_refuses_existing_symlinks_and_cleans_up() {
+        let dir = tempdir().expect("tempdir");
+        let (dest, outside) = (dir.path().join("out"), dir.path().join("outside"));
+        fs::create_dir_all(&dest).expect("mkdir");
+        fs::create_dir_all(&outside).expect("mkdir");
+        std::os::unix::fs::symlink(&outside, dest.join("link")).expect("symlink");
+        let archive = dir.path().join("evil.tar.gz");
+        let mut tar = tar::Builder::new(GzEncoder::new(fs::File::create(&archive).expect("create"), Compression::default()));
+        for name in ["fresh/one.txt", "link/pwn.txt"] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(4);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, name, &b"data"[..]).expect("entry");
+        }
+        tar.into_inner().expect("tar").finish().expect("gzip");
+
+        let err = extract_archive(&archive, &dest, ArchiveFormat::TarGz, false).expect_err("symlink");
+        assert_eq!(err, "archive_entry_through_symlink:link/pwn.txt");
+        assert!(!outside.join("pwn.txt").exists());
+        assert!(!dest.join("fresh").exists(), "partial extraction left behind");
+        assert!(dest.join("link").exists());
+    }
+}
@@ -0,0 +1,297 @@
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+/// Selenium/geckodriver's default port; chromedriver listens on 9515 unless given `--port`.
+pub const DEFAULT_WEBDRIVER_URL: &str = "http://127.0.0.1:4444";
+pub const MAX_PAGE_TEXT_CHARS: usize = 20_000;
+/// Per-request budget when the call has no deadline of its own.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// W3C WebDriver key of an element reference.
+const ELEMENT_KEY: &str = "element-6066-11e4-a52e-4f735466cecf";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BrowserConfig {
+    pub webdriver_url: String,
+    pub headless: bool,
+}
+
+impl Default for BrowserConfig {
+    fn default() -> Self {
+        Self {
+            webdriver_url: DEFAULT_WEBDRIVER_URL.to_string(),
+            headless: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PageInfo {
+    pub url: String,
+    pub title: String,
+}
+
+/// A WebDriver session, started on first use and kept across tool calls (and chats) until
+/// `browser.close` or drop, so a page opened by one call can be read or clicked by the next.
+#[derive(Debug)]
+pub struct WebDriverBrowser {
+    config: BrowserConfig,
+    session_id: RefCell<Option<String>>,
+}
+
+impl WebDriverBrowser {
+    pub fn new(config: BrowserConfig) -> Self {
+        Self {
+            config,
+            session_id: RefCell::new(None),
+        }
+    }
+
+    pub fn config(&self) -> &BrowserConfig {
+        &self.config
+    }
+
+    /// Only `http`/`https` URLs, so the browser can't be pointed at local files.
+    pub fn navigate(&self, url: &str, deadline: Option<Instant>) -> Result<PageInfo, String> {
+        let scheme = url.split_once("://").map(|(scheme, _)| scheme.to_ascii_lowercase());
+        if !matches!(scheme.as_deref(), Some("http" | "https")) {
+            return Err("unsupported_url: only http and https URLs can be opened".to_string());
+        }
+        self.command("POST", "url", Some(json!({ "url": url })), deadline)?;
+        self.page_info(deadline)
+    }
+
+    /// `innerText` of the first element matching `selector`, or of the whole body, cut to
+    /// `MAX_PAGE_TEXT_CHARS`. The flag reports whether it was cut.
+    pub fn read_text(&self, selector: Option<&str>, deadline: Option<Instant>) -> Result<(PageInfo, String, bool), String> {
+        let script = "const el = arguments[0] ? document.querySelector(arguments[0]) : document.body;\
+                      return el ? el.innerText : null;";
+        let text = self.command("POST", "execute/sync", Some(json!({ "script": script, "args": [selector] })), deadline)?;
+        let Some(text) = text.as_str() else {
+            return Err(format!("no_such_element:{}", selector.unwrap_or("body")));
+        };
+        let truncated = text.chars().count() > MAX_PAGE_TEXT_CHARS;
+        let text = text.chars().take(MAX_PAGE_TEXT_CHARS).collect();
+        Ok((self.page_info(deadline)?, text, truncated))
+    }
+
+    pub fn click(&self, selector: &str, deadline: Option<Instant>) -> Result<PageInfo, String> {
+        let element = self.command("POST", "element", Some(json!({ "using": "css selector", "value": selector })), deadline)?;
+        let Some(element_id) = element.get(ELEMENT_KEY).and_then(Value::as_str) else {
+            return Err(format!("no_such_element:{selector}"));
+        };
+        self.command("POST", &format!("element/{element_id}/click"), Some(json!({})), deadline)?;
+        self.page_info(deadline)
+    }
+
+    /// PNG of the current viewport.
+    pub fn screenshot(&self, deadline: Option<Instant>) -> Result<(PageInfo, Vec<u8>), String> {
+        use base64::prelude::{Engine as _, BASE64_STANDARD};
+        let encoded = self.command("GET", "screenshot", None, deadline)?;
+        let png = BASE64_STANDARD
+            .decode(encoded.as_str().unwrap_or_default())
+            .map_err(|err| format!("invalid_screenshot:{err}"))?;
+        Ok((self.page_info(deadline)?, png))
+    }
+
+    /// Ends the session; returns whether one was open.
+    pub fn close(&self, deadline: Option<Instant>) -> Result<bool, String> {
+        let Some(session_id) = self.session_id.borrow_mut().take() else {
+            return Ok(false);
+        };
+        self.request("DELETE", &format!("session/{session_id}"), None, deadline)?;
+        Ok(true)
+    }
+
+    fn page_info(&self, deadline: Option<Instant>) -> Result<PageInfo, String> {
+        let text = |value: Value| value.as_str().unwrap_or_default().to_string();
+        Ok(PageInfo {
+            url: text(self.command("GET", "url", None, deadline)?),
+            title: text(self.command("GET", "title", None, deadline)?),
+        })
+    }
+
+    /// Runs a session command, starting a session first if needed. A session the driver no
+    /// longer knows (the browser was closed by hand) is replaced once.
+    fn command(&self, method: &str, path: &str, body: Option<Value>, deadline: Option<Instant>) -> Result<Value, String> {
+        for attempt in 0..2 {
+            let session_id = self.session(deadline)?;
+            match self.request(method, &format!("session/{session_id}/{path}"), body.clone(), deadline) {
+                Err(err) if attempt == 0 && err.starts_with("webdriver:invalid session id") => {
+                    self.session_id.borrow_mut().take();
+                }
+                other => return other,
+            }
+        }
+        unreachable!("the second attempt always returns")
+    }
+
+    fn session(&self, deadline: Option<Instant>) -> Result<String, String> {
+        if let Some(session_id) = self.session_id.borrow().clone() {
+            return Ok(session_id);
+        }
+        let mut always_match = json!({});
+        if self.config.headless {
+            // Each driver ignores the other vendors' options.
+            always_match = json!({
+                "goog:chromeOptions": { "args": ["--headless=new"] },
+                "moz:firefoxOptions": { "args": ["-headless"] }
+            });
+        }
+        let created = self.request(
+            "POST",
+            "session",
+            Some(json!({ "capabilities": { "alwaysMatch": always_match } })),
+            deadline,
+        )?;
+        let session_id = created
+            .get("sessionId")
+            .and_then(Value::as_str)
+            .ok_or_else(|| "webdriver:session not created: missing sessionId".to_string())?
+            .to_string();
+        *self.session_id.borrow_mut() = Some(session_id.clone());
+        Ok(session_id)
+    }
+
+    fn request(&self, method: &str, path: &str, body: Option<Value>, deadline: Option<Instant>) -> Result<Value, String> {
+        let timeout = deadline.map_or(REQUEST_TIMEOUT, |d| d.saturating_duration_since(Instant::now()));
+        if timeout.is_zero() {
+            return Err(crate::traits::TOOL_TIMEOUT_ERROR.to_string());
+        }
+        let url = format!("{}/{path}", self.config.webdriver_url.trim_end_matches('/'));
+        let response = webdriver_request(method, &url, body, timeout)?;
+        let value = response.get("value").cloned().unwrap_or(Value::Null);
+        match value.get("error").and_then(Value::as_str) {
+            Some(error) => Err(format!(
+                "webdriver:{error}: {}",
+                value.get("message").and_then(Value::as_str).unwrap_or_default()
+            )),
+            None => Ok(value),
+        }
+    }
+}
+
+impl Drop for WebDriverBrowser {
+    fn drop(&mut self) {
+        let _ = self.close(Some(Instant::now() + Duration::from_secs(5)));
+    }
+}
+
+/// Sends one WebDriver request and returns the response body. Error statuses still carry a
+/// `{"value": {"error": ...}}` body, so those are returned as bodies too.
+#[cfg(feature = "browser")]
+fn webdriver_request(method: &str, url: &str, body: Option<Value>, timeout: Duration) -> Result<Value, String> {
+    let request = ureq::request(method, url).timeout(timeout);
+    let response = match body {
+        Some(body) => request.send_json(body),
+        None => request.call(),
+    };
+    let response = match response {
+        Ok(response) => response,
+        Err(ureq::Error::Status(_, response)) => response,
+        Err(ureq::Error::Transport(err)) => return Err(format!("webdriver_unreachable:{err}")),
+    };
+    response.into_json::<Value>().map_err(|err| format!("webdriver_invalid_response:{err}"))
+}
+
+#[cfg(not(feature = "browser"))]
+fn webdriver_request(_method: &str, _url: &str, _body: Option<Value>, _timeout: Duration) -> Result<Value, String> {
+    Err("browser_not_supported: build with the browser feature".to_string())
+}
+
+#[cfg(all(test, feature = "browser"))]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    /// A WebDriver stand-in answering each request from `respond(method, path, body)`.
+    fn fake_driver(respond: fn(&str, &str, &Value) -> Value) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let url = format!("http://{}", listener.local_addr().expect("addr"));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&seen);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.expect("accept");
+                let mut reader = BufReader::new(stream.try_clone().expect("clone"));
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).expect("request line");
+                let mut length = 0;
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).expect("header");
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(value) = header.to_ascii_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap_or(0);
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).expect("body");
+                let body = serde_json::from_slice(&body).unwrap_or(Value::Null);
+                let mut parts = request_line.split_whitespace();
+                let (method, path) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+                log.lock().expect("log").push(format!("{method} {path}"));
+                let reply = respond(method, path, &body).to_string();
+                let status = if reply.contains("\"error\"") { "404 Not Found" } else { "200 OK" };
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{reply}",
+                    reply.len()
+                );
+            }
+        });
+        (url, seen)
+    }
+
+    fn driver(method: &str, path: &str, body: &Value) -> Value {
+        match (method, path) {
+            ("POST", "/session") => json!({ "value": { "sessionId": "s1", "capabilities": {} } }),
+            ("GET", "/session/s1/url") => json!({ "value": "https://example.com/" }),
+            ("GET", "/session/s1/title") => json!({ "value": "Example Domain" }),
+            ("POST", "/session/s1/execute/sync") if body["args"][0] == "#missing" => json!({ "value": null }),
+            ("POST", "/session/s1/execute/sync") => json!({ "value": "Example Domain\nMore information..." }),
+            ("POST", "/session/s1/element") if body["value"] == "a" => {
+                json!({ "value": { ELEMENT_KEY: "e1" } })
+            }
+            ("POST", "/session/s1/element") => {
+                json!({ "value": { "error": "no such element", "message": "Unable to locate element" } })
+            }
+            ("GET", "/session/s1/screenshot") => json!({ "value": "iVBORw0KGgo=" }),
+            _ => json!({ "value": null }),
+        }
+    }
+
+    #[test]
+    fn drives_one_session_across_commands() {
+        let (url, seen) = fake_driver(driver);
+        let browser = WebDriverBrowser::new(BrowserConfig {
+            webdriver_url: url,
+            headless: true,
+        });
+        let page = browser.navigate("https://example.com", None).expect("navigate");
+        assert_eq!(page.title, "Example Domain");
+        let (_, text, truncated) = browser.read_text(None, None).expect("read");
+        assert!(text.starts_with("Example Domain") && !truncated);
+        assert_eq!(browser.read_text(Some("#missing"), None), Err("no_such_element:#missing".to_string()));
+        browser.click("a", None).expect("click");
+        assert_eq!(
+            browser.click("button.buy", None),
+            Err("webdriver:no such element: Unable to locate element".to_string())
+        );
+        let (_, png) = browser.screenshot(None).expect("screenshot");
+        assert!(png.starts_with(b"\x89PNG"));
+        assert_eq!(browser.navigate("file:///etc/passwd", None), Err("unsupported_url: only http and https URLs can be opened".to_string()));
+        assert_eq!(browser.close(None), Ok(true));
+        assert_eq!(browser.close(None), Ok(false));
+
+        let seen = seen.lock().expect("log");
+        assert_eq!(seen.iter().filter(|r| r.as_str() == "POST /session").count(), 1);
+        assert!(seen.contains(&"POST /session/s1/element/e1/click".to_string()));
+        assert_eq!(seen.last().map(String::as_str), Some("DELETE /session/s1"));
+    }
+}
@@ -0,0 +1,829 @@
+use serde_json::{json, Value};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Calendar file used when a call names neither a `path` nor a CalDAV `calendar`.
+pub const DEFAULT_CALENDAR_PATH: &str = "calendar.ics";
+pub const DEFAULT_EVENT_LIMIT: usize = 50;
+pub const MAX_EVENT_LIMIT: usize = 500;
+const SECS_PER_DAY: i64 = 86_400;
+
+/// A DTSTART/DTEND value. `seconds` counts wall-clock seconds since the epoch: exact for UTC
+/// times, and the local time read as if it were UTC for floating and `TZID` times, which is
+/// how ranges compare them (there is no time zone database here).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EventTime {
+    pub seconds: i64,
+    pub all_day: bool,
+    pub utc: bool,
+}
+
+impl EventTime {
+    /// `YYYY-MM-DD` (all day) or `YYYY-MM-DDTHH:MM[:SS]` with an optional `Z` or `±HH:MM`
+    /// offset; offset times are converted to UTC, others stay floating.
+    pub fn parse_input(text: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid_datetime:{text}");
+        let text = text.trim();
+        let (date, time) = match text.split_once(['T', ' ']) {
+            Some((date, time)) => (date, Some(time)),
+            None => (text, None),
+        };
+        let mut parts = date.splitn(3, '-').map(|p| p.parse::<i64>().ok());
+        let (Some(Some(year)), Some(Some(month)), Some(Some(day))) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+        let days = days_from_civil(year, month, day).ok_or_else(invalid)?;
+        let Some(time) = time else {
+            return Ok(Self {
+                seconds: days * SECS_PER_DAY,
+                all_day: true,
+                utc: false,
+            });
+        };
+        let (clock, offset) = if let Some(clock) = time.strip_suffix(['Z', 'z']) {
+            (clock, Some(0))
+        } else if let Some(pos) = time.rfind(['+', '-']) {
+            let (clock, offset) = time.split_at(pos);
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            let digits = offset[1..].replace(':', "");
+            let (hours, minutes) = (digits.get(..2), digits.get(2..).filter(|m| !m.is_empty()).unwrap_or("00"));
+            let hours = hours.and_then(|h| h.parse::<i64>().ok()).ok_or_else(invalid)?;
+            let minutes = minutes.parse::<i64>().map_err(|_| invalid())?;
+            (clock, Some(sign * (hours * 3_600 + minutes * 60)))
+        } else {
+            (time, None)
+        };
+        let mut fields = clock.split(':').map(|p| p.parse::<i64>().ok());
+        let (Some(Some(hour)), Some(Some(minute))) = (fields.next(), fields.next()) else {
+            return Err(invalid());
+        };
+        let second = fields.next().flatten().unwrap_or(0);
+        if hour > 23 || minute > 59 || second > 60 {
+            return Err(invalid());
+        }
+        let wall = days * SECS_PER_DAY + hour * 3_600 + minute * 60 + second;
+        Ok(Self {
+            seconds: wall - offset.unwrap_or(0),
+            all_day: false,
+            utc: offset.is_some(),
+        })
+    }
+
+    fn parse_ics(value: &str, date_only: bool) -> Option<Self> {
+        let value = value.trim();
+        let date = |text: &str| {
+            let number = |range: std::ops::Range<usize>| text.get(range).and_then(|p| p.parse::<i64>().ok());
+            days_from_civil(number(0..4)?, number(4..6)?, number(6..8)?)
+        };
+        if date_only || value.len() == 8 {
+            return Some(Self {
+                seconds: date(value)? * SECS_PER_DAY,
+                all_day: true,
+                utc: false,
+            });
+        }
+        let (day, clock) = value.split_once('T')?;
+        let utc = clock.ends_with('Z');
+        let clock = clock.trim_end_matches('Z');
+        let number = |range: std::ops::Range<usize>| clock.get(range).and_then(|p| p.parse::<i64>().ok());
+        Some(Self {
+            seconds: date(day)? * SECS_PER_DAY + number(0..2)? * 3_600 + number(2..4)? * 60 + number(4..6).unwrap_or(0),
+            all_day: false,
+            utc,
+        })
+    }
+
+    /// ISO 8601: `2026-10-17`, `2026-10-17T09:00:00Z` or floating `2026-10-17T09:00:00`.
+    pub fn iso(&self) -> String {
+        let (year, month, day) = civil_from_days(self.seconds.div_euclid(SECS_PER_DAY));
+        if self.all_day {
+            return format!("{year:04}-{month:02}-{day:02}");
+        }
+        let secs = self.seconds.rem_euclid(SECS_PER_DAY);
+        format!(
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}{}",
+            secs / 3_600,
+            secs % 3_600 / 60,
+            secs % 60,
+            if self.utc { "Z" } else { "" }
+        )
+    }
+
+    fn ics_property(&self, name: &str) -> String {
+        let (year, month, day) = civil_from_days(self.seconds.div_euclid(SECS_PER_DAY));
+        if self.all_day {
+            return format!("{name};VALUE=DATE:{year:04}{month:02}{day:02}");
+        }
+        let secs = self.seconds.rem_euclid(SECS_PER_DAY);
+        format!(
+            "{name}:{year:04}{month:02}{day:02}T{:02}{:02}{:02}{}",
+            secs / 3_600,
+            secs % 3_600 / 60,
+            secs % 60,
+            if self.utc { "Z" } else { "" }
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CalendarEvent {
+    pub uid: String,
+    pub summary: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub start: Option<EventTime>,
+    pub end: Option<EventTime>,
+    pub tzid: Option<String>,
+    /// Kept as written; recurrences are not expanded.
+    pub rrule: Option<String>,
+    /// Minutes before the start of the first `VALARM`.
+    pub reminder_minutes: Option<i64>,
+}
+
+impl CalendarEvent {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "uid": self.uid,
+            "summary": self.summary,
+            "start": self.start.map(|t| t.iso()),
+            "end": self.end.map(|t| t.iso()),
+            "all_day": self.start.is_some_and(|t| t.all_day),
+            "tzid": self.tzid,
+            "location": self.location,
+            "description": self.description,
+            "recurrence": self.rrule,
+            "recurring": self.rrule.is_some(),
+            "reminder_minutes": self.reminder_minutes,
+        })
+    }
+
+    /// Whether the event touches `[from, to)`. A recurring event counts once it has started
+    /// before `to`, since later occurrences aren't expanded.
+    fn overlaps(&self, from: Option<i64>, to: Option<i64>) -> bool {
+        let Some(start) = self.start else {
+            return false;
+        };
+        let end = self.end.map_or(
+            start.seconds + if start.all_day { SECS_PER_DAY } else { 0 },
+            |end| end.seconds,
+        );
+        let after_from = self.rrule.is_some() || from.is_none_or(|from| end > from || start.seconds >= from);
+        after_from && to.is_none_or(|to| start.seconds < to)
+    }
+
+    fn to_ics(&self) -> String {
+        let mut lines = vec![
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}", escape_text(&self.uid)),
+            format!("DTSTAMP:{}", utc_stamp(now_unix_seconds())),
+        ];
+        if let Some(start) = self.start {
+            lines.push(start.ics_property("DTSTART"));
+        }
+        if let Some(end) = self.end {
+            lines.push(end.ics_property("DTEND"));
+        }
+        lines.push(format!("SUMMARY:{}", escape_text(&self.summary)));
+        if let Some(location) = &self.location {
+            lines.push(format!("LOCATION:{}", escape_text(location)));
+        }
+        if let Some(description) = &self.description {
+            lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+        }
+        if let Some(minutes) = self.reminder_minutes {
+            lines.extend([
+                "BEGIN:VALARM".to_string(),
+                "ACTION:DISPLAY".to_string(),
+                format!("DESCRIPTION:{}", escape_text(&self.summary)),
+                format!("TRIGGER:-PT{minutes}M"),
+                "END:VALARM".to_string(),
+            ]);
+        }
+        lines.push("END:VEVENT".to_string());
+        lines.iter().map(|line| fold_line(line)).collect()
+    }
+}
+
+/// Filters for `calendar.list_events`.
+#[derive(Clone, Debug, Default)]
+pub struct EventQuery {
+    pub from: Option<EventTime>,
+    pub to: Option<EventTime>,
+    /// Case-insensitive match on summary, location or description.
+    pub text: Option<String>,
+    pub limit: usize,
+}
+
+impl EventQuery {
+    pub fn from_args(args: &Value) -> Result<Self, String> {
+        let time = |key: &str| {
+            args.get(key)
+                .and_then(Value::as_str)
+                .filter(|text| !text.trim().is_empty())
+                .map(EventTime::parse_input)
+                .transpose()
+        };
+        Ok(Self {
+            from: time("from")?,
+            to: time("to")?,
+            text: args
+                .get("query")
+                .and_then(Value::as_str)
+                .map(|q| q.trim().to_lowercase())
+                .filter(|q| !q.is_empty()),
+            limit: args
+                .get("limit")
+                .and_then(Value::as_u64)
+                .map_or(DEFAULT_EVENT_LIMIT, |limit| usize::try_from(limit).unwrap_or(MAX_EVENT_LIMIT))
+                .clamp(1, MAX_EVENT_LIMIT),
+        })
+    }
+
+    /// Matching events by start time, cut to `limit`, and how many matched in total.
+    pub fn apply(&self, events: Vec<CalendarEvent>) -> (Vec<CalendarEvent>, usize) {
+        let mut matching = events
+            .into_iter()
+            .filter(|event| event.overlaps(self.from.map(|t| t.seconds), self.to.map(|t| t.seconds)))
+            .filter(|event| {
+                self.text.as_ref().is_none_or(|text| {
+                    [Some(&event.summary), event.location.as_ref(), event.description.as_ref()]
+                        .into_iter()
+                        .flatten()
+                        .any(|field| field.to_lowercase().contains(text))
+                })
+            })
+            .collect::<Vec<_>>();
+        matching.sort_by_key(|event| event.start);
+        let total = matching.len();
+        matching.truncate(self.limit);
+        (matching, total)
+    }
+}
+
+/// Builds the event `calendar.create_event` describes. `end` defaults to `duration_minutes`
+/// (60) after a timed start, or the next day for an all-day one.
+pub fn event_from_args(args: &Value) -> Result<CalendarEvent, String> {
+    let text = |key: &str| args.get(key).and_then(Value::as_str).map(str::trim).filter(|v| !v.is_empty());
+    let summary = text("summary").ok_or("missing_summary")?;
+    let start = EventTime::parse_input(text("start").ok_or("missing_start")?)?;
+    let end = match text("end") {
+        Some(end) => EventTime::parse_input(end)?,
+        None if start.all_day => EventTime {
+            seconds: start.seconds + SECS_PER_DAY,
+            ..start
+        },
+        None => EventTime {
+            seconds: start.seconds + args.get("duration_minutes").and_then(Value::as_i64).unwrap_or(60) * 60,
+            ..start
+        },
+    };
+    if end.seconds < start.seconds || end.all_day != start.all_day {
+        return Err("invalid_end: must not precede start and must match its all-day form".to_string());
+    }
+    Ok(CalendarEvent {
+        uid: new_uid(),
+        summary: summary.to_string(),
+        description: text("description").map(str::to_string),
+        location: text("location").map(str::to_string),
+        start: Some(start),
+        end: Some(end),
+        tzid: None,
+        rrule: None,
+        reminder_minutes: args.get("reminder_minutes").and_then(Value::as_i64).filter(|m| *m >= 0),
+    })
+}
+
+pub fn read_events(path: &Path) -> Result<Vec<CalendarEvent>, String> {
+    let ics = fs::read_to_string(path).map_err(|err| match err.kind() {
+        std::io::ErrorKind::NotFound => "calendar_not_found".to_string(),
+        _ => format!("read_failed:{err}"),
+    })?;
+    Ok(parse_events(&ics))
+}
+
+/// Adds `event` to the calendar file at `path`, creating the file if needed, and returns the
+/// file's text before and after.
+pub fn append_event(path: &Path, event: &CalendarEvent) -> Result<(String, String), String> {
+    let before = match fs::read_to_string(path) {
+        Ok(text) => Some(text),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => return Err(format!("read_failed:{err}")),
+    };
+    let after = with_event(before.as_deref(), event)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| format!("write_failed:{err}"))?;
+    }
+    fs::write(path, &after).map_err(|err| format!("write_failed:{err}"))?;
+    Ok((before.unwrap_or_default(), after))
+}
+
+/// `calendar` (or a new calendar when `None`) with `event` inserted before its last
+/// `END:VCALENDAR`.
+pub fn with_event(calendar: Option<&str>, event: &CalendarEvent) -> Result<String, String> {
+    let Some(calendar) = calendar else {
+        return Ok(vcalendar(std::slice::from_ref(event)));
+    };
+    let end = calendar
+        .to_ascii_uppercase()
+        .rfind("END:VCALENDAR")
+        .ok_or("invalid_calendar: missing END:VCALENDAR")?;
+    let (head, tail) = calendar.split_at(end);
+    let separator = if head.is_empty() || head.ends_with('\n') { "" } else { "\r\n" };
+    Ok(format!("{head}{separator}{}{tail}", event.to_ics()))
+}
+
+pub fn vcalendar(events: &[CalendarEvent]) -> String {
+    let mut out = String::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//cmnd-n-ctrl//calendar//EN\r\n");
+    for event in events {
+        out.push_str(&event.to_ics());
+    }
+    out.push_str("END:VCALENDAR\r\n");
+    out
+}
+
+/// Every `VEVENT` in an iCalendar document. Unknown properties are ignored and events without
+/// a `DTSTART` are kept (they never match a time range).
+pub fn parse_events(ics: &str) -> Vec<CalendarEvent> {
+    let mut events = Vec::new();
+    let mut current: Option<CalendarEvent> = None;
+    let mut in_alarm = false;
+    for line in unfold(ics) {
+        let Some((head, value)) = line.split_once(':') else {
+            continue;
+        };
+        let mut params = head.split(';');
+        let name = params.next().unwrap_or_default().to_ascii_uppercase();
+        let params = params.map(str::to_ascii_uppercase).collect::<Vec<_>>();
+        match (name.as_str(), value.trim().to_ascii_uppercase().as_str()) {
+            ("BEGIN", "VEVENT") => current = Some(CalendarEvent::default()),
+            ("END", "VEVENT") => events.extend(current.take()),
+            ("BEGIN", "VALARM") => in_alarm = true,
+            ("END", "VALARM") => in_alarm = false,
+            _ => {}
+        }
+        let Some(event) = current.as_mut() else {
+            continue;
+        };
+        if in_alarm {
+            if name == "TRIGGER" && event.reminder_minutes.is_none() {
+                event.reminder_minutes = trigger_minutes(value);
+            }
+            continue;
+        }
+        let date_only = params.iter().any(|p| p == "VALUE=DATE");
+        match name.as_str() {
+            "UID" => event.uid = unescape_text(value),
+            "SUMMARY" => event.summary = unescape_text(value),
+            "DESCRIPTION" => event.description = Some(unescape_text(value)),
+            "LOCATION" => event.location = Some(unescape_text(value)),
+            "RRULE" => event.rrule = Some(value.trim().to_string()),
+            "DTSTART" | "DTEND" => {
+                let time = EventTime::parse_ics(value, date_only);
+                if name == "DTSTART" {
+                    event.start = time;
+                    event.tzid = head
+                        .split(';')
+                        .find_map(|p| p.strip_prefix("TZID=").or_else(|| p.strip_prefix("tzid=")))
+                        .map(|tz| tz.trim_matches('"').to_string());
+                } else {
+                    event.end = time;
+                }
+            }
+            _ => {}
+        }
+    }
+    events
+}
+
+/// `-PT15M`, `-PT1H30M`, `-P1D`... as minutes before the start; triggers after it are ignored.
+fn trigger_minutes(value: &str) -> Option<i64> {
+    let duration = value.trim().strip_prefix('-')?.strip_prefix('P')?;
+    let mut minutes = 0;
+    let mut number = String::new();
+    for c in duration.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => {}
+            unit => {
+                let n = number.parse::<i64>().ok()?;
+                number.clear();
+                minutes += match unit {
+                    'W' => n * 7 * 24 * 60,
+                    'D' => n * 24 * 60,
+                    'H' => n * 60,
+                    'M' => n,
+                    'S' => n / 60,
+                    _ => return None,
+                };
+            }
+        }
+    }
+    Some(minutes)
+}
+
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in ics.split('\n') {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        match (raw.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    lines
+}
+
+/// Splits `line` into CRLF-terminated lines of at most 75 octets, continuations starting with
+/// a space.
+fn fold_line(line: &str) -> String {
+    let mut out = String::with_capacity(line.len() + 8);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+    out
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+fn unescape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+pub fn new_uid() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("{nanos}-{}@cmnd-n-ctrl", std::process::id())
+}
+
+fn utc_stamp(unix_seconds: i64) -> String {
+    let mut out = String::new();
+    let (year, month, day) = civil_from_days(unix_seconds.div_euclid(SECS_PER_DAY));
+    let secs = unix_seconds.rem_euclid(SECS_PER_DAY);
+    let _ = write!(
+        out,
+        "{year:04}{month:02}{day:02}T{:02}{:02}{:02}Z",
+        secs / 3_600,
+        secs % 3_600 / 60,
+        secs % 60
+    );
+    out
+}
+
+fn now_unix_seconds() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Howard Hinnant's `days_from_civil`; `None` for an impossible month or day.
+fn days_from_civil(year: i64, month: i64, day: i64) -> Option<i64> {
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        _ => return None,
+    };
+    if !(1..=days_in_month).contains(&day) {
+        return None;
+    }
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    Some(era * 146_097 + doe - 719_468)
+}
+
+/// Howard Hinnant's `civil_from_days`: `(year, month, day)` for days since 1970-01-01, which
+/// may be negative. Also backs cron matching and build dates in the agent.
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// A CalDAV calendar collection from `calendars.caldav` in agent settings.
+#[derive(Clone, PartialEq, Eq)]
+pub struct CalDavCalendar {
+    /// Collection URL, e.g. `https://dav.example.com/calendars/me/work/`.
+    pub url: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl std::fmt::Debug for CalDavCalendar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CalDavCalendar")
+            .field("url", &self.url)
+            .field("username", &self.username)
+            .field("has_password", &self.password.is_some())
+            .finish()
+    }
+}
+
+/// Events from a `calendar-query` REPORT, narrowed server-side to the UTC-converted range.
+#[cfg(feature = "caldav")]
+pub fn caldav_list(calendar: &CalDavCalendar, query: &EventQuery, deadline: Option<Instant>) -> Result<Vec<CalendarEvent>, String> {
+    let range = match (query.from, query.to) {
+        (None, None) => String::new(),
+        (from, to) => format!(
+            "<c:time-range{}{}/>",
+            from.map(|t| format!(" start=\"{}\"", utc_stamp(t.seconds))).unwrap_or_default(),
+            to.map(|t| format!(" end=\"{}\"", utc_stamp(t.seconds))).unwrap_or_default()
+        ),
+    };
+    let body = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\
+         <c:calendar-query xmlns:d=\"DAV:\" xmlns:c=\"urn:ietf:params:xml:ns:caldav\">\
+         <d:prop><c:calendar-data/></d:prop>\
+         <c:filter><c:comp-filter name=\"VCALENDAR\"><c:comp-filter name=\"VEVENT\">{range}</c:comp-filter></c:comp-filter></c:filter>\
+         </c:calendar-query>"
+    );
+    let response = caldav_request(calendar, "REPORT", &calendar.url, deadline)
+        .and_then(|request| {
+            request
+                .set("Depth", "1")
+                .set("Content-Type", "application/xml; charset=utf-8")
+                .send_string(&body)
+                .map_err(|err| format!("caldav_request_failed:{err}"))
+        })?
+        .into_string()
+        .map_err(|err| format!("caldav_invalid_response:{err}"))?;
+    Ok(calendar_data_blocks(&response).iter().flat_map(|ics| parse_events(ics)).collect())
+}
+
+/// PUTs `event` as a new resource in the collection and returns its URL.
+#[cfg(feature = "caldav")]
+pub fn caldav_create(calendar: &CalDavCalendar, event: &CalendarEvent, deadline: Option<Instant>) -> Result<String, String> {
+    let resource: String = event
+        .uid
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    let url = format!("{}/{resource}.ics", calendar.url.trim_end_matches('/'));
+    caldav_request(calendar, "PUT", &url, deadline)?
+        .set("Content-Type", "text/calendar; charset=utf-8")
+        .set("If-None-Match", "*")
+        .send_string(&vcalendar(std::slice::from_ref(event)))
+        .map_err(|err| format!("caldav_request_failed:{err}"))?;
+    Ok(url)
+}
+
+#[cfg(feature = "caldav")]
+fn caldav_request(calendar: &CalDavCalendar, method: &str, url: &str, deadline: Option<Instant>) -> Result<ureq::Request, String> {
+    use base64::prelude::{Engine as _, BASE64_STANDARD};
+    let timeout = deadline.map_or(std::time::Duration::from_secs(30), |d| d.saturating_duration_since(Instant::now()));
+    if timeout.is_zero() {
+        return Err(crate::traits::TOOL_TIMEOUT_ERROR.to_string());
+    }
+    let mut request = ureq::request(method, url).timeout(timeout);
+    if let Some(username) = &calendar.username {
+        let credentials = format!("{username}:{}", calendar.password.as_deref().unwrap_or_default());
+        request = request.set("Authorization", &format!("Basic {}", BASE64_STANDARD.encode(credentials)));
+    }
+    Ok(request)
+}
+
+/// Contents of every `calendar-data` element (any namespace prefix) in a multistatus response.
+#[cfg(feature = "caldav")]
+fn calendar_data_blocks(xml: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut rest = xml;
+    while let Some(open) = rest.find('<') {
+        rest = &rest[open + 1..];
+        let Some(tag_end) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[..tag_end];
+        rest = &rest[tag_end + 1..];
+        let name = tag.split_whitespace().next().unwrap_or_default();
+        if name.rsplit(':').next() != Some("calendar-data") || name.starts_with('/') || tag.ends_with('/') {
+            continue;
+        }
+        if let Some(cdata) = rest.trim_start().strip_prefix("<![CDATA[") {
+            let end = cdata.find("]]>").unwrap_or(cdata.len());
+            blocks.push(cdata[..end].to_string());
+            rest = &cdata[end..];
+            continue;
+        }
+        let end = rest.find('<').unwrap_or(rest.len());
+        blocks.push(
+            rest[..end]
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&#13;", "\r")
+                .replace("&amp;", "&"),
+        );
+        rest = &rest[end..];
+    }
+    blocks
+}
+
+#[cfg(not(feature = "caldav"))]
+pub fn caldav_list(_calendar: &CalDavCalendar, _query: &EventQuery, _deadline: Option<Instant>) -> Result<Vec<CalendarEvent>, String> {
+    Err("caldav_not_supported: build with the caldav feature".to_string())
+}
+
+#[cfg(not(feature = "caldav"))]
+pub fn caldav_create(_calendar: &CalDavCalendar, _event: &CalendarEvent, _deadline: Option<Instant>) -> Result<String, String> {
+    Err("caldav_not_supported: build with the caldav feature".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:standup\r\nDTSTART;TZID=Europe/Berlin:20261019T093000\r\nDTEND;TZID=Europe/Berlin:20261019T094500\r\nRRULE:FREQ=WEEKLY;BYDAY=MO\r\nSUMMARY:Team standup\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nUID:dentist\r\nDTSTART:20261021T140000Z\r\nDTEND:20261021T150000Z\r\nSUMMARY:Dentist\\, Dr. Who\r\nLOCATION:Main St\r\nDESCRIPTION:Bring the x-ray\\nand card\r\n  forms\r\nBEGIN:VALARM\r\nTRIGGER:-PT1H30M\r\nDESCRIPTION:ignored\r\nEND:VALARM\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nUID:trip\r\nDTSTART;VALUE=DATE:20261101\r\nDTEND;VALUE=DATE:20261104\r\nSUMMARY:Trip\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+
+    #[test]
+    fn parses_events_with_escapes_folding_and_alarms() {
+        let events = parse_events(SAMPLE);
+        assert_eq!(events.len(), 3);
+        let dentist = &events[1];
+        assert_eq!(dentist.summary, "Dentist, Dr. Who");
+        assert_eq!(dentist.description.as_deref(), Some("Bring the x-ray\nand card forms"));
+        assert_eq!(dentist.reminder_minutes, Some(90));
+        assert_eq!(dentist.to_json()["start"], "2026-10-21T14:00:00Z");
+        assert_eq!(events[0].tzid.as_deref(), Some("Europe/Berlin"));
+        assert_eq!(events[0].to_json()["start"], "2026-10-19T09:30:00");
+        assert_eq!(events[0].to_json()["recurring"], true);
+        assert_eq!(dentist.to_json()["recurring"], false);
+        assert_eq!(events[2].to_json()["all_day"], true);
+    }
+
+    #[test]
+    fn queries_filter_by_range_and_text() {
+        let query = |args: Value| EventQuery::from_args(&args).expect("query").apply(parse_events(SAMPLE));
+        let uids = |(events, _): (Vec<CalendarEvent>, usize)| events.into_iter().map(|e| e.uid).collect::<Vec<_>>();
+        assert_eq!(uids(query(json!({ "from": "2026-10-21", "to": "2026-10-22" }))), vec!["standup", "dentist"]);
+        assert_eq!(uids(query(json!({ "from": "2026-11-02" }))), vec!["standup", "trip"]);
+        assert_eq!(uids(query(json!({ "query": "main st" }))), vec!["dentist"]);
+        let (events, total) = query(json!({ "limit": 1 }));
+        assert_eq!((events.len(), total), (1, 3));
+        assert!(EventQuery::from_args(&json!({ "from": "2026-02-30" })).is_err());
+    }
+
+    #[test]
+    fn parses_input_times_with_offsets() {
+        let time = |text: &str| EventTime::parse_input(text).expect("time");
+        assert_eq!(time("2026-10-17T11:00+02:00"), time("2026-10-17T09:00:00Z"));
+        assert_eq!(time("2026-10-17T09:00:00Z").iso(), "2026-10-17T09:00:00Z");
+        assert_eq!(time("1969-12-31").iso(), "1969-12-31");
+        assert_eq!(time("2026-10-17 08:15").iso(), "2026-10-17T08:15:00");
+        assert!(EventTime::parse_input("tomorrow").is_err());
+    }
+
+    #[test]
+    fn created_events_round_trip_through_the_file() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("plans/calendar.ics");
+        let event = event_from_args(&json!({
+            "summary": "Review; notes, part 2",
+            "start": "2026-10-20T15:00:00Z",
+            "duration_minutes": 30,
+            "reminder_minutes": 10,
+            "description": "x".repeat(120),
+        }))
+        .expect("event");
+        append_event(&path, &event).expect("create file");
+        append_event(&path, &event_from_args(&json!({ "summary": "Holiday", "start": "2026-12-24" })).expect("event"))
+            .expect("append");
+        let written = fs::read_to_string(&path).expect("read");
+        assert!(written.lines().all(|line| line.len() <= 76));
+        let events = read_events(&path).expect("events");
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].summary, "Review; notes, part 2");
+        assert_eq!(events[0].to_json()["end"], "2026-10-20T15:30:00Z");
+        assert_eq!(events[0].reminder_minutes, Some(10));
+        assert_eq!(events[0].description.as_deref().map(str::len), Some(120));
+        assert_eq!(events[1].to_json()["end"], "2026-12-25");
+
+        assert!(event_from_args(&json!({ "summary": "x", "start": "2026-10-20T15:00Z", "end": "2026-10-20T14:00Z" })).is_err());
+        assert_eq!(read_events(&dir.path().join("missing.ics")), Err("calendar_not_found".to_string()));
+    }
+}
+
+#[cfg(all(test, feature = "caldav"))]
+mod caldav_tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    /// A CalDAV stand-in answering REPORT with one event and PUT with 201, logging each request
+    /// line, authorization header and body.
+    fn fake_server() -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let url = format!("http://{}/cal/work/", listener.local_addr().expect("addr"));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&seen);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.expect("accept");
+                let mut reader = BufReader::new(stream.try_clone().expect("clone"));
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).expect("request line");
+                let (mut length, mut auth) = (0, String::new());
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).expect("header");
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    let lower = header.to_ascii_lowercase();
+                    if let Some(value) = lower.strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap_or(0);
+                    }
+                    if lower.starts_with("authorization:") {
+                        auth = header.trim().to_string();
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).expect("body");
+                let request_line = request_line.trim().to_string();
+                log.lock().expect("log").extend([request_line.clone(), auth, String::from_utf8_lossy(&body).into_owned()]);
+                let (status, reply) = if request_line.starts_with("REPORT") {
+                    (
+                        "207 Multi-Status",
+                        "<?xml version=\"1.0\"?><d:multistatus xmlns:d=\"DAV:\" xmlns:cal=\"urn:ietf:params:xml:ns:caldav\"><d:response><d:propstat><d:prop><cal:calendar-data>BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:r1\r\nDTSTART:20261021T140000Z\r\nSUMMARY:Tom &amp; Jerry\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n</cal:calendar-data></d:prop></d:propstat></d:response></d:multistatus>",
+                    )
+                } else {
+                    ("201 Created", "")
+                };
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{reply}",
+                    reply.len()
+                );
+            }
+        });
+        (url, seen)
+    }
+
+    #[test]
+    fn lists_and_creates_events_over_caldav() {
+        let (url, seen) = fake_server();
+        let calendar = CalDavCalendar {
+            url: url.clone(),
+            username: Some("me".to_string()),
+            password: Some("secret".to_string()),
+        };
+        let query = EventQuery::from_args(&json!({ "from": "2026-10-01", "to": "2026-11-01" })).expect("query");
+        let events = caldav_list(&calendar, &query, None).expect("list");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].summary, "Tom & Jerry");
+
+        let event = event_from_args(&json!({ "summary": "Sync", "start": "2026-10-22T10:00:00Z" })).expect("event");
+        let created = caldav_create(&calendar, &event, None).expect("create");
+        assert!(created.starts_with(&format!("{url}{}", event.uid.split('@').next().unwrap_or_default())));
+
+        let seen = seen.lock().expect("log").clone();
+        assert!(seen[0].starts_with("REPORT /cal/work/"), "{seen:?}");
+        assert_eq!(seen[1], "Authorization: Basic bWU6c2VjcmV0");
+        assert!(seen[2].contains("start=\"20261001T000000Z\" end=\"20261101T000000Z\""), "{}", seen[2]);
+        assert!(seen[3].starts_with("PUT /cal/work/") && seen[3].contains(".ics"), "{seen:?}");
+        assert!(seen[5].contains("SUMMARY:Sync"));
+        assert!(!format!("{calendar:?}").contains("secret"));
+    }
+}
@@ -0,0 +1,278 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Scratch directories for `code.run` live here, under the project root, and are removed after
+/// each run.
+pub const SCRATCH_DIR: &str = ".cmnd-n-ctrl/code-run";
+pub const DEFAULT_TIMEOUT_SECS: u64 = 10;
+pub const MAX_TIMEOUT_SECS: u64 = 60;
+pub const MEMORY_LIMIT_MB: u64 = 512;
+pub const MAX_CODE_BYTES: usize = 64 * 1024;
+/// Each of stdout and stderr is cut to this many bytes in the result.
+pub const MAX_STREAM_BYTES: usize = 64 * 1024;
+
+/// Environment passed through to the interpreter; everything else (API keys included) is dropped.
+const INHERITED_ENV: &[&str] = &["PATH", "LANG", "LC_ALL", "LC_CTYPE", "TZ", "SYSTEMROOT"];
+
+/// Runs inside the namespaces as `sh -c SANDBOX_SCRIPT sh <project root> <scratch dir> ...`: hides
+/// the project root under an empty tmpfs, binds the scratch directory (held open on fd 3) back
+/// at its own path, and leaves the interpreter's argv for the limits line that follows.
+const SANDBOX_SCRIPT: &str = r#"set -e
+exec 3<"$2"
+mount -t tmpfs -o mode=0755 tmpfs "$1"
+mkdir -p "$2"
+mount --no-canonicalize --bind /proc/self/fd/3 "$2"
+exec 3<&-
+cd "$2"
+shift 2
+"#;
+
+/// Loaded before the snippet: sockets and child processes raise instead of running. The network
+/// namespace is the boundary; the snippet can undo this, so it only makes failures clearer.
+const PYTHON_GUARD: &str = r#"import os, socket, subprocess
+def _blocked(*args, **kwargs):
+    raise PermissionError("code.run: network and subprocess access are disabled")
+class _NoSocket(socket.socket):
+    def __init__(self, *args, **kwargs):
+        _blocked()
+class _NoPopen(subprocess.Popen):
+    def __init__(self, *args, **kwargs):
+        _blocked()
+socket.socket = _NoSocket
+for _name in ("create_connection", "create_server", "getaddrinfo", "socketpair", "fromfd"):
+    setattr(socket, _name, _blocked)
+subprocess.Popen = _NoPopen
+for _name in ("system", "popen", "fork", "forkpty", "execv", "execve", "posix_spawn", "posix_spawnp"):
+    if hasattr(os, _name):
+        setattr(os, _name, _blocked)
+del _name
+exec(compile(open("snippet.py", encoding="utf-8").read(), "snippet.py", "exec"), {"__name__": "__main__", "__file__": "snippet.py"})
+"#;
+
+const NODE_GUARD: &str = r#"'use strict';
+const blocked = () => {
+  throw new Error('code.run: network and subprocess access are disabled');
+};
+// Classes stay intact because other built-ins extend them; their connecting methods don't.
+for (const name of ['net', 'tls', 'http', 'https', 'http2', 'dgram', 'dns', 'dns/promises', 'child_process']) {
+  const mod = require(name);
+  for (const key of Object.keys(mod)) {
+    if (typeof mod[key] === 'function' && !/^[A-Z]/.test(key)) {
+      try {
+        mod[key] = blocked;
+      } catch {}
+    }
+  }
+}
+const net = require('net');
+const dgram = require('dgram');
+net.Socket.prototype.connect = blocked;
+net.Server.prototype.listen = blocked;
+dgram.Socket.prototype.bind = blocked;
+dgram.Socket.prototype.send = blocked;
+globalThis.fetch = blocked;
+globalThis.WebSocket = undefined;
+"#;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CodeLanguage {
+    Python,
+    Node,
+}
+
+impl CodeLanguage {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "python" | "python3" | "py" => Ok(Self::Python),
+            "node" | "javascript" | "js" => Ok(Self::Node),
+            other => Err(format!("unsupported_language:{other}")),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Python => "python",
+            Self::Node => "node",
+        }
+    }
+
+    fn interpreter(self) -> &'static str {
+        match self {
+            Self::Python => "python3",
+            Self::Node => "node",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CodeRunLimits {
+    pub timeout: Duration,
+    /// CPU seconds (`ulimit -t`).
+    pub cpu_secs: u64,
+    /// Address space for Python (`ulimit -v`) and V8 heap size for Node.
+    pub memory_mb: u64,
+}
+
+impl CodeRunLimits {
+    /// `timeout_secs` from the call, defaulted and clamped to `1..=MAX_TIMEOUT_SECS`.
+    pub fn from_timeout_secs(timeout_secs: Option<u64>) -> Self {
+        let secs = timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS).clamp(1, MAX_TIMEOUT_SECS);
+        Self {
+            timeout: Duration::from_secs(secs),
+            cpu_secs: secs,
+            memory_mb: MEMORY_LIMIT_MB,
+        }
+    }
+}
+
+/// A snippet written to its own scratch directory, with the command that runs it. The
+/// directory is removed when this is dropped.
+#[derive(Debug)]
+pub struct PreparedCodeRun {
+    pub dir: PathBuf,
+    pub command: Command,
+}
+
+impl Drop for PreparedCodeRun {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Writes `code` under `<project_root>/SCRATCH_DIR` and builds the sandboxed command: the
+/// interpreter runs under `unshare` with no network and a private mount namespace in which the
+/// project root shows only the scratch dir, under `ulimit`, with the scratch dir as cwd and
+/// `HOME` and the environment reduced to `INHERITED_ENV`. Without working unprivileged
+/// namespaces it refuses with `sandbox_unavailable` rather than run unconfined.
+pub fn prepare(language: CodeLanguage, code: &str, project_root: &Path, limits: &CodeRunLimits) -> Result<PreparedCodeRun, String> {
+    if code.trim().is_empty() {
+        return Err("missing_code".to_string());
+    }
+    if code.len() > MAX_CODE_BYTES {
+        return Err(format!("code_too_large:{}>{MAX_CODE_BYTES}", code.len()));
+    }
+    if !sandbox_available() {
+        return Err("sandbox_unavailable:code.run needs unshare with unprivileged user, network and mount namespaces".to_string());
+    }
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let dir = project_root
+        .join(SCRATCH_DIR)
+        .join(format!("run-{}-{nanos}", std::process::id()));
+    fs::create_dir_all(&dir).map_err(|err| format!("scratch_dir_failed:{err}"))?;
+    // From here on the guard cleans up the directory if writing a file fails.
+    let mut prepared = PreparedCodeRun {
+        dir: dir.clone(),
+        command: Command::new(language.interpreter()),
+    };
+    let write = |name: &str, contents: &str| fs::write(dir.join(name), contents).map_err(|err| format!("scratch_write_failed:{err}"));
+    let mut argv = vec![language.interpreter().to_string()];
+    match language {
+        CodeLanguage::Python => {
+            write("snippet.py", code)?;
+            write("guard.py", PYTHON_GUARD)?;
+            argv.extend(["-I".to_string(), "guard.py".to_string()]);
+        }
+        CodeLanguage::Node => {
+            write("snippet.js", code)?;
+            write("guard.js", NODE_GUARD)?;
+            argv.extend([
+                format!("--max-old-space-size={}", limits.memory_mb),
+                "--require".to_string(),
+                dir.join("guard.js").display().to_string(),
+                "snippet.js".to_string(),
+            ]);
+        }
+    }
+
+    let mut script = format!("{SANDBOX_SCRIPT}ulimit -t {}", limits.cpu_secs);
+    if language == CodeLanguage::Python {
+        script.push_str(&format!(" && ulimit -v {}", limits.memory_mb * 1024));
+    }
+    script.push_str(" && exec \"$@\"");
+    let mut command = Command::new("unshare");
+    command.args(["--net", "--mount", "--map-root-user", "/bin/sh", "-c", &script, "sh"]);
+    command.arg(project_root).arg(&dir).args(&argv);
+    command.current_dir(&dir).env_clear().env("HOME", &dir).env("TMPDIR", &dir).env("TEMP", &dir);
+    for key in INHERITED_ENV {
+        if let Some(value) = std::env::var_os(key) {
+            command.env(key, value);
+        }
+    }
+    prepared.command = command;
+    Ok(prepared)
+}
+
+/// Cuts `bytes` to `MAX_STREAM_BYTES`, reporting whether anything was dropped.
+pub fn capped_stream(bytes: &[u8]) -> (String, bool) {
+    let truncated = bytes.len() > MAX_STREAM_BYTES;
+    let text = String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_STREAM_BYTES)]).into_owned();
+    (text, truncated)
+}
+
+/// Whether `unshare` can give a process its own network and mount namespaces and mount a tmpfs
+/// there; probed once per process. Only Linux has them.
+pub fn sandbox_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        cfg!(target_os = "linux")
+            && Command::new("unshare")
+                .args(["--net", "--mount", "--map-root-user", "/bin/sh", "-c", "mount -t tmpfs tmpfs \"$1\"", "sh"])
+                .arg(std::env::temp_dir())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .status()
+                .is_ok_and(|status| status.success())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn parses_languages_and_clamps_timeouts() {
+        assert_eq!(CodeLanguage::parse("Python"), Ok(CodeLanguage::Python));
+        assert_eq!(CodeLanguage::parse("js"), Ok(CodeLanguage::Node));
+        assert_eq!(CodeLanguage::parse("ruby"), Err("unsupported_language:ruby".to_string()));
+        assert_eq!(CodeRunLimits::from_timeout_secs(None).timeout, Duration::from_secs(DEFAULT_TIMEOUT_SECS));
+        assert_eq!(CodeRunLimits::from_timeout_secs(Some(0)).cpu_secs, 1);
+        assert_eq!(CodeRunLimits::from_timeout_secs(Some(3600)).cpu_secs, MAX_TIMEOUT_SECS);
+    }
+
+    #[test]
+    fn prepares_scratch_dir_and_removes_it_on_drop() {
+        let root = tempdir().expect("tempdir");
+        let limits = CodeRunLimits::from_timeout_secs(None);
+        assert_eq!(
+            prepare(CodeLanguage::Python, "  ", root.path(), &limits).map(|_| ()),
+            Err("missing_code".to_string())
+        );
+        if !sandbox_available() {
+            let err = prepare(CodeLanguage::Python, "print(1)", root.path(), &limits).expect_err("no sandbox");
+            assert!(err.starts_with("sandbox_unavailable:"), "{err}");
+            assert!(!root.path().join(SCRATCH_DIR).exists());
+            return;
+        }
+        let prepared = prepare(CodeLanguage::Python, "print(1)", root.path(), &limits).expect("prepare");
+        let dir = prepared.dir.clone();
+        assert!(dir.starts_with(root.path().join(SCRATCH_DIR)));
+        assert_eq!(fs::read_to_string(dir.join("snippet.py")).expect("snippet"), "print(1)");
+        assert_eq!(prepared.command.get_current_dir(), Some(dir.as_path()));
+        drop(prepared);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn caps_streams() {
+        assert_eq!(capped_stream(b"ok"), ("ok".to_string(), false));
+        let (text, truncated) = capped_stream(&vec![b'x'; MAX_STREAM_BYTES + 1]);
+        assert!(truncated && text.len() == MAX_STREAM_BYTES);
+    }
+}
@@ -0,0 +1,157 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+/// A user-declared tool that runs a program with arguments filled in from the tool call.
+/// `command` is an argv template, never handed to a shell: `{name}` in an element is replaced
+/// by the call's `name` argument, so a value can't break out of the element it lands in.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommandToolSpec {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// JSON schema for the arguments; an object with no properties when unset.
+    #[serde(default)]
+    pub input_schema: Option<Value>,
+    pub command: Vec<String>,
+    /// Upper bound on top of the policy's `tool_timeout_secs`.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+impl CommandToolSpec {
+    /// The program must be fixed: only its arguments may come from the model.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("name is empty".to_string());
+        }
+        match self.command.first() {
+            None => Err("command is empty".to_string()),
+            Some(program) if program.is_empty() || program.contains(['{', '}']) => {
+                Err(format!("command program '{program}' must be a fixed, non-empty path"))
+            }
+            _ if self.input_schema.as_ref().is_some_and(|schema| !schema.is_object()) => {
+                Err("input_schema must be a JSON object".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn input_json_schema(&self) -> String {
+        self.input_schema
+            .as_ref()
+            .map(Value::to_string)
+            .unwrap_or_else(|| r#"{"type":"object","properties":{}}"#.to_string())
+    }
+
+    /// Fills the template from `args`. An element that is exactly `{name}` is dropped when the
+    /// argument is missing or null and expands to one element per item when it is an array;
+    /// elsewhere a missing argument is an error. `{{` and `}}` are literal braces.
+    pub fn render_argv(&self, args: &Value) -> Result<Vec<String>, String> {
+        let mut argv = Vec::with_capacity(self.command.len());
+        for template in &self.command {
+            let whole = template
+                .strip_prefix('{')
+                .and_then(|rest| rest.strip_suffix('}'))
+                .filter(|name| is_placeholder_name(name));
+            if let Some(name) = whole {
+                match args.get(name) {
+                    None | Some(Value::Null) => {}
+                    Some(Value::Array(items)) => {
+                        for item in items {
+                            argv.push(scalar(name, item)?);
+                        }
+                    }
+                    Some(value) => argv.push(scalar(name, value)?),
+                }
+                continue;
+            }
+            argv.push(substitute(template, args)?);
+        }
+        if argv.first().is_none_or(|program| program.is_empty()) {
+            return Err("empty_command".to_string());
+        }
+        Ok(argv)
+    }
+}
+
+fn substitute(template: &str, args: &Value) -> Result<String, String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(pos) = rest.find(['{', '}']) {
+        out.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            out.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+        let end = tail.find('}').filter(|_| tail.starts_with('{'));
+        let Some(name) = end.map(|end| &tail[1..end]).filter(|name| is_placeholder_name(name)) else {
+            return Err(format!("invalid_template:{template}"));
+        };
+        match args.get(name) {
+            None | Some(Value::Null) => return Err(format!("missing_argument:{name}")),
+            Some(value) => out.push_str(&scalar(name, value)?),
+        }
+        rest = &tail[name.len() + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn scalar(name: &str, value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Number(number) => Ok(number.to_string()),
+        Value::Bool(flag) => Ok(flag.to_string()),
+        _ => Err(format!("unsupported_argument_type:{name}")),
+    }
+}
+
+fn is_placeholder_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn spec(command: &[&str]) -> CommandToolSpec {
+        CommandToolSpec {
+            name: "git.log".to_string(),
+            description: String::new(),
+            input_schema: None,
+            command: command.iter().map(|s| s.to_string()).collect(),
+            timeout_secs: None,
+        }
+    }
+
+    #[test]
+    fn renders_argv_without_splitting_values() {
+        let tool = spec(&["git", "log", "-n{count}", "{paths}", "--grep={query}", "{author}", "{{literal}}"]);
+        let argv = tool
+            .render_argv(&json!({ "count": 5, "paths": ["src", "a b; rm -rf /"], "query": "fix $(x)" }))
+            .expect("argv");
+        assert_eq!(
+            argv,
+            vec!["git", "log", "-n5", "src", "a b; rm -rf /", "--grep=fix $(x)", "{literal}"]
+        );
+        assert_eq!(tool.input_json_schema(), r#"{"type":"object","properties":{}}"#);
+    }
+
+    #[test]
+    fn rejects_missing_embedded_arguments_and_bad_templates() {
+        assert_eq!(spec(&["git", "-n{count}"]).render_argv(&json!({})), Err("missing_argument:count".to_string()));
+        assert_eq!(
+            spec(&["git", "{nested}"]).render_argv(&json!({ "nested": { "a": 1 } })),
+            Err("unsupported_argument_type:nested".to_string())
+        );
+        assert!(spec(&["git", "{open"]).render_argv(&json!({})).is_err());
+        assert_eq!(spec(&["{program}"]).render_argv(&json!({})), Err("empty_command".to_string()));
+        assert!(spec(&["{program}", "x"]).validate().is_err());
+        assert!(spec(&[]).validate().is_err());
+        assert_eq!(spec(&["git", "{x}"]).validate(), Ok(()));
+    }
+}
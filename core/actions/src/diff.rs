@@ -0,0 +1,72 @@
+/// Renders a compact unified-style diff of `before` -> `after` for consent previews. Only the
+/// changed region (after trimming the common prefix/suffix) is shown, with up to `context`
+/// unchanged lines on each side and at most `max_lines` body lines.
+pub fn preview_diff(label: &str, before: &str, after: &str, context: usize, max_lines: usize) -> String {
+    let old: Vec<&str> = before.lines().collect();
+    let new: Vec<&str> = after.lines().collect();
+
+    let prefix = old.iter().zip(new.iter()).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut out = vec![format!("--- {label}"), format!("+++ {label}")];
+    if before == after {
+        out.push("(no changes)".to_string());
+        return out.join("\n");
+    }
+
+    let start = prefix.saturating_sub(context);
+    let old_end = (old.len() - suffix + context).min(old.len());
+    let new_end = (new.len() - suffix + context).min(new.len());
+    out.push(format!(
+        "@@ -{},{} +{},{} @@",
+        start + 1,
+        old_end - start,
+        start + 1,
+        new_end - start
+    ));
+
+    let mut body = Vec::new();
+    body.extend(old[start..prefix].iter().map(|line| format!(" {line}")));
+    body.extend(old[prefix..old.len() - suffix].iter().map(|line| format!("-{line}")));
+    body.extend(new[prefix..new.len() - suffix].iter().map(|line| format!("+{line}")));
+    body.extend(new[new.len() - suffix..new_end].iter().map(|line| format!(" {line}")));
+    if !before.is_empty() && !after.is_empty() && before.ends_with('\n') != after.ends_with('\n') {
+        body.push("\\ trailing newline changed".to_string());
+    }
+
+    let total = body.len();
+    out.extend(body.into_iter().take(max_lines));
+    if total > max_lines {
+        out.push(format!("... {} more line(s)", total - max_lines));
+    }
+    out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shows_only_changed_region_with_context() {
+        let before = "a\nb\nc\nd\ne\n";
+        let after = "a\nb\nC\nd\ne\n";
+        let diff = preview_diff("notes.txt", before, after, 1, 20);
+        assert_eq!(
+            diff,
+            "--- notes.txt\n+++ notes.txt\n@@ -2,3 +2,3 @@\n b\n-c\n+C\n d"
+        );
+    }
+
+    #[test]
+    fn new_file_and_truncation() {
+        let diff = preview_diff("new.txt", "", "one\ntwo\nthree\n", 3, 2);
+        assert!(diff.contains("@@ -1,0 +1,3 @@"));
+        assert!(diff.contains("+one\n+two\n... 1 more line(s)"));
+        assert!(!diff.contains("+three"));
+    }
+}
@@ -1,8 +1,21 @@
-use ipc::Evidence;
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use ipc::{Evidence, EvidenceAttachment};
 
 pub fn action_evidence(summary: impl Into<String>, artifact: impl Into<String>) -> Evidence {
     Evidence {
         summary: summary.into(),
         artifacts: vec![artifact.into()],
+        attachments: Vec::new(),
+        original_result_bytes: None,
     }
 }
+
+/// Adds content for the agent to keep in its evidence store alongside the audit entry.
+pub fn with_attachment(mut evidence: Evidence, name: impl Into<String>, media_type: &str, bytes: &[u8]) -> Evidence {
+    evidence.attachments.push(EvidenceAttachment {
+        name: name.into(),
+        media_type: media_type.to_string(),
+        content_base64: BASE64_STANDARD.encode(bytes),
+    });
+    evidence
+}
@@ -0,0 +1,110 @@
+use globset::GlobBuilder;
+use ignore::WalkBuilder;
+use std::path::Path;
+use std::time::Instant;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GlobMatches {
+    /// Paths relative to the walk root, `/`-separated and sorted.
+    pub paths: Vec<String>,
+    /// Total matches seen, which can exceed `paths.len()` when truncated.
+    pub count: usize,
+    pub truncated: bool,
+    pub timed_out: bool,
+}
+
+/// Expands `pattern` (`*` stays within one path segment, `**` crosses them) under `root`.
+/// Hidden files and `.git` are skipped; `.gitignore`/`.ignore` rules apply unless disabled.
+pub fn glob_files(
+    root: &Path,
+    pattern: &str,
+    respect_gitignore: bool,
+    limit: usize,
+    deadline: Option<Instant>,
+) -> Result<GlobMatches, String> {
+    let pattern = pattern.trim().trim_start_matches("./");
+    if pattern.is_empty() {
+        return Err("missing_pattern".to_string());
+    }
+    let matcher = GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map_err(|err| format!("invalid_pattern:{err}"))?
+        .compile_matcher();
+
+    let walker = WalkBuilder::new(root)
+        .git_ignore(respect_gitignore)
+        .git_exclude(respect_gitignore)
+        .git_global(respect_gitignore)
+        .ignore(respect_gitignore)
+        .parents(respect_gitignore)
+        .require_git(false)
+        .build();
+
+    let mut out = GlobMatches::default();
+    for entry in walker.flatten() {
+        if deadline.map(|d| Instant::now() >= d).unwrap_or(false) {
+            out.timed_out = true;
+            break;
+        }
+        if !entry.file_type().map(|t| t.is_file()).unwrap_or(false) {
+            continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(root) else {
+            continue;
+        };
+        if !matcher.is_match(relative) {
+            continue;
+        }
+        out.count += 1;
+        if out.paths.len() < limit {
+            out.paths.push(
+                relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+            );
+        } else {
+            out.truncated = true;
+        }
+    }
+    out.paths.sort();
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn glob_matches_recursive_patterns_and_honours_gitignore() {
+        let tmp = tempdir().expect("tempdir");
+        let dir = tmp.path();
+        fs::create_dir_all(dir.join("src/bin")).expect("mkdir");
+        fs::create_dir_all(dir.join("target/debug")).expect("mkdir");
+        fs::write(dir.join(".gitignore"), "target/\n").expect("gitignore");
+        fs::write(dir.join("src/lib.rs"), "").expect("write");
+        fs::write(dir.join("src/bin/cli.rs"), "").expect("write");
+        fs::write(dir.join("src/notes.md"), "").expect("write");
+        fs::write(dir.join("target/debug/build.rs"), "").expect("write");
+
+        let found = glob_files(dir, "src/**/*.rs", true, 10, None).expect("glob");
+        assert_eq!(found.paths, vec!["src/bin/cli.rs", "src/lib.rs"]);
+        assert_eq!(found.count, 2);
+
+        let single_level = glob_files(dir, "src/*.rs", true, 10, None).expect("glob");
+        assert_eq!(single_level.paths, vec!["src/lib.rs"]);
+
+        let ignored = glob_files(dir, "**/*.rs", true, 10, None).expect("glob");
+        assert_eq!(ignored.count, 2);
+        let everything = glob_files(dir, "**/*.rs", false, 1, None).expect("glob");
+        assert_eq!(everything.count, 3);
+        assert_eq!(everything.paths.len(), 1);
+        assert!(everything.truncated);
+
+        assert!(glob_files(dir, "src/[", true, 10, None).is_err());
+    }
+}
@@ -1,7 +1,18 @@
 pub mod android_stub;
+pub mod archive;
+pub mod browser;
+pub mod calendar;
+pub mod code_run;
+pub mod command_tool;
+pub mod db_query;
 pub mod desktop_linux_stub;
 pub mod desktop_macos_stub;
 pub mod desktop_windows_stub;
+pub mod diff;
 pub mod evidence;
+pub mod file_glob;
 pub mod ios_stub;
+pub mod mime;
+pub mod processes;
+pub mod project_index;
 pub mod traits;
@@ -0,0 +1,84 @@
+use std::path::Path;
+
+/// Bytes `sniff_mime` needs from the start of a file.
+pub const SNIFF_LEN: usize = 16;
+
+/// Magic-number MIME detection with an extension fallback; `application/octet-stream` when unknown.
+pub fn sniff_mime(head: &[u8], path: &Path) -> &'static str {
+    const MAGIC: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"PK\x05\x06", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"BZh", "application/x-bzip2"),
+        (b"\xfd7zXZ\x00", "application/x-xz"),
+        (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+        (b"\x00asm", "application/wasm"),
+        (b"\x7fELF", "application/x-elf"),
+        (b"MZ", "application/vnd.microsoft.portable-executable"),
+        (b"OggS", "audio/ogg"),
+        (b"fLaC", "audio/flac"),
+        (b"ID3", "audio/mpeg"),
+        (b"SQLite format 3\x00", "application/vnd.sqlite3"),
+    ];
+    if let Some((_, mime)) = MAGIC.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return mime;
+    }
+    if head.len() >= 12 && &head[0..4] == b"RIFF" {
+        match &head[8..12] {
+            b"WEBP" => return "image/webp",
+            b"WAVE" => return "audio/wav",
+            _ => {}
+        }
+    }
+    if head.len() >= 12 && &head[4..8] == b"ftyp" {
+        return "video/mp4";
+    }
+
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "svg" => "image/svg+xml",
+        "json" => "application/json",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "md" => "text/markdown",
+        "txt" | "log" | "rs" | "toml" | "yaml" | "yml" => "text/plain",
+        "tar" => "application/x-tar",
+        _ if looks_like_text(head) => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+/// A multibyte character cut off by the sniff window still counts as text.
+fn looks_like_text(head: &[u8]) -> bool {
+    if head.is_empty() || head.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(err) => err.error_len().is_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniff_prefers_magic_numbers_over_extensions() {
+        assert_eq!(sniff_mime(b"\x89PNG\r\n\x1a\n\0\0", Path::new("logo.txt")), "image/png");
+        assert_eq!(sniff_mime(b"RIFF\0\0\0\0WEBPVP8 ", Path::new("a")), "image/webp");
+        assert_eq!(sniff_mime(b"PK\x03\x04rest", Path::new("bundle.jar")), "application/zip");
+        assert_eq!(sniff_mime(b"<svg xmlns=", Path::new("icon.svg")), "image/svg+xml");
+        assert_eq!(sniff_mime(b"hello", Path::new("README")), "text/plain");
+        assert_eq!(sniff_mime(b"\x00\x01\x02", Path::new("blob")), "application/octet-stream");
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+use std::process::Command;
+
+/// A running process plus any top-level window titles the platform reports for it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DesktopApp {
+    pub pid: u32,
+    pub name: String,
+    pub window_titles: Vec<String>,
+}
+
+/// Real process enumeration; only available with the `process-list` feature.
+pub fn list_desktop_apps(filter: &str, windowed_only: bool) -> Result<Vec<DesktopApp>, String> {
+    let processes = running_processes()?;
+    let mut windows = window_titles_by_pid();
+    let apps = processes
+        .into_iter()
+        .map(|(pid, name)| DesktopApp {
+            pid,
+            name,
+            window_titles: windows.remove(&pid).unwrap_or_default(),
+        })
+        .collect::<Vec<_>>();
+    Ok(filter_apps(apps, filter, windowed_only))
+}
+
+pub fn filter_apps(mut apps: Vec<DesktopApp>, filter: &str, windowed_only: bool) -> Vec<DesktopApp> {
+    let needle = filter.trim().to_ascii_lowercase();
+    apps.retain(|app| {
+        if windowed_only && app.window_titles.is_empty() {
+            return false;
+        }
+        needle.is_empty()
+            || app.name.to_ascii_lowercase().contains(&needle)
+            || app
+                .window_titles
+                .iter()
+                .any(|title| title.to_ascii_lowercase().contains(&needle))
+    });
+    apps.sort_by(|a, b| {
+        a.window_titles
+            .is_empty()
+            .cmp(&b.window_titles.is_empty())
+            .then_with(|| a.name.to_ascii_lowercase().cmp(&b.name.to_ascii_lowercase()))
+            .then(a.pid.cmp(&b.pid))
+    });
+    apps
+}
+
+#[cfg(feature = "process-list")]
+fn running_processes() -> Result<Vec<(u32, String)>, String> {
+    let mut system = sysinfo::System::new();
+    system.refresh_processes();
+    Ok(system
+        .processes()
+        .iter()
+        .map(|(pid, process)| (pid.as_u32(), process.name().to_string()))
+        .collect())
+}
+
+#[cfg(not(feature = "process-list"))]
+fn running_processes() -> Result<Vec<(u32, String)>, String> {
+    Err("process_list_feature_disabled".to_string())
+}
+
+/// Best effort: windows are only known where a lister is installed (`wmctrl` on Linux, `tasklist` on
+/// Windows). macOS needs accessibility permission for titles, so none are reported there.
+fn window_titles_by_pid() -> BTreeMap<u32, Vec<String>> {
+    #[cfg(target_os = "linux")]
+    let windows = Command::new("wmctrl")
+        .arg("-lp")
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .map(|out| parse_wmctrl_windows(&String::from_utf8_lossy(&out.stdout)));
+    #[cfg(target_os = "windows")]
+    let windows = Command::new("tasklist")
+        .args(["/v", "/fo", "csv", "/nh"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .map(|out| parse_tasklist_windows(&String::from_utf8_lossy(&out.stdout)));
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    let windows: Option<Vec<(u32, String)>> = None;
+
+    let mut by_pid = BTreeMap::<u32, Vec<String>>::new();
+    for (pid, title) in windows.unwrap_or_default() {
+        by_pid.entry(pid).or_default().push(title);
+    }
+    by_pid
+}
+
+/// Parses `wmctrl -lp` lines: `<window id> <desktop> <pid> <host> <title...>`.
+pub fn parse_wmctrl_windows(output: &str) -> Vec<(u32, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let _window_id = fields.next()?;
+            let _desktop = fields.next()?;
+            let pid = fields.next()?.parse::<u32>().ok().filter(|pid| *pid > 0)?;
+            let _host = fields.next()?;
+            let title = fields.collect::<Vec<_>>().join(" ");
+            (!title.is_empty()).then_some((pid, title))
+        })
+        .collect()
+}
+
+/// Parses `tasklist /v /fo csv /nh`: PID is the second column, the window title the last.
+pub fn parse_tasklist_windows(output: &str) -> Vec<(u32, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let columns = line
+                .trim()
+                .trim_start_matches('"')
+                .trim_end_matches('"')
+                .split("\",\"")
+                .collect::<Vec<_>>();
+            let pid = columns.get(1)?.parse::<u32>().ok()?;
+            let title = columns.last()?.trim();
+            (!title.is_empty() && title != "N/A").then(|| (pid, title.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_listers_are_parsed_and_filtered() {
+        let wmctrl = "0x03a00003  0 4242   host Mozilla Firefox\n0x04000001 -1 0 host Desktop\n";
+        assert_eq!(parse_wmctrl_windows(wmctrl), vec![(4242, "Mozilla Firefox".to_string())]);
+        let tasklist = "\"code.exe\",\"812\",\"Console\",\"1\",\"90,000 K\",\"Running\",\"me\",\"0:00:10\",\"main.rs - Visual Studio Code\"\n\"svchost.exe\",\"4\",\"Services\",\"0\",\"1,000 K\",\"Unknown\",\"N/A\",\"0:00:00\",\"N/A\"";
+        assert_eq!(
+            parse_tasklist_windows(tasklist),
+            vec![(812, "main.rs - Visual Studio Code".to_string())]
+        );
+
+        let apps = vec![
+            DesktopApp { pid: 3, name: "sshd".to_string(), window_titles: Vec::new() },
+            DesktopApp { pid: 2, name: "firefox".to_string(), window_titles: vec!["Docs".to_string()] },
+            DesktopApp { pid: 1, name: "code".to_string(), window_titles: vec!["main.rs".to_string()] },
+        ];
+        let names = |apps: Vec<DesktopApp>| apps.into_iter().map(|a| a.name).collect::<Vec<_>>();
+        assert_eq!(names(filter_apps(apps.clone(), "", false)), vec!["code", "firefox", "sshd"]);
+        assert_eq!(names(filter_apps(apps.clone(), "", true)), vec!["code", "firefox"]);
+        assert_eq!(names(filter_apps(apps, "DOCS", false)), vec!["firefox"]);
+    }
+
+    #[cfg(feature = "process-list")]
+    #[test]
+    fn list_desktop_apps_includes_current_process() {
+        let apps = list_desktop_apps("", false).expect("list");
+        assert!(apps.iter().any(|app| app.pid == std::process::id()));
+    }
+}
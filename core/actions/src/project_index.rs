@@ -0,0 +1,240 @@
+use crate::traits::normalize_path;
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+const INDEX_VERSION: u32 = 1;
+/// Same cap `file.search_text` applies when scanning without an index.
+pub const MAX_INDEXED_FILE_BYTES: u64 = 512 * 1024;
+
+/// Persistent per-project trigram index so `file.search_text` only opens files that can match.
+/// Entries are keyed by `/`-separated paths relative to the project root and refreshed by mtime/size.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProjectIndex {
+    version: u32,
+    root: PathBuf,
+    files: BTreeMap<String, IndexedFile>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct IndexedFile {
+    mtime_ms: u64,
+    size: u64,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    binary: bool,
+    /// Sorted, lowercased byte trigrams packed as `b0 << 16 | b1 << 8 | b2`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    trigrams: Vec<u32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexRefresh {
+    pub files: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
+impl IndexRefresh {
+    pub fn changed(&self) -> bool {
+        self.updated > 0 || self.removed > 0
+    }
+}
+
+impl ProjectIndex {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            version: INDEX_VERSION,
+            root: normalize_path(root.to_path_buf()),
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// `<index_dir>/<fnv1a(root)>.json`; one file per project root.
+    pub fn index_path(index_dir: &Path, root: &Path) -> PathBuf {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in normalize_path(root.to_path_buf()).to_string_lossy().bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        index_dir.join(format!("{hash:016x}.json"))
+    }
+
+    /// A missing, unreadable or stale-format index loads as empty and is rebuilt by `refresh`.
+    pub fn load(index_dir: &Path, root: &Path) -> Self {
+        let root = &normalize_path(root.to_path_buf());
+        fs::read_to_string(Self::index_path(index_dir, root))
+            .ok()
+            .and_then(|raw| serde_json::from_str::<Self>(&raw).ok())
+            .filter(|index| index.version == INDEX_VERSION && index.root == *root)
+            .unwrap_or_else(|| Self::new(root))
+    }
+
+    pub fn save(&self, index_dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(index_dir)?;
+        let raw = serde_json::to_string(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let path = Self::index_path(index_dir, &self.root);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, raw)?;
+        fs::rename(tmp, path)
+    }
+
+    /// Load, refresh and persist (only when something changed) in one step.
+    pub fn update(index_dir: &Path, root: &Path) -> io::Result<(Self, IndexRefresh)> {
+        let mut index = Self::load(index_dir, root);
+        let refresh = index.refresh_excluding(Some(index_dir));
+        if refresh.changed() {
+            index.save(index_dir)?;
+        }
+        Ok((index, refresh))
+    }
+
+    /// Walks the project (honouring `.gitignore`) and re-reads only files whose mtime or size moved.
+    pub fn refresh(&mut self) -> IndexRefresh {
+        self.refresh_excluding(None)
+    }
+
+    /// `exclude` keeps the index's own directory out when it lives inside the project.
+    fn refresh_excluding(&mut self, exclude: Option<&Path>) -> IndexRefresh {
+        let mut seen = BTreeSet::new();
+        let mut refresh = IndexRefresh::default();
+        let exclude = exclude.map(|p| normalize_path(p.to_path_buf()));
+        let walker = WalkBuilder::new(&self.root)
+            .require_git(false)
+            .filter_entry(move |entry| exclude.as_ref().is_none_or(|dir| !entry.path().starts_with(dir)))
+            .build();
+        for entry in walker.flatten() {
+            if !entry.file_type().map(|t| t.is_file()).unwrap_or(false) {
+                continue;
+            }
+            let Some(key) = relative_key(&self.root, entry.path()) else {
+                continue;
+            };
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.len() > MAX_INDEXED_FILE_BYTES {
+                continue;
+            }
+            let mtime_ms = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0);
+            seen.insert(key.clone());
+            let fresh = self
+                .files
+                .get(&key)
+                .is_some_and(|f| f.mtime_ms == mtime_ms && f.size == meta.len());
+            if fresh {
+                continue;
+            }
+            let Ok(bytes) = fs::read(entry.path()) else {
+                continue;
+            };
+            let binary = bytes.iter().take(8 * 1024).any(|b| *b == 0);
+            self.files.insert(
+                key,
+                IndexedFile {
+                    mtime_ms,
+                    size: meta.len(),
+                    binary,
+                    trigrams: if binary { Vec::new() } else { trigrams(&bytes) },
+                },
+            );
+            refresh.updated += 1;
+        }
+        let before = self.files.len();
+        self.files.retain(|key, _| seen.contains(key));
+        refresh.removed = before - self.files.len();
+        refresh.files = self.files.len();
+        refresh
+    }
+
+    /// Text files under `dir` that may contain `literal` (case-insensitively); every text file
+    /// when there is no literal or it is shorter than a trigram.
+    pub fn candidates(&self, dir: &Path, literal: Option<&str>) -> Vec<PathBuf> {
+        let needed = literal.map(|q| trigrams(q.as_bytes())).unwrap_or_default();
+        let Some(prefix) = relative_key(&self.root, dir) else {
+            return Vec::new();
+        };
+        let nested = format!("{prefix}/");
+        self.files
+            .iter()
+            .filter(|(key, _)| prefix.is_empty() || **key == prefix || key.starts_with(&nested))
+            .filter(|(_, file)| !file.binary)
+            .filter(|(_, file)| needed.iter().all(|t| file.trigrams.binary_search(t).is_ok()))
+            .map(|(key, _)| self.root.join(key))
+            .collect()
+    }
+
+    /// Root-relative, `/`-separated keys of every indexed text file.
+    pub fn text_files(&self) -> Vec<String> {
+        self.files
+            .iter()
+            .filter(|(_, file)| !file.binary)
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    pub fn binary_files(&self) -> usize {
+        self.files.values().filter(|f| f.binary).count()
+    }
+}
+
+fn relative_key(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    Some(
+        relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+    )
+}
+
+fn trigrams(bytes: &[u8]) -> Vec<u32> {
+    let lowered = bytes.to_ascii_lowercase();
+    let set = lowered
+        .windows(3)
+        .map(|w| (u32::from(w[0]) << 16) | (u32::from(w[1]) << 8) | u32::from(w[2]))
+        .collect::<BTreeSet<_>>();
+    set.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn index_narrows_candidates_and_refreshes_incrementally() {
+        let project = tempdir().expect("project");
+        let cache = tempdir().expect("cache");
+        let root = project.path();
+        fs::create_dir_all(root.join("src")).expect("mkdir");
+        fs::write(root.join("src/lib.rs"), "pub fn ParseConfig() {}\n").expect("write");
+        fs::write(root.join("README.md"), "hello\n").expect("write");
+        fs::write(root.join("logo.png"), b"\x89PNG\0\0").expect("write");
+
+        let (index, refresh) = ProjectIndex::update(cache.path(), root).expect("build");
+        assert_eq!(refresh.updated, 3);
+        assert_eq!(index.binary_files(), 1);
+        assert_eq!(index.candidates(root, Some("parseconfig")), vec![root.join("src/lib.rs")]);
+        assert_eq!(index.candidates(&root.join("src"), Some("hello")), Vec::<PathBuf>::new());
+        assert_eq!(index.candidates(root, None).len(), 2);
+
+        let (_, unchanged) = ProjectIndex::update(cache.path(), root).expect("reload");
+        assert!(!unchanged.changed());
+
+        fs::remove_file(root.join("README.md")).expect("rm");
+        fs::write(root.join("src/lib.rs"), "pub fn load() {}\n// hello again\n").expect("rewrite");
+        let (index, refresh) = ProjectIndex::update(cache.path(), root).expect("refresh");
+        assert_eq!((refresh.updated, refresh.removed), (1, 1));
+        assert_eq!(index.candidates(root, Some("hello")), vec![root.join("src/lib.rs")]);
+    }
+}
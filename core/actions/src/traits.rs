@@ -1,21 +1,73 @@
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use ipc::{ToolCall, ToolResult};
+use regex::RegexBuilder;
+use crate::browser::WebDriverBrowser;
+use crate::calendar::{CalDavCalendar, EventQuery};
+use crate::code_run::{CodeLanguage, CodeRunLimits};
+use crate::command_tool::CommandToolSpec;
+use crate::project_index::{ProjectIndex, MAX_INDEXED_FILE_BYTES};
 use serde_json::{json, Value};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Component, Path, PathBuf};
-use std::process::Command;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::process::{Command, Output, Stdio};
 use std::rc::Rc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Error reported by every tool that runs past its deadline (`tool_timeout_secs` or its own).
+pub const TOOL_TIMEOUT_ERROR: &str = "tool_timeout";
 
 pub trait ActionBackend {
     fn platform_name(&self) -> &'static str;
     fn execute_tool(&self, tool_call: &ToolCall) -> ToolResult;
+
+    /// Executes a tool within a wall-clock budget. This default cannot interrupt anything and
+    /// just calls `execute_tool`, so the budget is only enforced by backends that override it.
+    fn execute_tool_with_timeout(&self, tool_call: &ToolCall, timeout: Duration) -> ToolResult {
+        let _ = timeout;
+        self.execute_tool(tool_call)
+    }
+
+    /// Describes what a consent-gated call would change, without performing it.
+    fn preview_tool_effect(&self, tool_call: &ToolCall) -> Option<String> {
+        let _ = tool_call;
+        None
+    }
+
+    /// Stands in for `execute_tool` in a dry run: reports what the call would do and changes
+    /// nothing.
+    fn simulate_tool(&self, tool_call: &ToolCall) -> ToolResult {
+        simulated_result(
+            self.platform_name(),
+            tool_call,
+            format!("would run {}", tool_call.name),
+            self.preview_tool_effect(tool_call),
+        )
+    }
 }
 
+/// Invokes `(server_id, tool_name, arguments_json, deadline)` against an MCP server runtime.
+/// `deadline` is the caller's tool timeout; without one the runtime uses its own.
+pub type McpInvoker = Rc<dyn Fn(&str, &str, &str, Option<Instant>) -> Result<String, String>>;
+
+/// Delivers `(title, message, targets)` for `notify.send`, returning one JSON delivery report
+/// per target.
+pub type Notifier = Rc<dyn Fn(&str, &str, &[String]) -> Result<Vec<Value>, String>>;
+
 #[derive(Clone)]
 pub struct StubActionBackend {
     platform: &'static str,
     project_root: Option<PathBuf>,
-    mcp_invoker: Option<Rc<dyn Fn(&str, &str, &str) -> Result<String, String>>>,
+    mcp_invoker: Option<McpInvoker>,
+    index_dir: Option<PathBuf>,
+    command_tools: Vec<CommandToolSpec>,
+    db_connections: BTreeMap<String, String>,
+    browser: Option<Rc<WebDriverBrowser>>,
+    notifier: Option<Notifier>,
+    caldav_calendars: BTreeMap<String, CalDavCalendar>,
 }
 
 impl std::fmt::Debug for StubActionBackend {
@@ -24,6 +76,12 @@ impl std::fmt::Debug for StubActionBackend {
             .field("platform", &self.platform)
             .field("project_root", &self.project_root)
             .field("has_mcp_invoker", &self.mcp_invoker.is_some())
+            .field("index_dir", &self.index_dir)
+            .field("command_tools", &self.command_tools.len())
+            .field("db_connections", &self.db_connections.keys().collect::<Vec<_>>())
+            .field("browser", &self.browser.as_ref().map(|b| b.config().webdriver_url.clone()))
+            .field("has_notifier", &self.notifier.is_some())
+            .field("caldav_calendars", &self.caldav_calendars.keys().collect::<Vec<_>>())
             .finish()
     }
 }
@@ -34,6 +92,12 @@ impl StubActionBackend {
             platform,
             project_root: None,
             mcp_invoker: None,
+            index_dir: None,
+            command_tools: Vec::new(),
+            db_connections: BTreeMap::new(),
+            browser: None,
+            notifier: None,
+            caldav_calendars: BTreeMap::new(),
         }
     }
 
@@ -42,17 +106,513 @@ impl StubActionBackend {
             platform,
             project_root,
             mcp_invoker: None,
+            index_dir: None,
+            command_tools: Vec::new(),
+            db_connections: BTreeMap::new(),
+            browser: None,
+            notifier: None,
+            caldav_calendars: BTreeMap::new(),
         }
     }
 
-    pub fn with_mcp_invoker(
-        mut self,
-        mcp_invoker: Rc<dyn Fn(&str, &str, &str) -> Result<String, String>>,
-    ) -> Self {
+    pub fn with_mcp_invoker(mut self, mcp_invoker: McpInvoker) -> Self {
         self.mcp_invoker = Some(mcp_invoker);
         self
     }
 
+    /// Lets `file.search_text` consult the persistent project index kept under `index_dir`.
+    pub fn with_index_dir(mut self, index_dir: PathBuf) -> Self {
+        self.index_dir = Some(index_dir);
+        self
+    }
+
+    /// Runs the user-declared `command_tools` by name, in the project root when one is set.
+    pub fn with_command_tools(mut self, command_tools: Vec<CommandToolSpec>) -> Self {
+        self.command_tools = command_tools;
+        self
+    }
+
+    /// Postgres connection strings `db.query` may use, by name. Only names reach the model;
+    /// connection strings appear in results with their password masked.
+    pub fn with_db_connections(mut self, db_connections: BTreeMap<String, String>) -> Self {
+        self.db_connections = db_connections;
+        self
+    }
+
+    pub fn with_notifier(mut self, notifier: Notifier) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Runs `browser.*` against this WebDriver session, which outlives the backend.
+    pub fn with_browser(mut self, browser: Rc<WebDriverBrowser>) -> Self {
+        self.browser = Some(browser);
+        self
+    }
+
+    /// CalDAV collections `calendar.*` may use by name instead of a project `.ics` file.
+    pub fn with_caldav_calendars(mut self, caldav_calendars: BTreeMap<String, CalDavCalendar>) -> Self {
+        self.caldav_calendars = caldav_calendars;
+        self
+    }
+
+    fn run_command_tool(&self, spec: &CommandToolSpec, args: &Value, deadline: Option<Instant>) -> ToolResult {
+        let fail = |err: String| tool_error(&spec.name, self.platform, err, &spec.name, format!("command://{}", spec.name));
+        let argv = match spec.render_argv(args) {
+            Ok(argv) => argv,
+            Err(err) => return fail(err),
+        };
+        let own_deadline = spec.timeout_secs.map(|secs| Instant::now() + Duration::from_secs(secs));
+        let deadline = match (deadline, own_deadline) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        let mut command = Command::new(&argv[0]);
+        command.args(&argv[1..]);
+        if let Some(root) = &self.project_root {
+            command.current_dir(root);
+        }
+        let output = match command_output_with_deadline(&mut command, deadline) {
+            Ok(output) => output,
+            Err(err) if err.kind() == io::ErrorKind::TimedOut => return fail(TOOL_TIMEOUT_ERROR.to_string()),
+            Err(err) => return fail(format!("spawn_failed:{err}")),
+        };
+        let exit_code = output.status.code();
+        let ok = output.status.success();
+        let mut result = json!({
+            "status": if ok { "ok" } else { "error" },
+            "platform": self.platform,
+            "exit_code": exit_code,
+            "stdout": String::from_utf8_lossy(&output.stdout),
+            "stderr": String::from_utf8_lossy(&output.stderr),
+        });
+        if !ok {
+            result["error"] = json!(format!(
+                "exit={}",
+                exit_code.map(|v| v.to_string()).unwrap_or_else(|| "signal".to_string())
+            ));
+        }
+        ToolResult {
+            tool_call_id: None,
+            name: spec.name.clone(),
+            result_json: result.to_string(),
+            evidence: crate::evidence::action_evidence(
+                format!("Ran command tool '{}': {}", spec.name, argv.join(" ")),
+                format!("command://{}", spec.name),
+            ),
+        }
+    }
+
+    fn run_notify(&self, args: &Value) -> ToolResult {
+        let fail = |err: String| tool_error("notify.send", self.platform, err, "notify.send", format!("notify://{}", self.platform));
+        let text = |key: &str| args.get(key).and_then(Value::as_str).map(str::trim).filter(|value| !value.is_empty());
+        let Some(message) = text("message") else {
+            return fail("missing_message".to_string());
+        };
+        let title = text("title").unwrap_or("cmnd-n-ctrl");
+        let targets = args
+            .get("targets")
+            .and_then(Value::as_array)
+            .map(|items| items.iter().filter_map(Value::as_str).map(str::to_string).collect::<Vec<_>>())
+            .unwrap_or_default();
+        let Some(notifier) = self.notifier.as_ref() else {
+            return fail("notifications_unavailable".to_string());
+        };
+        let deliveries = match notifier(title, message, &targets) {
+            Ok(deliveries) => deliveries,
+            Err(err) => return fail(err),
+        };
+        let sent = deliveries
+            .iter()
+            .filter(|d| d.get("status").and_then(Value::as_str) == Some("sent"))
+            .filter_map(|d| d.get("target").and_then(Value::as_str))
+            .collect::<Vec<_>>();
+        let mut result = json!({
+            "status": if sent.is_empty() { "error" } else { "ok" },
+            "platform": self.platform,
+            "title": title,
+            "deliveries": deliveries,
+        });
+        if sent.is_empty() {
+            result["error"] = json!("delivery_failed");
+        }
+        ToolResult {
+            tool_call_id: None,
+            name: "notify.send".to_string(),
+            result_json: result.to_string(),
+            evidence: crate::evidence::action_evidence(
+                format!("Sent notification '{title}' to {}", if sent.is_empty() { "no target".to_string() } else { sent.join(", ") }),
+                format!("notify://{}", self.platform),
+            ),
+        }
+    }
+
+    fn run_browser_tool(&self, tool_call: &ToolCall, args: &Value, deadline: Option<Instant>) -> ToolResult {
+        let tool = tool_call.name.as_str();
+        let fail = |err: String| tool_error(tool, self.platform, err, tool, format!("browser://{}/{tool}", self.platform));
+        let Some(browser) = self.browser.as_ref() else {
+            return fail("browser_not_configured".to_string());
+        };
+        let arg = |key: &str| args.get(key).and_then(Value::as_str).filter(|value| !value.trim().is_empty());
+        let page = |page: crate::browser::PageInfo| json!({ "url": page.url, "title": page.title });
+        let outcome = match tool {
+            "browser.navigate" => match arg("url") {
+                Some(url) => browser.navigate(url, deadline).map(|info| (page(info), format!("Opened {url} in the browser"), None)),
+                None => Err("missing_url".to_string()),
+            },
+            "browser.read_text" => browser.read_text(arg("selector"), deadline).map(|(info, text, truncated)| {
+                let summary = format!("Read {} characters of page text from {}", text.chars().count(), info.url);
+                let mut result = page(info);
+                result["text"] = json!(text);
+                result["truncated"] = json!(truncated);
+                (result, summary, None)
+            }),
+            "browser.click" => match arg("selector") {
+                Some(selector) => browser
+                    .click(selector, deadline)
+                    .map(|info| (page(info), format!("Clicked '{selector}' in the browser"), None)),
+                None => Err("missing_selector".to_string()),
+            },
+            "browser.screenshot" => browser.screenshot(deadline).and_then(|(info, png)| {
+                let saved = match arg("path") {
+                    Some(path) => {
+                        let target = self.scoped_path(Some(path))?;
+                        fs::write(&target, &png).map_err(|err| format!("write_failed:{err}"))?;
+                        Some(target.display().to_string())
+                    }
+                    None => None,
+                };
+                let summary = format!("Captured a {}-byte screenshot of {}", png.len(), info.url);
+                let mut result = page(info);
+                result["bytes"] = json!(png.len());
+                result["saved_to"] = json!(saved);
+                Ok((result, summary, Some(png)))
+            }),
+            "browser.close" => browser
+                .close(deadline)
+                .map(|closed| (json!({ "closed": closed }), "Closed the browser session".to_string(), None)),
+            other => Err(format!("unknown_browser_tool:{other}")),
+        };
+        let (mut result, summary, png) = match outcome {
+            Ok(outcome) => outcome,
+            Err(err) => return fail(err),
+        };
+        result["status"] = json!("ok");
+        result["platform"] = json!(self.platform);
+        let mut evidence = crate::evidence::action_evidence(summary, format!("browser://{}/{tool}", self.platform));
+        if let Some(png) = png {
+            evidence = crate::evidence::with_attachment(evidence, "screenshot.png", "image/png", &png);
+        }
+        ToolResult {
+            tool_call_id: None,
+            name: tool_call.name.clone(),
+            result_json: result.to_string(),
+            evidence,
+        }
+    }
+
+    fn run_calendar_tool(&self, tool_call: &ToolCall, args: &Value, deadline: Option<Instant>) -> ToolResult {
+        let tool = tool_call.name.as_str();
+        let fail = |err: String| tool_error(tool, self.platform, err, tool, format!("calendar://{}/{tool}", self.platform));
+        let target = match args.get("calendar").and_then(Value::as_str) {
+            Some(name) => match self.caldav_calendars.get(name) {
+                Some(calendar) => CalendarTarget::CalDav(name, calendar),
+                None => {
+                    let known = self.caldav_calendars.keys().cloned().collect::<Vec<_>>().join(", ");
+                    return fail(format!("unknown_calendar:{name} (configured: {known})"));
+                }
+            },
+            None => {
+                let path = args.get("path").and_then(Value::as_str).unwrap_or(crate::calendar::DEFAULT_CALENDAR_PATH);
+                match self.scoped_path(Some(path)) {
+                    Ok(path) => CalendarTarget::File(path),
+                    Err(err) => return fail(err),
+                }
+            }
+        };
+        let source = match &target {
+            CalendarTarget::CalDav(name, _) => format!("caldav:{name}"),
+            CalendarTarget::File(path) => path.display().to_string(),
+        };
+
+        if tool == "calendar.list_events" {
+            let query = match EventQuery::from_args(args) {
+                Ok(query) => query,
+                Err(err) => return fail(err),
+            };
+            let events = match &target {
+                CalendarTarget::CalDav(_, calendar) => crate::calendar::caldav_list(calendar, &query, deadline),
+                CalendarTarget::File(path) => crate::calendar::read_events(path),
+            };
+            let (events, total) = match events {
+                Ok(events) => query.apply(events),
+                Err(err) => return fail(err),
+            };
+            return ToolResult {
+                tool_call_id: None,
+                name: tool_call.name.clone(),
+                result_json: json!({
+                    "status": "ok",
+                    "platform": self.platform,
+                    "source": source,
+                    "events": events.iter().map(|event| event.to_json()).collect::<Vec<_>>(),
+                    "total": total,
+                    "truncated": total > events.len(),
+                })
+                .to_string(),
+                evidence: crate::evidence::action_evidence(
+                    format!("Listed {} calendar events from {source}", events.len()),
+                    format!("calendar://{}/{tool}", self.platform),
+                ),
+            };
+        }
+
+        if tool != "calendar.create_event" {
+            return fail(format!("unknown_calendar_tool:{tool}"));
+        }
+        let event = match crate::calendar::event_from_args(args) {
+            Ok(event) => event,
+            Err(err) => return fail(err),
+        };
+        let summary = format!("Created calendar event '{}' in {source}", event.summary);
+        let evidence = match &target {
+            CalendarTarget::CalDav(_, calendar) => crate::calendar::caldav_create(calendar, &event, deadline)
+                .map(|url| crate::evidence::action_evidence(summary, url)),
+            CalendarTarget::File(path) => crate::calendar::append_event(path, &event).map(|(before, after)| {
+                diff_evidence(
+                    crate::evidence::action_evidence(summary, format!("calendar://{}/{tool}", self.platform)),
+                    path,
+                    &before,
+                    &after,
+                )
+            }),
+        };
+        let evidence = match evidence {
+            Ok(evidence) => evidence,
+            Err(err) => return fail(err),
+        };
+        ToolResult {
+            tool_call_id: None,
+            name: tool_call.name.clone(),
+            result_json: json!({
+                "status": "ok",
+                "platform": self.platform,
+                "source": source,
+                "event": event.to_json(),
+            })
+            .to_string(),
+            evidence,
+        }
+    }
+
+    fn run_db_query(&self, args: &Value, deadline: Option<Instant>) -> ToolResult {
+        let fail = |err: String| tool_error("db.query", self.platform, err, "db.query", format!("stub://{}/db.query", self.platform));
+        let sql = match args.get("sql").and_then(Value::as_str).filter(|sql| !sql.trim().is_empty()) {
+            Some(sql) => sql,
+            None => return fail("missing_sql".to_string()),
+        };
+        let max_rows = crate::db_query::max_rows(args.get("max_rows").and_then(Value::as_u64));
+        let path = args.get("path").and_then(Value::as_str);
+        let connection = args.get("connection").and_then(Value::as_str);
+        let (engine, database, target, output) = match (path, connection) {
+            (Some(path), None) => {
+                let resolved = match self.scoped_path(Some(path)) {
+                    Ok(resolved) => resolved,
+                    Err(err) => return fail(err),
+                };
+                let output = crate::db_query::query_sqlite(&resolved, sql, max_rows, deadline);
+                ("sqlite", path.to_string(), format!("sqlite://{}", resolved.display()), output)
+            }
+            (None, Some(name)) => {
+                let Some(dsn) = self.db_connections.get(name) else {
+                    let known = self.db_connections.keys().cloned().collect::<Vec<_>>().join(", ");
+                    return fail(format!("unknown_connection:{name} (configured: {known})"));
+                };
+                let output = crate::db_query::query_postgres(dsn, sql, max_rows, deadline);
+                ("postgres", name.to_string(), crate::db_query::redact_dsn(dsn), output)
+            }
+            _ => return fail("expected_path_or_connection".to_string()),
+        };
+        let output = match output {
+            Ok(output) => output,
+            Err(err) => return fail(err),
+        };
+        ToolResult {
+            tool_call_id: None,
+            name: "db.query".to_string(),
+            result_json: json!({
+                "status": "ok",
+                "platform": self.platform,
+                "engine": engine,
+                "database": database,
+                "columns": output.columns,
+                "row_count": output.rows.len(),
+                "rows": output.rows,
+                "truncated": output.truncated.is_some(),
+                "truncated_reason": output.truncated,
+            })
+            .to_string(),
+            evidence: crate::evidence::action_evidence(
+                format!("Read {} rows from {engine} database {database}", output.rows.len()),
+                target,
+            ),
+        }
+    }
+
+    fn run_code(&self, args: &Value, deadline: Option<Instant>) -> ToolResult {
+        let fail = |err: String| tool_error("code.run", self.platform, err, "code.run", format!("stub://{}/code.run", self.platform));
+        let language = match CodeLanguage::parse(args.get("language").and_then(Value::as_str).unwrap_or_default()) {
+            Ok(language) => language,
+            Err(err) => return fail(err),
+        };
+        let limits = CodeRunLimits::from_timeout_secs(args.get("timeout_secs").and_then(Value::as_u64));
+        let root = match self.scoped_path(None) {
+            Ok(root) => root,
+            Err(err) => return fail(err),
+        };
+        let code = args.get("code").and_then(Value::as_str).unwrap_or_default();
+        let mut prepared = match crate::code_run::prepare(language, code, &root, &limits) {
+            Ok(prepared) => prepared,
+            Err(err) => return fail(err),
+        };
+        let own_deadline = Instant::now() + limits.timeout;
+        let deadline = deadline.map_or(own_deadline, |d| d.min(own_deadline));
+        let output = match command_output_with_deadline(&mut prepared.command, Some(deadline)) {
+            Ok(output) => output,
+            Err(err) if err.kind() == io::ErrorKind::TimedOut => return fail(TOOL_TIMEOUT_ERROR.to_string()),
+            Err(err) => return fail(format!("spawn_failed:{err}")),
+        };
+        let exit_code = output.status.code();
+        let ok = output.status.success();
+        let (stdout, stdout_truncated) = crate::code_run::capped_stream(&output.stdout);
+        let (stderr, stderr_truncated) = crate::code_run::capped_stream(&output.stderr);
+        let mut result = json!({
+            "status": if ok { "ok" } else { "error" },
+            "platform": self.platform,
+            "language": language.label(),
+            "exit_code": exit_code,
+            "stdout": stdout,
+            "stderr": stderr,
+            "stdout_truncated": stdout_truncated,
+            "stderr_truncated": stderr_truncated,
+            "network_isolation": "namespace",
+            "limits": {
+                "timeout_secs": limits.timeout.as_secs(),
+                "cpu_secs": limits.cpu_secs,
+                "memory_mb": limits.memory_mb,
+            },
+        });
+        if !ok {
+            result["error"] = json!(format!(
+                "exit={}",
+                exit_code.map(|v| v.to_string()).unwrap_or_else(|| "signal".to_string())
+            ));
+        }
+        ToolResult {
+            tool_call_id: None,
+            name: "code.run".to_string(),
+            result_json: result.to_string(),
+            evidence: crate::evidence::action_evidence(
+                format!("Ran a {}-byte {} snippet in {}", code.len(), language.label(), prepared.dir.display()),
+                format!("stub://{}/code.run", self.platform),
+            ),
+        }
+    }
+
+    fn run_archive_tool(&self, tool_call: &ToolCall, args: &Value) -> ToolResult {
+        let tool = tool_call.name.as_str();
+        let fail = |err: String| tool_error(tool, self.platform, err, tool, self.project_root_display());
+        let root = match self.scoped_path(None) {
+            Ok(p) => p,
+            Err(err) => return fail(err),
+        };
+        let format_arg = args.get("format").and_then(Value::as_str);
+        let (summary, archive, destination, format) = if tool == "archive.create" {
+            let sources = match args.get("sources").and_then(Value::as_array) {
+                Some(items) if !items.is_empty() => items
+                    .iter()
+                    .map(|v| self.scoped_path(v.as_str()))
+                    .collect::<Result<Vec<_>, _>>(),
+                _ => Err("missing_sources".to_string()),
+            };
+            let archive = self.scoped_path(args.get("destination").and_then(Value::as_str).filter(|s| !s.trim().is_empty()));
+            let (sources, archive) = match (sources, archive) {
+                (Ok(sources), Ok(archive)) if archive != root => (sources, archive),
+                (Err(err), _) | (_, Err(err)) => return fail(err),
+                _ => return fail("missing_destination".to_string()),
+            };
+            let format = match crate::archive::ArchiveFormat::resolve(format_arg, &archive) {
+                Ok(f) => f,
+                Err(err) => return fail(err),
+            };
+            match crate::archive::create_archive(&root, &sources, &archive, format) {
+                Ok(summary) => (summary, archive.clone(), archive, format),
+                Err(err) => return fail(err),
+            }
+        } else {
+            let archive = match self.scoped_path(args.get("archive").and_then(Value::as_str).filter(|s| !s.trim().is_empty())) {
+                Ok(p) if p != root => p,
+                Ok(_) => return fail("missing_archive".to_string()),
+                Err(err) => return fail(err),
+            };
+            let format = match crate::archive::ArchiveFormat::resolve(format_arg, &archive) {
+                Ok(f) => f,
+                Err(err) => return fail(err),
+            };
+            let destination = match args.get("destination").and_then(Value::as_str) {
+                Some(dest) => self.scoped_path(Some(dest)),
+                None => {
+                    let name = archive.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                    let stem = name.trim_end_matches(".zip").trim_end_matches(".tar.gz").trim_end_matches(".tgz");
+                    Ok(archive.with_file_name(stem))
+                }
+            };
+            let destination = match destination {
+                Ok(p) => p,
+                Err(err) => return fail(err),
+            };
+            let overwrite = args.get("overwrite").and_then(Value::as_bool).unwrap_or(false);
+            match crate::archive::extract_archive(&archive, &destination, format, overwrite) {
+                Ok(summary) => (summary, archive, destination, format),
+                Err(err) => return fail(err),
+            }
+        };
+        ToolResult {
+            tool_call_id: None,
+            name: tool_call.name.clone(),
+            result_json: json!({
+                "status": "ok",
+                "platform": self.platform,
+                "project_root": self.project_root_display(),
+                "archive": archive.display().to_string(),
+                "destination": destination.display().to_string(),
+                "format": format.label(),
+                "entry_count": summary.entries.len(),
+                "bytes": summary.bytes,
+                "entries": summary.entries.iter().take(200).collect::<Vec<_>>(),
+            })
+            .to_string(),
+            evidence: crate::evidence::action_evidence(
+                format!(
+                    "{} {} entries ({} bytes) into {}",
+                    if tool == "archive.create" { "Packed" } else { "Extracted" },
+                    summary.entries.len(),
+                    summary.bytes,
+                    destination.display()
+                ),
+                format!("stub://{}/{}", self.platform, tool),
+            ),
+        }
+    }
+
+    /// Files under `dir` that may match, plus the index's binary count; `None` without an index.
+    fn indexed_candidates(&self, dir: &Path, literal: Option<&str>) -> Option<(Vec<PathBuf>, usize)> {
+        let index_dir = self.index_dir.as_ref()?;
+        let root = self.project_root.as_ref()?;
+        let (index, _) = ProjectIndex::update(index_dir, root).ok()?;
+        Some((index.candidates(dir, literal), index.binary_files()))
+    }
+
     fn scoped_path(&self, requested: Option<&str>) -> Result<PathBuf, String> {
         let root = self
             .project_root
@@ -92,8 +652,135 @@ impl ActionBackend for StubActionBackend {
     }
 
     fn execute_tool(&self, tool_call: &ToolCall) -> ToolResult {
+        self.run_tool(tool_call, None)
+    }
+
+    fn execute_tool_with_timeout(&self, tool_call: &ToolCall, timeout: Duration) -> ToolResult {
+        self.run_tool(tool_call, Some(Instant::now() + timeout))
+    }
+
+    fn preview_tool_effect(&self, tool_call: &ToolCall) -> Option<String> {
+        const PREVIEW_CONTEXT_LINES: usize = 2;
+        const PREVIEW_MAX_LINES: usize = 40;
+        let args = serde_json::from_str::<Value>(&tool_call.arguments_json).ok()?;
+        if tool_call.name == "calendar.create_event" && args.get("calendar").is_none() {
+            let path = args.get("path").and_then(Value::as_str).unwrap_or(crate::calendar::DEFAULT_CALENDAR_PATH);
+            let path = self.scoped_path(Some(path)).ok()?;
+            let before = fs::read_to_string(&path).ok();
+            let event = crate::calendar::event_from_args(&args).ok()?;
+            let after = crate::calendar::with_event(before.as_deref(), &event).ok()?;
+            return Some(crate::diff::preview_diff(
+                &path.display().to_string(),
+                before.as_deref().unwrap_or_default(),
+                &after,
+                PREVIEW_CONTEXT_LINES,
+                PREVIEW_MAX_LINES,
+            ));
+        }
+        if !matches!(tool_call.name.as_str(), "file.write_text" | "file.append_text") {
+            return None;
+        }
+        let path = self.scoped_path(args.get("path").and_then(Value::as_str)).ok()?;
+        let content = args.get("content").and_then(Value::as_str).unwrap_or("");
+        let before = fs::read_to_string(&path).unwrap_or_default();
+        let after = if tool_call.name == "file.append_text" {
+            format!("{before}{content}")
+        } else {
+            content.to_string()
+        };
+        Some(crate::diff::preview_diff(
+            &path.display().to_string(),
+            &before,
+            &after,
+            PREVIEW_CONTEXT_LINES,
+            PREVIEW_MAX_LINES,
+        ))
+    }
+
+    fn simulate_tool(&self, tool_call: &ToolCall) -> ToolResult {
+        simulated_result(
+            self.platform,
+            tool_call,
+            self.simulated_effect(tool_call),
+            self.preview_tool_effect(tool_call),
+        )
+    }
+}
+
+impl StubActionBackend {
+    /// One line on what `tool_call` would do, for `simulate_tool`.
+    fn simulated_effect(&self, tool_call: &ToolCall) -> String {
+        let args = serde_json::from_str::<Value>(&tool_call.arguments_json).unwrap_or(Value::Null);
+        let text = |key: &str| args.get(key).and_then(Value::as_str).unwrap_or_default();
+        let path = |key: &str| match self.scoped_path(args.get(key).and_then(Value::as_str)) {
+            Ok(path) => path.display().to_string(),
+            Err(err) => format!("<{err}>"),
+        };
+        if let Some(spec) = self.command_tools.iter().find(|spec| spec.name == tool_call.name) {
+            return match spec.render_argv(&args) {
+                Ok(argv) => format!("would run {}", argv.join(" ")),
+                Err(err) => format!("would fail: {err}"),
+            };
+        }
+        match tool_call.name.as_str() {
+            "file.write_text" => format!("would write {} bytes to {}", text("content").len(), path("path")),
+            "file.append_text" => format!("would append {} bytes to {}", text("content").len(), path("path")),
+            "file.mkdir" => match self.scoped_path(args.get("path").and_then(Value::as_str)) {
+                Ok(dir) if dir.is_dir() => format!("directory {} already exists", dir.display()),
+                Ok(dir) => format!("would create directory {}", dir.display()),
+                Err(err) => format!("would fail: {err}"),
+            },
+            "archive.create" => {
+                let sources = args
+                    .get("sources")
+                    .and_then(Value::as_array)
+                    .map(|items| items.iter().filter_map(Value::as_str).collect::<Vec<_>>().join(", "))
+                    .unwrap_or_default();
+                format!("would pack {sources} into {}", path("destination"))
+            }
+            "archive.extract" => format!(
+                "would extract {} into {}",
+                path("archive"),
+                args.get("destination").map_or("a directory next to it".to_string(), |_| path("destination"))
+            ),
+            "calendar.create_event" => match crate::calendar::event_from_args(&args) {
+                Ok(event) => format!(
+                    "would add '{}' at {} to {}",
+                    event.summary,
+                    event.start.map(|start| start.iso()).unwrap_or_default(),
+                    match (args.get("calendar").and_then(Value::as_str), args.get("path")) {
+                        (Some(name), _) => format!("caldav:{name}"),
+                        (None, Some(_)) => path("path"),
+                        (None, None) => crate::calendar::DEFAULT_CALENDAR_PATH.to_string(),
+                    }
+                ),
+                Err(err) => format!("would fail: {err}"),
+            },
+            "code.run" => format!("would run a {} snippet of {} bytes", text("language"), text("code").len()),
+            "notify.send" => {
+                let targets = args
+                    .get("targets")
+                    .and_then(Value::as_array)
+                    .map(|items| items.iter().filter_map(Value::as_str).collect::<Vec<_>>().join(", "))
+                    .filter(|targets| !targets.is_empty())
+                    .unwrap_or_else(|| "the default targets".to_string());
+                format!("would send '{}' to {targets}", text("message"))
+            }
+            "browser.navigate" | "desktop.open_url" => format!("would open {}", text("url")),
+            "browser.click" => format!("would click '{}' in the browser", text("selector")),
+            "desktop.app.activate" => format!("would bring {} to the front", text("app")),
+            "mcp.tool_call" => format!("would call {} on MCP server {}", text("tool_name"), text("server_id")),
+            other => format!("would run {other}"),
+        }
+    }
+
+    fn run_tool(&self, tool_call: &ToolCall, deadline: Option<Instant>) -> ToolResult {
         let args = serde_json::from_str::<Value>(&tool_call.arguments_json).unwrap_or(Value::Null);
 
+        if let Some(spec) = self.command_tools.iter().find(|spec| spec.name == tool_call.name) {
+            return self.run_command_tool(spec, &args, deadline);
+        }
+
         if tool_call.name == "time.now" {
             let unix_seconds = SystemTime::now()
                 .duration_since(UNIX_EPOCH)
@@ -272,6 +959,63 @@ impl ActionBackend for StubActionBackend {
             };
         }
 
+        if tool_call.name == "file.read_bytes" {
+            let requested = args.get("path").and_then(Value::as_str);
+            let path = match self.scoped_path(requested) {
+                Ok(p) => p,
+                Err(err) => {
+                    return tool_error(
+                        &tool_call.name,
+                        self.platform,
+                        err,
+                        "file.read_bytes",
+                        self.project_root_display(),
+                    )
+                }
+            };
+            let offset = args.get("offset").and_then(Value::as_u64).unwrap_or(0);
+            let length = args
+                .get("length")
+                .and_then(Value::as_u64)
+                .unwrap_or(READ_BYTES_DEFAULT_LEN)
+                .min(READ_BYTES_MAX_LEN);
+            let read = read_byte_range(&path, offset, length);
+            let (size, head, bytes) = match read {
+                Ok(v) => v,
+                Err(err) => {
+                    return tool_error(
+                        &tool_call.name,
+                        self.platform,
+                        format!("read_failed:{err}"),
+                        "file.read_bytes",
+                        path.display().to_string(),
+                    )
+                }
+            };
+            let end = offset.saturating_add(bytes.len() as u64);
+            return ToolResult {
+                tool_call_id: None,
+                name: tool_call.name.clone(),
+                result_json: json!({
+                    "status": "ok",
+                    "platform": self.platform,
+                    "project_root": self.project_root_display(),
+                    "path": path.display().to_string(),
+                    "mime_type": crate::mime::sniff_mime(&head, &path),
+                    "size": size,
+                    "offset": offset,
+                    "length": bytes.len(),
+                    "truncated": end < size,
+                    "base64": BASE64_STANDARD.encode(&bytes),
+                })
+                .to_string(),
+                evidence: crate::evidence::action_evidence(
+                    format!("Read {} bytes at offset {} from {}", bytes.len(), offset, path.display()),
+                    format!("stub://{}/file.read_bytes", self.platform),
+                ),
+            };
+        }
+
         if tool_call.name == "file.read_csv" {
             let requested = args.get("path").and_then(Value::as_str);
             let path = match self.scoped_path(requested) {
@@ -418,9 +1162,32 @@ impl ActionBackend for StubActionBackend {
                 }
             };
             let limit = args.get("limit").and_then(Value::as_u64).unwrap_or(25).min(200) as usize;
+            let regex = args.get("regex").and_then(Value::as_bool).unwrap_or(false);
+            let case_insensitive = args.get("case_insensitive").and_then(Value::as_bool).unwrap_or(false);
+            let search = match TextSearch::new(query, regex, case_insensitive, args.get("include"), args.get("exclude")) {
+                Ok(search) => search,
+                Err(err) => {
+                    return tool_error(&tool_call.name, self.platform, err, "file.search_text", root.display().to_string())
+                }
+            };
             let mut matches = Vec::new();
-            let mut scanned_files = 0usize;
-            search_text_recursive(&root, query, limit, &mut matches, &mut scanned_files);
+            let mut stats = TextSearchStats::default();
+            let indexed = self.indexed_candidates(&root, (!regex).then_some(query));
+            if let Some((candidates, binary_files)) = &indexed {
+                stats.skipped_binary = *binary_files;
+                for path in candidates {
+                    if matches.len() >= limit || deadline.map(|d| Instant::now() >= d).unwrap_or(false) {
+                        break;
+                    }
+                    if search.filters_out(path.strip_prefix(&root).unwrap_or(path)) {
+                        continue;
+                    }
+                    search_text_file(path, &search, limit, &mut matches, &mut stats);
+                }
+            } else {
+                search_text_recursive(&root, &root, &search, limit, deadline, &mut matches, &mut stats);
+            }
+            let timed_out = deadline.map(|d| Instant::now() >= d).unwrap_or(false);
             return ToolResult {
                 tool_call_id: None,
                 name: tool_call.name.clone(),
@@ -430,8 +1197,13 @@ impl ActionBackend for StubActionBackend {
                     "project_root": self.project_root_display(),
                     "path": root.display().to_string(),
                     "query": query,
-                    "scanned_files": scanned_files,
+                    "regex": regex,
+                    "case_insensitive": case_insensitive,
+                    "scanned_files": stats.scanned_files,
+                    "skipped_binary": stats.skipped_binary,
+                    "indexed": indexed.is_some(),
                     "limit": limit,
+                    "timed_out": timed_out,
                     "matches": matches
                 })
                 .to_string(),
@@ -442,6 +1214,56 @@ impl ActionBackend for StubActionBackend {
             };
         }
 
+        if tool_call.name == "file.glob" {
+            let pattern = args.get("pattern").and_then(Value::as_str).unwrap_or("");
+            let requested = args.get("path").and_then(Value::as_str);
+            let root = match self.scoped_path(requested) {
+                Ok(p) => p,
+                Err(err) => {
+                    return tool_error(
+                        &tool_call.name,
+                        self.platform,
+                        err,
+                        "file.glob",
+                        self.project_root_display(),
+                    )
+                }
+            };
+            let respect_gitignore = args.get("respect_gitignore").and_then(Value::as_bool).unwrap_or(true);
+            let limit = args.get("limit").and_then(Value::as_u64).unwrap_or(200).min(1000) as usize;
+            return match crate::file_glob::glob_files(&root, pattern, respect_gitignore, limit, deadline) {
+                Ok(found) => ToolResult {
+                    tool_call_id: None,
+                    name: tool_call.name.clone(),
+                    result_json: json!({
+                        "status": "ok",
+                        "platform": self.platform,
+                        "project_root": self.project_root_display(),
+                        "path": root.display().to_string(),
+                        "pattern": pattern,
+                        "respect_gitignore": respect_gitignore,
+                        "count": found.count,
+                        "limit": limit,
+                        "truncated": found.truncated,
+                        "timed_out": found.timed_out,
+                        "paths": found.paths
+                    })
+                    .to_string(),
+                    evidence: crate::evidence::action_evidence(
+                        format!("Matched {} files for '{}' under {}", found.count, pattern, root.display()),
+                        format!("stub://{}/file.glob", self.platform),
+                    ),
+                },
+                Err(err) => tool_error(
+                    &tool_call.name,
+                    self.platform,
+                    err,
+                    "file.glob",
+                    root.display().to_string(),
+                ),
+            };
+        }
+
         if tool_call.name == "file.stat" {
             let requested = args.get("path").and_then(Value::as_str);
             let path = match self.scoped_path(requested) {
@@ -520,6 +1342,7 @@ impl ActionBackend for StubActionBackend {
                     );
                 }
             }
+            let before = fs::read_to_string(&path).unwrap_or_default();
             if let Err(err) = fs::write(&path, content) {
                 return tool_error(
                     &tool_call.name,
@@ -541,9 +1364,14 @@ impl ActionBackend for StubActionBackend {
                     "note": "file written under project scope"
                 })
                 .to_string(),
-                evidence: crate::evidence::action_evidence(
-                    format!("Wrote text file {} ({} bytes)", path.display(), content.len()),
-                    format!("stub://{}/file.write_text", self.platform),
+                evidence: diff_evidence(
+                    crate::evidence::action_evidence(
+                        format!("Wrote text file {} ({} bytes)", path.display(), content.len()),
+                        format!("stub://{}/file.write_text", self.platform),
+                    ),
+                    &path,
+                    &before,
+                    content,
                 ),
             };
         }
@@ -574,12 +1402,12 @@ impl ActionBackend for StubActionBackend {
                     );
                 }
             }
-            let mut existing = if path.exists() {
+            let before = if path.exists() {
                 fs::read_to_string(&path).unwrap_or_default()
             } else {
                 String::new()
             };
-            existing.push_str(content);
+            let existing = format!("{before}{content}");
             if let Err(err) = fs::write(&path, &existing) {
                 return tool_error(
                     &tool_call.name,
@@ -602,9 +1430,14 @@ impl ActionBackend for StubActionBackend {
                     "note": "file appended under project scope"
                 })
                 .to_string(),
-                evidence: crate::evidence::action_evidence(
-                    format!("Appended text file {} ({} bytes)", path.display(), content.len()),
-                    format!("stub://{}/file.append_text", self.platform),
+                evidence: diff_evidence(
+                    crate::evidence::action_evidence(
+                        format!("Appended text file {} ({} bytes)", path.display(), content.len()),
+                        format!("stub://{}/file.append_text", self.platform),
+                    ),
+                    &path,
+                    &before,
+                    &existing,
                 ),
             };
         }
@@ -676,7 +1509,7 @@ impl ActionBackend for StubActionBackend {
                     server_id.to_string(),
                 );
             };
-            let result_json = match invoker(server_id, tool_name, &arguments_json) {
+            let result_json = match invoker(server_id, tool_name, &arguments_json, deadline) {
                 Ok(result_json) => result_json,
                 Err(err) => {
                     return tool_error(
@@ -719,7 +1552,7 @@ impl ActionBackend for StubActionBackend {
                 );
             };
             let arguments_json = serde_json::to_string(&args).unwrap_or_else(|_| "{}".to_string());
-            let result_json = match invoker(server_id, tool_name, &arguments_json) {
+            let result_json = match invoker(server_id, tool_name, &arguments_json, deadline) {
                 Ok(result_json) => result_json,
                 Err(err) => {
                     return tool_error(
@@ -742,8 +1575,32 @@ impl ActionBackend for StubActionBackend {
             };
         }
 
-        if tool_call.name == "desktop.open_url" {
-            let url = args.get("url").and_then(Value::as_str).unwrap_or("about:blank");
+        if tool_call.name == "archive.create" || tool_call.name == "archive.extract" {
+            return self.run_archive_tool(tool_call, &args);
+        }
+
+        if tool_call.name == "notify.send" {
+            return self.run_notify(&args);
+        }
+
+        if tool_call.name.starts_with("browser.") {
+            return self.run_browser_tool(tool_call, &args, deadline);
+        }
+
+        if tool_call.name.starts_with("calendar.") {
+            return self.run_calendar_tool(tool_call, &args, deadline);
+        }
+
+        if tool_call.name == "db.query" {
+            return self.run_db_query(&args, deadline);
+        }
+
+        if tool_call.name == "code.run" {
+            return self.run_code(&args, deadline);
+        }
+
+        if tool_call.name == "desktop.open_url" {
+            let url = args.get("url").and_then(Value::as_str).unwrap_or("about:blank");
             let Some((cmd, argv)) = desktop_open_url_command(url) else {
                 return tool_error(
                     &tool_call.name,
@@ -784,6 +1641,43 @@ impl ActionBackend for StubActionBackend {
 
         if tool_call.name == "desktop.app.list" {
             let filter = args.get("filter").and_then(Value::as_str).unwrap_or_default();
+            if cfg!(feature = "process-list") {
+                let windowed_only = args.get("windowed_only").and_then(Value::as_bool).unwrap_or(false);
+                return match crate::processes::list_desktop_apps(filter, windowed_only) {
+                    Ok(apps) => {
+                        let total = apps.len();
+                        let apps = apps
+                            .into_iter()
+                            .take(DESKTOP_APP_LIST_LIMIT)
+                            .map(|app| json!({"pid": app.pid, "name": app.name, "window_titles": app.window_titles}))
+                            .collect::<Vec<_>>();
+                        ToolResult {
+                            tool_call_id: None,
+                            name: tool_call.name.clone(),
+                            result_json: json!({
+                                "status": "ok",
+                                "platform": self.platform,
+                                "apps": apps,
+                                "total": total,
+                                "truncated": total > DESKTOP_APP_LIST_LIMIT,
+                                "filter": filter
+                            })
+                            .to_string(),
+                            evidence: crate::evidence::action_evidence(
+                                format!("Listed {total} running apps on {}", self.platform),
+                                format!("desktop://{}/desktop.app.list", self.platform),
+                            ),
+                        }
+                    }
+                    Err(err) => tool_error(
+                        &tool_call.name,
+                        self.platform,
+                        format!("app_list_failed:{err}"),
+                        "desktop.app.list",
+                        format!("desktop://{}/desktop.app.list", self.platform),
+                    ),
+                };
+            }
             return ToolResult {
                 tool_call_id: None,
                 name: tool_call.name.clone(),
@@ -806,8 +1700,13 @@ impl ActionBackend for StubActionBackend {
         }
 
         if tool_call.name == "desktop.app.activate" {
+            let pid = args.get("pid").and_then(Value::as_u64).and_then(|v| u32::try_from(v).ok());
             let app = args.get("app").and_then(Value::as_str).unwrap_or("unknown");
-            let Some((cmd, argv)) = desktop_activate_command(app) else {
+            let command = match pid {
+                Some(pid) => desktop_activate_pid_command(pid),
+                None => desktop_activate_command(app),
+            };
+            let Some((cmd, argv)) = command else {
                 return tool_error(
                     &tool_call.name,
                     self.platform,
@@ -816,7 +1715,7 @@ impl ActionBackend for StubActionBackend {
                     format!("desktop://{}/app_activate", self.platform),
                 );
             };
-            let output = Command::new(&cmd).args(&argv).output();
+            let output = command_output_with_deadline(Command::new(&cmd).args(&argv), deadline);
             return match output {
                 Ok(out) if out.status.success() => ToolResult {
                     tool_call_id: None,
@@ -825,6 +1724,7 @@ impl ActionBackend for StubActionBackend {
                         "status": "ok",
                         "platform": self.platform,
                         "app": app,
+                        "pid": pid,
                         "command": cmd,
                         "args": argv,
                         "note": "best_effort_activation_attempted"
@@ -846,6 +1746,13 @@ impl ActionBackend for StubActionBackend {
                     "desktop.app.activate",
                     format!("desktop://{}/desktop.app.activate", self.platform),
                 ),
+                Err(err) if err.kind() == io::ErrorKind::TimedOut => tool_error(
+                    &tool_call.name,
+                    self.platform,
+                    TOOL_TIMEOUT_ERROR,
+                    "desktop.app.activate",
+                    format!("desktop://{}/desktop.app.activate", self.platform),
+                ),
                 Err(err) => tool_error(
                     &tool_call.name,
                     self.platform,
@@ -936,83 +1843,107 @@ fn desktop_activate_command(app: &str) -> Option<(String, Vec<String>)> {
     None
 }
 
+/// Targets a pid from `desktop.app.list`, which is unambiguous when several instances share a name.
+fn desktop_activate_pid_command(pid: u32) -> Option<(String, Vec<String>)> {
+    #[cfg(target_os = "macos")]
+    {
+        return Some((
+            "osascript".to_string(),
+            vec![
+                "-e".to_string(),
+                format!("tell application \"System Events\" to set frontmost of (first process whose unix id is {pid}) to true"),
+            ],
+        ));
+    }
+    #[cfg(target_os = "windows")]
+    {
+        return Some((
+            "powershell".to_string(),
+            vec![
+                "-NoProfile".to_string(),
+                "-Command".to_string(),
+                format!("if ((New-Object -ComObject WScript.Shell).AppActivate({pid})) {{ exit 0 }} else {{ exit 1 }}"),
+            ],
+        ));
+    }
+    #[cfg(target_os = "linux")]
+    {
+        return Some((
+            "/bin/sh".to_string(),
+            vec![
+                "-lc".to_string(),
+                format!(
+                    "if command -v wmctrl >/dev/null 2>&1; then w=$(wmctrl -lp | awk '$3 == {pid} {{ print $1; exit }}'); [ -n \"$w\" ] && wmctrl -ia \"$w\"; elif command -v xdotool >/dev/null 2>&1; then xdotool search --pid {pid} windowactivate; else exit 127; fi"
+                ),
+            ],
+        ));
+    }
+    #[allow(unreachable_code)]
+    None
+}
+
 fn shell_single_quote(input: &str) -> String {
     format!("'{}'", input.replace('\'', "'\"'\"'"))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+const DESKTOP_APP_LIST_LIMIT: usize = 200;
+const READ_BYTES_DEFAULT_LEN: u64 = 64 * 1024;
+/// Base64 inflates by a third, so this keeps a single tool result around 350 KB.
+const READ_BYTES_MAX_LEN: u64 = 256 * 1024;
 
-    #[test]
-    fn mcp_tool_call_uses_invoker() {
-        let backend = StubActionBackend::new("test").with_mcp_invoker(Rc::new(|server, tool, args| {
-            Ok(json!({
-                "server": server,
-                "tool": tool,
-                "args": serde_json::from_str::<Value>(args).unwrap_or_else(|_| json!({}))
-            })
-            .to_string())
-        }));
-        let result = backend.execute_tool(&ToolCall {
-            tool_call_id: None,
-            name: "mcp.tool_call".to_string(),
-            arguments_json: json!({
-                "server_id": "mcp-1",
-                "tool_name": "browser.open",
-                "arguments": { "url": "https://example.com" }
-            })
-            .to_string(),
-        });
-        assert!(result.result_json.contains("\"server\":\"mcp-1\""));
-        assert!(result.result_json.contains("\"tool\":\"browser.open\""));
-    }
+/// Returns the file size, its first `SNIFF_LEN` bytes (for MIME detection) and the requested range.
+fn read_byte_range(path: &Path, offset: u64, length: u64) -> io::Result<(u64, Vec<u8>, Vec<u8>)> {
+    let mut file = fs::File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut head = Vec::new();
+    (&mut file).take(crate::mime::SNIFF_LEN as u64).read_to_end(&mut head)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::new();
+    file.take(length).read_to_end(&mut bytes)?;
+    Ok((size, head, bytes))
+}
 
-    #[test]
-    fn mcp_server_alias_uses_invoker() {
-        let backend = StubActionBackend::new("test").with_mcp_invoker(Rc::new(|server, tool, args| {
-            Ok(json!({
-                "server": server,
-                "tool": tool,
-                "args": serde_json::from_str::<Value>(args).unwrap_or_else(|_| json!({}))
-            })
-            .to_string())
-        }));
-        let result = backend.execute_tool(&ToolCall {
-            tool_call_id: None,
-            name: "mcp.server.mcp-1.browser.open".to_string(),
-            arguments_json: json!({ "url": "https://example.com" }).to_string(),
-        });
-        assert!(result.result_json.contains("\"server\":\"mcp-1\""));
-        assert!(result.result_json.contains("\"tool\":\"browser.open\""));
-        assert!(result.result_json.contains("\"url\":\"https://example.com\""));
-    }
+/// Evidence diff for a file write; larger than the consent preview, but still capped.
+const EVIDENCE_DIFF_MAX_LINES: usize = 5_000;
 
-    #[test]
-    fn desktop_open_url_command_selects_platform_launcher() {
-        let (cmd, args) = desktop_open_url_command("https://example.com").expect("command");
-        #[cfg(target_os = "linux")]
-        assert_eq!(cmd, "xdg-open");
-        #[cfg(target_os = "macos")]
-        assert_eq!(cmd, "open");
-        #[cfg(target_os = "windows")]
-        assert_eq!(cmd, "cmd");
-        assert!(!args.is_empty());
+/// The result a dry run returns in place of running `tool_call`: its `effect` and, for writes
+/// the backend can preview, the diff it would apply.
+pub fn simulated_result(platform: &str, tool_call: &ToolCall, effect: String, preview_diff: Option<String>) -> ToolResult {
+    let mut evidence = crate::evidence::action_evidence(
+        format!("Simulated {}: {effect}", tool_call.name),
+        format!("dry-run://{platform}/{}", tool_call.name),
+    );
+    if let Some(diff) = &preview_diff {
+        evidence = crate::evidence::with_attachment(evidence, "preview.diff", "text/x-diff", diff.as_bytes());
     }
-
-    #[test]
-    fn desktop_activate_command_selects_platform_adapter() {
-        let (cmd, args) = desktop_activate_command("Browser").expect("command");
-        #[cfg(target_os = "linux")]
-        assert_eq!(cmd, "/bin/sh");
-        #[cfg(target_os = "macos")]
-        assert_eq!(cmd, "osascript");
-        #[cfg(target_os = "windows")]
-        assert_eq!(cmd, "powershell");
-        assert!(!args.is_empty());
+    ToolResult {
+        tool_call_id: None,
+        name: tool_call.name.clone(),
+        result_json: json!({
+            "status": "simulated",
+            "platform": platform,
+            "effect": effect,
+            "preview_diff": preview_diff,
+            "note": "dry run: nothing was changed",
+        })
+        .to_string(),
+        evidence,
     }
 }
 
+/// Where a `calendar.*` call reads or writes: a configured CalDAV collection or a project file.
+enum CalendarTarget<'a> {
+    CalDav(&'a str, &'a CalDavCalendar),
+    File(PathBuf),
+}
+
+fn diff_evidence(evidence: ipc::Evidence, path: &Path, before: &str, after: &str) -> ipc::Evidence {
+    let label = path.display().to_string();
+    let diff = crate::diff::preview_diff(&label, before, after, 3, EVIDENCE_DIFF_MAX_LINES);
+    let name = format!("{}.diff", path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default());
+    crate::evidence::with_attachment(evidence, name, "text/x-diff", diff.as_bytes())
+}
+
 fn tool_error(
     tool_name: &str,
     platform: &str,
@@ -1047,7 +1978,7 @@ fn truncate_chars(input: &str, max_chars: usize) -> String {
     }
 }
 
-fn normalize_path(path: PathBuf) -> PathBuf {
+pub(crate) fn normalize_path(path: PathBuf) -> PathBuf {
     let mut out = PathBuf::new();
     for comp in path.components() {
         match comp {
@@ -1104,17 +2035,134 @@ fn preview_json_scalar_or_shallow(value: &Value) -> Value {
     }
 }
 
+/// Output past this is read and discarded by `command_output_with_deadline`.
+const MAX_CAPTURED_OUTPUT_BYTES: usize = 16 * 1024 * 1024;
+
+/// Runs a command to completion, killing it once `deadline` passes.
+fn command_output_with_deadline(command: &mut Command, deadline: Option<Instant>) -> io::Result<Output> {
+    let Some(deadline) = deadline else {
+        return command.output();
+    };
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Drain both pipes while waiting so a chatty child can't block on a full pipe buffer.
+    let stdout = child.stdout.take().map(drain_pipe);
+    let stderr = child.stderr.take().map(drain_pipe);
+    loop {
+        if let Some(status) = child.try_wait()? {
+            let collect = |reader: Option<thread::JoinHandle<Vec<u8>>>| {
+                reader.map(|r| r.join().unwrap_or_default()).unwrap_or_default()
+            };
+            return Ok(Output {
+                status,
+                stdout: collect(stdout),
+                stderr: collect(stderr),
+            });
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "tool execution timed out"));
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Reads `pipe` to the end on its own thread, keeping the first `MAX_CAPTURED_OUTPUT_BYTES`.
+fn drain_pipe(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut kept = Vec::new();
+        let mut buf = [0u8; 8192];
+        while let Ok(n) = pipe.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            let room = MAX_CAPTURED_OUTPUT_BYTES.saturating_sub(kept.len());
+            kept.extend_from_slice(&buf[..n.min(room)]);
+        }
+        kept
+    })
+}
+
+struct TextSearch {
+    pattern: regex::Regex,
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl TextSearch {
+    fn new(
+        query: &str,
+        regex: bool,
+        case_insensitive: bool,
+        include: Option<&Value>,
+        exclude: Option<&Value>,
+    ) -> Result<Self, String> {
+        let source = if regex { query.to_string() } else { regex::escape(query) };
+        let pattern = RegexBuilder::new(&source)
+            .case_insensitive(case_insensitive)
+            .size_limit(1 << 20)
+            .build()
+            .map_err(|err| format!("invalid_regex:{err}"))?;
+        Ok(Self {
+            pattern,
+            include: glob_set(include)?,
+            exclude: glob_set(exclude)?,
+        })
+    }
+
+    /// Include/exclude check for a file path relative to the search root.
+    fn filters_out(&self, relative: &Path) -> bool {
+        self.exclude.as_ref().is_some_and(|set| set.is_match(relative))
+            || self.include.as_ref().is_some_and(|set| !set.is_match(relative))
+    }
+}
+
+/// Accepts a single glob or an array; patterns match paths relative to the search root.
+fn glob_set(value: Option<&Value>) -> Result<Option<GlobSet>, String> {
+    let patterns = match value {
+        None | Some(Value::Null) => return Ok(None),
+        Some(Value::String(p)) => vec![p.as_str()],
+        Some(Value::Array(items)) => items.iter().filter_map(Value::as_str).collect(),
+        Some(_) => return Err("invalid_glob_filter".to_string()),
+    };
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern.trim()).map_err(|err| format!("invalid_glob:{err}"))?);
+    }
+    builder.build().map(Some).map_err(|err| format!("invalid_glob:{err}"))
+}
+
+#[derive(Default)]
+struct TextSearchStats {
+    scanned_files: usize,
+    skipped_binary: usize,
+}
+
+/// NUL bytes in the first 8 KB mark a file as binary, the same sniff git uses.
+fn looks_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(8 * 1024).any(|b| *b == 0)
+}
+
 fn search_text_recursive(
-    root: &Path,
-    query: &str,
+    base: &Path,
+    dir: &Path,
+    search: &TextSearch,
     limit: usize,
+    deadline: Option<Instant>,
     matches_out: &mut Vec<Value>,
-    scanned_files: &mut usize,
+    stats: &mut TextSearchStats,
 ) {
-    if matches_out.len() >= limit {
+    if matches_out.len() >= limit || deadline.map(|d| Instant::now() >= d).unwrap_or(false) {
         return;
     }
-    let Ok(read_dir) = fs::read_dir(root) else {
+    let Ok(read_dir) = fs::read_dir(dir) else {
         return;
     };
     for entry in read_dir.flatten() {
@@ -1122,34 +2170,248 @@ fn search_text_recursive(
             return;
         }
         let path = entry.path();
+        let relative = path.strip_prefix(base).unwrap_or(&path);
+        if search.exclude.as_ref().is_some_and(|set| set.is_match(relative)) {
+            continue;
+        }
         if path.is_dir() {
-            search_text_recursive(&path, query, limit, matches_out, scanned_files);
+            search_text_recursive(base, &path, search, limit, deadline, matches_out, stats);
             continue;
         }
         if !path.is_file() {
             continue;
         }
-        *scanned_files += 1;
+        if search.include.as_ref().is_some_and(|set| !set.is_match(relative)) {
+            continue;
+        }
         let Ok(meta) = entry.metadata() else {
             continue;
         };
-        if meta.len() > 512 * 1024 {
+        if meta.len() > MAX_INDEXED_FILE_BYTES {
+            stats.scanned_files += 1;
             continue;
         }
-        let Ok(raw) = fs::read_to_string(&path) else {
-            continue;
-        };
-        for (line_no, line) in raw.lines().enumerate() {
-            if line.contains(query) {
-                matches_out.push(json!({
-                    "path": path.display().to_string(),
-                    "line": line_no + 1,
-                    "snippet": truncate_chars(line, 220)
-                }));
-                if matches_out.len() >= limit {
-                    return;
-                }
+        search_text_file(&path, search, limit, matches_out, stats);
+    }
+}
+
+fn search_text_file(path: &Path, search: &TextSearch, limit: usize, matches_out: &mut Vec<Value>, stats: &mut TextSearchStats) {
+    stats.scanned_files += 1;
+    let Ok(bytes) = fs::read(path) else {
+        return;
+    };
+    if looks_binary(&bytes) {
+        stats.skipped_binary += 1;
+        return;
+    }
+    let raw = String::from_utf8_lossy(&bytes);
+    for (line_no, line) in raw.lines().enumerate() {
+        if search.pattern.is_match(line) {
+            matches_out.push(json!({
+                "path": path.display().to_string(),
+                "line": line_no + 1,
+                "snippet": truncate_chars(line, 220)
+            }));
+            if matches_out.len() >= limit {
+                return;
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_bytes_returns_base64_ranges_with_mime() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        fs::write(dir.path().join("pixel.png"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").expect("write");
+        let backend = StubActionBackend::with_project_root("test", Some(dir.path().to_path_buf()));
+        let result = backend.execute_tool(&ToolCall {
+            tool_call_id: None,
+            name: "file.read_bytes".to_string(),
+            arguments_json: json!({"path": "pixel.png", "offset": 12, "length": 2}).to_string(),
+        });
+        let value: Value = serde_json::from_str(&result.result_json).expect("json");
+        assert_eq!(value["mime_type"], "image/png");
+        assert_eq!(value["size"], 16);
+        assert_eq!(value["base64"], BASE64_STANDARD.encode(b"IH"));
+        assert_eq!(value["truncated"], true);
+    }
+
+    #[test]
+    fn search_text_supports_regex_case_and_glob_filters() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        fs::create_dir_all(dir.path().join("vendor")).expect("mkdir");
+        fs::write(dir.path().join("main.rs"), "fn Main() {}\nlet total = 42;\n").expect("write");
+        fs::write(dir.path().join("notes.txt"), "main notes\n").expect("write");
+        fs::write(dir.path().join("blob.bin"), b"main\0\x01\x02").expect("write");
+        fs::write(dir.path().join("vendor/lib.rs"), "fn main() {}\n").expect("write");
+        let backend = StubActionBackend::with_project_root("test", Some(dir.path().to_path_buf()));
+        let search = |args: Value| -> Value {
+            let result = backend.execute_tool(&ToolCall {
+                tool_call_id: None,
+                name: "file.search_text".to_string(),
+                arguments_json: args.to_string(),
+            });
+            serde_json::from_str(&result.result_json).expect("json")
+        };
+
+        let literal = search(json!({"query": "main"}));
+        assert_eq!(literal["matches"].as_array().map(Vec::len), Some(2));
+        assert_eq!(literal["skipped_binary"], 1);
+
+        let insensitive = search(json!({"query": "MAIN", "case_insensitive": true, "include": ["*.rs"], "exclude": ["vendor"]}));
+        let matches = insensitive["matches"].as_array().expect("matches");
+        assert_eq!(matches.len(), 1);
+        assert!(matches[0]["path"].as_str().unwrap_or_default().ends_with("main.rs"));
+
+        let regex = search(json!({"query": r"total = \d+", "regex": true}));
+        assert_eq!(regex["matches"][0]["line"], 2);
+        assert_eq!(search(json!({"query": "(", "regex": true}))["status"], "error");
+    }
+
+    #[test]
+    fn mcp_tool_call_uses_invoker() {
+        let backend = StubActionBackend::new("test").with_mcp_invoker(Rc::new(|server, tool, args, _| {
+            Ok(json!({
+                "server": server,
+                "tool": tool,
+                "args": serde_json::from_str::<Value>(args).unwrap_or_else(|_| json!({}))
+            })
+            .to_string())
+        }));
+        let result = backend.execute_tool(&ToolCall {
+            tool_call_id: None,
+            name: "mcp.tool_call".to_string(),
+            arguments_json: json!({
+                "server_id": "mcp-1",
+                "tool_name": "browser.open",
+                "arguments": { "url": "https://example.com" }
+            })
+            .to_string(),
+        });
+        assert!(result.result_json.contains("\"server\":\"mcp-1\""));
+        assert!(result.result_json.contains("\"tool\":\"browser.open\""));
+    }
+
+    #[test]
+    fn mcp_server_alias_uses_invoker() {
+        let backend = StubActionBackend::new("test").with_mcp_invoker(Rc::new(|server, tool, args, deadline| {
+            Ok(json!({
+                "server": server,
+                "tool": tool,
+                "args": serde_json::from_str::<Value>(args).unwrap_or_else(|_| json!({})),
+                "has_deadline": deadline.is_some()
+            })
+            .to_string())
+        }));
+        let result = backend.execute_tool_with_timeout(
+            &ToolCall {
+                tool_call_id: None,
+                name: "mcp.server.mcp-1.browser.open".to_string(),
+                arguments_json: json!({ "url": "https://example.com" }).to_string(),
+            },
+            Duration::from_secs(5),
+        );
+        assert!(result.result_json.contains("\"has_deadline\":true"));
+        assert!(result.result_json.contains("\"server\":\"mcp-1\""));
+        assert!(result.result_json.contains("\"tool\":\"browser.open\""));
+        assert!(result.result_json.contains("\"url\":\"https://example.com\""));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn command_output_with_deadline_kills_slow_commands() {
+        let started = Instant::now();
+        let result = command_output_with_deadline(
+            Command::new("/bin/sh").args(["-c", "sleep 5"]),
+            Some(Instant::now() + Duration::from_millis(100)),
+        );
+        assert_eq!(result.expect_err("timeout").kind(), io::ErrorKind::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(3));
+
+        let ok = command_output_with_deadline(
+            Command::new("/bin/sh").args(["-c", "echo done"]),
+            Some(Instant::now() + Duration::from_secs(5)),
+        )
+        .expect("command output");
+        assert_eq!(String::from_utf8_lossy(&ok.stdout).trim(), "done");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn code_run_executes_snippets_without_network_secrets_or_project_files() {
+        if Command::new("python3").arg("--version").output().is_err() {
+            return;
+        }
+        let dir = tempfile::tempdir().expect("tempdir");
+        fs::write(dir.path().join("secret.txt"), "top secret").expect("write secret");
+        let backend = StubActionBackend::with_project_root("test", Some(dir.path().to_path_buf()));
+        let run = |code: &str, timeout_secs: u64| {
+            let result = backend.execute_tool(&ToolCall {
+                tool_call_id: None,
+                name: "code.run".to_string(),
+                arguments_json: json!({"language": "python", "code": code, "timeout_secs": timeout_secs}).to_string(),
+            });
+            serde_json::from_str::<Value>(&result.result_json).expect("json")
+        };
+
+        let value = run("import os\nprint(sum(range(10)), os.environ.get('OPENAI_API_KEY'))\nprint('x' * 100000)", 10);
+        if !crate::code_run::sandbox_available() {
+            assert!(value["error"].as_str().expect("error").starts_with("sandbox_unavailable:"), "{value}");
+            return;
+        }
+        assert_eq!(value["status"], "ok", "{value}");
+        assert_eq!(value["network_isolation"], "namespace");
+        assert!(value["stdout"].as_str().expect("stdout").starts_with("45 None\n"));
+        assert_eq!(value["stdout_truncated"], true);
+
+        // The guard is undone, so only the network namespace stands in the way.
+        let value = run(
+            "import importlib, socket\nimportlib.reload(socket)\nsocket.create_connection(('1.1.1.1', 53), timeout=1)",
+            10,
+        );
+        assert_eq!(value["status"], "error");
+        assert!(value["stderr"].as_str().expect("stderr").contains("Error"), "{value}");
+
+        let secret = dir.path().join("secret.txt").display().to_string();
+        let value = run(&format!("import os\nprint(os.listdir('../../..'))\nprint(open({secret:?}).read())"), 10);
+        assert_eq!(value["status"], "error", "{value}");
+        assert!(value["stdout"].as_str().expect("stdout").starts_with("['.cmnd-n-ctrl']"), "{value}");
+        assert!(value["stderr"].as_str().expect("stderr").contains("FileNotFoundError"), "{value}");
+
+        let started = Instant::now();
+        assert_eq!(run("while True: pass", 1)["error"], TOOL_TIMEOUT_ERROR);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(fs::read_dir(dir.path().join(crate::code_run::SCRATCH_DIR)).expect("scratch").count(), 0);
+    }
+
+    #[test]
+    fn desktop_open_url_command_selects_platform_launcher() {
+        let (cmd, args) = desktop_open_url_command("https://example.com").expect("command");
+        #[cfg(target_os = "linux")]
+        assert_eq!(cmd, "xdg-open");
+        #[cfg(target_os = "macos")]
+        assert_eq!(cmd, "open");
+        #[cfg(target_os = "windows")]
+        assert_eq!(cmd, "cmd");
+        assert!(!args.is_empty());
+    }
+
+    #[test]
+    fn desktop_activate_command_selects_platform_adapter() {
+        let (cmd, args) = desktop_activate_command("Browser").expect("command");
+        #[cfg(target_os = "linux")]
+        assert_eq!(cmd, "/bin/sh");
+        #[cfg(target_os = "macos")]
+        assert_eq!(cmd, "osascript");
+        #[cfg(target_os = "windows")]
+        assert_eq!(cmd, "powershell");
+        assert!(!args.is_empty());
+
+        let (_, args) = desktop_activate_pid_command(4242).expect("pid command");
+        assert!(args.iter().any(|arg| arg.contains("4242")));
+    }
+}
@@ -0,0 +1,184 @@
+use serde_json::Value;
+
+/// Validates tool call arguments against the subset of JSON Schema used by tool declarations:
+/// `type`, `properties`, `required`, `additionalProperties`, `enum`, `items`,
+/// `minimum`/`maximum` and `minLength`/`maxLength`. Unknown keywords are ignored so schemas
+/// reported by MCP servers never cause false rejections.
+pub fn validate_tool_arguments(input_json_schema: &str, arguments_json: &str) -> Result<(), String> {
+    let Ok(schema) = serde_json::from_str::<Value>(input_json_schema) else {
+        return Ok(());
+    };
+    let raw = if arguments_json.trim().is_empty() {
+        "{}"
+    } else {
+        arguments_json
+    };
+    let arguments = serde_json::from_str::<Value>(raw)
+        .map_err(|err| format!("arguments are not valid JSON: {err}"))?;
+    validate_value(&schema, &arguments, "$")
+}
+
+fn validate_value(schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    let Some(schema) = schema.as_object() else {
+        return Ok(());
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let matches = match expected {
+            Value::String(t) => type_matches(t, value),
+            Value::Array(types) => types
+                .iter()
+                .filter_map(Value::as_str)
+                .any(|t| type_matches(t, value)),
+            _ => true,
+        };
+        if !matches {
+            return Err(format!(
+                "{path}: expected {}, got {}",
+                type_label(expected),
+                json_type_name(value)
+            ));
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            return Err(format!("{path}: value is not one of the allowed options"));
+        }
+    }
+
+    if let Some(n) = value.as_f64() {
+        if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+            if n < min {
+                return Err(format!("{path}: must be >= {min}"));
+            }
+        }
+        if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+            if n > max {
+                return Err(format!("{path}: must be <= {max}"));
+            }
+        }
+    }
+
+    if let Some(s) = value.as_str() {
+        let len = s.chars().count() as u64;
+        if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+            if len < min {
+                return Err(format!("{path}: must be at least {min} characters"));
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+            if len > max {
+                return Err(format!("{path}: must be at most {max} characters"));
+            }
+        }
+    }
+
+    if let Some(obj) = value.as_object() {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !obj.contains_key(key) {
+                    return Err(format!("{path}: missing required property '{key}'"));
+                }
+            }
+        }
+        for (key, item) in obj {
+            let child_path = format!("{path}.{key}");
+            match properties.and_then(|p| p.get(key)) {
+                Some(prop_schema) => validate_value(prop_schema, item, &child_path)?,
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        return Err(format!("{path}: unexpected property '{key}'"));
+                    }
+                    Some(extra @ Value::Object(_)) => validate_value(extra, item, &child_path)?,
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    if let (Some(items), Some(item_schema)) = (value.as_array(), schema.get("items")) {
+        for (idx, item) in items.iter().enumerate() {
+            validate_value(item_schema, item, &format!("{path}[{idx}]"))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64()
+                || value.is_u64()
+                || value.as_f64().map(|f| f.fract() == 0.0).unwrap_or(false)
+        }
+        _ => true,
+    }
+}
+
+fn type_label(expected: &Value) -> String {
+    match expected {
+        Value::String(t) => t.clone(),
+        Value::Array(types) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join("|"),
+        _ => "any".to_string(),
+    }
+}
+
+fn json_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const READ_SCHEMA: &str = r#"{"type":"object","properties":{"path":{"type":"string"},"limit":{"type":"integer","minimum":1}},"required":["path"],"additionalProperties":false}"#;
+
+    #[test]
+    fn accepts_arguments_matching_schema() {
+        assert!(validate_tool_arguments(READ_SCHEMA, r#"{"path":"notes.txt","limit":5}"#).is_ok());
+        assert!(validate_tool_arguments(READ_SCHEMA, r#"{"path":"notes.txt"}"#).is_ok());
+    }
+
+    #[test]
+    fn rejects_missing_wrong_typed_and_unexpected_arguments() {
+        let missing = validate_tool_arguments(READ_SCHEMA, "{}").unwrap_err();
+        assert!(missing.contains("missing required property 'path'"));
+
+        let wrong_type = validate_tool_arguments(READ_SCHEMA, r#"{"path":42}"#).unwrap_err();
+        assert!(wrong_type.contains("$.path: expected string, got number"));
+
+        let below_min = validate_tool_arguments(READ_SCHEMA, r#"{"path":"a","limit":0}"#).unwrap_err();
+        assert!(below_min.contains("$.limit: must be >= 1"));
+
+        let extra = validate_tool_arguments(READ_SCHEMA, r#"{"path":"a","mode":"x"}"#).unwrap_err();
+        assert!(extra.contains("unexpected property 'mode'"));
+
+        let garbage = validate_tool_arguments(READ_SCHEMA, "not json").unwrap_err();
+        assert!(garbage.contains("not valid JSON"));
+    }
+
+    #[test]
+    fn unparseable_schema_is_not_enforced() {
+        assert!(validate_tool_arguments("not a schema", r#"{"anything":true}"#).is_ok());
+    }
+}
@@ -0,0 +1,37 @@
+//! What this backend is, for `system.version`, `system.health` and `cli version`.
+
+use ipc::BuildInfo;
+
+use actions::calendar::civil_from_days;
+
+/// Cargo features compiled into this build.
+const FEATURES: &[(&str, bool)] = &[("process-list", cfg!(feature = "process-list"))];
+
+pub fn build_info() -> BuildInfo {
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        git_commit: option_env!("CMND_GIT_COMMIT").map(str::to_string),
+        build_date: option_env!("CMND_BUILD_UNIX_SECONDS")
+            .and_then(|secs| secs.parse::<u64>().ok())
+            .map(utc_date),
+        features: FEATURES.iter().filter(|(_, on)| *on).map(|(name, _)| name.to_string()).collect(),
+    }
+}
+
+fn utc_date(unix_seconds: u64) -> String {
+    let (year, month, day) = civil_from_days((unix_seconds / 86_400) as i64);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_the_crate_version_and_a_build_date() {
+        let info = build_info();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(info.build_date.as_deref().map(str::len), Some(10));
+        assert_eq!(utc_date(1_709_164_800), "2024-02-29");
+    }
+}
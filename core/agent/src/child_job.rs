@@ -0,0 +1,83 @@
+//! Ties spawned MCP servers to the agent's lifetime. On Windows each child is assigned to a
+//! job object created with `JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE`; the job handle is never
+//! closed explicitly, so the children are killed when the agent exits for any reason,
+//! including a crash or `taskkill /F`. Elsewhere `shutdown` stops children as before.
+
+use std::process::{Child, Command};
+
+/// Platform flags for a child spawned by the agent: no console window on Windows, which
+/// matters when the agent itself runs as a service or detached daemon.
+pub fn configure(command: &mut Command) {
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    #[cfg(not(windows))]
+    let _ = command;
+}
+
+/// Assigns `child` to the agent's job object on Windows; a no-op elsewhere.
+pub fn attach(child: &Child) -> Result<(), String> {
+    #[cfg(windows)]
+    {
+        windows::attach(child)
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = child;
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::ffi::c_void;
+    use std::os::windows::io::AsRawHandle;
+    use std::process::Child;
+    use std::sync::OnceLock;
+
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation, SetInformationJobObject,
+        JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+
+    /// The job handle as an integer so it can live in a static; created on first use.
+    static JOB: OnceLock<Result<usize, String>> = OnceLock::new();
+
+    fn job() -> Result<HANDLE, String> {
+        JOB.get_or_init(|| unsafe {
+            let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+            if job.is_null() {
+                return Err(format!("CreateJobObjectW failed: {}", std::io::Error::last_os_error()));
+            }
+            let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+            info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+            let ok = SetInformationJobObject(
+                job,
+                JobObjectExtendedLimitInformation,
+                &info as *const JOBOBJECT_EXTENDED_LIMIT_INFORMATION as *const c_void,
+                std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            );
+            if ok == 0 {
+                let err = std::io::Error::last_os_error();
+                CloseHandle(job);
+                return Err(format!("SetInformationJobObject failed: {err}"));
+            }
+            Ok(job as usize)
+        })
+        .clone()
+        .map(|job| job as HANDLE)
+    }
+
+    pub fn attach(child: &Child) -> Result<(), String> {
+        let job = job()?;
+        let ok = unsafe { AssignProcessToJobObject(job, child.as_raw_handle() as HANDLE) };
+        if ok == 0 {
+            return Err(format!("AssignProcessToJobObject failed: {}", std::io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+}
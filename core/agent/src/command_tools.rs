@@ -0,0 +1,98 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use actions::command_tool::CommandToolSpec;
+use ipc::Tool;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::tool_registry::ToolRegistry;
+
+/// User-declared command tools, in the storage dir: `{"tools": [CommandToolSpec, ...]}`.
+pub const COMMAND_TOOLS_FILE: &str = "command_tools.json";
+
+#[derive(Debug, Default, Deserialize)]
+struct CommandToolsFile {
+    #[serde(default)]
+    tools: Vec<Value>,
+}
+
+/// Reads `command_tools.json`. Like other optional config, a missing file means no tools, and
+/// entries that don't parse, fail validation or reuse a built-in or reserved name are skipped
+/// with a warning.
+pub fn load(storage_dir: &Path, builtins: &ToolRegistry) -> Vec<CommandToolSpec> {
+    let raw = match fs::read_to_string(storage_dir.join(COMMAND_TOOLS_FILE)) {
+        Ok(raw) => raw,
+        Err(_) => return Vec::new(),
+    };
+    let file = match serde_json::from_str::<CommandToolsFile>(&raw) {
+        Ok(file) => file,
+        Err(err) => {
+            tracing::warn!(%err, "ignoring unreadable {COMMAND_TOOLS_FILE}");
+            return Vec::new();
+        }
+    };
+    let mut seen = BTreeSet::new();
+    file.tools
+        .into_iter()
+        .filter_map(|entry| {
+            let spec = serde_json::from_value::<CommandToolSpec>(entry)
+                .map_err(|err| err.to_string())
+                .and_then(|spec| spec.validate().map(|_| spec))
+                .and_then(|spec| {
+                    let name = spec.name.as_str();
+                    if builtins.has_tool(name) || name.starts_with("mcp.") || name.starts_with("internal.") {
+                        Err(format!("'{name}' is a built-in or reserved tool name"))
+                    } else if !seen.insert(spec.name.clone()) {
+                        Err(format!("'{name}' is declared twice"))
+                    } else {
+                        Ok(spec)
+                    }
+                });
+            match spec {
+                Ok(spec) => Some(spec),
+                Err(err) => {
+                    tracing::warn!(%err, "ignoring command tool");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+pub fn to_tool(spec: &CommandToolSpec) -> Tool {
+    Tool {
+        name: spec.name.clone(),
+        description: spec.description.clone(),
+        input_json_schema: spec.input_json_schema(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn skips_invalid_duplicate_and_reserved_entries() {
+        let dir = tempdir().expect("tempdir");
+        assert!(load(dir.path(), &ToolRegistry::new_default()).is_empty());
+        fs::write(
+            dir.path().join(COMMAND_TOOLS_FILE),
+            r#"{"tools":[
+                {"name":"git.status","description":"Show git status","command":["git","status","--short"]},
+                {"name":"git.status","command":["git","status"]},
+                {"name":"echo","command":["echo","{input}"]},
+                {"name":"mcp.fake","command":["true"]},
+                {"name":"run.any","command":["{program}"]},
+                {"name":"typo","command":["true"],"timeout":5}
+            ]}"#,
+        )
+        .expect("write");
+        let specs = load(dir.path(), &ToolRegistry::new_default());
+        assert_eq!(specs.len(), 1);
+        let tool = to_tool(&specs[0]);
+        assert_eq!((tool.name.as_str(), tool.description.as_str()), ("git.status", "Show git status"));
+    }
+}
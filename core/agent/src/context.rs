@@ -0,0 +1,313 @@
+//! Opt-in retrieval-augmented context. `project.open` chunks and embeds the project's text files;
+//! before a chat run the chunks closest to the latest user message are injected as a `system`
+//! message and their paths recorded in the audit entry.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use actions::project_index::ProjectIndex;
+use serde::{Deserialize, Serialize};
+
+const CONTEXT_INDEX_VERSION: u32 = 1;
+pub const DEFAULT_TOP_K: usize = 4;
+const CHUNK_LINES: usize = 40;
+const MAX_CHUNKS: usize = 2_000;
+const EMBED_BATCH: usize = 64;
+/// Upper bound on the injected text, so a few huge chunks cannot crowd out the conversation.
+const MAX_CONTEXT_CHARS: usize = 12_000;
+
+/// Embedding vectors for one project, keyed like `ProjectIndex` by root-relative path. Vectors
+/// from different providers or models are not comparable, so a change of either rebuilds it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ContextIndex {
+    version: u32,
+    provider: String,
+    #[serde(default)]
+    model: Option<String>,
+    files: BTreeMap<String, ContextFile>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct ContextFile {
+    content_hash: u64,
+    chunks: Vec<Chunk>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct Chunk {
+    start_line: usize,
+    end_line: usize,
+    text: String,
+    embedding: Vec<f32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetrievedChunk {
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub text: String,
+    pub score: f32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ContextRefresh {
+    pub files: usize,
+    pub chunks: usize,
+    pub embedded: usize,
+}
+
+impl ContextIndex {
+    /// Sits next to the trigram index: `<index_dir>/<fnv1a(root)>.context.json`.
+    pub fn index_path(index_dir: &Path, root: &Path) -> PathBuf {
+        ProjectIndex::index_path(index_dir, root).with_extension("context.json")
+    }
+
+    /// `None` when the project was never embedded or the file is from another format.
+    pub fn load(index_dir: &Path, root: &Path) -> Option<Self> {
+        fs::read_to_string(Self::index_path(index_dir, root))
+            .ok()
+            .and_then(|raw| serde_json::from_str::<Self>(&raw).ok())
+            .filter(|index| index.version == CONTEXT_INDEX_VERSION)
+    }
+
+    pub fn save(&self, index_dir: &Path, root: &Path) -> io::Result<()> {
+        fs::create_dir_all(index_dir)?;
+        let raw = serde_json::to_string(self).map_err(io::Error::other)?;
+        let path = Self::index_path(index_dir, root);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, raw)?;
+        fs::rename(tmp, path)
+    }
+
+    pub fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    pub fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    /// Re-chunks `files` (root-relative keys from `ProjectIndex::text_files`) and embeds only the
+    /// files whose content changed since the last build, then saves the index.
+    pub fn update(
+        index_dir: &Path,
+        root: &Path,
+        files: &[String],
+        provider: &str,
+        model: Option<&str>,
+        mut embed: impl FnMut(&[String]) -> Result<Vec<Vec<f32>>, String>,
+    ) -> Result<(Self, ContextRefresh), String> {
+        let mut previous = Self::load(index_dir, root)
+            .filter(|index| index.provider == provider && index.model.as_deref() == model)
+            .map(|index| index.files)
+            .unwrap_or_default();
+        let mut index = Self {
+            version: CONTEXT_INDEX_VERSION,
+            provider: provider.to_string(),
+            model: model.map(str::to_string),
+            files: BTreeMap::new(),
+        };
+        let mut pending: Vec<(String, Chunk)> = Vec::new();
+        let mut changed = !Self::index_path(index_dir, root).exists();
+        let mut total_chunks = 0;
+        for key in files {
+            let Ok(text) = fs::read_to_string(root.join(key)) else {
+                continue;
+            };
+            let content_hash = fnv1a(text.as_bytes());
+            let chunks = match previous.remove(key) {
+                Some(file) if file.content_hash == content_hash => file.chunks,
+                _ => {
+                    changed = true;
+                    let fresh = chunk_lines(&text);
+                    pending.extend(fresh.iter().cloned().map(|chunk| (key.clone(), chunk)));
+                    fresh
+                }
+            };
+            if chunks.is_empty() {
+                continue;
+            }
+            total_chunks += chunks.len();
+            if total_chunks > MAX_CHUNKS {
+                tracing::warn!(max = MAX_CHUNKS, "context index truncated; remaining files are not embedded");
+                pending.retain(|(path, _)| path != key);
+                break;
+            }
+            index.files.insert(key.clone(), ContextFile { content_hash, chunks });
+        }
+
+        changed |= !previous.is_empty();
+        let refresh = ContextRefresh {
+            files: index.files.len(),
+            chunks: index.files.values().map(|f| f.chunks.len()).sum(),
+            embedded: pending.len(),
+        };
+        for batch in pending.chunks(EMBED_BATCH) {
+            let inputs = batch
+                .iter()
+                .map(|(path, chunk)| format!("{path}\n{}", chunk.text))
+                .collect::<Vec<_>>();
+            let vectors = embed(&inputs)?;
+            if vectors.len() != inputs.len() {
+                return Err(format!("expected {} embeddings, got {}", inputs.len(), vectors.len()));
+            }
+            for ((path, chunk), vector) in batch.iter().zip(vectors) {
+                if let Some(stored) = index
+                    .files
+                    .get_mut(path)
+                    .and_then(|file| file.chunks.iter_mut().find(|c| c.start_line == chunk.start_line))
+                {
+                    stored.embedding = vector;
+                }
+            }
+        }
+        if changed {
+            index.save(index_dir, root).map_err(|err| format!("failed to save context index: {err}"))?;
+        }
+        Ok((index, refresh))
+    }
+
+    /// Top `top_k` chunks by cosine similarity to `query`; non-positive scores are dropped.
+    pub fn retrieve(&self, query: &[f32], top_k: usize) -> Vec<RetrievedChunk> {
+        let mut scored = self
+            .files
+            .iter()
+            .flat_map(|(path, file)| file.chunks.iter().map(move |chunk| (path, chunk)))
+            .map(|(path, chunk)| (cosine(query, &chunk.embedding), path, chunk))
+            .filter(|(score, _, _)| *score > 0.0)
+            .collect::<Vec<_>>();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        scored
+            .into_iter()
+            .take(top_k)
+            .map(|(score, path, chunk)| RetrievedChunk {
+                path: path.clone(),
+                start_line: chunk.start_line,
+                end_line: chunk.end_line,
+                text: chunk.text.clone(),
+                score,
+            })
+            .collect()
+    }
+}
+
+/// The `system` message injected before the conversation; `None` when nothing was retrieved.
+pub fn render(chunks: &[RetrievedChunk]) -> Option<String> {
+    if chunks.is_empty() {
+        return None;
+    }
+    let mut out = String::from(
+        "Project context retrieved for this request (excerpts; may be incomplete or out of date):\n",
+    );
+    for chunk in chunks {
+        let section = format!(
+            "\n--- {}:{}-{} ---\n{}\n",
+            chunk.path,
+            chunk.start_line,
+            chunk.end_line,
+            chunk.text.trim_end()
+        );
+        if out.len() + section.len() > MAX_CONTEXT_CHARS {
+            break;
+        }
+        out.push_str(&section);
+    }
+    Some(out)
+}
+
+/// Fixed windows of `CHUNK_LINES` lines (1-based, inclusive); blank windows are skipped.
+fn chunk_lines(text: &str) -> Vec<Chunk> {
+    let lines = text.lines().collect::<Vec<_>>();
+    lines
+        .chunks(CHUNK_LINES)
+        .enumerate()
+        .filter(|(_, window)| window.iter().any(|line| !line.trim().is_empty()))
+        .map(|(i, window)| Chunk {
+            start_line: i * CHUNK_LINES + 1,
+            end_line: i * CHUNK_LINES + window.len(),
+            text: window.join("\n"),
+            embedding: Vec::new(),
+        })
+        .collect()
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot = a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+    let norm = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        0.0
+    } else {
+        dot / norm
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325u64, |h, b| (h ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// One dimension per keyword, so similarity is easy to reason about.
+    fn keyword_embed(texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        Ok(texts
+            .iter()
+            .map(|t| {
+                ["database", "render", "config"]
+                    .iter()
+                    .map(|k| t.matches(k).count() as f32)
+                    .collect()
+            })
+            .collect())
+    }
+
+    #[test]
+    fn update_embeds_changed_files_and_retrieves_nearest_chunks() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path().join("proj");
+        let index_dir = dir.path().join("indexes");
+        fs::create_dir_all(&root).expect("mkdir");
+        fs::write(root.join("db.rs"), "fn open_database() {}\n// database pool\n").expect("write");
+        fs::write(root.join("ui.rs"), "fn render() {}\n").expect("write");
+        let files = vec!["db.rs".to_string(), "ui.rs".to_string()];
+
+        let (index, refresh) =
+            ContextIndex::update(&index_dir, &root, &files, "stub", None, keyword_embed).expect("build");
+        assert_eq!((refresh.files, refresh.chunks, refresh.embedded), (2, 2, 2));
+
+        let hits = index.retrieve(&[1.0, 0.0, 0.0], 4);
+        assert_eq!(hits.len(), 1);
+        assert_eq!((hits[0].path.as_str(), hits[0].start_line, hits[0].end_line), ("db.rs", 1, 2));
+        assert!(render(&hits).expect("context").contains("--- db.rs:1-2 ---"));
+        assert_eq!(render(&[]), None);
+
+        fs::write(root.join("ui.rs"), "fn render_config() {}\n").expect("rewrite");
+        let (_, refresh) = ContextIndex::update(&index_dir, &root, &files, "stub", None, keyword_embed).expect("update");
+        assert_eq!(refresh.embedded, 1);
+        let (_, refresh) =
+            ContextIndex::update(&index_dir, &root, &files, "stub", Some("other"), keyword_embed).expect("new model");
+        assert_eq!(refresh.embedded, 2);
+        let loaded = ContextIndex::load(&index_dir, &root).expect("saved");
+        assert_eq!(loaded.model(), Some("other"));
+        assert_eq!(loaded.retrieve(&[0.0, 0.0, 1.0], 4)[0].path, "ui.rs");
+    }
+
+    #[test]
+    fn chunks_follow_line_windows_and_skip_blank_ones() {
+        let text = (1..=CHUNK_LINES + 3).map(|n| format!("line {n}")).collect::<Vec<_>>().join("\n");
+        let chunks = chunk_lines(&text);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!((chunks[1].start_line, chunks[1].end_line), (CHUNK_LINES + 1, CHUNK_LINES + 3));
+        assert!(chunk_lines("\n\n  \n").is_empty());
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::VecDeque;
+
+use ipc::{AgentEvent, EventsSubscribeRequest, EventsSubscribeResponse};
+use serde_json::Value;
+
+pub const CONSENT_CREATED: &str = "consent.created";
+pub const CONSENT_RESOLVED: &str = "consent.resolved";
+pub const MCP_SERVER_EXITED: &str = "mcp.server.exited";
+pub const MCP_SERVER_RESTARTED: &str = "mcp.server.restarted";
+pub const AUDIT_APPENDED: &str = "audit.appended";
+pub const SESSION_UPDATED: &str = "session.updated";
+pub const PROJECT_CHANGED: &str = "project.changed";
+pub const TASK_RAN: &str = "task.ran";
+
+const EVENT_LOG_CAPACITY: usize = 1024;
+const DEFAULT_EVENT_LIMIT: usize = 256;
+
+/// Bounded in-memory event buffer with monotonically increasing sequence numbers. Events are not
+/// persisted: a restarted service starts a new sequence and clients resync from list calls.
+#[derive(Debug, Default)]
+pub struct EventLog {
+    next_seq: u64,
+    events: VecDeque<AgentEvent>,
+}
+
+impl EventLog {
+    pub fn push(&mut self, event_type: &str, timestamp_unix_seconds: u64, payload: Value) {
+        self.next_seq += 1;
+        self.events.push_back(AgentEvent {
+            seq: self.next_seq,
+            event_type: event_type.to_string(),
+            timestamp_unix_seconds,
+            payload_json: payload.to_string(),
+        });
+        while self.events.len() > EVENT_LOG_CAPACITY {
+            self.events.pop_front();
+        }
+    }
+
+    pub fn since(&self, params: &EventsSubscribeRequest) -> EventsSubscribeResponse {
+        let limit = params.limit.unwrap_or(DEFAULT_EVENT_LIMIT).max(1);
+        let oldest = self.events.front().map(|e| e.seq).unwrap_or(self.next_seq + 1);
+        // A cursor ahead of the sequence comes from a previous service instance; start over.
+        let since = if params.since > self.next_seq { 0 } else { params.since };
+        let events = self
+            .events
+            .iter()
+            .filter(|e| e.seq > since)
+            .filter(|e| params.event_types.is_empty() || params.event_types.iter().any(|t| t == &e.event_type))
+            .take(limit)
+            .cloned()
+            .collect::<Vec<_>>();
+        let scanned_to = match events.last() {
+            Some(last) if events.len() == limit => last.seq,
+            _ => self.next_seq,
+        };
+        EventsSubscribeResponse {
+            events,
+            cursor: scanned_to,
+            truncated: params.since > 0 && since + 1 < oldest,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn since_filters_by_cursor_type_and_limit() {
+        let mut log = EventLog::default();
+        log.push(CONSENT_CREATED, 1, json!({"consent_id": "c1"}));
+        log.push(AUDIT_APPENDED, 2, json!({"audit_id": "a1"}));
+        log.push(CONSENT_RESOLVED, 3, json!({"consent_id": "c1"}));
+
+        let all = log.since(&EventsSubscribeRequest::default());
+        assert_eq!(all.events.len(), 3);
+        assert_eq!(all.cursor, 3);
+
+        let consents = log.since(&EventsSubscribeRequest {
+            since: 1,
+            event_types: vec![CONSENT_RESOLVED.to_string()],
+            ..Default::default()
+        });
+        assert_eq!(consents.events.len(), 1);
+        assert_eq!(consents.events[0].seq, 3);
+        assert_eq!(consents.cursor, 3);
+
+        let paged = log.since(&EventsSubscribeRequest {
+            limit: Some(2),
+            ..Default::default()
+        });
+        assert_eq!(paged.events.len(), 2);
+        assert_eq!(paged.cursor, 2);
+
+        let caught_up = log.since(&EventsSubscribeRequest {
+            since: 3,
+            ..Default::default()
+        });
+        assert!(caught_up.events.is_empty());
+        assert_eq!(caught_up.cursor, 3);
+    }
+
+    #[test]
+    fn overflow_reports_truncation() {
+        let mut log = EventLog::default();
+        for i in 0..(EVENT_LOG_CAPACITY as u64 + 10) {
+            log.push(AUDIT_APPENDED, i, json!({}));
+        }
+        let resp = log.since(&EventsSubscribeRequest {
+            since: 5,
+            limit: Some(1),
+            ..Default::default()
+        });
+        assert!(resp.truncated);
+        assert_eq!(resp.events[0].seq, 11);
+    }
+}
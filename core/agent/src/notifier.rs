@@ -0,0 +1,406 @@
+use ipc::PendingConsentRecord;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+use storage::NotificationSettings;
+
+/// The `notify.send` target that raises a desktop notification.
+pub const DESKTOP_TARGET: &str = "desktop";
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Fans a newly created pending consent out to the desktop and/or a webhook. Delivery runs on a
+/// detached thread and failures are dropped: notifications are a courtesy, never a gate.
+#[derive(Clone, Debug, Default)]
+pub struct ConsentNotifier {
+    desktop: bool,
+    webhook_url: Option<String>,
+}
+
+impl ConsentNotifier {
+    pub fn from_settings(settings: &NotificationSettings) -> Self {
+        Self {
+            desktop: settings.desktop.unwrap_or(false),
+            webhook_url: settings
+                .webhook_url
+                .as_deref()
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+                .map(str::to_string),
+        }
+    }
+
+    pub fn notify_consent_created(&self, record: &PendingConsentRecord) {
+        if !self.desktop && self.webhook_url.is_none() {
+            return;
+        }
+        let notifier = self.clone();
+        let record = record.clone();
+        thread::spawn(move || {
+            // Unit tests create consents constantly; keep them from spamming the developer's desktop.
+            if notifier.desktop && !cfg!(test) {
+                let _ = send_desktop_notification(&consent_title(&record), &consent_body(&record));
+            }
+            if let Some(url) = notifier.webhook_url.as_deref() {
+                let _ = post_consent_webhook(url, &record);
+            }
+        });
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WebhookKind {
+    Slack,
+    Discord,
+    Generic,
+}
+
+impl WebhookKind {
+    /// An explicit `kind` wins; otherwise Slack and Discord webhook hosts are recognised.
+    pub fn resolve(kind: Option<&str>, url: &str) -> Result<Self, String> {
+        match kind.map(|k| k.trim().to_ascii_lowercase()).as_deref() {
+            Some("slack") => return Ok(Self::Slack),
+            Some("discord") => return Ok(Self::Discord),
+            Some("generic") => return Ok(Self::Generic),
+            Some(other) => return Err(format!("unknown webhook kind '{other}'")),
+            None => {}
+        }
+        let host = url.split_once("://").map_or(url, |(_, rest)| rest).split('/').next().unwrap_or_default();
+        Ok(if host == "hooks.slack.com" {
+            Self::Slack
+        } else if host == "discord.com" || host == "discordapp.com" {
+            Self::Discord
+        } else {
+            Self::Generic
+        })
+    }
+
+    pub fn payload(self, title: &str, message: &str) -> Value {
+        match self {
+            Self::Slack => json!({ "text": format!("*{title}*\n{message}") }),
+            Self::Discord => json!({ "content": format!("**{title}**\n{message}") }),
+            Self::Generic => json!({ "event": "notify.send", "title": title, "message": message }),
+        }
+    }
+}
+
+/// Delivers `notify.send` messages. Unlike consent alerts this runs in the tool call, so the
+/// model learns which targets got the message.
+#[derive(Clone, Debug, Default)]
+pub struct MessageNotifier {
+    desktop: bool,
+    targets: BTreeMap<String, (WebhookKind, String)>,
+}
+
+impl MessageNotifier {
+    /// Targets with an empty URL or unknown `kind` are skipped with a warning.
+    pub fn from_settings(settings: &NotificationSettings) -> Self {
+        let targets = settings
+            .targets
+            .iter()
+            .filter_map(|(name, target)| {
+                let url = target.url.trim();
+                let kind = if url.is_empty() {
+                    Err("url is empty".to_string())
+                } else {
+                    WebhookKind::resolve(target.kind.as_deref(), url)
+                };
+                match kind {
+                    Ok(kind) => Some((name.clone(), (kind, url.to_string()))),
+                    Err(err) => {
+                        tracing::warn!(%name, %err, "ignoring notification target");
+                        None
+                    }
+                }
+            })
+            .collect();
+        Self {
+            desktop: settings.desktop.unwrap_or(false),
+            targets,
+        }
+    }
+
+    /// Sends to `targets` (names from `notifications.targets`, or `desktop`), or to every
+    /// enabled target when none are given. Returns one `{target, status, error?}` per target.
+    pub fn send(&self, title: &str, message: &str, targets: &[String]) -> Result<Vec<Value>, String> {
+        let targets = if targets.is_empty() {
+            let desktop = self.desktop.then(|| DESKTOP_TARGET.to_string());
+            desktop.into_iter().chain(self.targets.keys().cloned()).collect::<Vec<_>>()
+        } else {
+            targets.to_vec()
+        };
+        if targets.is_empty() {
+            return Err("no_notification_targets: configure notifications.targets or enable desktop".to_string());
+        }
+        Ok(targets
+            .iter()
+            .map(|name| {
+                let delivered = if name == DESKTOP_TARGET {
+                    if self.desktop {
+                        send_desktop_notification(title, message)
+                    } else {
+                        Err("desktop notifications are disabled".to_string())
+                    }
+                } else {
+                    match self.targets.get(name) {
+                        Some((kind, url)) => post_json(url, &kind.payload(title, message)),
+                        None => Err(format!("unknown target '{name}'")),
+                    }
+                };
+                match delivered {
+                    Ok(()) => json!({ "target": name, "status": "sent" }),
+                    Err(err) => json!({ "target": name, "status": "failed", "error": err }),
+                }
+            })
+            .collect())
+    }
+}
+
+fn consent_title(record: &PendingConsentRecord) -> String {
+    let count = record.pending_actions.len().max(1);
+    if count == 1 {
+        "cmnd-n-ctrl: approval needed".to_string()
+    } else {
+        format!("cmnd-n-ctrl: {count} actions need approval")
+    }
+}
+
+fn consent_body(record: &PendingConsentRecord) -> String {
+    format!(
+        "{} ({}) - consent {}",
+        record.tool_name, record.capability_tier, record.consent_id
+    )
+}
+
+pub fn consent_webhook_payload(record: &PendingConsentRecord) -> serde_json::Value {
+    json!({
+        "event": "consent.created",
+        "consent_id": record.consent_id,
+        "session_id": record.session_id,
+        "tool_name": record.tool_name,
+        "capability_tier": record.capability_tier,
+        "rationale": record.rationale,
+        "requested_at_unix_seconds": record.requested_at_unix_seconds,
+        "expires_at_unix_seconds": record.expires_at_unix_seconds,
+        "pending_action_count": record.pending_actions.len(),
+    })
+}
+
+pub fn post_consent_webhook(url: &str, record: &PendingConsentRecord) -> Result<(), String> {
+    post_json(url, &consent_webhook_payload(record))
+}
+
+fn post_json(url: &str, payload: &Value) -> Result<(), String> {
+    ureq::post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .set("Content-Type", "application/json")
+        .send_string(&payload.to_string())
+        .map(|_| ())
+        .map_err(|err| match err {
+            // ureq's Display includes the URL, which for Slack/Discord hooks is the credential.
+            ureq::Error::Status(code, _) => format!("webhook delivery failed: HTTP {code}"),
+            other => format!("webhook delivery failed: {}", other.kind()),
+        })
+}
+
+fn send_desktop_notification(title: &str, body: &str) -> Result<(), String> {
+    let Some((program, args)) = desktop_notification_command(title, body) else {
+        return Err("desktop notifications unsupported on this platform".to_string());
+    };
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|_| ())
+        .map_err(|err| format!("desktop notification failed: {err}"))
+}
+
+fn desktop_notification_command(title: &str, body: &str) -> Option<(String, Vec<String>)> {
+    #[cfg(target_os = "macos")]
+    {
+        let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+        return Some((
+            "osascript".to_string(),
+            vec![
+                "-e".to_string(),
+                format!(
+                    "display notification \"{}\" with title \"{}\"",
+                    escape(body),
+                    escape(title)
+                ),
+            ],
+        ));
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let escape = |s: &str| s.replace('\'', "''");
+        return Some((
+            "powershell".to_string(),
+            vec![
+                "-NoProfile".to_string(),
+                "-Command".to_string(),
+                format!(
+                    "[reflection.assembly]::loadwithpartialname('System.Windows.Forms') | Out-Null; $n = New-Object System.Windows.Forms.NotifyIcon; $n.Icon = [System.Drawing.SystemIcons]::Information; $n.Visible = $true; $n.ShowBalloonTip(10000, '{}', '{}', 'Info'); Start-Sleep -Seconds 10; $n.Dispose()",
+                    escape(title),
+                    escape(body)
+                ),
+            ],
+        ));
+    }
+    #[cfg(target_os = "linux")]
+    {
+        return Some(("notify-send".to_string(), vec![title.to_string(), body.to_string()]));
+    }
+    #[allow(unreachable_code)]
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    fn record() -> PendingConsentRecord {
+        PendingConsentRecord {
+            consent_id: "consent-7".to_string(),
+            session_id: Some("session-1".to_string()),
+            requested_at_unix_seconds: 10,
+            expires_at_unix_seconds: 310,
+            tool_name: "desktop.app.activate".to_string(),
+            capability_tier: "SystemActions".to_string(),
+            status: "pending".to_string(),
+            rationale: "requires explicit consent".to_string(),
+            arguments_preview: None,
+            request_fingerprint: "req-1".to_string(),
+            pending_actions: vec![],
+            risk_factors: vec![],
+        }
+    }
+
+    #[test]
+    fn settings_default_to_no_notifications() {
+        let notifier = ConsentNotifier::from_settings(&NotificationSettings::default());
+        assert!(!notifier.desktop);
+        assert!(notifier.webhook_url.is_none());
+        assert!(!MessageNotifier::from_settings(&NotificationSettings::default()).desktop);
+
+        let notifier = ConsentNotifier::from_settings(&NotificationSettings {
+            desktop: Some(true),
+            webhook_url: Some("  ".to_string()),
+            ..NotificationSettings::default()
+        });
+        assert!(notifier.desktop);
+        assert!(notifier.webhook_url.is_none());
+    }
+
+    #[test]
+    fn webhook_kinds_are_inferred_and_shape_the_payload() {
+        assert_eq!(WebhookKind::resolve(None, "https://hooks.slack.com/services/T/B/x"), Ok(WebhookKind::Slack));
+        assert_eq!(WebhookKind::resolve(None, "https://discord.com/api/webhooks/1/x"), Ok(WebhookKind::Discord));
+        assert_eq!(WebhookKind::resolve(Some("Slack"), "http://127.0.0.1:9/relay"), Ok(WebhookKind::Slack));
+        assert_eq!(WebhookKind::resolve(None, "https://example.com/hook"), Ok(WebhookKind::Generic));
+        assert!(WebhookKind::resolve(Some("email"), "https://example.com").is_err());
+        assert_eq!(WebhookKind::Slack.payload("Done", "build ok"), json!({ "text": "*Done*\nbuild ok" }));
+        assert_eq!(WebhookKind::Discord.payload("Done", "build ok")["content"], "**Done**\nbuild ok");
+    }
+
+    #[test]
+    fn message_notifier_reports_each_target() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("accept");
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 1024];
+            loop {
+                let n = stream.read(&mut chunk).expect("read");
+                buf.extend_from_slice(&chunk[..n]);
+                if n == 0 || String::from_utf8_lossy(&buf).ends_with('}') {
+                    break;
+                }
+            }
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+            String::from_utf8_lossy(&buf).to_string()
+        });
+        let settings = NotificationSettings {
+            desktop: Some(false),
+            webhook_url: None,
+            targets: BTreeMap::from([
+                (
+                    "team".to_string(),
+                    storage::NotifyTarget {
+                        url: format!("http://{addr}/slack"),
+                        kind: Some("slack".to_string()),
+                    },
+                ),
+                ("broken".to_string(), storage::NotifyTarget::default()),
+            ]),
+        };
+        let notifier = MessageNotifier::from_settings(&settings);
+        let deliveries = notifier
+            .send("Done", "report ready", &["team".to_string(), "broken".to_string(), DESKTOP_TARGET.to_string()])
+            .expect("send");
+        assert_eq!(deliveries[0], json!({ "target": "team", "status": "sent" }));
+        assert_eq!(deliveries[1]["error"], "unknown target 'broken'");
+        assert_eq!(deliveries[2]["error"], "desktop notifications are disabled");
+        assert!(server.join().expect("server").contains(r#"{"text":"*Done*\nreport ready"}"#));
+
+        assert!(MessageNotifier::from_settings(&NotificationSettings {
+            desktop: Some(false),
+            ..NotificationSettings::default()
+        })
+        .send("t", "m", &[])
+        .is_err());
+    }
+
+    #[test]
+    fn webhook_posts_consent_payload() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("accept");
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 1024];
+            loop {
+                let n = stream.read(&mut chunk).expect("read");
+                buf.extend_from_slice(&chunk[..n]);
+                let text = String::from_utf8_lossy(&buf);
+                if n == 0 || text.contains("\"consent.created\"") && text.ends_with('}') {
+                    break;
+                }
+            }
+            let _ = stream.write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n");
+            String::from_utf8_lossy(&buf).to_string()
+        });
+
+        post_consent_webhook(&format!("http://{addr}/hook"), &record()).expect("webhook");
+        let request = server.join().expect("server");
+        assert!(request.starts_with("POST /hook"));
+        assert!(request.contains("\"consent_id\":\"consent-7\""));
+        assert!(request.contains("\"tool_name\":\"desktop.app.activate\""));
+    }
+
+    #[test]
+    fn failed_deliveries_do_not_echo_the_webhook_url() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("accept");
+            let _ = stream.read(&mut [0u8; 4096]);
+            let _ = stream.write_all(b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\n\r\n");
+        });
+        let rejected = post_json(&format!("http://{addr}/services/T0/B0/secret-token"), &json!({})).expect_err("403");
+        server.join().expect("server");
+        assert_eq!(rejected, "webhook delivery failed: HTTP 403");
+
+        let closed = TcpListener::bind("127.0.0.1:0").expect("bind").local_addr().expect("addr");
+        let unreachable = post_json(&format!("http://{closed}/services/T0/B0/secret-token"), &json!({})).expect_err("refused");
+        assert!(unreachable.starts_with("webhook delivery failed: "), "{unreachable}");
+        assert!(!unreachable.contains("secret-token") && !unreachable.contains(&closed.to_string()), "{unreachable}");
+    }
+}
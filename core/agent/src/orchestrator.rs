@@ -1,19 +1,53 @@
 use actions::traits::ActionBackend;
-use ipc::{ActionEvent, ChatMessage, ChatMode, ChatResponse, ProviderConfig, ToolResult};
+use ipc::{ActionEvent, ChatMessage, ChatMode, ChatResponse, EvidenceAttachment, ProviderConfig, Tool, ToolCall, ToolResult};
 use providers::provider_trait::{Provider, ProviderReply};
 use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet};
 use std::hash::{Hash, Hasher};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use crate::arg_validation::validate_tool_arguments;
 use crate::policy::{Authorization, CapabilityTier, Policy, PolicyContext};
-use crate::tool_registry::ToolRegistry;
+use crate::project_config::ToolRules;
+use crate::redaction::Redactor;
+use crate::secret_scan::{SecretScanAction, PROVIDER_SEND_ACTION};
+use crate::tool_registry::{ToolRegistry, SUBTASK_TOOL};
+
+/// Provider rounds a `task.spawn` sub-task gets unless it asks for fewer.
+const DEFAULT_SUBTASK_ROUNDS: usize = 3;
+/// Hard cap on a sub-task's rounds, whatever it asks for.
+const MAX_SUBTASK_ROUNDS: usize = 4;
 
 #[derive(Clone, Debug)]
 pub struct PolicyDecisionRecord {
     pub tool_name: String,
+    /// Redacted like the argument previews, but not truncated.
+    pub arguments_json: String,
     pub capability_tier: CapabilityTier,
     pub decision: String,
     pub reason: Option<String>,
+    /// The decision followed a user approval or denial rather than the policy alone.
+    pub consented: bool,
+}
+
+/// Final text of a run stopped because its next provider request needs consent to include
+/// detected secrets.
+const SECRETS_HELD_TEXT: &str = "Confirmation required before sending detected secrets to the provider.";
+
+/// What a sub-task did, merged into the parent run so its audit entry covers the nested calls.
+struct SubtaskRun {
+    policy_decisions: Vec<PolicyDecisionRecord>,
+    executed_actions: Vec<String>,
+    executed_action_events: Vec<ActionEvent>,
+    evidence_attachments: Vec<(String, EvidenceAttachment)>,
+}
+
+/// A `guard_outbound` verdict: the `provider.send` action event, its `actions_executed` entry,
+/// and whether the provider round may go ahead.
+struct SecretFinding {
+    event: ActionEvent,
+    log_entry: String,
+    send: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -24,7 +58,110 @@ pub struct AuditEvent {
     pub tool_calls_requested: Vec<String>,
     pub tool_calls_executed: Vec<String>,
     pub evidence_summaries: Vec<String>,
+    /// `(tool name, attachment)` for every attachment the run's tools returned.
+    pub evidence_attachments: Vec<(String, EvidenceAttachment)>,
     pub policy_decisions: Vec<PolicyDecisionRecord>,
+    /// Every call that needed consent, in slot order, with canonical arguments; a replay must
+    /// propose the same calls (see `Orchestrator::expect_consent_calls`).
+    pub consent_calls: Vec<ToolCall>,
+}
+
+/// Which consent-required actions a replayed request may execute, indexed in the order the
+/// actions were proposed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConsentApprovals {
+    None,
+    All,
+    Only(BTreeSet<usize>),
+}
+
+/// Hooks into a run for callers that watch it live (streaming clients, metrics) without the
+/// orchestrator knowing about them. Every method defaults to a no-op.
+pub trait OrchestrationObserver {
+    /// Ask providers to stream, so `token` sees text as it arrives.
+    fn wants_tokens(&self) -> bool {
+        false
+    }
+
+    /// Round 1 is the first provider call; each later round follows a batch of tool results.
+    fn provider_round_started(&mut self, _round: usize) {}
+
+    fn provider_round_finished(&mut self, _round: usize, _reply: &ProviderReply, _elapsed: Duration) {}
+
+    fn token(&mut self, _text: &str) {}
+
+    /// An allowed call is about to execute.
+    fn tool_started(&mut self, _call: &ToolCall) {}
+
+    fn tool_finished(&mut self, _result: &ToolResult, _elapsed: Duration) {}
+
+    /// Every allow, deny or confirmation decision, in order.
+    fn policy_decision(&mut self, _decision: &PolicyDecisionRecord) {}
+}
+
+struct NoopObserver;
+
+impl OrchestrationObserver for NoopObserver {}
+
+impl ConsentApprovals {
+    /// `None` while no decision was made for `slot`, else whether it was approved.
+    fn approves(&self, slot: usize) -> Option<bool> {
+        match self {
+            Self::None => None,
+            Self::All => Some(true),
+            Self::Only(set) => Some(set.contains(&slot)),
+        }
+    }
+}
+
+/// Progress reported while a run is in flight, for clients that render it live.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StreamEvent {
+    /// A chunk of assistant text, in arrival order.
+    Token(String),
+    /// An allowed tool call is about to execute.
+    ToolStarted(String),
+    /// Results of tool round `n` are going back to the provider.
+    RoundFinished(usize),
+}
+
+/// What a sub-task passes up to the parent run's observer: its tool activity and decisions, but
+/// not its rounds or text, which are not the parent's reply.
+struct SubtaskObserver<'a>(&'a mut dyn OrchestrationObserver);
+
+impl OrchestrationObserver for SubtaskObserver<'_> {
+    fn tool_started(&mut self, call: &ToolCall) {
+        self.0.tool_started(call);
+    }
+
+    fn tool_finished(&mut self, result: &ToolResult, elapsed: Duration) {
+        self.0.tool_finished(result, elapsed);
+    }
+
+    fn policy_decision(&mut self, decision: &PolicyDecisionRecord) {
+        self.0.policy_decision(decision);
+    }
+}
+
+/// A `StreamEvent` callback observes tokens and tool progress.
+impl<F: FnMut(StreamEvent)> OrchestrationObserver for F {
+    fn wants_tokens(&self) -> bool {
+        true
+    }
+
+    fn provider_round_started(&mut self, round: usize) {
+        if round > 1 {
+            self(StreamEvent::RoundFinished(round - 1));
+        }
+    }
+
+    fn token(&mut self, text: &str) {
+        self(StreamEvent::Token(text.to_string()));
+    }
+
+    fn tool_started(&mut self, call: &ToolCall) {
+        self(StreamEvent::ToolStarted(call.name.clone()));
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -53,6 +190,12 @@ where
     action_backend: A,
     audit_counter: u64,
     audit_log: AuditLog,
+    system_prompt: Option<String>,
+    context: Option<String>,
+    dry_run: bool,
+    expected_consent_calls: Option<Vec<ToolCall>>,
+    /// Set while a sub-task runs, so it records under the parent run's audit id.
+    subtask_audit_id: Option<String>,
 }
 
 impl<P, A> Orchestrator<P, A>
@@ -60,8 +203,6 @@ where
     P: Provider,
     A: ActionBackend,
 {
-    const MAX_TOOL_ROUNDS: usize = 4;
-
     pub fn new(policy: Policy, tool_registry: ToolRegistry, provider: P, action_backend: A) -> Self {
         Self {
             policy,
@@ -70,9 +211,38 @@ where
             action_backend,
             audit_counter: 0,
             audit_log: AuditLog::default(),
+            system_prompt: None,
+            context: None,
+            dry_run: false,
+            expected_consent_calls: None,
+            subtask_audit_id: None,
         }
     }
 
+    /// Instructions prepended as a `system` message on every run.
+    pub fn with_system_prompt(mut self, system_prompt: Option<String>) -> Self {
+        self.system_prompt = system_prompt.filter(|s| !s.trim().is_empty());
+        self
+    }
+
+    /// Retrieved project context, sent as a `system` message after the instructions.
+    pub fn set_context(&mut self, context: Option<String>) {
+        self.context = context.filter(|s| !s.trim().is_empty());
+    }
+
+    /// In a dry run, calls above ReadOnly that the policy would allow or ask consent for go to
+    /// `ActionBackend::simulate_tool` instead, and sub-tasks inherit the setting.
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// For a consent replay: the `AuditEvent::consent_calls` of the run that asked. An approved
+    /// slot only executes when the replay proposes the same tool with the same arguments there;
+    /// otherwise it is denied with `consent_fingerprint_mismatch`.
+    pub fn expect_consent_calls(&mut self, calls: Option<Vec<ToolCall>>) {
+        self.expected_consent_calls = calls;
+    }
+
     pub fn handle_user_message(
         &mut self,
         user_message: String,
@@ -82,6 +252,9 @@ where
         let messages = vec![ChatMessage {
             role: "user".to_string(),
             content: user_message,
+            parts: Vec::new(),
+            message_id: None,
+            created_at_unix_seconds: None,
         }];
         self.run(messages, provider_config, mode)
     }
@@ -102,7 +275,41 @@ where
         mode: ChatMode,
         user_confirmed: bool,
     ) -> ChatResponse {
-        let audit_id = self.next_audit_id();
+        let approvals = if user_confirmed {
+            ConsentApprovals::All
+        } else {
+            ConsentApprovals::None
+        };
+        self.run_with_approvals(messages, provider_config, mode, &approvals, &BTreeMap::new())
+    }
+
+    pub fn run_with_approvals(
+        &mut self,
+        messages: Vec<ChatMessage>,
+        provider_config: ProviderConfig,
+        mode: ChatMode,
+        approvals: &ConsentApprovals,
+        argument_overrides: &BTreeMap<usize, String>,
+    ) -> ChatResponse {
+        self.run_observed(messages, provider_config, mode, approvals, argument_overrides, &mut NoopObserver)
+    }
+
+    /// `run_with_approvals`, reporting rounds, tokens, tool executions and policy decisions to
+    /// `observer` as they happen.
+    pub fn run_observed(
+        &mut self,
+        messages: Vec<ChatMessage>,
+        provider_config: ProviderConfig,
+        mode: ChatMode,
+        approvals: &ConsentApprovals,
+        argument_overrides: &BTreeMap<usize, String>,
+        observer: &mut dyn OrchestrationObserver,
+    ) -> ChatResponse {
+        let mut messages = self.with_system_message(messages);
+        let audit_id = match self.subtask_audit_id.take() {
+            Some(parent_audit_id) => parent_audit_id,
+            None => self.next_audit_id(),
+        };
         let request_fingerprint = request_fingerprint(&messages, &provider_config, &mode);
         let timestamp_unix_seconds = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -112,55 +319,190 @@ where
         let mut executed_actions = Vec::new();
         let mut proposed_actions: Vec<ActionEvent> = Vec::new();
         let mut executed_action_events: Vec<ActionEvent> = Vec::new();
+        let mut subtask_attachments = Vec::new();
         let mut tool_results: Vec<ToolResult> = Vec::new();
         let mut requested_tool_calls = Vec::new();
+        let mut written_paths = BTreeSet::new();
         let mut policy_decisions = Vec::new();
+        let mut consent_calls = Vec::new();
+        tracing::info!(%audit_id, provider = %provider_config.provider_name, ?mode, "chat run started");
 
-        let mut provider_reply = self.provider.chat(&messages, &tools, &tool_results, &provider_config);
+        let finding = self.guard_outbound(&audit_id, &provider_config, &mut messages, &mut [], approvals, &mut consent_calls);
+        let held = finding.is_some_and(|finding| {
+            proposed_actions.push(finding.event);
+            executed_actions.push(finding.log_entry);
+            !finding.send
+        });
+        let mut scanned_results = 0usize;
+        let mut provider_reply = if held {
+            ProviderReply::FinalText(SECRETS_HELD_TEXT.to_string())
+        } else {
+            ask_provider(&self.provider, &messages, &tools, &tool_results, &provider_config, 1, observer)
+        };
+        let mut provider_retries = self.provider.last_retry_count();
         let mut tool_rounds = 0usize;
         let final_text = loop {
             match provider_reply {
                 ProviderReply::FinalText(text) => break text,
                 ProviderReply::ToolCalls(calls) => {
                     tool_rounds += 1;
-                    if tool_rounds > Self::MAX_TOOL_ROUNDS {
+                    if tool_rounds > self.policy.max_tool_rounds {
                         break format!(
                             "Provider requested more than {} tool rounds; stopping for safety.",
-                            Self::MAX_TOOL_ROUNDS
+                            self.policy.max_tool_rounds
                         );
                     }
 
                     let tool_results_before = tool_results.len();
                     let mut pending_confirmation = false;
 
-                    for call in calls {
+                    for mut call in calls {
                         requested_tool_calls.push(call.name.clone());
                         if !self.tool_registry.has_tool(&call.name) {
+                            tracing::warn!(%audit_id, tool = %call.name, "provider requested unknown tool");
                             executed_actions.push(format!("denied:{}:unknown_tool", call.name));
                             proposed_actions.push(ActionEvent {
                                 tool_name: call.name.clone(),
                                 capability_tier: capability_tier_label(&CapabilityTier::SystemActions),
                                 status: "denied".to_string(),
                                 reason: Some("unknown_tool".to_string()),
-                                arguments_preview: Some(arguments_preview(&call.arguments_json)),
+                                arguments_preview: Some(arguments_preview(&self.policy.redactor, &call.arguments_json)),
                                 evidence_summary: None,
+                                preview_diff: None,
                             });
-                            policy_decisions.push(PolicyDecisionRecord {
+                            push_decision(&mut policy_decisions, observer, PolicyDecisionRecord {
+                                arguments_json: self.policy.redactor.redact_json_str(&call.arguments_json),
                                 tool_name: call.name,
                                 capability_tier: CapabilityTier::SystemActions,
                                 decision: "deny".to_string(),
                                 reason: Some("unknown_tool".to_string()),
+                                consented: false,
                             });
                             continue;
                         }
                         let tier = self.policy.capability_tier(&call);
-                        let auth = self.policy.authorize(
+                        let validation = self
+                            .tool_registry
+                            .get(&call.name)
+                            .map(|tool| validate_tool_arguments(&tool.input_json_schema, &call.arguments_json))
+                            .unwrap_or(Ok(()));
+                        if let Err(detail) = validation {
+                            tracing::warn!(%audit_id, tool = %call.name, %detail, "tool arguments failed validation");
+                            executed_actions.push(format!("denied:{}:invalid_arguments", call.name));
+                            proposed_actions.push(ActionEvent {
+                                tool_name: call.name.clone(),
+                                capability_tier: capability_tier_label(&tier),
+                                status: "denied".to_string(),
+                                reason: Some(format!("invalid_arguments: {detail}")),
+                                arguments_preview: Some(arguments_preview(&self.policy.redactor, &call.arguments_json)),
+                                evidence_summary: None,
+                                preview_diff: None,
+                            });
+                            push_decision(&mut policy_decisions, observer, PolicyDecisionRecord {
+                                arguments_json: self.policy.redactor.redact_json_str(&call.arguments_json),
+                                tool_name: call.name,
+                                capability_tier: tier,
+                                decision: "deny".to_string(),
+                                reason: Some("invalid_arguments".to_string()),
+                                consented: false,
+                            });
+                            continue;
+                        }
+                        let mut auth = self.policy.authorize(
                             &call,
                             &PolicyContext {
                                 mode: mode.clone(),
-                                user_confirmed,
+                                user_confirmed: false,
                             },
                         );
+                        let mut consented = false;
+                        if let Authorization::RequireConfirmation { .. } = auth {
+                            let slot = consent_calls.len();
+                            consent_calls.push(canonical_call(&call));
+                            let approved = approvals.approves(slot);
+                            consented = approved.is_some();
+                            match approved {
+                                None => {}
+                                Some(false) => {
+                                    auth = Authorization::Deny {
+                                        reason: "user_denied".to_string(),
+                                    }
+                                }
+                                Some(true) if !self.replays_consented_call(&consent_calls, slot) => {
+                                    tracing::warn!(%audit_id, tool = %call.name, slot, "replayed call differs from the consented one");
+                                    auth = Authorization::Deny {
+                                        reason: "consent_fingerprint_mismatch".to_string(),
+                                    }
+                                }
+                                Some(true) => {
+                                    auth = Authorization::Allow;
+                                    if let Some(edit) = argument_overrides.get(&slot) {
+                                        let edited = merge_argument_override(&call.arguments_json, edit).and_then(|merged| {
+                                            self.tool_registry
+                                                .get(&call.name)
+                                                .map(|tool| validate_tool_arguments(&tool.input_json_schema, &merged))
+                                                .unwrap_or(Ok(()))
+                                                .map(|_| merged)
+                                        });
+                                        match edited {
+                                            Ok(merged) => call.arguments_json = merged,
+                                            Err(detail) => {
+                                                auth = Authorization::Deny {
+                                                    reason: format!("invalid_arguments: {detail}"),
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        if !matches!(auth, Authorization::Deny { .. }) {
+                            if let Err(reason) = self.policy.check_file_write(&call, &mut written_paths) {
+                                auth = Authorization::Deny { reason };
+                            }
+                        }
+                        if self.dry_run && tier != CapabilityTier::ReadOnly && !matches!(auth, Authorization::Deny { .. }) {
+                            let reason = match auth {
+                                Authorization::RequireConfirmation { reason } => Some(reason),
+                                _ => None,
+                            };
+                            tracing::info!(%audit_id, tool = %call.name, tier = ?tier, "tool simulated");
+                            let mut result = self.action_backend.simulate_tool(&call);
+                            result.tool_call_id = call.tool_call_id.clone();
+                            if let Some(max_bytes) = self.policy.result_limits.limit_for(&call.name) {
+                                truncate_result(&mut result, max_bytes);
+                            }
+                            let preview = Some(arguments_preview(&self.policy.redactor, &call.arguments_json));
+                            proposed_actions.push(ActionEvent {
+                                tool_name: call.name.clone(),
+                                capability_tier: capability_tier_label(&tier),
+                                status: "simulated".to_string(),
+                                reason: reason.clone(),
+                                arguments_preview: preview.clone(),
+                                evidence_summary: None,
+                                preview_diff: None,
+                            });
+                            executed_actions.push(format!("simulated:{}", call.name));
+                            executed_action_events.push(ActionEvent {
+                                tool_name: call.name.clone(),
+                                capability_tier: capability_tier_label(&tier),
+                                status: "simulated".to_string(),
+                                reason: None,
+                                arguments_preview: preview,
+                                evidence_summary: Some(result.evidence.summary.clone()),
+                                preview_diff: self.action_backend.preview_tool_effect(&call),
+                            });
+                            push_decision(&mut policy_decisions, observer, PolicyDecisionRecord {
+                                arguments_json: self.policy.redactor.redact_json_str(&call.arguments_json),
+                                tool_name: call.name.clone(),
+                                capability_tier: tier,
+                                decision: if reason.is_some() { "require_confirmation" } else { "allow" }.to_string(),
+                                reason,
+                                consented,
+                            });
+                            tool_results.push(result);
+                            continue;
+                        }
                         match auth {
                             Authorization::Allow => {
                                 proposed_actions.push(ActionEvent {
@@ -168,11 +510,32 @@ where
                                     capability_tier: capability_tier_label(&tier),
                                     status: "approved".to_string(),
                                     reason: None,
-                                    arguments_preview: Some(arguments_preview(&call.arguments_json)),
+                                    arguments_preview: Some(arguments_preview(&self.policy.redactor, &call.arguments_json)),
                                     evidence_summary: None,
+                                    preview_diff: None,
                                 });
-                                let mut result = self.action_backend.execute_tool(&call);
+                                observer.tool_started(&call);
+                                let started = Instant::now();
+                                let mut result = if call.name == SUBTASK_TOOL {
+                                    let (result, nested) =
+                                        self.run_subtask(&audit_id, &call, &provider_config, &mode, observer);
+                                    policy_decisions.extend(nested.policy_decisions);
+                                    executed_actions.extend(nested.executed_actions);
+                                    executed_action_events.extend(nested.executed_action_events);
+                                    subtask_attachments.extend(nested.evidence_attachments);
+                                    result
+                                } else {
+                                    match self.policy.tool_timeout {
+                                        Some(timeout) => self.action_backend.execute_tool_with_timeout(&call, timeout),
+                                        None => self.action_backend.execute_tool(&call),
+                                    }
+                                };
                                 result.tool_call_id = call.tool_call_id.clone();
+                                if let Some(max_bytes) = self.policy.result_limits.limit_for(&call.name) {
+                                    truncate_result(&mut result, max_bytes);
+                                }
+                                observer.tool_finished(&result, started.elapsed());
+                                tracing::info!(%audit_id, tool = %call.name, tier = ?tier, "tool executed");
                                 let evidence_summary = result.evidence.summary.clone();
                                 executed_actions.push(call.name.clone());
                                 executed_action_events.push(ActionEvent {
@@ -180,50 +543,61 @@ where
                                     capability_tier: capability_tier_label(&tier),
                                     status: "executed".to_string(),
                                     reason: None,
-                                    arguments_preview: Some(arguments_preview(&call.arguments_json)),
+                                    arguments_preview: Some(arguments_preview(&self.policy.redactor, &call.arguments_json)),
                                     evidence_summary: Some(evidence_summary),
+                                    preview_diff: None,
                                 });
-                                policy_decisions.push(PolicyDecisionRecord {
+                                push_decision(&mut policy_decisions, observer, PolicyDecisionRecord {
+                                    arguments_json: self.policy.redactor.redact_json_str(&call.arguments_json),
                                     tool_name: call.name.clone(),
                                     capability_tier: tier,
                                     decision: "allow".to_string(),
                                     reason: None,
+                                    consented,
                                 });
                                 tool_results.push(result);
                             }
                             Authorization::RequireConfirmation { reason } => {
+                                tracing::info!(%audit_id, tool = %call.name, tier = ?tier, %reason, "tool awaiting consent");
                                 pending_confirmation = true;
                                 proposed_actions.push(ActionEvent {
                                     tool_name: call.name.clone(),
                                     capability_tier: capability_tier_label(&tier),
                                     status: "consent_required".to_string(),
                                     reason: Some(reason.clone()),
-                                    arguments_preview: Some(arguments_preview(&call.arguments_json)),
+                                    arguments_preview: Some(arguments_preview(&self.policy.redactor, &call.arguments_json)),
                                     evidence_summary: None,
+                                    preview_diff: self.action_backend.preview_tool_effect(&call),
                                 });
-                                policy_decisions.push(PolicyDecisionRecord {
+                                push_decision(&mut policy_decisions, observer, PolicyDecisionRecord {
+                                    arguments_json: self.policy.redactor.redact_json_str(&call.arguments_json),
                                     tool_name: call.name.clone(),
                                     capability_tier: tier,
                                     decision: "require_confirmation".to_string(),
                                     reason: Some(reason.clone()),
+                                    consented,
                                 });
                                 executed_actions
                                     .push(format!("confirm_required:{}:{}", call.name, reason));
                             }
                             Authorization::Deny { reason } => {
+                                tracing::info!(%audit_id, tool = %call.name, tier = ?tier, %reason, "tool denied");
                                 proposed_actions.push(ActionEvent {
                                     tool_name: call.name.clone(),
                                     capability_tier: capability_tier_label(&tier),
                                     status: "denied".to_string(),
                                     reason: Some(reason.clone()),
-                                    arguments_preview: Some(arguments_preview(&call.arguments_json)),
+                                    arguments_preview: Some(arguments_preview(&self.policy.redactor, &call.arguments_json)),
                                     evidence_summary: None,
+                                    preview_diff: None,
                                 });
-                                policy_decisions.push(PolicyDecisionRecord {
+                                push_decision(&mut policy_decisions, observer, PolicyDecisionRecord {
+                                    arguments_json: self.policy.redactor.redact_json_str(&call.arguments_json),
                                     tool_name: call.name.clone(),
                                     capability_tier: tier,
                                     decision: "deny".to_string(),
                                     reason: Some(reason.clone()),
+                                    consented,
                                 });
                                 executed_actions.push(format!("denied:{}:{}", call.name, reason));
                             }
@@ -233,21 +607,55 @@ where
                     if pending_confirmation && tool_results.len() == tool_results_before {
                         break "Confirmation required before executing requested tools.".to_string();
                     }
+                    let finding = self.guard_outbound(
+                        &audit_id,
+                        &provider_config,
+                        &mut [],
+                        &mut tool_results[scanned_results..],
+                        approvals,
+                        &mut consent_calls,
+                    );
+                    scanned_results = tool_results.len();
+                    if let Some(finding) = finding {
+                        proposed_actions.push(finding.event);
+                        executed_actions.push(finding.log_entry);
+                        if !finding.send {
+                            break SECRETS_HELD_TEXT.to_string();
+                        }
+                    }
 
-                    provider_reply =
-                        self.provider.chat(&messages, &tools, &tool_results, &provider_config);
+                    provider_reply = ask_provider(
+                        &self.provider,
+                        &messages,
+                        &tools,
+                        &tool_results,
+                        &provider_config,
+                        tool_rounds + 1,
+                        observer,
+                    );
+                    provider_retries += self.provider.last_retry_count();
                 }
             }
         };
 
+        tracing::info!(%audit_id, tool_rounds, provider_retries, "chat run finished");
         self.audit_log.push(AuditEvent {
             audit_id: audit_id.clone(),
             timestamp_unix_seconds,
             provider: provider_config.provider_name.clone(),
             tool_calls_requested: requested_tool_calls,
             tool_calls_executed: executed_actions.clone(),
-            evidence_summaries: tool_results.into_iter().map(|r| r.evidence.summary).collect(),
+            evidence_summaries: tool_results.iter().map(|r| r.evidence.summary.clone()).collect(),
+            evidence_attachments: tool_results
+                .into_iter()
+                .flat_map(|r| {
+                    let name = r.name;
+                    r.evidence.attachments.into_iter().map(move |a| (name.clone(), a))
+                })
+                .chain(subtask_attachments)
+                .collect(),
             policy_decisions,
+            consent_calls,
         });
 
         let mut action_events = proposed_actions.clone();
@@ -265,7 +673,247 @@ where
             proposed_actions,
             executed_action_events,
             action_events,
+            provider_retries,
+            cached_from: None,
+        }
+    }
+
+    /// Runs a `task.spawn` call as a nested orchestration with the same provider, policy and
+    /// backend, but only the parent's tools it asked for (never `task.spawn` itself) and at most
+    /// `MAX_SUBTASK_ROUNDS` rounds. Consent cannot be collected mid-task, so nested calls that
+    /// need it are reported back rather than run. The nested run records under `audit_id`, and
+    /// its decisions, executed actions and evidence come back for the parent's audit entry.
+    fn run_subtask(
+        &mut self,
+        audit_id: &str,
+        call: &ToolCall,
+        provider_config: &ProviderConfig,
+        mode: &ChatMode,
+        observer: &mut dyn OrchestrationObserver,
+    ) -> (ToolResult, SubtaskRun) {
+        let args = serde_json::from_str::<serde_json::Value>(&call.arguments_json).unwrap_or_default();
+        let prompt = args.get("prompt").and_then(serde_json::Value::as_str).unwrap_or_default().to_string();
+        let rules = ToolRules {
+            allow: args.get("tools").and_then(serde_json::Value::as_array).map(|tools| {
+                tools
+                    .iter()
+                    .filter_map(serde_json::Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            }),
+            deny: vec![SUBTASK_TOOL.to_string()],
+        };
+        let rounds = args
+            .get("max_rounds")
+            .and_then(serde_json::Value::as_u64)
+            .map_or(DEFAULT_SUBTASK_ROUNDS, |n| n as usize)
+            .min(MAX_SUBTASK_ROUNDS)
+            .min(self.policy.max_tool_rounds);
+        let mut tools = self.tool_registry.list();
+        tools.retain(|tool| rules.permits(&tool.name));
+        let parent_tools = std::mem::replace(&mut self.tool_registry, ToolRegistry::from_tools(tools));
+        let parent_rounds = std::mem::replace(&mut self.policy.max_tool_rounds, rounds);
+        tracing::info!(rounds, "sub-task started");
+
+        let messages = vec![ChatMessage {
+            role: "user".to_string(),
+            content: prompt,
+            parts: Vec::new(),
+            message_id: None,
+            created_at_unix_seconds: None,
+        }];
+        self.subtask_audit_id = Some(audit_id.to_string());
+        let response = self.run_observed(
+            messages,
+            provider_config.clone(),
+            mode.clone(),
+            &ConsentApprovals::None,
+            &BTreeMap::new(),
+            &mut SubtaskObserver(observer),
+        );
+        self.tool_registry = parent_tools;
+        self.policy.max_tool_rounds = parent_rounds;
+        let (policy_decisions, evidence_attachments) = self
+            .audit_log
+            .events
+            .pop()
+            .map(|run| (run.policy_decisions, run.evidence_attachments))
+            .unwrap_or_default();
+
+        let names_with_status = |status: &str| {
+            response
+                .action_events
+                .iter()
+                .filter(|event| event.status == status)
+                .map(|event| event.tool_name.clone())
+                .collect::<Vec<_>>()
+        };
+        let executed = names_with_status("executed");
+        let needs_consent = names_with_status("consent_required");
+        let denied = names_with_status("denied");
+        let simulated = response
+            .executed_action_events
+            .iter()
+            .filter(|event| event.status == "simulated")
+            .map(|event| event.tool_name.clone())
+            .collect::<Vec<_>>();
+        let mut summary = format!("sub-task ran {} tool call(s)", executed.len());
+        if !simulated.is_empty() {
+            summary.push_str(&format!("; simulated {}: {}", simulated.len(), simulated.join(", ")));
         }
+        if !needs_consent.is_empty() {
+            summary.push_str(&format!("; {} need consent: {}", needs_consent.len(), needs_consent.join(", ")));
+        }
+        let result = ToolResult {
+            tool_call_id: None,
+            name: call.name.clone(),
+            result_json: serde_json::json!({
+                "final_text": response.final_text,
+                "max_rounds": rounds,
+                "executed_tools": executed,
+                "needs_consent": needs_consent,
+                "denied_tools": denied,
+                "simulated_tools": simulated,
+            })
+            .to_string(),
+            evidence: ipc::Evidence {
+                summary,
+                artifacts: Vec::new(),
+                attachments: Vec::new(),
+                original_result_bytes: None,
+            },
+        };
+        let nested = SubtaskRun {
+            policy_decisions,
+            executed_actions: response.actions_executed,
+            executed_action_events: response.executed_action_events,
+            evidence_attachments,
+        };
+        (result, nested)
+    }
+
+    /// Runs `policy.secret_scan` over what the next provider round sends for the first time:
+    /// the messages before round one, then each new batch of tool results. Matches are masked in
+    /// place unless the policy asks for consent and the user approved (or a grant covers)
+    /// sending them as is. `None` when nothing was found or the provider isn't scanned.
+    fn guard_outbound(
+        &self,
+        audit_id: &str,
+        provider_config: &ProviderConfig,
+        messages: &mut [ChatMessage],
+        results: &mut [ToolResult],
+        approvals: &ConsentApprovals,
+        consent_calls: &mut Vec<ToolCall>,
+    ) -> Option<SecretFinding> {
+        let scanner = &self.policy.secret_scan;
+        if !scanner.applies_to(self.provider.is_remote(provider_config)) {
+            return None;
+        }
+        let mut detectors = BTreeSet::new();
+        let mut sources = BTreeSet::new();
+        for message in messages.iter() {
+            let found = scanner.detect(&message.content);
+            if !found.is_empty() {
+                detectors.extend(found);
+                sources.insert(format!("{} message", message.role));
+            }
+        }
+        for result in results.iter() {
+            let found = scanner.detect_json(&result.result_json);
+            if !found.is_empty() {
+                detectors.extend(found);
+                sources.insert(format!("{} result", result.name));
+            }
+        }
+        if detectors.is_empty() {
+            return None;
+        }
+        let approved = match scanner.action {
+            SecretScanAction::Consent if self.policy.grants.iter().any(|g| g.tool_name == PROVIDER_SEND_ACTION) => {
+                Some(true)
+            }
+            SecretScanAction::Consent => {
+                let slot = consent_calls.len();
+                consent_calls.push(ToolCall {
+                    tool_call_id: None,
+                    name: PROVIDER_SEND_ACTION.to_string(),
+                    arguments_json: "{}".to_string(),
+                });
+                // A mismatched slot sends masked data, as a denial would.
+                approvals.approves(slot).map(|approved| approved && self.replays_consented_call(consent_calls, slot))
+            }
+            SecretScanAction::Redact | SecretScanAction::Off => Some(false),
+        };
+        let names = detectors.iter().cloned().collect::<Vec<_>>().join(",");
+        let detail = format!("{names} in {}", sources.iter().cloned().collect::<Vec<_>>().join(", "));
+        let (status, reason, log_entry) = match approved {
+            None => (
+                "consent_required",
+                format!("secrets_detected: {detail}"),
+                format!("confirm_required:{PROVIDER_SEND_ACTION}:secrets_detected"),
+            ),
+            Some(true) => ("approved", format!("secrets_detected: {detail}"), PROVIDER_SEND_ACTION.to_string()),
+            Some(false) => {
+                for message in messages.iter_mut() {
+                    message.content = scanner.redact(&message.content);
+                }
+                for result in results.iter_mut() {
+                    result.result_json = scanner.redact_json(&result.result_json);
+                }
+                let status = if scanner.action == SecretScanAction::Consent { "denied" } else { "redacted" };
+                (status, format!("secrets_redacted: {detail}"), format!("secrets_redacted:{names}"))
+            }
+        };
+        tracing::warn!(%audit_id, %status, %detail, "secrets found in provider request");
+        Some(SecretFinding {
+            event: ActionEvent {
+                tool_name: PROVIDER_SEND_ACTION.to_string(),
+                capability_tier: capability_tier_label(&CapabilityTier::SystemActions),
+                status: status.to_string(),
+                reason: Some(reason),
+                arguments_preview: Some(serde_json::json!({ "detectors": detectors, "sources": sources }).to_string()),
+                evidence_summary: None,
+                preview_diff: None,
+            },
+            log_entry,
+            send: approved.is_some(),
+        })
+    }
+
+    fn with_system_message(&self, messages: Vec<ChatMessage>) -> Vec<ChatMessage> {
+        let mut out = messages;
+        if let Some(prompt) = self.system_prompt.as_ref() {
+            if !out
+                .first()
+                .map(|m| m.role == "system" && &m.content == prompt)
+                .unwrap_or(false)
+            {
+                out.insert(
+                    0,
+                    ChatMessage {
+                        role: "system".to_string(),
+                        content: prompt.clone(),
+                        parts: Vec::new(),
+                        message_id: None,
+                        created_at_unix_seconds: None,
+                    },
+                );
+            }
+        }
+        if let Some(context) = self.context.as_ref() {
+            let at = out.iter().take_while(|m| m.role == "system").count();
+            out.insert(
+                at,
+                ChatMessage {
+                    role: "system".to_string(),
+                    content: context.clone(),
+                    parts: Vec::new(),
+                    message_id: None,
+                    created_at_unix_seconds: None,
+                },
+            );
+        }
+        out
     }
 
     fn next_audit_id(&mut self) -> String {
@@ -276,6 +924,108 @@ where
     pub fn audit_events(&self) -> &[AuditEvent] {
         self.audit_log.events()
     }
+
+    /// Moves the last run's attachments out, so the in-memory log does not hold their content.
+    pub fn take_evidence_attachments(&mut self) -> Vec<(String, EvidenceAttachment)> {
+        self.audit_log
+            .events
+            .last_mut()
+            .map(|run| std::mem::take(&mut run.evidence_attachments))
+            .unwrap_or_default()
+    }
+
+    /// The fingerprint `run_with_approvals` would record for this request, system prompt included.
+    pub fn request_fingerprint(&self, messages: &[ChatMessage], provider_config: &ProviderConfig, mode: &ChatMode) -> String {
+        request_fingerprint(&self.with_system_message(messages.to_vec()), provider_config, mode)
+    }
+
+    /// Key for `cache_ttl_secs` reuse: the request fingerprint plus the project root, offered
+    /// tools and policy profile, so a cached answer never stands in for a run that could have
+    /// read other files or used other tools.
+    pub fn response_cache_key(
+        &self,
+        messages: &[ChatMessage],
+        provider_config: &ProviderConfig,
+        mode: &ChatMode,
+        project_root: Option<&str>,
+    ) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.request_fingerprint(messages, provider_config, mode).hash(&mut hasher);
+        project_root.hash(&mut hasher);
+        let mut tools = self.tool_registry.list().into_iter().map(|tool| tool.name).collect::<Vec<_>>();
+        tools.sort();
+        tools.hash(&mut hasher);
+        self.policy.profile.hash(&mut hasher);
+        format!("cache-{:016x}", hasher.finish())
+    }
+
+    /// Whether the call just recorded in `consent_calls[slot]` is the one consent was asked for;
+    /// always true outside a replay.
+    fn replays_consented_call(&self, consent_calls: &[ToolCall], slot: usize) -> bool {
+        let Some(expected) = &self.expected_consent_calls else {
+            return true;
+        };
+        match (expected.get(slot), consent_calls.get(slot)) {
+            (Some(expected), Some(actual)) => {
+                expected.name == actual.name && expected.arguments_json == actual.arguments_json
+            }
+            _ => false,
+        }
+    }
+
+    pub fn has_tool(&self, name: &str) -> bool {
+        self.tool_registry.has_tool(name)
+    }
+}
+
+fn ask_provider<P: Provider>(
+    provider: &P,
+    messages: &[ChatMessage],
+    tools: &[Tool],
+    tool_results: &[ToolResult],
+    config: &ProviderConfig,
+    round: usize,
+    observer: &mut dyn OrchestrationObserver,
+) -> ProviderReply {
+    observer.provider_round_started(round);
+    let started = Instant::now();
+    let reply = if observer.wants_tokens() {
+        provider.chat_streaming(messages, tools, tool_results, config, &mut |token| observer.token(token))
+    } else {
+        provider.chat(messages, tools, tool_results, config)
+    };
+    observer.provider_round_finished(round, &reply, started.elapsed());
+    reply
+}
+
+/// Cuts `result_json` down to `max_bytes` (on a char boundary) and appends a marker saying so,
+/// recording the original size in the evidence. The result is no longer valid JSON once cut.
+fn truncate_result(result: &mut ToolResult, max_bytes: usize) {
+    let original = result.result_json.len();
+    if original <= max_bytes {
+        return;
+    }
+    let mut kept = max_bytes;
+    while !result.result_json.is_char_boundary(kept) {
+        kept -= 1;
+    }
+    result.result_json.truncate(kept);
+    result
+        .result_json
+        .push_str(&format!("\n[truncated: result was {original} bytes; only the first {kept} are shown]"));
+    result.evidence.original_result_bytes = Some(original as u64);
+    let note = format!("result truncated from {original} to {kept} bytes");
+    let summary = &mut result.evidence.summary;
+    *summary = if summary.is_empty() { note } else { format!("{summary} ({note})") };
+}
+
+fn push_decision(
+    decisions: &mut Vec<PolicyDecisionRecord>,
+    observer: &mut dyn OrchestrationObserver,
+    decision: PolicyDecisionRecord,
+) {
+    observer.policy_decision(&decision);
+    decisions.push(decision);
 }
 
 fn capability_tier_label(tier: &CapabilityTier) -> String {
@@ -287,10 +1037,27 @@ fn capability_tier_label(tier: &CapabilityTier) -> String {
     .to_string()
 }
 
-fn arguments_preview(arguments_json: &str) -> String {
+/// Applies user-edited fields from a consent approval on top of the provider's proposed arguments.
+fn merge_argument_override(arguments_json: &str, override_json: &str) -> Result<String, String> {
+    let mut merged = serde_json::from_str::<serde_json::Value>(arguments_json)
+        .ok()
+        .filter(serde_json::Value::is_object)
+        .unwrap_or_else(|| serde_json::json!({}));
+    let edits = serde_json::from_str::<serde_json::Value>(override_json)
+        .map_err(|err| format!("edited arguments are not valid JSON: {err}"))?;
+    let serde_json::Value::Object(edits) = edits else {
+        return Err("edited arguments must be a JSON object".to_string());
+    };
+    if let Some(target) = merged.as_object_mut() {
+        target.extend(edits);
+    }
+    Ok(merged.to_string())
+}
+
+fn arguments_preview(redactor: &Redactor, arguments_json: &str) -> String {
     const MAX_CHARS: usize = 180;
-    let sanitized = sanitize_arguments_preview(arguments_json);
-    let compact = sanitized.replace('\n', " ").replace('\r', " ");
+    let sanitized = redactor.redact_json_str(arguments_json);
+    let compact = sanitized.replace(['\n', '\r'], " ");
     let mut chars = compact.chars();
     let preview: String = chars.by_ref().take(MAX_CHARS).collect();
     if chars.next().is_some() {
@@ -300,45 +1067,27 @@ fn arguments_preview(arguments_json: &str) -> String {
     }
 }
 
-fn sanitize_arguments_preview(arguments_json: &str) -> String {
-    let Ok(mut value) = serde_json::from_str::<serde_json::Value>(arguments_json) else {
-        return arguments_json.to_string();
-    };
-    redact_sensitive_json(&mut value);
-    serde_json::to_string(&value).unwrap_or_else(|_| arguments_json.to_string())
-}
-
-fn redact_sensitive_json(value: &mut serde_json::Value) {
-    match value {
-        serde_json::Value::Object(map) => {
-            for (key, value) in map.iter_mut() {
-                if is_sensitive_key(key) {
-                    *value = serde_json::Value::String("[REDACTED]".to_string());
-                } else {
-                    redact_sensitive_json(value);
-                }
-            }
-        }
-        serde_json::Value::Array(items) => {
-            for item in items {
-                redact_sensitive_json(item);
-            }
-        }
-        _ => {}
+/// `call` with its arguments re-serialized with sorted keys and no whitespace, so equal
+/// arguments compare equal however the provider formatted them.
+fn canonical_call(call: &ToolCall) -> ToolCall {
+    let arguments_json = serde_json::from_str::<serde_json::Value>(&call.arguments_json)
+        .map(|value| value.to_string())
+        .unwrap_or_else(|_| call.arguments_json.clone());
+    ToolCall {
+        tool_call_id: None,
+        name: call.name.clone(),
+        arguments_json,
     }
 }
 
-fn is_sensitive_key(key: &str) -> bool {
-    matches!(
-        key.to_ascii_lowercase().as_str(),
-        "api_key" | "apikey" | "token" | "authorization" | "password" | "secret" | "content"
-    )
-}
-
 fn request_fingerprint(messages: &[ChatMessage], provider_config: &ProviderConfig, mode: &ChatMode) -> String {
     let mut hasher = DefaultHasher::new();
     provider_config.provider_name.hash(&mut hasher);
     provider_config.model.hash(&mut hasher);
+    provider_config.temperature.map(f64::to_bits).hash(&mut hasher);
+    provider_config.max_tokens.hash(&mut hasher);
+    provider_config.top_p.map(f64::to_bits).hash(&mut hasher);
+    provider_config.stop.hash(&mut hasher);
     match mode {
         ChatMode::RequireConfirmation => "RequireConfirmation".hash(&mut hasher),
         ChatMode::BestEffort => "BestEffort".hash(&mut hasher),
@@ -346,6 +1095,7 @@ fn request_fingerprint(messages: &[ChatMessage], provider_config: &ProviderConfi
     for message in messages {
         message.role.hash(&mut hasher);
         message.content.hash(&mut hasher);
+        message.parts.hash(&mut hasher);
     }
     format!("req-{:016x}", hasher.finish())
 }
@@ -353,6 +1103,7 @@ fn request_fingerprint(messages: &[ChatMessage], provider_config: &ProviderConfi
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::policy::PolicyProfile;
     use ipc::{Evidence, ProviderConfig, ToolCall, ToolResult};
     use providers::provider_trait::ProviderReply;
     use serde_json::json;
@@ -402,6 +1153,15 @@ mod tests {
                 evidence: Evidence {
                     summary: format!("executed {}", tool_call.name),
                     artifacts: vec![],
+                    attachments: (tool_call.name == "file.write_text")
+                        .then(|| EvidenceAttachment {
+                            name: "write.diff".to_string(),
+                            media_type: "text/x-diff".to_string(),
+                            content_base64: String::new(),
+                        })
+                        .into_iter()
+                        .collect(),
+                    original_result_bytes: None,
                 },
             }
         }
@@ -420,11 +1180,15 @@ mod tests {
             vec![ChatMessage {
                 role: "user".to_string(),
                 content: "do thing".to_string(),
+                parts: Vec::new(),
+                message_id: None,
+                created_at_unix_seconds: None,
             }],
             ProviderConfig {
                 provider_name: "multi-round-test".to_string(),
                 model: None,
                 config_json: None,
+                ..Default::default()
             },
             ChatMode::BestEffort,
         );
@@ -435,9 +1199,392 @@ mod tests {
         assert_eq!(response.executed_action_events[1].tool_name, "math.add");
     }
 
+    #[test]
+    fn streaming_run_reports_tool_progress_and_text() {
+        let mut orchestrator = Orchestrator::new(
+            Policy::default(),
+            ToolRegistry::new_default(),
+            MultiRoundProvider,
+            TestActionBackend,
+        );
+        let mut events = Vec::new();
+        let response = orchestrator.run_observed(
+            ipc::sample_messages("do thing"),
+            ProviderConfig::default(),
+            ChatMode::BestEffort,
+            &ConsentApprovals::None,
+            &BTreeMap::new(),
+            &mut |event| events.push(event),
+        );
+
+        assert_eq!(
+            events,
+            vec![
+                StreamEvent::ToolStarted("echo".to_string()),
+                StreamEvent::RoundFinished(1),
+                StreamEvent::ToolStarted("math.add".to_string()),
+                StreamEvent::RoundFinished(2),
+                StreamEvent::Token("done after two rounds".to_string()),
+            ]
+        );
+        assert_eq!(response.final_text, "done after two rounds");
+    }
+
+    #[derive(Default)]
+    struct RecordingObserver {
+        log: Vec<String>,
+    }
+
+    impl OrchestrationObserver for RecordingObserver {
+        fn provider_round_started(&mut self, round: usize) {
+            self.log.push(format!("round {round}"));
+        }
+
+        fn provider_round_finished(&mut self, round: usize, reply: &ProviderReply, _elapsed: Duration) {
+            let kind = match reply {
+                ProviderReply::FinalText(_) => "text",
+                ProviderReply::ToolCalls(_) => "tools",
+            };
+            self.log.push(format!("round {round} -> {kind}"));
+        }
+
+        fn tool_started(&mut self, call: &ToolCall) {
+            self.log.push(format!("start {}", call.name));
+        }
+
+        fn tool_finished(&mut self, result: &ToolResult, _elapsed: Duration) {
+            self.log.push(format!("end {}", result.name));
+        }
+
+        fn policy_decision(&mut self, decision: &PolicyDecisionRecord) {
+            self.log.push(format!("{} {}", decision.decision, decision.tool_name));
+        }
+    }
+
+    #[test]
+    fn observer_sees_rounds_tools_and_decisions_in_order() {
+        let mut orchestrator = Orchestrator::new(
+            Policy::default(),
+            ToolRegistry::new_default(),
+            MultiRoundProvider,
+            TestActionBackend,
+        );
+        let mut observer = RecordingObserver::default();
+        orchestrator.run_observed(
+            ipc::sample_messages("do thing"),
+            ProviderConfig::default(),
+            ChatMode::BestEffort,
+            &ConsentApprovals::None,
+            &BTreeMap::new(),
+            &mut observer,
+        );
+
+        assert_eq!(
+            observer.log,
+            vec![
+                "round 1",
+                "round 1 -> tools",
+                "start echo",
+                "end echo",
+                "allow echo",
+                "round 2",
+                "round 2 -> tools",
+                "start math.add",
+                "end math.add",
+                "allow math.add",
+                "round 3",
+                "round 3 -> text",
+            ]
+        );
+    }
+
+    /// Delegates once, then reports what the sub-task returned; the sub-task (prompt "child")
+    /// echoes and tries a write that needs consent.
+    struct DelegatingProvider;
+
+    impl Provider for DelegatingProvider {
+        fn name(&self) -> &'static str {
+            "delegating-test"
+        }
+
+        fn chat(
+            &self,
+            messages: &[ChatMessage],
+            tools: &[ipc::Tool],
+            tool_results: &[ToolResult],
+            _config: &ProviderConfig,
+        ) -> ProviderReply {
+            let child = messages.last().is_some_and(|m| m.content == "child");
+            match (child, tool_results.first()) {
+                (false, None) => ProviderReply::ToolCalls(vec![ToolCall {
+                    tool_call_id: None,
+                    name: SUBTASK_TOOL.to_string(),
+                    arguments_json: json!({ "prompt": "child", "tools": ["echo", "file.*"] }).to_string(),
+                }]),
+                (true, None) => {
+                    assert!(tools.iter().all(|t| t.name == "echo" || t.name.starts_with("file.")));
+                    ProviderReply::ToolCalls(vec![
+                        ToolCall {
+                            tool_call_id: None,
+                            name: "echo".to_string(),
+                            arguments_json: json!({ "input": "x" }).to_string(),
+                        },
+                        ToolCall {
+                            tool_call_id: None,
+                            name: "file.write_text".to_string(),
+                            arguments_json: json!({ "path": "a.txt", "content": "x" }).to_string(),
+                        },
+                    ])
+                }
+                (true, Some(_)) => ProviderReply::FinalText("child done".to_string()),
+                (false, Some(result)) => ProviderReply::FinalText(format!("parent got {}", result.result_json)),
+            }
+        }
+    }
+
+    #[test]
+    fn task_spawn_runs_a_bounded_nested_orchestration() {
+        let mut orchestrator = Orchestrator::new(
+            Policy::default(),
+            ToolRegistry::new_default(),
+            DelegatingProvider,
+            TestActionBackend,
+        );
+        let response = orchestrator.run(
+            ipc::sample_messages("parent"),
+            ProviderConfig::default(),
+            ChatMode::BestEffort,
+        );
+
+        let result: serde_json::Value = serde_json::from_str(
+            response.final_text.strip_prefix("parent got ").expect("parent reply"),
+        )
+        .expect("result json");
+        assert_eq!(result["final_text"], "child done");
+        assert_eq!(result["max_rounds"], DEFAULT_SUBTASK_ROUNDS);
+        assert_eq!(result["executed_tools"], json!(["echo"]));
+        assert_eq!(result["needs_consent"], json!(["file.write_text"]));
+        let executed = response
+            .executed_action_events
+            .iter()
+            .map(|evt| evt.tool_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(executed, vec!["echo", SUBTASK_TOOL]);
+
+        assert_eq!(orchestrator.audit_events().len(), 1);
+        let decisions = orchestrator.audit_events()[0]
+            .policy_decisions
+            .iter()
+            .map(|d| format!("{} {}", d.decision, d.tool_name))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            decisions,
+            vec!["allow echo", "require_confirmation file.write_text", "allow task.spawn"]
+        );
+    }
+
+    #[test]
+    fn task_spawn_records_nested_actions_in_the_parent_audit() {
+        let policy = Policy {
+            profile: PolicyProfile::Permissive,
+            ..Policy::default()
+        };
+        let mut orchestrator = Orchestrator::new(policy, ToolRegistry::new_default(), DelegatingProvider, TestActionBackend);
+        let response = orchestrator.run(ipc::sample_messages("parent"), ProviderConfig::default(), ChatMode::BestEffort);
+
+        assert_eq!(response.audit_id, "audit-000001");
+        assert_eq!(orchestrator.audit_events().len(), 1);
+        let audit = &orchestrator.audit_events()[0];
+        assert_eq!(audit.audit_id, "audit-000001");
+        assert_eq!(audit.tool_calls_executed, vec!["echo", "file.write_text", SUBTASK_TOOL]);
+        assert_eq!(response.actions_executed, audit.tool_calls_executed);
+        let attachments = audit
+            .evidence_attachments
+            .iter()
+            .map(|(tool, attachment)| format!("{tool} {}", attachment.name))
+            .collect::<Vec<_>>();
+        assert_eq!(attachments, vec!["file.write_text write.diff"]);
+
+        // The next top-level run gets the next id: the sub-task did not use one up.
+        let next = orchestrator.run(ipc::sample_messages("parent"), ProviderConfig::default(), ChatMode::BestEffort);
+        assert_eq!(next.audit_id, "audit-000002");
+    }
+
+    #[test]
+    fn oversized_tool_results_are_truncated_with_a_marker() {
+        let mut policy = Policy::default();
+        policy.result_limits.per_tool.insert("echo".to_string(), Some(8));
+        let mut orchestrator = Orchestrator::new(policy, ToolRegistry::new_default(), MultiRoundProvider, TestActionBackend);
+        let response = orchestrator.run(ipc::sample_messages("do thing"), ProviderConfig::default(), ChatMode::BestEffort);
+        let summaries = response
+            .executed_action_events
+            .iter()
+            .map(|evt| evt.evidence_summary.clone().unwrap_or_default())
+            .collect::<Vec<_>>();
+        assert!(summaries[0].starts_with("executed echo (result truncated from "), "{summaries:?}");
+        assert!(summaries[0].ends_with(" to 8 bytes)"));
+        assert_eq!(summaries[1], "executed math.add");
+
+        let mut result = ToolResult {
+            tool_call_id: None,
+            name: "file.read_text".to_string(),
+            result_json: "ééé".to_string(),
+            evidence: Evidence {
+                summary: String::new(),
+                artifacts: vec![],
+                attachments: vec![],
+                original_result_bytes: None,
+            },
+        };
+        truncate_result(&mut result, 3);
+        assert_eq!(result.result_json, "é\n[truncated: result was 6 bytes; only the first 2 are shown]");
+        assert_eq!(result.evidence.original_result_bytes, Some(6));
+        assert_eq!(result.evidence.summary, "result truncated from 6 to 2 bytes");
+    }
+
+    /// Asks for one file write, then stops.
+    struct WriteOnceProvider;
+
+    impl Provider for WriteOnceProvider {
+        fn name(&self) -> &'static str {
+            "write-once-test"
+        }
+
+        fn chat(
+            &self,
+            _messages: &[ChatMessage],
+            _tools: &[ipc::Tool],
+            tool_results: &[ToolResult],
+            _config: &ProviderConfig,
+        ) -> ProviderReply {
+            if tool_results.is_empty() {
+                ProviderReply::ToolCalls(vec![ToolCall {
+                    tool_call_id: None,
+                    name: "file.write_text".to_string(),
+                    arguments_json: json!({ "path": "a.txt", "content": "x" }).to_string(),
+                }])
+            } else {
+                ProviderReply::FinalText("done".to_string())
+            }
+        }
+    }
+
+    #[test]
+    fn simulated_tool_results_are_truncated_like_real_ones() {
+        let mut policy = Policy::default();
+        policy.result_limits.per_tool.insert("file.write_text".to_string(), Some(8));
+        let mut orchestrator = Orchestrator::new(policy, ToolRegistry::new_default(), WriteOnceProvider, TestActionBackend);
+        orchestrator.set_dry_run(true);
+        let response = orchestrator.run(ipc::sample_messages("write"), ProviderConfig::default(), ChatMode::BestEffort);
+        let simulated = response
+            .executed_action_events
+            .iter()
+            .find(|evt| evt.status == "simulated")
+            .expect("simulated write");
+        let summary = simulated.evidence_summary.clone().unwrap_or_default();
+        assert!(summary.contains("(result truncated from "), "{summary}");
+        assert!(summary.ends_with(" to 8 bytes)"));
+    }
+
+    /// Asks to write whichever path is set, so a test can change the call between a run and its
+    /// consent replay.
+    struct SwappingWriteProvider {
+        path: std::cell::Cell<&'static str>,
+    }
+
+    impl Provider for SwappingWriteProvider {
+        fn name(&self) -> &'static str {
+            "swapping-write-test"
+        }
+
+        fn chat(
+            &self,
+            _messages: &[ChatMessage],
+            _tools: &[ipc::Tool],
+            tool_results: &[ToolResult],
+            _config: &ProviderConfig,
+        ) -> ProviderReply {
+            if tool_results.is_empty() {
+                ProviderReply::ToolCalls(vec![ToolCall {
+                    tool_call_id: None,
+                    name: "file.write_text".to_string(),
+                    arguments_json: format!(r#"{{ "path": "{}", "content": "x" }}"#, self.path.get()),
+                }])
+            } else {
+                ProviderReply::FinalText("done".to_string())
+            }
+        }
+    }
+
+    #[test]
+    fn consent_replay_denies_calls_that_differ_from_the_consented_ones() {
+        let provider = SwappingWriteProvider {
+            path: std::cell::Cell::new("a.txt"),
+        };
+        let mut orchestrator = Orchestrator::new(Policy::default(), ToolRegistry::new_default(), provider, TestActionBackend);
+        let asked = orchestrator.run(ipc::sample_messages("write"), ProviderConfig::default(), ChatMode::BestEffort);
+        assert_eq!(asked.proposed_actions[0].status, "consent_required");
+        let consent_calls = orchestrator.audit_events()[0].consent_calls.clone();
+        assert_eq!(consent_calls.len(), 1);
+        assert_eq!(consent_calls[0].arguments_json, json!({ "path": "a.txt", "content": "x" }).to_string());
+
+        // Same call, formatted differently by the provider: still the consented one.
+        orchestrator.expect_consent_calls(Some(consent_calls.clone()));
+        let replay = orchestrator.run_with_approvals(
+            ipc::sample_messages("write"),
+            ProviderConfig::default(),
+            ChatMode::BestEffort,
+            &ConsentApprovals::Only(BTreeSet::from([0])),
+            &BTreeMap::new(),
+        );
+        assert_eq!(replay.actions_executed, vec!["file.write_text".to_string()]);
+
+        orchestrator.provider.path.set("b.txt");
+        let replay = orchestrator.run_with_approvals(
+            ipc::sample_messages("write"),
+            ProviderConfig::default(),
+            ChatMode::BestEffort,
+            &ConsentApprovals::All,
+            &BTreeMap::new(),
+        );
+        // The provider keeps asking after the denial; no slot, recorded or not, lets it through.
+        assert!(!replay.actions_executed.is_empty());
+        assert!(replay
+            .actions_executed
+            .iter()
+            .all(|entry| entry == "denied:file.write_text:consent_fingerprint_mismatch"));
+        assert!(replay.executed_action_events.is_empty());
+    }
+
+    #[test]
+    fn orchestrator_honors_configured_max_tool_rounds() {
+        let policy = Policy {
+            max_tool_rounds: 1,
+            ..Policy::default()
+        };
+        let mut orchestrator =
+            Orchestrator::new(policy, ToolRegistry::new_default(), MultiRoundProvider, TestActionBackend);
+
+        let response = orchestrator.handle_user_message(
+            "do thing".to_string(),
+            ProviderConfig {
+                provider_name: "multi-round-test".to_string(),
+                ..Default::default()
+            },
+            ChatMode::BestEffort,
+        );
+
+        assert_eq!(
+            response.final_text,
+            "Provider requested more than 1 tool rounds; stopping for safety."
+        );
+        assert_eq!(response.executed_action_events.len(), 1);
+    }
+
     #[test]
     fn arguments_preview_redacts_sensitive_fields() {
         let preview = arguments_preview(
+            &Redactor::default(),
             r#"{"path":"notes/out.txt","content":"super secret body","token":"abc123","nested":{"api_key":"k","safe":"ok"}}"#,
         );
         assert!(preview.contains("\"path\":\"notes/out.txt\""));
@@ -448,4 +1595,112 @@ mod tests {
         assert!(!preview.contains("super secret body"));
         assert!(!preview.contains("abc123"));
     }
+
+    struct EchoFirstMessageProvider;
+
+    impl Provider for EchoFirstMessageProvider {
+        fn name(&self) -> &'static str {
+            "echo-first-message"
+        }
+
+        fn chat(
+            &self,
+            messages: &[ChatMessage],
+            _tools: &[ipc::Tool],
+            _tool_results: &[ToolResult],
+            _config: &ProviderConfig,
+        ) -> ProviderReply {
+            let first = &messages[0];
+            ProviderReply::FinalText(format!("{}:{}:{}", first.role, first.content, messages.len()))
+        }
+    }
+
+    #[test]
+    fn orchestrator_prepends_system_prompt() {
+        let mut orchestrator = Orchestrator::new(
+            Policy::default(),
+            ToolRegistry::new_default(),
+            EchoFirstMessageProvider,
+            TestActionBackend,
+        )
+        .with_system_prompt(Some("Be terse.".to_string()));
+
+        let response = orchestrator.handle_user_message(
+            "hello".to_string(),
+            ProviderConfig {
+                provider_name: "echo-first-message".to_string(),
+                ..Default::default()
+            },
+            ChatMode::BestEffort,
+        );
+        assert_eq!(response.final_text, "system:Be terse.:2");
+
+        orchestrator.set_context(Some("--- notes.md:1-3 ---".to_string()));
+        let response = orchestrator.handle_user_message(
+            "hello".to_string(),
+            ProviderConfig {
+                provider_name: "echo-first-message".to_string(),
+                ..Default::default()
+            },
+            ChatMode::BestEffort,
+        );
+        assert_eq!(response.final_text, "system:Be terse.:3");
+    }
+
+    struct BadArgumentsProvider;
+
+    impl Provider for BadArgumentsProvider {
+        fn name(&self) -> &'static str {
+            "bad-arguments-test"
+        }
+
+        fn chat(
+            &self,
+            _messages: &[ChatMessage],
+            _tools: &[ipc::Tool],
+            tool_results: &[ToolResult],
+            _config: &ProviderConfig,
+        ) -> ProviderReply {
+            if tool_results.is_empty() {
+                ProviderReply::ToolCalls(vec![ToolCall {
+                    tool_call_id: None,
+                    name: "math.add".to_string(),
+                    arguments_json: json!({ "a": "two", "b": 3 }).to_string(),
+                }])
+            } else {
+                ProviderReply::FinalText("unexpected execution".to_string())
+            }
+        }
+    }
+
+    #[test]
+    fn orchestrator_denies_tool_calls_with_invalid_arguments() {
+        let mut orchestrator = Orchestrator::new(
+            Policy::default(),
+            ToolRegistry::new_default(),
+            BadArgumentsProvider,
+            TestActionBackend,
+        );
+
+        let response = orchestrator.handle_user_message(
+            "add".to_string(),
+            ProviderConfig {
+                provider_name: "bad-arguments-test".to_string(),
+                ..Default::default()
+            },
+            ChatMode::BestEffort,
+        );
+
+        assert!(response.executed_action_events.is_empty());
+        assert!(response
+            .actions_executed
+            .contains(&"denied:math.add:invalid_arguments".to_string()));
+        let denied = &response.proposed_actions[0];
+        assert_eq!(denied.status, "denied");
+        assert!(denied
+            .reason
+            .as_deref()
+            .unwrap_or_default()
+            .starts_with("invalid_arguments: $.a: expected number"));
+    }
 }
@@ -1,4 +1,15 @@
-use ipc::{ChatMode, ToolCall};
+use globset::GlobBuilder;
+use ipc::{ChatMode, ConsentGrant, ToolCall};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use crate::project_config::{pattern_matches, ToolRules};
+use crate::tool_registry::ToolToggles;
+use crate::redaction::Redactor;
+use crate::secret_scan::SecretScanner;
+use crate::tool_registry::SUBTASK_TOOL;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum CapabilityTier {
@@ -7,6 +18,158 @@ pub enum CapabilityTier {
     SystemActions,
 }
 
+impl CapabilityTier {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "ReadOnly" => Ok(Self::ReadOnly),
+            "LocalActions" => Ok(Self::LocalActions),
+            "SystemActions" => Ok(Self::SystemActions),
+            other => Err(format!(
+                "unknown capability tier '{other}' (expected ReadOnly|LocalActions|SystemActions)"
+            )),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::ReadOnly => "ReadOnly",
+            Self::LocalActions => "LocalActions",
+            Self::SystemActions => "SystemActions",
+        }
+    }
+}
+
+/// Built-in safety postures, selected with `policy.profile.set`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PolicyProfile {
+    /// Every non-ReadOnly call needs fresh consent: consent grants don't apply, and MCP tier
+    /// overrides can't drop below LocalActions.
+    Strict,
+    /// ReadOnly tools run in BestEffort mode; everything else needs consent.
+    #[default]
+    Balanced,
+    /// LocalActions also run without consent in BestEffort mode; SystemActions still ask.
+    Permissive,
+}
+
+impl PolicyProfile {
+    pub const ALL: [Self; 3] = [Self::Strict, Self::Balanced, Self::Permissive];
+
+    pub fn parse(value: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|p| p.label() == value)
+            .ok_or_else(|| format!("unknown policy profile '{value}' (expected strict|balanced|permissive)"))
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Strict => "strict",
+            Self::Balanced => "balanced",
+            Self::Permissive => "permissive",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Strict => "consent for every non-ReadOnly call, ignoring consent grants; MCP servers can't be marked ReadOnly",
+            Self::Balanced => "ReadOnly tools run freely in BestEffort mode; all other tools need consent",
+            Self::Permissive => "ReadOnly and LocalActions tools run freely in BestEffort mode; SystemActions need consent",
+        }
+    }
+}
+
+/// Restrictions for tools proxied to one MCP server.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct McpToolPolicy {
+    /// `None` allows every tool the server offers.
+    pub allowed_tools: Option<BTreeSet<String>>,
+    pub tier: CapabilityTier,
+}
+
+impl Default for McpToolPolicy {
+    fn default() -> Self {
+        Self {
+            allowed_tools: None,
+            tier: CapabilityTier::SystemActions,
+        }
+    }
+}
+
+/// Tools whose `path` argument is written; `check_file_write` guards them before they run.
+const FILE_WRITE_TOOLS: &[&str] = &["file.write_text", "file.append_text"];
+
+/// Limits on file-writing tools. Paths are matched relative to the project root, and `**/`
+/// also matches at the root (`**/.env` covers `.env`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileGuards {
+    pub max_write_bytes: Option<u64>,
+    pub max_files_per_request: Option<usize>,
+    pub forbidden_paths: Vec<String>,
+}
+
+impl FileGuards {
+    pub const DEFAULT_FORBIDDEN_PATHS: &'static [&'static str] = &["**/.git/**", "**/.env"];
+
+    fn forbidden_match(&self, path: &str) -> Option<&str> {
+        self.forbidden_paths
+            .iter()
+            .find(|pattern| {
+                GlobBuilder::new(pattern)
+                    .literal_separator(true)
+                    .build()
+                    .is_ok_and(|glob| glob.compile_matcher().is_match(path))
+            })
+            .map(String::as_str)
+    }
+}
+
+impl Default for FileGuards {
+    fn default() -> Self {
+        Self {
+            max_write_bytes: None,
+            max_files_per_request: None,
+            forbidden_paths: Self::DEFAULT_FORBIDDEN_PATHS.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+}
+
+/// Caps on the `result_json` a tool call feeds back to the provider, so one large read or a
+/// chatty MCP tool can't crowd out the rest of the context.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResultLimits {
+    /// `None` leaves results uncapped.
+    pub max_bytes: Option<usize>,
+    /// Keyed by tool name or `prefix*`; exact names win over patterns, `None` lifts the cap.
+    pub per_tool: BTreeMap<String, Option<usize>>,
+}
+
+impl ResultLimits {
+    pub const DEFAULT_MAX_BYTES: usize = 64 * 1024;
+
+    pub fn limit_for(&self, tool_name: &str) -> Option<usize> {
+        self.per_tool
+            .get(tool_name)
+            .or_else(|| {
+                self.per_tool
+                    .iter()
+                    .find(|(pattern, _)| pattern.ends_with('*') && pattern_matches(pattern, tool_name))
+                    .map(|(_, limit)| limit)
+            })
+            .copied()
+            .unwrap_or(self.max_bytes)
+    }
+}
+
+impl Default for ResultLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: Some(Self::DEFAULT_MAX_BYTES),
+            per_tool: BTreeMap::new(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Authorization {
     Allow,
@@ -14,6 +177,15 @@ pub enum Authorization {
     Deny { reason: String },
 }
 
+/// Outcome of `Policy::explain`: the decision plus the rules that produced the tier and decision.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PolicyExplanation {
+    pub tier: CapabilityTier,
+    pub tier_rule: String,
+    pub decision: Authorization,
+    pub rule: String,
+}
+
 #[derive(Clone, Debug)]
 pub struct PolicyContext {
     pub mode: ChatMode,
@@ -23,64 +195,276 @@ pub struct PolicyContext {
 #[derive(Clone, Debug)]
 pub struct Policy {
     pub default_require_confirmation: bool,
+    pub max_tool_rounds: usize,
+    pub tool_timeout: Option<Duration>,
+    pub consent_ttl: Duration,
+    pub profile: PolicyProfile,
+    /// Keyed by MCP server id.
+    pub mcp_servers: HashMap<String, McpToolPolicy>,
+    /// Allow/deny lists from the open project's config.
+    pub project_tools: ToolRules,
+    /// Allow/deny lists from the session's own overrides.
+    pub session_tools: ToolRules,
+    /// Tools switched off with `tools.disable` for every project or this one.
+    pub tool_toggles: ToolToggles,
+    /// Names declared in `command_tools.json`; always `SystemActions`, whatever their prefix.
+    pub command_tools: BTreeSet<String>,
+    /// Standing approvals that apply to this session.
+    pub grants: Vec<ConsentGrant>,
+    pub file_guards: FileGuards,
+    pub result_limits: ResultLimits,
+    /// Root that relative tool paths resolve against, for `file_guards`.
+    pub project_root: Option<PathBuf>,
+    /// Masks secrets in argument previews and the arguments recorded for audits.
+    pub redactor: Redactor,
+    /// Credential detectors run on everything a run is about to send to the provider.
+    pub secret_scan: SecretScanner,
 }
 
 impl Default for Policy {
     fn default() -> Self {
         Self {
             default_require_confirmation: false,
+            max_tool_rounds: Self::DEFAULT_MAX_TOOL_ROUNDS,
+            tool_timeout: None,
+            consent_ttl: Duration::from_secs(Self::DEFAULT_CONSENT_TTL_SECS),
+            profile: PolicyProfile::default(),
+            mcp_servers: HashMap::new(),
+            project_tools: ToolRules::default(),
+            session_tools: ToolRules::default(),
+            tool_toggles: ToolToggles::default(),
+            command_tools: BTreeSet::new(),
+            grants: Vec::new(),
+            file_guards: FileGuards::default(),
+            result_limits: ResultLimits::default(),
+            project_root: None,
+            redactor: Redactor::default(),
+            secret_scan: SecretScanner::default(),
         }
     }
 }
 
 impl Policy {
-    pub fn capability_tier(&self, tool_call: &ToolCall) -> CapabilityTier {
-        if tool_call.name == "desktop.app.activate" {
-            return CapabilityTier::SystemActions;
+    pub const DEFAULT_MAX_TOOL_ROUNDS: usize = 4;
+    pub const DEFAULT_CONSENT_TTL_SECS: u64 = 300;
+
+    /// `(server_id, tool_name)` for `mcp.tool_call` and `mcp.server.<id>.<tool>` aliases.
+    pub fn mcp_target(tool_call: &ToolCall) -> Option<(String, String)> {
+        if tool_call.name == "mcp.tool_call" {
+            let args = serde_json::from_str::<Value>(&tool_call.arguments_json).ok()?;
+            let field = |key: &str| args.get(key).and_then(Value::as_str).map(|v| v.trim().to_string());
+            return Some((field("server_id")?, field("tool_name")?));
         }
-        if tool_call.name == "desktop.app.list" {
-            return CapabilityTier::LocalActions;
+        let (server_id, tool_name) = tool_call.name.strip_prefix("mcp.server.")?.split_once('.')?;
+        Some((server_id.to_string(), tool_name.to_string()))
+    }
+
+    pub fn capability_tier(&self, tool_call: &ToolCall) -> CapabilityTier {
+        self.tier_with_rule(tool_call).0
+    }
+
+    fn tier_with_rule(&self, tool_call: &ToolCall) -> (CapabilityTier, String) {
+        if let Some((server_id, _)) = Self::mcp_target(tool_call) {
+            return match self.mcp_servers.get(&server_id) {
+                Some(p) if p.tier == CapabilityTier::ReadOnly && self.profile == PolicyProfile::Strict => (
+                    CapabilityTier::LocalActions,
+                    "strict profile raises ReadOnly MCP tools to LocalActions".to_string(),
+                ),
+                Some(p) => (p.tier.clone(), format!("policy.json mcp_servers.{server_id}.capability_tier")),
+                None => (CapabilityTier::SystemActions, "MCP tools default to SystemActions".to_string()),
+            };
         }
-        if matches!(
-            tool_call.name.as_str(),
-            "file.write_text" | "file.append_text" | "file.mkdir"
+        let name = tool_call.name.as_str();
+        let (tier, rule) = if self.command_tools.contains(name) {
+            (CapabilityTier::SystemActions, "command_tools.json tools are SystemActions")
+        } else if name == "notify.send" {
+            (CapabilityTier::LocalActions, "notify.send is LocalActions")
+        } else if name.starts_with("browser.") {
+            (CapabilityTier::SystemActions, "browser.* drives a real browser and is SystemActions")
+        } else if name == "db.query" {
+            (CapabilityTier::ReadOnly, "db.query is ReadOnly; databases are opened read-only")
+        } else if name == "code.run" {
+            (CapabilityTier::SystemActions, "code.run executes arbitrary code and is SystemActions")
+        } else if name == "calendar.create_event" {
+            (CapabilityTier::LocalActions, "calendar.create_event writes a calendar and is LocalActions")
+        } else if name == "calendar.list_events" {
+            (CapabilityTier::ReadOnly, "calendar.list_events is ReadOnly")
+        } else if name == "desktop.app.activate" {
+            (CapabilityTier::SystemActions, "desktop.app.activate is SystemActions")
+        } else if name == "desktop.app.list" {
+            (CapabilityTier::LocalActions, "desktop.app.list is LocalActions")
+        } else if matches!(
+            name,
+            "file.write_text" | "file.append_text" | "file.mkdir" | "archive.create" | "archive.extract"
         ) {
-            return CapabilityTier::LocalActions;
-        }
-        if tool_call.name.starts_with("time.")
-            || tool_call.name.starts_with("math.")
-            || tool_call.name.starts_with("text.")
-            || tool_call.name.starts_with("file.")
-            || tool_call.name == "echo"
+            (CapabilityTier::LocalActions, "file/archive writes are LocalActions")
+        } else if name == SUBTASK_TOOL {
+            (CapabilityTier::ReadOnly, "task.spawn is ReadOnly; its nested calls are authorized one by one")
+        } else if name.starts_with("time.")
+            || name.starts_with("math.")
+            || name.starts_with("text.")
+            || name.starts_with("file.")
+            || name == "echo"
         {
-            CapabilityTier::ReadOnly
-        } else if tool_call.name.starts_with("desktop.") || tool_call.name.starts_with("android.") || tool_call.name.starts_with("ios.") {
-            CapabilityTier::LocalActions
+            (CapabilityTier::ReadOnly, "time.*, math.*, text.*, file.* reads and echo are ReadOnly")
+        } else if name.starts_with("desktop.") || name.starts_with("android.") || name.starts_with("ios.") {
+            (CapabilityTier::LocalActions, "desktop.*, android.* and ios.* are LocalActions")
         } else {
-            CapabilityTier::SystemActions
+            (CapabilityTier::SystemActions, "other tools default to SystemActions")
+        };
+        (tier, rule.to_string())
+    }
+
+    /// Applies `file_guards` to a file-writing call. `touched` collects the paths written so far
+    /// in the current request and gains this call's path when it passes.
+    pub fn check_file_write(&self, tool_call: &ToolCall, touched: &mut BTreeSet<String>) -> Result<(), String> {
+        if !FILE_WRITE_TOOLS.contains(&tool_call.name.as_str()) {
+            return Ok(());
         }
+        let args = serde_json::from_str::<Value>(&tool_call.arguments_json).unwrap_or(Value::Null);
+        let Some(raw_path) = args.get("path").and_then(Value::as_str) else {
+            return Ok(());
+        };
+        let path = self.guard_path(raw_path);
+        if let Some(pattern) = self.file_guards.forbidden_match(&path) {
+            return Err(format!("file_guard: path '{path}' matches forbidden pattern '{pattern}'"));
+        }
+        let size = ["content", "patch"]
+            .iter()
+            .find_map(|key| args.get(key).and_then(Value::as_str))
+            .map(|text| text.len() as u64)
+            .unwrap_or(0);
+        if let Some(max) = self.file_guards.max_write_bytes.filter(|max| size > *max) {
+            return Err(format!("file_guard: writing {size} bytes to '{path}' exceeds max_write_bytes {max}"));
+        }
+        if let Some(max) = self.file_guards.max_files_per_request {
+            if !touched.contains(&path) && touched.len() >= max {
+                return Err(format!(
+                    "file_guard: writing '{path}' would touch more than max_files_per_request {max} files"
+                ));
+            }
+        }
+        touched.insert(path);
+        Ok(())
+    }
+
+    /// Project-relative, lexically normalized `/` path, so `a/../.git/x` can't dodge a pattern.
+    fn guard_path(&self, raw: &str) -> String {
+        let path = Path::new(raw);
+        let path = self
+            .project_root
+            .as_deref()
+            .and_then(|root| path.strip_prefix(root).ok())
+            .unwrap_or(path);
+        let mut parts: Vec<String> = Vec::new();
+        for component in path.components() {
+            match component {
+                Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+                Component::ParentDir => {
+                    parts.pop();
+                }
+                Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+            }
+        }
+        parts.join("/")
     }
 
     pub fn authorize(&self, tool_call: &ToolCall, context: &PolicyContext) -> Authorization {
+        self.explain(tool_call, context).decision
+    }
+
+    /// `authorize` with the reasoning attached, for `policy.explain`.
+    pub fn explain(&self, tool_call: &ToolCall, context: &PolicyContext) -> PolicyExplanation {
+        let (tier, tier_rule) = self.tier_with_rule(tool_call);
+        let deny = |reason: String, rule: &str| PolicyExplanation {
+            tier: tier.clone(),
+            tier_rule: tier_rule.clone(),
+            decision: Authorization::Deny { reason },
+            rule: rule.to_string(),
+        };
         if tool_call.name.starts_with("internal.") {
-            return Authorization::Deny {
-                reason: "internal.* tools are reserved".to_string(),
-            };
+            return deny("internal.* tools are reserved".to_string(), "internal.* tools are always denied");
+        }
+        if !self.project_tools.permits(&tool_call.name) {
+            return deny(
+                format!("tool '{}' is disabled by the project config", tool_call.name),
+                "project .cmnd-n-ctrl/config tools.allow/tools.deny",
+            );
+        }
+        if !self.session_tools.permits(&tool_call.name) {
+            return deny(
+                format!("tool '{}' is disabled for this session", tool_call.name),
+                "session policy allow_tools/deny_tools",
+            );
+        }
+        if !self.tool_toggles.permits(&tool_call.name) {
+            return deny(
+                format!("tool '{}' is disabled", tool_call.name),
+                "tools.disable setting (global or for this project)",
+            );
+        }
+        if let Some((server_id, tool_name)) = Self::mcp_target(tool_call) {
+            let allowed = self
+                .mcp_servers
+                .get(&server_id)
+                .and_then(|p| p.allowed_tools.as_ref())
+                .map(|tools| tools.contains(&tool_name))
+                .unwrap_or(true);
+            if !allowed {
+                return deny(
+                    format!("mcp tool '{tool_name}' is not allowed for server '{server_id}'"),
+                    &format!("policy.json mcp_servers.{server_id}.allowed_tools"),
+                );
+            }
         }
 
-        let require_confirmation = match self.capability_tier(tool_call) {
-            CapabilityTier::ReadOnly => {
-                self.default_require_confirmation || matches!(context.mode, ChatMode::RequireConfirmation)
+        let relaxed = !self.default_require_confirmation && matches!(context.mode, ChatMode::BestEffort);
+        let profile = self.profile.label();
+        let (require_confirmation, rule) = match tier {
+            CapabilityTier::ReadOnly if self.default_require_confirmation => {
+                (true, "ReadOnly tools need consent because default_require_confirmation is on".to_string())
+            }
+            CapabilityTier::ReadOnly if matches!(context.mode, ChatMode::RequireConfirmation) => {
+                (true, "ReadOnly tools need consent in RequireConfirmation mode".to_string())
+            }
+            CapabilityTier::ReadOnly => (false, "ReadOnly tools run without consent in BestEffort mode".to_string()),
+            CapabilityTier::LocalActions if relaxed && self.profile == PolicyProfile::Permissive => (
+                false,
+                "permissive profile: LocalActions tools run without consent in BestEffort mode".to_string(),
+            ),
+            CapabilityTier::LocalActions | CapabilityTier::SystemActions => {
+                (true, format!("{profile} profile: {} tools need consent", tier.label()))
             }
-            CapabilityTier::LocalActions | CapabilityTier::SystemActions => true,
         };
 
-        if require_confirmation && !context.user_confirmed {
+        let mcp_target = Self::mcp_target(tool_call);
+        let grant = self
+            .grants
+            .iter()
+            .find(|g| g.tool_name == tool_call.name && (mcp_target.is_none() || g.mcp_target == mcp_target));
+        let (require_confirmation, rule) = match grant {
+            Some(_) if require_confirmation && self.profile == PolicyProfile::Strict => (
+                true,
+                format!("strict profile: {} tools need consent on every call; consent grants don't apply", tier.label()),
+            ),
+            Some(grant) if require_confirmation => (
+                false,
+                format!("consent grant {} ({} scope) for {}", grant.grant_id, grant.scope, grant.tool_name),
+            ),
+            _ => (require_confirmation, rule),
+        };
+        let decision = if require_confirmation && !context.user_confirmed {
             Authorization::RequireConfirmation {
                 reason: format!("Tool '{}' requires explicit user consent", tool_call.name),
             }
         } else {
             Authorization::Allow
+        };
+        PolicyExplanation {
+            tier,
+            tier_rule,
+            decision,
+            rule,
         }
     }
 }
@@ -144,7 +528,7 @@ mod tests {
     #[test]
     fn file_append_and_mkdir_require_confirmation() {
         let policy = Policy::default();
-        for name in ["file.append_text", "file.mkdir"] {
+        for name in ["file.append_text", "file.mkdir", "archive.create", "archive.extract"] {
             assert!(matches!(
                 policy.capability_tier(&call(name)),
                 CapabilityTier::LocalActions
@@ -160,6 +544,219 @@ mod tests {
         }
     }
 
+    #[test]
+    fn mcp_server_policy_restricts_tools_and_maps_tier() {
+        let mut policy = Policy::default();
+        let context = PolicyContext {
+            mode: ChatMode::BestEffort,
+            user_confirmed: false,
+        };
+        let alias = call("mcp.server.mcp-1.search");
+        assert_eq!(policy.capability_tier(&alias), CapabilityTier::SystemActions);
+        assert!(matches!(policy.authorize(&alias, &context), Authorization::RequireConfirmation { .. }));
+
+        policy.mcp_servers.insert(
+            "mcp-1".to_string(),
+            McpToolPolicy {
+                allowed_tools: Some(BTreeSet::from(["search".to_string()])),
+                tier: CapabilityTier::ReadOnly,
+            },
+        );
+        assert!(matches!(policy.authorize(&alias, &context), Authorization::Allow));
+        assert!(matches!(
+            policy.authorize(&call("mcp.server.mcp-1.delete"), &context),
+            Authorization::Deny { .. }
+        ));
+        let generic = ToolCall {
+            tool_call_id: None,
+            name: "mcp.tool_call".to_string(),
+            arguments_json: r#"{"server_id":"mcp-1","tool_name":"delete"}"#.to_string(),
+        };
+        assert!(matches!(policy.authorize(&generic, &context), Authorization::Deny { .. }));
+        assert!(CapabilityTier::parse("Root").is_err());
+    }
+
+    #[test]
+    fn browser_and_code_tools_are_system_actions_and_db_query_is_read_only() {
+        let policy = Policy::default();
+        for name in ["browser.navigate", "browser.read_text", "browser.close", "code.run"] {
+            assert_eq!(policy.capability_tier(&call(name)), CapabilityTier::SystemActions, "{name}");
+        }
+        assert_eq!(policy.capability_tier(&call("db.query")), CapabilityTier::ReadOnly);
+        assert_eq!(policy.capability_tier(&call("notify.send")), CapabilityTier::LocalActions);
+        assert_eq!(policy.capability_tier(&call("calendar.list_events")), CapabilityTier::ReadOnly);
+        assert_eq!(policy.capability_tier(&call("calendar.create_event")), CapabilityTier::LocalActions);
+    }
+
+    #[test]
+    fn explain_names_the_deciding_rule() {
+        let mut policy = Policy::default();
+        let context = PolicyContext {
+            mode: ChatMode::RequireConfirmation,
+            user_confirmed: false,
+        };
+        let read = policy.explain(&call("file.read_text"), &context);
+        assert_eq!(read.tier, CapabilityTier::ReadOnly);
+        assert!(matches!(read.decision, Authorization::RequireConfirmation { .. }));
+        assert!(read.rule.contains("RequireConfirmation mode"));
+
+        policy.project_tools.deny = vec!["file.*".to_string()];
+        let denied = policy.explain(&call("file.read_text"), &context);
+        assert!(matches!(denied.decision, Authorization::Deny { .. }));
+        assert!(denied.rule.contains("tools.deny"));
+        assert_eq!(
+            policy.explain(&call("mcp.server.mcp-9.run"), &context).tier_rule,
+            "MCP tools default to SystemActions"
+        );
+    }
+
+    #[test]
+    fn file_guards_check_paths_sizes_and_file_counts() {
+        let mut policy = Policy {
+            project_root: Some(PathBuf::from("/work")),
+            ..Policy::default()
+        };
+        policy.file_guards.max_write_bytes = Some(8);
+        policy.file_guards.max_files_per_request = Some(2);
+        let write = |path: &str, content: &str| ToolCall {
+            tool_call_id: None,
+            name: "file.write_text".to_string(),
+            arguments_json: serde_json::json!({ "path": path, "content": content }).to_string(),
+        };
+        let mut touched = BTreeSet::new();
+        for path in [".env", "src/../.git/config", "/work/app/.env"] {
+            let err = policy.check_file_write(&write(path, "x"), &mut touched).expect_err(path);
+            assert!(err.contains("forbidden pattern"), "{err}");
+        }
+        assert!(policy.check_file_write(&write("a.txt", "too many bytes"), &mut touched).is_err());
+        assert!(policy.check_file_write(&write("a.txt", "ok"), &mut touched).is_ok());
+        assert!(policy.check_file_write(&write("./a.txt", "again"), &mut touched).is_ok());
+        assert!(policy.check_file_write(&write("b.txt", "ok"), &mut touched).is_ok());
+        let err = policy.check_file_write(&write("c.txt", "ok"), &mut touched).expect_err("third file");
+        assert!(err.contains("max_files_per_request 2"));
+        assert!(policy.check_file_write(&call("file.read_text"), &mut touched).is_ok());
+    }
+
+    #[test]
+    fn profiles_shift_which_tiers_need_consent() {
+        let best_effort = PolicyContext {
+            mode: ChatMode::BestEffort,
+            user_confirmed: false,
+        };
+        let mut policy = Policy {
+            profile: PolicyProfile::Permissive,
+            ..Policy::default()
+        };
+        assert!(matches!(policy.authorize(&call("file.mkdir"), &best_effort), Authorization::Allow));
+        assert!(matches!(
+            policy.authorize(&call("shell.run"), &best_effort),
+            Authorization::RequireConfirmation { .. }
+        ));
+        let confirm = PolicyContext {
+            mode: ChatMode::RequireConfirmation,
+            user_confirmed: false,
+        };
+        assert!(matches!(
+            policy.authorize(&call("file.mkdir"), &confirm),
+            Authorization::RequireConfirmation { .. }
+        ));
+
+        policy.profile = PolicyProfile::Strict;
+        policy.mcp_servers.insert(
+            "mcp-1".to_string(),
+            McpToolPolicy {
+                allowed_tools: None,
+                tier: CapabilityTier::ReadOnly,
+            },
+        );
+        assert!(matches!(
+            policy.authorize(&call("mcp.server.mcp-1.read"), &best_effort),
+            Authorization::RequireConfirmation { .. }
+        ));
+        assert_eq!(PolicyProfile::parse("balanced"), Ok(PolicyProfile::Balanced));
+        assert!(PolicyProfile::parse("yolo").is_err());
+    }
+
+    #[test]
+    fn mcp_grants_cover_only_their_server_and_tool() {
+        let best_effort = PolicyContext {
+            mode: ChatMode::BestEffort,
+            user_confirmed: false,
+        };
+        let mcp_call = |server_id: &str, tool_name: &str| ToolCall {
+            tool_call_id: None,
+            name: "mcp.tool_call".to_string(),
+            arguments_json: serde_json::json!({ "server_id": server_id, "tool_name": tool_name, "arguments": {} })
+                .to_string(),
+        };
+        let grant = |mcp_target: Option<(&str, &str)>| ConsentGrant {
+            grant_id: "grant-000001".to_string(),
+            tool_name: "mcp.tool_call".to_string(),
+            mcp_target: mcp_target.map(|(server, tool)| (server.to_string(), tool.to_string())),
+            scope: "always".to_string(),
+            session_id: None,
+            consent_id: "consent-1".to_string(),
+            granted_at_unix_seconds: 0,
+        };
+        let mut policy = Policy {
+            grants: vec![grant(Some(("server-a", "search")))],
+            ..Policy::default()
+        };
+        assert!(matches!(policy.authorize(&mcp_call("server-a", "search"), &best_effort), Authorization::Allow));
+        for other in [mcp_call("server-b", "search"), mcp_call("server-a", "delete")] {
+            assert!(matches!(
+                policy.authorize(&other, &best_effort),
+                Authorization::RequireConfirmation { .. }
+            ));
+        }
+
+        policy.grants = vec![grant(None)];
+        assert!(matches!(
+            policy.authorize(&mcp_call("server-a", "search"), &best_effort),
+            Authorization::RequireConfirmation { .. }
+        ));
+    }
+
+    #[test]
+    fn strict_profile_ignores_consent_grants_that_balanced_honors() {
+        let best_effort = PolicyContext {
+            mode: ChatMode::BestEffort,
+            user_confirmed: false,
+        };
+        let mut policy = Policy {
+            grants: vec![ConsentGrant {
+                grant_id: "grant-000001".to_string(),
+                tool_name: "file.write_text".to_string(),
+                mcp_target: None,
+                scope: "always".to_string(),
+                session_id: None,
+                consent_id: "consent-1".to_string(),
+                granted_at_unix_seconds: 0,
+            }],
+            ..Policy::default()
+        };
+        assert!(matches!(policy.authorize(&call("file.write_text"), &best_effort), Authorization::Allow));
+
+        policy.profile = PolicyProfile::Strict;
+        let explanation = policy.explain(&call("file.write_text"), &best_effort);
+        assert!(matches!(explanation.decision, Authorization::RequireConfirmation { .. }));
+        assert!(explanation.rule.starts_with("strict profile"), "{}", explanation.rule);
+    }
+
+    #[test]
+    fn result_limits_prefer_exact_tool_names_over_patterns() {
+        let mut limits = ResultLimits::default();
+        assert_eq!(limits.limit_for("file.read_csv"), Some(ResultLimits::DEFAULT_MAX_BYTES));
+        limits.per_tool.insert("file.*".to_string(), Some(1024));
+        limits.per_tool.insert("file.read_csv".to_string(), Some(4096));
+        limits.per_tool.insert("mcp.server.logs.*".to_string(), None);
+        assert_eq!(limits.limit_for("file.read_csv"), Some(4096));
+        assert_eq!(limits.limit_for("file.read_text"), Some(1024));
+        assert_eq!(limits.limit_for("mcp.server.logs.tail"), None);
+        limits.max_bytes = None;
+        assert_eq!(limits.limit_for("echo"), None);
+    }
+
     #[test]
     fn authorize_denies_internal_tools() {
         let policy = Policy::default();
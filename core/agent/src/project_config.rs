@@ -0,0 +1,132 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+pub const PROJECT_CONFIG_PATH: &str = ".cmnd-n-ctrl/config";
+
+/// Project-local overrides read from `<project>/.cmnd-n-ctrl/config` (JSON). Explicit request
+/// fields still win; the file sits between them and the global settings.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    #[serde(default)]
+    pub tools: ToolRules,
+    #[serde(default)]
+    pub consent: ConsentDefaults,
+    #[serde(default)]
+    pub context: ContextSettings,
+}
+
+/// Tool name patterns; a trailing `*` matches a prefix (`file.*`). Deny beats allow, and an
+/// unset `allow` permits everything not denied.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ToolRules {
+    #[serde(default)]
+    pub allow: Option<Vec<String>>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConsentDefaults {
+    /// Ask before read-only tools too, as in `RequireConfirmation` mode.
+    #[serde(default)]
+    pub require_confirmation: Option<bool>,
+    #[serde(default)]
+    pub ttl_secs: Option<u64>,
+}
+
+/// Retrieval-augmented context (see `context`). Off unless `enabled`; `provider`/`model` pick the
+/// embedding provider, defaulting to the active provider and its configured embedding model.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContextSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub top_k: Option<usize>,
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+impl ProjectConfig {
+    pub fn path_in(root: &Path) -> PathBuf {
+        root.join(PROJECT_CONFIG_PATH)
+    }
+
+    /// `Ok(None)` when the project has no config file.
+    pub fn load(root: &Path) -> Result<Option<Self>, String> {
+        let path = Self::path_in(root);
+        let raw = match fs::read_to_string(&path) {
+            Ok(raw) => raw,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(format!("failed to read {}: {err}", path.display())),
+        };
+        serde_json::from_str(&raw)
+            .map(Some)
+            .map_err(|err| format!("invalid {}: {err}", path.display()))
+    }
+}
+
+impl ToolRules {
+    pub fn is_unrestricted(&self) -> bool {
+        self.allow.is_none() && self.deny.is_empty()
+    }
+
+    pub fn permits(&self, tool_name: &str) -> bool {
+        if self.deny.iter().any(|p| pattern_matches(p, tool_name)) {
+            return false;
+        }
+        self.allow
+            .as_ref()
+            .is_none_or(|allow| allow.iter().any(|p| pattern_matches(p, tool_name)))
+    }
+}
+
+/// A tool name, or a prefix ending in `*` such as `file.*`.
+pub(crate) fn pattern_matches(pattern: &str, tool_name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => tool_name.starts_with(prefix),
+        None => pattern == tool_name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn loads_config_and_applies_tool_rules() {
+        let dir = tempdir().expect("tempdir");
+        assert_eq!(ProjectConfig::load(dir.path()), Ok(None));
+
+        fs::create_dir_all(dir.path().join(".cmnd-n-ctrl")).expect("mkdir");
+        fs::write(
+            ProjectConfig::path_in(dir.path()),
+            r#"{"model":"gpt-4o-mini","tools":{"allow":["file.*","echo"],"deny":["file.write_text"]}}"#,
+        )
+        .expect("write config");
+        let config = ProjectConfig::load(dir.path()).expect("load").expect("config");
+        assert_eq!(config.model.as_deref(), Some("gpt-4o-mini"));
+        assert!(config.tools.permits("file.read_text"));
+        assert!(config.tools.permits("echo"));
+        assert!(!config.tools.permits("file.write_text"));
+        assert!(!config.tools.permits("desktop.app.list"));
+
+        fs::write(ProjectConfig::path_in(dir.path()), r#"{"modle":"typo"}"#).expect("write config");
+        assert!(ProjectConfig::load(dir.path()).is_err());
+    }
+}
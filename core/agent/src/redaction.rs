@@ -0,0 +1,116 @@
+use regex::Regex;
+use serde_json::Value;
+use std::path::Path;
+use storage::{FileStorage, RedactionState, Storage};
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Keys masked in argument previews, audits, provider configs and logs whatever the policy says.
+pub const DEFAULT_KEYS: &[&str] = &["api_key", "apikey", "token", "authorization", "password", "secret", "content"];
+
+/// Masks secrets in JSON and free text: values under sensitive keys are replaced wholesale,
+/// and substrings matching a configured pattern are replaced wherever they appear.
+#[derive(Clone, Debug, Default)]
+pub struct Redactor {
+    /// Lowercased; checked after `DEFAULT_KEYS`.
+    extra_keys: Vec<String>,
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    /// Invalid patterns are skipped with a warning, like invalid `forbidden_paths` globs.
+    pub fn from_state(state: &RedactionState) -> Self {
+        let patterns = state
+            .value_patterns
+            .iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(err) => {
+                    tracing::warn!(%pattern, %err, "ignoring invalid redaction value pattern");
+                    None
+                }
+            })
+            .collect();
+        Self {
+            extra_keys: state.keys.iter().map(|key| key.to_ascii_lowercase()).collect(),
+            patterns,
+        }
+    }
+
+    /// Reads the `redaction` section of `policy.json` in `storage_dir`, for code that runs
+    /// before an `AgentService` exists (the CLI log writer). Unreadable policy keeps the defaults.
+    pub fn load(storage_dir: &Path) -> Self {
+        FileStorage::new_in_dir(storage_dir)
+            .and_then(|store| store.read_policy_state())
+            .map(|state| Self::from_state(&state.redaction))
+            .unwrap_or_default()
+    }
+
+    pub fn is_sensitive_key(&self, key: &str) -> bool {
+        let key = key.to_ascii_lowercase();
+        DEFAULT_KEYS.contains(&key.as_str()) || self.extra_keys.contains(&key)
+    }
+
+    pub fn redact_text(&self, text: &str) -> String {
+        self.patterns
+            .iter()
+            .fold(text.to_string(), |text, regex| regex.replace_all(&text, REDACTED).into_owned())
+    }
+
+    pub fn redact_json(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if self.is_sensitive_key(key) {
+                        *value = Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact_json(value);
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    self.redact_json(item);
+                }
+            }
+            Value::String(text) if !self.patterns.is_empty() => *text = self.redact_text(text),
+            _ => {}
+        }
+    }
+
+    /// `redact_json` for serialized JSON; text that doesn't parse only gets the value patterns.
+    pub fn redact_json_str(&self, json: &str) -> String {
+        let Ok(mut value) = serde_json::from_str::<Value>(json) else {
+            return self.redact_text(json);
+        };
+        self.redact_json(&mut value);
+        serde_json::to_string(&value).unwrap_or_else(|_| self.redact_text(json))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configured_keys_and_patterns_extend_the_defaults() {
+        let redactor = Redactor::from_state(&RedactionState {
+            keys: vec!["Session_Cookie".to_string()],
+            value_patterns: vec!["sk-[A-Za-z0-9]+".to_string(), "(unclosed".to_string()],
+        });
+        let redacted = redactor.redact_json_str(
+            r#"{"password":"p","session_cookie":"c","query":"use sk-abc123 now","nested":[{"note":"sk-XYZ"}]}"#,
+        );
+        let value: Value = serde_json::from_str(&redacted).expect("json");
+        assert_eq!(value["password"], REDACTED);
+        assert_eq!(value["session_cookie"], REDACTED);
+        assert_eq!(value["query"], "use [REDACTED] now");
+        assert_eq!(value["nested"][0]["note"], REDACTED);
+        assert_eq!(redactor.redact_text("key=sk-abc, not json"), "key=[REDACTED], not json");
+
+        let defaults = Redactor::default();
+        assert!(defaults.is_sensitive_key("API_KEY"));
+        assert!(!defaults.is_sensitive_key("session_cookie"));
+        assert_eq!(defaults.redact_text("sk-abc"), "sk-abc");
+    }
+}
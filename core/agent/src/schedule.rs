@@ -0,0 +1,189 @@
+//! Cron-like schedules for `tasks.create`, evaluated in UTC.
+//!
+//! Accepts five fields (`minute hour day-of-month month day-of-week`, each `*`, `n`, `a-b`,
+//! a comma list of those and an optional `/step`), the `@hourly`/`@daily`/`@weekly`/`@monthly`/
+//! `@yearly` shorthands, and `@every <n>m|h|d` intervals. As in cron, when both day fields are
+//! restricted a day matching either one fires.
+
+use actions::calendar::civil_from_days;
+
+const SECS_PER_MINUTE: u64 = 60;
+const SECS_PER_DAY: u64 = 86_400;
+/// Far enough to find `0 0 29 2 *` after any date.
+const SEARCH_DAYS: u64 = 366 * 8;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Schedule {
+    Cron(CronFields),
+    Every { secs: u64 },
+}
+
+/// One bit per allowed value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CronFields {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl Schedule {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        let expanded = match spec {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            _ => spec,
+        };
+        if let Some(interval) = expanded.strip_prefix("@every") {
+            return parse_interval(interval.trim()).map(|secs| Self::Every { secs });
+        }
+        let fields = expanded.split_whitespace().collect::<Vec<_>>();
+        let [minute, hour, dom, month, dow] = fields[..] else {
+            return Err(format!(
+                "schedule '{spec}' needs 5 fields (minute hour day-of-month month day-of-week) or an @ shorthand"
+            ));
+        };
+        let mut days_of_week = parse_field(dow, 0, 7, "day-of-week")?;
+        // Both 0 and 7 mean Sunday.
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+        Ok(Self::Cron(CronFields {
+            minutes: parse_field(minute, 0, 59, "minute")?,
+            hours: parse_field(hour, 0, 23, "hour")?,
+            days_of_month: parse_field(dom, 1, 31, "day-of-month")?,
+            months: parse_field(month, 1, 12, "month")?,
+            days_of_week,
+            any_day_of_month: dom == "*",
+            any_day_of_week: dow == "*",
+        }))
+    }
+
+    /// First firing time strictly after `after` (unix seconds); `None` if the schedule can
+    /// never fire, e.g. `0 0 31 2 *`.
+    pub fn next_after(&self, after: u64) -> Option<u64> {
+        let fields = match self {
+            Self::Every { secs } => return after.checked_add(*secs),
+            Self::Cron(fields) => fields,
+        };
+        let start = (after / SECS_PER_MINUTE + 1) * SECS_PER_MINUTE;
+        let first_day = start / SECS_PER_DAY;
+        let mut first_minute = (start % SECS_PER_DAY) / SECS_PER_MINUTE;
+        for day in first_day..first_day + SEARCH_DAYS {
+            if fields.day_matches(day) {
+                let hit = (first_minute..24 * 60)
+                    .find(|m| bit(fields.hours, m / 60) && bit(fields.minutes, m % 60));
+                if let Some(minute) = hit {
+                    return Some(day * SECS_PER_DAY + minute * SECS_PER_MINUTE);
+                }
+            }
+            first_minute = 0;
+        }
+        None
+    }
+}
+
+impl CronFields {
+    fn day_matches(&self, days_since_epoch: u64) -> bool {
+        let (_, month, day) = civil_from_days(days_since_epoch as i64);
+        let (month, day) = (month as u64, day as u64);
+        if !bit(self.months, month) {
+            return false;
+        }
+        // 1970-01-01 was a Thursday.
+        let weekday = (days_since_epoch + 4) % 7;
+        let dom = bit(self.days_of_month, day);
+        let dow = bit(self.days_of_week, weekday);
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (true, true) => true,
+            (false, true) => dom,
+            (true, false) => dow,
+            (false, false) => dom || dow,
+        }
+    }
+}
+
+fn bit(mask: u64, value: u64) -> bool {
+    mask & (1 << value) != 0
+}
+
+fn parse_field(field: &str, min: u64, max: u64, name: &str) -> Result<u64, String> {
+    let invalid = || format!("invalid {name} field '{field}' (allowed {min}-{max})");
+    let mut mask = 0u64;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u64>().ok().filter(|s| *s > 0).ok_or_else(invalid)?),
+            None => (item, 1),
+        };
+        let (low, high) = if range == "*" {
+            (min, max)
+        } else if let Some((low, high)) = range.split_once('-') {
+            (low.parse().map_err(|_| invalid())?, high.parse().map_err(|_| invalid())?)
+        } else {
+            let value = range.parse().map_err(|_| invalid())?;
+            // `5/15` means "from 5, every 15" as in common cron implementations.
+            (value, if step > 1 { max } else { value })
+        };
+        if low < min || high > max || low > high {
+            return Err(invalid());
+        }
+        for value in (low..=high).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_interval(text: &str) -> Result<u64, String> {
+    let invalid = || format!("invalid interval '@every {text}' (use e.g. 15m, 2h or 1d)");
+    let unit = text.chars().last().ok_or_else(invalid)?;
+    let count = text[..text.len() - unit.len_utf8()].trim().parse::<u64>().map_err(|_| invalid())?;
+    let secs = match unit {
+        'm' => count.checked_mul(60),
+        'h' => count.checked_mul(3_600),
+        'd' => count.checked_mul(SECS_PER_DAY),
+        _ => None,
+    };
+    secs.filter(|s| *s > 0).ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-02-28T23:30:00Z, a Wednesday.
+    const BASE: u64 = 1_709_163_000;
+
+    #[test]
+    fn cron_fields_find_the_next_matching_minute() {
+        let every_15 = Schedule::parse("*/15 * * * *").expect("parse");
+        assert_eq!(every_15.next_after(BASE), Some(BASE + 15 * 60));
+
+        // Leap day, then the 1st of March.
+        let daily_9 = Schedule::parse("0 9 * * *").expect("parse");
+        assert_eq!(daily_9.next_after(BASE), Some(BASE + 30 * 60 + 9 * 3_600));
+        assert_eq!(civil_from_days((BASE / SECS_PER_DAY + 1) as i64), (2024, 2, 29));
+
+        let monday = Schedule::parse("30 8 * * 1").expect("parse");
+        assert_eq!(monday.next_after(BASE), Some(BASE + 30 * 60 + 4 * SECS_PER_DAY + 8 * 3_600 + 30 * 60));
+        assert_eq!(Schedule::parse("0 0 * * 7"), Schedule::parse("0 0 * * 0"));
+        assert_eq!(Schedule::parse("@weekly"), Schedule::parse("0 0 * * 0"));
+
+        assert_eq!(Schedule::parse("0 0 31 2 *").expect("parse").next_after(BASE), None);
+        assert_eq!(Schedule::parse("@every 2h").expect("parse").next_after(BASE), Some(BASE + 7_200));
+    }
+
+    #[test]
+    fn rejects_malformed_specs() {
+        for spec in ["* * * *", "60 * * * *", "*/0 * * * *", "5-1 * * * *", "@every 10s", "@every m", "@often"] {
+            assert!(Schedule::parse(spec).is_err(), "{spec}");
+        }
+    }
+}
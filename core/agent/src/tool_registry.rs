@@ -1,112 +1,300 @@
+use crate::project_config::{pattern_matches, ToolRules};
 use ipc::Tool;
+use storage::ToolSettings;
+
+/// Runs a nested orchestration instead of an action backend call (see `Orchestrator`).
+pub const SUBTASK_TOOL: &str = "task.spawn";
 
 #[derive(Clone, Debug, Default)]
 pub struct ToolRegistry {
     tools: Vec<Tool>,
+    /// Every rule set must permit a tool for `list`, `has_tool` and `get` to see it.
+    rules: Vec<ToolRules>,
+    toggles: ToolToggles,
+}
+
+/// The `tools.enable`/`tools.disable` settings that apply in one project. A project's own
+/// choice wins over the global one, and within a project `disabled` wins over `enabled`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ToolToggles {
+    pub disabled: Vec<String>,
+    pub project_enabled: Vec<String>,
+    pub project_disabled: Vec<String>,
+}
+
+impl ToolToggles {
+    pub fn from_settings(settings: &ToolSettings, project_path: Option<&str>) -> Self {
+        let project = project_path.and_then(|path| settings.projects.get(path)).cloned().unwrap_or_default();
+        Self {
+            disabled: settings.disabled.clone(),
+            project_enabled: project.enabled,
+            project_disabled: project.disabled,
+        }
+    }
+
+    pub fn permits(&self, tool_name: &str) -> bool {
+        let matches = |patterns: &[String]| patterns.iter().any(|p| pattern_matches(p, tool_name));
+        if matches(&self.project_disabled) {
+            return false;
+        }
+        matches(&self.project_enabled) || !matches(&self.disabled)
+    }
+}
+
+/// `browser.*`, offered when built with the `browser` feature.
+fn browser_tools() -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "browser.navigate".to_string(),
+            description: "Open an http(s) URL in the WebDriver-controlled browser; returns the final URL and title (consent required)".to_string(),
+            input_json_schema: "{\"type\":\"object\",\"properties\":{\"url\":{\"type\":\"string\"}},\"required\":[\"url\"],\"additionalProperties\":false}".to_string(),
+        },
+        Tool {
+            name: "browser.read_text".to_string(),
+            description: "Read the visible text of the current page, or of the first element matching a CSS selector (consent required)".to_string(),
+            input_json_schema: "{\"type\":\"object\",\"properties\":{\"selector\":{\"type\":\"string\"}},\"additionalProperties\":false}".to_string(),
+        },
+        Tool {
+            name: "browser.click".to_string(),
+            description: "Click the first element matching a CSS selector on the current page (consent required)".to_string(),
+            input_json_schema: "{\"type\":\"object\",\"properties\":{\"selector\":{\"type\":\"string\"}},\"required\":[\"selector\"],\"additionalProperties\":false}".to_string(),
+        },
+        Tool {
+            name: "browser.screenshot".to_string(),
+            description: "Capture a PNG of the current page, kept as evidence and optionally saved to a project path (consent required)".to_string(),
+            input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"}},\"additionalProperties\":false}".to_string(),
+        },
+        Tool {
+            name: "browser.close".to_string(),
+            description: "End the browser session; the next browser call starts a fresh one".to_string(),
+            input_json_schema: "{\"type\":\"object\",\"properties\":{},\"additionalProperties\":false}".to_string(),
+        },
+    ]
 }
 
 impl ToolRegistry {
     pub fn new_default() -> Self {
+        let mut tools = vec![
+            Tool {
+                name: "time.now".to_string(),
+                description: "Return the current UTC timestamp from the local runtime".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{},\"additionalProperties\":false}".to_string(),
+            },
+            Tool {
+                name: "echo".to_string(),
+                description: "Echo a payload for testing tool orchestration".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"input\":{\"type\":\"string\"}},\"required\":[\"input\"]}".to_string(),
+            },
+            Tool {
+                name: "text.uppercase".to_string(),
+                description: "Uppercase a provided string locally".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"text\":{\"type\":\"string\"}},\"required\":[\"text\"]}".to_string(),
+            },
+            Tool {
+                name: "math.add".to_string(),
+                description: "Add two numbers locally".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"a\":{\"type\":\"number\"},\"b\":{\"type\":\"number\"}},\"required\":[\"a\",\"b\"]}".to_string(),
+            },
+            Tool {
+                name: "file.list".to_string(),
+                description: "List files in the current project (read-only)".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"}},\"additionalProperties\":false}".to_string(),
+            },
+            Tool {
+                name: "file.read_text".to_string(),
+                description: "Read a text file from the current project (read-only)".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"}},\"required\":[\"path\"]}".to_string(),
+            },
+            Tool {
+                name: "file.read_bytes".to_string(),
+                description: "Read a byte range from a project file as base64 with a detected MIME type (read-only, max 256 KB)".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"},\"offset\":{\"type\":\"integer\",\"minimum\":0},\"length\":{\"type\":\"integer\",\"minimum\":1}},\"required\":[\"path\"],\"additionalProperties\":false}".to_string(),
+            },
+            Tool {
+                name: "file.read_csv".to_string(),
+                description: "Read a CSV file from the current project (read-only, preview rows)".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"},\"limit\":{\"type\":\"integer\",\"minimum\":1}},\"required\":[\"path\"]}".to_string(),
+            },
+            Tool {
+                name: "file.read_json".to_string(),
+                description: "Read and parse a JSON file from the current project (read-only)".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"}},\"required\":[\"path\"]}".to_string(),
+            },
+            Tool {
+                name: "file.search_text".to_string(),
+                description: "Search project text files for a literal or regex query, optionally filtered by include/exclude globs (read-only, scoped)".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"query\":{\"type\":\"string\"},\"path\":{\"type\":\"string\"},\"limit\":{\"type\":\"integer\",\"minimum\":1},\"regex\":{\"type\":\"boolean\"},\"case_insensitive\":{\"type\":\"boolean\"},\"include\":{\"type\":\"array\",\"items\":{\"type\":\"string\"}},\"exclude\":{\"type\":\"array\",\"items\":{\"type\":\"string\"}}},\"required\":[\"query\"]}".to_string(),
+            },
+            Tool {
+                name: "file.glob".to_string(),
+                description: "Find project files matching a glob like src/**/*.rs, honouring .gitignore (read-only, scoped)".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"pattern\":{\"type\":\"string\"},\"path\":{\"type\":\"string\"},\"respect_gitignore\":{\"type\":\"boolean\"},\"limit\":{\"type\":\"integer\",\"minimum\":1}},\"required\":[\"pattern\"],\"additionalProperties\":false}".to_string(),
+            },
+            Tool {
+                name: "file.stat".to_string(),
+                description: "Get metadata for a project-scoped file or directory (read-only)".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"}},\"required\":[\"path\"]}".to_string(),
+            },
+            Tool {
+                name: "file.write_text".to_string(),
+                description: "Write a text file under the current project root (consent required)".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"},\"content\":{\"type\":\"string\"}},\"required\":[\"path\",\"content\"]}".to_string(),
+            },
+            Tool {
+                name: "file.append_text".to_string(),
+                description: "Append text to a file under the current project root (consent required)".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"},\"content\":{\"type\":\"string\"}},\"required\":[\"path\",\"content\"]}".to_string(),
+            },
+            Tool {
+                name: "file.mkdir".to_string(),
+                description: "Create a directory under the current project root (consent required)".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"}},\"required\":[\"path\"]}".to_string(),
+            },
+            Tool {
+                name: "mcp.tool_call".to_string(),
+                description: "Call a tool on a running MCP server (consent required)".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"server_id\":{\"type\":\"string\"},\"tool_name\":{\"type\":\"string\"},\"arguments\":{\"type\":\"object\"}},\"required\":[\"server_id\",\"tool_name\"],\"additionalProperties\":false}".to_string(),
+            },
+            Tool {
+                name: "archive.create".to_string(),
+                description: "Pack project files/directories into a .zip or .tar.gz inside the project (local action)".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"sources\":{\"type\":\"array\",\"items\":{\"type\":\"string\"}},\"destination\":{\"type\":\"string\"},\"format\":{\"type\":\"string\",\"enum\":[\"zip\",\"tar.gz\"]}},\"required\":[\"sources\",\"destination\"],\"additionalProperties\":false}".to_string(),
+            },
+            Tool {
+                name: "archive.extract".to_string(),
+                description: "Extract a .zip or .tar.gz inside the project; entries escaping the destination are rejected (local action)".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"archive\":{\"type\":\"string\"},\"destination\":{\"type\":\"string\"},\"format\":{\"type\":\"string\",\"enum\":[\"zip\",\"tar.gz\"]},\"overwrite\":{\"type\":\"boolean\"}},\"required\":[\"archive\"],\"additionalProperties\":false}".to_string(),
+            },
+            Tool {
+                name: "notify.send".to_string(),
+                description: "Send a message to the desktop and/or configured Slack, Discord or generic webhook targets, e.g. to report that a task finished (local action)".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"title\":{\"type\":\"string\"},\"message\":{\"type\":\"string\"},\"targets\":{\"type\":\"array\",\"items\":{\"type\":\"string\"}}},\"required\":[\"message\"],\"additionalProperties\":false}".to_string(),
+            },
+            Tool {
+                name: "db.query".to_string(),
+                description: "Run one read-only SQL query against a SQLite file in the project (path) or a configured Postgres connection (connection name); returns columns and up to max_rows rows".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"},\"connection\":{\"type\":\"string\"},\"sql\":{\"type\":\"string\"},\"max_rows\":{\"type\":\"integer\",\"minimum\":1,\"maximum\":1000}},\"required\":[\"sql\"],\"additionalProperties\":false}".to_string(),
+            },
+            Tool {
+                name: "calendar.list_events".to_string(),
+                description: "List events from a project .ics file (path, default calendar.ics) or a configured CalDAV calendar (calendar name), optionally within from/to (ISO dates or datetimes) and matching query text. Recurring (RRULE) events are listed once with recurring: true, not expanded into occurrences, and TZID times are compared as local wall-clock times without applying the zone's offset".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"},\"calendar\":{\"type\":\"string\"},\"from\":{\"type\":\"string\"},\"to\":{\"type\":\"string\"},\"query\":{\"type\":\"string\"},\"limit\":{\"type\":\"integer\",\"minimum\":1,\"maximum\":500}},\"additionalProperties\":false}".to_string(),
+            },
+            Tool {
+                name: "calendar.create_event".to_string(),
+                description: "Add an event to a project .ics file (path, default calendar.ics) or a configured CalDAV calendar; start/end are ISO dates (all day) or datetimes, end defaults to duration_minutes (60) after start (consent required)".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"},\"calendar\":{\"type\":\"string\"},\"summary\":{\"type\":\"string\"},\"start\":{\"type\":\"string\"},\"end\":{\"type\":\"string\"},\"duration_minutes\":{\"type\":\"integer\",\"minimum\":0},\"location\":{\"type\":\"string\"},\"description\":{\"type\":\"string\"},\"reminder_minutes\":{\"type\":\"integer\",\"minimum\":0}},\"required\":[\"summary\",\"start\"],\"additionalProperties\":false}".to_string(),
+            },
+            Tool {
+                name: "code.run".to_string(),
+                description: "Run a short Python or Node snippet in a scratch dir under the project with no network, a reduced environment and time/CPU/memory limits; returns stdout/stderr (consent required)".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"language\":{\"type\":\"string\",\"enum\":[\"python\",\"node\"]},\"code\":{\"type\":\"string\"},\"timeout_secs\":{\"type\":\"integer\",\"minimum\":1,\"maximum\":60}},\"required\":[\"language\",\"code\"],\"additionalProperties\":false}".to_string(),
+            },
+            Tool {
+                name: SUBTASK_TOOL.to_string(),
+                description: "Delegate a self-contained sub-task to a nested agent run with its own round budget and an optional subset of tools; returns its final answer. Nested tool calls follow the same policy, and ones needing consent are reported instead of run".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"prompt\":{\"type\":\"string\"},\"tools\":{\"type\":\"array\",\"items\":{\"type\":\"string\"}},\"max_rounds\":{\"type\":\"integer\",\"minimum\":1}},\"required\":[\"prompt\"],\"additionalProperties\":false}".to_string(),
+            },
+            Tool {
+                name: "desktop.open_url".to_string(),
+                description: "Open a URL using the platform shell (stubbed)".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"url\":{\"type\":\"string\"}},\"required\":[\"url\"]}".to_string(),
+            },
+            Tool {
+                name: "desktop.app.list".to_string(),
+                description: "List running applications with pid and window titles (stubbed unless built with process-list)".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"filter\":{\"type\":\"string\"},\"windowed_only\":{\"type\":\"boolean\"}},\"additionalProperties\":false}".to_string(),
+            },
+            Tool {
+                name: "desktop.app.activate".to_string(),
+                description: "Activate/focus a desktop application by name, or by pid from desktop.app.list".to_string(),
+                input_json_schema: "{\"type\":\"object\",\"properties\":{\"app\":{\"type\":\"string\"},\"pid\":{\"type\":\"integer\"}}}".to_string(),
+            },
+        ];
+        if cfg!(feature = "browser") {
+            tools.extend(browser_tools());
+        }
         Self {
-            tools: vec![
-                Tool {
-                    name: "time.now".to_string(),
-                    description: "Return the current UTC timestamp from the local runtime".to_string(),
-                    input_json_schema: "{\"type\":\"object\",\"properties\":{},\"additionalProperties\":false}".to_string(),
-                },
-                Tool {
-                    name: "echo".to_string(),
-                    description: "Echo a payload for testing tool orchestration".to_string(),
-                    input_json_schema: "{\"type\":\"object\",\"properties\":{\"input\":{\"type\":\"string\"}},\"required\":[\"input\"]}".to_string(),
-                },
-                Tool {
-                    name: "text.uppercase".to_string(),
-                    description: "Uppercase a provided string locally".to_string(),
-                    input_json_schema: "{\"type\":\"object\",\"properties\":{\"text\":{\"type\":\"string\"}},\"required\":[\"text\"]}".to_string(),
-                },
-                Tool {
-                    name: "math.add".to_string(),
-                    description: "Add two numbers locally".to_string(),
-                    input_json_schema: "{\"type\":\"object\",\"properties\":{\"a\":{\"type\":\"number\"},\"b\":{\"type\":\"number\"}},\"required\":[\"a\",\"b\"]}".to_string(),
-                },
-                Tool {
-                    name: "file.list".to_string(),
-                    description: "List files in the current project (read-only)".to_string(),
-                    input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"}},\"additionalProperties\":false}".to_string(),
-                },
-                Tool {
-                    name: "file.read_text".to_string(),
-                    description: "Read a text file from the current project (read-only)".to_string(),
-                    input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"}},\"required\":[\"path\"]}".to_string(),
-                },
-                Tool {
-                    name: "file.read_csv".to_string(),
-                    description: "Read a CSV file from the current project (read-only, preview rows)".to_string(),
-                    input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"},\"limit\":{\"type\":\"integer\",\"minimum\":1}},\"required\":[\"path\"]}".to_string(),
-                },
-                Tool {
-                    name: "file.read_json".to_string(),
-                    description: "Read and parse a JSON file from the current project (read-only)".to_string(),
-                    input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"}},\"required\":[\"path\"]}".to_string(),
-                },
-                Tool {
-                    name: "file.search_text".to_string(),
-                    description: "Search project files for a text query (read-only, scoped)".to_string(),
-                    input_json_schema: "{\"type\":\"object\",\"properties\":{\"query\":{\"type\":\"string\"},\"path\":{\"type\":\"string\"},\"limit\":{\"type\":\"integer\",\"minimum\":1}},\"required\":[\"query\"]}".to_string(),
-                },
-                Tool {
-                    name: "file.stat".to_string(),
-                    description: "Get metadata for a project-scoped file or directory (read-only)".to_string(),
-                    input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"}},\"required\":[\"path\"]}".to_string(),
-                },
-                Tool {
-                    name: "file.write_text".to_string(),
-                    description: "Write a text file under the current project root (consent required)".to_string(),
-                    input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"},\"content\":{\"type\":\"string\"}},\"required\":[\"path\",\"content\"]}".to_string(),
-                },
-                Tool {
-                    name: "file.append_text".to_string(),
-                    description: "Append text to a file under the current project root (consent required)".to_string(),
-                    input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"},\"content\":{\"type\":\"string\"}},\"required\":[\"path\",\"content\"]}".to_string(),
-                },
-                Tool {
-                    name: "file.mkdir".to_string(),
-                    description: "Create a directory under the current project root (consent required)".to_string(),
-                    input_json_schema: "{\"type\":\"object\",\"properties\":{\"path\":{\"type\":\"string\"}},\"required\":[\"path\"]}".to_string(),
-                },
-                Tool {
-                    name: "mcp.tool_call".to_string(),
-                    description: "Call a tool on a running MCP server (consent required)".to_string(),
-                    input_json_schema: "{\"type\":\"object\",\"properties\":{\"server_id\":{\"type\":\"string\"},\"tool_name\":{\"type\":\"string\"},\"arguments\":{\"type\":\"object\"}},\"required\":[\"server_id\",\"tool_name\"],\"additionalProperties\":false}".to_string(),
-                },
-                Tool {
-                    name: "desktop.open_url".to_string(),
-                    description: "Open a URL using the platform shell (stubbed)".to_string(),
-                    input_json_schema: "{\"type\":\"object\",\"properties\":{\"url\":{\"type\":\"string\"}},\"required\":[\"url\"]}".to_string(),
-                },
-                Tool {
-                    name: "desktop.app.list".to_string(),
-                    description: "List desktop applications/windows (stubbed)".to_string(),
-                    input_json_schema: "{\"type\":\"object\",\"properties\":{\"filter\":{\"type\":\"string\"}},\"additionalProperties\":false}".to_string(),
-                },
-                Tool {
-                    name: "desktop.app.activate".to_string(),
-                    description: "Activate/focus a desktop application/window (stubbed)".to_string(),
-                    input_json_schema: "{\"type\":\"object\",\"properties\":{\"app\":{\"type\":\"string\"}},\"required\":[\"app\"]}".to_string(),
-                },
-            ],
+            tools,
+            ..Self::default()
         }
     }
 
     pub fn from_tools(tools: Vec<Tool>) -> Self {
-        Self { tools }
+        Self {
+            tools,
+            ..Self::default()
+        }
     }
 
+    /// Hides tools `rules` doesn't permit, on top of any rules already applied.
+    pub fn restricted(mut self, rules: ToolRules) -> Self {
+        if !rules.is_unrestricted() {
+            self.rules.push(rules);
+        }
+        self
+    }
+
+    pub fn with_toggles(mut self, toggles: ToolToggles) -> Self {
+        self.toggles = toggles;
+        self
+    }
+
+    /// The tools offered to the provider: registered, and not filtered out.
     pub fn list(&self) -> Vec<Tool> {
-        self.tools.clone()
+        self.tools.iter().filter(|t| self.offers(&t.name)).cloned().collect()
     }
 
     pub fn has_tool(&self, name: &str) -> bool {
-        self.tools.iter().any(|t| t.name == name)
+        self.get(name).is_some()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Tool> {
+        self.tools.iter().find(|t| t.name == name && self.offers(name))
+    }
+
+    fn offers(&self, name: &str) -> bool {
+        self.toggles.permits(name) && self.rules.iter().all(|rules| rules.permits(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use storage::ProjectToolSettings;
+
+    fn names(registry: &ToolRegistry) -> Vec<String> {
+        registry.list().into_iter().map(|t| t.name).collect()
+    }
+
+    #[test]
+    fn toggles_and_rules_hide_tools_from_list_and_lookup() {
+        let settings = ToolSettings {
+            disabled: vec!["file.*".to_string(), "echo".to_string()],
+            projects: BTreeMap::from([(
+                "/docs".to_string(),
+                ProjectToolSettings {
+                    enabled: vec!["file.*".to_string()],
+                    disabled: vec!["file.write_text".to_string()],
+                },
+            )]),
+        };
+        let global = ToolRegistry::new_default().with_toggles(ToolToggles::from_settings(&settings, None));
+        assert!(!names(&global).iter().any(|n| n.starts_with("file.") || n == "echo"));
+        assert!(global.has_tool("time.now"));
+        assert!(!global.has_tool("file.read_text"));
+
+        let docs = ToolRegistry::new_default().with_toggles(ToolToggles::from_settings(&settings, Some("/docs")));
+        assert!(docs.has_tool("file.read_text"));
+        assert!(docs.get("file.write_text").is_none());
+        assert!(!docs.has_tool("echo"));
+
+        let restricted = docs.restricted(ToolRules {
+            allow: Some(vec!["file.*".to_string()]),
+            deny: Vec::new(),
+        });
+        assert!(names(&restricted).iter().all(|n| n.starts_with("file.") && n != "file.write_text"));
+        assert!(!names(&restricted).is_empty());
     }
 }
@@ -0,0 +1,217 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use ipc::{ToolCall, ToolResult, ToolStatsEntry, ToolStatsResponse};
+use providers::provider_trait::ProviderReply;
+use serde_json::Value;
+
+use crate::orchestrator::{OrchestrationObserver, PolicyDecisionRecord};
+
+/// Latency samples kept per tool; percentiles describe the most recent calls.
+const LATENCY_SAMPLES: usize = 512;
+
+/// Per-tool execution counters. Like the event log they live in memory only: a restarted
+/// service starts counting again.
+#[derive(Debug)]
+pub struct ToolStats {
+    since_unix_seconds: u64,
+    tools: BTreeMap<String, ToolCounters>,
+}
+
+#[derive(Debug, Default)]
+struct ToolCounters {
+    calls: u64,
+    errors: u64,
+    latencies_ms: VecDeque<u64>,
+    max_ms: u64,
+    last_error: Option<String>,
+    last_called_unix_seconds: u64,
+}
+
+impl Default for ToolStats {
+    fn default() -> Self {
+        Self {
+            since_unix_seconds: now_unix_seconds(),
+            tools: BTreeMap::new(),
+        }
+    }
+}
+
+impl ToolStats {
+    pub fn record(&mut self, result: &ToolResult, elapsed: Duration) {
+        let counters = self.tools.entry(result.name.clone()).or_default();
+        let elapsed_ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+        counters.calls += 1;
+        counters.max_ms = counters.max_ms.max(elapsed_ms);
+        counters.last_called_unix_seconds = now_unix_seconds();
+        if counters.latencies_ms.len() == LATENCY_SAMPLES {
+            counters.latencies_ms.pop_front();
+        }
+        counters.latencies_ms.push_back(elapsed_ms);
+        if let Some(error) = result_error(&result.result_json) {
+            counters.errors += 1;
+            counters.last_error = Some(error);
+        }
+    }
+
+    /// Busiest tool first; ties by name.
+    pub fn snapshot(&self) -> ToolStatsResponse {
+        let mut tools = self
+            .tools
+            .iter()
+            .map(|(name, counters)| {
+                let mut sorted = counters.latencies_ms.iter().copied().collect::<Vec<_>>();
+                sorted.sort_unstable();
+                ToolStatsEntry {
+                    name: name.clone(),
+                    calls: counters.calls,
+                    errors: counters.errors,
+                    error_rate: counters.errors as f64 / counters.calls as f64,
+                    p50_ms: percentile(&sorted, 50),
+                    p95_ms: percentile(&sorted, 95),
+                    p99_ms: percentile(&sorted, 99),
+                    max_ms: counters.max_ms,
+                    last_error: counters.last_error.clone(),
+                    last_called_unix_seconds: counters.last_called_unix_seconds,
+                }
+            })
+            .collect::<Vec<_>>();
+        tools.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.name.cmp(&b.name)));
+        ToolStatsResponse {
+            since_unix_seconds: self.since_unix_seconds,
+            tools,
+        }
+    }
+}
+
+/// Records every finished tool into `stats` and passes all hooks on to `inner`, if any.
+pub(crate) struct ToolStatsObserver<'s, 'o> {
+    pub stats: &'s mut ToolStats,
+    pub inner: Option<&'o mut dyn OrchestrationObserver>,
+}
+
+impl OrchestrationObserver for ToolStatsObserver<'_, '_> {
+    fn wants_tokens(&self) -> bool {
+        self.inner.as_ref().is_some_and(|inner| inner.wants_tokens())
+    }
+
+    fn provider_round_started(&mut self, round: usize) {
+        if let Some(inner) = self.inner.as_mut() {
+            inner.provider_round_started(round);
+        }
+    }
+
+    fn provider_round_finished(&mut self, round: usize, reply: &ProviderReply, elapsed: Duration) {
+        if let Some(inner) = self.inner.as_mut() {
+            inner.provider_round_finished(round, reply, elapsed);
+        }
+    }
+
+    fn token(&mut self, text: &str) {
+        if let Some(inner) = self.inner.as_mut() {
+            inner.token(text);
+        }
+    }
+
+    fn tool_started(&mut self, call: &ToolCall) {
+        if let Some(inner) = self.inner.as_mut() {
+            inner.tool_started(call);
+        }
+    }
+
+    fn tool_finished(&mut self, result: &ToolResult, elapsed: Duration) {
+        self.stats.record(result, elapsed);
+        if let Some(inner) = self.inner.as_mut() {
+            inner.tool_finished(result, elapsed);
+        }
+    }
+
+    fn policy_decision(&mut self, decision: &PolicyDecisionRecord) {
+        if let Some(inner) = self.inner.as_mut() {
+            inner.policy_decision(decision);
+        }
+    }
+}
+
+/// The `error` of a `{"status": "error", ...}` result, or the status itself if there is none.
+fn result_error(result_json: &str) -> Option<String> {
+    let value = serde_json::from_str::<Value>(result_json).ok()?;
+    if value.get("status").and_then(Value::as_str) != Some("error") {
+        return None;
+    }
+    Some(match value.get("error") {
+        Some(Value::String(error)) => error.clone(),
+        Some(other) => other.to_string(),
+        None => "error".to_string(),
+    })
+}
+
+/// Nearest-rank percentile of ascending `sorted`.
+fn percentile(sorted: &[u64], pct: usize) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (pct * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+fn now_unix_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ipc::Evidence;
+
+    fn result(name: &str, result_json: &str) -> ToolResult {
+        ToolResult {
+            tool_call_id: None,
+            name: name.to_string(),
+            result_json: result_json.to_string(),
+            evidence: Evidence {
+                summary: String::new(),
+                artifacts: Vec::new(),
+                attachments: Vec::new(),
+                original_result_bytes: None,
+            },
+        }
+    }
+
+    #[test]
+    fn counts_errors_and_reports_latency_percentiles_busiest_first() {
+        let mut stats = ToolStats::default();
+        for ms in 1..=100 {
+            stats.record(&result("file.read_text", r#"{"status":"ok"}"#), Duration::from_millis(ms));
+        }
+        stats.record(&result("shell.run", r#"{"status":"error","error":"timeout"}"#), Duration::from_millis(7));
+        stats.record(&result("shell.run", r#"{"status":"ok"}"#), Duration::from_millis(3));
+
+        let snapshot = stats.snapshot();
+        let names = snapshot.tools.iter().map(|t| t.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["file.read_text", "shell.run"]);
+        let read = &snapshot.tools[0];
+        assert_eq!((read.calls, read.errors, read.error_rate), (100, 0, 0.0));
+        assert_eq!((read.p50_ms, read.p95_ms, read.p99_ms, read.max_ms), (50, 95, 99, 100));
+        assert_eq!(read.last_error, None);
+        let shell = &snapshot.tools[1];
+        assert_eq!((shell.calls, shell.errors, shell.error_rate), (2, 1, 0.5));
+        assert_eq!(shell.last_error.as_deref(), Some("timeout"));
+        assert_eq!((shell.p50_ms, shell.max_ms), (3, 7));
+    }
+
+    #[test]
+    fn keeps_only_recent_latency_samples() {
+        let mut stats = ToolStats::default();
+        stats.record(&result("web.fetch", "not json"), Duration::from_secs(60));
+        for _ in 0..LATENCY_SAMPLES {
+            stats.record(&result("web.fetch", "{}"), Duration::from_millis(10));
+        }
+        let entry = &stats.snapshot().tools[0];
+        assert_eq!(entry.calls, LATENCY_SAMPLES as u64 + 1);
+        assert_eq!((entry.p99_ms, entry.max_ms), (10, 60_000));
+    }
+}
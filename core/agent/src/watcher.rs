@@ -0,0 +1,120 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+use ipc::{ProjectChange, ProjectChangesRequest};
+use notify::event::{EventKind, ModifyKind};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+const CHANGE_LOG_CAPACITY: usize = 512;
+const DEFAULT_CHANGE_LIMIT: usize = 100;
+
+/// Recursive watch on the open project. notify delivers on its own thread; changes queue in a
+/// channel and are drained on the service thread whenever a caller asks (`drain`).
+pub struct ProjectWatcher {
+    root: PathBuf,
+    _watcher: RecommendedWatcher,
+    rx: Receiver<notify::Result<Event>>,
+    next_seq: u64,
+    changes: VecDeque<ProjectChange>,
+}
+
+impl ProjectWatcher {
+    pub fn start(root: &Path) -> Result<Self, String> {
+        if !root.is_dir() {
+            return Err(format!("project path is not a directory: {}", root.display()));
+        }
+        // Some backends (FSEvents) report canonical paths, so strip against the canonical root.
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let root = root.as_path();
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        })
+        .map_err(|err| format!("failed to start file watcher: {err}"))?;
+        watcher
+            .watch(root, RecursiveMode::Recursive)
+            .map_err(|err| format!("failed to watch {}: {err}", root.display()))?;
+        Ok(Self {
+            root: root.to_path_buf(),
+            _watcher: watcher,
+            rx,
+            next_seq: 0,
+            changes: VecDeque::new(),
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Moves queued notify events into the change log and returns the newly recorded ones.
+    pub fn drain(&mut self, timestamp_unix_seconds: u64) -> Vec<ProjectChange> {
+        let mut fresh = Vec::new();
+        while let Ok(event) = self.rx.try_recv() {
+            let Ok(event) = event else {
+                continue;
+            };
+            let Some(kind) = change_kind(&event.kind) else {
+                continue;
+            };
+            let paths = event
+                .paths
+                .iter()
+                .filter_map(|p| p.strip_prefix(&self.root).ok())
+                .filter(|p| !p.starts_with(".git"))
+                .map(|p| p.to_string_lossy().replace('\\', "/"))
+                .filter(|p| !p.is_empty())
+                .collect::<Vec<_>>();
+            if paths.is_empty() {
+                continue;
+            }
+            // Editors often fire several writes per save; fold repeats of the last change.
+            if let Some(last) = self.changes.back() {
+                if last.kind == kind && last.paths == paths && last.timestamp_unix_seconds == timestamp_unix_seconds {
+                    continue;
+                }
+            }
+            self.next_seq += 1;
+            let change = ProjectChange {
+                seq: self.next_seq,
+                kind: kind.to_string(),
+                paths,
+                timestamp_unix_seconds,
+            };
+            self.changes.push_back(change.clone());
+            fresh.push(change);
+        }
+        while self.changes.len() > CHANGE_LOG_CAPACITY {
+            self.changes.pop_front();
+        }
+        fresh
+    }
+
+    pub fn since(&self, params: &ProjectChangesRequest) -> (Vec<ProjectChange>, u64) {
+        let limit = params.limit.unwrap_or(DEFAULT_CHANGE_LIMIT).max(1);
+        let changes = self
+            .changes
+            .iter()
+            .filter(|c| c.seq > params.since)
+            .take(limit)
+            .cloned()
+            .collect::<Vec<_>>();
+        let cursor = match changes.last() {
+            Some(last) if changes.len() == limit => last.seq,
+            _ => self.next_seq,
+        };
+        (changes, cursor)
+    }
+}
+
+fn change_kind(kind: &EventKind) -> Option<&'static str> {
+    match kind {
+        EventKind::Create(_) => Some("created"),
+        EventKind::Modify(ModifyKind::Name(_)) => Some("renamed"),
+        EventKind::Modify(ModifyKind::Metadata(_)) => None,
+        EventKind::Modify(_) => Some("modified"),
+        EventKind::Remove(_) => Some("removed"),
+        EventKind::Access(_) | EventKind::Any | EventKind::Other => None,
+    }
+}
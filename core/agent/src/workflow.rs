@@ -0,0 +1,243 @@
+//! Declarative multi-step runs for `workflow.run`. A workflow file (JSON or YAML) lists steps,
+//! each either a chat `prompt` or a direct `tool` call, with `depends_on` edges:
+//!
+//! ```yaml
+//! name: readme-summary
+//! steps:
+//!   - id: readme
+//!     tool: file.read_text
+//!     arguments: { path: README.md }
+//!   - id: summary
+//!     depends_on: [readme]
+//!     prompt: "Summarize this file: {{steps.readme.output}}"
+//! ```
+//!
+//! `{{steps.<id>.output}}` in a prompt or in a string argument is replaced by that step's output;
+//! it may only name a step listed in `depends_on`.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use ipc::ChatMode;
+use serde::Deserialize;
+use serde_json::Value;
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Workflow {
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Defaults to `RequireConfirmation`; the request's `mode` wins.
+    #[serde(default)]
+    pub mode: Option<ChatMode>,
+    /// Provider for prompt steps; the request's `provider_name` wins.
+    #[serde(default)]
+    pub provider: Option<String>,
+    pub steps: Vec<WorkflowStep>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkflowStep {
+    pub id: String,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub prompt: Option<String>,
+    #[serde(default)]
+    pub tool: Option<String>,
+    #[serde(default)]
+    pub arguments: Option<Value>,
+}
+
+pub enum StepAction {
+    Prompt(String),
+    Tool { name: String, arguments: Value },
+}
+
+impl Workflow {
+    /// Parses and validates `definition`; JSON when it starts with `{`, YAML otherwise.
+    pub fn parse(definition: &str) -> Result<Self, String> {
+        let workflow: Self = if definition.trim_start().starts_with('{') {
+            serde_json::from_str(definition).map_err(|err| format!("invalid workflow JSON: {err}"))?
+        } else {
+            serde_yaml::from_str(definition).map_err(|err| format!("invalid workflow YAML: {err}"))?
+        };
+        workflow.validate()?;
+        Ok(workflow)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.steps.is_empty() {
+            return Err("workflow has no steps".to_string());
+        }
+        let mut seen = BTreeSet::new();
+        for step in &self.steps {
+            if step.id.trim().is_empty() {
+                return Err("every step needs an id".to_string());
+            }
+            if !seen.insert(step.id.as_str()) {
+                return Err(format!("duplicate step id '{}'", step.id));
+            }
+            match (&step.prompt, &step.tool) {
+                (Some(_), None) if step.arguments.is_some() => {
+                    return Err(format!("step '{}': arguments only apply to tool steps", step.id))
+                }
+                (Some(_), None) | (None, Some(_)) => {}
+                _ => return Err(format!("step '{}' needs exactly one of prompt or tool", step.id)),
+            }
+            if step.arguments.as_ref().is_some_and(|a| !a.is_object()) {
+                return Err(format!("step '{}': arguments must be an object", step.id));
+            }
+            for referenced in step_references(step) {
+                if !step.depends_on.contains(&referenced) {
+                    return Err(format!(
+                        "step '{}' uses the output of '{referenced}' without listing it in depends_on",
+                        step.id
+                    ));
+                }
+            }
+        }
+        for step in &self.steps {
+            if let Some(missing) = step.depends_on.iter().find(|d| !seen.contains(d.as_str())) {
+                return Err(format!("step '{}' depends on unknown step '{missing}'", step.id));
+            }
+        }
+        self.order().map(|_| ())
+    }
+
+    /// Step indices in dependency order; among ready steps, file order. Errors on a cycle.
+    pub fn order(&self) -> Result<Vec<usize>, String> {
+        let mut done = BTreeSet::new();
+        let mut order = Vec::with_capacity(self.steps.len());
+        while order.len() < self.steps.len() {
+            let ready = (0..self.steps.len()).find(|&i| {
+                !done.contains(self.steps[i].id.as_str())
+                    && self.steps[i].depends_on.iter().all(|d| done.contains(d.as_str()))
+            });
+            let Some(i) = ready else {
+                let stuck = self
+                    .steps
+                    .iter()
+                    .filter(|s| !done.contains(s.id.as_str()))
+                    .map(|s| s.id.as_str())
+                    .collect::<Vec<_>>();
+                return Err(format!("workflow steps form a dependency cycle: {}", stuck.join(", ")));
+            };
+            done.insert(self.steps[i].id.as_str());
+            order.push(i);
+        }
+        Ok(order)
+    }
+}
+
+impl WorkflowStep {
+    /// The step with `{{steps.<id>.output}}` references filled from `outputs`.
+    pub fn action(&self, outputs: &BTreeMap<String, String>) -> StepAction {
+        match (&self.prompt, &self.tool) {
+            (Some(prompt), _) => StepAction::Prompt(fill(prompt, outputs)),
+            (None, tool) => StepAction::Tool {
+                name: tool.clone().unwrap_or_default(),
+                arguments: fill_value(self.arguments.clone().unwrap_or_else(|| serde_json::json!({})), outputs),
+            },
+        }
+    }
+}
+
+const REF_OPEN: &str = "{{steps.";
+const REF_CLOSE: &str = ".output}}";
+
+fn step_references(step: &WorkflowStep) -> Vec<String> {
+    let mut texts = step.prompt.iter().cloned().collect::<Vec<_>>();
+    if let Some(arguments) = &step.arguments {
+        texts.push(arguments.to_string());
+    }
+    let mut refs = Vec::new();
+    for text in texts {
+        let mut rest = text.as_str();
+        while let Some(start) = rest.find(REF_OPEN) {
+            let after = &rest[start + REF_OPEN.len()..];
+            let Some(end) = after.find(REF_CLOSE) else {
+                break;
+            };
+            refs.push(after[..end].to_string());
+            rest = &after[end + REF_CLOSE.len()..];
+        }
+    }
+    refs
+}
+
+fn fill(text: &str, outputs: &BTreeMap<String, String>) -> String {
+    outputs.iter().fold(text.to_string(), |text, (id, output)| {
+        text.replace(&format!("{REF_OPEN}{id}{REF_CLOSE}"), output)
+    })
+}
+
+fn fill_value(value: Value, outputs: &BTreeMap<String, String>) -> Value {
+    match value {
+        Value::String(s) => Value::String(fill(&s, outputs)),
+        Value::Array(items) => Value::Array(items.into_iter().map(|v| fill_value(v, outputs)).collect()),
+        Value::Object(map) => Value::Object(map.into_iter().map(|(k, v)| (k, fill_value(v, outputs))).collect()),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_yaml_and_orders_steps_by_dependencies() {
+        let workflow = Workflow::parse(
+            r#"
+name: demo
+steps:
+  - id: summary
+    depends_on: [readme, notes]
+    prompt: "Summarize {{steps.readme.output}}"
+  - id: readme
+    tool: file.read_text
+    arguments: { path: README.md }
+  - id: notes
+    depends_on: [readme]
+    tool: file.write_text
+    arguments: { path: notes.txt, content: "{{steps.readme.output}}" }
+"#,
+        )
+        .expect("parse");
+        assert_eq!(workflow.name.as_deref(), Some("demo"));
+        assert_eq!(workflow.order().expect("order"), vec![1, 2, 0]);
+
+        let outputs = BTreeMap::from([("readme".to_string(), "hello \"world\"".to_string())]);
+        match workflow.steps[2].action(&outputs) {
+            StepAction::Tool { name, arguments } => {
+                assert_eq!(name, "file.write_text");
+                assert_eq!(arguments["content"], "hello \"world\"");
+            }
+            StepAction::Prompt(_) => panic!("expected a tool step"),
+        }
+        match workflow.steps[0].action(&outputs) {
+            StepAction::Prompt(prompt) => assert_eq!(prompt, "Summarize hello \"world\""),
+            StepAction::Tool { .. } => panic!("expected a prompt step"),
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_workflows() {
+        let cases = [
+            (r#"{"steps":[]}"#, "no steps"),
+            (r#"{"steps":[{"id":"a","prompt":"x","tool":"echo"}]}"#, "exactly one"),
+            (r#"{"steps":[{"id":"a","prompt":"x"},{"id":"a","prompt":"y"}]}"#, "duplicate"),
+            (r#"{"steps":[{"id":"a","prompt":"x","depends_on":["b"]}]}"#, "unknown step"),
+            (r#"{"steps":[{"id":"a","prompt":"{{steps.b.output}}"},{"id":"b","prompt":"y"}]}"#, "depends_on"),
+            (
+                r#"{"steps":[{"id":"a","prompt":"x","depends_on":["b"]},{"id":"b","prompt":"y","depends_on":["a"]}]}"#,
+                "cycle",
+            ),
+            ("steps: [ {id: a, promt: x} ]", "invalid workflow YAML"),
+        ];
+        for (definition, expected) in cases {
+            let err = Workflow::parse(definition).expect_err(definition);
+            assert!(err.contains(expected), "{definition}: {err}");
+        }
+    }
+}
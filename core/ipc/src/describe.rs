@@ -0,0 +1,148 @@
+//! `system.describe`: an OpenRPC document listing every JSON-RPC method with schemas generated
+//! from its params and result types.
+
+use crate::*;
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use serde_json::{json, Value};
+
+macro_rules! methods {
+    ($gen:ident; $($name:literal: $params:ty => $result:ty,)*) => {
+        vec![$(method::<$params, $result>(&mut $gen, $name),)*]
+    };
+}
+
+/// Methods appear in the order `JsonRpcServer` dispatches them. `params` describes the whole params object.
+pub fn describe() -> Value {
+    let mut gen = SchemaSettings::draft07()
+        .with(|s| s.definitions_path = "#/components/schemas/".to_string())
+        .into_generator();
+    let methods = methods![gen;
+        "tools.list": EmptyParams => Vec<Tool>,
+        "tools.stats": EmptyParams => ToolStatsResponse,
+        "tools.enable": ToolToggleRequest => ToolToggleResponse,
+        "tools.disable": ToolToggleRequest => ToolToggleResponse,
+        "chat.request": ChatRequest => ChatResponse,
+        "chat.approve": ChatApproveRequest => ChatResponse,
+        "chat.deny": ChatDenyRequest => ChatResponse,
+        "sessions.create": SessionCreateRequest => Session,
+        "sessions.list": SessionListRequest => Vec<SessionSummary>,
+        "sessions.get": SessionGetRequest => Session,
+        "sessions.delete": SessionDeleteRequest => SessionDeleteResponse,
+        "sessions.fork": SessionForkRequest => Session,
+        "sessions.pin": SessionPinRequest => Session,
+        "sessions.archive": SessionArchiveRequest => Session,
+        "sessions.update": SessionUpdateRequest => Session,
+        "sessions.policy.set": SessionPolicySetRequest => Session,
+        "sessions.messages.append": SessionMessagesAppendRequest => SessionMessagesAppendResponse,
+        "sessions.messages.edit": SessionMessagesEditRequest => Session,
+        "sessions.messages.delete": SessionMessagesDeleteRequest => Session,
+        "providers.list": EmptyParams => Vec<ProviderInfo>,
+        "providers.set": ProvidersSetRequest => ProviderInfo,
+        "providers.config.get": ProviderConfigGetRequest => ProviderConfigRecord,
+        "providers.config.set": ProviderConfigSetRequest => ProviderConfigSetResponse,
+        "providers.models.list": ProviderModelsListRequest => ProviderModelsListResponse,
+        "ai.embed": AiEmbedRequest => AiEmbedResponse,
+        "mcp.servers.list": EmptyParams => Vec<McpServerRecord>,
+        "mcp.servers.add": McpServerAddRequest => McpServerMutationResponse,
+        "mcp.servers.policy.set": McpServerPolicyRecord => McpServerPolicyRecord,
+        "mcp.servers.import": McpServersImportRequest => McpServersImportResponse,
+        "mcp.servers.remove": McpServerRemoveRequest => McpServerMutationResponse,
+        "mcp.servers.start": McpServerStateRequest => McpServerMutationResponse,
+        "mcp.servers.stop": McpServerStateRequest => McpServerMutationResponse,
+        "mcp.servers.probe": McpServerStateRequest => McpServerProbeResponse,
+        "mcp.servers.tools": McpServerStateRequest => McpServerToolsResponse,
+        "mcp.servers.call": McpServerCallRequest => McpServerCallResponse,
+        "mcp.servers.tool_call": McpServerToolCallRequest => McpServerToolCallResponse,
+        "project.open": ProjectOpenRequest => ProjectOpenResponse,
+        "project.status": ProjectStatusRequest => ProjectStatusResponse,
+        "project.list": EmptyParams => ProjectListResponse,
+        "project.recent": EmptyParams => ProjectRecentResponse,
+        "project.recent.pin": ProjectPinRequest => ProjectRecentResponse,
+        "project.switch": ProjectSwitchRequest => ProjectSwitchResponse,
+        "project.watch": ProjectWatchRequest => ProjectWatchResponse,
+        "project.changes": ProjectChangesRequest => ProjectChangesResponse,
+        "state.backup": StateBackupRequest => StateBackupResponse,
+        "state.restore": StateRestoreRequest => StateRestoreResponse,
+        "storage.prune": StoragePruneRequest => StoragePruneResponse,
+        "policy.explain": PolicyExplainRequest => PolicyExplainResponse,
+        "policy.profile.get": EmptyParams => PolicyProfileResponse,
+        "policy.profile.set": PolicyProfileSetRequest => PolicyProfileResponse,
+        "agent.instructions.get": AgentInstructionsGetRequest => AgentInstructionsRecord,
+        "agent.instructions.set": AgentInstructionsSetRequest => AgentInstructionsRecord,
+        "audit.list": AuditListRequest => ListPage<AuditEntry>,
+        "audit.get": AuditGetRequest => AuditEntry,
+        "audit.replay": AuditReplayRequest => AuditReplayResponse,
+        "audit.evidence.get": AuditEvidenceGetRequest => AuditEvidenceResponse,
+        "consent.list": ConsentListRequest => ListPage<PendingConsentRecord>,
+        "consent.approve": ConsentActionRequest => ChatResponse,
+        "consent.approve_with_args": ConsentApproveWithArgsRequest => ChatResponse,
+        "consent.deny": ConsentActionRequest => ChatResponse,
+        "consent.grants": EmptyParams => Vec<ConsentGrant>,
+        "consent.revoke": ConsentRevokeRequest => ConsentRevokeResponse,
+        "system.health": EmptyParams => SystemHealthResponse,
+        "system.version": EmptyParams => BuildInfo,
+        "system.shutdown": SystemShutdownRequest => SystemShutdownResponse,
+        "events.subscribe": EventsSubscribeRequest => EventsSubscribeResponse,
+        "tasks.create": TasksCreateRequest => ScheduledTask,
+        "tasks.list": EmptyParams => Vec<ScheduledTask>,
+        "tasks.delete": TasksDeleteRequest => TasksDeleteResponse,
+        "workflow.run": WorkflowRunRequest => WorkflowRunResponse,
+        "system.describe": EmptyParams => Value,
+        "rpc.raw": RawRpcRequest => Value,
+        "rpc.batch": RpcBatchRequest => RpcBatchResponse,
+    ];
+    json!({
+        "openrpc": "1.2.6",
+        "info": { "title": "cmnd-n-ctrl", "version": env!("CARGO_PKG_VERSION") },
+        "methods": methods,
+        "components": { "schemas": gen.take_definitions() },
+    })
+}
+
+fn method<P: JsonSchema, R: JsonSchema>(gen: &mut SchemaGenerator, name: &str) -> Value {
+    json!({
+        "name": name,
+        "params": [{ "name": "params", "required": true, "schema": gen.subschema_for::<P>() }],
+        "result": { "name": "result", "schema": gen.subschema_for::<R>() },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn describes_every_dispatched_method() {
+        let source = include_str!("lib.rs");
+        let start = source.find("fn dispatch(&mut self").expect("dispatch");
+        let end = source[start..].find("pub fn handle_json").expect("handle_json") + start;
+        let dispatched = source[start..end]
+            .lines()
+            .filter_map(|line| line.trim_start().strip_prefix('"')?.split_once("\" =>"))
+            .map(|(name, _)| name.to_string())
+            .collect::<BTreeSet<_>>();
+
+        let doc = describe();
+        let described = doc["methods"]
+            .as_array()
+            .expect("methods")
+            .iter()
+            .map(|m| m["name"].as_str().expect("name").to_string())
+            .collect::<BTreeSet<_>>();
+        assert_eq!(described, dispatched);
+
+        let schemas = doc["components"]["schemas"].as_object().expect("schemas");
+        assert!(schemas.contains_key("ChatRequest"));
+        let session_create = doc["methods"]
+            .as_array()
+            .expect("methods")
+            .iter()
+            .find(|m| m["name"] == "sessions.create")
+            .expect("sessions.create");
+        assert_eq!(
+            session_create["params"][0]["schema"]["$ref"],
+            "#/components/schemas/SessionCreateRequest"
+        );
+    }
+}
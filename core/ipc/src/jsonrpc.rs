@@ -1,6 +1,10 @@
+use serde_json::{Map, Value};
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Id {
     Number(u64),
+    /// Negative or fractional ids, echoed back exactly as received.
+    Numeric(serde_json::Number),
     String(String),
     Null,
 }
@@ -27,6 +31,95 @@ pub struct Error {
     pub message: String,
 }
 
+/// A call decoded from the wire; `id: None` marks a notification, which gets no response.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Call {
+    pub id: Option<Id>,
+    pub method: String,
+    pub params: Value,
+}
+
+/// A decoded JSON-RPC payload. Entries that aren't valid requests carry their error response.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Payload {
+    Single(Result<Call, Response>),
+    Batch(Vec<Result<Call, Response>>),
+}
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+
+impl Id {
+    /// `None` for values the spec doesn't allow as ids (objects, arrays, booleans).
+    pub fn from_json(value: &Value) -> Option<Self> {
+        match value {
+            Value::Number(n) => Some(n.as_u64().map(Id::Number).unwrap_or_else(|| Id::Numeric(n.clone()))),
+            Value::String(s) => Some(Id::String(s.clone())),
+            Value::Null => Some(Id::Null),
+            _ => None,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        match self {
+            Id::Number(n) => Value::from(*n),
+            Id::Numeric(n) => Value::Number(n.clone()),
+            Id::String(s) => Value::String(s.clone()),
+            Id::Null => Value::Null,
+        }
+    }
+}
+
+impl Call {
+    /// Missing params become `{}`, which every parameterless method accepts.
+    pub fn into_request(self) -> Request {
+        let params = if self.params.is_null() {
+            Value::Object(Map::new())
+        } else {
+            self.params
+        };
+        Request::new(self.id.unwrap_or(Id::Null), self.method, params.to_string())
+    }
+}
+
+/// Decodes a JSON-RPC 2.0 payload: one request object or a non-empty batch array. `Err` is the
+/// single response to send when the payload isn't JSON or isn't a request at all.
+pub fn parse_payload(raw: &str) -> Result<Payload, Response> {
+    let value: Value = serde_json::from_str(raw)
+        .map_err(|err| Response::error(Id::Null, PARSE_ERROR, format!("parse error: {err}")))?;
+    match value {
+        Value::Array(items) if items.is_empty() => {
+            Err(Response::error(Id::Null, INVALID_REQUEST, "invalid request: empty batch"))
+        }
+        Value::Array(items) => Ok(Payload::Batch(items.into_iter().map(parse_call).collect())),
+        value => Ok(Payload::Single(parse_call(value))),
+    }
+}
+
+fn parse_call(value: Value) -> Result<Call, Response> {
+    let Value::Object(mut object) = value else {
+        return Err(Response::error(Id::Null, INVALID_REQUEST, "invalid request: expected an object"));
+    };
+    let id = match object.remove("id") {
+        Some(raw) => Some(Id::from_json(&raw).ok_or_else(|| {
+            Response::error(Id::Null, INVALID_REQUEST, "invalid request: id must be a string, number or null")
+        })?),
+        None => None,
+    };
+    let invalid = |message: &str| Response::error(id.clone().unwrap_or(Id::Null), INVALID_REQUEST, format!("invalid request: {message}"));
+    if object.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+        return Err(invalid("jsonrpc must be \"2.0\""));
+    }
+    let Some(Value::String(method)) = object.remove("method") else {
+        return Err(invalid("method must be a string"));
+    };
+    let params = object.remove("params").unwrap_or(Value::Null);
+    if !matches!(params, Value::Object(_) | Value::Array(_) | Value::Null) {
+        return Err(invalid("params must be an object or array"));
+    }
+    Ok(Call { id, method, params })
+}
+
 impl Request {
     pub fn new(id: Id, method: impl Into<String>, params_json: impl Into<String>) -> Self {
         Self {
@@ -59,6 +152,24 @@ impl Response {
             }),
         }
     }
+
+    /// The wire object; a result that isn't valid JSON is sent as a string.
+    pub fn to_json(&self) -> Value {
+        let mut wire = Map::new();
+        wire.insert("jsonrpc".to_string(), Value::String(self.jsonrpc.clone()));
+        wire.insert("id".to_string(), self.id.to_json());
+        if let Some(raw) = &self.result_json {
+            let result = serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.clone()));
+            wire.insert("result".to_string(), result);
+        }
+        if let Some(err) = &self.error {
+            wire.insert(
+                "error".to_string(),
+                serde_json::json!({ "code": err.code, "message": err.message }),
+            );
+        }
+        Value::Object(wire)
+    }
 }
 
 #[cfg(test)]
@@ -79,4 +190,33 @@ mod tests {
         assert!(err.result_json.is_none());
         assert_eq!(err.error.as_ref().map(|e| e.code), Some(-32601));
     }
+
+    #[test]
+    fn parse_payload_follows_json_rpc_2() {
+        let Ok(Payload::Single(Ok(call))) = parse_payload(r#"{"jsonrpc":"2.0","id":-1.5,"method":"tools.list"}"#) else {
+            panic!("expected a single call");
+        };
+        assert_eq!(call.id.as_ref().map(Id::to_json), Some(serde_json::json!(-1.5)));
+        assert_eq!(call.into_request().params_json, "{}");
+
+        let Ok(Payload::Batch(calls)) = parse_payload(
+            r#"[{"jsonrpc":"2.0","id":"a","method":"x"},{"jsonrpc":"2.0","method":"notify"},1,{"jsonrpc":"1.0","id":2,"method":"y"}]"#,
+        ) else {
+            panic!("expected a batch");
+        };
+        assert_eq!(calls[0].as_ref().map(|c| c.id.clone()), Ok(Some(Id::String("a".to_string()))));
+        assert_eq!(calls[1].as_ref().map(|c| c.id.clone()), Ok(None));
+        assert_eq!(calls[2].as_ref().unwrap_err().error.as_ref().map(|e| e.code), Some(INVALID_REQUEST));
+        assert_eq!(calls[3].as_ref().unwrap_err().id, Id::Number(2));
+
+        assert_eq!(parse_payload("[]").unwrap_err().error.map(|e| e.code), Some(INVALID_REQUEST));
+        assert_eq!(parse_payload("{").unwrap_err().error.map(|e| e.code), Some(PARSE_ERROR));
+        let Ok(Payload::Single(Err(bad_id))) = parse_payload(r#"{"jsonrpc":"2.0","id":{},"method":"x"}"#) else {
+            panic!("expected an invalid request");
+        };
+        assert_eq!(bad_id.id, Id::Null);
+
+        let wire = Response::success(Id::Numeric(serde_json::Number::from(-3)), r#"{"ok":true}"#).to_json();
+        assert_eq!(wire, serde_json::json!({ "jsonrpc": "2.0", "id": -3, "result": { "ok": true } }));
+    }
 }
@@ -1,19 +1,22 @@
+pub mod describe;
 pub mod jsonrpc;
 pub mod mcp;
 
-use crate::jsonrpc::{Id, Request, Response};
+use crate::jsonrpc::{parse_payload, Call, Id, Payload, Request, Response};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 pub type JsonBlob = String;
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct Tool {
     pub name: String,
     pub description: String,
     pub input_json_schema: JsonBlob,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ToolCall {
     #[serde(default)]
     pub tool_call_id: Option<String>,
@@ -21,13 +24,88 @@ pub struct ToolCall {
     pub arguments_json: JsonBlob,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// Params of `tools.enable`/`tools.disable`. `name` may be a `prefix*` pattern.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ToolToggleRequest {
+    pub name: String,
+    /// Project the change applies to; defaults to the open project.
+    #[serde(default)]
+    pub project_path: Option<String>,
+    /// Change the setting for every project instead.
+    #[serde(default)]
+    pub global: bool,
+}
+
+/// Tool settings in force for `project_path` after a `tools.enable`/`tools.disable`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ToolToggleResponse {
+    pub project_path: Option<String>,
+    /// Disabled in every project.
+    pub disabled: Vec<String>,
+    /// Offered in this project despite `disabled`.
+    pub project_enabled: Vec<String>,
+    pub project_disabled: Vec<String>,
+    /// Registered tools that are now hidden from the provider here.
+    pub hidden_tools: Vec<String>,
+}
+
+/// Result of `tools.stats`: executions counted since `since_unix_seconds` (agent start), busiest
+/// tool first.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ToolStatsResponse {
+    pub since_unix_seconds: u64,
+    pub tools: Vec<ToolStatsEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ToolStatsEntry {
+    pub name: String,
+    pub calls: u64,
+    /// Calls whose result had `"status": "error"`.
+    pub errors: u64,
+    /// `errors / calls`, 0.0 to 1.0.
+    pub error_rate: f64,
+    /// Latency percentiles over the most recent calls, in milliseconds.
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+    pub max_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    pub last_called_unix_seconds: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct Evidence {
     pub summary: String,
     pub artifacts: Vec<String>,
+    /// Content worth keeping with the audit entry (a diff, a screenshot, a fetched page). The
+    /// agent moves it into the evidence store and records an `EvidenceArtifact` instead.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<EvidenceAttachment>,
+    /// Size of `result_json` before the orchestrator cut it down to the tool's result budget.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_result_bytes: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct EvidenceAttachment {
+    pub name: String,
+    pub media_type: String,
+    pub content_base64: String,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// An attachment kept in the content-addressed evidence store, referenced from an audit entry.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct EvidenceArtifact {
+    pub sha256: String,
+    pub name: String,
+    pub media_type: String,
+    pub size_bytes: u64,
+    pub tool_name: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ToolResult {
     #[serde(default)]
     pub tool_call_id: Option<String>,
@@ -36,95 +114,277 @@ pub struct ToolResult {
     pub evidence: Evidence,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ChatMessage {
     pub role: String,
     pub content: String,
+    /// Extra content after `content`, in order; providers without multimodal support see only
+    /// the text.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parts: Vec<ContentPart>,
+    /// Assigned when the message is stored in a session (`msg-000001`, unique per session).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    /// Set with `message_id`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at_unix_seconds: Option<u64>,
+}
+
+/// Roles accepted by `sessions.messages.append`.
+pub const MESSAGE_ROLES: &[&str] = &["system", "user", "assistant"];
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text { text: String },
+    /// Read by the backend when the provider request is built.
+    ImagePath { path: String },
+    ImageBase64 { media_type: String, data: String },
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct ProviderConfig {
     pub provider_name: String,
     pub model: Option<String>,
     #[serde(default)]
     pub config_json: Option<JsonBlob>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// `RequireConfirmation` is what a request without a mode (or session default) runs in.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum ChatMode {
+    #[default]
     RequireConfirmation,
     BestEffort,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct ChatRequest {
     #[serde(default)]
     pub session_id: Option<String>,
     pub messages: Vec<ChatMessage>,
     pub provider_config: ProviderConfig,
-    pub mode: ChatMode,
+    /// Unset uses the session's `default_mode`, then `RequireConfirmation`.
+    #[serde(default)]
+    pub mode: Option<ChatMode>,
+    /// Reuse a completed response to an identical request (same fingerprint) from the last
+    /// this-many seconds instead of calling the provider, and cache this one for as long.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_ttl_secs: Option<u64>,
+    /// Simulate every tool that isn't ReadOnly instead of running it (and without asking for
+    /// consent); their events are marked `simulated`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ChatApproveRequest {
     pub consent_token: String,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ChatDenyRequest {
     pub consent_token: String,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct SessionSummary {
     pub id: String,
     pub title: String,
     pub created_at_unix_seconds: u64,
     pub updated_at_unix_seconds: u64,
     pub message_count: usize,
+    /// Parent session id for sessions made by `sessions.fork`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forked_from: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SessionListRequest {
+    /// Archived sessions are left out unless set.
+    #[serde(default)]
+    pub include_archived: bool,
+    /// Pinned sessions before the rest; each group stays most recently updated first.
+    #[serde(default)]
+    pub pinned_first: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct Session {
     pub id: String,
     pub created_at_unix_seconds: u64,
     pub updated_at_unix_seconds: u64,
     pub title: String,
     pub messages: Vec<ChatMessage>,
+    /// Project this session works in; unset sessions follow the open project.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<SessionPolicy>,
+    /// Parent session id for sessions made by `sessions.fork`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forked_from: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// Hidden from `sessions.list` unless `include_archived` is set.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+    /// Used by `chat.request` for this session when the request names no provider; sits
+    /// between the request and the project config.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Used when a request for this session sets no `mode`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_mode: Option<ChatMode>,
+    /// Bumped by every change; pass it as `expected_revision` to have a write fail with a
+    /// `conflict` error instead of overwriting a change made by another client.
+    #[serde(default)]
+    pub revision: u64,
+}
+
+/// Per-session overrides layered over `policy.json` and the project config.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SessionPolicy {
+    /// Replaces the global policy profile for this session.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Tool name patterns (`file.*` matches a prefix); unset allows everything not denied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_tools: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny_tools: Vec<String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SessionPolicySetRequest {
+    pub session_id: String,
+    /// `None` clears the session's overrides.
+    #[serde(default)]
+    pub policy: Option<SessionPolicy>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct SessionCreateRequest {
     pub title: Option<String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct SessionGetRequest {
     pub session_id: String,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct SessionDeleteRequest {
     pub session_id: String,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct SessionDeleteResponse {
     pub deleted: bool,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SessionPinRequest {
+    pub session_id: String,
+    pub pinned: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SessionArchiveRequest {
+    pub session_id: String,
+    pub archived: bool,
+}
+
+/// Changes only the fields that are set; an empty `provider_name` or `model` clears it and
+/// `clear_defaults` clears all three chat defaults first.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SessionUpdateRequest {
+    pub session_id: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub provider_name: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub default_mode: Option<ChatMode>,
+    #[serde(default)]
+    pub clear_defaults: bool,
+    /// The `Session::revision` the caller last saw; a mismatch is a `conflict` error.
+    #[serde(default)]
+    pub expected_revision: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SessionForkRequest {
+    pub session_id: String,
+    /// Last message (0-based, inclusive) copied into the fork; unset copies every message.
+    #[serde(default)]
+    pub message_index: Option<usize>,
+    /// Defaults to the parent's title with a "(fork)" suffix.
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct SessionMessagesAppendRequest {
     pub session_id: String,
     pub messages: Vec<ChatMessage>,
+    /// The `Session::revision` the caller last saw; a mismatch is a `conflict` error.
+    #[serde(default)]
+    pub expected_revision: Option<u64>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct SessionMessagesAppendResponse {
     pub session: Session,
+    /// Ids given to the appended messages, in order.
+    #[serde(default)]
+    pub message_ids: Vec<String>,
+}
+
+/// Picks a session message by `message_index` (0-based) or `message_id`; give exactly one.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SessionMessagesEditRequest {
+    pub session_id: String,
+    #[serde(default)]
+    pub message_index: Option<usize>,
+    #[serde(default)]
+    pub message_id: Option<String>,
+    /// Replaces the message text; attached parts are kept.
+    pub content: String,
+    /// The `Session::revision` the caller last saw; a mismatch is a `conflict` error.
+    #[serde(default)]
+    pub expected_revision: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SessionMessagesDeleteRequest {
+    pub session_id: String,
+    #[serde(default)]
+    pub message_index: Option<usize>,
+    #[serde(default)]
+    pub message_id: Option<String>,
+    /// The `Session::revision` the caller last saw; a mismatch is a `conflict` error.
+    #[serde(default)]
+    pub expected_revision: Option<u64>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ProviderInfo {
     pub name: String,
     pub enabled: bool,
@@ -133,76 +393,180 @@ pub struct ProviderInfo {
     pub config_summary: String,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ProvidersSetRequest {
     pub provider_name: String,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ProviderConfigGetRequest {
     pub provider_name: Option<String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ProviderConfigRecord {
     pub provider_name: String,
     pub is_active: bool,
     pub config_json: JsonBlob,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ProviderConfigSetRequest {
     pub provider_name: String,
     pub config_json: JsonBlob,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ProviderConfigSetResponse {
     pub provider_name: String,
     pub has_auth: bool,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ProviderModelsListRequest {
+    #[serde(default)]
+    pub provider_name: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ProviderModelsListResponse {
+    pub provider_name: String,
+    pub models: Vec<String>,
+    pub configured_model: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AiEmbedRequest {
+    pub texts: Vec<String>,
+    /// Defaults to the active provider.
+    #[serde(default)]
+    pub provider_name: Option<String>,
+    /// Overrides the provider's configured embedding model.
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AiEmbedResponse {
+    pub provider_name: String,
+    /// One vector per input text, in request order.
+    pub embeddings: Vec<Vec<f32>>,
+    pub dimensions: usize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AgentInstructionsGetRequest {
+    /// Project path to resolve instructions for; defaults to the open project.
+    #[serde(default)]
+    pub project_path: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AgentInstructionsSetRequest {
+    /// Empty text clears the instructions for the selected scope.
+    pub instructions: String,
+    #[serde(default)]
+    pub project_path: Option<String>,
+    /// Store as the global fallback instead of for a project.
+    #[serde(default)]
+    pub global: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AgentInstructionsRecord {
+    pub project_path: Option<String>,
+    pub instructions: Option<String>,
+    /// `project`, `project_config`, `global`, or `none`.
+    pub source: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct McpServerRecord {
     pub id: String,
     pub name: String,
     pub command: String,
     pub args: Vec<String>,
     pub status: String,
+    /// How the process last ended, e.g. `exit status: 1` or `stopped`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_exit_status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_exit_unix_seconds: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_exit_code: Option<i32>,
+    #[serde(default)]
+    pub restart_policy: McpRestartPolicy,
+    /// Automatic restarts since the server was last started by hand.
+    #[serde(default)]
+    pub restart_count: u32,
+    /// Extra environment for the spawned process.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+/// When the supervisor restarts a crashed MCP server (with exponential backoff).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum McpRestartPolicy {
+    #[default]
+    Never,
+    OnFailure,
+    Always,
+}
+
+impl McpRestartPolicy {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "never" => Ok(Self::Never),
+            "on-failure" | "on_failure" => Ok(Self::OnFailure),
+            "always" => Ok(Self::Always),
+            other => Err(format!("unknown restart policy '{other}' (expected never|on-failure|always)")),
+        }
+    }
+
+    pub fn should_restart(self, exit_code: Option<i32>) -> bool {
+        match self {
+            Self::Never => false,
+            Self::OnFailure => exit_code != Some(0),
+            Self::Always => true,
+        }
+    }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct McpServerAddRequest {
     pub name: String,
     pub command: String,
     pub args: Vec<String>,
+    #[serde(default)]
+    pub restart_policy: McpRestartPolicy,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct McpServerRemoveRequest {
     pub server_id: String,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct McpServerStateRequest {
     pub server_id: String,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct McpServerCallRequest {
     pub server_id: String,
     pub method: String,
     pub params_json: JsonBlob,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct McpServerToolCallRequest {
     pub server_id: String,
     pub tool_name: String,
     pub arguments_json: JsonBlob,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct McpServerProbeResponse {
     pub server_id: String,
     pub ok: bool,
@@ -210,7 +574,7 @@ pub struct McpServerProbeResponse {
     pub error: Option<String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct McpServerToolsResponse {
     pub server_id: String,
     pub ok: bool,
@@ -218,7 +582,7 @@ pub struct McpServerToolsResponse {
     pub error: Option<String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct McpServerCallResponse {
     pub server_id: String,
     pub method: String,
@@ -227,7 +591,7 @@ pub struct McpServerCallResponse {
     pub error: Option<String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct McpServerToolCallResponse {
     pub server_id: String,
     pub tool_name: String,
@@ -236,30 +600,117 @@ pub struct McpServerToolCallResponse {
     pub error: Option<String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// Per-server MCP tool policy: `allowed_tools` (when set) restricts which tools may be invoked and
+/// `capability_tier` (`ReadOnly`, `LocalActions` or `SystemActions`, the default) decides whether
+/// calls need consent. Setting both to `None` clears the policy.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct McpServerPolicyRecord {
+    pub server_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_tools: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capability_tier: Option<String>,
+}
+
+/// `config_json` is a Claude Desktop / Cursor style `{"mcpServers": {"<name>": {...}}}` document.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct McpServersImportRequest {
+    pub config_json: String,
+    #[serde(default)]
+    pub restart_policy: McpRestartPolicy,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct McpServerImportSkip {
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct McpServersImportResponse {
+    pub imported: Vec<McpServerRecord>,
+    pub skipped: Vec<McpServerImportSkip>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct McpServerMutationResponse {
     pub ok: bool,
     pub server: Option<McpServerRecord>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ProjectOpenRequest {
     pub path: String,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ProjectOpenResponse {
     pub path: String,
     pub exists: bool,
     pub is_dir: bool,
+    /// The `.cmnd-n-ctrl/config` applied to this project, if any.
+    #[serde(default)]
+    pub config_path: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RecentProject {
+    pub path: String,
+    pub last_opened_unix_seconds: u64,
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+/// Pinned projects first, then most recently opened.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ProjectRecentResponse {
+    pub projects: Vec<RecentProject>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ProjectPinRequest {
+    pub path: String,
+    pub pinned: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ProjectWorkspace {
+    pub name: String,
+    pub path: String,
+    /// True for the globally open project.
+    pub active: bool,
+    /// Sessions bound to this workspace.
+    pub session_ids: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ProjectListResponse {
+    pub active_path: Option<String>,
+    pub workspaces: Vec<ProjectWorkspace>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ProjectSwitchRequest {
+    /// Workspace name or path.
+    pub name: String,
+    /// Bind only this session instead of switching the open project.
+    #[serde(default)]
+    pub session_id: Option<String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ProjectSwitchResponse {
+    pub name: String,
+    pub path: String,
+    pub session_id: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ProjectStatusRequest {
     pub path: Option<String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ProjectStatusResponse {
     pub path: String,
     pub exists: bool,
@@ -267,7 +718,128 @@ pub struct ProjectStatusResponse {
     pub entry_count: usize,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ProjectWatchRequest {
+    pub enabled: bool,
+    /// Directory to watch; defaults to the open project.
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ProjectWatchResponse {
+    pub watching: bool,
+    pub path: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ProjectChangesRequest {
+    /// Return changes with `seq` greater than this cursor.
+    #[serde(default)]
+    pub since: u64,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ProjectChange {
+    pub seq: u64,
+    /// `created`, `modified`, `removed` or `renamed`.
+    pub kind: String,
+    /// Paths relative to the watched root.
+    pub paths: Vec<String>,
+    pub timestamp_unix_seconds: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ProjectChangesResponse {
+    pub watching: bool,
+    pub path: Option<String>,
+    pub changes: Vec<ProjectChange>,
+    pub cursor: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct StateBackupRequest {
+    /// Destination `.tar.gz`.
+    pub path: String,
+    /// Drop inline provider API keys from the snapshot.
+    #[serde(default)]
+    pub exclude_secrets: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct StateBackupResponse {
+    pub path: String,
+    pub files: Vec<String>,
+    pub bytes: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct StateRestoreRequest {
+    pub path: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct StateRestoreResponse {
+    pub path: String,
+    pub files: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PolicyExplainRequest {
+    pub tool_call: ToolCall,
+    /// Defaults to `BestEffort`.
+    #[serde(default)]
+    pub mode: Option<ChatMode>,
+    /// Evaluate against this session's project (config and tool lists).
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PolicyProfileSetRequest {
+    /// `strict`, `balanced` or `permissive`.
+    pub profile: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PolicyProfileResponse {
+    pub profile: String,
+    pub description: String,
+    pub available: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PolicyExplainResponse {
+    pub tool_name: String,
+    pub capability_tier: String,
+    pub tier_rule: String,
+    /// `allow`, `require_confirmation`, or `deny`.
+    pub decision: String,
+    pub reason: Option<String>,
+    /// The rule that produced `decision`.
+    pub rule: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct StoragePruneRequest {
+    /// Report what would be removed without writing.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct StoragePruneResponse {
+    pub dry_run: bool,
+    pub sessions_removed: usize,
+    pub audit_entries_removed: usize,
+    pub consents_removed: usize,
+    #[serde(default)]
+    pub evidence_files_removed: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct AuditEntry {
     pub audit_id: String,
     pub timestamp_unix_seconds: u64,
@@ -277,20 +849,122 @@ pub struct AuditEntry {
     pub proposed_tool_calls: Vec<String>,
     pub executed_actions: Vec<String>,
     pub evidence_summaries: Vec<String>,
+    /// Mode the request ran (or is waiting to run) under. With `tool_calls` it lets
+    /// `audit.replay` re-evaluate a run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<ChatMode>,
+    /// The consent this entry belongs to: the one the run created, the one whose approval
+    /// authorized its executed actions, or the one a lifecycle entry records.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consent_id: Option<String>,
+    /// `request_fingerprint` of the chat request, shared with its consent record.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_fingerprint: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<AuditToolCall>,
+    /// Project files whose chunks were injected as retrieved context for this run.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub retrieved_paths: Vec<String>,
+    /// Tool attachments stored for this run; fetch the content with `audit.evidence.get`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub evidence_artifacts: Vec<EvidenceArtifact>,
+}
+
+/// A tool call as the policy saw it. `arguments_json` has secret-looking fields redacted;
+/// `decision` is the policy's own verdict (`allow`, `require_confirmation` or `deny`), before
+/// any user approval.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AuditToolCall {
+    pub tool_name: String,
+    pub arguments_json: String,
+    pub decision: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Every artifact of the entry, or only the one with `sha256`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AuditEvidenceGetRequest {
+    pub audit_id: String,
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AuditEvidenceResponse {
+    pub audit_id: String,
+    pub artifacts: Vec<AuditEvidenceItem>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AuditEvidenceItem {
+    #[serde(flatten)]
+    pub artifact: EvidenceArtifact,
+    pub content_base64: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AuditReplayRequest {
+    pub audit_id: String,
+    /// Must be true: replays only re-run policy evaluation, never the tools.
+    #[serde(default)]
+    pub dry_run: bool,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AuditReplayResponse {
+    pub audit_id: String,
+    /// False for entries recorded before tool calls were kept; `decisions` is then empty.
+    pub replayable: bool,
+    /// Every decision matches what was recorded.
+    pub unchanged: bool,
+    pub decisions: Vec<AuditReplayDecision>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AuditReplayDecision {
+    pub tool_name: String,
+    pub original_decision: String,
+    pub current_decision: String,
+    pub current_reason: Option<String>,
+    pub rule: String,
+    pub changed: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct AuditListRequest {
     pub session_id: Option<String>,
     pub limit: Option<usize>,
+    /// Cursor: start after this entry, i.e. the previous page's `next_after_id`.
+    #[serde(default)]
+    pub after_id: Option<String>,
+    /// Inclusive bounds on `timestamp_unix_seconds`.
+    #[serde(default)]
+    pub since_unix_seconds: Option<u64>,
+    #[serde(default)]
+    pub until_unix_seconds: Option<u64>,
+    /// Entries that proposed or recorded a call to this tool; a trailing `*` matches a prefix.
+    #[serde(default)]
+    pub tool_name: Option<String>,
+    #[serde(default)]
+    pub provider: Option<String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// One page of `audit.list` or `consent.list`, newest first. `total` counts everything matching
+/// the filters; `next_after_id` is set while more pages follow.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ListPage<T> {
+    pub items: Vec<T>,
+    pub total: usize,
+    pub next_after_id: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct AuditGetRequest {
     pub audit_id: String,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct SystemHealthResponse {
     pub ok: bool,
     pub active_provider: Option<String>,
@@ -300,9 +974,89 @@ pub struct SystemHealthResponse {
     pub mcp_servers_running: usize,
     pub project_path: Option<String>,
     pub warnings: Vec<String>,
+    /// False when any store file fails to parse as its typed contents.
+    pub storage_integrity: bool,
+    /// Everything under the state directory, caches and indexes included.
+    pub storage_bytes: u64,
+    pub stores: Vec<StoreDiagnostics>,
+    pub build: BuildInfo,
+}
+
+/// Identifies a backend build in bug reports; returned by `system.version`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct BuildInfo {
+    pub version: String,
+    /// Short commit hash; unset for builds made outside a git checkout.
+    pub git_commit: Option<String>,
+    /// UTC `YYYY-MM-DD`; `SOURCE_DATE_EPOCH` overrides it for reproducible builds.
+    pub build_date: Option<String>,
+    /// Optional cargo features compiled in, e.g. `process-list`.
+    pub features: Vec<String>,
+}
+
+/// One state file. Lock counters cover writes made by this backend process since it started.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct StoreDiagnostics {
+    pub file_name: String,
+    pub exists: bool,
+    pub size_bytes: u64,
+    pub modified_unix_seconds: Option<u64>,
+    pub schema_version: Option<u32>,
+    /// Why the file failed the integrity check; reads of this store fail the same way.
+    pub error: Option<String>,
+    /// Its `.lock` file is present: a write in progress or one left by a crashed process.
+    pub lock_held: bool,
+    pub lock_acquisitions: u64,
+    /// Acquisitions that had to wait for another writer.
+    pub lock_contended: u64,
+    pub lock_wait_ms: u64,
+    pub lock_timeouts: u64,
+}
+
+/// `token` must match the one the server wrote to `shutdown.token` in its state directory.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SystemShutdownRequest {
+    pub token: String,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SystemShutdownResponse {
+    pub shutting_down: bool,
+}
+
+/// Typed backend event; `payload_json` is an event-specific JSON object.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AgentEvent {
+    pub seq: u64,
+    pub event_type: String,
+    pub timestamp_unix_seconds: u64,
+    pub payload_json: String,
+}
+
+/// Returns events after cursor `since`, optionally filtered by `event_types`. `timeout_ms` lets
+/// transports that support it (the HTTP server) hold the request open until an event arrives.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct EventsSubscribeRequest {
+    #[serde(default)]
+    pub since: u64,
+    #[serde(default)]
+    pub event_types: Vec<String>,
+    #[serde(default)]
+    pub timeout_ms: u64,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// `cursor` is the value to pass as `since` next time; `truncated` means events between the
+/// requested cursor and the oldest buffered one were dropped, so clients should resync.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct EventsSubscribeResponse {
+    pub events: Vec<AgentEvent>,
+    pub cursor: u64,
+    pub truncated: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct PendingConsentRecord {
     pub consent_id: String,
     pub session_id: Option<String>,
@@ -315,27 +1069,219 @@ pub struct PendingConsentRecord {
     pub rationale: String,
     pub arguments_preview: Option<String>,
     pub request_fingerprint: String,
+    /// Every action awaiting consent, in proposal order; `consent.approve` indices refer to this list.
+    #[serde(default)]
+    pub pending_actions: Vec<ActionEvent>,
+    #[serde(default)]
+    pub risk_factors: Vec<String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ConsentListRequest {
     pub status: Option<String>,
     pub session_id: Option<String>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// Cursor: start after this consent, i.e. the previous page's `next_after_id`.
+    #[serde(default)]
+    pub after_id: Option<String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ConsentActionRequest {
     pub consent_id: String,
+    /// Approve only these `pending_actions` indices; `None` approves everything not denied.
+    #[serde(default)]
+    pub approve_indices: Option<Vec<usize>>,
+    #[serde(default)]
+    pub deny_indices: Vec<usize>,
+    /// `once` (default), `session` or `always`; the wider scopes also grant the approved tools
+    /// for later requests until revoked.
+    #[serde(default)]
+    pub scope: Option<String>,
+}
+
+/// A standing approval: calls to `tool_name` skip consent for one session or everywhere.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ConsentGrant {
+    pub grant_id: String,
+    pub tool_name: String,
+    /// For MCP calls, the `(server_id, tool_name)` the grant covers; an MCP call to any other
+    /// server or tool, or a grant without one, still asks.
+    #[serde(default)]
+    pub mcp_target: Option<(String, String)>,
+    /// `session` or `always`.
+    pub scope: String,
+    /// Set for `session` grants.
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// The consent approved with this scope.
+    pub consent_id: String,
+    pub granted_at_unix_seconds: u64,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ConsentRevokeRequest {
+    #[serde(default)]
+    pub grant_id: Option<String>,
+    /// Revokes every grant for this tool.
+    #[serde(default)]
+    pub tool_name: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ConsentRevokeResponse {
+    pub revoked: Vec<ConsentGrant>,
+}
+
+/// A chat prompt run on a schedule by `serve-http`; each run is an ordinary audited
+/// `chat.request` whose consent-required actions are left pending.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ScheduledTask {
+    pub id: String,
+    /// Five cron fields (UTC), an `@daily`-style shorthand or `@every <n>m|h|d`.
+    pub schedule: String,
+    pub prompt: String,
+    pub mode: ChatMode,
+    #[serde(default)]
+    pub provider_name: Option<String>,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    pub created_at_unix_seconds: u64,
+    /// `None` once the schedule can no longer fire.
+    #[serde(default)]
+    pub next_run_unix_seconds: Option<u64>,
+    #[serde(default)]
+    pub last_run_unix_seconds: Option<u64>,
+    #[serde(default)]
+    pub last_audit_id: Option<String>,
+    /// `execution_state` of the last run: `completed` or `awaiting_consent`.
+    #[serde(default)]
+    pub last_status: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TasksCreateRequest {
+    pub schedule: String,
+    pub prompt: String,
+    /// Defaults to `RequireConfirmation`.
+    #[serde(default)]
+    pub mode: Option<ChatMode>,
+    #[serde(default)]
+    pub provider_name: Option<String>,
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TasksDeleteRequest {
+    pub task_id: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TasksDeleteResponse {
+    pub deleted: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct WorkflowRunRequest {
+    /// Workflow file contents (JSON or YAML).
+    pub definition: String,
+    /// Overrides the workflow's `mode`.
+    #[serde(default)]
+    pub mode: Option<ChatMode>,
+    /// Overrides the workflow's `provider` for prompt steps.
+    #[serde(default)]
+    pub provider_name: Option<String>,
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct WorkflowStepResult {
+    pub step_id: String,
+    /// `completed`, `awaiting_consent`, `failed`, or `skipped` when a dependency did not complete.
+    pub status: String,
+    /// Each executed step is its own audited chat request.
+    #[serde(default)]
+    pub audit_id: Option<String>,
+    #[serde(default)]
+    pub consent_token: Option<String>,
+    pub output: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct WorkflowRunResponse {
+    #[serde(default)]
+    pub name: Option<String>,
+    /// `completed` when every step completed, else `awaiting_consent` or `failed`.
+    pub status: String,
+    pub steps: Vec<WorkflowStepResult>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ConsentArgumentEdit {
+    pub action_index: usize,
+    /// JSON object whose fields replace those in the proposed call's arguments.
+    pub arguments_json: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ConsentApproveWithArgsRequest {
+    pub consent_id: String,
+    #[serde(default)]
+    pub edits: Vec<ConsentArgumentEdit>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct RawRpcRequest {
     pub id: Option<u64>,
     pub method: String,
     pub params_json: String,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// Params of `rpc.batch`: the calls run in order. With `abort_on_error` the first failing call
+/// stops the batch and the calls after it are reported as skipped.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RpcBatchRequest {
+    pub requests: Vec<RpcBatchCall>,
+    #[serde(default)]
+    pub abort_on_error: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RpcBatchCall {
+    pub method: String,
+    /// Missing params become `{}`, as for a single request.
+    #[serde(default)]
+    pub params: serde_json::Value,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RpcBatchResponse {
+    /// One entry per call, in request order.
+    pub responses: Vec<RpcBatchResult>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RpcBatchResult {
+    pub method: String,
+    pub ok: bool,
+    #[serde(default)]
+    pub skipped: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcBatchError>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RpcBatchError {
+    pub code: i64,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ActionEvent {
     pub tool_name: String,
     pub capability_tier: String,
@@ -343,9 +1289,12 @@ pub struct ActionEvent {
     pub reason: Option<String>,
     pub arguments_preview: Option<String>,
     pub evidence_summary: Option<String>,
+    /// Before/after diff of the file a pending write would change.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview_diff: Option<String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum ExecutionEventKind {
     ExecutionStarted,
     TokenStream,
@@ -356,7 +1305,7 @@ pub enum ExecutionEventKind {
     ExecutionFailed,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ExecutionEvent {
     pub kind: ExecutionEventKind,
     pub text: Option<String>,
@@ -366,7 +1315,7 @@ pub struct ExecutionEvent {
     pub metadata_json: Option<JsonBlob>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ExecutionFeedItem {
     pub execution_id: String,
     pub session_id: Option<String>,
@@ -379,7 +1328,7 @@ pub struct ExecutionFeedItem {
     pub events: Vec<ExecutionEvent>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ConsentRequest {
     pub scope: String,
     pub human_summary: String,
@@ -389,9 +1338,11 @@ pub struct ConsentRequest {
     pub expires_at_unix_seconds: Option<u64>,
     #[serde(default)]
     pub ttl_seconds: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview_diff: Option<String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ChatResponse {
     pub final_text: String,
     pub audit_id: String,
@@ -404,6 +1355,11 @@ pub struct ChatResponse {
     pub proposed_actions: Vec<ActionEvent>,
     pub executed_action_events: Vec<ActionEvent>,
     pub action_events: Vec<ActionEvent>,
+    #[serde(default)]
+    pub provider_retries: u32,
+    /// Audit id of the response this one was served from, when it came from the cache.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_from: Option<String>,
 }
 
 impl ChatResponse {
@@ -477,13 +1433,20 @@ pub trait ChatService {
     fn chat_approve(&mut self, params: ChatApproveRequest) -> Result<ChatResponse, String>;
     fn chat_deny(&mut self, params: ChatDenyRequest) -> Result<ChatResponse, String>;
     fn sessions_create(&mut self, params: SessionCreateRequest) -> Result<Session, String>;
-    fn sessions_list(&self) -> Result<Vec<SessionSummary>, String>;
+    fn sessions_list(&self, params: SessionListRequest) -> Result<Vec<SessionSummary>, String>;
     fn sessions_get(&self, params: SessionGetRequest) -> Result<Session, String>;
     fn sessions_delete(&mut self, params: SessionDeleteRequest) -> Result<SessionDeleteResponse, String>;
+    fn sessions_fork(&mut self, params: SessionForkRequest) -> Result<Session, String>;
+    fn sessions_pin(&mut self, params: SessionPinRequest) -> Result<Session, String>;
+    fn sessions_archive(&mut self, params: SessionArchiveRequest) -> Result<Session, String>;
+    fn sessions_update(&mut self, params: SessionUpdateRequest) -> Result<Session, String>;
+    fn sessions_policy_set(&mut self, params: SessionPolicySetRequest) -> Result<Session, String>;
     fn sessions_messages_append(
         &mut self,
         params: SessionMessagesAppendRequest,
     ) -> Result<SessionMessagesAppendResponse, String>;
+    fn sessions_messages_edit(&mut self, params: SessionMessagesEditRequest) -> Result<Session, String>;
+    fn sessions_messages_delete(&mut self, params: SessionMessagesDeleteRequest) -> Result<Session, String>;
     fn providers_list(&self) -> Result<Vec<ProviderInfo>, String>;
     fn providers_set(&mut self, params: ProvidersSetRequest) -> Result<ProviderInfo, String>;
     fn providers_config_get(&self, params: ProviderConfigGetRequest) -> Result<ProviderConfigRecord, String>;
@@ -491,8 +1454,15 @@ pub trait ChatService {
         &mut self,
         params: ProviderConfigSetRequest,
     ) -> Result<ProviderConfigSetResponse, String>;
+    fn providers_models_list(
+        &self,
+        params: ProviderModelsListRequest,
+    ) -> Result<ProviderModelsListResponse, String>;
+    fn ai_embed(&self, params: AiEmbedRequest) -> Result<AiEmbedResponse, String>;
     fn mcp_servers_list(&self) -> Result<Vec<McpServerRecord>, String>;
     fn mcp_servers_add(&mut self, params: McpServerAddRequest) -> Result<McpServerMutationResponse, String>;
+    fn mcp_servers_import(&mut self, params: McpServersImportRequest) -> Result<McpServersImportResponse, String>;
+    fn mcp_servers_policy_set(&mut self, params: McpServerPolicyRecord) -> Result<McpServerPolicyRecord, String>;
     fn mcp_servers_remove(
         &mut self,
         params: McpServerRemoveRequest,
@@ -508,17 +1478,65 @@ pub trait ChatService {
     ) -> Result<McpServerToolCallResponse, String>;
     fn project_open(&mut self, params: ProjectOpenRequest) -> Result<ProjectOpenResponse, String>;
     fn project_status(&self, params: ProjectStatusRequest) -> Result<ProjectStatusResponse, String>;
-    fn audit_list(&self, params: AuditListRequest) -> Result<Vec<AuditEntry>, String>;
+    fn project_list(&self) -> Result<ProjectListResponse, String>;
+    fn project_recent(&self) -> Result<ProjectRecentResponse, String>;
+    fn project_pin(&mut self, params: ProjectPinRequest) -> Result<ProjectRecentResponse, String>;
+    fn project_switch(&mut self, params: ProjectSwitchRequest) -> Result<ProjectSwitchResponse, String>;
+    fn project_watch(&mut self, params: ProjectWatchRequest) -> Result<ProjectWatchResponse, String>;
+    fn project_changes(&self, params: ProjectChangesRequest) -> Result<ProjectChangesResponse, String>;
+    fn state_backup(&self, params: StateBackupRequest) -> Result<StateBackupResponse, String>;
+    fn state_restore(&mut self, params: StateRestoreRequest) -> Result<StateRestoreResponse, String>;
+    fn storage_prune(&mut self, params: StoragePruneRequest) -> Result<StoragePruneResponse, String>;
+    fn policy_explain(&self, params: PolicyExplainRequest) -> Result<PolicyExplainResponse, String>;
+    fn policy_profile_get(&self) -> Result<PolicyProfileResponse, String>;
+    fn policy_profile_set(&mut self, params: PolicyProfileSetRequest) -> Result<PolicyProfileResponse, String>;
+    fn agent_instructions_get(
+        &self,
+        params: AgentInstructionsGetRequest,
+    ) -> Result<AgentInstructionsRecord, String>;
+    fn agent_instructions_set(
+        &mut self,
+        params: AgentInstructionsSetRequest,
+    ) -> Result<AgentInstructionsRecord, String>;
+    fn audit_list(&self, params: AuditListRequest) -> Result<ListPage<AuditEntry>, String>;
     fn audit_get(&self, params: AuditGetRequest) -> Result<AuditEntry, String>;
-    fn consent_list(&self, params: ConsentListRequest) -> Result<Vec<PendingConsentRecord>, String>;
+    fn audit_replay(&self, params: AuditReplayRequest) -> Result<AuditReplayResponse, String>;
+    fn audit_evidence_get(&self, params: AuditEvidenceGetRequest) -> Result<AuditEvidenceResponse, String>;
+    fn consent_list(&self, params: ConsentListRequest) -> Result<ListPage<PendingConsentRecord>, String>;
     fn consent_approve(&mut self, params: ConsentActionRequest) -> Result<ChatResponse, String>;
+    fn consent_approve_with_args(&mut self, params: ConsentApproveWithArgsRequest) -> Result<ChatResponse, String>;
     fn consent_deny(&mut self, params: ConsentActionRequest) -> Result<ChatResponse, String>;
+    fn consent_grants(&self) -> Result<Vec<ConsentGrant>, String>;
+    fn consent_revoke(&mut self, params: ConsentRevokeRequest) -> Result<ConsentRevokeResponse, String>;
     fn tools_list(&self) -> Vec<Tool>;
+    fn tools_stats(&self) -> Result<ToolStatsResponse, String>;
+    fn tools_enable(&mut self, params: ToolToggleRequest) -> Result<ToolToggleResponse, String>;
+    fn tools_disable(&mut self, params: ToolToggleRequest) -> Result<ToolToggleResponse, String>;
+    fn tasks_create(&mut self, params: TasksCreateRequest) -> Result<ScheduledTask, String>;
+    fn tasks_list(&self) -> Result<Vec<ScheduledTask>, String>;
+    fn tasks_delete(&mut self, params: TasksDeleteRequest) -> Result<TasksDeleteResponse, String>;
+    fn workflow_run(&mut self, params: WorkflowRunRequest) -> Result<WorkflowRunResponse, String>;
     fn system_health(&self) -> Result<SystemHealthResponse, String>;
+    fn system_version(&self) -> Result<BuildInfo, String>;
+    fn system_shutdown(&mut self, params: SystemShutdownRequest) -> Result<SystemShutdownResponse, String>;
+    fn events_subscribe(&self, params: EventsSubscribeRequest) -> Result<EventsSubscribeResponse, String>;
+}
+
+/// Hooks run around every request `JsonRpcServer::handle` dispatches, for logging, auth, rate
+/// limiting or metrics. `before` runs in registration order and `after` in reverse.
+pub trait Middleware {
+    /// Returning a response skips the handler and any later middleware.
+    fn before(&mut self, _request: &Request) -> Option<Response> {
+        None
+    }
+
+    /// Sees, and may rewrite, the response of every request whose `before` ran.
+    fn after(&mut self, _request: &Request, _response: &mut Response) {}
 }
 
 pub struct JsonRpcServer<S> {
     service: S,
+    middleware: Vec<Box<dyn Middleware>>,
 }
 
 impl<S> JsonRpcServer<S>
@@ -526,10 +1544,43 @@ where
     S: ChatService,
 {
     pub fn new(service: S) -> Self {
-        Self { service }
+        Self {
+            service,
+            middleware: Vec::new(),
+        }
+    }
+
+    pub fn with_middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(Box::new(middleware));
+        self
     }
 
     pub fn handle(&mut self, request: Request) -> Response {
+        if self.middleware.is_empty() {
+            return self.dispatch(request);
+        }
+        let mut middleware = std::mem::take(&mut self.middleware);
+        let mut ran = 0;
+        let mut response = None;
+        for hook in middleware.iter_mut() {
+            ran += 1;
+            response = hook.before(&request);
+            if response.is_some() {
+                break;
+            }
+        }
+        let mut response = match response {
+            Some(response) => response,
+            None => self.dispatch(request.clone()),
+        };
+        for hook in middleware[..ran].iter_mut().rev() {
+            hook.after(&request, &mut response);
+        }
+        self.middleware = middleware;
+        response
+    }
+
+    fn dispatch(&mut self, request: Request) -> Response {
         match request.method.as_str() {
             "tools.list" => {
                 match serde_json::to_string(&self.service.tools_list()) {
@@ -537,6 +1588,9 @@ where
                     Err(err) => Response::error(request.id, -32603, format!("serialization error: {err}")),
                 }
             }
+            "tools.stats" => self.parse_and_call(&request, |s, _: EmptyParams| s.tools_stats()),
+            "tools.enable" => self.parse_and_call(&request, |s, p: ToolToggleRequest| s.tools_enable(p)),
+            "tools.disable" => self.parse_and_call(&request, |s, p: ToolToggleRequest| s.tools_disable(p)),
             "chat.request" => {
                 match serde_json::from_str::<ChatRequest>(&request.params_json) {
                     Ok(params) => match serde_json::to_string(&self.service.chat_request(params)) {
@@ -565,12 +1619,25 @@ where
                 }
             }
             "sessions.create" => self.parse_and_call(&request, |s, p: SessionCreateRequest| s.sessions_create(p)),
-            "sessions.list" => self.parse_and_call(&request, |s, _p: EmptyParams| s.sessions_list()),
+            "sessions.list" => self.parse_and_call(&request, |s, p: SessionListRequest| s.sessions_list(p)),
             "sessions.get" => self.parse_and_call(&request, |s, p: SessionGetRequest| s.sessions_get(p)),
             "sessions.delete" => self.parse_and_call(&request, |s, p: SessionDeleteRequest| s.sessions_delete(p)),
+            "sessions.fork" => self.parse_and_call(&request, |s, p: SessionForkRequest| s.sessions_fork(p)),
+            "sessions.pin" => self.parse_and_call(&request, |s, p: SessionPinRequest| s.sessions_pin(p)),
+            "sessions.archive" => self.parse_and_call(&request, |s, p: SessionArchiveRequest| s.sessions_archive(p)),
+            "sessions.update" => self.parse_and_call(&request, |s, p: SessionUpdateRequest| s.sessions_update(p)),
+            "sessions.policy.set" => {
+                self.parse_and_call(&request, |s, p: SessionPolicySetRequest| s.sessions_policy_set(p))
+            }
             "sessions.messages.append" => self.parse_and_call(&request, |s, p: SessionMessagesAppendRequest| {
                 s.sessions_messages_append(p)
             }),
+            "sessions.messages.edit" => self.parse_and_call(&request, |s, p: SessionMessagesEditRequest| {
+                s.sessions_messages_edit(p)
+            }),
+            "sessions.messages.delete" => self.parse_and_call(&request, |s, p: SessionMessagesDeleteRequest| {
+                s.sessions_messages_delete(p)
+            }),
             "providers.list" => self.parse_and_call(&request, |s, _p: EmptyParams| s.providers_list()),
             "providers.set" => self.parse_and_call(&request, |s, p: ProvidersSetRequest| s.providers_set(p)),
             "providers.config.get" => {
@@ -579,8 +1646,16 @@ where
             "providers.config.set" => {
                 self.parse_and_call(&request, |s, p: ProviderConfigSetRequest| s.providers_config_set(p))
             }
+            "providers.models.list" => {
+                self.parse_and_call(&request, |s, p: ProviderModelsListRequest| s.providers_models_list(p))
+            }
+            "ai.embed" => self.parse_and_call(&request, |s, p: AiEmbedRequest| s.ai_embed(p)),
             "mcp.servers.list" => self.parse_and_call(&request, |s, _p: EmptyParams| s.mcp_servers_list()),
             "mcp.servers.add" => self.parse_and_call(&request, |s, p: McpServerAddRequest| s.mcp_servers_add(p)),
+            "mcp.servers.policy.set" => {
+                self.parse_and_call(&request, |s, p: McpServerPolicyRecord| s.mcp_servers_policy_set(p))
+            }
+            "mcp.servers.import" => self.parse_and_call(&request, |s, p: McpServersImportRequest| s.mcp_servers_import(p)),
             "mcp.servers.remove" => {
                 self.parse_and_call(&request, |s, p: McpServerRemoveRequest| s.mcp_servers_remove(p))
             }
@@ -596,14 +1671,51 @@ where
             }
             "project.open" => self.parse_and_call(&request, |s, p: ProjectOpenRequest| s.project_open(p)),
             "project.status" => self.parse_and_call(&request, |s, p: ProjectStatusRequest| s.project_status(p)),
+            "project.list" => self.parse_and_call(&request, |s, _p: EmptyParams| s.project_list()),
+            "project.recent" => self.parse_and_call(&request, |s, _p: EmptyParams| s.project_recent()),
+            "project.recent.pin" => self.parse_and_call(&request, |s, p: ProjectPinRequest| s.project_pin(p)),
+            "project.switch" => self.parse_and_call(&request, |s, p: ProjectSwitchRequest| s.project_switch(p)),
+            "project.watch" => self.parse_and_call(&request, |s, p: ProjectWatchRequest| s.project_watch(p)),
+            "project.changes" => self.parse_and_call(&request, |s, p: ProjectChangesRequest| s.project_changes(p)),
+            "state.backup" => self.parse_and_call(&request, |s, p: StateBackupRequest| s.state_backup(p)),
+            "state.restore" => self.parse_and_call(&request, |s, p: StateRestoreRequest| s.state_restore(p)),
+            "storage.prune" => self.parse_and_call(&request, |s, p: StoragePruneRequest| s.storage_prune(p)),
+            "policy.explain" => self.parse_and_call(&request, |s, p: PolicyExplainRequest| s.policy_explain(p)),
+            "policy.profile.get" => self.parse_and_call(&request, |s, _: EmptyParams| s.policy_profile_get()),
+            "policy.profile.set" => {
+                self.parse_and_call(&request, |s, p: PolicyProfileSetRequest| s.policy_profile_set(p))
+            }
+            "agent.instructions.get" => {
+                self.parse_and_call(&request, |s, p: AgentInstructionsGetRequest| s.agent_instructions_get(p))
+            }
+            "agent.instructions.set" => {
+                self.parse_and_call(&request, |s, p: AgentInstructionsSetRequest| s.agent_instructions_set(p))
+            }
             "audit.list" => self.parse_and_call(&request, |s, p: AuditListRequest| s.audit_list(p)),
             "audit.get" => self.parse_and_call(&request, |s, p: AuditGetRequest| s.audit_get(p)),
+            "audit.replay" => self.parse_and_call(&request, |s, p: AuditReplayRequest| s.audit_replay(p)),
+            "audit.evidence.get" => {
+                self.parse_and_call(&request, |s, p: AuditEvidenceGetRequest| s.audit_evidence_get(p))
+            }
             "consent.list" => self.parse_and_call(&request, |s, p: ConsentListRequest| s.consent_list(p)),
             "consent.approve" => {
                 self.parse_and_call(&request, |s, p: ConsentActionRequest| s.consent_approve(p))
             }
+            "consent.approve_with_args" => self.parse_and_call(&request, |s, p: ConsentApproveWithArgsRequest| {
+                s.consent_approve_with_args(p)
+            }),
             "consent.deny" => self.parse_and_call(&request, |s, p: ConsentActionRequest| s.consent_deny(p)),
+            "consent.grants" => self.parse_and_call(&request, |s, _: EmptyParams| s.consent_grants()),
+            "consent.revoke" => self.parse_and_call(&request, |s, p: ConsentRevokeRequest| s.consent_revoke(p)),
             "system.health" => self.parse_and_call(&request, |s, _p: EmptyParams| s.system_health()),
+            "system.version" => self.parse_and_call(&request, |s, _p: EmptyParams| s.system_version()),
+            "system.shutdown" => self.parse_and_call(&request, |s, p: SystemShutdownRequest| s.system_shutdown(p)),
+            "events.subscribe" => self.parse_and_call(&request, |s, p: EventsSubscribeRequest| s.events_subscribe(p)),
+            "tasks.create" => self.parse_and_call(&request, |s, p: TasksCreateRequest| s.tasks_create(p)),
+            "tasks.list" => self.parse_and_call(&request, |s, _: EmptyParams| s.tasks_list()),
+            "tasks.delete" => self.parse_and_call(&request, |s, p: TasksDeleteRequest| s.tasks_delete(p)),
+            "workflow.run" => self.parse_and_call(&request, |s, p: WorkflowRunRequest| s.workflow_run(p)),
+            "system.describe" => serialize_ok(request.id, describe::describe()),
             "rpc.raw" => {
                 match serde_json::from_str::<RawRpcRequest>(&request.params_json) {
                     Ok(inner) => self.handle(Request::new(
@@ -614,10 +1726,50 @@ where
                     Err(err) => Response::error(request.id, -32602, format!("invalid params: {err}")),
                 }
             }
+            "rpc.batch" => {
+                match serde_json::from_str::<RpcBatchRequest>(&request.params_json) {
+                    Ok(batch) => serialize_ok(request.id, self.run_batch(batch)),
+                    Err(err) => Response::error(request.id, -32602, format!("invalid params: {err}")),
+                }
+            }
             _ => Response::error(request.id, -32601, "method not found"),
         }
     }
 
+    /// Handles a raw JSON-RPC 2.0 payload, single or batch. Notifications run but aren't
+    /// answered, so `None` means there is nothing to send back.
+    pub fn handle_json(&mut self, raw: &str) -> Option<String> {
+        let reply = match parse_payload(raw) {
+            Ok(payload) => self.handle_payload(payload)?,
+            Err(response) => response.to_json(),
+        };
+        Some(reply.to_string())
+    }
+
+    pub fn handle_payload(&mut self, payload: Payload) -> Option<serde_json::Value> {
+        match payload {
+            Payload::Single(call) => self.handle_call(call).map(|r| r.to_json()),
+            Payload::Batch(calls) => {
+                let replies = calls
+                    .into_iter()
+                    .filter_map(|call| self.handle_call(call))
+                    .map(|r| r.to_json())
+                    .collect::<Vec<_>>();
+                (!replies.is_empty()).then_some(serde_json::Value::Array(replies))
+            }
+        }
+    }
+
+    fn handle_call(&mut self, call: Result<Call, Response>) -> Option<Response> {
+        let call = match call {
+            Ok(call) => call,
+            Err(response) => return Some(response),
+        };
+        let notification = call.id.is_none();
+        let response = self.handle(call.into_request());
+        (!notification).then_some(response)
+    }
+
     pub fn service_mut(&mut self) -> &mut S {
         &mut self.service
     }
@@ -644,6 +1796,61 @@ where
             ),
         }
     }
+
+    /// Each call goes through `handle`, so middleware sees it like a standalone request.
+    fn run_batch(&mut self, batch: RpcBatchRequest) -> RpcBatchResponse {
+        let mut responses = Vec::with_capacity(batch.requests.len());
+        let mut aborted = false;
+        for (index, call) in batch.requests.into_iter().enumerate() {
+            if aborted {
+                responses.push(RpcBatchResult {
+                    method: call.method,
+                    ok: false,
+                    skipped: true,
+                    result: None,
+                    error: None,
+                });
+                continue;
+            }
+            let id = Id::Number(index as u64);
+            let response = if call.method == "rpc.batch" {
+                Response::error(id, jsonrpc::INVALID_REQUEST, "rpc.batch cannot be nested")
+            } else {
+                self.handle(
+                    Call {
+                        id: Some(id),
+                        method: call.method.clone(),
+                        params: call.params,
+                    }
+                    .into_request(),
+                )
+            };
+            let result = match response.error {
+                Some(error) => RpcBatchResult {
+                    method: call.method,
+                    ok: false,
+                    skipped: false,
+                    result: None,
+                    error: Some(RpcBatchError {
+                        code: error.code,
+                        message: error.message,
+                    }),
+                },
+                None => RpcBatchResult {
+                    method: call.method,
+                    ok: true,
+                    skipped: false,
+                    result: response
+                        .result_json
+                        .map(|raw| serde_json::from_str(&raw).unwrap_or(serde_json::Value::String(raw))),
+                    error: None,
+                },
+            };
+            aborted = batch.abort_on_error && !result.ok;
+            responses.push(result);
+        }
+        RpcBatchResponse { responses }
+    }
 }
 
 pub struct JsonRpcClient<'a, S> {
@@ -658,6 +1865,11 @@ where
         Self { server }
     }
 
+    /// Direct access for in-process hosts that need more than the RPC surface.
+    pub fn service_mut(&mut self) -> &mut S {
+        self.server.service_mut()
+    }
+
     pub fn chat_request(&mut self, params: ChatRequest) -> ChatResponse {
         self.server.service_mut().chat_request(params)
     }
@@ -683,10 +1895,13 @@ pub fn sample_messages(user_message: &str) -> Vec<ChatMessage> {
     vec![ChatMessage {
         role: "user".to_string(),
         content: user_message.to_string(),
+        parts: Vec::new(),
+        message_id: None,
+        created_at_unix_seconds: None,
     }]
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct EmptyParams {}
 
 fn serialize_ok<T: Serialize>(id: Id, value: T) -> Response {
@@ -1,20 +1,28 @@
 pub mod anthropic_stub;
 pub mod gemini_stub;
+pub mod multimodal;
 pub mod openai_http;
 pub mod openai_stub;
 pub mod provider_trait;
+pub mod registry;
+pub mod scripted;
+pub mod secrets;
 
 use crate::anthropic_stub::AnthropicStubProvider;
 use crate::gemini_stub::GeminiStubProvider;
 use crate::openai_http::OpenAiHttpProvider;
 use crate::openai_stub::OpenAiStubProvider;
 use crate::provider_trait::Provider;
+use crate::registry::UnavailableProvider;
+use crate::scripted::{ScriptedProvider, SCRIPTED_PROVIDER};
 
 pub enum ProviderChoice {
     OpenAi(OpenAiHttpProvider),
     OpenAiStub(OpenAiStubProvider),
     Anthropic(AnthropicStubProvider),
     Gemini(GeminiStubProvider),
+    Scripted(ScriptedProvider),
+    Unavailable(UnavailableProvider),
 }
 
 impl ProviderChoice {
@@ -22,15 +30,23 @@ impl ProviderChoice {
         &["openai", "openai-stub", "anthropic-stub", "gemini-stub"]
     }
 
-    pub fn by_name(name: &str) -> Self {
+    pub fn builtin(name: &str) -> Option<Self> {
         match name {
-            "openai" => Self::OpenAi(OpenAiHttpProvider),
-            "anthropic" | "anthropic-stub" => Self::Anthropic(AnthropicStubProvider),
-            "gemini" | "gemini-stub" => Self::Gemini(GeminiStubProvider),
-            "openai-stub" => Self::OpenAiStub(OpenAiStubProvider),
-            _ => Self::OpenAi(OpenAiHttpProvider),
+            "openai" => Some(Self::OpenAi(OpenAiHttpProvider::default())),
+            "anthropic" | "anthropic-stub" => Some(Self::Anthropic(AnthropicStubProvider)),
+            "gemini" | "gemini-stub" => Some(Self::Gemini(GeminiStubProvider)),
+            "openai-stub" => Some(Self::OpenAiStub(OpenAiStubProvider)),
+            SCRIPTED_PROVIDER => Some(Self::Scripted(ScriptedProvider)),
+            _ => None,
         }
     }
+
+    /// Built-ins only; configured names go through `registry::ProviderRegistry`.
+    pub fn by_name(name: &str) -> Self {
+        Self::builtin(name).unwrap_or_else(|| {
+            Self::Unavailable(UnavailableProvider::new(format!("provider '{name}' is not a built-in provider")))
+        })
+    }
 }
 
 impl Provider for ProviderChoice {
@@ -40,6 +56,8 @@ impl Provider for ProviderChoice {
             Self::OpenAiStub(inner) => inner.name(),
             Self::Anthropic(inner) => inner.name(),
             Self::Gemini(inner) => inner.name(),
+            Self::Scripted(inner) => inner.name(),
+            Self::Unavailable(inner) => inner.name(),
         }
     }
 
@@ -55,6 +73,70 @@ impl Provider for ProviderChoice {
             Self::OpenAiStub(inner) => inner.chat(messages, tools, tool_results, config),
             Self::Anthropic(inner) => inner.chat(messages, tools, tool_results, config),
             Self::Gemini(inner) => inner.chat(messages, tools, tool_results, config),
+            Self::Scripted(inner) => inner.chat(messages, tools, tool_results, config),
+            Self::Unavailable(inner) => inner.chat(messages, tools, tool_results, config),
+        }
+    }
+
+    fn chat_streaming(
+        &self,
+        messages: &[ipc::ChatMessage],
+        tools: &[ipc::Tool],
+        tool_results: &[ipc::ToolResult],
+        config: &ipc::ProviderConfig,
+        on_token: &mut dyn FnMut(&str),
+    ) -> crate::provider_trait::ProviderReply {
+        match self {
+            Self::OpenAi(inner) => inner.chat_streaming(messages, tools, tool_results, config, on_token),
+            Self::OpenAiStub(inner) => inner.chat_streaming(messages, tools, tool_results, config, on_token),
+            Self::Anthropic(inner) => inner.chat_streaming(messages, tools, tool_results, config, on_token),
+            Self::Gemini(inner) => inner.chat_streaming(messages, tools, tool_results, config, on_token),
+            Self::Scripted(inner) => inner.chat_streaming(messages, tools, tool_results, config, on_token),
+            Self::Unavailable(inner) => inner.chat_streaming(messages, tools, tool_results, config, on_token),
+        }
+    }
+
+    fn last_retry_count(&self) -> u32 {
+        match self {
+            Self::OpenAi(inner) => inner.last_retry_count(),
+            Self::OpenAiStub(inner) => inner.last_retry_count(),
+            Self::Anthropic(inner) => inner.last_retry_count(),
+            Self::Gemini(inner) => inner.last_retry_count(),
+            Self::Scripted(inner) => inner.last_retry_count(),
+            Self::Unavailable(inner) => inner.last_retry_count(),
+        }
+    }
+
+    fn is_remote(&self, config: &ipc::ProviderConfig) -> bool {
+        match self {
+            Self::OpenAi(inner) => inner.is_remote(config),
+            Self::OpenAiStub(inner) => inner.is_remote(config),
+            Self::Anthropic(inner) => inner.is_remote(config),
+            Self::Gemini(inner) => inner.is_remote(config),
+            Self::Scripted(inner) => inner.is_remote(config),
+            Self::Unavailable(inner) => inner.is_remote(config),
+        }
+    }
+
+    fn list_models(&self, config: &ipc::ProviderConfig) -> Result<Vec<String>, String> {
+        match self {
+            Self::OpenAi(inner) => inner.list_models(config),
+            Self::OpenAiStub(inner) => inner.list_models(config),
+            Self::Anthropic(inner) => inner.list_models(config),
+            Self::Gemini(inner) => inner.list_models(config),
+            Self::Scripted(inner) => inner.list_models(config),
+            Self::Unavailable(inner) => inner.list_models(config),
+        }
+    }
+
+    fn embed(&self, texts: &[String], model: Option<&str>, config: &ipc::ProviderConfig) -> Result<Vec<Vec<f32>>, String> {
+        match self {
+            Self::OpenAi(inner) => inner.embed(texts, model, config),
+            Self::OpenAiStub(inner) => inner.embed(texts, model, config),
+            Self::Anthropic(inner) => inner.embed(texts, model, config),
+            Self::Gemini(inner) => inner.embed(texts, model, config),
+            Self::Scripted(inner) => inner.embed(texts, model, config),
+            Self::Unavailable(inner) => inner.embed(texts, model, config),
         }
     }
 }
@@ -0,0 +1,139 @@
+//! Maps `ChatMessage` content parts onto provider wire formats.
+
+use std::fs;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ipc::{ChatMessage, ContentPart};
+use serde_json::{json, Value};
+
+/// Matches the per-image limit of the hosted vision APIs.
+pub const MAX_IMAGE_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Reads an image into an `ImageBase64` part; the media type comes from the file's magic bytes.
+pub fn image_part_from_path(path: &str) -> Result<ContentPart, String> {
+    let (media_type, data) = load_image(path)?;
+    Ok(ContentPart::ImageBase64 { media_type, data })
+}
+
+fn load_image(path: &str) -> Result<(String, String), String> {
+    let size = fs::metadata(path)
+        .map_err(|err| format!("failed to read image {path}: {err}"))?
+        .len();
+    if size > MAX_IMAGE_BYTES {
+        return Err(format!("image {path} is {size} bytes; the limit is {MAX_IMAGE_BYTES}"));
+    }
+    let bytes = fs::read(path).map_err(|err| format!("failed to read image {path}: {err}"))?;
+    let media_type = sniff_image_type(&bytes)
+        .ok_or_else(|| format!("{path} is not a PNG, JPEG, GIF or WebP image"))?;
+    Ok((media_type.to_string(), STANDARD.encode(bytes)))
+}
+
+fn sniff_image_type(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [0xff, 0xd8, 0xff, ..] => Some("image/jpeg"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// `(media_type, base64)` for image parts, reading `ImagePath`s from disk.
+fn image_data(part: &ContentPart) -> Result<Option<(String, String)>, String> {
+    match part {
+        ContentPart::Text { .. } => Ok(None),
+        ContentPart::ImagePath { path } => load_image(path).map(Some),
+        ContentPart::ImageBase64 { media_type, data } => Ok(Some((media_type.clone(), data.clone()))),
+    }
+}
+
+/// A plain string for text-only messages, else Chat Completions content parts.
+pub fn openai_content(message: &ChatMessage) -> Result<Value, String> {
+    if message.parts.is_empty() {
+        return Ok(json!(message.content));
+    }
+    let mut out = Vec::with_capacity(message.parts.len() + 1);
+    if !message.content.is_empty() {
+        out.push(json!({ "type": "text", "text": message.content }));
+    }
+    for part in &message.parts {
+        match (part, image_data(part)?) {
+            (ContentPart::Text { text }, _) => out.push(json!({ "type": "text", "text": text })),
+            (_, Some((media_type, data))) => out.push(json!({
+                "type": "image_url",
+                "image_url": { "url": format!("data:{media_type};base64,{data}") },
+            })),
+            (_, None) => {}
+        }
+    }
+    Ok(Value::Array(out))
+}
+
+/// A plain string for text-only messages, else Messages API content blocks.
+pub fn anthropic_content(message: &ChatMessage) -> Result<Value, String> {
+    if message.parts.is_empty() {
+        return Ok(json!(message.content));
+    }
+    let mut out = Vec::with_capacity(message.parts.len() + 1);
+    if !message.content.is_empty() {
+        out.push(json!({ "type": "text", "text": message.content }));
+    }
+    for part in &message.parts {
+        match (part, image_data(part)?) {
+            (ContentPart::Text { text }, _) => out.push(json!({ "type": "text", "text": text })),
+            (_, Some((media_type, data))) => out.push(json!({
+                "type": "image",
+                "source": { "type": "base64", "media_type": media_type, "data": data },
+            })),
+            (_, None) => {}
+        }
+    }
+    Ok(Value::Array(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG_HEADER: &[u8] = &[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+    #[test]
+    fn maps_image_parts_to_openai_and_anthropic_payloads() {
+        let dir = std::env::temp_dir().join(format!("multimodal-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("mkdir");
+        let image = dir.join("pixel.png");
+        fs::write(&image, PNG_HEADER).expect("write image");
+        let message = ChatMessage {
+            role: "user".to_string(),
+            content: "what is this?".to_string(),
+            parts: vec![ContentPart::ImagePath {
+                path: image.display().to_string(),
+            }],
+            message_id: None,
+            created_at_unix_seconds: None,
+        };
+
+        let openai = openai_content(&message).expect("openai");
+        assert_eq!(openai[0], json!({ "type": "text", "text": "what is this?" }));
+        assert_eq!(openai[1]["image_url"]["url"], json!("data:image/png;base64,iVBORw0KGgo="));
+        let anthropic = anthropic_content(&message).expect("anthropic");
+        assert_eq!(
+            anthropic[1]["source"],
+            json!({ "type": "base64", "media_type": "image/png", "data": "iVBORw0KGgo=" })
+        );
+        assert_eq!(
+            image_part_from_path(&image.display().to_string()),
+            Ok(ContentPart::ImageBase64 {
+                media_type: "image/png".to_string(),
+                data: "iVBORw0KGgo=".to_string(),
+            })
+        );
+
+        fs::write(&image, b"not an image").expect("write text");
+        assert!(openai_content(&message).is_err());
+        let text_only = ChatMessage { parts: Vec::new(), ..message };
+        assert_eq!(openai_content(&text_only), Ok(json!("what is this?")));
+        let _ = fs::remove_dir_all(dir);
+    }
+}
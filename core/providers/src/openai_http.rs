@@ -1,11 +1,26 @@
+use std::cell::Cell;
+use std::collections::BTreeMap;
 use std::env;
+use std::io::BufRead;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use ipc::{ChatMessage, ProviderConfig, Tool, ToolCall, ToolResult};
 use serde_json::{json, Value};
 
+use crate::multimodal;
 use crate::provider_trait::{Provider, ProviderReply};
 
-pub struct OpenAiHttpProvider;
+const DEFAULT_MAX_RETRIES: u32 = 2;
+const DEFAULT_RETRY_BACKOFF_MS: u64 = 500;
+const MAX_RETRY_BACKOFF_MS: u64 = 30_000;
+const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+const DEFAULT_AZURE_API_VERSION: &str = "2024-10-21";
+
+#[derive(Default)]
+pub struct OpenAiHttpProvider {
+    last_retries: Cell<u32>,
+}
 
 impl Provider for OpenAiHttpProvider {
     fn name(&self) -> &'static str {
@@ -19,62 +34,529 @@ impl Provider for OpenAiHttpProvider {
         tool_results: &[ToolResult],
         config: &ProviderConfig,
     ) -> ProviderReply {
+        self.complete(messages, tools, tool_results, config, None)
+    }
+
+    /// Sends `"stream": true` and forwards content deltas; servers that answer with a plain
+    /// completion instead are handled like `chat`.
+    fn chat_streaming(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[Tool],
+        tool_results: &[ToolResult],
+        config: &ProviderConfig,
+        on_token: &mut dyn FnMut(&str),
+    ) -> ProviderReply {
+        self.complete(messages, tools, tool_results, config, Some(on_token))
+    }
+
+    fn last_retry_count(&self) -> u32 {
+        self.last_retries.get()
+    }
+
+    /// Endpoints on a loopback host (Ollama, a local LiteLLM) count as local.
+    fn is_remote(&self, config: &ProviderConfig) -> bool {
+        let Some(base_url) = ProviderRuntimeConfig::from_provider_config(config).base_url else {
+            return true;
+        };
+        let authority = base_url.split_once("://").map_or(base_url.as_str(), |(_, rest)| rest);
+        let authority = authority.split('/').next().unwrap_or_default();
+        let host = match authority.strip_prefix('[') {
+            Some(bracketed) => bracketed.split(']').next().unwrap_or_default(),
+            None => authority.split(':').next().unwrap_or_default(),
+        };
+        !matches!(host.to_ascii_lowercase().as_str(), "localhost" | "127.0.0.1" | "::1")
+    }
+
+    fn list_models(&self, config: &ProviderConfig) -> Result<Vec<String>, String> {
         let provider_cfg = ProviderRuntimeConfig::from_provider_config(config);
-        let api_key = match resolve_api_key(config) {
-            Some(v) => v,
-            None => {
-                return ProviderReply::FinalText(
-                    "OpenAI-compatible provider is selected but no API key was found in provider config or environment."
-                        .to_string(),
-                )
+        let endpoint = Endpoint::from_runtime(&provider_cfg)?;
+        let headers = endpoint.headers(resolve_api_key(config).as_deref());
+
+        // OpenAI-compatible servers expose `/v1/models`; fall back to Ollama's native tag listing.
+        let openai_url = endpoint.models_url();
+        let openai_err = match http_get_json(&openai_url, &headers) {
+            Ok(payload) => {
+                if let Some(models) = parse_openai_models_payload(&payload) {
+                    return Ok(models);
+                }
+                "unexpected /v1/models payload".to_string()
             }
+            Err(err) => err,
         };
 
-        let base_url = provider_cfg
-            .base_url
-            .or_else(|| env::var("OPENAI_BASE_URL").ok())
-            .unwrap_or_else(|| "https://api.openai.com".to_string())
-            .trim_end_matches('/')
-            .to_string();
+        let Some(ollama_url) = endpoint.ollama_url("tags") else {
+            return Err(format!("model listing failed: {openai_err}"));
+        };
+        match http_get_json(&ollama_url, &headers) {
+            Ok(payload) => parse_ollama_tags_payload(&payload)
+                .ok_or_else(|| format!("model listing failed: {openai_err}")),
+            Err(_) => Err(format!("model listing failed: {openai_err}")),
+        }
+    }
+
+    fn embed(&self, texts: &[String], model: Option<&str>, config: &ProviderConfig) -> Result<Vec<Vec<f32>>, String> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        let provider_cfg = ProviderRuntimeConfig::from_provider_config(config);
+        let endpoint = Endpoint::from_runtime(&provider_cfg)?;
+        let headers = endpoint.headers(resolve_api_key(config).as_deref());
+        let model = resolve_embedding_model(model, provider_cfg.embedding_model);
+        let body = json!({ "model": model, "input": texts });
+
+        // Same fallback as `list_models`: OpenAI-compatible first, then Ollama's native API.
+        let openai_url = endpoint.url("embeddings", &model);
+        let openai_err = match http_post_json(&openai_url, &headers, &body) {
+            Ok(payload) => match parse_openai_embeddings_payload(&payload, texts.len()) {
+                Some(vectors) => return Ok(vectors),
+                None => "unexpected /v1/embeddings payload".to_string(),
+            },
+            Err(err) => err,
+        };
+        let Some(ollama_url) = endpoint.ollama_url("embed") else {
+            return Err(format!("embedding request failed: {openai_err}"));
+        };
+        match http_post_json(&ollama_url, &headers, &body) {
+            Ok(payload) => parse_ollama_embed_payload(&payload, texts.len())
+                .ok_or_else(|| format!("embedding request failed: {openai_err}")),
+            Err(_) => Err(format!("embedding request failed: {openai_err}")),
+        }
+    }
+}
+
+impl OpenAiHttpProvider {
+    fn complete(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[Tool],
+        tool_results: &[ToolResult],
+        config: &ProviderConfig,
+        on_token: Option<&mut dyn FnMut(&str)>,
+    ) -> ProviderReply {
+        self.last_retries.set(0);
+        let provider_cfg = ProviderRuntimeConfig::from_provider_config(config);
+        // Local OpenAI-compatible servers (Ollama, LiteLLM) usually need no key; only the default
+        // OpenAI endpoint insists on one.
+        let api_key = resolve_api_key(config);
+        if api_key.is_none() && provider_cfg.base_url.is_none() {
+            tracing::warn!(provider = "openai", "no api key configured");
+            return ProviderReply::FinalText(
+                "OpenAI-compatible provider is selected but no API key was found in provider config or environment."
+                    .to_string(),
+            );
+        }
+
+        let endpoint = match Endpoint::from_runtime(&provider_cfg) {
+            Ok(endpoint) => endpoint,
+            Err(err) => return ProviderReply::FinalText(format!("OpenAI provider is misconfigured: {err}")),
+        };
         let model = provider_cfg
             .model
+            .clone()
             .or_else(|| config.model.clone())
             .or_else(|| env::var("OPENAI_MODEL").ok())
             .filter(|m| !m.trim().is_empty())
             .unwrap_or_else(|| "gpt-4.1-mini".to_string());
 
-        let body = json!({
+        let messages_json = match build_openai_messages(messages, tool_results) {
+            Ok(v) => v,
+            Err(err) => return ProviderReply::FinalText(format!("OpenAI provider could not build the request: {err}")),
+        };
+        let mut body = json!({
             "model": model,
-            "messages": build_openai_messages(messages, tool_results),
+            "messages": messages_json,
             "tools": build_openai_tools(tools),
             "tool_choice": "auto",
         });
+        apply_generation_params(&mut body, config);
+        if on_token.is_some() {
+            body["stream"] = json!(true);
+        }
 
-        let url = format!("{base_url}/v1/chat/completions");
-        let response = match ureq::post(&url)
-            .set("Authorization", &format!("Bearer {}", api_key))
-            .set("Content-Type", "application/json")
-            .send_json(body)
-        {
-            Ok(resp) => resp,
-            Err(err) => {
-                return ProviderReply::FinalText(format!(
-                    "OpenAI provider request failed: {err}. Use 'openai-stub' for offline testing."
-                ))
+        let url = endpoint.url("chat/completions", &model);
+        let headers = endpoint.headers(api_key.as_deref());
+        let max_retries = provider_cfg.max_retries.unwrap_or(DEFAULT_MAX_RETRIES);
+        let backoff_ms = provider_cfg
+            .retry_backoff_ms
+            .unwrap_or(DEFAULT_RETRY_BACKOFF_MS);
+        let mut attempt = 0u32;
+        tracing::debug!(provider = "openai", %url, %model, messages = messages.len(), tools = tools.len(), "chat request");
+        let response = loop {
+            let result = headers
+                .iter()
+                .fold(ureq::post(&url), |request, (name, value)| request.set(name, value))
+                .set("Content-Type", "application/json")
+                .send_json(body.clone());
+            match result {
+                Ok(resp) => break resp,
+                Err(err) if attempt < max_retries && is_retryable_error(&err) => {
+                    let server_hint_ms = match &err {
+                        ureq::Error::Status(_, resp) => retry_after_ms(resp.header("Retry-After")),
+                        ureq::Error::Transport(_) => None,
+                    };
+                    let delay = server_hint_ms
+                        .unwrap_or_else(|| retry_delay_ms(backoff_ms, attempt, jitter_seed()));
+                    tracing::warn!(provider = "openai", attempt, delay_ms = delay, error = %err, "retrying chat request");
+                    thread::sleep(Duration::from_millis(delay));
+                    attempt += 1;
+                    self.last_retries.set(attempt);
+                }
+                Err(err) => {
+                    tracing::error!(provider = "openai", attempt, error = %err, "chat request failed");
+                    let retry_note = if attempt > 0 {
+                        format!(" after {attempt} retries")
+                    } else {
+                        String::new()
+                    };
+                    return ProviderReply::FinalText(format!(
+                        "OpenAI provider request failed{retry_note}: {err}. Use 'openai-stub' for offline testing."
+                    ));
+                }
             }
         };
 
+        let on_token = match on_token {
+            Some(on_token) if response.content_type() == "text/event-stream" => {
+                return read_chat_stream(std::io::BufReader::new(response.into_reader()), on_token);
+            }
+            other => other,
+        };
         let payload: Value = match response.into_json() {
             Ok(v) => v,
             Err(err) => {
+                tracing::error!(provider = "openai", error = %err, "invalid chat response json");
                 return ProviderReply::FinalText(format!(
                     "OpenAI provider returned invalid JSON: {err}"
                 ))
             }
         };
 
-        interpret_chat_completion_payload(&payload)
+        let reply = interpret_chat_completion_payload(&payload);
+        if let (Some(on_token), ProviderReply::FinalText(text)) = (on_token, &reply) {
+            on_token(text);
+        }
+        reply
+    }
+}
+
+/// Reads the `data:` events of a streamed completion until `[DONE]`, forwarding content deltas
+/// and assembling tool-call fragments by their index.
+fn read_chat_stream(reader: impl BufRead, on_token: &mut dyn FnMut(&str)) -> ProviderReply {
+    let mut stream = ChatStream::default();
+    for line in reader.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                tracing::error!(provider = "openai", error = %err, "chat stream interrupted");
+                break;
+            }
+        };
+        let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+            continue;
+        };
+        if data == "[DONE]" {
+            break;
+        }
+        match serde_json::from_str::<Value>(data) {
+            Ok(chunk) => {
+                if let Some(delta) = stream.apply(&chunk) {
+                    on_token(delta);
+                }
+            }
+            Err(err) => tracing::warn!(provider = "openai", error = %err, "skipping malformed stream event"),
+        }
     }
+    stream.into_reply()
+}
+
+#[derive(Default)]
+struct ChatStream {
+    text: String,
+    tool_calls: BTreeMap<u64, PartialToolCall>,
+}
+
+#[derive(Default)]
+struct PartialToolCall {
+    id: Option<String>,
+    name: String,
+    arguments: String,
+}
+
+impl ChatStream {
+    /// Folds one chunk in and returns its content delta, if any.
+    fn apply<'a>(&mut self, chunk: &'a Value) -> Option<&'a str> {
+        let delta = chunk
+            .get("choices")
+            .and_then(Value::as_array)
+            .and_then(|choices| choices.first())
+            .and_then(|c| c.get("delta"))?;
+        for call in delta.get("tool_calls").and_then(Value::as_array).into_iter().flatten() {
+            let index = call.get("index").and_then(Value::as_u64).unwrap_or(0);
+            let partial = self.tool_calls.entry(index).or_default();
+            if let Some(id) = call.get("id").and_then(Value::as_str) {
+                partial.id = Some(id.to_string());
+            }
+            if let Some(function) = call.get("function") {
+                partial.name.push_str(function.get("name").and_then(Value::as_str).unwrap_or(""));
+                partial
+                    .arguments
+                    .push_str(function.get("arguments").and_then(Value::as_str).unwrap_or(""));
+            }
+        }
+        let text = delta.get("content").and_then(Value::as_str).filter(|s| !s.is_empty())?;
+        self.text.push_str(text);
+        Some(text)
+    }
+
+    fn into_reply(self) -> ProviderReply {
+        let calls = self
+            .tool_calls
+            .into_values()
+            .filter(|call| !call.name.is_empty())
+            .map(|call| ToolCall {
+                tool_call_id: call.id,
+                name: call.name,
+                arguments_json: if call.arguments.trim().is_empty() {
+                    "{}".to_string()
+                } else {
+                    call.arguments
+                },
+            })
+            .collect::<Vec<_>>();
+        if !calls.is_empty() {
+            return ProviderReply::ToolCalls(calls);
+        }
+        if self.text.trim().is_empty() {
+            return ProviderReply::FinalText("OpenAI provider returned no text content and no tool calls.".to_string());
+        }
+        ProviderReply::FinalText(self.text)
+    }
+}
+
+fn apply_generation_params(body: &mut Value, config: &ProviderConfig) {
+    let Some(obj) = body.as_object_mut() else {
+        return;
+    };
+    if let Some(temperature) = config.temperature {
+        obj.insert("temperature".to_string(), json!(temperature));
+    }
+    if let Some(max_tokens) = config.max_tokens {
+        obj.insert("max_tokens".to_string(), json!(max_tokens));
+    }
+    if let Some(top_p) = config.top_p {
+        obj.insert("top_p".to_string(), json!(top_p));
+    }
+    if !config.stop.is_empty() {
+        obj.insert("stop".to_string(), json!(config.stop));
+    }
+}
+
+/// Where requests go and how they authenticate. `"api": "azure"` in the provider config selects
+/// Azure OpenAI; anything else is the OpenAI wire format, which also covers gateways such as
+/// OpenRouter or LiteLLM given a `base_url`.
+#[derive(Debug, PartialEq)]
+struct Endpoint {
+    base_url: String,
+    azure: Option<AzureTarget>,
+    /// `headers` from the provider config, e.g. OpenRouter's `HTTP-Referer`.
+    extra_headers: Vec<(String, String)>,
+}
+
+#[derive(Debug, PartialEq)]
+struct AzureTarget {
+    /// Falls back to the model name, which is how most deployments are named.
+    deployment: Option<String>,
+    api_version: String,
+}
+
+impl Endpoint {
+    fn from_runtime(cfg: &ProviderRuntimeConfig) -> Result<Self, String> {
+        let azure = match cfg.api.as_deref() {
+            None | Some("openai") => None,
+            Some("azure") => Some(AzureTarget {
+                deployment: cfg.deployment.clone(),
+                api_version: cfg
+                    .api_version
+                    .clone()
+                    .or_else(|| env::var("OPENAI_API_VERSION").ok())
+                    .unwrap_or_else(|| DEFAULT_AZURE_API_VERSION.to_string()),
+            }),
+            Some(other) => return Err(format!("unknown api '{other}' (expected 'openai' or 'azure')")),
+        };
+        let base_url = match (&azure, cfg.base_url.clone()) {
+            (_, Some(url)) => url,
+            (Some(_), None) => env::var("AZURE_OPENAI_ENDPOINT")
+                .map_err(|_| "azure needs base_url (or AZURE_OPENAI_ENDPOINT)".to_string())?,
+            (None, None) => env::var("OPENAI_BASE_URL").unwrap_or_else(|_| "https://api.openai.com".to_string()),
+        };
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            azure,
+            extra_headers: cfg.headers.clone(),
+        })
+    }
+
+    /// `path` is relative to the API root, e.g. `chat/completions`. A `base_url` that already
+    /// ends in `/v1` (as gateways often document it) is not given a second one.
+    fn url(&self, path: &str, model: &str) -> String {
+        match &self.azure {
+            Some(azure) => format!(
+                "{}/openai/deployments/{}/{path}?api-version={}",
+                self.base_url,
+                azure.deployment.as_deref().unwrap_or(model),
+                azure.api_version
+            ),
+            None if self.base_url.ends_with("/v1") => format!("{}/{path}", self.base_url),
+            None => format!("{}/v1/{path}", self.base_url),
+        }
+    }
+
+    fn models_url(&self) -> String {
+        match &self.azure {
+            Some(azure) => format!("{}/openai/models?api-version={}", self.base_url, azure.api_version),
+            None => self.url("models", ""),
+        }
+    }
+
+    /// Ollama's native API, tried when the OpenAI-style call fails; never for Azure.
+    fn ollama_url(&self, path: &str) -> Option<String> {
+        self.azure
+            .is_none()
+            .then(|| format!("{}/api/{path}", self.base_url.trim_end_matches("/v1")))
+    }
+
+    fn headers(&self, api_key: Option<&str>) -> Vec<(String, String)> {
+        let mut out = Vec::with_capacity(self.extra_headers.len() + 1);
+        if let Some(key) = api_key {
+            out.push(match self.azure {
+                Some(_) => ("api-key".to_string(), key.to_string()),
+                None => ("Authorization".to_string(), format!("Bearer {key}")),
+            });
+        }
+        out.extend(self.extra_headers.iter().cloned());
+        out
+    }
+}
+
+/// Explicit override, then `embedding_model` in the provider config, then `OPENAI_EMBEDDING_MODEL`.
+fn resolve_embedding_model(requested: Option<&str>, configured: Option<String>) -> String {
+    requested
+        .map(str::to_string)
+        .or(configured)
+        .or_else(|| env::var("OPENAI_EMBEDDING_MODEL").ok())
+        .filter(|m| !m.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EMBEDDING_MODEL.to_string())
+}
+
+fn http_get_json(url: &str, headers: &[(String, String)]) -> Result<Value, String> {
+    let request = headers
+        .iter()
+        .fold(ureq::get(url).timeout(Duration::from_secs(10)), |request, (name, value)| request.set(name, value));
+    let response = request.call().map_err(|err| err.to_string())?;
+    response
+        .into_json::<Value>()
+        .map_err(|err| format!("invalid JSON from {url}: {err}"))
+}
+
+fn http_post_json(url: &str, headers: &[(String, String)], body: &Value) -> Result<Value, String> {
+    let request = headers
+        .iter()
+        .fold(ureq::post(url).timeout(Duration::from_secs(60)), |request, (name, value)| request.set(name, value));
+    let response = request.send_json(body.clone()).map_err(|err| err.to_string())?;
+    response
+        .into_json::<Value>()
+        .map_err(|err| format!("invalid JSON from {url}: {err}"))
+}
+
+/// `data[].embedding`, reordered by `index` when present.
+fn parse_openai_embeddings_payload(payload: &Value, expected: usize) -> Option<Vec<Vec<f32>>> {
+    let data = payload.get("data").and_then(Value::as_array)?;
+    let mut indexed = data
+        .iter()
+        .enumerate()
+        .map(|(pos, item)| {
+            let index = item.get("index").and_then(Value::as_u64).map_or(pos, |i| i as usize);
+            Some((index, parse_vector(item.get("embedding")?)?))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    indexed.sort_by_key(|(index, _)| *index);
+    let vectors = indexed.into_iter().map(|(_, v)| v).collect::<Vec<_>>();
+    (vectors.len() == expected).then_some(vectors)
+}
+
+fn parse_ollama_embed_payload(payload: &Value, expected: usize) -> Option<Vec<Vec<f32>>> {
+    let vectors = payload
+        .get("embeddings")
+        .and_then(Value::as_array)?
+        .iter()
+        .map(parse_vector)
+        .collect::<Option<Vec<_>>>()?;
+    (vectors.len() == expected).then_some(vectors)
+}
+
+fn parse_vector(value: &Value) -> Option<Vec<f32>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|x| x.as_f64().map(|f| f as f32))
+        .collect()
+}
+
+fn parse_openai_models_payload(payload: &Value) -> Option<Vec<String>> {
+    let data = payload.get("data").and_then(Value::as_array)?;
+    let mut models = data
+        .iter()
+        .filter_map(|m| m.get("id").and_then(Value::as_str))
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+    models.sort();
+    models.dedup();
+    Some(models)
+}
+
+fn parse_ollama_tags_payload(payload: &Value) -> Option<Vec<String>> {
+    let entries = payload.get("models").and_then(Value::as_array)?;
+    let mut models = entries
+        .iter()
+        .filter_map(|m| m.get("name").or_else(|| m.get("model")).and_then(Value::as_str))
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+    models.sort();
+    models.dedup();
+    Some(models)
+}
+
+fn is_retryable_error(err: &ureq::Error) -> bool {
+    match err {
+        ureq::Error::Status(code, _) => *code == 429 || (500..=599).contains(code),
+        ureq::Error::Transport(_) => true,
+    }
+}
+
+fn retry_after_ms(header: Option<&str>) -> Option<u64> {
+    let secs = header?.trim().parse::<u64>().ok()?;
+    Some(secs.saturating_mul(1000).min(MAX_RETRY_BACKOFF_MS))
+}
+
+/// Exponential backoff (`base * 2^attempt`) plus up to 50% jitter, capped at 30s.
+fn retry_delay_ms(base_ms: u64, attempt: u32, jitter_seed: u64) -> u64 {
+    let exp = base_ms.saturating_mul(1u64 << attempt.min(16));
+    let capped = exp.min(MAX_RETRY_BACKOFF_MS);
+    let jitter_range = capped / 2;
+    let jitter = if jitter_range == 0 {
+        0
+    } else {
+        jitter_seed % (jitter_range + 1)
+    };
+    (capped + jitter).min(MAX_RETRY_BACKOFF_MS)
+}
+
+fn jitter_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or(0)
 }
 
 fn build_openai_tools(tools: &[Tool]) -> Vec<Value> {
@@ -164,14 +646,14 @@ fn extract_message_text(message: &Value) -> Option<String> {
     }
 }
 
-fn build_openai_messages(messages: &[ChatMessage], tool_results: &[ToolResult]) -> Vec<Value> {
+fn build_openai_messages(messages: &[ChatMessage], tool_results: &[ToolResult]) -> Result<Vec<Value>, String> {
     let mut out = messages
         .iter()
-        .map(|m| json!({"role": m.role, "content": m.content}))
-        .collect::<Vec<_>>();
+        .map(|m| Ok(json!({"role": m.role, "content": multimodal::openai_content(m)?})))
+        .collect::<Result<Vec<_>, String>>()?;
 
     if tool_results.is_empty() {
-        return out;
+        return Ok(out);
     }
 
     // Reconstruct a minimal assistant tool-call message so OpenAI-compatible APIs can accept the
@@ -212,15 +694,23 @@ fn build_openai_messages(messages: &[ChatMessage], tool_results: &[ToolResult])
         }));
     }
 
-    out
+    Ok(out)
 }
 
 #[derive(Debug, Default)]
 struct ProviderRuntimeConfig {
+    api: Option<String>,
     base_url: Option<String>,
+    api_version: Option<String>,
+    deployment: Option<String>,
+    headers: Vec<(String, String)>,
     model: Option<String>,
+    embedding_model: Option<String>,
     api_key: Option<String>,
+    api_key_ref: Option<String>,
     api_key_env: Option<String>,
+    max_retries: Option<u32>,
+    retry_backoff_ms: Option<u64>,
 }
 
 impl ProviderRuntimeConfig {
@@ -232,14 +722,38 @@ impl ProviderRuntimeConfig {
         let Ok(v) = serde_json::from_str::<Value>(raw) else {
             return out;
         };
+        out.api = v
+            .get("api")
+            .or_else(|| v.get("type").filter(|t| t.as_str() == Some("azure")))
+            .and_then(Value::as_str)
+            .map(|s| s.to_string());
         out.base_url = v.get("base_url").and_then(Value::as_str).map(|s| s.to_string());
+        out.api_version = v.get("api_version").and_then(Value::as_str).map(|s| s.to_string());
+        out.deployment = v.get("deployment").and_then(Value::as_str).map(|s| s.to_string());
+        out.headers = v
+            .get("headers")
+            .and_then(Value::as_object)
+            .map(|headers| {
+                headers
+                    .iter()
+                    .filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default();
         out.model = v.get("model").and_then(Value::as_str).map(|s| s.to_string());
+        out.embedding_model = v.get("embedding_model").and_then(Value::as_str).map(|s| s.to_string());
         out.api_key = v.get("api_key").and_then(Value::as_str).map(|s| s.to_string());
+        out.api_key_ref = v.get("api_key_ref").and_then(Value::as_str).map(|s| s.to_string());
         out.api_key_env = v
             .get("api_key_env")
             .or_else(|| v.get("token_env"))
             .and_then(Value::as_str)
             .map(|s| s.to_string());
+        out.max_retries = v
+            .get("max_retries")
+            .and_then(Value::as_u64)
+            .map(|n| n.min(10) as u32);
+        out.retry_backoff_ms = v.get("retry_backoff_ms").and_then(Value::as_u64);
         out
     }
 }
@@ -249,6 +763,12 @@ fn resolve_api_key(config: &ProviderConfig) -> Option<String> {
     if let Some(key) = runtime.api_key.filter(|s| !s.trim().is_empty()) {
         return Some(key);
     }
+    if let Some(reference) = runtime.api_key_ref {
+        match crate::secrets::resolve_secret_ref(&reference) {
+            Ok(key) => return Some(key),
+            Err(err) => tracing::warn!(provider = "openai", error = %err, "api_key_ref not resolved"),
+        }
+    }
     if let Some(env_name) = runtime.api_key_env {
         if let Ok(key) = env::var(env_name) {
             if !key.trim().is_empty() {
@@ -256,9 +776,15 @@ fn resolve_api_key(config: &ProviderConfig) -> Option<String> {
             }
         }
     }
-    if let Ok(key) = env::var("OPENAI_API_KEY") {
-        if !key.trim().is_empty() {
-            return Some(key);
+    let default_envs: &[&str] = match runtime.api.as_deref() {
+        Some("azure") => &["AZURE_OPENAI_API_KEY", "OPENAI_API_KEY"],
+        _ => &["OPENAI_API_KEY"],
+    };
+    for name in default_envs {
+        if let Ok(key) = env::var(name) {
+            if !key.trim().is_empty() {
+                return Some(key);
+            }
         }
     }
     let path = env::var("OPENAI_API_KEY_FILE").ok()?;
@@ -332,10 +858,175 @@ mod tests {
                 r#"{"base_url":"http://127.0.0.1:11434/v1","model":"qwen","api_key_env":"OLLAMA_TOKEN"}"#
                     .to_string(),
             ),
+            ..Default::default()
         };
         let parsed = ProviderRuntimeConfig::from_provider_config(&cfg);
         assert_eq!(parsed.base_url.as_deref(), Some("http://127.0.0.1:11434/v1"));
         assert_eq!(parsed.model.as_deref(), Some("qwen"));
         assert_eq!(parsed.api_key_env.as_deref(), Some("OLLAMA_TOKEN"));
     }
+
+    #[test]
+    fn provider_runtime_config_reads_retry_settings() {
+        let cfg = ProviderConfig {
+            provider_name: "openai".to_string(),
+            model: None,
+            config_json: Some(r#"{"max_retries":3,"retry_backoff_ms":250}"#.to_string()),
+            ..Default::default()
+        };
+        let parsed = ProviderRuntimeConfig::from_provider_config(&cfg);
+        assert_eq!(parsed.max_retries, Some(3));
+        assert_eq!(parsed.retry_backoff_ms, Some(250));
+    }
+
+    #[test]
+    fn retry_delay_grows_exponentially_with_bounded_jitter() {
+        assert_eq!(retry_delay_ms(100, 0, 0), 100);
+        assert_eq!(retry_delay_ms(100, 2, 0), 400);
+        let jittered = retry_delay_ms(100, 2, u64::MAX);
+        assert!((400..=600).contains(&jittered));
+        assert_eq!(retry_delay_ms(10_000, 5, 0), MAX_RETRY_BACKOFF_MS);
+        assert_eq!(retry_after_ms(Some("2")), Some(2000));
+        assert_eq!(retry_after_ms(Some("soon")), None);
+    }
+
+    #[test]
+    fn parses_model_listing_payloads() {
+        let openai = json!({
+            "object": "list",
+            "data": [{"id": "gpt-4.1-mini"}, {"id": "gpt-4.1"}, {"id": "gpt-4.1"}]
+        });
+        assert_eq!(
+            parse_openai_models_payload(&openai),
+            Some(vec!["gpt-4.1".to_string(), "gpt-4.1-mini".to_string()])
+        );
+
+        let ollama = json!({ "models": [{"name": "qwen2.5:7b"}, {"model": "llama3:8b"}] });
+        assert_eq!(
+            parse_ollama_tags_payload(&ollama),
+            Some(vec!["llama3:8b".to_string(), "qwen2.5:7b".to_string()])
+        );
+        assert_eq!(parse_openai_models_payload(&ollama), None);
+    }
+
+    #[test]
+    fn parses_openai_and_ollama_embedding_payloads() {
+        let openai = json!({ "data": [
+            {"index": 1, "embedding": [0.5, -1.0]},
+            {"index": 0, "embedding": [1.0, 0.25]}
+        ] });
+        assert_eq!(
+            parse_openai_embeddings_payload(&openai, 2),
+            Some(vec![vec![1.0, 0.25], vec![0.5, -1.0]])
+        );
+        assert_eq!(parse_openai_embeddings_payload(&openai, 3), None);
+
+        let ollama = json!({ "model": "nomic-embed-text", "embeddings": [[0.1, 0.2], [0.3, 0.4]] });
+        assert_eq!(
+            parse_ollama_embed_payload(&ollama, 2),
+            Some(vec![vec![0.1, 0.2], vec![0.3, 0.4]])
+        );
+        assert_eq!(parse_openai_embeddings_payload(&ollama, 2), None);
+        assert_eq!(resolve_embedding_model(Some("custom"), Some("configured".to_string())), "custom");
+        assert_eq!(resolve_embedding_model(None, Some("configured".to_string())), "configured");
+    }
+
+    #[test]
+    fn endpoint_builds_azure_and_gateway_urls_from_config() {
+        let config = |json: &str| ProviderConfig {
+            provider_name: "custom".to_string(),
+            config_json: Some(json.to_string()),
+            ..Default::default()
+        };
+        let runtime = |json: &str| ProviderRuntimeConfig::from_provider_config(&config(json));
+
+        let azure = Endpoint::from_runtime(&runtime(
+            r#"{"api":"azure","base_url":"https://acme.openai.azure.com/","deployment":"gpt4o-prod","api_version":"2024-06-01"}"#,
+        ))
+        .expect("azure");
+        assert_eq!(
+            azure.url("chat/completions", "gpt-4o"),
+            "https://acme.openai.azure.com/openai/deployments/gpt4o-prod/chat/completions?api-version=2024-06-01"
+        );
+        assert_eq!(azure.headers(Some("k")), vec![("api-key".to_string(), "k".to_string())]);
+        assert_eq!(azure.ollama_url("tags"), None);
+        let by_model = Endpoint::from_runtime(&runtime(r#"{"api":"azure","base_url":"https://acme.openai.azure.com"}"#))
+            .expect("azure without deployment");
+        assert!(by_model
+            .url("embeddings", "text-embedding-3-small")
+            .starts_with("https://acme.openai.azure.com/openai/deployments/text-embedding-3-small/embeddings?api-version="));
+
+        let gateway = Endpoint::from_runtime(&runtime(
+            r#"{"base_url":"https://openrouter.ai/api/v1","headers":{"X-Title":"cmnd-n-ctrl"}}"#,
+        ))
+        .expect("gateway");
+        assert_eq!(gateway.url("chat/completions", "m"), "https://openrouter.ai/api/v1/chat/completions");
+        assert_eq!(
+            gateway.headers(Some("k")),
+            vec![
+                ("Authorization".to_string(), "Bearer k".to_string()),
+                ("X-Title".to_string(), "cmnd-n-ctrl".to_string()),
+            ]
+        );
+        let litellm = Endpoint::from_runtime(&runtime(r#"{"base_url":"http://localhost:4000"}"#)).expect("litellm");
+        assert_eq!(litellm.models_url(), "http://localhost:4000/v1/models");
+        assert_eq!(litellm.ollama_url("tags").as_deref(), Some("http://localhost:4000/api/tags"));
+
+        let provider = OpenAiHttpProvider::default();
+        assert!(!provider.is_remote(&config(r#"{"base_url":"http://localhost:4000"}"#)));
+        assert!(!provider.is_remote(&config(r#"{"base_url":"http://[::1]:11434/v1"}"#)));
+        assert!(provider.is_remote(&config(r#"{"base_url":"https://openrouter.ai/api/v1"}"#)));
+        assert!(provider.is_remote(&config("{}")));
+
+        assert!(Endpoint::from_runtime(&runtime(r#"{"api":"bedrock"}"#)).is_err());
+    }
+
+    #[test]
+    fn reads_streamed_text_and_tool_call_fragments() {
+        let sse = concat!(
+            "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\",\"content\":\"Hel\"}}]}\n\n",
+            ": keep-alive\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"lo\"}}]}\n\n",
+            "data: [DONE]\n\n",
+        );
+        let mut chunks = Vec::new();
+        let reply = read_chat_stream(sse.as_bytes(), &mut |t| chunks.push(t.to_string()));
+        assert_eq!(chunks, vec!["Hel", "lo"]);
+        assert!(matches!(reply, ProviderReply::FinalText(ref text) if text == "Hello"));
+
+        let sse = concat!(
+            "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"function\":{\"name\":\"file.read_text\",\"arguments\":\"\"}}]}}]}\n",
+            "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"{\\\"path\\\":\"}}]}}]}\n",
+            "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"\\\"a.txt\\\"}\"}}]}}]}\n",
+            "data: [DONE]\n",
+        );
+        let ProviderReply::ToolCalls(calls) = read_chat_stream(sse.as_bytes(), &mut |_| {}) else {
+            panic!("expected tool calls");
+        };
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].tool_call_id.as_deref(), Some("call_1"));
+        assert_eq!(calls[0].name, "file.read_text");
+        assert_eq!(calls[0].arguments_json, r#"{"path":"a.txt"}"#);
+    }
+
+    #[test]
+    fn generation_params_are_only_sent_when_set() {
+        let mut body = json!({ "model": "gpt-4.1-mini" });
+        apply_generation_params(&mut body, &ProviderConfig::default());
+        assert_eq!(body, json!({ "model": "gpt-4.1-mini" }));
+
+        let cfg = ProviderConfig {
+            provider_name: "openai".to_string(),
+            temperature: Some(0.2),
+            max_tokens: Some(256),
+            top_p: Some(0.9),
+            stop: vec!["END".to_string()],
+            ..Default::default()
+        };
+        apply_generation_params(&mut body, &cfg);
+        assert_eq!(body["temperature"], json!(0.2));
+        assert_eq!(body["max_tokens"], json!(256));
+        assert_eq!(body["top_p"], json!(0.9));
+        assert_eq!(body["stop"], json!(["END"]));
+    }
 }
@@ -19,8 +19,9 @@ impl Provider for OpenAiStubProvider {
     ) -> ProviderReply {
         if tool_results.is_empty() {
             if let Some(last) = messages.last() {
-                if let Some(call) = select_stub_tool_call(&last.content, tools) {
-                    return ProviderReply::ToolCalls(vec![call]);
+                let calls = select_stub_tool_calls(&last.content, tools);
+                if !calls.is_empty() {
+                    return ProviderReply::ToolCalls(calls);
                 }
             }
         }
@@ -35,6 +36,57 @@ impl Provider for OpenAiStubProvider {
             suffix
         ))
     }
+
+    /// Streams the canned reply a word at a time so incremental rendering can be tried offline.
+    fn chat_streaming(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[Tool],
+        tool_results: &[ToolResult],
+        config: &ProviderConfig,
+        on_token: &mut dyn FnMut(&str),
+    ) -> ProviderReply {
+        let reply = self.chat(messages, tools, tool_results, config);
+        if let ProviderReply::FinalText(text) = &reply {
+            text.split_inclusive(' ').for_each(&mut *on_token);
+        }
+        reply
+    }
+
+    /// Deterministic hashed bag-of-words vectors so retrieval can be exercised offline.
+    fn embed(&self, texts: &[String], _model: Option<&str>, _config: &ProviderConfig) -> Result<Vec<Vec<f32>>, String> {
+        Ok(texts.iter().map(|text| stub_embedding(text)).collect())
+    }
+}
+
+const STUB_EMBEDDING_DIMENSIONS: usize = 64;
+
+fn stub_embedding(text: &str) -> Vec<f32> {
+    let mut vector = vec![0.0f32; STUB_EMBEDDING_DIMENSIONS];
+    for word in text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+        // FNV-1a, so vectors are stable across runs.
+        let hash = word
+            .to_lowercase()
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325u64, |h, b| (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3));
+        vector[(hash % STUB_EMBEDDING_DIMENSIONS as u64) as usize] += 1.0;
+    }
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
+/// Legacy `tool:` prompts may chain several calls with ` && ` to exercise multi-action flows.
+fn select_stub_tool_calls(prompt: &str, tools: &[Tool]) -> Vec<ToolCall> {
+    if prompt.to_ascii_lowercase().contains("tool:") && prompt.contains(" && ") {
+        return prompt
+            .split(" && ")
+            .filter_map(|part| select_legacy_tool_syntax_call(part.trim(), tools))
+            .collect();
+    }
+    select_stub_tool_call(prompt, tools).into_iter().collect()
 }
 
 fn select_stub_tool_call(prompt: &str, tools: &[Tool]) -> Option<ToolCall> {
@@ -50,19 +102,18 @@ fn select_stub_tool_call(prompt: &str, tools: &[Tool]) -> Option<ToolCall> {
 fn select_natural_language_tool_call(prompt: &str, tools: &[Tool]) -> Option<ToolCall> {
     let lower = prompt.to_ascii_lowercase();
 
-    if has_tool(tools, "desktop.app.list") {
-        if lower.contains("list apps")
+    if has_tool(tools, "desktop.app.list")
+        && (lower.contains("list apps")
             || lower.contains("list applications")
             || lower.contains("show apps")
             || lower.contains("show applications")
-            || lower.contains("running apps")
-        {
-            return Some(ToolCall {
-                tool_call_id: None,
-                name: "desktop.app.list".to_string(),
-                arguments_json: json!({ "filter": "" }).to_string(),
-            });
-        }
+            || lower.contains("running apps"))
+    {
+        return Some(ToolCall {
+            tool_call_id: None,
+            name: "desktop.app.list".to_string(),
+            arguments_json: json!({ "filter": "" }).to_string(),
+        });
     }
 
     if has_tool(tools, "desktop.app.activate") && (lower.contains("open browser") || lower.contains("launch browser")) {
@@ -122,14 +173,14 @@ fn select_natural_language_tool_call(prompt: &str, tools: &[Tool]) -> Option<Too
         }
     }
 
-    if has_tool(tools, "file.list") {
-        if lower.contains("list files") || lower.contains("show files") || lower.contains("what files") {
-            return Some(ToolCall {
-                tool_call_id: None,
-                name: "file.list".to_string(),
-                arguments_json: json!({ "path": "." }).to_string(),
-            });
-        }
+    if has_tool(tools, "file.list")
+        && (lower.contains("list files") || lower.contains("show files") || lower.contains("what files"))
+    {
+        return Some(ToolCall {
+            tool_call_id: None,
+            name: "file.list".to_string(),
+            arguments_json: json!({ "path": "." }).to_string(),
+        });
     }
 
     if has_tool(tools, "file.read_text") {
@@ -168,21 +219,21 @@ fn select_natural_language_tool_call(prompt: &str, tools: &[Tool]) -> Option<Too
         }
     }
 
-    if has_tool(tools, "mcp.tool_call") {
-        if lower.contains("mcp") && lower.contains("server") && lower.contains("tool") {
-            let server_id = token_after(prompt, "server").unwrap_or("mcp-000001");
-            let tool_name = token_after(prompt, "tool").unwrap_or("echo");
-            return Some(ToolCall {
-                tool_call_id: None,
-                name: "mcp.tool_call".to_string(),
-                arguments_json: json!({
-                    "server_id": server_id,
-                    "tool_name": tool_name,
-                    "arguments": {}
-                })
-                .to_string(),
-            });
-        }
+    if has_tool(tools, "mcp.tool_call")
+        && lower.contains("mcp") && lower.contains("server") && lower.contains("tool")
+    {
+        let server_id = token_after(prompt, "server").unwrap_or("mcp-000001");
+        let tool_name = token_after(prompt, "tool").unwrap_or("echo");
+        return Some(ToolCall {
+            tool_call_id: None,
+            name: "mcp.tool_call".to_string(),
+            arguments_json: json!({
+                "server_id": server_id,
+                "tool_name": tool_name,
+                "arguments": {}
+            })
+            .to_string(),
+        });
     }
 
     if prompt.to_ascii_lowercase().contains("what time") && has_tool(tools, "time.now") {
@@ -484,6 +535,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn stub_embeddings_are_deterministic_and_normalized() {
+        let texts = vec!["Open the README".to_string(), "open the readme".to_string(), String::new()];
+        let vectors = OpenAiStubProvider
+            .embed(&texts, None, &ProviderConfig::default())
+            .expect("embed");
+        assert_eq!(vectors.len(), 3);
+        assert_eq!(vectors[0], vectors[1]);
+        assert_eq!(vectors[0].len(), STUB_EMBEDDING_DIMENSIONS);
+        let norm = vectors[0].iter().map(|x| x * x).sum::<f32>();
+        assert!((norm - 1.0).abs() < 1e-5);
+        assert!(vectors[2].iter().all(|x| *x == 0.0));
+    }
+
+    #[test]
+    fn streaming_chunks_add_up_to_the_final_text() {
+        let messages = ipc::sample_messages("hello");
+        let mut chunks = Vec::new();
+        let reply = OpenAiStubProvider.chat_streaming(&messages, &[], &[], &ProviderConfig::default(), &mut |t| {
+            chunks.push(t.to_string())
+        });
+        let ProviderReply::FinalText(text) = reply else {
+            panic!("expected text");
+        };
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.concat(), text);
+    }
+
     #[test]
     fn natural_language_open_url_maps_to_desktop_tool() {
         let tools = vec![tool("desktop.open_url")];
@@ -16,4 +16,42 @@ pub trait Provider {
         tool_results: &[ToolResult],
         config: &ProviderConfig,
     ) -> ProviderReply;
+
+    /// Like `chat`, but hands assistant text to `on_token` as it arrives. Providers that cannot
+    /// stream deliver their final text as a single chunk.
+    fn chat_streaming(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[Tool],
+        tool_results: &[ToolResult],
+        config: &ProviderConfig,
+        on_token: &mut dyn FnMut(&str),
+    ) -> ProviderReply {
+        let reply = self.chat(messages, tools, tool_results, config);
+        if let ProviderReply::FinalText(text) = &reply {
+            on_token(text);
+        }
+        reply
+    }
+
+    /// Number of transport retries spent on the most recent `chat` call.
+    fn last_retry_count(&self) -> u32 {
+        0
+    }
+
+    /// Whether `chat` sends the conversation off this machine. Offline stubs stay local.
+    fn is_remote(&self, _config: &ProviderConfig) -> bool {
+        false
+    }
+
+    /// Model ids the provider reports as available. Offline stubs report none.
+    fn list_models(&self, _config: &ProviderConfig) -> Result<Vec<String>, String> {
+        Ok(Vec::new())
+    }
+
+    /// One embedding vector per input text, in order. `model` overrides the provider's
+    /// configured embedding model.
+    fn embed(&self, _texts: &[String], _model: Option<&str>, _config: &ProviderConfig) -> Result<Vec<Vec<f32>>, String> {
+        Err(format!("provider '{}' does not support embeddings", self.name()))
+    }
 }
@@ -0,0 +1,164 @@
+//! Named providers: the built-ins plus every name with a stored config. A config's `type` picks
+//! the implementation, so aliases such as `ollama-local` are first-class providers.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use ipc::{ChatMessage, ProviderConfig, Tool, ToolResult};
+use serde_json::Value;
+
+use crate::provider_trait::{Provider, ProviderReply};
+use crate::ProviderChoice;
+
+/// Accepted `type` values. `openai-compatible` is the OpenAI wire format at a configured
+/// `base_url` (Ollama, LiteLLM, OpenRouter, ...); `azure` is Azure OpenAI.
+pub const PROVIDER_TYPES: &[&str] = &[
+    "openai-compatible",
+    "openai",
+    "azure",
+    "openai-stub",
+    "anthropic-stub",
+    "gemini-stub",
+];
+
+/// Configs written before `type` existed are treated as this.
+const LEGACY_CONFIG_TYPE: &str = "openai-compatible";
+
+pub struct ProviderRegistry<'a> {
+    configs: &'a BTreeMap<String, String>,
+}
+
+impl<'a> ProviderRegistry<'a> {
+    /// `configs` maps provider names to their stored config JSON.
+    pub fn new(configs: &'a BTreeMap<String, String>) -> Self {
+        Self { configs }
+    }
+
+    /// Rejects a config whose `type` (or older `api`) is not in `PROVIDER_TYPES`.
+    pub fn validate_config(config_json: &str) -> Result<(), String> {
+        match configured_type(config_json) {
+            Some(kind) if !PROVIDER_TYPES.contains(&kind.as_str()) => Err(format!(
+                "unknown provider type '{kind}' (expected one of: {})",
+                PROVIDER_TYPES.join(", ")
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// The configured type, else the built-in name; `None` for an unregistered name.
+    pub fn kind(&self, name: &str) -> Option<String> {
+        let config = self.configs.get(name);
+        if let Some(kind) = config.and_then(|raw| configured_type(raw)) {
+            return Some(kind);
+        }
+        if ProviderChoice::builtin(name).is_some() {
+            return Some(name.to_string());
+        }
+        config.map(|_| LEGACY_CONFIG_TYPE.to_string())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.kind(name).is_some()
+    }
+
+    /// Built-in names first-class alongside configured ones, sorted.
+    pub fn names(&self) -> BTreeSet<String> {
+        ProviderChoice::builtin_names()
+            .iter()
+            .map(|name| name.to_string())
+            .chain(self.configs.keys().cloned())
+            .collect()
+    }
+
+    /// Never falls back silently: an unregistered name resolves to a provider whose replies say
+    /// how to register it.
+    pub fn resolve(&self, name: &str) -> ProviderChoice {
+        match self.kind(name).as_deref() {
+            Some("openai" | "openai-compatible" | "azure") => ProviderChoice::by_name("openai"),
+            Some(kind) => ProviderChoice::builtin(kind).unwrap_or_else(|| {
+                ProviderChoice::Unavailable(UnavailableProvider::new(format!(
+                    "provider '{name}' has unknown type '{kind}'"
+                )))
+            }),
+            None => ProviderChoice::Unavailable(UnavailableProvider::new(format!(
+                "provider '{name}' is not registered; add it with `providers config-set {name} \
+                 '{{\"type\":\"openai-compatible\",\"base_url\":\"http://localhost:11434\"}}'`"
+            ))),
+        }
+    }
+}
+
+fn configured_type(config_json: &str) -> Option<String> {
+    let value = serde_json::from_str::<Value>(config_json).ok()?;
+    value
+        .get("type")
+        .or_else(|| value.get("api"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+/// Stands in for a name that cannot be resolved; every call reports why.
+pub struct UnavailableProvider {
+    reason: String,
+}
+
+impl UnavailableProvider {
+    pub fn new(reason: String) -> Self {
+        Self { reason }
+    }
+}
+
+impl Provider for UnavailableProvider {
+    fn name(&self) -> &'static str {
+        "unavailable"
+    }
+
+    fn chat(
+        &self,
+        _messages: &[ChatMessage],
+        _tools: &[Tool],
+        _tool_results: &[ToolResult],
+        _config: &ProviderConfig,
+    ) -> ProviderReply {
+        ProviderReply::FinalText(format!("Provider unavailable: {}.", self.reason))
+    }
+
+    fn list_models(&self, _config: &ProviderConfig) -> Result<Vec<String>, String> {
+        Err(self.reason.clone())
+    }
+
+    fn embed(&self, _texts: &[String], _model: Option<&str>, _config: &ProviderConfig) -> Result<Vec<Vec<f32>>, String> {
+        Err(self.reason.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_configured_types_and_refuses_unregistered_names() {
+        let configs = BTreeMap::from([
+            (
+                "ollama-local".to_string(),
+                r#"{"type":"openai-compatible","base_url":"http://localhost:11434"}"#.to_string(),
+            ),
+            ("offline".to_string(), r#"{"type":"openai-stub"}"#.to_string()),
+            ("legacy".to_string(), r#"{"api_key_env":"LEGACY_TOKEN"}"#.to_string()),
+        ]);
+        let registry = ProviderRegistry::new(&configs);
+        assert_eq!(registry.resolve("ollama-local").name(), "openai");
+        assert_eq!(registry.resolve("offline").name(), "openai-stub");
+        assert_eq!(registry.resolve("anthropic").name(), "anthropic-stub");
+        assert_eq!(registry.kind("legacy").as_deref(), Some("openai-compatible"));
+        assert!(registry.names().contains("ollama-local"));
+
+        let missing = registry.resolve("ollama");
+        assert_eq!(missing.name(), "unavailable");
+        let err = missing.list_models(&ProviderConfig::default()).expect_err("unregistered");
+        assert!(err.contains("not registered"), "{err}");
+        assert!(!registry.contains("ollama"));
+
+        assert!(ProviderRegistry::validate_config(r#"{"type":"openai-compatible"}"#).is_ok());
+        assert!(ProviderRegistry::validate_config(r#"{"type":"bedrock"}"#).is_err());
+    }
+}
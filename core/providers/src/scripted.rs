@@ -0,0 +1,106 @@
+use ipc::{ChatMessage, ProviderConfig, Tool, ToolCall, ToolResult};
+use serde_json::{json, Value};
+
+use crate::provider_trait::{Provider, ProviderReply};
+
+/// Built-in name for `ScriptedProvider`. Not listed with the selectable providers.
+pub const SCRIPTED_PROVIDER: &str = "scripted";
+
+/// Proposes exactly the tool call written in the last message (see `scripted_call_message`) and
+/// answers with its raw result. Workflow tool steps run through it so they get the same policy,
+/// consent and audit handling as model-proposed calls, and replay identically after approval.
+pub struct ScriptedProvider;
+
+/// The message `ScriptedProvider` turns into a call to `tool` with `arguments`.
+pub fn scripted_call_message(tool: &str, arguments: &Value) -> String {
+    json!({ "tool": tool, "arguments": arguments }).to_string()
+}
+
+impl Provider for ScriptedProvider {
+    fn name(&self) -> &'static str {
+        SCRIPTED_PROVIDER
+    }
+
+    fn chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[Tool],
+        tool_results: &[ToolResult],
+        _config: &ProviderConfig,
+    ) -> ProviderReply {
+        if !tool_results.is_empty() {
+            let results = tool_results.iter().map(|r| r.result_json.as_str()).collect::<Vec<_>>();
+            return ProviderReply::FinalText(results.join("\n"));
+        }
+        let script = messages
+            .iter()
+            .rev()
+            .find(|m| m.role == "user")
+            .and_then(|m| serde_json::from_str::<Value>(&m.content).ok());
+        let Some(name) = script.as_ref().and_then(|s| s.get("tool")).and_then(Value::as_str) else {
+            return ProviderReply::FinalText(
+                "scripted provider expects {\"tool\": <name>, \"arguments\": {...}} as the last user message".to_string(),
+            );
+        };
+        if !tools.iter().any(|t| t.name == name) {
+            return ProviderReply::FinalText(format!("tool '{name}' is not available in this context"));
+        }
+        let arguments = script
+            .as_ref()
+            .and_then(|s| s.get("arguments"))
+            .cloned()
+            .unwrap_or_else(|| json!({}));
+        ProviderReply::ToolCalls(vec![ToolCall {
+            tool_call_id: Some("scripted-1".to_string()),
+            name: name.to_string(),
+            arguments_json: arguments.to_string(),
+        }])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proposes_the_scripted_call_then_returns_its_result() {
+        let tools = vec![Tool {
+            name: "file.read_text".to_string(),
+            description: String::new(),
+            input_json_schema: "{}".to_string(),
+        }];
+        let messages = vec![ChatMessage {
+            role: "user".to_string(),
+            content: scripted_call_message("file.read_text", &json!({"path": "README.md"})),
+            parts: Vec::new(),
+            message_id: None,
+            created_at_unix_seconds: None,
+        }];
+        let config = ProviderConfig::default();
+        let ProviderReply::ToolCalls(calls) = ScriptedProvider.chat(&messages, &tools, &[], &config) else {
+            panic!("expected a tool call");
+        };
+        assert_eq!(calls[0].name, "file.read_text");
+        assert_eq!(calls[0].arguments_json, r#"{"path":"README.md"}"#);
+
+        let result = ToolResult {
+            tool_call_id: calls[0].tool_call_id.clone(),
+            name: calls[0].name.clone(),
+            result_json: r#"{"text":"hi"}"#.to_string(),
+            evidence: ipc::Evidence {
+                summary: String::new(),
+                artifacts: Vec::new(),
+                attachments: Vec::new(),
+                original_result_bytes: None,
+            },
+        };
+        match ScriptedProvider.chat(&messages, &tools, &[result], &config) {
+            ProviderReply::FinalText(text) => assert_eq!(text, r#"{"text":"hi"}"#),
+            other => panic!("unexpected reply {other:?}"),
+        }
+        match ScriptedProvider.chat(&messages, &[], &[], &config) {
+            ProviderReply::FinalText(text) => assert!(text.contains("not available")),
+            other => panic!("unexpected reply {other:?}"),
+        }
+    }
+}
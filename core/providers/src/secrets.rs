@@ -0,0 +1,50 @@
+use std::sync::OnceLock;
+
+/// Prefix of an `api_key_ref` stored in the OS keychain, e.g. `keychain:openai`.
+pub const KEYCHAIN_PREFIX: &str = "keychain:";
+
+/// Looks up a keychain secret by name; `Ok(None)` means no such entry.
+pub type SecretResolver = fn(&str) -> Result<Option<String>, String>;
+
+static RESOLVER: OnceLock<SecretResolver> = OnceLock::new();
+
+/// Installed by hosts that have a secret store (the desktop bridge). Only the first call wins.
+pub fn set_secret_resolver(resolver: SecretResolver) -> bool {
+    RESOLVER.set(resolver).is_ok()
+}
+
+/// Validates an `api_key_ref` and returns the secret name it points at.
+pub fn parse_secret_ref(reference: &str) -> Result<&str, String> {
+    let name = reference
+        .strip_prefix(KEYCHAIN_PREFIX)
+        .ok_or_else(|| format!("unsupported api_key_ref '{reference}' (expected {KEYCHAIN_PREFIX}<name>)"))?
+        .trim();
+    if name.is_empty() {
+        return Err(format!("api_key_ref '{reference}' is missing a secret name"));
+    }
+    Ok(name)
+}
+
+/// Resolves a reference at request time, so the secret itself is never written to provider config.
+pub fn resolve_secret_ref(reference: &str) -> Result<String, String> {
+    let name = parse_secret_ref(reference)?;
+    let resolver = RESOLVER
+        .get()
+        .ok_or_else(|| format!("no keychain available in this process to resolve '{reference}'"))?;
+    resolver(name)?
+        .filter(|secret| !secret.trim().is_empty())
+        .ok_or_else(|| format!("keychain secret '{name}' not found"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_keychain_refs() {
+        assert_eq!(parse_secret_ref("keychain:openai"), Ok("openai"));
+        assert!(parse_secret_ref("keychain: ").is_err());
+        assert!(parse_secret_ref("env:OPENAI_API_KEY").is_err());
+        assert!(resolve_secret_ref("plain-key").is_err());
+    }
+}
@@ -0,0 +1,276 @@
+use crate::migrations;
+use crate::{invalid_data, FileLockGuard, FileStorage, StorageBatch};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MANIFEST_NAME: &str = "backup.json";
+const MAX_STORE_BYTES: u64 = 256 * 1024 * 1024;
+
+impl FileStorage {
+    /// Writes every store file into a `.tar.gz` at `dest` while holding all store locks, so the
+    /// snapshot is consistent. `exclude_secrets` drops inline `api_key`s from provider configs
+    /// and every inline credential in agent settings (see `strip_settings_secrets`).
+    pub fn backup_to(&self, dest: &Path, exclude_secrets: bool) -> io::Result<Vec<String>> {
+        let _locks = self.lock_all_stores()?;
+        let mut files = BTreeMap::new();
+        for schema in migrations::STORES {
+            let path = self.path_for(schema.file_name);
+            if !path.exists() {
+                continue;
+            }
+            let mut raw = fs::read(&path)?;
+            if exclude_secrets && schema.file_name == "providers.json" {
+                raw = strip_provider_secrets(&raw)?;
+            }
+            if exclude_secrets && schema.file_name == "agent_settings.json" {
+                raw = strip_settings_secrets(&raw)?;
+            }
+            files.insert(schema.file_name, raw);
+        }
+
+        let manifest = serde_json::json!({
+            "created_at_unix_seconds": SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            "exclude_secrets": exclude_secrets,
+            "files": files.keys().collect::<Vec<_>>(),
+        });
+        if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = dest.with_extension("partial");
+        let written = create_private(&tmp).and_then(|file| {
+            let mut tar = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+            append_bytes(&mut tar, MANIFEST_NAME, manifest.to_string().as_bytes())?;
+            for (name, raw) in &files {
+                append_bytes(&mut tar, name, raw)?;
+            }
+            tar.into_inner()?.finish()?.sync_all()?;
+            fs::rename(&tmp, dest)
+        });
+        if let Err(err) = written {
+            let _ = fs::remove_file(&tmp);
+            return Err(err);
+        }
+        Ok(files.keys().map(|name| name.to_string()).collect())
+    }
+
+    /// Replaces store files with those in a `backup_to` archive. Everything is read and checked
+    /// first; nothing is written if any file is unknown, unparsable or from a newer schema. The
+    /// files are then written as one journaled `commit`, so a crash leaves all or none of them.
+    pub fn restore_from(&self, src: &Path) -> io::Result<Vec<String>> {
+        let mut archive = tar::Archive::new(GzDecoder::new(fs::File::open(src)?));
+        let mut files = BTreeMap::new();
+        let mut saw_manifest = false;
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let name = entry.path()?.to_string_lossy().to_string();
+            if name == MANIFEST_NAME {
+                saw_manifest = true;
+                continue;
+            }
+            let schema = migrations::schema_for(&name)
+                .ok_or_else(|| invalid_data(format!("unexpected file in backup: {name}")))?;
+            let mut raw = Vec::new();
+            (&mut entry).take(MAX_STORE_BYTES + 1).read_to_end(&mut raw)?;
+            if raw.len() as u64 > MAX_STORE_BYTES {
+                return Err(invalid_data(format!("{name} in backup is too large")));
+            }
+            let value: Value = serde_json::from_slice(&raw).map_err(|err| invalid_data(format!("{name} in backup: {err}")))?;
+            let (version, _) = migrations::split_versioned(schema, value).map_err(invalid_data)?;
+            if version > schema.version() {
+                return Err(invalid_data(format!(
+                    "{name} in backup has schema_version {version}, newer than supported {}",
+                    schema.version()
+                )));
+            }
+            files.insert(schema.file_name, raw);
+        }
+        if !saw_manifest {
+            return Err(invalid_data("not a state backup (missing backup.json)"));
+        }
+
+        let restored = files.keys().map(|name| name.to_string()).collect();
+        let mut batch = StorageBatch::default();
+        for (name, raw) in files {
+            batch.write_raw(name, raw);
+        }
+        self.commit(batch)?;
+        self.run_migrations()?;
+        Ok(restored)
+    }
+
+    /// Locks are taken in `STORES` order so concurrent snapshots cannot deadlock.
+    fn lock_all_stores(&self) -> io::Result<Vec<FileLockGuard>> {
+        migrations::STORES
+            .iter()
+            .map(|schema| self.acquire_file_lock(schema.file_name))
+            .collect()
+    }
+}
+
+/// Backups hold every store and maybe API keys, so only the owner may read them.
+fn create_private(path: &Path) -> io::Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let file = options.open(path)?;
+    // `mode` only applies to newly created files; a leftover `.partial` keeps its own.
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    Ok(file)
+}
+
+fn append_bytes<W: Write>(tar: &mut tar::Builder<W>, name: &str, bytes: &[u8]) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o600);
+    header.set_cksum();
+    tar.append_data(&mut header, name, bytes)
+}
+
+/// Provider configs are JSON strings keyed by provider; inline `api_key`s are removed while
+/// `api_key_ref` keychain references are kept.
+fn strip_provider_secrets(raw: &[u8]) -> io::Result<Vec<u8>> {
+    let mut value: Value = serde_json::from_slice(raw).map_err(|err| invalid_data(format!("providers.json: {err}")))?;
+    if let Some(configs) = value.get_mut("configs").and_then(Value::as_object_mut) {
+        for config in configs.values_mut() {
+            let Some(mut parsed) = config.as_str().and_then(|s| serde_json::from_str::<Value>(s).ok()) else {
+                continue;
+            };
+            if let Some(map) = parsed.as_object_mut() {
+                map.remove("api_key");
+            }
+            *config = Value::String(parsed.to_string());
+        }
+    }
+    serde_json::to_vec_pretty(&value).map_err(io::Error::other)
+}
+
+/// `databases.postgres` values may embed a password, so only `keychain:` references are kept;
+/// CalDAV passwords likewise. Slack/Discord webhook URLs carry their token in the path and have
+/// no keychain form, so `notifications.webhook_url` and `notifications.targets` are dropped.
+fn strip_settings_secrets(raw: &[u8]) -> io::Result<Vec<u8>> {
+    let is_keychain_ref = |value: &Value| value.as_str().is_some_and(|value| value.starts_with("keychain:"));
+    let mut value: Value =
+        serde_json::from_slice(raw).map_err(|err| invalid_data(format!("agent_settings.json: {err}")))?;
+    if let Some(postgres) = value.pointer_mut("/databases/postgres").and_then(Value::as_object_mut) {
+        postgres.retain(|_, dsn| is_keychain_ref(dsn));
+    }
+    if let Some(caldav) = value.pointer_mut("/calendars/caldav").and_then(Value::as_object_mut) {
+        for calendar in caldav.values_mut().filter_map(Value::as_object_mut) {
+            if calendar.get("password").is_some_and(|password| !is_keychain_ref(password)) {
+                calendar.remove("password");
+            }
+        }
+    }
+    if let Some(notifications) = value.get_mut("notifications").and_then(Value::as_object_mut) {
+        notifications.remove("webhook_url");
+        notifications.remove("targets");
+    }
+    serde_json::to_vec_pretty(&value).map_err(io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AgentSettingsState, CalDavSettings, NotifyTarget, ProjectState, ProviderState, Storage};
+    use tempfile::tempdir;
+
+    #[test]
+    fn backup_round_trips_and_can_drop_secrets() {
+        let dir = tempdir().expect("tempdir");
+        let store = FileStorage::new_in_dir(dir.path().join("state")).expect("store");
+        store
+            .write_provider_state(&ProviderState {
+                active_provider: Some("openai".to_string()),
+                configs: BTreeMap::from([(
+                    "openai".to_string(),
+                    r#"{"api_key":"sk-secret","model":"gpt-4o"}"#.to_string(),
+                )]),
+            })
+            .expect("providers");
+        store
+            .write_project_state(&ProjectState {
+                open_path: Some("/work".to_string()),
+                ..ProjectState::default()
+            })
+            .expect("project");
+
+        let full = dir.path().join("full.tar.gz");
+        let files = store.backup_to(&full, false).expect("backup");
+        assert_eq!(files, vec!["project.json", "providers.json"]);
+        let redacted = dir.path().join("redacted.tar.gz");
+        store.backup_to(&redacted, true).expect("backup without secrets");
+
+        let restored = FileStorage::new_in_dir(dir.path().join("restored")).expect("store");
+        restored.restore_from(&redacted).expect("restore");
+        let providers = restored.read_provider_state().expect("providers");
+        assert!(!providers.configs["openai"].contains("sk-secret"));
+        assert!(providers.configs["openai"].contains("gpt-4o"));
+        assert_eq!(restored.read_project_state().expect("project").open_path.as_deref(), Some("/work"));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&full).expect("meta").permissions().mode() & 0o777, 0o600);
+        }
+        assert!(!dir.path().join("full.partial").exists());
+        assert!(!dir.path().join("restored/journal.json").exists());
+        assert!(!dir.path().join("restored/providers.json.staged").exists());
+
+        restored.restore_from(&full).expect("restore full");
+        assert!(restored.read_provider_state().expect("providers").configs["openai"].contains("sk-secret"));
+        assert!(restored.restore_from(&dir.path().join("state/providers.json")).is_err());
+    }
+
+    #[test]
+    fn backup_without_secrets_keeps_only_keychain_refs_in_settings() {
+        let dir = tempdir().expect("tempdir");
+        let store = FileStorage::new_in_dir(dir.path().join("state")).expect("store");
+        let mut settings = AgentSettingsState::default();
+        settings.databases.postgres = BTreeMap::from([
+            ("app".to_string(), "postgres://app:hunter2@db/app".to_string()),
+            ("reports".to_string(), "keychain:reports-db".to_string()),
+        ]);
+        settings.calendars.caldav = BTreeMap::from([
+            ("work".to_string(), caldav("https://dav.example.com/work/", "app-token")),
+            ("home".to_string(), caldav("https://dav.example.com/home/", "keychain:home-dav")),
+        ]);
+        settings.notifications.webhook_url = Some("https://hooks.slack.com/services/T/B/consent".to_string());
+        settings.notifications.targets = BTreeMap::from([(
+            "team".to_string(),
+            NotifyTarget {
+                url: "https://discord.com/api/webhooks/1/token".to_string(),
+                kind: None,
+            },
+        )]);
+        store.write_agent_settings(&settings).expect("settings");
+
+        let redacted = dir.path().join("redacted.tar.gz");
+        store.backup_to(&redacted, true).expect("backup without secrets");
+        let restored = FileStorage::new_in_dir(dir.path().join("restored")).expect("store");
+        restored.restore_from(&redacted).expect("restore");
+        let settings = restored.read_agent_settings().expect("settings");
+        assert_eq!(settings.databases.postgres, BTreeMap::from([("reports".to_string(), "keychain:reports-db".to_string())]));
+        assert_eq!(settings.calendars.caldav["work"].password, None);
+        assert_eq!(settings.calendars.caldav["work"].url, "https://dav.example.com/work/");
+        assert_eq!(settings.calendars.caldav["home"].password.as_deref(), Some("keychain:home-dav"));
+        assert_eq!(settings.notifications.webhook_url, None);
+        assert!(settings.notifications.targets.is_empty());
+    }
+
+    fn caldav(url: &str, password: &str) -> CalDavSettings {
+        CalDavSettings {
+            url: url.to_string(),
+            username: Some("me".to_string()),
+            password: Some(password.to_string()),
+        }
+    }
+}
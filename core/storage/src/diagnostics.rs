@@ -0,0 +1,116 @@
+use crate::{migrations, FileStorage, Storage};
+use ipc::StoreDiagnostics;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+impl FileStorage {
+    /// Size, age, schema version and lock counters for every store, plus an integrity check
+    /// that reads each existing file as its typed contents.
+    pub fn diagnostics(&self) -> Vec<StoreDiagnostics> {
+        let lock_stats = self.lock_stats.lock().map(|stats| stats.clone()).unwrap_or_default();
+        migrations::STORES
+            .iter()
+            .map(|schema| {
+                let name = schema.file_name;
+                let metadata = fs::metadata(self.path_for(name)).ok();
+                let stats = lock_stats.get(name).copied().unwrap_or_default();
+                let exists = metadata.is_some();
+                StoreDiagnostics {
+                    file_name: name.to_string(),
+                    exists,
+                    size_bytes: metadata.as_ref().map_or(0, |m| m.len()),
+                    modified_unix_seconds: metadata
+                        .and_then(|m| m.modified().ok())
+                        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                        .map(|d| d.as_secs()),
+                    schema_version: exists
+                        .then(|| self.read_versioned(name).ok().map(|(version, _)| version))
+                        .flatten(),
+                    error: exists.then(|| self.check_store(name).err().map(|e| e.to_string())).flatten(),
+                    lock_held: self.path_for(&format!("{name}.lock")).exists(),
+                    lock_acquisitions: stats.acquisitions,
+                    lock_contended: stats.contended,
+                    lock_wait_ms: stats.wait.as_millis() as u64,
+                    lock_timeouts: stats.timeouts,
+                }
+            })
+            .collect()
+    }
+
+    /// Bytes used by everything under the state directory.
+    pub fn disk_usage_bytes(&self) -> u64 {
+        dir_size(self.root())
+    }
+
+    fn check_store(&self, file_name: &str) -> io::Result<()> {
+        match file_name {
+            "sessions.json" => self.list_sessions().map(drop),
+            "providers.json" => self.read_provider_state().map(drop),
+            "audit.json" => self.read_audit_entries().map(drop),
+            "pending_consents.json" => self.read_pending_consents().map(drop),
+            "mcp_servers.json" => self.read_mcp_servers().map(drop),
+            "project.json" => self.read_project_state().map(drop),
+            "agent_settings.json" => self.read_agent_settings().map(drop),
+            "policy.json" => self.read_policy_state().map(drop),
+            "response_cache.json" => self.read_response_cache().map(drop),
+            "tasks.json" => self.read_scheduled_tasks().map(drop),
+            _ => self.read_versioned(file_name).map(drop),
+        }
+    }
+}
+
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(kind) if kind.is_dir() => dir_size(&entry.path()),
+            Ok(kind) if kind.is_file() => entry.metadata().map_or(0, |m| m.len()),
+            _ => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProviderState;
+    use std::thread;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn reports_sizes_corrupt_stores_and_lock_contention() {
+        let dir = tempdir().expect("tempdir");
+        let store = FileStorage::new_in_dir(dir.path()).expect("store");
+        store.write_provider_state(&ProviderState::default()).expect("write providers");
+        fs::write(dir.path().join("sessions.json"), r#"{"schema_version":1,"items":[{"id":1}]}"#).expect("corrupt");
+
+        let lock = dir.path().join("providers.json.lock");
+        fs::write(&lock, "").expect("hold lock");
+        let release = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            fs::remove_file(lock).expect("release lock");
+        });
+        store.write_provider_state(&ProviderState::default()).expect("write after wait");
+        release.join().expect("join");
+
+        let diagnostics = store.diagnostics();
+        assert_eq!(diagnostics.len(), migrations::STORES.len());
+        let find = |name: &str| diagnostics.iter().find(|d| d.file_name == name).expect(name);
+        let providers = find("providers.json");
+        assert!(providers.exists && providers.size_bytes > 0 && providers.modified_unix_seconds.is_some());
+        assert_eq!((providers.schema_version, providers.error.as_deref()), (Some(1), None));
+        // Startup journal recovery takes every store lock once, then the two writes.
+        assert_eq!((providers.lock_acquisitions, providers.lock_contended, providers.lock_held), (3, 1, false));
+        assert!(providers.lock_wait_ms >= 40);
+        let sessions = find("sessions.json");
+        assert!(sessions.error.as_deref().is_some_and(|e| e.contains("sessions.json")));
+        assert!(!find("tasks.json").exists);
+        assert!(store.disk_usage_bytes() >= providers.size_bytes + sessions.size_bytes);
+    }
+}
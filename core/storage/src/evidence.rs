@@ -0,0 +1,87 @@
+use crate::{invalid_data, FileStorage};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+
+const EVIDENCE_DIR: &str = "evidence";
+
+impl FileStorage {
+    /// Stores `bytes` under `evidence/<sha256>` and returns the hash. Identical content is kept
+    /// once, so storing it again is a no-op.
+    pub fn put_evidence(&self, bytes: &[u8]) -> io::Result<String> {
+        let sha256 = Sha256::digest(bytes).iter().map(|b| format!("{b:02x}")).collect::<String>();
+        let path = self.evidence_path(&sha256)?;
+        if !path.exists() {
+            fs::create_dir_all(self.root().join(EVIDENCE_DIR))?;
+            let tmp = path.with_extension("tmp");
+            fs::write(&tmp, bytes)?;
+            fs::rename(tmp, path)?;
+        }
+        Ok(sha256)
+    }
+
+    pub fn read_evidence(&self, sha256: &str) -> io::Result<Vec<u8>> {
+        fs::read(self.evidence_path(sha256)?)
+    }
+
+    /// Deletes stored evidence whose hash is not in `referenced`; returns how many files that
+    /// was (or would be, with `dry_run`).
+    pub fn prune_evidence(&self, referenced: &BTreeSet<String>, dry_run: bool) -> io::Result<usize> {
+        let entries = match fs::read_dir(self.root().join(EVIDENCE_DIR)) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(err),
+        };
+        let mut removed = 0;
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if is_sha256(&name) && !referenced.contains(&name) {
+                if !dry_run {
+                    fs::remove_file(entry.path())?;
+                }
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Hashes come from RPC callers, so anything but 64 hex digits is refused before it can
+    /// name a path outside the store.
+    fn evidence_path(&self, sha256: &str) -> io::Result<PathBuf> {
+        if !is_sha256(sha256) {
+            return Err(invalid_data(format!("invalid evidence hash '{sha256}'")));
+        }
+        Ok(self.root().join(EVIDENCE_DIR).join(sha256))
+    }
+}
+
+fn is_sha256(name: &str) -> bool {
+    name.len() == 64 && name.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn evidence_is_content_addressed_and_pruned_when_unreferenced() {
+        let dir = tempdir().expect("tempdir");
+        let store = FileStorage::new_in_dir(dir.path()).expect("store");
+        let diff = store.put_evidence(b"--- a\n+++ b\n").expect("put diff");
+        assert_eq!(store.put_evidence(b"--- a\n+++ b\n").expect("put again"), diff);
+        let page = store.put_evidence(b"<html></html>").expect("put page");
+        assert_eq!(store.read_evidence(&diff).expect("read"), b"--- a\n+++ b\n");
+        assert!(store.read_evidence("../policy.json").is_err());
+
+        let referenced = BTreeSet::from([diff.clone()]);
+        assert_eq!(store.prune_evidence(&referenced, true).expect("dry run"), 1);
+        assert!(store.read_evidence(&page).is_ok());
+        assert_eq!(store.prune_evidence(&referenced, false).expect("prune"), 1);
+        assert!(store.read_evidence(&page).is_err());
+        assert!(store.read_evidence(&diff).is_ok());
+    }
+}
@@ -0,0 +1,249 @@
+use crate::{migrations, AuditEntry, FileLockGuard, FileStorage, PendingConsentState, PolicyState};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+const JOURNAL_NAME: &str = "journal.json";
+const STAGED_SUFFIX: &str = ".staged";
+
+/// Store files that must change together, e.g. a resolved consent and its audit entry. Written
+/// with `FileStorage::commit`, which applies all of them or, after a crash, none of them.
+#[derive(Debug, Default)]
+pub struct StorageBatch {
+    writes: Vec<(&'static str, Staged)>,
+}
+
+#[derive(Debug)]
+enum Staged {
+    /// Stamped with the current schema version when committed.
+    Value(Value),
+    /// Written byte for byte, e.g. a store restored from a backup at its own schema version.
+    Raw(Vec<u8>),
+}
+
+impl StorageBatch {
+    pub fn write_pending_consents(&mut self, entries: &[PendingConsentState]) -> io::Result<()> {
+        self.stage("pending_consents.json", &entries)
+    }
+
+    pub fn write_audit_entries(&mut self, entries: &[AuditEntry]) -> io::Result<()> {
+        self.stage("audit.json", &entries)
+    }
+
+    pub fn write_policy_state(&mut self, state: &PolicyState) -> io::Result<()> {
+        self.stage("policy.json", state)
+    }
+
+    pub(crate) fn write_raw(&mut self, file_name: &'static str, raw: Vec<u8>) {
+        self.writes.retain(|(name, _)| *name != file_name);
+        self.writes.push((file_name, Staged::Raw(raw)));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    fn stage<T: Serialize>(&mut self, file_name: &'static str, value: &T) -> io::Result<()> {
+        let payload = serde_json::to_value(value).map_err(|err| io::Error::other(format!("serialize {file_name}: {err}")))?;
+        self.writes.retain(|(name, _)| *name != file_name);
+        self.writes.push((file_name, Staged::Value(payload)));
+        Ok(())
+    }
+}
+
+/// `journal.json`: the files of a commit whose staged copies are complete. Its presence at
+/// startup means the commit has to be rolled forward.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Journal {
+    files: Vec<String>,
+}
+
+/// What `recover_journal` did at startup.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct JournalRecovery {
+    /// Files moved into place from an interrupted commit.
+    pub rolled_forward: Vec<String>,
+    /// Staged files of a commit that never reached its journal, deleted.
+    pub discarded: Vec<String>,
+}
+
+impl FileStorage {
+    /// Applies `batch` atomically across files: every file is staged next to its store and
+    /// synced, then `journal.json` records the commit, then the staged files are renamed into
+    /// place. A crash before the journal is written leaves the old state; one after it is
+    /// finished by `recover_journal` on the next start.
+    pub fn commit(&self, batch: StorageBatch) -> io::Result<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let _locks = self.lock_stores(batch.writes.iter().map(|(name, _)| *name))?;
+        // Commits over disjoint stores would otherwise share `journal.json`.
+        let _journal_lock = self.acquire_file_lock(JOURNAL_NAME)?;
+        let mut staged = Vec::new();
+        for (file_name, payload) in batch.writes {
+            let raw = match payload {
+                Staged::Value(payload) => {
+                    self.check_not_newer(file_name)?;
+                    self.render_stamped(file_name, payload)?.into_bytes()
+                }
+                Staged::Raw(raw) => raw,
+            };
+            let path = self.staged_path(file_name);
+            let mut file = fs::File::create(&path)?;
+            file.write_all(&raw)?;
+            file.sync_all()?;
+            staged.push(file_name.to_string());
+        }
+
+        let journal = serde_json::to_string_pretty(&Journal { files: staged.clone() }).map_err(io::Error::other)?;
+        let journal_path = self.path_for(JOURNAL_NAME);
+        let tmp = journal_path.with_extension("tmp");
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(journal.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, &journal_path)?;
+
+        self.apply_staged(&staged)?;
+        fs::remove_file(journal_path)
+    }
+
+    /// Finishes or discards a commit interrupted by a crash. Runs on construction, before
+    /// migrations, so they see the state the last commit left. Holds every store lock and then
+    /// the journal lock, the order `commit` uses, so a commit still staging in another process
+    /// is waited for rather than mistaken for a crashed one.
+    pub fn recover_journal(&self) -> io::Result<JournalRecovery> {
+        let _locks = self.lock_stores(migrations::STORES.iter().map(|schema| schema.file_name))?;
+        let _journal_lock = self.acquire_file_lock(JOURNAL_NAME)?;
+        let mut recovery = JournalRecovery::default();
+        let journal_path = self.path_for(JOURNAL_NAME);
+        if journal_path.exists() {
+            let journal: Journal = serde_json::from_str(&fs::read_to_string(&journal_path)?)
+                .map_err(|err| crate::invalid_data(format!("failed to parse {JOURNAL_NAME}: {err}")))?;
+            // Files renamed before the crash have no staged copy left.
+            let pending = journal
+                .files
+                .into_iter()
+                .filter(|name| migrations::schema_for(name).is_some() && self.staged_path(name).exists())
+                .collect::<Vec<_>>();
+            self.apply_staged(&pending)?;
+            fs::remove_file(&journal_path)?;
+            recovery.rolled_forward = pending;
+        }
+        for schema in migrations::STORES {
+            let path = self.staged_path(schema.file_name);
+            if path.exists() {
+                fs::remove_file(path)?;
+                recovery.discarded.push(schema.file_name.to_string());
+            }
+        }
+        Ok(recovery)
+    }
+
+    fn apply_staged(&self, files: &[String]) -> io::Result<()> {
+        for name in files {
+            fs::rename(self.staged_path(name), self.path_for(name))?;
+        }
+        Ok(())
+    }
+
+    fn staged_path(&self, file_name: &str) -> PathBuf {
+        self.path_for(&format!("{file_name}{STAGED_SUFFIX}"))
+    }
+
+    /// Locks are taken in `STORES` order, like `lock_all_stores`, so commits cannot deadlock.
+    fn lock_stores<'a>(&self, files: impl Iterator<Item = &'a str> + Clone) -> io::Result<Vec<FileLockGuard>> {
+        migrations::STORES
+            .iter()
+            .filter(|schema| files.clone().any(|name| name == schema.file_name))
+            .map(|schema| self.acquire_file_lock(schema.file_name))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Storage;
+    use tempfile::tempdir;
+
+    fn audit(id: &str) -> AuditEntry {
+        AuditEntry {
+            audit_id: id.to_string(),
+            timestamp_unix_seconds: 1,
+            session_id: None,
+            provider: String::new(),
+            policy_decisions: vec![],
+            proposed_tool_calls: vec![],
+            executed_actions: vec![],
+            evidence_summaries: vec![],
+            mode: None,
+            tool_calls: vec![],
+            retrieved_paths: vec![],
+            evidence_artifacts: vec![],
+            consent_id: None,
+            request_fingerprint: None,
+        }
+    }
+
+    #[test]
+    fn commit_writes_every_file_and_leaves_no_journal() {
+        let dir = tempdir().expect("tempdir");
+        let store = FileStorage::new_in_dir(dir.path()).expect("store");
+        let mut batch = StorageBatch::default();
+        batch.write_audit_entries(&[audit("audit-1")]).expect("stage audit");
+        batch
+            .write_policy_state(&PolicyState {
+                consent_ttl_secs: Some(7),
+                ..PolicyState::default()
+            })
+            .expect("stage policy");
+        store.commit(batch).expect("commit");
+
+        assert_eq!(store.read_audit_entries().expect("audit")[0].audit_id, "audit-1");
+        assert_eq!(store.read_policy_state().expect("policy").consent_ttl_secs, Some(7));
+        assert!(!dir.path().join(JOURNAL_NAME).exists());
+        assert!(!dir.path().join("audit.json.staged").exists());
+    }
+
+    #[test]
+    fn startup_rolls_a_journaled_commit_forward_and_drops_unjournaled_staging() {
+        let dir = tempdir().expect("tempdir");
+        let store = FileStorage::new_in_dir(dir.path()).expect("store");
+        store.write_audit_entries(&[audit("old")]).expect("old audit");
+
+        // Crash after the journal was written and policy.json was already renamed into place.
+        let staged = store.render_stamped("audit.json", serde_json::to_value([audit("new")]).expect("json")).expect("render");
+        fs::write(dir.path().join("audit.json.staged"), staged).expect("stage");
+        fs::write(dir.path().join(JOURNAL_NAME), r#"{"files":["policy.json","audit.json"]}"#).expect("journal");
+        let recovered = FileStorage::new_in_dir(dir.path()).expect("reopen");
+        assert_eq!(recovered.read_audit_entries().expect("audit")[0].audit_id, "new");
+        assert!(!dir.path().join(JOURNAL_NAME).exists());
+
+        // Crash while staging: no journal, so the half-written copy is thrown away.
+        fs::write(dir.path().join("pending_consents.json.staged"), "{\"schema_ver").expect("partial");
+        let report = recovered.recover_journal().expect("recover");
+        assert_eq!(report.discarded, ["pending_consents.json"]);
+        assert!(report.rolled_forward.is_empty());
+        assert!(recovered.read_pending_consents().expect("consents").is_empty());
+    }
+
+    #[test]
+    fn startup_waits_for_a_commit_that_is_still_staging() {
+        let dir = tempdir().expect("tempdir");
+        let store = FileStorage::new_in_dir(dir.path()).expect("store");
+        let staged_path = dir.path().join("audit.json.staged");
+        let commit_lock = store.acquire_file_lock("audit.json").expect("lock");
+        fs::write(&staged_path, "{\"schema_ver").expect("staging");
+
+        std::thread::scope(|scope| {
+            let opening = scope.spawn(|| FileStorage::new_in_dir(dir.path()).map(|_| ()));
+            std::thread::sleep(std::time::Duration::from_millis(200));
+            assert!(staged_path.exists(), "recovery discarded a commit in progress");
+            drop(commit_lock);
+            opening.join().expect("join").expect("reopen");
+        });
+        assert!(!staged_path.exists());
+    }
+}
@@ -1,13 +1,36 @@
+mod backup;
+mod diagnostics;
+mod evidence;
+mod journal;
+pub mod migrations;
+mod retention;
+
+pub use journal::{JournalRecovery, StorageBatch};
+pub use retention::{PruneReport, RetentionRule, RetentionState};
+
 use directories::ProjectDirs;
-use ipc::{AuditEntry, ChatRequest, McpServerRecord, PendingConsentRecord, Session};
+use ipc::{AuditEntry, ChatRequest, ChatResponse, ConsentGrant, McpServerPolicyRecord, McpServerRecord, PendingConsentRecord, RecentProject, ScheduledTask, Session, ToolCall};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::BTreeMap;
 use std::fs;
 use std::io;
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Overrides the state directory, e.g. for a service running under another account.
+pub const STATE_DIR_ENV: &str = "CMND_N_CTRL_STATE_DIR";
+
+/// Modification time and size of a store file. A stamp that differs from an earlier one means
+/// the file was rewritten since, by this process or another one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChangeStamp {
+    modified: SystemTime,
+    len: u64,
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct ProviderState {
@@ -19,11 +42,210 @@ pub struct ProviderState {
 pub struct PendingConsentState {
     pub record: PendingConsentRecord,
     pub chat_request: ChatRequest,
+    /// The calls consent was asked for, by slot, with canonical arguments; a replay that
+    /// proposes anything else in a slot is denied there.
+    #[serde(default)]
+    pub consent_calls: Vec<ToolCall>,
+}
+
+/// A completed chat response kept for requests that opt into caching with `cache_ttl_secs`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub stored_at_unix_seconds: u64,
+    /// What a later request must match to reuse this response; entries without one never match.
+    #[serde(default)]
+    pub cache_key: String,
+    pub response: ChatResponse,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct ProjectState {
     pub open_path: Option<String>,
+    /// Named workspaces (name -> path), registered by `project.open`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub workspaces: BTreeMap<String, String>,
+    /// Most recently opened first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recent: Vec<RecentProject>,
+}
+
+/// User-editable policy overrides read from `policy.json`; unset fields keep built-in defaults.
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+pub struct PolicyState {
+    #[serde(default)]
+    pub max_tool_rounds: Option<usize>,
+    #[serde(default)]
+    pub tool_timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub consent_ttl_secs: Option<u64>,
+    /// `strict`, `balanced` (default) or `permissive`.
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Largest `content` a file-writing tool may write in one call.
+    #[serde(default)]
+    pub max_write_bytes: Option<u64>,
+    /// Distinct files a single chat request may write.
+    #[serde(default)]
+    pub max_files_per_request: Option<usize>,
+    /// Largest `result_json` a tool call may feed back to the provider; unset keeps the
+    /// built-in 64 KiB, `0` lifts the cap.
+    #[serde(default)]
+    pub max_tool_result_bytes: Option<u64>,
+    /// Per-tool result budgets keyed by tool name or `prefix*`; `0` lifts the cap for that tool.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tool_result_bytes: BTreeMap<String, u64>,
+    /// Globs (relative to the project root) file-writing tools must not touch; unset keeps the
+    /// built-in `.git` and `.env` patterns, `[]` disables them.
+    #[serde(default)]
+    pub forbidden_paths: Option<Vec<String>>,
+    /// Keyed by MCP server id.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub mcp_servers: BTreeMap<String, McpServerPolicyRecord>,
+    #[serde(default, skip_serializing_if = "RetentionState::is_unset")]
+    pub retention: RetentionState,
+    #[serde(default, skip_serializing_if = "RedactionState::is_unset")]
+    pub redaction: RedactionState,
+    #[serde(default, skip_serializing_if = "SecretScanState::is_unset")]
+    pub secret_scan: SecretScanState,
+    /// Standing approvals from `session`/`always` consent scopes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub consent_grants: Vec<ConsentGrant>,
+}
+
+/// `redaction` section of `policy.json`: secrets to mask on top of the built-in key names.
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct RedactionState {
+    /// Extra JSON object keys (case-insensitive) whose values are replaced wholesale.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<String>,
+    /// Regexes; every match inside a string value or free text is replaced.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub value_patterns: Vec<String>,
+}
+
+impl RedactionState {
+    pub fn is_unset(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// `secret_scan` section of `policy.json`: credential detectors run on what is about to be
+/// sent to a provider.
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct SecretScanState {
+    /// `redact` (default), `consent` or `off`.
+    #[serde(default)]
+    pub action: Option<String>,
+    /// Built-in detectors to run; unset runs all of them.
+    #[serde(default)]
+    pub detectors: Option<Vec<String>>,
+    /// Extra detectors as name -> regex. A `secret` capture group limits what gets masked.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub patterns: BTreeMap<String, String>,
+    /// Also scan requests to local providers (stubs, loopback endpoints).
+    #[serde(default)]
+    pub include_local_providers: bool,
+}
+
+impl SecretScanState {
+    pub fn is_unset(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+pub struct AgentSettingsState {
+    #[serde(default)]
+    pub global_instructions: Option<String>,
+    #[serde(default)]
+    pub project_instructions: BTreeMap<String, String>,
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+    #[serde(default)]
+    pub tools: ToolSettings,
+    #[serde(default)]
+    pub databases: DatabaseSettings,
+    #[serde(default)]
+    pub browser: BrowserSettings,
+    #[serde(default)]
+    pub calendars: CalendarSettings,
+}
+
+/// Named CalDAV collections `calendar.*` may use alongside project `.ics` files.
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct CalendarSettings {
+    #[serde(default)]
+    pub caldav: BTreeMap<String, CalDavSettings>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct CalDavSettings {
+    /// Calendar collection URL.
+    pub url: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Password or app token, or a `keychain:<name>` reference to one.
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+/// The WebDriver server `browser.*` tools talk to (chromedriver, geckodriver, Selenium).
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct BrowserSettings {
+    /// Defaults to `http://127.0.0.1:4444`.
+    #[serde(default)]
+    pub webdriver_url: Option<String>,
+    /// Defaults to `true`.
+    #[serde(default)]
+    pub headless: Option<bool>,
+}
+
+/// Named database connections for `db.query`.
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct DatabaseSettings {
+    /// Name -> Postgres connection string, or a `keychain:<name>` reference to one.
+    #[serde(default)]
+    pub postgres: BTreeMap<String, String>,
+}
+
+/// Tool names or `prefix*` patterns switched with `tools.enable`/`tools.disable`.
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct ToolSettings {
+    /// Hidden in every project.
+    #[serde(default)]
+    pub disabled: Vec<String>,
+    /// Keyed by project path.
+    #[serde(default)]
+    pub projects: BTreeMap<String, ProjectToolSettings>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct ProjectToolSettings {
+    /// Offered in this project even when disabled globally.
+    #[serde(default)]
+    pub enabled: Vec<String>,
+    #[serde(default)]
+    pub disabled: Vec<String>,
+}
+
+/// Where pending-consent alerts go. Desktop notifications are off unless `desktop` is `true`.
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct NotificationSettings {
+    #[serde(default)]
+    pub desktop: Option<bool>,
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// Named webhooks `notify.send` can post to.
+    #[serde(default)]
+    pub targets: BTreeMap<String, NotifyTarget>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct NotifyTarget {
+    pub url: String,
+    /// `slack`, `discord` or `generic`; inferred from the URL when unset.
+    #[serde(default)]
+    pub kind: Option<String>,
 }
 
 pub trait Storage {
@@ -44,11 +266,33 @@ pub trait Storage {
 
     fn read_project_state(&self) -> io::Result<ProjectState>;
     fn write_project_state(&self, state: &ProjectState) -> io::Result<()>;
+
+    fn read_agent_settings(&self) -> io::Result<AgentSettingsState>;
+    fn write_agent_settings(&self, state: &AgentSettingsState) -> io::Result<()>;
+
+    fn read_policy_state(&self) -> io::Result<PolicyState>;
+    fn write_policy_state(&self, state: &PolicyState) -> io::Result<()>;
+
+    fn read_response_cache(&self) -> io::Result<Vec<CachedResponse>>;
+    fn write_response_cache(&self, entries: &[CachedResponse]) -> io::Result<()>;
+
+    fn read_scheduled_tasks(&self) -> io::Result<Vec<ScheduledTask>>;
+    fn write_scheduled_tasks(&self, tasks: &[ScheduledTask]) -> io::Result<()>;
 }
 
 #[derive(Clone, Debug)]
 pub struct FileStorage {
     root: PathBuf,
+    /// Keyed by store file name; shared by clones.
+    lock_stats: Arc<Mutex<BTreeMap<String, LockStats>>>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct LockStats {
+    acquisitions: u64,
+    contended: u64,
+    wait: Duration,
+    timeouts: u64,
 }
 
 impl FileStorage {
@@ -56,15 +300,66 @@ impl FileStorage {
     const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
     pub fn new_default() -> io::Result<Self> {
+        Self::new_in_dir(Self::default_root()?)
+    }
+
+    /// `CMND_N_CTRL_STATE_DIR` if set, else the platform app data dir. Used by `new_default`.
+    pub fn default_root() -> io::Result<PathBuf> {
+        if let Some(dir) = std::env::var_os(STATE_DIR_ENV).filter(|dir| !dir.is_empty()) {
+            return Ok(PathBuf::from(dir));
+        }
         let proj = ProjectDirs::from("com", "cmnd-n-ctrl", "cmnd-n-ctrl")
             .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "unable to resolve app data dir"))?;
-        Self::new_in_dir(proj.data_local_dir())
+        Ok(proj.data_local_dir().to_path_buf())
     }
 
     pub fn new_in_dir(path: impl AsRef<Path>) -> io::Result<Self> {
         let root = path.as_ref().to_path_buf();
         fs::create_dir_all(&root)?;
-        Ok(Self { root })
+        let storage = Self {
+            root,
+            lock_stats: Arc::default(),
+        };
+        storage.recover_journal()?;
+        storage.run_migrations()?;
+        Ok(storage)
+    }
+
+    /// Upgrades every store file older than its current schema in place. Runs on construction;
+    /// returns `(file, from, to)` for each file it rewrote.
+    pub fn run_migrations(&self) -> io::Result<Vec<(&'static str, u32, u32)>> {
+        let mut upgraded = Vec::new();
+        for schema in migrations::STORES {
+            if !self.path_for(schema.file_name).exists() {
+                continue;
+            }
+            let _lock = self.acquire_file_lock(schema.file_name)?;
+            // Corrupt files are left alone so the error surfaces on the read that needs them.
+            let Ok((version, payload)) = self.read_versioned(schema.file_name) else {
+                continue;
+            };
+            if version >= schema.version() {
+                continue;
+            }
+            let payload = migrations::migrate(schema, version, payload).map_err(invalid_data)?;
+            self.write_stamped(schema.file_name, payload)?;
+            upgraded.push((schema.file_name, version, schema.version()));
+        }
+        Ok(upgraded)
+    }
+
+    /// Directory holding the state files; callers may keep caches in subdirectories.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// `None` while `file_name` does not exist. Cheap enough to check before every id allocation.
+    pub fn change_stamp(&self, file_name: &str) -> Option<ChangeStamp> {
+        let meta = fs::metadata(self.path_for(file_name)).ok()?;
+        Some(ChangeStamp {
+            modified: meta.modified().ok()?,
+            len: meta.len(),
+        })
     }
 
     fn path_for(&self, file_name: &str) -> PathBuf {
@@ -75,17 +370,44 @@ impl FileStorage {
     where
         T: DeserializeOwned + Default,
     {
-        let path = self.path_for(file_name);
-        if !path.exists() {
+        if !self.path_for(file_name).exists() {
             return Ok(T::default());
         }
-        let raw = fs::read_to_string(path)?;
-        serde_json::from_str(&raw).map_err(|err| {
-            io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("failed to parse {}: {err}", file_name),
-            )
-        })
+        let (version, payload) = self.read_versioned(file_name)?;
+        let payload = match migrations::schema_for(file_name) {
+            Some(schema) => migrations::migrate(schema, version, payload).map_err(invalid_data)?,
+            None => payload,
+        };
+        serde_json::from_value(payload).map_err(|err| invalid_data(format!("failed to parse {file_name}: {err}")))
+    }
+
+    /// Stored schema version (0 for pre-versioning files) and the bare payload.
+    fn read_versioned(&self, file_name: &str) -> io::Result<(u32, Value)> {
+        let raw = fs::read_to_string(self.path_for(file_name))?;
+        let value: Value =
+            serde_json::from_str(&raw).map_err(|err| invalid_data(format!("failed to parse {file_name}: {err}")))?;
+        match migrations::schema_for(file_name) {
+            Some(schema) => migrations::split_versioned(schema, value).map_err(invalid_data),
+            None => Ok((0, value)),
+        }
+    }
+
+    fn write_stamped(&self, file_name: &str, payload: Value) -> io::Result<()> {
+        let payload = self.render_stamped(file_name, payload)?;
+        let path = self.path_for(file_name);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, payload)?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    /// The file contents for `payload`, wrapped with the store's `schema_version`.
+    fn render_stamped(&self, file_name: &str, payload: Value) -> io::Result<String> {
+        let value = match migrations::schema_for(file_name) {
+            Some(schema) => migrations::stamp_versioned(schema, payload).map_err(invalid_data)?,
+            None => payload,
+        };
+        serde_json::to_string_pretty(&value).map_err(|err| io::Error::other(format!("serialize {file_name}: {err}")))
     }
 
     fn write_json<T>(&self, file_name: &str, value: &T) -> io::Result<()>
@@ -93,27 +415,92 @@ impl FileStorage {
         T: Serialize,
     {
         let _lock = self.acquire_file_lock(file_name)?;
-        let path = self.path_for(file_name);
-        let tmp = path.with_extension("tmp");
-        let payload = serde_json::to_string_pretty(value)
-            .map_err(|err| io::Error::other(format!("serialize {file_name}: {err}")))?;
-        fs::write(&tmp, payload)?;
-        fs::rename(tmp, path)?;
+        self.write_json_locked(file_name, value)
+    }
+
+    /// `write_json` for a caller that already holds the file's lock.
+    fn write_json_locked<T>(&self, file_name: &str, value: &T) -> io::Result<()>
+    where
+        T: Serialize,
+    {
+        self.check_not_newer(file_name)?;
+        let payload = serde_json::to_value(value).map_err(|err| io::Error::other(format!("serialize {file_name}: {err}")))?;
+        self.write_stamped(file_name, payload)
+    }
+
+    /// Reads, changes and writes `sessions.json` under one lock, so two clients cannot both
+    /// read the same list and have the second write drop the first one's change. Nothing is
+    /// written when `apply` returns `Err`.
+    pub fn update_sessions<R, E>(&self, apply: impl FnOnce(&mut Vec<Session>) -> Result<R, E>) -> io::Result<Result<R, E>> {
+        let _lock = self.acquire_file_lock("sessions.json")?;
+        let mut sessions: Vec<Session> = self.read_json("sessions.json")?;
+        let result = apply(&mut sessions);
+        if result.is_ok() {
+            self.write_json_locked("sessions.json", &sessions)?;
+        }
+        Ok(result)
+    }
+
+    /// Hands out the next id number for `kind` (`sess`, `consent`, ...) from `id_counters.json`,
+    /// under its lock, so concurrent processes never get the same one. `floor` is the highest
+    /// number the caller already knows to be taken, e.g. from records written before the
+    /// counter existed.
+    pub fn allocate_id(&self, kind: &str, floor: u64) -> io::Result<u64> {
+        let _lock = self.acquire_file_lock("id_counters.json")?;
+        let mut counters: BTreeMap<String, u64> = self.read_json("id_counters.json")?;
+        let last = counters.entry(kind.to_string()).or_default();
+        *last = (*last).max(floor) + 1;
+        let id = *last;
+        self.write_json_locked("id_counters.json", &counters)?;
+        Ok(id)
+    }
+
+    /// An unparsable file may be overwritten as before; only a newer schema is protected.
+    fn check_not_newer(&self, file_name: &str) -> io::Result<()> {
+        let Some(schema) = migrations::schema_for(file_name) else {
+            return Ok(());
+        };
+        if !self.path_for(file_name).exists() {
+            return Ok(());
+        }
+        if let Ok((version, _)) = self.read_versioned(file_name) {
+            if version > schema.version() {
+                return Err(invalid_data(format!(
+                    "refusing to overwrite {file_name}: schema_version {version} is newer than supported {}",
+                    schema.version()
+                )));
+            }
+        }
         Ok(())
     }
 
     fn acquire_file_lock(&self, file_name: &str) -> io::Result<FileLockGuard> {
         let lock_path = self.path_for(&format!("{file_name}.lock"));
         let start = Instant::now();
+        let mut waited = false;
         loop {
             match fs::OpenOptions::new()
                 .write(true)
                 .create_new(true)
                 .open(&lock_path)
             {
-                Ok(_) => return Ok(FileLockGuard { path: lock_path }),
+                Ok(_) => {
+                    self.record_lock(file_name, |stats| {
+                        stats.acquisitions += 1;
+                        if waited {
+                            stats.contended += 1;
+                            stats.wait += start.elapsed();
+                        }
+                    });
+                    return Ok(FileLockGuard { path: lock_path });
+                }
                 Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                    waited = true;
                     if start.elapsed() >= Self::LOCK_WAIT_TIMEOUT {
+                        self.record_lock(file_name, |stats| {
+                            stats.timeouts += 1;
+                            stats.wait += start.elapsed();
+                        });
                         return Err(io::Error::new(
                             ErrorKind::TimedOut,
                             format!("timeout waiting for storage lock: {}", lock_path.display()),
@@ -125,6 +512,16 @@ impl FileStorage {
             }
         }
     }
+
+    fn record_lock(&self, file_name: &str, update: impl FnOnce(&mut LockStats)) {
+        if let Ok(mut stats) = self.lock_stats.lock() {
+            update(stats.entry(file_name.to_string()).or_default());
+        }
+    }
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
 }
 
 struct FileLockGuard {
@@ -185,6 +582,38 @@ impl Storage for FileStorage {
     fn write_project_state(&self, state: &ProjectState) -> io::Result<()> {
         self.write_json("project.json", state)
     }
+
+    fn read_agent_settings(&self) -> io::Result<AgentSettingsState> {
+        self.read_json("agent_settings.json")
+    }
+
+    fn write_agent_settings(&self, state: &AgentSettingsState) -> io::Result<()> {
+        self.write_json("agent_settings.json", state)
+    }
+
+    fn read_policy_state(&self) -> io::Result<PolicyState> {
+        self.read_json("policy.json")
+    }
+
+    fn write_policy_state(&self, state: &PolicyState) -> io::Result<()> {
+        self.write_json("policy.json", state)
+    }
+
+    fn read_response_cache(&self) -> io::Result<Vec<CachedResponse>> {
+        self.read_json("response_cache.json")
+    }
+
+    fn write_response_cache(&self, entries: &[CachedResponse]) -> io::Result<()> {
+        self.write_json("response_cache.json", &entries)
+    }
+
+    fn read_scheduled_tasks(&self) -> io::Result<Vec<ScheduledTask>> {
+        self.read_json("tasks.json")
+    }
+
+    fn write_scheduled_tasks(&self, tasks: &[ScheduledTask]) -> io::Result<()> {
+        self.write_json("tasks.json", &tasks)
+    }
 }
 
 #[cfg(test)]
@@ -207,9 +636,21 @@ mod tests {
             messages: vec![ChatMessage {
                 role: "user".to_string(),
                 content: "hello".to_string(),
+                parts: Vec::new(),
+                message_id: None,
+                created_at_unix_seconds: None,
             }],
+            project_path: None,
+            policy: None,
+            forked_from: None,
+            pinned: false,
+            archived: false,
+            provider_name: None,
+            model: None,
+            default_mode: None,
+            revision: 0,
         };
-        store.write_sessions(&[session.clone()]).expect("write");
+        store.write_sessions(std::slice::from_ref(&session)).expect("write");
         let got = store.list_sessions().expect("read");
         assert_eq!(got, vec![session]);
     }
@@ -230,6 +671,8 @@ mod tests {
                 rationale: "requires explicit consent".to_string(),
                 arguments_preview: Some("{\"app\":\"x\"}".to_string()),
                 request_fingerprint: "req-1".to_string(),
+                pending_actions: vec![],
+                risk_factors: vec![],
             },
             chat_request: ChatRequest {
                 session_id: None,
@@ -238,16 +681,47 @@ mod tests {
                     provider_name: "openai-stub".to_string(),
                     model: None,
                     config_json: None,
+                    ..Default::default()
                 },
-                mode: ChatMode::RequireConfirmation,
+                mode: Some(ChatMode::RequireConfirmation),
+                cache_ttl_secs: None,
+                dry_run: false,
             },
+            consent_calls: vec![],
         };
-        store.write_pending_consents(&[item.clone()]).expect("write");
+        store.write_pending_consents(std::slice::from_ref(&item)).expect("write");
         let got = store.read_pending_consents().expect("read");
         assert_eq!(got.len(), 1);
         assert_eq!(got[0].record.consent_id, item.record.consent_id);
     }
 
+    #[test]
+    fn legacy_files_are_migrated_and_newer_files_are_not_overwritten() {
+        let dir = tempdir().expect("tempdir");
+        fs::write(
+            dir.path().join("sessions.json"),
+            r#"[{"id":"sess-1","created_at_unix_seconds":1,"updated_at_unix_seconds":1,"title":"Old","messages":[]}]"#,
+        )
+        .expect("legacy sessions");
+        fs::write(dir.path().join("policy.json"), r#"{"consent_ttl_secs":42}"#).expect("legacy policy");
+        fs::write(dir.path().join("project.json"), r#"{"schema_version":99,"open_path":"/future"}"#).expect("future project");
+
+        let store = FileStorage::new_in_dir(dir.path()).expect("store");
+        let raw: Value = serde_json::from_str(&fs::read_to_string(dir.path().join("sessions.json")).expect("read"))
+            .expect("json");
+        assert_eq!(raw["schema_version"], 1);
+        assert_eq!(raw["items"][0]["id"], "sess-1");
+        assert_eq!(store.list_sessions().expect("sessions")[0].title, "Old");
+        assert_eq!(store.read_policy_state().expect("policy").consent_ttl_secs, Some(42));
+        assert!(store.run_migrations().expect("rerun").is_empty());
+
+        assert_eq!(store.read_project_state().expect("project").open_path.as_deref(), Some("/future"));
+        let err = store
+            .write_project_state(&ProjectState::default())
+            .expect_err("newer schema must not be overwritten");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
     #[test]
     fn concurrent_writes_are_serialized_by_lock() {
         let dir = tempdir().expect("tempdir");
@@ -267,6 +741,15 @@ mod tests {
                         updated_at_unix_seconds: n,
                         title: format!("T{i}"),
                         messages: vec![],
+                        project_path: None,
+                        policy: None,
+                        forked_from: None,
+                        pinned: false,
+                        archived: false,
+                        provider_name: None,
+                        model: None,
+                        default_mode: None,
+                        revision: 0,
                     };
                     store.write_sessions(&[session])?;
                 }
@@ -281,4 +764,80 @@ mod tests {
 
         let _ = store.list_sessions().expect("read sessions after concurrent writes");
     }
+
+    #[test]
+    fn concurrent_id_allocations_are_unique_and_respect_the_floor() {
+        let dir = tempdir().expect("tempdir");
+        let store = Arc::new(FileStorage::new_in_dir(dir.path()).expect("store"));
+        assert_eq!(store.allocate_id("sess", 7).expect("first"), 8);
+
+        let handles = (0..2)
+            .map(|_| {
+                let store = Arc::clone(&store);
+                thread::spawn(move || (0..20).map(|_| store.allocate_id("sess", 0).expect("allocate")).collect::<Vec<_>>())
+            })
+            .collect::<Vec<_>>();
+        let mut ids = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("thread"))
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        assert_eq!(ids, (9..49).collect::<Vec<_>>());
+        assert_eq!(store.allocate_id("consent", 0).expect("other kind"), 1);
+    }
+
+    #[test]
+    fn concurrent_session_updates_are_not_lost() {
+        let dir = tempdir().expect("tempdir");
+        let store = Arc::new(FileStorage::new_in_dir(dir.path()).expect("store"));
+        let barrier = Arc::new(Barrier::new(3));
+
+        let mut handles = Vec::new();
+        for i in 0..2 {
+            let store = Arc::clone(&store);
+            let barrier = Arc::clone(&barrier);
+            handles.push(thread::spawn(move || {
+                barrier.wait();
+                for n in 0..25 {
+                    store
+                        .update_sessions(|sessions| {
+                            sessions.push(Session {
+                                id: format!("sess-{i}-{n}"),
+                                created_at_unix_seconds: n,
+                                updated_at_unix_seconds: n,
+                                title: format!("T{i}"),
+                                messages: vec![],
+                                project_path: None,
+                                policy: None,
+                                forked_from: None,
+                                pinned: false,
+                                archived: false,
+                                provider_name: None,
+                                model: None,
+                                default_mode: None,
+                                revision: 0,
+                            });
+                            Ok::<(), ()>(())
+                        })?
+                        .expect("apply");
+                }
+                Ok::<(), io::Error>(())
+            }));
+        }
+
+        barrier.wait();
+        for handle in handles {
+            handle.join().expect("thread join").expect("thread update");
+        }
+
+        assert_eq!(store.list_sessions().expect("sessions").len(), 50);
+        let unchanged = store
+            .update_sessions(|sessions| {
+                sessions.clear();
+                Err::<(), _>("rejected")
+            })
+            .expect("io");
+        assert_eq!(unchanged, Err("rejected"));
+        assert_eq!(store.list_sessions().expect("sessions").len(), 50);
+    }
 }
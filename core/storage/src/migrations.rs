@@ -0,0 +1,113 @@
+use serde_json::{Map, Value};
+
+/// Upgrades a store payload from version `n` to `n + 1`.
+pub type Migration = fn(Value) -> Result<Value, String>;
+
+/// Versioning rules for one JSON file. Object stores carry `schema_version` next to their fields
+/// (so hand-edited files like `policy.json` stay flat); list stores become
+/// `{"schema_version": N, "items": [...]}`. Files written before versioning count as version 0.
+#[derive(Clone, Copy, Debug)]
+pub struct StoreSchema {
+    pub file_name: &'static str,
+    pub list: bool,
+    /// `migrations[n]` upgrades version `n`; the current version is `migrations.len()`.
+    pub migrations: &'static [Migration],
+}
+
+impl StoreSchema {
+    pub fn version(&self) -> u32 {
+        self.migrations.len() as u32
+    }
+}
+
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+const ITEMS_KEY: &str = "items";
+
+/// v0 -> v1 only adds the version stamp; the payload is unchanged.
+fn stamp_only(value: Value) -> Result<Value, String> {
+    Ok(value)
+}
+
+pub const STORES: &[StoreSchema] = &[
+    StoreSchema { file_name: "sessions.json", list: true, migrations: &[stamp_only] },
+    StoreSchema { file_name: "providers.json", list: false, migrations: &[stamp_only] },
+    StoreSchema { file_name: "audit.json", list: true, migrations: &[stamp_only] },
+    StoreSchema { file_name: "pending_consents.json", list: true, migrations: &[stamp_only] },
+    StoreSchema { file_name: "mcp_servers.json", list: true, migrations: &[stamp_only] },
+    StoreSchema { file_name: "project.json", list: false, migrations: &[stamp_only] },
+    StoreSchema { file_name: "agent_settings.json", list: false, migrations: &[stamp_only] },
+    StoreSchema { file_name: "policy.json", list: false, migrations: &[stamp_only] },
+    StoreSchema { file_name: "response_cache.json", list: true, migrations: &[stamp_only] },
+    StoreSchema { file_name: "tasks.json", list: true, migrations: &[stamp_only] },
+    StoreSchema { file_name: "id_counters.json", list: false, migrations: &[stamp_only] },
+];
+
+pub fn schema_for(file_name: &str) -> Option<&'static StoreSchema> {
+    STORES.iter().find(|s| s.file_name == file_name)
+}
+
+/// Separates the stored version from the payload.
+pub fn split_versioned(schema: &StoreSchema, value: Value) -> Result<(u32, Value), String> {
+    let Value::Object(mut map) = value else {
+        return Ok((0, value));
+    };
+    let Some(version) = map.remove(SCHEMA_VERSION_KEY) else {
+        return Ok((0, Value::Object(map)));
+    };
+    let version = version
+        .as_u64()
+        .and_then(|v| u32::try_from(v).ok())
+        .ok_or_else(|| format!("{}: schema_version must be a non-negative integer", schema.file_name))?;
+    if schema.list {
+        let items = map.remove(ITEMS_KEY).unwrap_or_else(|| Value::Array(Vec::new()));
+        return Ok((version, items));
+    }
+    Ok((version, Value::Object(map)))
+}
+
+pub fn stamp_versioned(schema: &StoreSchema, payload: Value) -> Result<Value, String> {
+    let version = Value::from(schema.version());
+    match payload {
+        Value::Object(mut map) if !schema.list => {
+            map.insert(SCHEMA_VERSION_KEY.to_string(), version);
+            Ok(Value::Object(map))
+        }
+        Value::Array(items) if schema.list => {
+            let mut map = Map::new();
+            map.insert(SCHEMA_VERSION_KEY.to_string(), version);
+            map.insert(ITEMS_KEY.to_string(), Value::Array(items));
+            Ok(Value::Object(map))
+        }
+        _ => Err(format!("{}: unexpected payload shape", schema.file_name)),
+    }
+}
+
+/// Runs the migrations from `from` up to the current version. Payloads from a newer version are
+/// returned untouched so they can still be read; writers refuse to overwrite them.
+pub fn migrate(schema: &StoreSchema, from: u32, mut payload: Value) -> Result<Value, String> {
+    for (step, migration) in schema.migrations.iter().enumerate().skip(from as usize) {
+        payload = migration(payload).map_err(|err| format!("{}: migration v{step} failed: {err}", schema.file_name))?;
+    }
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn legacy_payloads_split_and_stamp_per_shape() {
+        let sessions = schema_for("sessions.json").expect("sessions");
+        let (version, payload) = split_versioned(sessions, json!([{"id": "s1"}])).expect("legacy list");
+        assert_eq!((version, payload.clone()), (0, json!([{"id": "s1"}])));
+        let stamped = stamp_versioned(sessions, payload).expect("stamp");
+        assert_eq!(stamped, json!({"schema_version": 1, "items": [{"id": "s1"}]}));
+        assert_eq!(split_versioned(sessions, stamped).expect("split"), (1, json!([{"id": "s1"}])));
+
+        let policy = schema_for("policy.json").expect("policy");
+        let stamped = stamp_versioned(policy, json!({"consent_ttl_secs": 42})).expect("stamp");
+        assert_eq!(stamped, json!({"consent_ttl_secs": 42, "schema_version": 1}));
+        assert!(split_versioned(policy, json!({"schema_version": "x"})).is_err());
+    }
+}
@@ -0,0 +1,174 @@
+use crate::{FileStorage, Storage};
+use serde::{Deserialize, Serialize};
+use std::io;
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Limits for one store; unset limits don't apply. Newest records are kept first.
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct RetentionRule {
+    #[serde(default)]
+    pub max_age_days: Option<u64>,
+    #[serde(default)]
+    pub max_count: Option<usize>,
+    /// Budget for the serialized records, in bytes.
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+}
+
+/// `retention` section of `policy.json`. A missing store falls back to its default rule; an
+/// empty rule (`{}`) keeps everything.
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct RetentionState {
+    #[serde(default)]
+    pub sessions: Option<RetentionRule>,
+    #[serde(default)]
+    pub audit: Option<RetentionRule>,
+    /// Applies to resolved (approved, denied, expired) consents; pending ones are always kept.
+    #[serde(default)]
+    pub consents: Option<RetentionRule>,
+}
+
+impl RetentionState {
+    pub fn is_unset(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Sessions are user content and are kept unless configured.
+    pub fn sessions_rule(&self) -> RetentionRule {
+        self.sessions.clone().unwrap_or_default()
+    }
+
+    pub fn audit_rule(&self) -> RetentionRule {
+        self.audit.clone().unwrap_or(RetentionRule {
+            max_age_days: None,
+            max_count: Some(10_000),
+            max_bytes: None,
+        })
+    }
+
+    pub fn consents_rule(&self) -> RetentionRule {
+        self.consents.clone().unwrap_or(RetentionRule {
+            max_age_days: Some(30),
+            max_count: None,
+            max_bytes: None,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    pub sessions_removed: usize,
+    pub audit_entries_removed: usize,
+    pub consents_removed: usize,
+    /// Evidence files no remaining audit entry refers to.
+    pub evidence_files_removed: usize,
+}
+
+impl FileStorage {
+    /// Drops records outside `retention` as of `now`. With `dry_run` nothing is written.
+    pub fn prune(&self, retention: &RetentionState, now: u64, dry_run: bool) -> io::Result<PruneReport> {
+        let mut report = PruneReport::default();
+
+        let sessions = self.list_sessions()?;
+        let total = sessions.len();
+        let kept = retain(sessions, &retention.sessions_rule(), now, |s| s.updated_at_unix_seconds)?;
+        report.sessions_removed = total - kept.len();
+        if report.sessions_removed > 0 && !dry_run {
+            self.write_sessions(&kept)?;
+        }
+
+        let entries = self.read_audit_entries()?;
+        let total = entries.len();
+        let kept = retain(entries, &retention.audit_rule(), now, |a| a.timestamp_unix_seconds)?;
+        report.audit_entries_removed = total - kept.len();
+        if report.audit_entries_removed > 0 && !dry_run {
+            self.write_audit_entries(&kept)?;
+        }
+        let referenced = kept
+            .iter()
+            .flat_map(|a| a.evidence_artifacts.iter().map(|artifact| artifact.sha256.clone()))
+            .collect();
+        report.evidence_files_removed = self.prune_evidence(&referenced, dry_run)?;
+
+        let (pending, resolved): (Vec<_>, Vec<_>) = self
+            .read_pending_consents()?
+            .into_iter()
+            .partition(|c| c.record.status == "pending");
+        let total = resolved.len();
+        let kept = retain(resolved, &retention.consents_rule(), now, |c| c.record.requested_at_unix_seconds)?;
+        report.consents_removed = total - kept.len();
+        if report.consents_removed > 0 && !dry_run {
+            // Stored order is oldest first; keep it that way.
+            let mut all = pending.into_iter().chain(kept).collect::<Vec<_>>();
+            all.sort_by_key(|c| c.record.requested_at_unix_seconds);
+            self.write_pending_consents(&all)?;
+        }
+
+        Ok(report)
+    }
+}
+
+/// Keeps the newest records that satisfy every limit, preserving their original order.
+fn retain<T: Serialize>(items: Vec<T>, rule: &RetentionRule, now: u64, timestamp: fn(&T) -> u64) -> io::Result<Vec<T>> {
+    let cutoff = rule.max_age_days.map(|days| now.saturating_sub(days.saturating_mul(SECS_PER_DAY)));
+    let mut order = (0..items.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| std::cmp::Reverse(timestamp(&items[i])));
+
+    let mut keep = vec![false; items.len()];
+    let mut bytes = 0u64;
+    for (count, i) in order.into_iter().enumerate() {
+        if cutoff.is_some_and(|cutoff| timestamp(&items[i]) < cutoff) {
+            break;
+        }
+        if rule.max_count.is_some_and(|max| count >= max) {
+            break;
+        }
+        if let Some(max) = rule.max_bytes {
+            let size = serde_json::to_vec(&items[i]).map_err(io::Error::other)?.len() as u64;
+            if bytes + size > max {
+                break;
+            }
+            bytes += size;
+        }
+        keep[i] = true;
+    }
+    Ok(items
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(item, keep)| keep.then_some(item))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retain_keeps_newest_within_every_limit() {
+        let now = 100 * SECS_PER_DAY;
+        let stamps = vec![now - 40 * SECS_PER_DAY, now - 3, now - 1, now - 2, now - 5];
+        let by_age = RetentionRule {
+            max_age_days: Some(30),
+            ..RetentionRule::default()
+        };
+        assert_eq!(retain(stamps.clone(), &by_age, now, |t| *t).expect("age").len(), 4);
+
+        let by_count = RetentionRule {
+            max_count: Some(2),
+            ..RetentionRule::default()
+        };
+        assert_eq!(retain(stamps.clone(), &by_count, now, |t| *t).expect("count"), vec![now - 1, now - 2]);
+
+        let item_bytes = serde_json::to_vec(&stamps[1]).expect("size").len() as u64;
+        let by_bytes = RetentionRule {
+            max_bytes: Some(item_bytes * 3),
+            ..RetentionRule::default()
+        };
+        assert_eq!(
+            retain(stamps.clone(), &by_bytes, now, |t| *t).expect("bytes"),
+            vec![now - 3, now - 1, now - 2]
+        );
+        assert_eq!(retain(stamps.clone(), &RetentionRule::default(), now, |t| *t).expect("none"), stamps);
+    }
+}